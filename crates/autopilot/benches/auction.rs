@@ -0,0 +1,154 @@
+//! Measures how much memory the run loop allocates per auction for sharing
+//! the auction and building the solve request. The previous behavior of deep
+//! copying the orders and prices is measured as a baseline.
+//!
+//! Run with `cargo bench -p autopilot --bench auction`.
+
+use {
+    autopilot::{
+        domain::{
+            Auction,
+            Order,
+            OrderUid,
+            auction::{
+                ContentHash,
+                OrderOrdering,
+                Price,
+                Prices,
+                order::{
+                    AppDataHash,
+                    BuyTokenDestination,
+                    EcdsaSignature,
+                    SellTokenSource,
+                    Side,
+                    Signature,
+                },
+            },
+            eth,
+        },
+        infra::solvers::dto::solve,
+    },
+    std::{
+        alloc::{GlobalAlloc, Layout, System},
+        collections::HashSet,
+        hint::black_box,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::{Duration, Instant},
+    },
+};
+
+/// Counts the allocated bytes on top of the system allocator.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const ORDERS: u32 = 5_000;
+const ITERATIONS: u32 = 100;
+
+fn order(index: u32) -> Order {
+    let mut uid = [0; 56];
+    uid[..4].copy_from_slice(&index.to_be_bytes());
+    let token = |byte: u32| eth::TokenAddress(eth::H160::from_low_u64_be(byte.into()));
+    Order {
+        uid: OrderUid(uid),
+        sell: eth::Asset {
+            token: token(index % 100),
+            amount: eth::U256::from(index).into(),
+        },
+        buy: eth::Asset {
+            token: token(index % 100 + 1),
+            amount: eth::U256::from(index).into(),
+        },
+        protocol_fees: vec![],
+        side: Side::Sell,
+        created: index,
+        valid_to: u32::MAX,
+        receiver: None,
+        owner: Default::default(),
+        partially_fillable: false,
+        executed: eth::U256::zero().into(),
+        pre_interactions: vec![],
+        post_interactions: vec![],
+        sell_token_balance: SellTokenSource::Erc20,
+        buy_token_balance: BuyTokenDestination::Erc20,
+        app_data: AppDataHash([0; 32]),
+        signature: Signature::Eip712(EcdsaSignature {
+            r: eth::H256::repeat_byte(1),
+            s: eth::H256::repeat_byte(2),
+            v: 27,
+        }),
+        quote: None,
+        fairness_exempt: false,
+    }
+}
+
+fn auction() -> Auction {
+    let orders: Vec<_> = (0..ORDERS).map(order).collect();
+    let prices: Prices = (0..=100)
+        .map(|byte: u64| {
+            let price = Price::try_new(eth::U256::from(byte + 1).into()).unwrap();
+            (eth::TokenAddress(eth::H160::from_low_u64_be(byte)), price)
+        })
+        .collect();
+    Auction {
+        id: 1,
+        block: 1,
+        content_hash: ContentHash::new(&orders, &prices, &[]),
+        orders: Arc::new(orders),
+        prices: Arc::new(prices),
+        surplus_capturing_jit_order_owners: vec![],
+        quote_only: false,
+        prioritized_orders: Default::default(),
+    }
+}
+
+/// Runs `f` `ITERATIONS` times and prints the average allocated bytes and
+/// duration per run.
+fn measure<T>(name: &str, mut f: impl FnMut() -> T) {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        drop(black_box(f()));
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+    let allocated = (ALLOCATED.load(Ordering::Relaxed) - before) / ITERATIONS as usize;
+    println!("{name:<32} {allocated:>12} bytes {elapsed:>12?}");
+}
+
+fn main() {
+    let auction = auction();
+    println!("auction with {ORDERS} orders, averaged over {ITERATIONS} runs");
+
+    measure("clone auction", || auction.clone());
+    measure("deep copy auction (baseline)", || {
+        (auction.orders.to_vec(), auction.prices.as_ref().clone())
+    });
+    measure("build solve request", || {
+        solve::Request::new(
+            &auction,
+            &HashSet::new(),
+            Duration::from_secs(1),
+            false,
+            None,
+            OrderOrdering::Priority,
+            None,
+        )
+    });
+}
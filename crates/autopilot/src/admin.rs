@@ -0,0 +1,330 @@
+//! Authenticated HTTP API that allows operators to intervene in the run loop
+//! during incidents without restarting the process.
+
+use {
+    crate::{
+        boundary,
+        domain,
+        maintenance::UnknownTasks,
+        run_loop::{InFlightRelease, RunLoop},
+    },
+    axum::{
+        Json,
+        Router,
+        extract::State,
+        http::{HeaderMap, StatusCode, header::AUTHORIZATION},
+        routing::post,
+    },
+    serde::{Deserialize, Serialize},
+    std::{net::SocketAddr, sync::Arc},
+    subtle::ConstantTimeEq,
+    tokio::task::JoinHandle,
+};
+
+/// Interventions the admin API exposes.
+#[async_trait::async_trait]
+pub trait Operations: Send + Sync + 'static {
+    fn skip_current_auction(&self);
+
+    async fn release_in_flight(&self, release: &InFlightRelease) -> Vec<domain::OrderUid>;
+
+    fn toggle_maintenance_tasks(&self, names: &[String], enabled: bool)
+    -> Result<(), UnknownTasks>;
+}
+
+#[async_trait::async_trait]
+impl Operations for RunLoop {
+    fn skip_current_auction(&self) {
+        RunLoop::skip_current_auction(self)
+    }
+
+    async fn release_in_flight(&self, release: &InFlightRelease) -> Vec<domain::OrderUid> {
+        RunLoop::release_in_flight(self, release).await
+    }
+
+    fn toggle_maintenance_tasks(
+        &self,
+        names: &[String],
+        enabled: bool,
+    ) -> Result<(), UnknownTasks> {
+        RunLoop::toggle_maintenance_tasks(self, names, enabled)
+    }
+}
+
+/// Serves the admin API at `address`. Requests have to authenticate with
+/// `Authorization: Bearer <token>`.
+pub fn serve(
+    operations: Arc<dyn Operations>,
+    token: String,
+    address: SocketAddr,
+) -> JoinHandle<()> {
+    let server = axum::Server::bind(&address).serve(router(operations, token).into_make_service());
+    tracing::info!(%address, "serving admin API");
+    tokio::spawn(async move {
+        if let Err(err) = server.await {
+            tracing::error!(?err, "admin API stopped");
+        }
+    })
+}
+
+#[derive(Clone)]
+struct Inner {
+    operations: Arc<dyn Operations>,
+    token: Arc<str>,
+}
+
+fn router(operations: Arc<dyn Operations>, token: String) -> Router {
+    Router::new()
+        .route("/admin/skip_auction", post(skip_auction))
+        .route("/admin/release_in_flight", post(release_in_flight))
+        .route("/admin/maintenance", post(toggle_maintenance))
+        .with_state(Inner {
+            operations,
+            token: token.into(),
+        })
+}
+
+fn is_authorized(inner: &Inner, headers: &HeaderMap) -> bool {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        // compare in constant time so the token can't be guessed byte by byte
+        .is_some_and(|token| token.as_bytes().ct_eq(inner.token.as_bytes()).into())
+}
+
+async fn skip_auction(State(inner): State<Inner>, headers: HeaderMap) -> StatusCode {
+    if !is_authorized(&inner, &headers) {
+        return StatusCode::UNAUTHORIZED;
+    }
+    inner.operations.skip_current_auction();
+    StatusCode::OK
+}
+
+/// Either `"all"` or `{"orders": [<UID>, ...]}`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum ReleaseRequest {
+    All,
+    Orders(Vec<boundary::OrderUid>),
+}
+
+#[derive(Debug, Serialize)]
+struct ReleaseResponse {
+    released: Vec<boundary::OrderUid>,
+}
+
+async fn release_in_flight(
+    State(inner): State<Inner>,
+    headers: HeaderMap,
+    Json(request): Json<ReleaseRequest>,
+) -> Result<Json<ReleaseResponse>, StatusCode> {
+    if !is_authorized(&inner, &headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    let release = match request {
+        ReleaseRequest::All => InFlightRelease::All,
+        ReleaseRequest::Orders(orders) => {
+            InFlightRelease::Orders(orders.into_iter().map(Into::into).collect())
+        }
+    };
+    let released = inner.operations.release_in_flight(&release).await;
+    Ok(Json(ReleaseResponse {
+        released: released.into_iter().map(Into::into).collect(),
+    }))
+}
+
+/// Enables or disables the maintenance tasks with the given names.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MaintenanceRequest {
+    tasks: Vec<String>,
+    enabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct MaintenanceError {
+    unknown: Vec<String>,
+}
+
+async fn toggle_maintenance(
+    State(inner): State<Inner>,
+    headers: HeaderMap,
+    Json(request): Json<MaintenanceRequest>,
+) -> Result<StatusCode, (StatusCode, Json<MaintenanceError>)> {
+    if !is_authorized(&inner, &headers) {
+        return Ok(StatusCode::UNAUTHORIZED);
+    }
+    inner
+        .operations
+        .toggle_maintenance_tasks(&request.tasks, request.enabled)
+        .map_err(|UnknownTasks(unknown)| {
+            (StatusCode::BAD_REQUEST, Json(MaintenanceError { unknown }))
+        })?;
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::sync::{
+            Mutex,
+            atomic::{AtomicBool, Ordering},
+        },
+    };
+
+    #[derive(Default)]
+    struct FakeOperations {
+        skipped: AtomicBool,
+        released: Mutex<Vec<InFlightRelease>>,
+        toggled: Mutex<Vec<(Vec<String>, bool)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Operations for FakeOperations {
+        fn skip_current_auction(&self) {
+            self.skipped.store(true, Ordering::SeqCst);
+        }
+
+        async fn release_in_flight(&self, release: &InFlightRelease) -> Vec<domain::OrderUid> {
+            self.released.lock().unwrap().push(release.clone());
+            vec![domain::OrderUid([1; 56])]
+        }
+
+        fn toggle_maintenance_tasks(
+            &self,
+            names: &[String],
+            enabled: bool,
+        ) -> Result<(), UnknownTasks> {
+            let unknown: Vec<_> = names
+                .iter()
+                .filter(|name| *name != "indexer")
+                .cloned()
+                .collect();
+            if !unknown.is_empty() {
+                return Err(UnknownTasks(unknown));
+            }
+            self.toggled.lock().unwrap().push((names.to_vec(), enabled));
+            Ok(())
+        }
+    }
+
+    /// Serves the admin API on an ephemeral port and returns its address.
+    fn start(operations: Arc<FakeOperations>) -> String {
+        let server = axum::Server::bind(&"127.0.0.1:0".parse().unwrap())
+            .serve(router(operations, "secret".to_string()).into_make_service());
+        let url = format!("http://{}/admin", server.local_addr());
+        tokio::spawn(server);
+        url
+    }
+
+    fn post(url: &str, token: Option<&str>) -> reqwest::RequestBuilder {
+        let request = reqwest::Client::new().post(url);
+        match token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_unauthorized_requests() {
+        let operations = Arc::new(FakeOperations::default());
+        let url = start(operations.clone());
+
+        for token in [None, Some("wrong"), Some("secre"), Some("secrets")] {
+            let response = post(&format!("{url}/skip_auction"), token)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+            let response = post(&format!("{url}/release_in_flight"), token)
+                .json(&"all")
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+            let response = post(&format!("{url}/maintenance"), token)
+                .json(&serde_json::json!({ "tasks": ["indexer"], "enabled": false }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+        }
+        assert!(!operations.skipped.load(Ordering::SeqCst));
+        assert!(operations.released.lock().unwrap().is_empty());
+        assert!(operations.toggled.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn toggles_maintenance_tasks() {
+        let operations = Arc::new(FakeOperations::default());
+        let url = start(operations.clone());
+
+        for enabled in [false, true] {
+            let response = post(&format!("{url}/maintenance"), Some("secret"))
+                .json(&serde_json::json!({ "tasks": ["indexer"], "enabled": enabled }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::OK);
+        }
+        assert_eq!(
+            *operations.toggled.lock().unwrap(),
+            [
+                (vec!["indexer".to_string()], false),
+                (vec!["indexer".to_string()], true),
+            ]
+        );
+
+        // unknown tasks get rejected and reported
+        let response = post(&format!("{url}/maintenance"), Some("secret"))
+            .json(&serde_json::json!({ "tasks": ["indexer", "typo"], "enabled": false }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.json::<serde_json::Value>().await.unwrap(),
+            serde_json::json!({ "unknown": ["typo"] })
+        );
+        assert_eq!(operations.toggled.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn forwards_authorized_requests() {
+        let operations = Arc::new(FakeOperations::default());
+        let url = start(operations.clone());
+
+        let response = post(&format!("{url}/skip_auction"), Some("secret"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert!(operations.skipped.load(Ordering::SeqCst));
+
+        let uid = boundary::OrderUid([2; 56]);
+        for body in [
+            serde_json::json!("all"),
+            serde_json::json!({ "orders": [uid] }),
+        ] {
+            let response = post(&format!("{url}/release_in_flight"), Some("secret"))
+                .json(&body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::OK);
+            assert_eq!(
+                response.json::<serde_json::Value>().await.unwrap(),
+                serde_json::json!({ "released": [boundary::OrderUid([1; 56])] })
+            );
+        }
+        assert_eq!(
+            *operations.released.lock().unwrap(),
+            [
+                InFlightRelease::All,
+                InFlightRelease::Orders([uid.into()].into()),
+            ]
+        );
+    }
+}
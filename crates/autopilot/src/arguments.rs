@@ -1,10 +1,13 @@
 use {
-    crate::{domain::fee::FeeFactor, infra},
+    crate::{
+        domain::{competition::FairnessThreshold, eth, fee::FeeFactor},
+        infra,
+    },
     anyhow::{Context, anyhow, ensure},
     clap::ValueEnum,
     primitive_types::{H160, U256},
     shared::{
-        arguments::{display_list, display_option},
+        arguments::{display_list, display_option, display_secret_option},
         bad_token::token_owner_finder,
         http_client,
         price_estimation::{self, NativePriceEstimators},
@@ -13,7 +16,8 @@ use {
         fmt,
         fmt::{Display, Formatter},
         net::SocketAddr,
-        num::NonZeroUsize,
+        num::{NonZeroU64, NonZeroUsize},
+        path::PathBuf,
         str::FromStr,
         time::Duration,
     },
@@ -59,6 +63,16 @@ pub struct Arguments {
     #[clap(long, env, default_value = "0.0.0.0:9589")]
     pub metrics_address: SocketAddr,
 
+    /// Address of the admin API that allows operators to skip the current
+    /// auction, release in-flight orders and toggle maintenance tasks. Only
+    /// served if `admin_token` is set.
+    #[clap(long, env, default_value = "0.0.0.0:9591")]
+    pub admin_address: SocketAddr,
+
+    /// Bearer token authenticating requests to the admin API.
+    #[clap(long, env)]
+    pub admin_token: Option<String>,
+
     /// Url of the Postgres database. By default connects to locally running
     /// postgres.
     #[clap(long, env, default_value = "postgresql://")]
@@ -68,6 +82,10 @@ pub struct Arguments {
     #[clap(long, env, default_value = "500")]
     pub insert_batch_size: NonZeroUsize,
 
+    /// Writes of auction data taking longer than this get logged.
+    #[clap(long, env, default_value = "1s", value_parser = humantime::parse_duration)]
+    pub db_slow_write_threshold: Duration,
+
     /// Skip syncing past events (useful for local deployments)
     #[clap(long, env, action = clap::ArgAction::Set, default_value = "false")]
     pub skip_event_sync: bool,
@@ -113,6 +131,12 @@ pub struct Arguments {
     #[clap(long, env, use_value_delimiter = true)]
     pub banned_users: Vec<H160>,
 
+    /// Owners whose orders are exempt from the fairness check, e.g. large
+    /// price-insensitive treasury rebalances. Solutions don't get invalidated
+    /// because of a worse execution of these orders.
+    #[clap(long, env, use_value_delimiter = true)]
+    pub fairness_exempt_owners: Vec<H160>,
+
     /// If the auction hasn't been updated in this amount of time the pod fails
     /// the liveness check. Expects a value in seconds.
     #[clap(
@@ -123,6 +147,16 @@ pub struct Arguments {
     )]
     pub max_auction_age: Duration,
 
+    /// How often the health of the Ethereum node (sync status, age of its
+    /// latest block and peer count) gets probed.
+    #[clap(long, env, default_value = "30s", value_parser = humantime::parse_duration)]
+    pub node_health_interval: Duration,
+
+    /// The Ethereum node is reported as stalled once its latest block is
+    /// older than this.
+    #[clap(long, env, default_value = "2m", value_parser = humantime::parse_duration)]
+    pub max_node_block_age: Duration,
+
     /// Used to filter out limit orders with prices that are too far from the
     /// market price. 0 means no filtering.
     #[clap(long, env, default_value = "0")]
@@ -149,6 +183,23 @@ pub struct Arguments {
 
     /// A list of drivers in the following format:
     /// `<NAME>|<URL>|<SUBMISSION_ADDRESS>|<FAIRNESS_THRESHOLD>`
+    /// Drivers that can't handle big auctions can additionally specify
+    /// `max-orders=<N>` to only receive the `N` highest priority orders.
+    /// Adding `max-settlement-gas=<GAS>` discards all solutions of the driver
+    /// that are expected to use more gas than that.
+    /// `fairness-threshold-bps=<BPS>` tightens the fairness threshold of each
+    /// order to that share of the order's value.
+    /// `submission-address=<ADDRESS>[@<FROM>..<TO>]` (repeatable) allows the
+    /// driver to also submit solutions from that address, optionally only
+    /// between the RFC 3339 timestamps `FROM` (inclusive) and `TO`
+    /// (exclusive). This allows rotating the driver's key without a
+    /// synchronized configuration change.
+    /// `token-allow-list=<TOKEN>;<TOKEN>;...` only sends the driver orders
+    /// whose sell and buy tokens are both in the list.
+    /// `observe-only` ranks and stores the driver's solutions for comparison
+    /// but never lets them win.
+    /// `dto-version=<N>` selects the version of the `/solve` schema the driver
+    /// understands. Defaults to the first version.
     #[clap(long, env, use_value_delimiter = true)]
     pub drivers: Vec<Solver>,
 
@@ -168,6 +219,196 @@ pub struct Arguments {
     )]
     pub max_settlement_transaction_wait: Duration,
 
+    /// If a settlement transaction did not appear on chain after this many
+    /// blocks, the winning driver gets asked once to rebroadcast it (e.g. with
+    /// a higher gas price). Disabled if not set.
+    #[clap(long, env)]
+    pub settle_rebroadcast_after_blocks: Option<u64>,
+
+    /// Settlements only get rebroadcasted if fewer than this many blocks are
+    /// left until the submission deadline.
+    #[clap(long, env, default_value = "2")]
+    pub settle_rebroadcast_max_remaining_blocks: u64,
+
+    /// Orders that expire before the projected timestamp of the submission
+    /// deadline plus this margin don't get sent to the drivers.
+    #[clap(
+        long,
+        env,
+        default_value = "0s",
+        value_parser = humantime::parse_duration,
+    )]
+    pub order_expiry_margin: Duration,
+
+    /// Orders need to stay valid for at least this many blocks after the
+    /// submission deadline to be sent to the drivers.
+    #[clap(long, env, default_value = "0")]
+    pub min_order_validity_blocks: u64,
+
+    /// Auctions whose content (orders, prices, JIT order owners) didn't change
+    /// since the last run only get sent to the drivers again after this many
+    /// blocks passed. The default of 1 re-solves unchanged auctions on every
+    /// new block.
+    #[clap(long, env, default_value = "1")]
+    pub max_auction_reuse_blocks: u64,
+
+    /// How many of the most recent auctions get solved again (without
+    /// settling anything) on startup to warm up caches before going live.
+    #[clap(long, env, default_value = "0")]
+    pub replay_recent_auctions: usize,
+
+    /// Order executions in proposed solutions that are worth less than this
+    /// many wei on either side get dropped. Executions with a zero amount are
+    /// always dropped.
+    #[clap(long, env, default_value = "0", value_parser = U256::from_dec_str)]
+    pub dust_execution_threshold: U256,
+
+    /// Settled trades worth less than this many wei are ignored when comparing
+    /// the native prices implied by a settlement with the native prices of its
+    /// auction, since rounding makes their implied prices too noisy.
+    #[clap(
+        long,
+        env,
+        default_value = "10000000000000000",
+        value_parser = U256::from_dec_str
+    )]
+    pub min_price_deviation_volume: U256,
+
+    /// Name of a configured driver that takes over the submission of a
+    /// winning solution if the `/settle` request to the winning driver times
+    /// out. It submits the winning solution's calldata, revealed before
+    /// settling, from its own address and only takes over in auctions it
+    /// didn't win itself. The original submission deadline still applies.
+    #[clap(long, env)]
+    pub fallback_submitter: Option<String>,
+
+    /// Over how many of the most recent auctions the concentration of winners
+    /// (Herfindahl-Hirschman index) gets measured.
+    #[clap(long, env, default_value = "100")]
+    pub winner_concentration_window: NonZeroUsize,
+
+    /// Directory to dump a JSON report of every auction's run loop decisions
+    /// (solutions, winner selection and settlement outcomes) into. Reports
+    /// are meant for post-mortems and are not written if not set.
+    #[clap(long, env)]
+    pub run_report_dir: Option<PathBuf>,
+
+    /// Every auction whose id is a multiple of this only gets solved to
+    /// measure the quality of the solvers' prices. None of its solutions get
+    /// settled. Quote-only auctions are disabled if not set.
+    #[clap(long, env)]
+    pub quote_only_interval: Option<NonZeroU64>,
+
+    /// Minimum time between two updates of the solver participation guard.
+    /// Competition updates arriving in between get coalesced into a single
+    /// update. 0s updates the guard after every auction.
+    #[clap(
+        long,
+        env,
+        default_value = "0s",
+        value_parser = humantime::parse_duration,
+    )]
+    pub participation_update_interval: Duration,
+
+    /// Once this fraction of the drivers responded to `/solve` the remaining
+    /// drivers only get `early_finish_grace` more time before they are
+    /// considered timed out. All drivers get the full solve deadline if not
+    /// set.
+    #[clap(long, env)]
+    pub early_finish_fraction: Option<f64>,
+
+    /// How much time drivers that didn't respond yet still get once
+    /// `early_finish_fraction` of the drivers responded.
+    #[clap(
+        long,
+        env,
+        default_value = "500ms",
+        value_parser = humantime::parse_duration,
+    )]
+    pub early_finish_grace: Duration,
+
+    /// Enables strict mode: the autopilot refuses to start without drivers and
+    /// reports itself as unhealthy once all drivers were deny listed or
+    /// unreachable for this many consecutive auctions.
+    #[clap(long, env)]
+    pub max_auctions_without_drivers: Option<NonZeroUsize>,
+
+    /// Reports the autopilot as unhealthy if the solvable orders cache still
+    /// hasn't built its first auction this long after startup. An auction
+    /// without orders doesn't count as unhealthy.
+    #[clap(long, env, value_parser = humantime::parse_duration)]
+    pub max_auction_cache_startup_delay: Option<Duration>,
+
+    /// How many blocks have to be built on top of the block a settlement
+    /// transaction got included in before the settlement counts as
+    /// successful. Settlements that get reorged out in the meantime fail.
+    #[clap(long, env, default_value = "0")]
+    pub settlement_confirmation_blocks: u64,
+
+    /// For how many blocks the orders of a successful settlement can't be
+    /// settled again in case the settlement gets reorged. Also delays further
+    /// fills of partially fillable orders by as many blocks.
+    #[clap(long, env, default_value = "2")]
+    pub in_flight_reorg_protection_blocks: u64,
+
+    /// How long estimating the gas of a winning solution without driver
+    /// reported gas may take. Runs alongside the settlement so it never
+    /// delays it.
+    #[clap(long, env, default_value = "1s", value_parser = humantime::parse_duration)]
+    pub gas_estimation_timeout: Duration,
+
+    /// Gas recorded for winning solutions whose gas could neither be taken
+    /// from the driver nor estimated. The gas of the hooks of their orders
+    /// gets added on top.
+    #[clap(long, env, default_value = "300000")]
+    pub fallback_settlement_gas: u64,
+
+    /// Scores (in wei) within this distance of each other are considered tied
+    /// and get ordered randomly instead of by meaningless score differences.
+    /// Reference scores of winners that tied with a higher score are capped at
+    /// the winning score so their rewards can't get negative.
+    #[clap(long, env, default_value = "0", value_parser = U256::from_dec_str)]
+    pub score_tie_epsilon: U256,
+
+    /// Fairness threshold (in wei) that applies to every solution. Drivers
+    /// without a fairness threshold get this one and drivers with a higher
+    /// (more lenient) threshold get capped to it. Drivers with a lower
+    /// (stricter) threshold keep theirs since a bigger threshold tolerates
+    /// more unfairness, so the floor never loosens a driver's threshold.
+    #[clap(long, env, value_parser = U256::from_dec_str)]
+    pub fairness_threshold_floor: Option<U256>,
+
+    /// Order in which the orders of an auction get sent to the drivers.
+    /// Everything but `unchanged` only depends on the auction id and its
+    /// orders, so all drivers see the same order.
+    #[clap(long, env, value_enum, default_value = "unchanged")]
+    pub order_ordering: OrderOrdering,
+
+    /// What solutions get ranked by before winners get picked. The scores of
+    /// the solutions don't change.
+    #[clap(long, env, value_enum, default_value = "score")]
+    pub winner_objective: WinnerObjective,
+
+    /// How winner slots get filled after the best solution.
+    #[clap(long, env, value_enum, default_value = "greedy")]
+    pub winner_merging: WinnerMerging,
+
+    /// How many solutions may win on top of `max_winners_per_auction` if they
+    /// settle orders no other winner settles. Only used with
+    /// `--winner-merging extra-winners`.
+    #[clap(long, env, default_value = "0")]
+    pub max_extra_winners: usize,
+
+    /// Report winning solutions whose protocol fee for an order exceeds this
+    /// share of the order's volume (in basis points).
+    #[clap(long, env)]
+    pub max_protocol_fee_bps: Option<u32>,
+
+    /// Names of maintenance tasks that should not run (e.g.
+    /// `cow_amm_indexer`).
+    #[clap(long, env, use_value_delimiter = true)]
+    pub disabled_maintenance_tasks: Vec<String>,
+
     /// Run the autopilot in a shadow mode by specifying an upstream CoW
     /// protocol deployment to pull auctions from. This will cause the autopilot
     /// to start a run loop where it performs solver competition on driver,
@@ -179,6 +420,13 @@ pub struct Arguments {
     #[clap(long, env)]
     pub shadow: Option<Url>,
 
+    /// Name of the driver under evaluation in shadow mode. If set, the shadow
+    /// competition gets compared with the production competition of the same
+    /// auction and the comparison gets stored. Drivers configured with an
+    /// address are matched with their production solutions by it.
+    #[clap(long, env)]
+    pub shadow_candidate_driver: Option<String>,
+
     /// Time solvers have to compute a score per auction.
     #[clap(
         long,
@@ -188,6 +436,17 @@ pub struct Arguments {
     )]
     pub solve_deadline: Duration,
 
+    /// Upper bound for how much earlier than the solve deadline a driver gets
+    /// asked to respond, based on the observed network latency to it. The
+    /// autopilot still waits for the full solve deadline. 0s disables it.
+    #[clap(
+        long,
+        env,
+        default_value = "0s",
+        value_parser = humantime::parse_duration,
+    )]
+    pub max_solve_deadline_padding: Duration,
+
     /// Describes how the protocol fees should be calculated.
     #[clap(long, env, use_value_delimiter = true)]
     pub fee_policies: Vec<FeePolicy>,
@@ -211,6 +470,31 @@ pub struct Arguments {
     #[clap(long, env, default_value = "30d", value_parser = humantime::parse_duration)]
     pub order_events_cleanup_threshold: Duration,
 
+    /// How long the JSON blobs of the `solver_competitions` table get kept.
+    /// Kept forever if not set.
+    #[clap(long, env, value_parser = humantime::parse_duration)]
+    pub solver_competition_retention: Option<Duration>,
+
+    /// How long the `auction_prices` of an auction get kept. Kept forever if
+    /// not set.
+    #[clap(long, env, value_parser = humantime::parse_duration)]
+    pub auction_prices_retention: Option<Duration>,
+
+    /// How long `settlement_executions` get kept. Kept forever if not set.
+    /// Gets extended to the longest retention of the auction based data since
+    /// the age of auctions is derived from their settlement executions.
+    #[clap(long, env, value_parser = humantime::parse_duration)]
+    pub settlement_executions_retention: Option<Duration>,
+
+    /// Maximum number of rows deleted by a single data retention query.
+    #[clap(long, env, default_value = "1000")]
+    pub retention_batch_size: u64,
+
+    /// Maximum number of rows deleted for data retention per block. Keeps
+    /// pruning from putting too much load on the database.
+    #[clap(long, env, default_value = "10000")]
+    pub retention_rows_per_block: u64,
+
     /// Configurations for indexing CoW AMMs. Supplied in the form of:
     /// "<factory1>|<helper1>|<block1>,<factory2>|<helper2>,<block2>"
     /// - factory is contract address emmiting CoW AMM deployment events.
@@ -227,6 +511,12 @@ pub struct Arguments {
     #[clap(long, env, default_value = "2s", value_parser = humantime::parse_duration)]
     pub max_run_loop_delay: Duration,
 
+    /// Maximum time storing the competition data of an auction may take. The
+    /// auction gets skipped instead of blocking the run loop if the database
+    /// hangs.
+    #[clap(long, env, default_value = "20s", value_parser = humantime::parse_duration)]
+    pub post_processing_timeout: Duration,
+
     /// Maximum timeout for fetching the native prices in the run loop
     /// If the value is 0, the native prices are fetched from the cache
     #[clap(long, env, default_value = "0s", value_parser = humantime::parse_duration)]
@@ -245,11 +535,59 @@ pub struct Arguments {
     /// `combinatorial_auctions_cutover`.
     pub max_winners_per_auction: NonZeroUsize,
 
+    #[clap(long, env)]
+    /// The maximum number of winners per auction that may share the same
+    /// submission address. Several drivers can settle through the same
+    /// on-chain solver, so this limits nonce contention between them.
+    /// Unlimited if not set.
+    pub max_winners_per_submission_address: Option<NonZeroUsize>,
+
+    /// For how many auctions the orders of solutions that only lost due to
+    /// token conflicts with the winners (while winner slots were left) get
+    /// prioritized when auctions get truncated or ordered by priority.
+    /// Disabled if not set.
+    #[clap(long, env)]
+    pub conflict_deferral_auctions: Option<NonZeroUsize>,
+
+    /// Submission addresses solutions may come from in addition to matching
+    /// their driver's configuration. Guards against a misconfigured driver
+    /// introducing an arbitrary address. All addresses are allowed if not set.
+    #[clap(long, env, use_value_delimiter = true)]
+    pub allowed_submission_addresses: Option<Vec<H160>>,
+
+    /// Whether winner selection considers solutions trading the native token
+    /// to overlap with solutions trading its wrapped counterpart (e.g. ETH
+    /// and WETH). Only applies to combinatorial auctions.
+    #[clap(long, env, action = clap::ArgAction::Set, default_value = "true")]
+    pub treat_native_as_weth: bool,
+
     #[clap(long, env, default_value = "3")]
     /// The maximum allowed number of solutions to be proposed from a single
     /// solver, per auction.
     pub max_solutions_per_solver: NonZeroUsize,
 
+    /// Driver responses containing more solutions get rejected as a whole
+    /// before any of them is processed.
+    #[clap(long, env, default_value = "100")]
+    pub max_solutions_per_response: NonZeroUsize,
+
+    /// Whether driver responses containing several solutions with the same id
+    /// get rejected as a whole. Otherwise only the first solution with each id
+    /// is kept.
+    #[clap(long, env, action = clap::ArgAction::Set, default_value = "true")]
+    pub reject_duplicate_solution_ids: bool,
+
+    /// Whether the competition data of auctions that produced solutions but no
+    /// winners still gets stored for auditing.
+    #[clap(long, env, action = clap::ArgAction::Set, default_value = "true")]
+    pub persist_competitions_without_winners: bool,
+
+    /// Directed token pairs `<SELL_TOKEN>/<BUY_TOKEN>` whose settlement
+    /// outcomes get tracked individually. All other pairs share the `other`
+    /// label to bound the metric cardinality.
+    #[clap(long, env, use_value_delimiter = true)]
+    pub tracked_token_pairs: Vec<TokenPair>,
+
     /// Archive node URL used to index CoW AMM
     #[clap(long, env)]
     pub archive_node_url: Option<Url>,
@@ -352,6 +690,8 @@ impl std::fmt::Display for Arguments {
             ethflow_contracts,
             ethflow_indexing_start,
             metrics_address,
+            admin_address,
+            admin_token,
             skip_event_sync,
             allowed_tokens,
             unsupported_tokens,
@@ -359,7 +699,10 @@ impl std::fmt::Display for Arguments {
             native_price_estimators,
             min_order_validity_period,
             banned_users,
+            fairness_exempt_owners,
             max_auction_age,
+            node_health_interval,
+            max_node_block_age,
             limit_order_price_factor,
             trusted_tokens_url,
             trusted_tokens,
@@ -367,23 +710,69 @@ impl std::fmt::Display for Arguments {
             drivers,
             submission_deadline,
             shadow,
+            shadow_candidate_driver,
             solve_deadline,
+            max_solve_deadline_padding,
             fee_policies,
             fee_policy_max_partner_fee,
             order_events_cleanup_interval,
             order_events_cleanup_threshold,
+            solver_competition_retention,
+            auction_prices_retention,
+            settlement_executions_retention,
+            retention_batch_size,
+            retention_rows_per_block,
             db_url,
             insert_batch_size,
+            db_slow_write_threshold,
             native_price_estimation_results_required,
             max_settlement_transaction_wait,
+            settle_rebroadcast_after_blocks,
+            settle_rebroadcast_max_remaining_blocks,
+            order_expiry_margin,
+            min_order_validity_blocks,
+            max_auction_reuse_blocks,
+            replay_recent_auctions,
+            dust_execution_threshold,
+            min_price_deviation_volume,
+            fallback_submitter,
+            winner_concentration_window,
+            run_report_dir,
+            quote_only_interval,
+            participation_update_interval,
+            early_finish_fraction,
+            early_finish_grace,
+            max_auctions_without_drivers,
+            max_auction_cache_startup_delay,
+            settlement_confirmation_blocks,
+            in_flight_reorg_protection_blocks,
+            gas_estimation_timeout,
+            fallback_settlement_gas,
+            score_tie_epsilon,
+            fairness_threshold_floor,
+            order_ordering,
+            winner_objective,
+            winner_merging,
+            max_extra_winners,
+            max_protocol_fee_bps,
+            disabled_maintenance_tasks,
             s3,
             cow_amm_configs,
             max_run_loop_delay,
+            post_processing_timeout,
             run_loop_native_price_timeout,
             combinatorial_auctions_cutover,
             max_winners_per_auction,
+            max_winners_per_submission_address,
+            conflict_deferral_auctions,
+            allowed_submission_addresses,
+            treat_native_as_weth,
             archive_node_url,
             max_solutions_per_solver,
+            max_solutions_per_response,
+            reject_duplicate_solution_ids,
+            persist_competitions_without_winners,
+            tracked_token_pairs,
             db_based_solver_participation_guard,
         } = self;
 
@@ -396,6 +785,8 @@ impl std::fmt::Display for Arguments {
         writeln!(f, "ethflow_contracts: {ethflow_contracts:?}")?;
         writeln!(f, "ethflow_indexing_start: {ethflow_indexing_start:?}")?;
         writeln!(f, "metrics_address: {metrics_address}")?;
+        writeln!(f, "admin_address: {admin_address}")?;
+        display_secret_option(f, "admin_token", admin_token.as_ref())?;
         let _intentionally_ignored = db_url;
         writeln!(f, "db_url: SECRET")?;
         writeln!(f, "skip_event_sync: {skip_event_sync}")?;
@@ -408,7 +799,10 @@ impl std::fmt::Display for Arguments {
             "min_order_validity_period: {min_order_validity_period:?}"
         )?;
         writeln!(f, "banned_users: {banned_users:?}")?;
+        writeln!(f, "fairness_exempt_owners: {fairness_exempt_owners:?}")?;
         writeln!(f, "max_auction_age: {max_auction_age:?}")?;
+        writeln!(f, "node_health_interval: {node_health_interval:?}")?;
+        writeln!(f, "max_node_block_age: {max_node_block_age:?}")?;
         writeln!(f, "limit_order_price_factor: {limit_order_price_factor:?}")?;
         display_option(f, "trusted_tokens_url", trusted_tokens_url)?;
         writeln!(f, "trusted_tokens: {trusted_tokens:?}")?;
@@ -419,7 +813,12 @@ impl std::fmt::Display for Arguments {
         display_list(f, "drivers", drivers.iter())?;
         writeln!(f, "submission_deadline: {submission_deadline}")?;
         display_option(f, "shadow", shadow)?;
+        display_option(f, "shadow_candidate_driver", shadow_candidate_driver)?;
         writeln!(f, "solve_deadline: {solve_deadline:?}")?;
+        writeln!(
+            f,
+            "max_solve_deadline_padding: {max_solve_deadline_padding:?}"
+        )?;
         writeln!(f, "fee_policies: {fee_policies:?}")?;
         writeln!(
             f,
@@ -433,7 +832,19 @@ impl std::fmt::Display for Arguments {
             f,
             "order_events_cleanup_threshold: {order_events_cleanup_threshold:?}"
         )?;
+        writeln!(
+            f,
+            "solver_competition_retention: {solver_competition_retention:?}"
+        )?;
+        writeln!(f, "auction_prices_retention: {auction_prices_retention:?}")?;
+        writeln!(
+            f,
+            "settlement_executions_retention: {settlement_executions_retention:?}"
+        )?;
+        writeln!(f, "retention_batch_size: {retention_batch_size}")?;
+        writeln!(f, "retention_rows_per_block: {retention_rows_per_block}")?;
         writeln!(f, "insert_batch_size: {insert_batch_size}")?;
+        writeln!(f, "db_slow_write_threshold: {db_slow_write_threshold:?}")?;
         writeln!(
             f,
             "native_price_estimation_results_required: {native_price_estimation_results_required}"
@@ -442,9 +853,70 @@ impl std::fmt::Display for Arguments {
             f,
             "max_settlement_transaction_wait: {max_settlement_transaction_wait:?}"
         )?;
+        writeln!(
+            f,
+            "settle_rebroadcast_after_blocks: {settle_rebroadcast_after_blocks:?}"
+        )?;
+        writeln!(
+            f,
+            "settle_rebroadcast_max_remaining_blocks: {settle_rebroadcast_max_remaining_blocks}"
+        )?;
+        writeln!(f, "order_expiry_margin: {order_expiry_margin:?}")?;
+        writeln!(f, "min_order_validity_blocks: {min_order_validity_blocks}")?;
+        writeln!(f, "max_auction_reuse_blocks: {max_auction_reuse_blocks}")?;
+        writeln!(f, "replay_recent_auctions: {replay_recent_auctions}")?;
+        writeln!(f, "dust_execution_threshold: {dust_execution_threshold}")?;
+        writeln!(
+            f,
+            "min_price_deviation_volume: {min_price_deviation_volume}"
+        )?;
+        display_option(f, "fallback_submitter", fallback_submitter)?;
+        writeln!(
+            f,
+            "winner_concentration_window: {winner_concentration_window}"
+        )?;
+        writeln!(f, "run_report_dir: {run_report_dir:?}")?;
+        display_option(f, "quote_only_interval", quote_only_interval)?;
+        writeln!(
+            f,
+            "participation_update_interval: {participation_update_interval:?}"
+        )?;
+        display_option(f, "early_finish_fraction", early_finish_fraction)?;
+        writeln!(f, "early_finish_grace: {early_finish_grace:?}")?;
+        display_option(
+            f,
+            "max_auctions_without_drivers",
+            max_auctions_without_drivers,
+        )?;
+        writeln!(
+            f,
+            "max_auction_cache_startup_delay: {max_auction_cache_startup_delay:?}"
+        )?;
+        writeln!(
+            f,
+            "settlement_confirmation_blocks: {settlement_confirmation_blocks}"
+        )?;
+        writeln!(
+            f,
+            "in_flight_reorg_protection_blocks: {in_flight_reorg_protection_blocks}"
+        )?;
+        writeln!(f, "gas_estimation_timeout: {gas_estimation_timeout:?}")?;
+        writeln!(f, "fallback_settlement_gas: {fallback_settlement_gas}")?;
+        writeln!(f, "score_tie_epsilon: {score_tie_epsilon}")?;
+        display_option(f, "fairness_threshold_floor", fairness_threshold_floor)?;
+        writeln!(f, "order_ordering: {order_ordering:?}")?;
+        writeln!(f, "winner_objective: {winner_objective:?}")?;
+        writeln!(f, "winner_merging: {winner_merging:?}")?;
+        writeln!(f, "max_extra_winners: {max_extra_winners}")?;
+        display_option(f, "max_protocol_fee_bps", max_protocol_fee_bps)?;
+        writeln!(
+            f,
+            "disabled_maintenance_tasks: {disabled_maintenance_tasks:?}"
+        )?;
         writeln!(f, "s3: {s3:?}")?;
         writeln!(f, "cow_amm_configs: {cow_amm_configs:?}")?;
         writeln!(f, "max_run_loop_delay: {max_run_loop_delay:?}")?;
+        writeln!(f, "post_processing_timeout: {post_processing_timeout:?}")?;
         writeln!(
             f,
             "run_loop_native_price_timeout: {run_loop_native_price_timeout:?}"
@@ -454,8 +926,32 @@ impl std::fmt::Display for Arguments {
             "combinatorial_auctions_cutover: {combinatorial_auctions_cutover:?}"
         )?;
         writeln!(f, "max_winners_per_auction: {max_winners_per_auction:?}")?;
+        display_option(
+            f,
+            "max_winners_per_submission_address",
+            max_winners_per_submission_address,
+        )?;
+        display_option(f, "conflict_deferral_auctions", conflict_deferral_auctions)?;
+        writeln!(
+            f,
+            "allowed_submission_addresses: {allowed_submission_addresses:?}"
+        )?;
+        writeln!(f, "treat_native_as_weth: {treat_native_as_weth}")?;
         writeln!(f, "archive_node_url: {archive_node_url:?}")?;
         writeln!(f, "max_solutions_per_solver: {max_solutions_per_solver:?}")?;
+        writeln!(
+            f,
+            "max_solutions_per_response: {max_solutions_per_response:?}"
+        )?;
+        writeln!(
+            f,
+            "reject_duplicate_solution_ids: {reject_duplicate_solution_ids}"
+        )?;
+        writeln!(
+            f,
+            "persist_competitions_without_winners: {persist_competitions_without_winners}"
+        )?;
+        writeln!(f, "tracked_token_pairs: {tracked_token_pairs:?}")?;
         writeln!(
             f,
             "db_based_solver_participation_guard: {db_based_solver_participation_guard:?}"
@@ -470,8 +966,22 @@ pub struct Solver {
     pub name: String,
     pub url: Url,
     pub submission_account: Account,
+    /// Further addresses the driver may submit solutions from, e.g. while its
+    /// key gets rotated.
+    pub additional_submission_addresses: Vec<infra::solvers::SubmissionAddress>,
     pub fairness_threshold: Option<U256>,
+    /// Tightens the fairness threshold to this share (in basis points) of
+    /// the value of each order.
+    pub fairness_threshold_bps: Option<u32>,
     pub requested_timeout_on_problems: bool,
+    pub max_orders: Option<NonZeroUsize>,
+    pub max_settlement_gas: Option<u64>,
+    /// Tokens the driver is allowed to trade.
+    pub token_allow_list: Option<Vec<H160>>,
+    /// Solutions get ranked and stored but never win.
+    pub observe_only: bool,
+    /// Version of the `/solve` schema the driver understands.
+    pub dto_version: infra::solvers::dto::solve::Version,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -538,12 +1048,94 @@ impl FromStr for Solver {
             requested_timeout_on_problems = value.to_lowercase() == "requested-timeout-on-problems";
         }
 
+        let max_orders = parts
+            .iter()
+            .skip(3)
+            .find_map(|part| part.strip_prefix("max-orders="))
+            .map(|value| value.parse().context("failed to parse max orders"))
+            .transpose()?;
+
+        let max_settlement_gas = parts
+            .iter()
+            .skip(3)
+            .find_map(|part| part.strip_prefix("max-settlement-gas="))
+            .map(|value| value.parse().context("failed to parse max settlement gas"))
+            .transpose()?;
+
+        let fairness_threshold_bps = parts
+            .iter()
+            .skip(3)
+            .find_map(|part| part.strip_prefix("fairness-threshold-bps="))
+            .map(|value| {
+                value
+                    .parse()
+                    .context("failed to parse fairness threshold bps")
+            })
+            .transpose()?;
+
+        let additional_submission_addresses = parts
+            .iter()
+            .skip(3)
+            .filter_map(|part| part.strip_prefix("submission-address="))
+            .map(|value| {
+                value
+                    .parse()
+                    .context("failed to parse additional submission address")
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        let token_allow_list = parts
+            .iter()
+            .skip(3)
+            .find_map(|part| part.strip_prefix("token-allow-list="))
+            .map(|value| {
+                value
+                    .split(';')
+                    .map(|token| H160::from_str(token).context("failed to parse allowed token"))
+                    .collect::<anyhow::Result<_>>()
+            })
+            .transpose()?;
+
+        let observe_only = parts
+            .iter()
+            .skip(3)
+            .any(|part| part.eq_ignore_ascii_case("observe-only"));
+
+        let dto_version = parts
+            .iter()
+            .skip(3)
+            .find_map(|part| part.strip_prefix("dto-version="))
+            .map(|value| value.parse())
+            .transpose()?
+            .unwrap_or_default();
+
         Ok(Self {
             name: name.to_owned(),
             url,
             fairness_threshold,
+            fairness_threshold_bps,
             submission_account,
+            additional_submission_addresses,
             requested_timeout_on_problems,
+            max_orders,
+            max_settlement_gas,
+            token_allow_list,
+            observe_only,
+            dto_version,
+        })
+    }
+}
+
+impl Solver {
+    /// Fairness threshold of the driver's solutions. Only setting the bps
+    /// makes the threshold purely relative to the order value.
+    pub fn fairness_threshold_policy(&self) -> Option<FairnessThreshold> {
+        if self.fairness_threshold.is_none() && self.fairness_threshold_bps.is_none() {
+            return None;
+        }
+        Some(FairnessThreshold {
+            flat: eth::Ether(self.fairness_threshold.unwrap_or(U256::MAX)),
+            bps_of_order_value: self.fairness_threshold_bps,
         })
     }
 }
@@ -585,6 +1177,39 @@ pub enum FeePolicyKind {
     Volume { factor: FeeFactor },
 }
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum OrderOrdering {
+    /// Keep the order in which the orders were loaded from the database.
+    Unchanged,
+    /// Shuffle the orders with the auction id as the seed.
+    Shuffled,
+    /// Oldest orders first.
+    CreationTime,
+    /// Sort the orders by uid.
+    Uid,
+    /// Orders that are kept when truncating auctions (newest) first.
+    Priority,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum WinnerObjective {
+    /// Rank solutions by score.
+    Score,
+    /// Rank solutions by the number of auction orders they fill, then by
+    /// score. Winners with lower scores than other solutions get no reward.
+    OrderCoverage,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum WinnerMerging {
+    /// Fill the winner slots greedily by score among solutions disjoint with
+    /// all previously selected winners.
+    Greedy,
+    /// Like `greedy` but allow up to `max_extra_winners` additional winners
+    /// that settle orders no other winner settles.
+    ExtraWinners,
+}
+
 #[derive(clap::Parser, clap::ValueEnum, Clone, Debug)]
 pub enum FeePolicyOrderClass {
     /// If a fee policy needs to be applied to in-market orders.
@@ -704,10 +1329,56 @@ impl FromStr for CowAmmConfig {
     }
 }
 
+/// A directed token pair in the format `<SELL_TOKEN>/<BUY_TOKEN>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TokenPair {
+    pub sell: H160,
+    pub buy: H160,
+}
+
+impl FromStr for TokenPair {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (sell, buy) = s
+            .split_once('/')
+            .context("token pair must have the format <SELL_TOKEN>/<BUY_TOKEN>")?;
+        Ok(Self {
+            sell: sell.parse().context("could not parse sell token as H160")?,
+            buy: buy.parse().context("could not parse buy token as H160")?,
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use {super::*, hex_literal::hex};
 
+    #[test]
+    fn parse_token_pair() {
+        let pair: TokenPair = "0x0000000000000000000000000000000000000001/\
+                               0x0000000000000000000000000000000000000002"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            pair,
+            TokenPair {
+                sell: H160::from_low_u64_be(1),
+                buy: H160::from_low_u64_be(2),
+            }
+        );
+        assert!(
+            "0x0000000000000000000000000000000000000001"
+                .parse::<TokenPair>()
+                .is_err()
+        );
+        assert!(
+            "0x01/0x0000000000000000000000000000000000000002"
+                .parse::<TokenPair>()
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_fee_factor_limits() {
         let policies = vec![
@@ -742,10 +1413,17 @@ mod test {
             name: "name1".into(),
             url: Url::parse("http://localhost:8080").unwrap(),
             fairness_threshold: None,
+            fairness_threshold_bps: None,
             requested_timeout_on_problems: false,
             submission_account: Account::Address(H160::from_slice(&hex!(
                 "C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
             ))),
+            max_orders: None,
+            max_settlement_gas: None,
+            additional_submission_addresses: vec![],
+            token_allow_list: None,
+            observe_only: false,
+            dto_version: Default::default(),
         };
         assert_eq!(driver, expected);
     }
@@ -758,10 +1436,17 @@ mod test {
             name: "name1".into(),
             url: Url::parse("http://localhost:8080").unwrap(),
             fairness_threshold: None,
+            fairness_threshold_bps: None,
             requested_timeout_on_problems: false,
             submission_account: Account::Kms(
                 Arn::from_str("arn:aws:kms:supersecretstuff").unwrap(),
             ),
+            max_orders: None,
+            max_settlement_gas: None,
+            additional_submission_addresses: vec![],
+            token_allow_list: None,
+            observe_only: false,
+            dto_version: Default::default(),
         };
         assert_eq!(driver, expected);
     }
@@ -777,7 +1462,14 @@ mod test {
                 "C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
             ))),
             fairness_threshold: Some(U256::exp10(18)),
+            fairness_threshold_bps: None,
             requested_timeout_on_problems: false,
+            max_orders: None,
+            max_settlement_gas: None,
+            additional_submission_addresses: vec![],
+            token_allow_list: None,
+            observe_only: false,
+            dto_version: Default::default(),
         };
         assert_eq!(driver, expected);
     }
@@ -794,7 +1486,14 @@ mod test {
                 "C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
             ))),
             fairness_threshold: None,
+            fairness_threshold_bps: None,
             requested_timeout_on_problems: true,
+            max_orders: None,
+            max_settlement_gas: None,
+            additional_submission_addresses: vec![],
+            token_allow_list: None,
+            observe_only: false,
+            dto_version: Default::default(),
         };
         assert_eq!(driver, expected);
     }
@@ -810,8 +1509,171 @@ mod test {
                 "C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
             ))),
             fairness_threshold: Some(U256::exp10(18)),
+            fairness_threshold_bps: None,
             requested_timeout_on_problems: true,
+            max_orders: None,
+            max_settlement_gas: None,
+            additional_submission_addresses: vec![],
+            token_allow_list: None,
+            observe_only: false,
+            dto_version: Default::default(),
+        };
+        assert_eq!(driver, expected);
+    }
+
+    #[test]
+    fn parse_driver_with_max_orders() {
+        let argument = "name1|http://localhost:8080|0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2|1000000000000000000|max-orders=500";
+        let driver = Solver::from_str(argument).unwrap();
+        let expected = Solver {
+            name: "name1".into(),
+            url: Url::parse("http://localhost:8080").unwrap(),
+            submission_account: Account::Address(H160::from_slice(&hex!(
+                "C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+            ))),
+            fairness_threshold: Some(U256::exp10(18)),
+            fairness_threshold_bps: None,
+            requested_timeout_on_problems: false,
+            max_orders: Some(NonZeroUsize::new(500).unwrap()),
+            max_settlement_gas: None,
+            additional_submission_addresses: vec![],
+            token_allow_list: None,
+            observe_only: false,
+            dto_version: Default::default(),
+        };
+        assert_eq!(driver, expected);
+    }
+
+    #[test]
+    fn parse_driver_with_max_settlement_gas() {
+        let argument = "name1|http://localhost:8080|0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2|max-orders=500|max-settlement-gas=1500000";
+        let driver = Solver::from_str(argument).unwrap();
+        let expected = Solver {
+            name: "name1".into(),
+            url: Url::parse("http://localhost:8080").unwrap(),
+            submission_account: Account::Address(H160::from_slice(&hex!(
+                "C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
+            ))),
+            fairness_threshold: None,
+            fairness_threshold_bps: None,
+            requested_timeout_on_problems: false,
+            max_orders: Some(NonZeroUsize::new(500).unwrap()),
+            max_settlement_gas: Some(1_500_000),
+            additional_submission_addresses: vec![],
+            token_allow_list: None,
+            observe_only: false,
+            dto_version: Default::default(),
         };
         assert_eq!(driver, expected);
     }
+
+    #[test]
+    fn parse_driver_with_additional_submission_addresses() {
+        let argument = "name1|http://localhost:8080|0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2|submission-address=0x0000000000000000000000000000000000000001|submission-address=0x0000000000000000000000000000000000000002@2025-01-01T00:00:00Z..";
+        let driver = Solver::from_str(argument).unwrap();
+        let time = |timestamp| {
+            chrono::DateTime::parse_from_rfc3339(timestamp)
+                .unwrap()
+                .with_timezone(&chrono::Utc)
+        };
+        assert_eq!(
+            driver.additional_submission_addresses,
+            vec![
+                infra::solvers::SubmissionAddress {
+                    address: eth::Address(H160::from_low_u64_be(1)),
+                    valid_from: None,
+                    valid_to: None,
+                },
+                infra::solvers::SubmissionAddress {
+                    address: eth::Address(H160::from_low_u64_be(2)),
+                    valid_from: Some(time("2025-01-01T00:00:00Z")),
+                    valid_to: None,
+                },
+            ]
+        );
+
+        for invalid in [
+            "submission-address=0x01",
+            "submission-address=0x0000000000000000000000000000000000000001@2025-01-01T00:00:00Z",
+            "submission-address=0x0000000000000000000000000000000000000001@2025-01-02T00:00:00Z..\
+             2025-01-01T00:00:00Z",
+        ] {
+            let argument = format!(
+                "name1|http://localhost:8080|0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2|{invalid}"
+            );
+            assert!(Solver::from_str(&argument).is_err());
+        }
+    }
+
+    #[test]
+    fn parse_driver_with_token_allow_list() {
+        let argument = "name1|http://localhost:8080|0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2|token-allow-list=0x0000000000000000000000000000000000000001;0x0000000000000000000000000000000000000002";
+        let driver = Solver::from_str(argument).unwrap();
+        assert_eq!(
+            driver.token_allow_list,
+            Some(vec![H160::from_low_u64_be(1), H160::from_low_u64_be(2)])
+        );
+
+        let argument = "name1|http://localhost:8080|0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2|token-allow-list=0x01";
+        assert!(Solver::from_str(argument).is_err());
+    }
+
+    #[test]
+    fn parse_driver_dto_version() {
+        let argument = "name1|http://localhost:8080|0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+        let driver = Solver::from_str(argument).unwrap();
+        assert_eq!(driver.dto_version, infra::solvers::dto::solve::Version::V1);
+
+        let argument =
+            "name1|http://localhost:8080|0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2|dto-version=2";
+        let driver = Solver::from_str(argument).unwrap();
+        assert_eq!(driver.dto_version, infra::solvers::dto::solve::Version::V2);
+
+        let argument =
+            "name1|http://localhost:8080|0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2|dto-version=9";
+        assert!(Solver::from_str(argument).is_err());
+    }
+
+    #[test]
+    fn parse_observe_only_driver() {
+        let argument =
+            "name1|http://localhost:8080|0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2|observe-only";
+        let driver = Solver::from_str(argument).unwrap();
+        assert!(driver.observe_only);
+        assert!(!driver.requested_timeout_on_problems);
+
+        let argument = "name1|http://localhost:8080|0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2|1000000000000000000|max-orders=10";
+        let driver = Solver::from_str(argument).unwrap();
+        assert!(!driver.observe_only);
+    }
+
+    #[test]
+    fn parse_driver_with_fairness_threshold_bps() {
+        let argument = "name1|http://localhost:8080|0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2|1000000000000000000|fairness-threshold-bps=50";
+        let driver = Solver::from_str(argument).unwrap();
+        assert_eq!(driver.fairness_threshold, Some(U256::exp10(18)));
+        assert_eq!(driver.fairness_threshold_bps, Some(50));
+        assert_eq!(
+            driver.fairness_threshold_policy(),
+            Some(FairnessThreshold {
+                flat: eth::Ether(U256::exp10(18)),
+                bps_of_order_value: Some(50),
+            })
+        );
+
+        // without a flat threshold only the relative one applies
+        let argument = "name1|http://localhost:8080|0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2|fairness-threshold-bps=50";
+        let driver = Solver::from_str(argument).unwrap();
+        assert_eq!(driver.fairness_threshold, None);
+        assert_eq!(
+            driver.fairness_threshold_policy(),
+            Some(FairnessThreshold {
+                flat: eth::Ether(U256::MAX),
+                bps_of_order_value: Some(50),
+            })
+        );
+
+        let argument = "name1|http://localhost:8080|0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2|fairness-threshold-bps=x";
+        assert!(Solver::from_str(argument).is_err());
+    }
 }
@@ -45,5 +45,6 @@ pub fn to_domain(
         app_data: order.data.app_data.into(),
         signature: order.signature.into(),
         quote,
+        fairness_exempt: false,
     }
 }
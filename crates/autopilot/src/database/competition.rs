@@ -12,7 +12,7 @@ use {
     },
     derive_more::Debug,
     model::solver_competition::SolverCompetitionDB,
-    number::conversions::u256_to_big_decimal,
+    number::db::{i64_block_number, u256_to_big_decimal},
     primitive_types::{H160, U256},
     std::collections::{BTreeMap, HashMap, HashSet},
 };
@@ -75,13 +75,9 @@ impl super::Postgres {
                     winner: ByteArray(legacy.winner.0),
                     winning_score: u256_to_big_decimal(&legacy.winning_score),
                     reference_score: u256_to_big_decimal(&legacy.reference_score),
-                    block_deadline: competition
-                        .block_deadline
-                        .try_into()
+                    block_deadline: i64_block_number(competition.block_deadline)
                         .context("convert block deadline")?,
-                    simulation_block: competition
-                        .competition_simulation_block
-                        .try_into()
+                    simulation_block: i64_block_number(competition.competition_simulation_block)
                         .context("convert simulation block")?,
                 },
             )
@@ -1,6 +1,12 @@
 use {
-    super::{Order, eth},
-    std::collections::HashMap,
+    super::{Order, OrderUid, eth},
+    rand::{SeedableRng, rngs::StdRng, seq::SliceRandom},
+    std::{
+        borrow::Borrow,
+        cmp::Reverse,
+        collections::{HashMap, HashSet},
+        sync::Arc,
+    },
 };
 
 pub mod order;
@@ -13,31 +19,271 @@ pub mod order;
 #[derive(Clone, Debug, PartialEq)]
 pub struct RawAuctionData {
     pub block: u64,
-    pub orders: Vec<Order>,
-    pub prices: Prices,
+    pub orders: Arc<Vec<Order>>,
+    pub prices: Arc<Prices>,
     pub surplus_capturing_jit_order_owners: Vec<eth::Address>,
 }
 
+impl RawAuctionData {
+    /// Removes the orders that can no longer be settled in a block with the
+    /// given timestamp and returns their uids.
+    pub fn remove_orders_expiring_before(&mut self, timestamp: u64) -> Vec<OrderUid> {
+        let (expiring, valid): (Vec<_>, Vec<_>) = self
+            .orders
+            .iter()
+            .cloned()
+            .partition(|order| order.expires_before(timestamp));
+        if !expiring.is_empty() {
+            self.orders = Arc::new(valid);
+        }
+        expiring.into_iter().map(|order| order.uid).collect()
+    }
+}
+
 pub type Id = i64;
 
+/// Cloning an auction is cheap since the orders and prices are shared. To
+/// modify them build a new `Arc` (or use [`Arc::make_mut`]) so previously
+/// cloned auctions keep seeing the original content.
 #[derive(Clone, Debug)]
 pub struct Auction {
     pub id: Id,
     pub block: u64,
-    pub orders: Vec<Order>,
-    pub prices: Prices,
+    pub orders: Arc<Vec<Order>>,
+    pub prices: Arc<Prices>,
     pub surplus_capturing_jit_order_owners: Vec<eth::Address>,
+    /// Identifies auctions with the same content. See [`ContentHash`].
+    pub content_hash: ContentHash,
+    /// Quote-only auctions only get solved to measure the quality of the
+    /// solvers' prices. Their solutions never get settled.
+    pub quote_only: bool,
+    /// Orders that come first when the auction gets truncated or ordered by
+    /// [`OrderOrdering::Priority`], e.g. because they recently lost only due
+    /// to token conflicts with the winners.
+    pub prioritized_orders: Arc<HashSet<OrderUid>>,
+}
+
+impl Auction {
+    /// Returns a copy of the auction that only contains the `max_orders`
+    /// orders with the highest priority (prioritized orders come first,
+    /// followed by newer orders). Returns
+    /// `None` if the auction already contains few enough orders.
+    pub fn truncated(&self, max_orders: usize) -> Option<Self> {
+        if self.orders.len() <= max_orders {
+            return None;
+        }
+        let mut orders = self.orders.to_vec();
+        orders.sort_by_key(|order| priority(&self.prioritized_orders, order));
+        orders.truncate(max_orders);
+        Some(Self {
+            id: self.id,
+            block: self.block,
+            content_hash: ContentHash::new(
+                &orders,
+                &self.prices,
+                &self.surplus_capturing_jit_order_owners,
+            ),
+            orders: Arc::new(orders),
+            prices: self.prices.clone(),
+            surplus_capturing_jit_order_owners: self.surplus_capturing_jit_order_owners.clone(),
+            quote_only: self.quote_only,
+            prioritized_orders: self.prioritized_orders.clone(),
+        })
+    }
+
+    /// Returns a copy of the auction that only contains the orders whose sell
+    /// and buy tokens are both in `tokens` and the prices of the tokens these
+    /// orders trade.
+    pub fn restricted_to_tokens(&self, tokens: &HashSet<eth::TokenAddress>) -> Self {
+        let orders: Vec<_> = self
+            .orders
+            .iter()
+            .filter(|order| tokens.contains(&order.sell.token) && tokens.contains(&order.buy.token))
+            .cloned()
+            .collect();
+        let traded: HashSet<_> = orders
+            .iter()
+            .flat_map(|order| [order.sell.token, order.buy.token])
+            .collect();
+        let prices = self
+            .prices
+            .iter()
+            .filter(|(token, _)| traded.contains(token))
+            .map(|(token, price)| (*token, *price))
+            .collect();
+        Self {
+            id: self.id,
+            block: self.block,
+            content_hash: ContentHash::new(
+                &orders,
+                &prices,
+                &self.surplus_capturing_jit_order_owners,
+            ),
+            orders: Arc::new(orders),
+            prices: Arc::new(prices),
+            surplus_capturing_jit_order_owners: self.surplus_capturing_jit_order_owners.clone(),
+            quote_only: self.quote_only,
+            prioritized_orders: self.prioritized_orders.clone(),
+        }
+    }
+
+    /// Converts `amount` of `token` into ETH. See [`price_in_eth`].
+    pub fn price_in_eth(&self, token: eth::TokenAddress, amount: eth::U256) -> Option<eth::U256> {
+        price_in_eth(&self.prices, token, amount)
+    }
+
+    /// Volume in ETH the auction could settle at most: the sum over all
+    /// orders of the smaller of their sell and buy amounts in ETH. Returns
+    /// `None` if a price of a traded token is missing.
+    pub fn total_volume_eth(&self) -> Option<eth::U256> {
+        self.orders
+            .iter()
+            .try_fold(eth::U256::zero(), |total, order| {
+                let sell = self.price_in_eth(order.sell.token, order.sell.amount.0)?;
+                let buy = self.price_in_eth(order.buy.token, order.buy.amount.0)?;
+                Some(total.saturating_add(sell.min(buy)))
+            })
+    }
+}
+
+/// Sort key of an order where lower keys have a higher priority.
+fn priority(prioritized: &HashSet<OrderUid>, order: &Order) -> (bool, Reverse<u32>) {
+    (!prioritized.contains(&order.uid), Reverse(order.created))
 }
 
+/// Compares the block and [`ContentHash`] instead of every order and price.
 impl PartialEq for Auction {
     fn eq(&self, other: &Self) -> bool {
-        self.block == other.block
-            && self.orders == other.orders
-            && self.prices == other.prices
-            && self.surplus_capturing_jit_order_owners == other.surplus_capturing_jit_order_owners
+        self.block == other.block && self.content_hash == other.content_hash
+    }
+}
+
+/// Order in which the orders of an auction get sent to the drivers. Some
+/// drivers process orders sequentially and run out of time before reaching the
+/// last ones, so the order of the request would otherwise favor whichever
+/// orders happen to be loaded first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OrderOrdering {
+    /// Keeps the orders in the order they were loaded from the database in.
+    #[default]
+    Unchanged,
+    /// Shuffles the orders with the auction id as the seed.
+    Shuffled,
+    /// Oldest orders first.
+    CreationTime,
+    /// Sorted by uid.
+    Uid,
+    /// Orders with the highest priority first, i.e. the ones that are kept
+    /// when truncating an auction (see [`Auction::truncated`]).
+    Priority,
+}
+
+impl OrderOrdering {
+    /// Orders `orders` of the auction with the given id. Except for
+    /// [`OrderOrdering::Unchanged`] the result only depends on the auction id
+    /// the set of orders and the prioritized orders, so all drivers see the
+    /// same order within one auction and replaying an auction produces the
+    /// same request.
+    /// Works on borrowed orders too so requests can be built without copying
+    /// the orders of the auction.
+    pub fn apply<O: Borrow<Order>>(
+        self,
+        auction: Id,
+        prioritized: &HashSet<OrderUid>,
+        orders: &mut [O],
+    ) {
+        if self == Self::Unchanged {
+            return;
+        }
+        // Makes the result independent of the input order. Since the sorts
+        // below are stable the uid also breaks ties between orders.
+        orders.sort_by_key(|order| order.borrow().uid.0);
+        match self {
+            Self::Unchanged | Self::Uid => (),
+            Self::Shuffled => orders.shuffle(&mut StdRng::seed_from_u64(u64::from_be_bytes(
+                auction.to_be_bytes(),
+            ))),
+            Self::CreationTime => orders.sort_by_key(|order| order.borrow().created),
+            Self::Priority => orders.sort_by_key(|order| priority(prioritized, order.borrow())),
+        }
+    }
+}
+
+impl From<crate::arguments::OrderOrdering> for OrderOrdering {
+    fn from(value: crate::arguments::OrderOrdering) -> Self {
+        match value {
+            crate::arguments::OrderOrdering::Unchanged => Self::Unchanged,
+            crate::arguments::OrderOrdering::Shuffled => Self::Shuffled,
+            crate::arguments::OrderOrdering::CreationTime => Self::CreationTime,
+            crate::arguments::OrderOrdering::Uid => Self::Uid,
+            crate::arguments::OrderOrdering::Priority => Self::Priority,
+        }
+    }
+}
+
+/// Hash over everything in an auction that can influence the solutions
+/// solvers propose (orders, prices and surplus capturing JIT order owners).
+/// Unlike [`Auction`]'s `PartialEq` implementation it doesn't depend on the
+/// block the auction was built in, so it can be used to detect when the same
+/// auction would be solved again.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, derive_more::Debug)]
+pub struct ContentHash(#[debug("0x{}", hex::encode(self.0))] pub [u8; 32]);
+
+impl ContentHash {
+    pub fn new(orders: &[Order], prices: &Prices, jit_owners: &[eth::Address]) -> Self {
+        let mut orders: Vec<_> = orders.iter().collect();
+        orders.sort_by_key(|order| order.uid.0);
+        let mut prices: Vec<_> = prices.iter().collect();
+        prices.sort_by_key(|(token, _)| token.0);
+        let mut jit_owners = jit_owners.to_vec();
+        jit_owners.sort_by_key(|owner| owner.0);
+
+        let mut buf = Vec::new();
+        buf.extend((orders.len() as u64).to_be_bytes());
+        for order in orders {
+            let signature = order.signature.to_bytes();
+            buf.extend_from_slice(&order.uid.0);
+            buf.extend((signature.len() as u64).to_be_bytes());
+            buf.extend(signature);
+            buf.extend(u256_bytes(order.executed.0));
+        }
+        buf.extend((prices.len() as u64).to_be_bytes());
+        for (token, price) in prices {
+            buf.extend_from_slice(token.0.as_bytes());
+            buf.extend(u256_bytes(price.get().0));
+        }
+        for owner in jit_owners {
+            buf.extend_from_slice(owner.0.as_bytes());
+        }
+        Self(web3::signing::keccak256(&buf))
+    }
+}
+
+/// Identifies a set of trusted tokens independently of the order of the
+/// tokens. Consecutive auctions mostly use the same trusted tokens, so every
+/// distinct set only gets stored once and auctions refer to it by its hash.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, derive_more::Debug)]
+pub struct TrustedTokensHash(#[debug("0x{}", hex::encode(self.0))] pub [u8; 32]);
+
+impl TrustedTokensHash {
+    pub fn new(tokens: &[eth::TokenAddress]) -> Self {
+        let mut tokens = tokens.to_vec();
+        tokens.sort_by_key(|token| token.0);
+        tokens.dedup();
+        let buf: Vec<_> = tokens
+            .iter()
+            .flat_map(|token| token.0.to_fixed_bytes())
+            .collect();
+        Self(web3::signing::keccak256(&buf))
     }
 }
 
+fn u256_bytes(value: eth::U256) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    value.to_big_endian(&mut bytes);
+    bytes
+}
+
 /// The price of a token in wei. This represents how much wei is needed to buy
 /// 10**18 of another token.
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -83,6 +329,394 @@ impl Price {
 /// All auction prices
 pub type Prices = HashMap<eth::TokenAddress, Price>;
 
+/// Converts `amount` of `token` into ETH using the native `prices`. The native
+/// token is interchangeable with WETH and therefore always worth exactly its
+/// amount in ETH, even if the auction doesn't contain a price for it.
+pub fn price_in_eth(
+    prices: &Prices,
+    token: eth::TokenAddress,
+    amount: eth::U256,
+) -> Option<eth::U256> {
+    match prices.get(&token) {
+        Some(price) => Some(price.in_eth(amount.into()).0),
+        None => (token == eth::NATIVE_TOKEN).then_some(amount),
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 #[error("price cannot be zero")]
 pub struct InvalidPrice;
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::domain::auction::order::{
+            AppDataHash,
+            BuyTokenDestination,
+            OrderUid,
+            SellTokenSource,
+            Side,
+            Signature,
+        },
+    };
+
+    fn order(uid: u8, executed: u64) -> Order {
+        Order {
+            uid: OrderUid([uid; 56]),
+            sell: eth::Asset {
+                token: eth::TokenAddress(eth::H160([1; 20])),
+                amount: eth::U256::from(100).into(),
+            },
+            buy: eth::Asset {
+                token: eth::TokenAddress(eth::H160([2; 20])),
+                amount: eth::U256::from(100).into(),
+            },
+            protocol_fees: vec![],
+            side: Side::Sell,
+            created: 0,
+            valid_to: u32::MAX,
+            receiver: None,
+            owner: Default::default(),
+            partially_fillable: true,
+            executed: eth::U256::from(executed).into(),
+            pre_interactions: vec![],
+            post_interactions: vec![],
+            sell_token_balance: SellTokenSource::Erc20,
+            buy_token_balance: BuyTokenDestination::Erc20,
+            app_data: AppDataHash([0; 32]),
+            signature: Signature::PreSign,
+            quote: None,
+            fairness_exempt: false,
+        }
+    }
+
+    fn prices() -> Prices {
+        [1, 2]
+            .into_iter()
+            .map(|byte| {
+                (
+                    eth::TokenAddress(eth::H160([byte; 20])),
+                    Price::try_new(eth::U256::from(byte).into()).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn native_token_is_priced_implicitly() {
+        let prices = prices();
+        let amount = eth::U256::exp10(18);
+
+        assert_eq!(
+            price_in_eth(&prices, eth::TokenAddress(eth::H160([2; 20])), amount),
+            Some(2.into())
+        );
+        assert_eq!(
+            price_in_eth(&prices, eth::NATIVE_TOKEN, amount),
+            Some(amount)
+        );
+        assert_eq!(
+            price_in_eth(&prices, eth::TokenAddress(eth::H160([3; 20])), amount),
+            None
+        );
+    }
+
+    fn uids(orders: &[Order]) -> Vec<u8> {
+        orders.iter().map(|order| order.uid.0[0]).collect()
+    }
+
+    #[test]
+    fn order_ordering_is_independent_of_input_order() {
+        let orders: Vec<_> = (0..8)
+            .map(|uid| Order {
+                // two orders per creation time to exercise tie-breaking
+                created: u32::from(uid / 2),
+                ..order(uid, 0)
+            })
+            .collect();
+
+        for ordering in [
+            OrderOrdering::Shuffled,
+            OrderOrdering::CreationTime,
+            OrderOrdering::Uid,
+            OrderOrdering::Priority,
+        ] {
+            let mut expected = orders.clone();
+            ordering.apply(1, &HashSet::from([OrderUid([5; 56])]), &mut expected);
+            // e.g. different drivers or a replay of the same auction
+            for seed in 0..10 {
+                let mut shuffled = orders.clone();
+                shuffled.shuffle(&mut StdRng::seed_from_u64(seed));
+                ordering.apply(1, &HashSet::from([OrderUid([5; 56])]), &mut shuffled);
+                assert_eq!(uids(&shuffled), uids(&expected), "{ordering:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn orders_orders() {
+        let orders: Vec<_> = [(2, 1), (0, 2), (3, 1), (1, 0)]
+            .into_iter()
+            .map(|(uid, created)| Order {
+                created,
+                ..order(uid, 0)
+            })
+            .collect();
+        let ordered = |ordering: OrderOrdering, auction: Id| {
+            let mut orders = orders.clone();
+            ordering.apply(auction, &Default::default(), &mut orders);
+            uids(&orders)
+        };
+
+        assert_eq!(ordered(OrderOrdering::Unchanged, 1), [2, 0, 3, 1]);
+        assert_eq!(ordered(OrderOrdering::Uid, 1), [0, 1, 2, 3]);
+        assert_eq!(ordered(OrderOrdering::CreationTime, 1), [1, 2, 3, 0]);
+        assert_eq!(ordered(OrderOrdering::Priority, 1), [0, 2, 3, 1]);
+        // prioritized orders come first regardless of their age
+        let mut prioritized = orders.clone();
+        OrderOrdering::Priority.apply(
+            1,
+            &HashSet::from([prioritized[3].uid, prioritized[2].uid]),
+            &mut prioritized,
+        );
+        assert_eq!(uids(&prioritized), [3, 1, 0, 2]);
+
+        // shuffling only depends on the auction id
+        let shuffled = ordered(OrderOrdering::Shuffled, 1);
+        assert_eq!(ordered(OrderOrdering::Shuffled, 1), shuffled);
+        assert!((2..10).any(|auction| ordered(OrderOrdering::Shuffled, auction) != shuffled));
+    }
+
+    fn auction(orders: Vec<Order>) -> Auction {
+        let prices = prices();
+        Auction {
+            id: 1,
+            block: 0,
+            content_hash: ContentHash::new(&orders, &prices, &[]),
+            orders: Arc::new(orders),
+            prices: Arc::new(prices),
+            surplus_capturing_jit_order_owners: vec![],
+            quote_only: false,
+            prioritized_orders: Default::default(),
+        }
+    }
+
+    #[test]
+    fn total_volume_uses_the_smaller_side_of_every_order() {
+        let order = |uid,
+                     (sell_token, sell): (eth::TokenAddress, u64),
+                     (buy_token, buy): (eth::TokenAddress, u64)| {
+            let mut order = order(uid, 0);
+            order.sell = eth::Asset {
+                token: sell_token,
+                amount: (eth::U256::exp10(18) * sell).into(),
+            };
+            order.buy = eth::Asset {
+                token: buy_token,
+                amount: (eth::U256::exp10(18) * buy).into(),
+            };
+            order
+        };
+        // 1e18 units are worth 1 and 2 wei respectively
+        let token1 = eth::TokenAddress(eth::H160([1; 20]));
+        let token2 = eth::TokenAddress(eth::H160([2; 20]));
+
+        assert_eq!(auction(vec![]).total_volume_eth(), Some(0.into()));
+        assert_eq!(
+            auction(vec![
+                // 3 wei of sell tokens for 2 wei of buy tokens
+                order(1, (token1, 3), (token2, 1)),
+                // 1 wei of sell tokens for 10 wei of buy tokens
+                order(2, (token1, 1), (token2, 5)),
+            ])
+            .total_volume_eth(),
+            Some(3.into())
+        );
+        // the native token doesn't need a price
+        assert_eq!(
+            auction(vec![order(1, (token2, 1), (eth::NATIVE_TOKEN, 1))]).total_volume_eth(),
+            Some(2.into())
+        );
+        assert_eq!(
+            auction(vec![
+                order(1, (token1, 3), (token2, 1)),
+                order(2, (token1, 1), (eth::TokenAddress(eth::H160([3; 20])), 5)),
+            ])
+            .total_volume_eth(),
+            None
+        );
+    }
+
+    #[test]
+    fn clones_share_orders_and_prices() {
+        let auction = auction((0..=u8::MAX).map(|uid| order(uid, 0)).collect());
+        let snapshot = auction.clone();
+
+        assert!(Arc::ptr_eq(&auction.orders, &snapshot.orders));
+        assert!(Arc::ptr_eq(&auction.prices, &snapshot.prices));
+        assert_eq!(auction, snapshot);
+    }
+
+    #[test]
+    fn mutations_dont_affect_snapshots() {
+        let mut auction = auction(vec![order(1, 0), order(2, 0)]);
+        let snapshot = auction.clone();
+
+        auction.orders = Arc::new(vec![order(1, 0)]);
+        Arc::make_mut(&mut auction.prices).clear();
+        auction.content_hash = ContentHash::new(&auction.orders, &auction.prices, &[]);
+
+        assert_eq!(uids(&snapshot.orders), [1, 2]);
+        assert_eq!(*snapshot.prices, prices());
+        assert_eq!(uids(&auction.orders), [1]);
+        assert!(auction.prices.is_empty());
+        assert_ne!(auction, snapshot);
+    }
+
+    #[test]
+    fn truncating_keeps_original_orders() {
+        let auction = auction(vec![order(1, 0), order(2, 0)]);
+
+        let truncated = auction.truncated(1).unwrap();
+
+        assert_eq!(truncated.orders.len(), 1);
+        assert_eq!(uids(&auction.orders), [1, 2]);
+        assert!(Arc::ptr_eq(&auction.prices, &truncated.prices));
+        // the content changed so the auctions must not compare equal
+        assert_ne!(truncated, auction);
+        assert_eq!(
+            truncated.content_hash,
+            ContentHash::new(&truncated.orders, &truncated.prices, &[])
+        );
+    }
+
+    #[test]
+    fn truncating_keeps_prioritized_orders() {
+        let auction = Auction {
+            prioritized_orders: Arc::new(HashSet::from([OrderUid([1; 56])])),
+            ..auction(vec![
+                order(1, 0),
+                Order {
+                    created: 1,
+                    ..order(2, 0)
+                },
+                Order {
+                    created: 2,
+                    ..order(3, 0)
+                },
+            ])
+        };
+
+        assert_eq!(uids(&auction.truncated(2).unwrap().orders), [1, 3]);
+        assert_eq!(uids(&auction.truncated(1).unwrap().orders), [1]);
+    }
+
+    #[test]
+    fn restricts_orders_and_prices_to_tokens() {
+        let token = |byte: u8| eth::TokenAddress(eth::H160([byte; 20]));
+        let mut unlisted = order(2, 0);
+        unlisted.buy.token = token(3);
+        let mut prices = prices();
+        prices.insert(token(3), Price::try_new(eth::U256::from(3).into()).unwrap());
+        let auction = Auction {
+            prices: Arc::new(prices),
+            ..auction(vec![order(1, 0), unlisted])
+        };
+
+        let restricted = auction.restricted_to_tokens(&HashSet::from([token(1), token(2)]));
+        assert_eq!(uids(&restricted.orders), [1]);
+        assert_eq!(
+            restricted.prices.keys().collect::<HashSet<_>>(),
+            HashSet::from([&token(1), &token(2)])
+        );
+        // the original auction is untouched
+        assert_eq!(auction.orders.len(), 2);
+        assert_ne!(restricted, auction);
+
+        // allowed tokens that no remaining order trades don't get priced
+        let restricted = auction.restricted_to_tokens(&HashSet::from([token(2), token(3)]));
+        assert!(restricted.orders.is_empty());
+        assert!(restricted.prices.is_empty());
+    }
+
+    #[test]
+    fn content_hash_ignores_ordering() {
+        let owners = [
+            eth::Address(eth::H160([3; 20])),
+            eth::Address(eth::H160([4; 20])),
+        ];
+        let reversed = [owners[1], owners[0]];
+
+        assert_eq!(
+            ContentHash::new(&[order(1, 0), order(2, 0)], &prices(), &owners),
+            ContentHash::new(&[order(2, 0), order(1, 0)], &prices(), &reversed),
+        );
+    }
+
+    #[test]
+    fn content_hash_detects_changes() {
+        let hash = ContentHash::new(&[order(1, 0)], &prices(), &[]);
+
+        assert_ne!(hash, ContentHash::new(&[order(1, 10)], &prices(), &[]));
+        assert_ne!(hash, ContentHash::new(&[order(2, 0)], &prices(), &[]));
+        assert_ne!(hash, ContentHash::new(&[order(1, 0)], &Prices::new(), &[]));
+        assert_ne!(
+            hash,
+            ContentHash::new(
+                &[order(1, 0)],
+                &prices(),
+                &[eth::Address(eth::H160([3; 20]))]
+            )
+        );
+    }
+
+    #[test]
+    fn removes_orders_expiring_before_timestamp() {
+        let mut auction = RawAuctionData {
+            block: 0,
+            orders: Arc::new(vec![
+                Order {
+                    valid_to: 99,
+                    ..order(1, 0)
+                },
+                Order {
+                    valid_to: 100,
+                    ..order(2, 0)
+                },
+                // partially executed orders get removed all the same
+                Order {
+                    valid_to: 50,
+                    ..order(3, 10)
+                },
+                order(4, 0),
+            ]),
+            prices: Default::default(),
+            surplus_capturing_jit_order_owners: vec![],
+        };
+
+        let removed = auction.remove_orders_expiring_before(100);
+
+        assert_eq!(removed, vec![OrderUid([1; 56]), OrderUid([3; 56])]);
+        assert_eq!(uids(&auction.orders), vec![2, 4]);
+
+        // nothing left to remove
+        let orders = Arc::clone(&auction.orders);
+        assert!(auction.remove_orders_expiring_before(100).is_empty());
+        assert!(Arc::ptr_eq(&orders, &auction.orders));
+    }
+
+    #[test]
+    fn trusted_tokens_hash_only_depends_on_the_set_of_tokens() {
+        let token = |byte: u8| eth::TokenAddress(eth::H160([byte; 20]));
+        let hash = TrustedTokensHash::new(&[token(1), token(2)]);
+
+        assert_eq!(hash, TrustedTokensHash::new(&[token(2), token(1)]));
+        assert_eq!(
+            hash,
+            TrustedTokensHash::new(&[token(1), token(2), token(1)])
+        );
+        assert_ne!(hash, TrustedTokensHash::new(&[token(1)]));
+        assert_ne!(hash, TrustedTokensHash::new(&[token(1), token(3)]));
+    }
+}
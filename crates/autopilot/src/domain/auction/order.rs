@@ -29,6 +29,19 @@ pub struct Order {
     pub app_data: AppDataHash,
     pub signature: Signature,
     pub quote: Option<domain::Quote>,
+    /// Whether the order explicitly opted out of the fairness check. A worse
+    /// execution of such an order than in competing solutions doesn't make
+    /// a solution unfair.
+    pub fairness_exempt: bool,
+}
+
+impl Order {
+    /// Whether the order can no longer be settled in a block with the given
+    /// timestamp. This doesn't depend on how much of the order was already
+    /// executed.
+    pub fn expires_before(&self, timestamp: u64) -> bool {
+        u64::from(self.valid_to) < timestamp
+    }
 }
 
 // uid as 56 bytes: 32 for orderDigest, 20 for ownerAddress and 4 for validTo
@@ -0,0 +1,62 @@
+//! Keeps track of orders that only lost an auction due to token conflicts
+//! with its winners so they can be prioritized in the next auctions.
+
+use {
+    crate::domain::OrderUid,
+    std::{
+        collections::{HashMap, HashSet},
+        num::NonZeroUsize,
+    },
+};
+
+/// Deferred orders and in how many more auctions they get prioritized.
+#[derive(Debug, Default)]
+pub struct DeferredOrders {
+    remaining: HashMap<OrderUid, usize>,
+}
+
+impl DeferredOrders {
+    /// Prioritizes the orders in the next `auctions` auctions. Orders that are
+    /// already deferred start over.
+    pub fn defer(&mut self, orders: impl IntoIterator<Item = OrderUid>, auctions: NonZeroUsize) {
+        for uid in orders {
+            self.remaining.insert(uid, auctions.get());
+        }
+    }
+
+    /// Returns the orders the next auction prioritizes. Only auctions that can
+    /// get settled count towards how long orders stay prioritized.
+    pub fn next_auction(&mut self, settleable: bool) -> HashSet<OrderUid> {
+        let prioritized = self.remaining.keys().copied().collect();
+        if settleable {
+            self.remaining.retain(|_, remaining| {
+                *remaining -= 1;
+                *remaining > 0
+            });
+        }
+        prioritized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priority_decays_after_settleable_auctions() {
+        let uid = |byte: u8| OrderUid([byte; 56]);
+        let mut deferred = DeferredOrders::default();
+        assert!(deferred.next_auction(true).is_empty());
+
+        deferred.defer([uid(1)], NonZeroUsize::new(2).unwrap());
+        assert_eq!(deferred.next_auction(true), HashSet::from([uid(1)]));
+        // quote-only auctions don't count
+        assert_eq!(deferred.next_auction(false), HashSet::from([uid(1)]));
+
+        // deferring an order again starts over
+        deferred.defer([uid(1), uid(2)], NonZeroUsize::new(2).unwrap());
+        assert_eq!(deferred.next_auction(true), HashSet::from([uid(1), uid(2)]));
+        assert_eq!(deferred.next_auction(true), HashSet::from([uid(1), uid(2)]));
+        assert!(deferred.next_auction(true).is_empty());
+    }
+}
@@ -0,0 +1,110 @@
+//! Detection of solutions that reuse another driver's execution of an order
+//! verbatim. A driver copying a competitor's execution for an order could
+//! claim credit for work it did not do, so such cases get surfaced for
+//! monitoring.
+
+use {
+    super::{Participant, TradedOrder},
+    crate::domain,
+    std::collections::HashMap,
+};
+
+/// Orders for which different drivers proposed the exact same execution,
+/// together with the names of all drivers sharing that execution.
+pub fn duplicate_executions<'a, T: 'a>(
+    participants: impl IntoIterator<Item = &'a Participant<T>>,
+) -> HashMap<domain::OrderUid, Vec<&'a str>> {
+    let mut executions: HashMap<&domain::OrderUid, Vec<(&str, &TradedOrder)>> = HashMap::new();
+    for participant in participants {
+        let driver = participant.driver().name.as_str();
+        for (uid, execution) in participant.solution().orders() {
+            executions.entry(uid).or_default().push((driver, execution));
+        }
+    }
+
+    let mut duplicates = HashMap::new();
+    for (uid, executions) in executions {
+        let mut drivers = Vec::new();
+        for (driver, execution) in &executions {
+            let copied = executions
+                .iter()
+                .any(|(other, other_execution)| driver != other && execution == other_execution);
+            if copied && !drivers.contains(driver) {
+                drivers.push(*driver);
+            }
+        }
+        if !drivers.is_empty() {
+            duplicates.insert(*uid, drivers);
+        }
+    }
+    duplicates
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            domain::{
+                auction::order,
+                competition::{Score, Solution, Unranked},
+                eth,
+            },
+            infra::solvers::fake::FakeDriver,
+        },
+        primitive_types::{H160, U256},
+    };
+
+    fn execution(executed_buy: u64) -> TradedOrder {
+        TradedOrder {
+            side: order::Side::Sell,
+            sell: eth::Asset {
+                token: H160::from_low_u64_be(1).into(),
+                amount: U256::from(100).into(),
+            },
+            buy: eth::Asset {
+                token: H160::from_low_u64_be(2).into(),
+                amount: U256::from(90).into(),
+            },
+            executed_sell: U256::from(100).into(),
+            executed_buy: U256::from(executed_buy).into(),
+        }
+    }
+
+    async fn participant(
+        driver: &str,
+        orders: &[(domain::OrderUid, TradedOrder)],
+    ) -> Participant<Unranked> {
+        let solver = H160::from_low_u64_be(1);
+        let solution = Solution::new(
+            0,
+            solver.into(),
+            Score::try_new(eth::Ether(U256::one())).unwrap(),
+            orders.iter().cloned().collect(),
+            Default::default(),
+            None,
+        );
+        let driver = FakeDriver::start(Default::default()).driver(driver).await;
+        Participant::new(solution, driver)
+    }
+
+    #[tokio::test]
+    async fn detects_identical_executions_across_drivers() {
+        let copied = domain::OrderUid([1; 56]);
+        let distinct = domain::OrderUid([2; 56]);
+        let participants = [
+            participant("a", &[(copied, execution(95)), (distinct, execution(95))]).await,
+            participant("b", &[(copied, execution(95)), (distinct, execution(96))]).await,
+            // the same driver proposing the same execution twice is fine
+            participant("c", &[(distinct, execution(97))]).await,
+            participant("c", &[(distinct, execution(97))]).await,
+        ];
+
+        let duplicates = duplicate_executions(&participants);
+
+        assert_eq!(duplicates.len(), 1);
+        let mut drivers = duplicates[&copied].clone();
+        drivers.sort();
+        assert_eq!(drivers, ["a", "b"]);
+    }
+}
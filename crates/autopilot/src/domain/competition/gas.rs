@@ -0,0 +1,116 @@
+//! Gas estimates for solutions whose driver didn't report a (reliable) gas
+//! figure.
+
+use {
+    crate::{
+        domain::{auction::order::Interaction, eth},
+        infra,
+    },
+    primitive_types::U256,
+};
+
+/// Intrinsic gas every transaction pays regardless of what it executes.
+const TX_BASE_GAS: u64 = 21_000;
+/// Gas per zero byte of calldata (EIP-2028).
+const ZERO_BYTE_GAS: u64 = 4;
+/// Gas per non-zero byte of calldata (EIP-2028).
+const NON_ZERO_BYTE_GAS: u64 = 16;
+/// Baseline gas of a settlement executing a single order.
+const STANDALONE_ORDER_GAS: u64 = shared::price_estimation::gas::SETTLEMENT_SINGLE_TRADE;
+
+/// Where the gas of a solution comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasSource {
+    /// The driver reported the gas along with the solution.
+    Reported,
+    /// The gas got estimated based on the calldata of the solution.
+    Estimated,
+    /// Estimating the gas failed so a configured default got used.
+    Fallback,
+}
+
+/// Estimates the total gas of executing the interactions within a single
+/// settlement by summing the `eth_estimateGas` result of each interaction.
+/// The intrinsic transaction gas included in every estimate is only paid once
+/// per settlement so it gets removed.
+pub async fn estimate_interactions_gas(
+    interactions: &[Interaction],
+    eth: &infra::Ethereum,
+) -> Result<eth::Gas, infra::blockchain::Error> {
+    let estimates = futures::future::try_join_all(
+        interactions
+            .iter()
+            .map(|interaction| eth.estimate_interaction_gas(interaction)),
+    )
+    .await?;
+    Ok(eth::Gas(
+        estimates
+            .into_iter()
+            .map(|gas| gas.0.saturating_sub(TX_BASE_GAS.into()))
+            .fold(U256::zero(), U256::saturating_add),
+    ))
+}
+
+/// Cheap lower bound of the gas the interactions use. Only accounts for the
+/// cost of passing their calldata, so it doesn't require any RPC requests.
+pub fn interactions_gas_lower_bound(interactions: &[Interaction]) -> eth::Gas {
+    let gas: u64 = interactions
+        .iter()
+        .flat_map(|interaction| &interaction.call_data)
+        .map(|byte| match byte {
+            0 => ZERO_BYTE_GAS,
+            _ => NON_ZERO_BYTE_GAS,
+        })
+        .sum();
+    eth::Gas(gas.into())
+}
+
+/// Estimates how much gas settling `orders` orders in a single settlement
+/// using `gas` saves compared to settling each of them on its own. Negative if
+/// the batch uses more gas.
+pub fn batching_gas_savings(orders: usize, gas: eth::Gas) -> eth::SignedGas {
+    let standalone = U256::from(STANDALONE_ORDER_GAS).saturating_mul(U256::from(orders));
+    eth::Gas(standalone).signed_sub(gas)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, primitive_types::H160};
+
+    fn interaction(call_data: Vec<u8>) -> Interaction {
+        Interaction {
+            target: H160([1; 20]),
+            value: U256::zero(),
+            call_data,
+        }
+    }
+
+    #[test]
+    fn lower_bound_counts_calldata() {
+        assert_eq!(interactions_gas_lower_bound(&[]), eth::Gas(0.into()));
+        assert_eq!(
+            interactions_gas_lower_bound(&[interaction(vec![0, 0, 1]), interaction(vec![2])]),
+            eth::Gas((2 * ZERO_BYTE_GAS + 2 * NON_ZERO_BYTE_GAS).into())
+        );
+    }
+
+    #[test]
+    fn batching_savings_can_be_negative() {
+        let gas = |gas: u64| eth::Gas(gas.into());
+        let standalone = STANDALONE_ORDER_GAS as i64;
+
+        assert_eq!(
+            batching_gas_savings(3, gas(200_000)),
+            eth::SignedGas(3 * standalone - 200_000)
+        );
+        assert_eq!(
+            batching_gas_savings(1, gas(STANDALONE_ORDER_GAS + 10)),
+            eth::SignedGas(-10)
+        );
+        assert_eq!(batching_gas_savings(0, gas(0)), eth::SignedGas(0));
+        assert_eq!(
+            batching_gas_savings(0, eth::Gas(U256::MAX)),
+            eth::SignedGas(-i64::MAX)
+        );
+    }
+}
@@ -2,16 +2,27 @@ use {
     super::auction::order,
     crate::domain::{self, auction, eth},
     derive_more::Display,
-    std::collections::HashMap,
+    std::collections::{HashMap, HashSet},
 };
 
+pub mod deferred_orders;
+pub mod duplicate_execution;
+pub mod gas;
+pub mod notification;
 mod participant;
+pub mod participation;
 mod participation_guard;
+pub mod price_improvement;
+pub mod price_spread;
+pub mod protocol_fee_cap;
+pub mod ranking;
+pub mod reward_inputs;
+pub mod winner_concentration;
 pub mod winner_selection;
 
 pub use {
     participant::{Participant, Ranked, Unranked},
-    participation_guard::SolverParticipationGuard,
+    participation_guard::{SolverParticipationGuard, coalesce_updates},
 };
 
 type SolutionId = u64;
@@ -24,7 +35,12 @@ pub struct Solution {
     /// Score reported by the solver in their response.
     score: Score,
     orders: HashMap<domain::OrderUid, TradedOrder>,
+    /// Orders the solver proposed to execute but that got dropped because
+    /// their executions were dust. The settlement may still execute them.
+    dust_orders: HashSet<domain::OrderUid>,
     prices: auction::Prices,
+    /// Gas the settlement is expected to use, if reported by the driver.
+    gas: Option<eth::Gas>,
     /// Score computed by the autopilot based on the solution
     /// of the solver.
     // TODO: refactor this to compute the score in the constructor
@@ -38,17 +54,27 @@ impl Solution {
         score: Score,
         orders: HashMap<domain::OrderUid, TradedOrder>,
         prices: auction::Prices,
+        gas: Option<eth::Gas>,
     ) -> Self {
         Self {
             id,
             solver,
             score,
             orders,
+            dust_orders: Default::default(),
             prices,
+            gas,
             computed_score: None,
         }
     }
 
+    pub fn with_dust_orders(self, dust_orders: HashSet<domain::OrderUid>) -> Self {
+        Self {
+            dust_orders,
+            ..self
+        }
+    }
+
     pub fn id(&self) -> SolutionId {
         self.id
     }
@@ -73,12 +99,68 @@ impl Solution {
         &self.orders
     }
 
+    pub fn dust_orders(&self) -> &HashSet<domain::OrderUid> {
+        &self.dust_orders
+    }
+
+    pub fn order_count(&self) -> usize {
+        self.orders.len()
+    }
+
+    /// Whether the solution doesn't settle any orders.
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
     pub fn prices(&self) -> &HashMap<eth::TokenAddress, auction::Price> {
         &self.prices
     }
+
+    pub fn gas(&self) -> Option<eth::Gas> {
+        self.gas
+    }
+
+    /// Gas saved by settling the orders of the solution together instead of
+    /// one by one. Negative if batching is more expensive. Unknown if the
+    /// driver didn't report the gas of the solution.
+    pub fn batching_gas_savings(&self) -> Option<eth::SignedGas> {
+        self.gas
+            .map(|gas| gas::batching_gas_savings(self.orders.len(), gas))
+    }
+
+    /// Hash over the content of the solution as proposed by the solver. Equal
+    /// solutions have equal hashes regardless of the order of their trades or
+    /// prices.
+    pub fn hash(&self) -> [u8; 32] {
+        let u256_bytes = |value: eth::U256| {
+            let mut bytes = [0; 32];
+            value.to_big_endian(&mut bytes);
+            bytes
+        };
+        let mut orders: Vec<_> = self.orders.iter().collect();
+        orders.sort_by_key(|(uid, _)| uid.0);
+        let mut prices: Vec<_> = self.prices.iter().collect();
+        prices.sort_by_key(|(token, _)| token.0);
+
+        let mut buf = Vec::new();
+        buf.extend(self.id.to_be_bytes());
+        buf.extend_from_slice(self.solver.0.as_bytes());
+        buf.extend(u256_bytes(self.score.get().0));
+        buf.extend((orders.len() as u64).to_be_bytes());
+        for (uid, order) in orders {
+            buf.extend_from_slice(&uid.0);
+            buf.extend(u256_bytes(order.executed_sell.0));
+            buf.extend(u256_bytes(order.executed_buy.0));
+        }
+        for (token, price) in prices {
+            buf.extend_from_slice(token.0.as_bytes());
+            buf.extend(u256_bytes(price.get().0));
+        }
+        web3::signing::keccak256(&buf)
+    }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct TradedOrder {
     pub side: order::Side,
     /// The sell token and limit sell amount of sell token.
@@ -91,6 +173,21 @@ pub struct TradedOrder {
     pub executed_buy: eth::TokenAmount,
 }
 
+impl TradedOrder {
+    /// Whether the execution is too small to be meaningful, i.e. one of its
+    /// executed amounts is zero or worth less than `threshold` according to
+    /// the auction prices. Sides without an auction price (e.g. of JIT
+    /// orders) can only be checked for zero amounts.
+    pub fn is_dust(&self, prices: &auction::Prices, threshold: eth::Ether) -> bool {
+        let is_dust = |token: &eth::TokenAddress, amount: eth::TokenAmount| {
+            amount.0.is_zero()
+                || auction::price_in_eth(prices, *token, amount.0)
+                    .is_some_and(|amount| eth::Ether(amount) < threshold)
+        };
+        is_dust(&self.sell.token, self.executed_sell) || is_dust(&self.buy.token, self.executed_buy)
+    }
+}
+
 #[derive(
     Debug,
     Clone,
@@ -121,6 +218,42 @@ impl Score {
     }
 }
 
+/// How a driver performed in the competition of an auction.
+#[derive(Debug, Clone, Copy)]
+pub struct CompetitionOutcome {
+    pub auction_id: auction::Id,
+    /// Rank of the driver's best solution (starting at 1).
+    pub rank: usize,
+    pub is_winner: bool,
+    /// Reference score used to compute the driver's rewards. Only
+    /// available for winners.
+    pub reference_score: Option<Score>,
+}
+
+/// How much worse than in competing solutions a driver's solutions may
+/// execute an order before they get discarded as unfair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FairnessThreshold {
+    /// Threshold that applies to all orders.
+    pub flat: eth::Ether,
+    /// Tightens the threshold to this share (in basis points) of the order's
+    /// value so that small orders can't be executed arbitrarily badly while
+    /// staying below the flat threshold.
+    pub bps_of_order_value: Option<u32>,
+}
+
+impl FairnessThreshold {
+    /// Threshold for an order worth `order_value`. Orders whose value is
+    /// unknown fall back to the flat threshold.
+    pub fn for_order(&self, order_value: Option<eth::Ether>) -> eth::Ether {
+        let (Some(bps), Some(value)) = (self.bps_of_order_value, order_value) else {
+            return self.flat;
+        };
+        let relative = eth::Ether(value.0.saturating_mul(bps.into()) / 10_000);
+        self.flat.min(relative)
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 #[error("the solver proposed a 0-score solution")]
 pub struct ZeroScore;
@@ -133,4 +266,10 @@ pub enum SolutionError {
     InvalidPrice(#[from] auction::InvalidPrice),
     #[error("the solver got deny listed")]
     SolverDenyListed,
+    #[error("the solution only contains dust executions")]
+    DustExecution,
+    #[error("the solution trades the native token without a price for it")]
+    MissingNativePrice,
+    #[error("a better solution of the driver executes the same orders for the same amounts")]
+    DuplicateExecution,
 }
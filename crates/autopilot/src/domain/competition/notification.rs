@@ -0,0 +1,24 @@
+use {
+    crate::domain::eth,
+    chrono::{DateTime, Utc},
+};
+
+/// What a solver got notified about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// The solver is not allowed to participate for some time.
+    Banned,
+    /// How the solver performed in an auction.
+    CompetitionOutcome,
+}
+
+/// An attempt to notify a solver. These get persisted so solvers can't claim
+/// they were never informed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub solver: eth::Address,
+    pub kind: Kind,
+    pub timestamp: DateTime<Utc>,
+    /// The reason why the notification couldn't be delivered.
+    pub result: Result<(), String>,
+}
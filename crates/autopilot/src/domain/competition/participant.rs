@@ -1,6 +1,6 @@
 use {
     super::{Score, Solution},
-    crate::infra,
+    crate::{domain::eth, infra},
     std::sync::Arc,
 };
 
@@ -16,6 +16,9 @@ pub struct Unranked;
 pub enum Ranked {
     Winner,
     NonWinner,
+    /// Didn't win only because it trades a directed token pair of a winner
+    /// that was picked before it.
+    BlockedByTokenConflict,
     FilteredOut,
 }
 
@@ -31,6 +34,16 @@ impl<T> Participant<T> {
     pub fn driver(&self) -> &Arc<infra::Driver> {
         &self.driver
     }
+
+    /// Address the driver of this participant settles solutions from.
+    pub fn submission_address(&self) -> eth::Address {
+        self.driver.submission_address
+    }
+
+    /// Address the solution claims to be submitted by.
+    pub fn solver_address(&self) -> eth::Address {
+        self.solution.solver()
+    }
 }
 
 impl Participant<Unranked> {
@@ -59,4 +72,8 @@ impl Participant<Ranked> {
     pub fn filtered_out(&self) -> bool {
         matches!(self.state, Ranked::FilteredOut)
     }
+
+    pub fn is_blocked_by_token_conflict(&self) -> bool {
+        matches!(self.state, Ranked::BlockedByTokenConflict)
+    }
 }
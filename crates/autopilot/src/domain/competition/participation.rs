@@ -0,0 +1,85 @@
+//! Record of how the configured drivers took part in a single auction.
+
+use {crate::domain::eth, std::time::Duration};
+
+/// Which drivers were asked to solve an auction, which of them responded
+/// with how many solutions and why the others produced nothing.
+#[derive(Debug, Clone, Default)]
+pub struct ParticipationMatrix(pub Vec<Participation>);
+
+#[derive(Debug, Clone)]
+pub struct Participation {
+    pub driver: String,
+    pub submission_address: eth::Address,
+    pub outcome: Outcome,
+    /// How long the driver took to respond (or until it got cut off).
+    pub solve_time: Duration,
+    /// How many of the proposed solutions passed all checks and entered the
+    /// competition.
+    pub accepted: usize,
+    /// Proposed solutions that got discarded before entering the competition.
+    pub filtered: Vec<Filtered>,
+}
+
+/// A proposed solution that got discarded before entering the competition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filtered {
+    /// `None` if the solution got discarded while being parsed.
+    pub solution_id: Option<u64>,
+    /// Why the solution got discarded, e.g. `zero_score`.
+    pub reason: &'static str,
+}
+
+impl ParticipationMatrix {
+    /// Whether any driver was allowed to participate and answered in time.
+    pub fn any_responded(&self) -> bool {
+        self.0
+            .iter()
+            .any(|participation| participation.outcome.responded())
+    }
+}
+
+/// What came of asking a driver to solve the auction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The driver responded with this many solutions.
+    Proposed(usize),
+    /// The driver did not respond before the solve deadline.
+    Timeout,
+    /// The driver's solver is not allowed to participate.
+    DenyListed,
+    /// The driver responded without any solutions.
+    NoSolutions,
+    /// The request failed for another reason.
+    Error,
+    /// The driver didn't get asked because the auction contains no orders
+    /// trading only tokens it is allowed to trade.
+    Skipped,
+}
+
+impl Outcome {
+    /// Number of solutions the driver proposed.
+    pub fn proposed(&self) -> usize {
+        match self {
+            Self::Proposed(solutions) => *solutions,
+            _ => 0,
+        }
+    }
+
+    /// Whether the driver was allowed to participate and answered in time,
+    /// regardless of whether it found solutions.
+    pub fn responded(&self) -> bool {
+        matches!(self, Self::Proposed(_) | Self::NoSolutions)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Proposed(_) => "proposed",
+            Self::Timeout => "timeout",
+            Self::DenyListed => "deny_listed",
+            Self::NoSolutions => "no_solutions",
+            Self::Error => "error",
+            Self::Skipped => "skipped",
+        }
+    }
+}
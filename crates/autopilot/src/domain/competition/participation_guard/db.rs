@@ -18,6 +18,13 @@ use {
     tokio::join,
 };
 
+/// A ban found in the DB statistics.
+struct Ban {
+    found_at: Instant,
+    reason: dto::notify::BanReason,
+    until: DateTime<Utc>,
+}
+
 /// Checks the DB by searching for solvers that won N last consecutive auctions
 /// and either never settled any of them or their settlement success rate is
 /// lower than `min_settlement_success_rate`.
@@ -26,7 +33,7 @@ pub(super) struct SolverValidator(Arc<Inner>);
 
 struct Inner {
     persistence: infra::Persistence,
-    banned_solvers: dashmap::DashMap<eth::Address, Instant>,
+    banned_solvers: dashmap::DashMap<eth::Address, Ban>,
     ttl: Duration,
     non_settling_config: NonSettlingSolversFinderConfig,
     low_settling_config: LowSettlingSolversFinderConfig,
@@ -170,14 +177,19 @@ impl SolverValidator {
                         .0
                         .banned_solvers
                         .get(&driver.submission_address)
-                        .is_none_or(|entry| entry.elapsed() >= self.0.ttl);
-                    // The solver should try again once the cache is expired.
+                        .is_none_or(|entry| entry.found_at.elapsed() >= self.0.ttl);
+                    // The solver should try again once the cache is expired. The driver
+                    // gets notified by the participation guard once it gets skipped.
                     if is_absent_or_expired {
                         tracing::debug!(solver = ?driver.name, "disabling solver temporarily");
-                        infra::notify_banned_solver(driver.clone(), ban_reason, banned_until);
-                        self.0
-                            .banned_solvers
-                            .insert(driver.submission_address, found_at_timestamp);
+                        self.0.banned_solvers.insert(
+                            driver.submission_address,
+                            Ban {
+                                found_at: found_at_timestamp,
+                                reason: ban_reason,
+                                until: banned_until,
+                            },
+                        );
                     }
                 }
                 driver.name.as_ref()
@@ -200,11 +212,15 @@ impl SolverValidator {
 
 #[async_trait::async_trait]
 impl super::SolverValidator for SolverValidator {
-    async fn is_allowed(&self, solver: &eth::Address) -> anyhow::Result<bool> {
-        if let Some(entry) = self.0.banned_solvers.get(solver) {
-            return Ok(entry.elapsed() >= self.0.ttl);
-        }
-
-        Ok(true)
+    async fn denial(&self, solver: &eth::Address) -> anyhow::Result<Option<super::Denial>> {
+        Ok(self
+            .0
+            .banned_solvers
+            .get(solver)
+            .filter(|ban| ban.found_at.elapsed() < self.0.ttl)
+            .map(|ban| super::Denial {
+                reason: ban.reason,
+                until: ban.until,
+            }))
     }
 }
@@ -2,8 +2,14 @@ mod db;
 mod onchain;
 
 use {
-    crate::{arguments::DbBasedSolverParticipationGuardConfig, domain::eth, infra},
-    std::sync::Arc,
+    crate::{
+        arguments::DbBasedSolverParticipationGuardConfig,
+        domain::{Metrics, eth},
+        infra::{self, solvers::dto::notify::BanReason},
+    },
+    chrono::{DateTime, Utc},
+    std::{collections::HashMap, sync::Arc, time::Duration},
+    tokio::sync::mpsc,
 };
 
 /// This struct checks whether a solver can participate in the competition by
@@ -14,6 +20,12 @@ pub struct SolverParticipationGuard(Arc<Inner>);
 struct Inner {
     /// Stores the validators in order they will be called.
     validators: Vec<Box<dyn SolverValidator + Send + Sync>>,
+    drivers_by_address: HashMap<eth::Address, Arc<infra::Driver>>,
+    /// Solvers that are currently not allowed to participate and already got
+    /// notified about it.
+    deny_listed: dashmap::DashSet<eth::Address>,
+    /// Records the notifications sent to deny listed solvers.
+    persistence: Option<infra::Persistence>,
 }
 
 impl SolverParticipationGuard {
@@ -25,24 +37,47 @@ impl SolverParticipationGuard {
         drivers: impl IntoIterator<Item = Arc<infra::Driver>>,
     ) -> Self {
         let mut validators: Vec<Box<dyn SolverValidator + Send + Sync>> = Vec::new();
+        let drivers_by_address = drivers_by_address(drivers);
+        let ban_duration = db_based_validator_config.solver_blacklist_cache_ttl;
 
         let current_block = eth.current_block().clone();
         let database_solver_participation_validator = db::SolverValidator::new(
-            persistence,
+            persistence.clone(),
             current_block,
             competition_updates_receiver,
             db_based_validator_config,
-            drivers
-                .into_iter()
-                .map(|driver| (driver.submission_address, driver.clone()))
-                .collect(),
+            drivers_by_address.clone(),
         );
         validators.push(Box::new(database_solver_participation_validator));
 
-        let onchain_solver_participation_validator = onchain::Validator { eth };
+        let onchain_solver_participation_validator = onchain::Validator { eth, ban_duration };
         validators.push(Box::new(onchain_solver_participation_validator));
 
-        Self(Arc::new(Inner { validators }))
+        for driver in drivers_by_address.values() {
+            Metrics::get()
+                .deny_listed_solver
+                .with_label_values(&[&driver.name])
+                .set(0);
+        }
+
+        Self(Arc::new(Inner {
+            validators,
+            drivers_by_address,
+            deny_listed: Default::default(),
+            persistence: Some(persistence),
+        }))
+    }
+
+    /// Lets every solver participate. Useful for tests that don't have access
+    /// to a database or node.
+    #[cfg(test)]
+    pub fn allow_all() -> Self {
+        Self(Arc::new(Inner {
+            validators: Vec::new(),
+            drivers_by_address: Default::default(),
+            deny_listed: Default::default(),
+            persistence: None,
+        }))
     }
 
     /// Checks if a solver can participate in the competition.
@@ -50,18 +85,241 @@ impl SolverParticipationGuard {
     /// the following order:
     /// 1. DB-based validator: operates fast since it uses in-memory cache.
     /// 2. Onchain-based validator: only then calls the Authenticator contract.
+    ///
+    /// The driver of a denied solver gets notified once per deny-list
+    /// episode, i.e. again only after it was allowed to participate in the
+    /// meantime.
     pub async fn can_participate(&self, solver: &eth::Address) -> anyhow::Result<bool> {
         for validator in &self.0.validators {
-            if !validator.is_allowed(solver).await? {
+            if let Some(denial) = validator.denial(solver).await? {
+                self.denied(solver, denial);
                 return Ok(false);
             }
         }
 
+        self.allowed(solver);
         Ok(true)
     }
+
+    fn denied(&self, solver: &eth::Address, denial: Denial) {
+        let Some(driver) = self.0.drivers_by_address.get(solver) else {
+            return;
+        };
+        Metrics::get()
+            .deny_listed_solver
+            .with_label_values(&[&driver.name])
+            .set(1);
+        if self.0.deny_listed.insert(*solver) {
+            tracing::info!(driver = %driver.name, reason = denial.reason.as_str(), "solver is deny listed");
+            infra::notify_banned_solver(
+                driver.clone(),
+                denial.reason,
+                denial.until,
+                self.0.persistence.clone(),
+            );
+        }
+    }
+
+    fn allowed(&self, solver: &eth::Address) {
+        if self.0.deny_listed.remove(solver).is_none() {
+            return;
+        }
+        if let Some(driver) = self.0.drivers_by_address.get(solver) {
+            Metrics::get()
+                .deny_listed_solver
+                .with_label_values(&[&driver.name])
+                .set(0);
+        }
+    }
+}
+
+/// Maps every address a driver may submit solutions from to the driver. That
+/// includes the addresses of all rotation windows since the stored competition
+/// data refers to the address that actually submitted a solution.
+fn drivers_by_address(
+    drivers: impl IntoIterator<Item = Arc<infra::Driver>>,
+) -> HashMap<eth::Address, Arc<infra::Driver>> {
+    drivers
+        .into_iter()
+        .flat_map(|driver| {
+            std::iter::once(driver.submission_address)
+                .chain(
+                    driver
+                        .additional_submission_addresses
+                        .iter()
+                        .map(|allowed| allowed.address),
+                )
+                .map(|address| (address, driver.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Forwards the competition updates sent to `updates` but at most one per
+/// `interval`. Updates arriving in the meantime get coalesced into a single one
+/// which is sent once the interval elapsed, so the latest update is never
+/// lost.
+pub fn coalesce_updates(
+    mut updates: mpsc::UnboundedReceiver<()>,
+    interval: Duration,
+) -> mpsc::UnboundedReceiver<()> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut last_sent = None;
+        while updates.recv().await.is_some() {
+            if let Some(last_sent) = last_sent {
+                tokio::time::sleep_until(last_sent + interval).await;
+            }
+            // all updates that arrived while waiting are covered by this one
+            while updates.try_recv().is_ok() {}
+            if sender.send(()).is_err() {
+                break;
+            }
+            last_sent = Some(tokio::time::Instant::now());
+        }
+    });
+    receiver
+}
+
+/// Why and for how long a solver is not allowed to participate.
+#[derive(Debug, Clone, Copy)]
+struct Denial {
+    reason: BanReason,
+    until: DateTime<Utc>,
 }
 
 #[async_trait::async_trait]
 trait SolverValidator: Send + Sync {
-    async fn is_allowed(&self, solver: &eth::Address) -> anyhow::Result<bool>;
+    /// Returns `None` if the solver is allowed to participate.
+    async fn denial(&self, solver: &eth::Address) -> anyhow::Result<Option<Denial>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::infra::solvers::{SubmissionAddress, fake::FakeDriver},
+        std::sync::atomic::{AtomicBool, Ordering},
+    };
+
+    /// Denies every solver while the flag is set.
+    struct Toggle(Arc<AtomicBool>);
+
+    #[async_trait::async_trait]
+    impl SolverValidator for Toggle {
+        async fn denial(&self, _: &eth::Address) -> anyhow::Result<Option<Denial>> {
+            Ok(self.0.load(Ordering::Relaxed).then(|| Denial {
+                reason: BanReason::DenyListed,
+                until: Utc::now(),
+            }))
+        }
+    }
+
+    async fn wait_for_notifications(fake: &FakeDriver, count: usize) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while fake.notifications().len() < count {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn notifies_deny_listed_drivers_once_per_episode() {
+        let fake = FakeDriver::start(Default::default());
+        let solver = fake.submission_address();
+        let denied = Arc::new(AtomicBool::new(true));
+        let guard = SolverParticipationGuard(Arc::new(Inner {
+            validators: vec![Box::new(Toggle(denied.clone()))],
+            drivers_by_address: drivers_by_address([fake.driver("deny_listed").await]),
+            deny_listed: Default::default(),
+            persistence: None,
+        }));
+        let status = || {
+            Metrics::get()
+                .deny_listed_solver
+                .with_label_values(&["deny_listed"])
+                .get()
+        };
+
+        for _ in 0..3 {
+            assert!(!guard.can_participate(&solver).await.unwrap());
+        }
+        wait_for_notifications(&fake, 1).await;
+        assert_eq!(fake.notifications()[0]["banned"]["reason"], "denyListed");
+        assert_eq!(status(), 1);
+
+        denied.store(false, Ordering::Relaxed);
+        assert!(guard.can_participate(&solver).await.unwrap());
+        assert_eq!(status(), 0);
+
+        // being denied again starts a new episode
+        denied.store(true, Ordering::Relaxed);
+        assert!(!guard.can_participate(&solver).await.unwrap());
+        wait_for_notifications(&fake, 2).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(fake.notifications().len(), 2);
+        assert_eq!(status(), 1);
+    }
+
+    #[tokio::test]
+    async fn maps_all_submission_addresses_to_their_driver() {
+        let rotating = FakeDriver::start(Default::default());
+        let mut driver = Arc::into_inner(rotating.driver("rotating").await).unwrap();
+        let (old, new) = (eth::H160::random().into(), eth::H160::random().into());
+        driver.additional_submission_addresses = vec![
+            SubmissionAddress {
+                address: old,
+                valid_from: None,
+                valid_to: Some(Utc::now()),
+            },
+            SubmissionAddress {
+                address: new,
+                valid_from: Some(Utc::now()),
+                valid_to: None,
+            },
+        ];
+        let other = FakeDriver::start(Default::default());
+
+        let drivers = drivers_by_address([Arc::new(driver), other.driver("other").await]);
+
+        assert_eq!(drivers.len(), 4);
+        for address in [rotating.submission_address(), old, new] {
+            assert_eq!(drivers[&address].name, "rotating");
+        }
+        assert_eq!(drivers[&other.submission_address()].name, "other");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn coalesces_updates() {
+        let interval = Duration::from_secs(10);
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut updates = coalesce_updates(receiver, interval);
+
+        // the first update gets forwarded right away
+        for _ in 0..3 {
+            sender.send(()).unwrap();
+        }
+        let start = tokio::time::Instant::now();
+        assert!(updates.recv().await.is_some());
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // later ones wait for the interval and get merged
+        sender.send(()).unwrap();
+        sender.send(()).unwrap();
+        assert!(updates.recv().await.is_some());
+        assert!(start.elapsed() >= interval);
+        assert!(
+            tokio::time::timeout(interval * 10, updates.recv())
+                .await
+                .is_err()
+        );
+
+        // updates after the interval are forwarded immediately
+        let start = tokio::time::Instant::now();
+        sender.send(()).unwrap();
+        assert!(updates.recv().await.is_some());
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
 }
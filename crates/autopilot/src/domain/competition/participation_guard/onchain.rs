@@ -1,20 +1,35 @@
-use crate::{domain::eth, infra};
+use {
+    super::Denial,
+    crate::{
+        domain::eth,
+        infra::{self, solvers::dto::notify::BanReason},
+    },
+    chrono::Utc,
+    std::time::Duration,
+};
 
 /// Calls Authenticator contract to check if a solver has a sufficient
 /// permission.
 pub(super) struct Validator {
     pub eth: infra::Ethereum,
+    /// The contract doesn't tell for how long a solver is denied so drivers
+    /// get told to check back after the regular ban duration.
+    pub ban_duration: Duration,
 }
 
 #[async_trait::async_trait]
 impl super::SolverValidator for Validator {
-    async fn is_allowed(&self, solver: &eth::Address) -> anyhow::Result<bool> {
-        Ok(self
+    async fn denial(&self, solver: &eth::Address) -> anyhow::Result<Option<Denial>> {
+        let is_solver = self
             .eth
             .contracts()
             .authenticator()
             .is_solver(solver.0)
             .call()
-            .await?)
+            .await?;
+        Ok((!is_solver).then(|| Denial {
+            reason: BanReason::DenyListed,
+            until: Utc::now() + self.ban_duration,
+        }))
     }
 }
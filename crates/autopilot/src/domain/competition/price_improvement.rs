@@ -0,0 +1,137 @@
+//! How much better than the external prices of an auction the winning
+//! solutions executed their orders. Used for analytics only.
+
+use {
+    super::Solution,
+    crate::domain::{OrderUid, auction},
+    num::{BigRational, One, ToPrimitive, Zero},
+    number::conversions::u256_to_big_rational,
+};
+
+/// Price improvement of a single order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceImprovement {
+    pub order: OrderUid,
+    /// By how many basis points the value the user received exceeds the value
+    /// they sold according to the external prices. Negative if the order got
+    /// executed worse than the external prices.
+    pub improvement_bps: f64,
+}
+
+/// Computes the [`PriceImprovement`] of every order executed by the given
+/// solutions. The executed amounts already account for the clearing prices
+/// and fees of the solution. Orders trading a token without an external price
+/// are skipped.
+pub fn price_improvements<'a>(
+    solutions: impl IntoIterator<Item = &'a Solution>,
+    external_prices: &auction::Prices,
+) -> Vec<PriceImprovement> {
+    let mut improvements = Vec::new();
+    for solution in solutions {
+        for (uid, order) in solution.orders() {
+            let (Some(sell_price), Some(buy_price)) = (
+                external_prices.get(&order.sell.token),
+                external_prices.get(&order.buy.token),
+            ) else {
+                tracing::debug!(order = ?uid, "skipping order trading a token without external price");
+                continue;
+            };
+            let sold = u256_to_big_rational(&order.executed_sell.0)
+                * u256_to_big_rational(&sell_price.get().0);
+            if sold.is_zero() {
+                continue;
+            }
+            let bought = u256_to_big_rational(&order.executed_buy.0)
+                * u256_to_big_rational(&buy_price.get().0);
+            let improvement: BigRational =
+                (bought / sold - BigRational::one()) * BigRational::from_integer(10_000.into());
+            let Some(improvement_bps) = improvement.to_f64() else {
+                continue;
+            };
+            improvements.push(PriceImprovement {
+                order: *uid,
+                improvement_bps,
+            });
+        }
+    }
+    improvements
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::domain::{
+            auction::order,
+            competition::{Score, TradedOrder},
+            eth,
+        },
+        primitive_types::{H160, U256},
+        std::collections::HashMap,
+    };
+
+    fn token(id: u64) -> eth::TokenAddress {
+        H160::from_low_u64_be(id).into()
+    }
+
+    fn price(value: u64) -> auction::Price {
+        auction::Price::try_new(eth::Ether(U256::from(value))).unwrap()
+    }
+
+    fn execution(
+        sell_token: u64,
+        buy_token: u64,
+        executed_sell: u64,
+        executed_buy: u64,
+    ) -> TradedOrder {
+        TradedOrder {
+            side: order::Side::Sell,
+            sell: eth::Asset {
+                token: token(sell_token),
+                amount: U256::from(executed_sell).into(),
+            },
+            buy: eth::Asset {
+                token: token(buy_token),
+                amount: U256::from(executed_buy).into(),
+            },
+            executed_sell: U256::from(executed_sell).into(),
+            executed_buy: U256::from(executed_buy).into(),
+        }
+    }
+
+    fn solution(orders: &[(OrderUid, TradedOrder)]) -> Solution {
+        Solution::new(
+            0,
+            H160::zero().into(),
+            Score::try_new(eth::Ether(U256::one())).unwrap(),
+            orders.iter().cloned().collect(),
+            HashMap::new(),
+            None,
+        )
+    }
+
+    #[test]
+    fn computes_improvement_against_external_prices() {
+        // 1 token 1 is worth 2 token 2
+        let external = HashMap::from([(token(1), price(200)), (token(2), price(100))]);
+        let better = OrderUid([1; 56]);
+        let worse = OrderUid([2; 56]);
+        let unpriced = OrderUid([3; 56]);
+        let solutions = [
+            solution(&[(better, execution(1, 2, 100, 201))]),
+            solution(&[
+                (worse, execution(2, 1, 200, 99)),
+                (unpriced, execution(1, 3, 100, 100)),
+            ]),
+        ];
+
+        let mut improvements = price_improvements(&solutions, &external);
+        improvements.sort_by_key(|improvement| improvement.order.0);
+
+        assert_eq!(improvements.len(), 2);
+        assert_eq!(improvements[0].order, better);
+        assert!((improvements[0].improvement_bps - 50.).abs() < 1e-9);
+        assert_eq!(improvements[1].order, worse);
+        assert!((improvements[1].improvement_bps + 100.).abs() < 1e-9);
+    }
+}
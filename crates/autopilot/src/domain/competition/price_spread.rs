@@ -0,0 +1,174 @@
+//! Monitoring of how much the clearing prices proposed by different solutions
+//! deviate from the external prices of an auction.
+//!
+//! Clearing prices are only meaningful relative to the other prices of the same
+//! solution (solvers can scale all of them by the same factor), so every
+//! solution's prices get normalized to one of its own tokens before they get
+//! compared to the external prices.
+
+use {
+    super::Solution,
+    crate::domain::{auction, eth},
+    num::{BigRational, ToPrimitive},
+    number::conversions::u256_to_big_rational,
+    std::collections::HashMap,
+};
+
+/// Spread of the normalized clearing prices of a token across all solutions
+/// relative to its external price.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceSpread {
+    /// Smallest ratio of normalized clearing price to external price.
+    pub min_ratio: f64,
+    /// Largest ratio of normalized clearing price to external price.
+    pub max_ratio: f64,
+    /// The external price of the token in the auction.
+    pub external: auction::Price,
+}
+
+/// Computes the [`PriceSpread`] of every token that has an external price and
+/// a clearing price in at least one of the solutions.
+pub fn price_spreads<'a>(
+    solutions: impl IntoIterator<Item = &'a Solution>,
+    external_prices: &auction::Prices,
+) -> HashMap<eth::TokenAddress, PriceSpread> {
+    let mut spreads: HashMap<eth::TokenAddress, PriceSpread> = HashMap::new();
+    for solution in solutions {
+        for (token, ratio) in normalized_ratios(solution, external_prices) {
+            let external = external_prices[&token];
+            spreads
+                .entry(token)
+                .and_modify(|spread| {
+                    spread.min_ratio = spread.min_ratio.min(ratio);
+                    spread.max_ratio = spread.max_ratio.max(ratio);
+                })
+                .or_insert(PriceSpread {
+                    min_ratio: ratio,
+                    max_ratio: ratio,
+                    external,
+                });
+        }
+    }
+    spreads
+}
+
+/// Ratios of clearing price to external price of all tokens of the solution
+/// with an external price, divided by the ratio of the reference token. The
+/// reference is the token with the lowest address so it doesn't depend on the
+/// iteration order of the prices.
+fn normalized_ratios(
+    solution: &Solution,
+    external_prices: &auction::Prices,
+) -> Vec<(eth::TokenAddress, f64)> {
+    let ratios: Vec<_> = solution
+        .prices()
+        .iter()
+        .filter_map(|(token, clearing)| {
+            let external = external_prices.get(token)?;
+            Some((*token, ratio(*clearing, *external)?))
+        })
+        .collect();
+    let Some(reference) = ratios
+        .iter()
+        .min_by_key(|(token, _)| *token)
+        .map(|(_, ratio)| *ratio)
+        .filter(|ratio| *ratio > 0.)
+    else {
+        return Vec::new();
+    };
+    ratios
+        .into_iter()
+        .map(|(token, ratio)| (token, ratio / reference))
+        .collect()
+}
+
+fn ratio(clearing: auction::Price, external: auction::Price) -> Option<f64> {
+    let ratio: BigRational =
+        u256_to_big_rational(&clearing.get().0) / u256_to_big_rational(&external.get().0);
+    ratio.to_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::domain::competition::Score,
+        primitive_types::{H160, U256},
+    };
+
+    fn token(id: u64) -> eth::TokenAddress {
+        H160::from_low_u64_be(id).into()
+    }
+
+    fn price(value: u64) -> auction::Price {
+        auction::Price::try_new(eth::Ether(U256::from(value))).unwrap()
+    }
+
+    fn solution(prices: &[(eth::TokenAddress, u64)]) -> Solution {
+        Solution::new(
+            0,
+            H160::zero().into(),
+            Score::try_new(eth::Ether(U256::one())).unwrap(),
+            HashMap::new(),
+            prices
+                .iter()
+                .map(|(token, value)| (*token, price(*value)))
+                .collect(),
+            None,
+        )
+    }
+
+    #[test]
+    fn computes_spread_across_solutions() {
+        let external = HashMap::from([
+            (token(1), price(100)),
+            (token(2), price(50)),
+            (token(3), price(10)),
+        ]);
+        let solutions = [
+            solution(&[(token(1), 100), (token(2), 45), (token(3), 10)]),
+            solution(&[(token(1), 100), (token(2), 60), (token(4), 10)]),
+            solution(&[(token(1), 100), (token(2), 50)]),
+        ];
+
+        let spreads = price_spreads(&solutions, &external);
+
+        assert_eq!(spreads.len(), 3);
+        // the reference token always matches its external price
+        assert_eq!(
+            spreads[&token(1)],
+            PriceSpread {
+                min_ratio: 1.,
+                max_ratio: 1.,
+                external: price(100),
+            }
+        );
+        assert_eq!(
+            spreads[&token(2)],
+            PriceSpread {
+                min_ratio: 0.9,
+                max_ratio: 1.2,
+                external: price(50),
+            }
+        );
+        // tokens without external price are ignored
+        assert!(!spreads.contains_key(&token(4)));
+    }
+
+    #[test]
+    fn normalizes_prices_per_solution() {
+        let external = HashMap::from([(token(1), price(100)), (token(2), price(50))]);
+        // both solutions have the same relative prices at different scales
+        let solutions = [
+            solution(&[(token(1), 100), (token(2), 50)]),
+            solution(&[(token(1), 1000), (token(2), 500)]),
+        ];
+
+        let spreads = price_spreads(&solutions, &external);
+
+        for token in [token(1), token(2)] {
+            assert_eq!(spreads[&token].min_ratio, 1.);
+            assert_eq!(spreads[&token].max_ratio, 1.);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Sanity check of the protocol fees implied by a solution. Fees that take up
+//! a large part of an order's volume are most likely caused by misconfigured
+//! fee policies.
+
+use {
+    super::Solution,
+    crate::domain::{
+        OrderUid,
+        auction::order::{self, TargetAmount},
+        eth,
+        fee,
+        settlement::{
+            math,
+            transaction::{ClearingPrices, Prices},
+        },
+    },
+    anyhow::{Context, Result},
+    std::collections::HashMap,
+};
+
+/// Returns the orders of the solution whose protocol fees exceed `max_bps`
+/// of the executed volume (in the surplus token). Orders for which the fees
+/// can't be computed are skipped.
+pub fn orders_exceeding_cap(
+    solution: &Solution,
+    fee_policies: &HashMap<OrderUid, impl AsRef<[fee::Policy]>>,
+    max_bps: u32,
+) -> Vec<OrderUid> {
+    solution
+        .orders()
+        .iter()
+        .filter(|(uid, _)| fee_policies.contains_key(uid))
+        .filter_map(|(uid, traded)| {
+            let (fee, volume) = match fee_and_volume(*uid, traded, solution, fee_policies) {
+                Ok(result) => result,
+                Err(err) => {
+                    tracing::debug!(?uid, ?err, "failed to compute protocol fees of order");
+                    return None;
+                }
+            };
+            // fee / volume > max_bps / 10_000
+            (fee.full_mul(10_000.into()) > volume.full_mul(max_bps.into())).then_some(*uid)
+        })
+        .collect()
+}
+
+/// Total protocol fee and executed volume of the order, both denominated in
+/// the surplus token.
+fn fee_and_volume(
+    uid: OrderUid,
+    traded: &super::TradedOrder,
+    solution: &Solution,
+    fee_policies: &HashMap<OrderUid, impl AsRef<[fee::Policy]>>,
+) -> Result<(eth::U256, eth::U256)> {
+    let uniform_sell_price = solution
+        .prices()
+        .get(&traded.sell.token)
+        .context("no uniform clearing price for sell token")?;
+    let uniform_buy_price = solution
+        .prices()
+        .get(&traded.buy.token)
+        .context("no uniform clearing price for buy token")?;
+    let trade = math::Trade {
+        uid,
+        sell: traded.sell,
+        buy: traded.buy,
+        side: traded.side,
+        executed: match traded.side {
+            order::Side::Buy => TargetAmount(traded.executed_buy.into()),
+            order::Side::Sell => TargetAmount(traded.executed_sell.into()),
+        },
+        prices: Prices {
+            uniform: ClearingPrices {
+                sell: uniform_sell_price.get().into(),
+                buy: uniform_buy_price.get().into(),
+            },
+            // the executed amounts already include all fees
+            custom: ClearingPrices {
+                sell: traded.executed_buy.into(),
+                buy: traded.executed_sell.into(),
+            },
+        },
+    };
+    let fee = trade
+        .protocol_fees(fee_policies)
+        .context("failed to compute protocol fees")?
+        .into_iter()
+        .try_fold(eth::U256::zero(), |acc, fee| {
+            acc.checked_add(fee.fee.amount.0)
+        })
+        .context("protocol fee overflow")?;
+    let volume = match traded.side {
+        order::Side::Sell => traded.executed_buy.0,
+        order::Side::Buy => traded.executed_sell.0,
+    };
+    Ok((fee, volume))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::domain::{
+            auction,
+            competition::{Score, TradedOrder},
+        },
+        primitive_types::{H160, U256},
+    };
+
+    fn token(id: u64) -> eth::TokenAddress {
+        H160::from_low_u64_be(id).into()
+    }
+
+    fn solution(uid: OrderUid) -> Solution {
+        let price = auction::Price::try_new(eth::Ether(U256::exp10(18))).unwrap();
+        Solution::new(
+            0,
+            H160::zero().into(),
+            Score::try_new(eth::Ether(U256::one())).unwrap(),
+            HashMap::from([(
+                uid,
+                TradedOrder {
+                    side: order::Side::Sell,
+                    sell: eth::Asset {
+                        token: token(1),
+                        amount: U256::from(1_000).into(),
+                    },
+                    buy: eth::Asset {
+                        token: token(2),
+                        amount: U256::from(500).into(),
+                    },
+                    executed_sell: U256::from(1_000).into(),
+                    executed_buy: U256::from(1_000).into(),
+                },
+            )]),
+            HashMap::from([(token(1), price), (token(2), price)]),
+            None,
+        )
+    }
+
+    fn volume_fee(factor: f64) -> Vec<fee::Policy> {
+        vec![fee::Policy::Volume {
+            factor: factor.try_into().unwrap(),
+        }]
+    }
+
+    #[test]
+    fn detects_excessive_protocol_fees() {
+        let uid = OrderUid([1; 56]);
+        let solution = solution(uid);
+
+        // 0.1% volume fee stays below a 1% cap
+        let policies = HashMap::from([(uid, volume_fee(0.001))]);
+        assert!(orders_exceeding_cap(&solution, &policies, 100).is_empty());
+
+        // 50% volume fee is as large as the executed amount
+        let policies = HashMap::from([(uid, volume_fee(0.5))]);
+        assert_eq!(orders_exceeding_cap(&solution, &policies, 100), vec![uid]);
+
+        // orders without fee policies are not checked
+        let policies = HashMap::<OrderUid, Vec<fee::Policy>>::new();
+        assert!(orders_exceeding_cap(&solution, &policies, 0).is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+//! Total order in which solutions get ranked.
+//!
+//! Equal scores are common (e.g. a driver proposing the same solution twice),
+//! so ties get broken by keys that don't change between runs to keep rankings
+//! reproducible. Callers shuffle solutions beforehand and use a stable sort so
+//! that only solutions which are identical on all keys end up in random order.
+
+use {
+    super::{Participant, SolutionId},
+    crate::domain::eth,
+    std::cmp::Ordering,
+};
+
+/// Everything solutions get ordered by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key<S> {
+    pub score: S,
+    pub submission_address: eth::Address,
+    pub solution_id: SolutionId,
+}
+
+impl<S> Key<S> {
+    pub fn new<T>(participant: &Participant<T>, score: S) -> Self {
+        Self {
+            score,
+            submission_address: participant.submission_address(),
+            solution_id: participant.solution().id(),
+        }
+    }
+}
+
+/// Orders by score descending, then by the driver's submission address
+/// ascending and finally by solution id ascending.
+pub fn compare<S: Ord>(a: &Key<S>, b: &Key<S>) -> Ordering {
+    b.score
+        .cmp(&a.score)
+        .then_with(|| a.submission_address.cmp(&b.submission_address))
+        .then_with(|| a.solution_id.cmp(&b.solution_id))
+}
+
+/// Sorts participants from best to worst like [`compare`] but treats scores
+/// within `epsilon` of each other as ties. Tied participants keep their
+/// relative input order, so callers that shuffle beforehand break the ties
+/// randomly instead of by keys solvers could pick to their advantage.
+pub fn sort_with_ties<T>(
+    participants: &mut Vec<Participant<T>>,
+    score: impl Fn(&Participant<T>) -> eth::U256,
+    epsilon: eth::U256,
+) {
+    sort_keys_with_ties(
+        participants,
+        |participant| Key::new(participant, score(participant)),
+        epsilon,
+    );
+}
+
+fn sort_keys_with_ties<T>(
+    items: &mut Vec<T>,
+    key: impl Fn(&T) -> Key<eth::U256>,
+    epsilon: eth::U256,
+) {
+    if epsilon.is_zero() {
+        items.sort_by(|a, b| compare(&key(a), &key(b)));
+        return;
+    }
+    // remember the input order to break ties with
+    let mut indexed: Vec<_> = items.drain(..).enumerate().collect();
+    indexed.sort_by(|(_, a), (_, b)| compare(&key(a), &key(b)));
+    let groups = tie_groups(indexed.iter().map(|(_, item)| key(item).score), epsilon);
+    // groups are numbered from best to worst
+    let mut grouped: Vec<_> = groups.into_iter().zip(indexed).collect();
+    grouped.sort_by_key(|(group, (index, _))| (*group, *index));
+    items.extend(grouped.into_iter().map(|(_, (_, item))| item));
+}
+
+/// Assigns scores sorted from highest to lowest to groups of ties. Groups get
+/// formed greedily: each group contains all scores within `epsilon` of its
+/// highest score, so the order of the groups never depends on tie-breaking.
+fn tie_groups(scores: impl IntoIterator<Item = eth::U256>, epsilon: eth::U256) -> Vec<usize> {
+    let mut group = 0;
+    let mut highest = None;
+    scores
+        .into_iter()
+        .map(|score| {
+            match highest {
+                None => highest = Some(score),
+                Some(highest_score) if highest_score.saturating_sub(score) <= epsilon => (),
+                Some(_) => {
+                    group += 1;
+                    highest = Some(score);
+                }
+            }
+            group
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, itertools::Itertools, primitive_types::H160};
+
+    fn key(score: u64, submission_address: u64, solution_id: SolutionId) -> Key<u64> {
+        Key {
+            score,
+            submission_address: H160::from_low_u64_be(submission_address).into(),
+            solution_id,
+        }
+    }
+
+    #[test]
+    fn orders_by_score_then_address_then_id() {
+        // all combinations of the keys in ranking order
+        let sorted = [
+            key(2, 1, 1),
+            key(2, 1, 2),
+            key(2, 2, 1),
+            key(2, 2, 2),
+            key(1, 1, 1),
+            key(1, 1, 2),
+            key(1, 2, 1),
+            key(1, 2, 2),
+        ];
+        for ((i, a), (j, b)) in sorted
+            .iter()
+            .enumerate()
+            .cartesian_product(sorted.iter().enumerate())
+        {
+            assert_eq!(compare(a, b), i.cmp(&j), "{a:?} vs {b:?}");
+        }
+    }
+
+    #[test]
+    fn sorting_is_independent_of_input_order() {
+        let keys = [key(1, 2, 1), key(3, 1, 5), key(1, 1, 7), key(3, 1, 2)];
+        let expected = [key(3, 1, 2), key(3, 1, 5), key(1, 1, 7), key(1, 2, 1)];
+        for permutation in keys.iter().copied().permutations(keys.len()) {
+            let mut sorted = permutation;
+            sorted.sort_by(compare);
+            assert_eq!(sorted, expected);
+        }
+    }
+
+    #[test]
+    fn only_identical_keys_are_equal() {
+        assert_eq!(compare(&key(1, 1, 1), &key(1, 1, 1)), Ordering::Equal);
+        assert_ne!(compare(&key(1, 1, 1), &key(1, 1, 2)), Ordering::Equal);
+        assert_ne!(compare(&key(1, 1, 1), &key(1, 2, 1)), Ordering::Equal);
+        assert_ne!(compare(&key(1, 1, 1), &key(2, 1, 1)), Ordering::Equal);
+    }
+
+    fn u256_key(score: u64, submission_address: u64, solution_id: SolutionId) -> Key<eth::U256> {
+        Key {
+            score: score.into(),
+            submission_address: H160::from_low_u64_be(submission_address).into(),
+            solution_id,
+        }
+    }
+
+    fn sorted_with_ties(mut keys: Vec<Key<eth::U256>>, epsilon: u64) -> Vec<Key<eth::U256>> {
+        sort_keys_with_ties(&mut keys, |key| *key, epsilon.into());
+        keys
+    }
+
+    #[test]
+    fn breaks_ties_within_epsilon() {
+        let keys = vec![u256_key(100, 1, 0), u256_key(95, 0, 0), u256_key(101, 2, 0)];
+
+        // without an epsilon the higher score always wins
+        assert_eq!(
+            sorted_with_ties(keys.clone(), 0),
+            [u256_key(101, 2, 0), u256_key(100, 1, 0), u256_key(95, 0, 0)]
+        );
+        // 101 and 100 are tied and keep their input order, 95 is outside the
+        // epsilon
+        assert_eq!(
+            sorted_with_ties(keys.clone(), 1),
+            [u256_key(100, 1, 0), u256_key(101, 2, 0), u256_key(95, 0, 0)]
+        );
+        let reversed: Vec<_> = keys.iter().rev().copied().collect();
+        assert_eq!(
+            sorted_with_ties(reversed.clone(), 1),
+            [u256_key(101, 2, 0), u256_key(100, 1, 0), u256_key(95, 0, 0)]
+        );
+        // a larger epsilon ties all of them
+        assert_eq!(sorted_with_ties(keys.clone(), 6), keys);
+        assert_eq!(sorted_with_ties(reversed.clone(), 6), reversed);
+    }
+
+    #[test]
+    fn ties_within_epsilon_dont_depend_on_addresses() {
+        // the lower address gets no advantage over the higher score
+        let keys = vec![u256_key(101, 2, 0), u256_key(100, 1, 0)];
+        assert_eq!(sorted_with_ties(keys.clone(), 1), keys);
+    }
+
+    #[test]
+    fn groups_ties_relative_to_highest_score() {
+        // 98 is within the epsilon of 99 but not of 100, so it starts a new
+        // group and can't overtake 100 although 99 was tied with 100
+        let keys = vec![u256_key(98, 0, 0), u256_key(99, 1, 0), u256_key(100, 2, 0)];
+        assert_eq!(
+            sorted_with_ties(keys, 1),
+            [u256_key(99, 1, 0), u256_key(100, 2, 0), u256_key(98, 0, 0)]
+        );
+    }
+
+    #[test]
+    fn assigns_tie_groups() {
+        let scores = [10, 9, 8, 7, 3, 3, 0].map(eth::U256::from);
+        assert_eq!(tie_groups(scores, 0.into()), [0, 1, 2, 3, 4, 4, 5]);
+        assert_eq!(tie_groups(scores, 2.into()), [0, 0, 0, 1, 2, 2, 3]);
+        assert_eq!(tie_groups(scores, 10.into()), [0, 0, 0, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,42 @@
+//! The autopilot's view of an auction that the solver rewards computation
+//! relies on. It gets assembled once all winners finished settling.
+
+use crate::domain::eth;
+
+#[derive(Debug, Clone)]
+pub struct RewardInputs {
+    /// The last block in which the winning solutions were allowed to get
+    /// settled.
+    pub block_deadline: u64,
+    /// Whether all competition data of the auction got stored. If not, the
+    /// rewards can't be computed from the stored competition data alone.
+    pub competition_data_complete: bool,
+    pub winners: Vec<Winner>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Winner {
+    pub solver: eth::Address,
+    /// Position of the solution in the ranking of the auction.
+    pub solution_uid: usize,
+    pub score: eth::Ether,
+    /// Score the auction would have had without this solver, if computed.
+    pub reference_score: Option<eth::Ether>,
+    /// `None` if the solver didn't manage to settle its solution in time,
+    /// which gets penalized.
+    pub settlement: Option<Settlement>,
+    /// Whether the autopilot never asked the solver to settle, e.g. because
+    /// storing the competition failed. Such winners must not get penalized
+    /// for the missing settlement.
+    pub settlement_skipped: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Settlement {
+    pub tx: eth::TxId,
+    /// Gas used by the settlement transaction, if it could be fetched.
+    pub gas: Option<eth::Gas>,
+    /// Effective gas price of the settlement transaction, if it could be
+    /// fetched.
+    pub gas_price: Option<eth::EffectiveGasPrice>,
+}
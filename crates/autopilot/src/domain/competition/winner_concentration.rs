@@ -0,0 +1,84 @@
+//! Measures how concentrated winning auctions is among solvers.
+
+use {
+    crate::domain::eth,
+    std::{
+        collections::{HashMap, VecDeque},
+        num::NonZeroUsize,
+    },
+};
+
+/// Keeps the winners of the most recent auctions to compute the
+/// Herfindahl-Hirschman index (HHI) of their wins.
+#[derive(Debug)]
+pub struct WinnerConcentration {
+    window: NonZeroUsize,
+    /// Winners per auction, oldest first.
+    auctions: VecDeque<Vec<eth::Address>>,
+}
+
+impl WinnerConcentration {
+    pub fn new(window: NonZeroUsize) -> Self {
+        Self {
+            window,
+            auctions: VecDeque::with_capacity(window.get()),
+        }
+    }
+
+    /// Records the winners of an auction, dropping the oldest auction if the
+    /// window is full.
+    pub fn record(&mut self, winners: Vec<eth::Address>) {
+        if self.auctions.len() == self.window.get() {
+            self.auctions.pop_front();
+        }
+        self.auctions.push_back(winners);
+    }
+
+    /// Sum of the squared shares of all wins within the window. Ranges from
+    /// `1 / solvers` (wins evenly spread) to 1 (a single solver won
+    /// everything). Returns 0 if there were no wins.
+    pub fn hhi(&self) -> f64 {
+        let mut wins = HashMap::<_, usize>::new();
+        for winner in self.auctions.iter().flatten() {
+            *wins.entry(winner).or_default() += 1;
+        }
+        let total: usize = wins.values().sum();
+        if total == 0 {
+            return 0.;
+        }
+        wins.values()
+            .map(|wins| {
+                let share = *wins as f64 / total as f64;
+                share * share
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solver(byte: u8) -> eth::Address {
+        eth::Address(eth::H160([byte; 20]))
+    }
+
+    #[test]
+    fn computes_hhi_over_window() {
+        let mut concentration = WinnerConcentration::new(NonZeroUsize::new(4).unwrap());
+        assert_eq!(concentration.hhi(), 0.);
+
+        concentration.record(vec![solver(1)]);
+        assert_eq!(concentration.hhi(), 1.);
+
+        concentration.record(vec![solver(2)]);
+        concentration.record(vec![solver(3), solver(4)]);
+        assert_eq!(concentration.hhi(), 0.25);
+
+        // the oldest auction drops out of the window
+        concentration.record(vec![solver(2)]);
+        concentration.record(vec![solver(2)]);
+        // wins: 2 => 3, 3 => 1, 4 => 1
+        assert!((concentration.hhi() - 0.44).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,186 @@
+//! Audit of the fairness of the whole winning coalition. The fairness check
+//! before winner selection compares every solution against all other
+//! solutions, including ones that could never have won together with the
+//! rest of the winners. After winners are picked this audit compares each
+//! settled order against the solutions that could have replaced its winner
+//! without conflicting with the remaining winners. Violations can't change
+//! the outcome anymore but get reported to monitor the selection policy.
+
+use {
+    super::{Ranking, combinatorial},
+    crate::domain::{OrderUid, competition::TradedOrder, eth::WrappedNativeToken},
+    primitive_types::U512,
+    std::collections::HashSet,
+};
+
+/// An order a winner executed worse than a losing solution that could have
+/// won alongside the rest of the winners.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub order: OrderUid,
+    /// Name of the driver whose winning solution executed the order.
+    pub winner: String,
+    pub winning_execution: TradedOrder,
+    /// Name of the driver with the best compatible execution.
+    pub alternative: String,
+    pub alternative_execution: TradedOrder,
+}
+
+/// Returns all orders whose winning execution is worse than the best
+/// execution of a non-winning solution that is disjoint with all other
+/// winners. If `weth` is set the native token is considered the same token as
+/// its wrapped counterpart when checking for overlapping solutions.
+pub fn audit(ranking: &Ranking, weth: Option<WrappedNativeToken>) -> Vec<Violation> {
+    let winners: Vec<_> = ranking.winners().collect();
+    let winning_token_pairs: Vec<_> = winners
+        .iter()
+        .map(|winner| combinatorial::swapped_token_pairs(winner.solution(), weth))
+        .collect();
+
+    let mut violations = Vec::new();
+    for (index, winner) in winners.iter().enumerate() {
+        let other_winners_token_pairs: HashSet<_> = winning_token_pairs
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != index)
+            .flat_map(|(_, pairs)| pairs.iter().cloned())
+            .collect();
+        let alternatives: Vec<_> = ranking
+            .non_winners()
+            .filter(|participant| {
+                combinatorial::can_coexist(participant.solution(), &other_winners_token_pairs, weth)
+            })
+            .collect();
+
+        for (uid, execution) in winner.solution().orders() {
+            let best = alternatives
+                .iter()
+                .filter_map(|participant| {
+                    Some((participant, participant.solution().orders().get(uid)?))
+                })
+                .reduce(|best, candidate| {
+                    if is_better(candidate.1, best.1) {
+                        candidate
+                    } else {
+                        best
+                    }
+                });
+            let Some((alternative, alternative_execution)) = best else {
+                continue;
+            };
+            if is_better(alternative_execution, execution) {
+                violations.push(Violation {
+                    order: *uid,
+                    winner: winner.driver().name.clone(),
+                    winning_execution: *execution,
+                    alternative: alternative.driver().name.clone(),
+                    alternative_execution: *alternative_execution,
+                });
+            }
+        }
+    }
+    violations
+}
+
+/// Whether `a` gives the user a better rate than `b`, i.e. more buy tokens
+/// per sold token. Holds for sell and buy orders alike.
+fn is_better(a: &TradedOrder, b: &TradedOrder) -> bool {
+    let rate = |execution: &TradedOrder, other: &TradedOrder| -> U512 {
+        execution.executed_buy.0.full_mul(other.executed_sell.0)
+    };
+    rate(a, b) > rate(b, a)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            domain::{
+                auction::order,
+                competition::{Participant, Ranked, Score, Solution},
+                eth,
+            },
+            infra::solvers::fake::offline_driver,
+        },
+        primitive_types::{H160, U256},
+        std::{collections::HashMap, sync::Arc},
+    };
+
+    fn execution(sell_token: u64, buy_token: u64, executed_buy: u64) -> TradedOrder {
+        TradedOrder {
+            side: order::Side::Sell,
+            sell: eth::Asset {
+                token: H160::from_low_u64_be(sell_token).into(),
+                amount: U256::from(100).into(),
+            },
+            buy: eth::Asset {
+                token: H160::from_low_u64_be(buy_token).into(),
+                amount: U256::from(90).into(),
+            },
+            executed_sell: U256::from(100).into(),
+            executed_buy: U256::from(executed_buy).into(),
+        }
+    }
+
+    async fn participant(
+        driver: &str,
+        rank: Ranked,
+        orders: &[(OrderUid, TradedOrder)],
+    ) -> Participant<Ranked> {
+        let solver = H160::from_low_u64_be(1);
+        let solution = Solution::new(
+            0,
+            solver.into(),
+            Score::try_new(eth::Ether(U256::one())).unwrap(),
+            orders.iter().cloned().collect::<HashMap<_, _>>(),
+            Default::default(),
+            None,
+        );
+        let driver = offline_driver(driver, solver).await;
+        Participant::new(solution, Arc::new(driver)).rank(rank)
+    }
+
+    #[tokio::test]
+    async fn compares_against_compatible_alternatives_only() {
+        let a = OrderUid([1; 56]);
+        let b = OrderUid([2; 56]);
+        let ranking = Ranking {
+            filtered_out: vec![],
+            ranked: vec![
+                participant("winner_1", Ranked::Winner, &[(a, execution(1, 2, 95))]).await,
+                participant("winner_2", Ranked::Winner, &[(b, execution(3, 4, 95))]).await,
+                // better execution of `a` that could have replaced `winner_1`
+                participant("compatible", Ranked::NonWinner, &[(a, execution(1, 2, 97))]).await,
+                participant(
+                    "compatible_worse",
+                    Ranked::NonWinner,
+                    &[(a, execution(1, 2, 96))],
+                )
+                .await,
+                // better execution of `b` but it also trades the token pair of
+                // `winner_1` so it conflicts with the rest of the coalition
+                participant(
+                    "incompatible",
+                    Ranked::NonWinner,
+                    &[(b, execution(3, 4, 99)), (a, execution(1, 2, 90))],
+                )
+                .await,
+            ],
+            observe_only: vec![],
+        };
+
+        let violations = audit(&ranking, None);
+
+        assert_eq!(
+            violations,
+            vec![Violation {
+                order: a,
+                winner: "winner_1".to_string(),
+                winning_execution: execution(1, 2, 95),
+                alternative: "compatible".to_string(),
+                alternative_execution: execution(1, 2, 97),
+            }]
+        );
+    }
+}
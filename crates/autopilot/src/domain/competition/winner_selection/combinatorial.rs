@@ -24,7 +24,18 @@
 //! That is effectively a measurement of how much better each order got executed
 //! because solver S participated in the competition.
 use {
-    super::{Arbitrator, PartitionedSolutions, Ranking},
+    super::{
+        Arbitrator,
+        Merging,
+        Objective,
+        PartitionedSolutions,
+        Ranking,
+        Selection,
+        SelectionMode,
+        max_score,
+        select_winning_solutions,
+        selections,
+    },
     crate::domain::{
         self,
         OrderUid,
@@ -32,7 +43,7 @@ use {
             Prices,
             order::{self, TargetAmount},
         },
-        competition::{Participant, Ranked, Score, Solution, Unranked},
+        competition::{Participant, Ranked, Score, Solution, Unranked, ranking},
         eth::{self, WrappedNativeToken},
         fee,
         settlement::{
@@ -44,6 +55,7 @@ use {
     itertools::{Either, Itertools},
     std::{
         collections::{HashMap, HashSet},
+        num::NonZeroUsize,
         ops::Add,
     },
 };
@@ -57,42 +69,62 @@ impl Arbitrator for Config {
         // Discard all solutions where we can't compute the aggregate scores
         // accurately because the fairness guarantees heavily rely on them.
         let scores_by_solution = compute_scores_by_solution(&mut participants, auction);
-        participants.sort_by_key(|participant| {
-            std::cmp::Reverse(
+        ranking::sort_with_ties(
+            &mut participants,
+            |participant| {
                 // we use the computed score to not trust the score provided by solvers
                 participant
                     .solution()
                     .computed_score()
                     .expect("every remaining participant has a computed score")
                     .get()
-                    .0,
-            )
-        });
+                    .0
+            },
+            self.score_tie_epsilon,
+        );
         let baseline_scores = compute_baseline_scores(&scores_by_solution);
-        let (fair, unfair) = participants.into_iter().partition_map(|p| {
-            let aggregated_scores = scores_by_solution
-                .get(&SolutionKey {
-                    driver: p.driver().submission_address,
-                    solution_id: p.solution().id(),
+        // the global fairness threshold also applies to orders across solutions
+        let fair_to_others: Vec<_> = (0..participants.len())
+            .map(|index| {
+                self.fairness_threshold_floor.is_none_or(|floor| {
+                    max_score::is_solution_fair(
+                        &participants[index],
+                        &participants[index..],
+                        auction,
+                        Some(floor),
+                    )
                 })
-                .expect("every remaining participant has an entry");
-            // only keep solutions where each order execution is at least as good as
-            // the baseline solution.
-            // we only filter out unfair solutions with more than one token pair,
-            // to avoid reference scores set to 0.
-            // see https://github.com/fhenneke/comb_auctions/issues/2
-            if aggregated_scores.len() == 1
-                || aggregated_scores.iter().all(|(pair, score)| {
-                    baseline_scores
-                        .get(pair)
-                        .is_none_or(|baseline| score >= baseline)
-                })
-            {
-                Either::Left(p)
-            } else {
-                Either::Right(p)
-            }
-        });
+            })
+            .collect();
+        let (fair, unfair) =
+            participants
+                .into_iter()
+                .zip(fair_to_others)
+                .partition_map(|(p, fair_to_others)| {
+                    let aggregated_scores = scores_by_solution
+                        .get(&SolutionKey {
+                            driver: p.submission_address(),
+                            solution_id: p.solution().id(),
+                        })
+                        .expect("every remaining participant has an entry");
+                    // only keep solutions where each order execution is at least as good as
+                    // the baseline solution.
+                    // we only filter out unfair solutions with more than one token pair,
+                    // to avoid reference scores set to 0.
+                    // see https://github.com/fhenneke/comb_auctions/issues/2
+                    if fair_to_others
+                        && (aggregated_scores.len() == 1
+                            || aggregated_scores.iter().all(|(pair, score)| {
+                                baseline_scores
+                                    .get(pair)
+                                    .is_none_or(|baseline| score >= baseline)
+                            }))
+                    {
+                        Either::Left(p)
+                    } else {
+                        Either::Right(p)
+                    }
+                });
         PartitionedSolutions {
             kept: fair,
             discarded: unfair,
@@ -100,26 +132,73 @@ impl Arbitrator for Config {
     }
 
     fn mark_winners(&self, participants: Vec<Participant<Unranked>>) -> Vec<Participant> {
-        let winner_indexes = self.pick_winners(participants.iter().map(|p| p.solution()));
+        let selections = selections(
+            &participants,
+            self.max_winners,
+            self.max_winners_per_submission_address,
+            self.native_token_normalization(),
+            self.selection_mode(),
+        );
+        let extra_winners = selections
+            .iter()
+            .filter(|selection| selection.is_winner())
+            .count()
+            .saturating_sub(self.max_winners);
+        domain::Metrics::get()
+            .extra_winners
+            .inc_by(extra_winners.try_into().unwrap_or(u64::MAX));
         participants
             .into_iter()
-            .enumerate()
-            .map(|(index, participant)| {
-                let rank = match winner_indexes.contains(&index) {
-                    true => Ranked::Winner,
-                    false => Ranked::NonWinner,
+            .zip(selections)
+            .map(|(participant, selection)| {
+                let rank = match selection {
+                    Selection::Won => Ranked::Winner,
+                    Selection::BlockedByTokenConflict => Ranked::BlockedByTokenConflict,
+                    Selection::Lost => Ranked::NonWinner,
                 };
                 participant.rank(rank)
             })
             .collect()
     }
 
+    fn score_tie_epsilon(&self) -> eth::U256 {
+        self.score_tie_epsilon
+    }
+
+    fn objective(&self) -> Objective {
+        self.objective
+    }
+
     fn compute_reference_scores(&self, ranking: &Ranking) -> HashMap<eth::Address, Score> {
         let mut reference_scores = HashMap::default();
+        // Winners can score slightly lower than the solutions they beat if
+        // their scores were tied. Rewards of such winners must not get
+        // negative. Otherwise the reference score may exceed the winning score
+        // like when merging other solutions beats the winner.
+        let winning_score = ranking
+            .winners()
+            .filter_map(|participant| participant.solution().computed_score)
+            .reduce(Score::add)
+            .unwrap_or_default();
+        let computed_score =
+            |participant: &Participant| participant.solution().computed_score.map(|s| s.get().0);
+        let won_tie = |winner: &Participant| {
+            computed_score(winner).is_some_and(|winner_score| {
+                ranking
+                    .non_winners()
+                    .filter_map(computed_score)
+                    .any(|score| {
+                        score > winner_score && score - winner_score <= self.score_tie_epsilon
+                    })
+            })
+        };
 
         for participant in &ranking.ranked {
-            let solver = participant.driver().submission_address;
-            if reference_scores.len() >= self.max_winners {
+            // keyed by the address that actually produced the winning solution
+            // which can differ from the driver's main submission address
+            let solver = participant.solver_address();
+            let driver = participant.submission_address();
+            if reference_scores.len() >= self.max_winners + self.merging.max_extra_winners() {
                 // all winners have been processed
                 return reference_scores;
             }
@@ -135,16 +214,26 @@ impl Arbitrator for Config {
             let solutions_without_solver = ranking
                 .ranked
                 .iter()
-                .filter(|p| p.driver().submission_address != solver)
+                .filter(|p| p.submission_address() != driver)
                 .map(|p| p.solution());
-            let winner_indices = self.pick_winners(solutions_without_solver.clone());
+            let winners = select_winning_solutions(
+                solutions_without_solver.clone(),
+                self.max_winners,
+                self.max_winners_per_submission_address,
+                self.native_token_normalization(),
+                self.selection_mode(),
+            );
 
             let score = solutions_without_solver
-                .enumerate()
-                .filter(|(index, _)| winner_indices.contains(index))
-                .filter_map(|(_, solution)| solution.computed_score)
+                .zip(winners)
+                .filter(|(_, selection)| selection.is_winner())
+                .filter_map(|(solution, _)| solution.computed_score)
                 .reduce(Score::add)
                 .unwrap_or_default();
+            let score = match won_tie(participant) {
+                true => score.min(winning_score),
+                false => score,
+            };
             reference_scores.insert(solver, score);
         }
 
@@ -152,40 +241,97 @@ impl Arbitrator for Config {
     }
 }
 
-impl Config {
-    /// Returns indices of winning solutions.
-    /// Assumes that `solutions` is sorted by score descendingly.
-    /// This logic was moved into a helper function to avoid a ton of `.clone()`
-    /// operations in `compute_reference_scores()`.
-    fn pick_winners<'a>(&self, solutions: impl Iterator<Item = &'a Solution>) -> HashSet<usize> {
-        // Winners are selected one by one, starting from the best solution,
-        // until `max_winners` are selected. A solution can only
-        // win if none of the (sell_token, buy_token) pairs of the executed
-        // orders have been covered by any previously selected winning solution.
-        // In other words this enforces a uniform **directional** clearing price.
-        let mut already_swapped_tokens_pairs = HashSet::new();
-        let mut winners = HashSet::default();
-        for (index, solution) in solutions.enumerate() {
-            if winners.len() >= self.max_winners {
-                return winners;
+/// Returns the outcome of the winner selection for every solution.
+/// Assumes that `solutions` is sorted by score descendingly.
+/// This logic was moved into a helper function to avoid a ton of `.clone()`
+/// operations in `compute_reference_scores()`.
+pub(super) fn pick_winners<'a>(
+    solutions: impl Iterator<Item = &'a Solution>,
+    max_winners: usize,
+    max_winners_per_submission_address: Option<NonZeroUsize>,
+    weth: Option<WrappedNativeToken>,
+    max_extra_winners: usize,
+) -> Vec<Selection> {
+    // Winners are selected one by one, starting from the best solution,
+    // until `max_winners` are selected. A solution can only
+    // win if none of the (sell_token, buy_token) pairs of the executed
+    // orders have been covered by any previously selected winning solution.
+    // In other words this enforces a uniform **directional** clearing price.
+    // Several drivers may share a submission address so the number of wins
+    // per submission address is capped separately to avoid nonce contention.
+    // Once `max_winners` are selected up to `max_extra_winners` more solutions
+    // may win but only if they settle orders no previous winner touches.
+    let mut already_swapped_tokens_pairs = HashSet::new();
+    let mut already_settled_orders = HashSet::new();
+    let mut winners = 0;
+    let mut extra_winners = 0;
+    let mut winners_by_submission_address = HashMap::<eth::Address, usize>::new();
+    solutions
+        .map(|solution| {
+            let is_extra = winners >= max_winners;
+            if is_extra && extra_winners >= max_extra_winners {
+                return Selection::Lost;
+            }
+            let submission_address_winners = winners_by_submission_address
+                .entry(solution.solver())
+                .or_default();
+            if max_winners_per_submission_address
+                .is_some_and(|max| *submission_address_winners >= max.get())
+            {
+                return Selection::Lost;
             }
 
-            let swapped_token_pairs = solution
-                .orders()
-                .values()
-                .map(|order| DirectedTokenPair {
-                    sell: order.sell.token.as_erc20(self.weth),
-                    buy: order.buy.token.as_erc20(self.weth),
-                })
-                .collect::<HashSet<_>>();
+            let settles_no_new_orders = is_extra
+                && solution
+                    .order_ids()
+                    .all(|uid| already_settled_orders.contains(uid));
+            if !can_coexist(solution, &already_swapped_tokens_pairs, weth) {
+                // extra winners must settle new orders regardless of conflicts
+                return match settles_no_new_orders {
+                    true => Selection::Lost,
+                    false => Selection::BlockedByTokenConflict,
+                };
+            }
+            if settles_no_new_orders {
+                return Selection::Lost;
+            }
 
-            if swapped_token_pairs.is_disjoint(&already_swapped_tokens_pairs) {
-                winners.insert(index);
-                already_swapped_tokens_pairs.extend(swapped_token_pairs);
+            winners += 1;
+            if is_extra {
+                extra_winners += 1;
             }
-        }
-        winners
-    }
+            *submission_address_winners += 1;
+            already_swapped_tokens_pairs.extend(swapped_token_pairs(solution, weth));
+            already_settled_orders.extend(solution.order_ids().copied());
+            Selection::Won
+        })
+        .collect()
+}
+
+/// Returns the directed token pairs the solution trades. If `weth` is set the
+/// native token gets treated as its wrapped counterpart.
+pub(super) fn swapped_token_pairs(
+    solution: &Solution,
+    weth: Option<WrappedNativeToken>,
+) -> HashSet<DirectedTokenPair> {
+    solution
+        .orders()
+        .values()
+        .map(|order| DirectedTokenPair {
+            sell: weth.map_or(order.sell.token, |weth| order.sell.token.as_erc20(weth)),
+            buy: weth.map_or(order.buy.token, |weth| order.buy.token.as_erc20(weth)),
+        })
+        .collect()
+}
+
+/// Whether the solution could win alongside winners that trade the given
+/// directed token pairs, i.e. whether it trades none of these pairs.
+pub(super) fn can_coexist(
+    solution: &Solution,
+    winning_token_pairs: &HashSet<DirectedTokenPair>,
+    weth: Option<WrappedNativeToken>,
+) -> bool {
+    swapped_token_pairs(solution, weth).is_disjoint(winning_token_pairs)
 }
 
 /// Let's call a solution that only trades 1 directed token pair a baseline
@@ -220,13 +366,11 @@ fn compute_scores_by_solution(
     let mut scores = HashMap::default();
 
     participants.retain_mut(|p| match score_by_token_pair(p.solution(), &auction) {
-        Ok(score) => {
-            let total_score = score
-                .values()
-                .fold(Default::default(), |acc, score| acc + *score);
+        Ok((score, exempt_score)) => {
+            let total_score = score.values().fold(exempt_score, |acc, score| acc + *score);
             scores.insert(
                 SolutionKey {
-                    driver: p.driver().submission_address,
+                    driver: p.submission_address(),
                     solution_id: p.solution().id,
                 },
                 score,
@@ -256,8 +400,15 @@ fn compute_scores_by_solution(
 /// it will return a map like:
 ///     (A, B) => 15
 ///     (B, C) => 5
-fn score_by_token_pair(solution: &Solution, auction: &Auction) -> Result<ScoreByDirection> {
+/// Orders exempt from the fairness check don't count towards the scores of
+/// their token pair so they can't make a solution unfair. Their total score
+/// gets returned separately.
+fn score_by_token_pair(
+    solution: &Solution,
+    auction: &Auction,
+) -> Result<(ScoreByDirection, Score)> {
     let mut scores = HashMap::default();
+    let mut exempt_score = Score::default();
     for (uid, trade) in solution.orders() {
         if !auction.contributes_to_score(uid) {
             continue;
@@ -302,6 +453,12 @@ fn score_by_token_pair(solution: &Solution, auction: &Auction) -> Result<ScoreBy
             .score(&auction.fee_policies, auction.native_prices)
             .context("failed to compute score")?;
 
+        if auction.fairness_exempt.contains(uid) {
+            tracing::debug!(?uid, "order is exempt from fairness check");
+            exempt_score += Score(score);
+            continue;
+        }
+
         let token_pair = DirectedTokenPair {
             sell: trade.sell.token,
             buy: trade.buy.token,
@@ -309,12 +466,43 @@ fn score_by_token_pair(solution: &Solution, auction: &Auction) -> Result<ScoreBy
 
         *scores.entry(token_pair).or_default() += Score(score);
     }
-    Ok(scores)
+    Ok((scores, exempt_score))
 }
 
 pub struct Config {
     pub max_winners: usize,
+    /// How many winners may share the same submission address. `None` means
+    /// only `max_winners` applies.
+    pub max_winners_per_submission_address: Option<NonZeroUsize>,
     pub weth: WrappedNativeToken,
+    /// Whether solutions trading the native token overlap with solutions
+    /// trading its wrapped counterpart.
+    pub treat_native_as_weth: bool,
+    /// Scores within this distance of each other are considered tied.
+    pub score_tie_epsilon: eth::U256,
+    /// What solutions get ranked by before winners get picked.
+    pub objective: Objective,
+    /// How winner slots get filled after the best solution.
+    pub merging: Merging,
+    /// Fairness threshold (in wei) every order execution must be within of the
+    /// best execution of the order across all solutions. Drivers with a lower
+    /// threshold keep theirs. Without it only the baseline scores are checked.
+    pub fairness_threshold_floor: Option<eth::U256>,
+}
+
+impl Config {
+    fn selection_mode(&self) -> SelectionMode {
+        match self.merging {
+            Merging::Greedy => SelectionMode::Combinatorial,
+            Merging::ExtraWinners { max_extra_winners } => {
+                SelectionMode::CombinatorialWithExtraWinners { max_extra_winners }
+            }
+        }
+    }
+
+    fn native_token_normalization(&self) -> Option<WrappedNativeToken> {
+        self.treat_native_as_weth.then_some(self.weth)
+    }
 }
 
 /// Relevant data from `domain::Auction` but with data structures
@@ -325,6 +513,8 @@ struct Auction<'a> {
     fee_policies: HashMap<OrderUid, &'a Vec<fee::Policy>>,
     surplus_capturing_jit_order_owners: HashSet<eth::Address>,
     native_prices: &'a Prices,
+    /// Orders that opted out of the fairness check.
+    fairness_exempt: HashSet<OrderUid>,
 }
 
 impl Auction<'_> {
@@ -352,12 +542,18 @@ impl<'a> From<&'a domain::Auction> for Auction<'a> {
                 .iter()
                 .cloned()
                 .collect(),
+            fairness_exempt: original
+                .orders
+                .iter()
+                .filter(|o| o.fairness_exempt)
+                .map(|o| o.uid)
+                .collect(),
         }
     }
 }
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
-struct DirectedTokenPair {
+pub(super) struct DirectedTokenPair {
     sell: eth::TokenAddress,
     buy: eth::TokenAddress,
 }
@@ -387,20 +583,29 @@ mod tests {
                 Order,
                 OrderUid,
                 auction::{
+                    ContentHash,
                     Price,
                     order::{self, AppDataHash},
                 },
                 competition::{
                     Participant,
+                    Ranked,
                     Score,
                     Solution,
                     TradedOrder,
                     Unranked,
-                    winner_selection::Arbitrator,
+                    winner_selection::{
+                        Arbitrator,
+                        Merging,
+                        Objective,
+                        Ranking,
+                        SelectionMode,
+                        select_winners,
+                    },
                 },
                 eth::{self, TokenAddress},
             },
-            infra::Driver,
+            infra::solvers::fake::offline_driver,
         },
         ethcontract::H160,
         hex_literal::hex,
@@ -411,6 +616,8 @@ mod tests {
         std::{
             collections::HashMap,
             hash::{DefaultHasher, Hash, Hasher},
+            num::NonZeroUsize,
+            sync::Arc,
         },
     };
 
@@ -961,6 +1168,62 @@ mod tests {
         TestCase::from_json(case).validate().await;
     }
 
+    #[tokio::test]
+    async fn reference_scores_only_get_capped_for_tied_winners() {
+        let amount = eth::U256::from(to_e15(1_000));
+        let order = |uid, sell, buy| {
+            create_order(
+                uid,
+                address(sell),
+                amount,
+                address(buy),
+                amount,
+                order::Side::Sell,
+            )
+        };
+        let order_x = order(1, 0, 1);
+        let order_y = order(2, 2, 3);
+        let trade = |order: &Order| (order.uid, create_trade(order, amount, amount));
+        // A trades the token pairs of B and C, it outscores each of them but
+        // not both of them combined
+        let a = create_solution(1, address(10), vec![trade(&order_x), trade(&order_y)], None).await;
+        let b = create_solution(2, address(11), vec![trade(&order_x)], None).await;
+        let c = create_solution(3, address(12), vec![trade(&order_y)], None).await;
+        let score = |value: u64| Score(eth::Ether(value.into()));
+        let ranked = |participant: &Participant<Unranked>, value, rank| {
+            let mut participant = participant.clone();
+            participant.set_computed_score(score(value));
+            participant.rank(rank)
+        };
+        let reference_score = |arbitrator: super::Config, b_score| {
+            let ranking = Ranking {
+                filtered_out: vec![],
+                ranked: vec![
+                    ranked(&a, 10, Ranked::Winner),
+                    ranked(&b, b_score, Ranked::NonWinner),
+                    ranked(&c, 6, Ranked::NonWinner),
+                ],
+                observe_only: vec![],
+            };
+            arbitrator
+                .compute_reference_scores(&ranking)
+                .get(&eth::Address(address(10)))
+                .copied()
+        };
+        let tied = || super::Config {
+            score_tie_epsilon: 1.into(),
+            ..create_test_arbitrator()
+        };
+
+        assert_eq!(
+            reference_score(create_test_arbitrator(), 6),
+            Some(score(12))
+        );
+        assert_eq!(reference_score(tied(), 6), Some(score(12)));
+        // B's score ties the winner's so its reward must not get negative
+        assert_eq!(reference_score(tied(), 11), Some(score(10)));
+    }
+
     #[tokio::test]
     async fn staging_mainnet_auction_12825008() {
         // https://solver-instances.s3.eu-central-1.amazonaws.com/staging/mainnet/autopilot/12825008.json
@@ -1022,6 +1285,459 @@ mod tests {
         TestCase::from_json(case).validate().await;
     }
 
+    #[tokio::test]
+    // Winner selection only depends on the sorted solutions and the config
+    async fn select_winners_is_pure() {
+        let token_a = address(0);
+        let token_b = address(1);
+        let token_c = address(2);
+        let token_d = address(3);
+        let amount = eth::U256::from(to_e15(1_000));
+        let order_1 = create_order(1, token_a, amount, token_b, amount, order::Side::Sell);
+        let order_2 = create_order(2, token_a, amount, token_b, amount, order::Side::Sell);
+        let order_3 = create_order(3, token_c, amount, token_d, amount, order::Side::Sell);
+
+        let mut solutions = vec![];
+        for (id, order) in [&order_1, &order_2, &order_3].into_iter().enumerate() {
+            let trade = create_trade(order, amount, amount);
+            solutions.push(
+                create_solution(
+                    id as u64,
+                    address(10 + id as u64),
+                    vec![(order.uid, trade)],
+                    None,
+                )
+                .await,
+            );
+        }
+        let weth = Some(create_test_arbitrator().weth);
+
+        // the second solution trades the same directed token pair as the first one
+        assert_eq!(
+            select_winners(&solutions, 10, None, weth, SelectionMode::Combinatorial),
+            vec![true, false, true]
+        );
+        // calling it again on the same input yields the same result
+        assert_eq!(
+            select_winners(&solutions, 10, None, weth, SelectionMode::Combinatorial),
+            vec![true, false, true]
+        );
+        assert_eq!(
+            select_winners(&solutions, 1, None, weth, SelectionMode::Combinatorial),
+            vec![true, false, false]
+        );
+        assert_eq!(
+            select_winners(&solutions, 10, None, weth, SelectionMode::SingleWinner),
+            vec![true, false, false]
+        );
+        assert_eq!(
+            select_winners(&solutions, 0, None, weth, SelectionMode::SingleWinner),
+            vec![false, false, false]
+        );
+    }
+
+    #[tokio::test]
+    async fn caps_winners_per_submission_address() {
+        let amount = eth::U256::from(to_e15(1_000));
+        let order_1 = create_order(1, address(0), amount, address(1), amount, order::Side::Sell);
+        let order_2 = create_order(2, address(2), amount, address(3), amount, order::Side::Sell);
+        let order_3 = create_order(3, address(4), amount, address(5), amount, order::Side::Sell);
+
+        // the first two solutions are submitted by the same address (e.g.
+        // different drivers sharing one on-chain solver)
+        let mut solutions = vec![];
+        for (id, (order, solver)) in [(&order_1, 10), (&order_2, 10), (&order_3, 11)]
+            .into_iter()
+            .enumerate()
+        {
+            let trade = create_trade(order, amount, amount);
+            solutions.push(
+                create_solution(id as u64, address(solver), vec![(order.uid, trade)], None).await,
+            );
+        }
+        let weth = Some(create_test_arbitrator().weth);
+
+        assert_eq!(
+            select_winners(&solutions, 10, None, weth, SelectionMode::Combinatorial),
+            vec![true, true, true]
+        );
+        assert_eq!(
+            select_winners(
+                &solutions,
+                10,
+                NonZeroUsize::new(1),
+                weth,
+                SelectionMode::Combinatorial
+            ),
+            vec![true, false, true]
+        );
+        assert_eq!(
+            select_winners(
+                &solutions,
+                2,
+                NonZeroUsize::new(2),
+                weth,
+                SelectionMode::Combinatorial
+            ),
+            vec![true, true, false]
+        );
+    }
+
+    #[tokio::test]
+    async fn extra_winners_settle_remaining_orders() {
+        let amount = eth::U256::from(to_e15(1_000));
+        let order_1 = create_order(1, address(0), amount, address(1), amount, order::Side::Sell);
+        let order_2 = create_order(2, address(2), amount, address(3), amount, order::Side::Sell);
+        let order_3 = create_order(3, address(4), amount, address(5), amount, order::Side::Sell);
+
+        // pairwise disjoint solutions
+        let mut solutions = vec![];
+        for (id, order) in [&order_1, &order_2, &order_3].into_iter().enumerate() {
+            let trade = create_trade(order, amount, amount);
+            solutions.push(
+                create_solution(
+                    id as u64,
+                    address(10 + id as u64),
+                    vec![(order.uid, trade)],
+                    None,
+                )
+                .await,
+            );
+        }
+        let weth = Some(create_test_arbitrator().weth);
+
+        // greedy merging leaves the other orders unsettled
+        assert_eq!(
+            select_winners(&solutions, 1, None, weth, SelectionMode::Combinatorial),
+            vec![true, false, false]
+        );
+        assert_eq!(
+            select_winners(
+                &solutions,
+                1,
+                None,
+                weth,
+                SelectionMode::CombinatorialWithExtraWinners {
+                    max_extra_winners: 1
+                }
+            ),
+            vec![true, true, false]
+        );
+        assert_eq!(
+            select_winners(
+                &solutions,
+                1,
+                None,
+                weth,
+                SelectionMode::CombinatorialWithExtraWinners {
+                    max_extra_winners: 5
+                }
+            ),
+            vec![true, true, true]
+        );
+    }
+
+    #[tokio::test]
+    async fn finds_solutions_blocked_by_token_conflicts() {
+        let amount = eth::U256::from(to_e15(1_000));
+        let order = |uid, sell, buy| {
+            create_order(
+                uid,
+                address(sell),
+                amount,
+                address(buy),
+                amount,
+                order::Side::Sell,
+            )
+        };
+        let order_1 = order(1, 0, 1);
+        let order_2 = order(2, 0, 1);
+        let order_3 = order(3, 2, 3);
+        let order_4 = order(4, 4, 5);
+        let order_5 = order(5, 6, 7);
+        let auction = create_auction(
+            vec![
+                order_1.clone(),
+                order_2.clone(),
+                order_3.clone(),
+                order_4.clone(),
+                order_5.clone(),
+            ],
+            None,
+        );
+
+        let trade = |order: &Order, executed_buy: u128| {
+            (
+                order.uid,
+                create_trade(order, amount, eth::U256::from(to_e15(executed_buy))),
+            )
+        };
+        let solution = |id, solver, trades| create_solution(id, address(solver), trades, None);
+        // both solutions trade the same directed token pair, the first one
+        // fills more orders but the second one has the higher score
+        let solutions = vec![
+            solution(
+                1,
+                10,
+                vec![
+                    trade(&order_1, 1_010),
+                    trade(&order_3, 1_010),
+                    trade(&order_5, 1_010),
+                ],
+            )
+            .await,
+            solution(2, 11, vec![trade(&order_2, 1_100), trade(&order_4, 1_100)]).await,
+        ];
+        let blocked = |arbitrator: super::Config, solutions: Vec<Participant<Unranked>>| {
+            let ranking = arbitrator.arbitrate(solutions, &auction);
+            ranking
+                .blocked_by_token_conflicts()
+                .map(|p| p.solution().id())
+                .collect::<Vec<_>>()
+        };
+        let coverage = || super::Config {
+            objective: Objective::OrderCoverage,
+            ..create_test_arbitrator()
+        };
+
+        assert_eq!(blocked(coverage(), solutions.clone()), [2]);
+        assert_eq!(blocked(create_test_arbitrator(), solutions.clone()), [1]);
+        // all winner slots are taken anyway
+        let arbitrator = super::Config {
+            max_winners: 1,
+            ..coverage()
+        };
+        assert_eq!(blocked(arbitrator, solutions.clone()), []);
+        // the submission address already has as many winners as it may have
+        let mut solutions = solutions;
+        solutions[1] = solution(2, 10, vec![trade(&order_2, 1_100), trade(&order_4, 1_100)]).await;
+        let arbitrator = super::Config {
+            max_winners_per_submission_address: NonZeroUsize::new(1),
+            ..coverage()
+        };
+        assert_eq!(blocked(arbitrator, solutions), []);
+    }
+
+    #[tokio::test]
+    async fn native_token_can_be_distinct_from_weth() {
+        let weth_address = H160::from_slice(&hex!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"));
+        let weth = WrappedNativeToken::from(weth_address);
+        let amount = eth::U256::from(to_e15(1_000));
+        let token_a = address(0);
+        // one order buys native ETH, the other one WETH
+        let order_1 = create_order(
+            1,
+            token_a,
+            amount,
+            eth::NATIVE_TOKEN.0,
+            amount,
+            order::Side::Sell,
+        );
+        let order_2 = create_order(2, token_a, amount, weth_address, amount, order::Side::Sell);
+
+        let mut solutions = vec![];
+        for (id, order) in [&order_1, &order_2].into_iter().enumerate() {
+            let trade = create_trade(order, amount, amount);
+            solutions.push(
+                create_solution(
+                    id as u64,
+                    address(10 + id as u64),
+                    vec![(order.uid, trade)],
+                    None,
+                )
+                .await,
+            );
+        }
+
+        // normalized to WETH both solutions trade the same directed token pair
+        assert_eq!(
+            select_winners(
+                &solutions,
+                10,
+                None,
+                Some(weth),
+                SelectionMode::Combinatorial
+            ),
+            vec![true, false]
+        );
+        assert_eq!(
+            select_winners(&solutions, 10, None, None, SelectionMode::Combinatorial),
+            vec![true, true]
+        );
+    }
+
+    #[tokio::test]
+    // Fairness is checked against the full auction even if some driver only
+    // received a truncated view of it
+    async fn fairness_uses_full_auction_when_truncated() {
+        let token_a = address(0);
+        let token_b = address(1);
+        let token_c = address(2);
+        let token_d = address(3);
+        let amount = eth::U256::from(to_e15(1_000));
+        let mut old_order = create_order(1, token_a, amount, token_b, amount, order::Side::Sell);
+        old_order.created = 1;
+        let mut new_order = create_order(2, token_c, amount, token_d, amount, order::Side::Sell);
+        new_order.created = 2;
+        let auction = create_auction(vec![old_order.clone(), new_order.clone()], None);
+
+        let truncated = auction.truncated(1).unwrap();
+        assert_eq!(*truncated.orders, vec![new_order.clone()]);
+        assert_eq!(auction.orders.len(), 2);
+        assert!(auction.truncated(2).is_none());
+
+        // settles the old order worse than the baseline solution below
+        let batch = create_solution(
+            1,
+            address(10),
+            vec![
+                (
+                    old_order.uid,
+                    create_trade(&old_order, amount, eth::U256::from(to_e15(1_100))),
+                ),
+                (
+                    new_order.uid,
+                    create_trade(&new_order, amount, eth::U256::from(to_e15(1_100))),
+                ),
+            ],
+            None,
+        )
+        .await;
+        let baseline = create_solution(
+            2,
+            address(11),
+            vec![(
+                old_order.uid,
+                create_trade(&old_order, amount, eth::U256::from(to_e15(1_200))),
+            )],
+            None,
+        )
+        .await;
+
+        let ranking =
+            create_test_arbitrator().arbitrate(vec![batch.clone(), baseline.clone()], &auction);
+        assert!(
+            ranking
+                .all()
+                .any(|p| p.solution().id() == batch.solution().id() && p.filtered_out())
+        );
+
+        // against the truncated view the unfair batch would have been kept
+        let ranking = create_test_arbitrator().arbitrate(vec![batch, baseline], &truncated);
+        assert!(ranking.all().all(|p| !p.filtered_out()));
+    }
+
+    #[tokio::test]
+    async fn fairness_exempt_orders_dont_invalidate_solutions() {
+        let token_a = address(0);
+        let token_b = address(1);
+        let token_c = address(2);
+        let token_d = address(3);
+        let amount = eth::U256::from(to_e15(1_000));
+        let mut order_1 = create_order(1, token_a, amount, token_b, amount, order::Side::Sell);
+        let order_2 = create_order(2, token_c, amount, token_d, amount, order::Side::Sell);
+
+        // settles order 1 worse than the baseline solution below
+        let batch = create_solution(
+            1,
+            address(10),
+            vec![
+                (
+                    order_1.uid,
+                    create_trade(&order_1, amount, eth::U256::from(to_e15(1_100))),
+                ),
+                (
+                    order_2.uid,
+                    create_trade(&order_2, amount, eth::U256::from(to_e15(1_100))),
+                ),
+            ],
+            None,
+        )
+        .await;
+        let baseline = create_solution(
+            2,
+            address(11),
+            vec![(
+                order_1.uid,
+                create_trade(&order_1, amount, eth::U256::from(to_e15(1_200))),
+            )],
+            None,
+        )
+        .await;
+        let filtered_out = |orders: Vec<Order>| {
+            create_test_arbitrator()
+                .arbitrate(
+                    vec![batch.clone(), baseline.clone()],
+                    &create_auction(orders, None),
+                )
+                .all()
+                .filter(|p| p.filtered_out())
+                .map(|p| p.solution().id())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(filtered_out(vec![order_1.clone(), order_2.clone()]), [1]);
+        order_1.fairness_exempt = true;
+        assert_eq!(filtered_out(vec![order_1, order_2]), Vec::<u64>::new());
+    }
+
+    #[tokio::test]
+    // The fairness threshold floor also discards solutions the baseline scores
+    // can't catch, e.g. ones trading a single token pair
+    async fn fairness_threshold_floor_applies() {
+        let token_a = address(0);
+        let token_b = address(1);
+        let amount = eth::U256::from(to_e15(1_000));
+        let order_1 = create_order(1, token_a, amount, token_b, amount, order::Side::Sell);
+        let order_2 = create_order(2, token_a, amount, token_b, amount, order::Side::Sell);
+        let auction = create_auction(vec![order_1.clone(), order_2.clone()], None);
+
+        // scores higher in total but settles order 1 worse than the solution
+        // below (by 100 wei worth of the buy token)
+        let batch = create_solution(
+            1,
+            address(10),
+            vec![
+                (
+                    order_1.uid,
+                    create_trade(&order_1, amount, eth::U256::from(to_e15(1_100))),
+                ),
+                (
+                    order_2.uid,
+                    create_trade(&order_2, amount, eth::U256::from(to_e15(1_300))),
+                ),
+            ],
+            None,
+        )
+        .await;
+        let single = create_solution(
+            2,
+            address(11),
+            vec![(
+                order_1.uid,
+                create_trade(&order_1, amount, eth::U256::from(to_e15(1_200))),
+            )],
+            None,
+        )
+        .await;
+        let filtered_out = |floor: Option<u64>| {
+            let arbitrator = super::Config {
+                fairness_threshold_floor: floor.map(Into::into),
+                ..create_test_arbitrator()
+            };
+            arbitrator
+                .arbitrate(vec![batch.clone(), single.clone()], &auction)
+                .all()
+                .filter(|p| p.filtered_out())
+                .map(|p| p.solution().id())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(filtered_out(None), Vec::<u64>::new());
+        assert_eq!(filtered_out(Some(200)), Vec::<u64>::new());
+        assert_eq!(filtered_out(Some(50)), [1]);
+    }
+
     #[serde_as]
     #[derive(Deserialize, Debug)]
     struct TestCase {
@@ -1199,7 +1915,13 @@ mod tests {
     fn create_test_arbitrator() -> super::Config {
         super::Config {
             max_winners: 10,
+            max_winners_per_submission_address: None,
             weth: H160::from_slice(&hex!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2")).into(),
+            treat_native_as_weth: true,
+            score_tie_epsilon: eth::U256::zero(),
+            objective: Objective::Score,
+            merging: Merging::Greedy,
+            fairness_threshold_floor: None,
         }
     }
 
@@ -1242,6 +1964,7 @@ mod tests {
             valid_to: Default::default(),
             signature: order::Signature::PreSign,
             quote: None,
+            fairness_exempt: false,
         }
     }
 
@@ -1275,9 +1998,12 @@ mod tests {
         Auction {
             id: 0,
             block: 0,
-            orders,
-            prices,
+            content_hash: ContentHash::new(&orders, &prices, &[]),
+            orders: Arc::new(orders),
+            prices: Arc::new(prices),
             surplus_capturing_jit_order_owners: vec![],
+            quote_only: false,
+            prioritized_orders: Default::default(),
         }
     }
 
@@ -1322,19 +2048,12 @@ mod tests {
             Score(eth::Ether(eth::U256::zero())),
             trade_order_map,
             prices,
+            None,
         );
 
-        let driver = Driver::try_new(
-            url::Url::parse("http://localhost").unwrap(),
-            solver_address.to_string(),
-            None,
-            crate::arguments::Account::Address(solver_address.0),
-            false,
-        )
-        .await
-        .unwrap();
+        let driver = offline_driver(&solver_address.to_string(), solver_address.0).await;
 
-        Participant::new(solution, std::sync::Arc::new(driver))
+        Participant::new(solution, Arc::new(driver))
     }
 
     fn amount(value: u128) -> String {
@@ -7,16 +7,17 @@
 //! EBBO (ethereum best bid offer) - that is an order should get executed at
 //! least as good as possible using very popular onchain liquidity sources. Each
 //! solver can opt-in to have their solutions invalidated if the estimated total
-//! EBBO violations would exceed a configurable threshold.
+//! EBBO violations would exceed a configurable threshold. A global threshold
+//! floor can enforce this for all solvers by capping their thresholds.
 //!
 //! Reference Score:
 //! The reference score is simply the second highest reported score of all
 //! solutions. If there is only 1 solution the reference score is 0.
 use {
-    super::{Arbitrator, PartitionedSolutions, Ranking},
+    super::{Arbitrator, Objective, PartitionedSolutions, Ranking},
     crate::domain::{
         Auction,
-        competition::{Participant, Ranked, Score, TradedOrder, Unranked},
+        competition::{Participant, Ranked, Score, TradedOrder, Unranked, ranking},
         eth,
     },
     ethcontract::U256,
@@ -24,7 +25,17 @@ use {
     std::collections::HashMap,
 };
 
-pub struct Config;
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// Scores within this distance of each other are considered tied.
+    pub score_tie_epsilon: U256,
+    /// What solutions get ranked by before the winner gets picked.
+    pub objective: Objective,
+    /// Fairness threshold (in wei) that applies to every solution, even if
+    /// its driver doesn't have a threshold configured. Drivers with a lower
+    /// (stricter) threshold keep theirs.
+    pub fairness_threshold_floor: Option<U256>,
+}
 
 impl Arbitrator for Config {
     fn partition_unfair_solutions(
@@ -33,15 +44,22 @@ impl Arbitrator for Config {
         auction: &Auction,
     ) -> PartitionedSolutions {
         // sort by score descending
-        participants.sort_unstable_by_key(|participant| {
-            std::cmp::Reverse(participant.solution().score().get().0)
-        });
+        ranking::sort_with_ties(
+            &mut participants,
+            |participant| participant.solution().score().get().0,
+            self.score_tie_epsilon,
+        );
         let (fair, unfair) =
             participants
                 .iter()
                 .enumerate()
                 .partition_map(|(index, participant)| {
-                    if is_solution_fair(participant, &participants[index..], auction) {
+                    if is_solution_fair(
+                        participant,
+                        &participants[index..],
+                        auction,
+                        self.fairness_threshold_floor,
+                    ) {
                         Either::Left(participant.clone())
                     } else {
                         tracing::warn!(
@@ -76,53 +94,55 @@ impl Arbitrator for Config {
             .collect()
     }
 
+    fn score_tie_epsilon(&self) -> U256 {
+        self.score_tie_epsilon
+    }
+
+    fn objective(&self) -> Objective {
+        self.objective
+    }
+
     fn compute_reference_scores(&self, ranking: &Ranking) -> HashMap<eth::Address, Score> {
         // this will hold at most 1 score but the interface needs to support multiple
         // scores to fit the interface
         let mut reference_scores = HashMap::default();
-        if let Some(winner) = ranking.ranked.first() {
+        if let Some(winner) = ranking.ranked.first().filter(|first| first.is_winner()) {
+            // The reference is the best score among the other solutions and not
+            // the next one in the ranking since objectives other than the score
+            // can rank higher scores lower. Such scores (or ones tied with the
+            // winner's) can exceed the winner's but the reward must not get
+            // negative.
             let runner_up = ranking
                 .ranked
-                .get(1)
+                .iter()
+                .skip(1)
                 .map(|s| s.solution().score())
-                .unwrap_or_default();
-            reference_scores.insert(winner.driver().submission_address, runner_up);
+                .max()
+                .unwrap_or_default()
+                .min(winner.solution().score());
+            // keyed by the address that actually produced the winning solution
+            reference_scores.insert(winner.solver_address(), runner_up);
         }
         reference_scores
     }
 }
 
-/// Returns true if solution is fair to other solutions.
-fn is_solution_fair(
+/// Returns true if solution is fair to other solutions. The threshold of an
+/// order is the driver's threshold but at most `threshold_floor`.
+/// A threshold is how much worse than the best execution an order may get
+/// executed, so a bigger threshold tolerates more unfairness. Taking the
+/// maximum of both would let the floor loosen drivers that are configured
+/// stricter than it, which is why the smaller one applies.
+pub(super) fn is_solution_fair(
     participant: &Participant<Unranked>,
     others: &[Participant<Unranked>],
     auction: &Auction,
+    threshold_floor: Option<U256>,
 ) -> bool {
-    let Some(fairness_threshold) = participant.driver().fairness_threshold else {
+    let fairness_threshold = participant.driver().fairness_threshold;
+    if fairness_threshold.is_none() && threshold_floor.is_none() {
         return true;
-    };
-
-    // Returns the surplus difference in the buy token if `left`
-    // is better for the trader than `right`, or 0 otherwise.
-    // This takes differently partial fills into account.
-    let improvement_in_buy = |left: &TradedOrder, right: &TradedOrder| {
-        // If `left.sell / left.buy < right.sell / right.buy`, left is "better" as the
-        // trader either sells less or gets more. This can be reformulated as
-        // `right.sell * left.buy > left.sell * right.buy`.
-        let right_sell_left_buy = right.executed_sell.0.full_mul(left.executed_buy.0);
-        let left_sell_right_buy = left.executed_sell.0.full_mul(right.executed_buy.0);
-        let improvement = right_sell_left_buy
-            .checked_sub(left_sell_right_buy)
-            .unwrap_or_default();
-
-        // The difference divided by the original sell amount is the improvement in buy
-        // token. Casting to U256 is safe because the difference is smaller than the
-        // original product, which if re-divided by right.sell must fit in U256.
-        improvement
-            .checked_div(right.executed_sell.0.into())
-            .map(|v| U256::try_from(v).expect("improvement in buy fits in U256"))
-            .unwrap_or_default()
-    };
+    }
 
     // Record best execution per order
     let mut best_executions = HashMap::new();
@@ -140,7 +160,7 @@ fn is_solution_fair(
     }
 
     // Check if the solution contains an order whose execution in the
-    // solution is more than `fairness_threshold` worse than the
+    // solution is more than the order's fairness threshold worse than the
     // order's best execution across all solutions
     let unfair = participant
         .solution()
@@ -166,14 +186,565 @@ fn is_solution_fair(
                 tracing::debug!(?uid, "cannot ensure fairness, order not found in auction");
                 return false;
             };
-            let Some(buy_price) = auction.prices.get(&order.buy.token) else {
+            if order.fairness_exempt {
+                tracing::info!(
+                    ?uid,
+                    ?improvement,
+                    solver = participant.driver().name,
+                    "order is exempt from fairness check"
+                );
+                return false;
+            }
+            let Some(improvement) = auction.price_in_eth(order.buy.token, improvement) else {
                 tracing::warn!(
                     ?order,
                     "cannot ensure fairness, buy price not found in auction"
                 );
                 return false;
             };
-            buy_price.in_eth(improvement.into()) > fairness_threshold
+            // The order's value is what the trader expects to receive at least.
+            let order_value = auction
+                .price_in_eth(order.buy.token, order.buy.amount.0)
+                .map(eth::Ether);
+            let driver_threshold =
+                fairness_threshold.map(|threshold| threshold.for_order(order_value).0);
+            let threshold = match threshold_floor {
+                Some(floor) => driver_threshold.map_or(floor, |threshold| threshold.min(floor)),
+                None => driver_threshold.unwrap_or_default(),
+            };
+            improvement > threshold
         });
     !unfair
 }
+
+/// Returns the surplus difference in the buy token if `left` is better for the
+/// trader than `right`, or 0 otherwise. This takes differently partial fills
+/// into account. Executions that didn't sell anything can't be compared and
+/// never count as an improvement.
+fn improvement_in_buy(left: &TradedOrder, right: &TradedOrder) -> U256 {
+    if left.executed_sell.0.is_zero() || right.executed_sell.0.is_zero() {
+        return U256::zero();
+    }
+
+    // If `left.sell / left.buy < right.sell / right.buy`, left is "better" as the
+    // trader either sells less or gets more. This can be reformulated as
+    // `right.sell * left.buy > left.sell * right.buy`.
+    let right_sell_left_buy = right.executed_sell.0.full_mul(left.executed_buy.0);
+    let left_sell_right_buy = left.executed_sell.0.full_mul(right.executed_buy.0);
+    let improvement = right_sell_left_buy
+        .checked_sub(left_sell_right_buy)
+        .unwrap_or_default();
+
+    // The difference divided by the original sell amount is the improvement in buy
+    // token. Casting to U256 is safe because the difference is smaller than the
+    // original product, which if re-divided by right.sell must fit in U256.
+    improvement
+        .checked_div(right.executed_sell.0.into())
+        .map(|v| U256::try_from(v).expect("improvement in buy fits in U256"))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            boundary,
+            domain::{
+                self,
+                Order,
+                auction::{ContentHash, Price, order::Side},
+                competition::{FairnessThreshold, Solution},
+                eth::TokenAddress,
+            },
+            infra::solvers::fake::offline_driver,
+        },
+        std::sync::Arc,
+    };
+
+    fn execution(executed_sell: u64, executed_buy: u64) -> TradedOrder {
+        TradedOrder {
+            side: Side::Sell,
+            sell: eth::Asset {
+                token: TokenAddress(eth::H160([1; 20])),
+                amount: U256::from(100).into(),
+            },
+            buy: eth::Asset {
+                token: TokenAddress(eth::H160([2; 20])),
+                amount: U256::from(1).into(),
+            },
+            executed_sell: U256::from(executed_sell).into(),
+            executed_buy: U256::from(executed_buy).into(),
+        }
+    }
+
+    #[test]
+    fn improvement_with_zero_sell() {
+        let regular = execution(100, 100);
+        assert_eq!(improvement_in_buy(&execution(0, 100), &regular), 0.into());
+        assert_eq!(improvement_in_buy(&regular, &execution(0, 100)), 0.into());
+        assert_eq!(
+            improvement_in_buy(&execution(0, 0), &execution(0, 0)),
+            0.into()
+        );
+    }
+
+    #[test]
+    fn improvement_with_zero_buy() {
+        let regular = execution(100, 100);
+        assert_eq!(improvement_in_buy(&execution(100, 0), &regular), 0.into());
+        // Anything beats an execution that didn't pay out anything.
+        assert_eq!(improvement_in_buy(&regular, &execution(100, 0)), 100.into());
+    }
+
+    #[test]
+    fn improvement_with_one_wei_sell() {
+        let regular = execution(100, 100);
+        assert_eq!(improvement_in_buy(&execution(1, 1), &regular), 0.into());
+        assert_eq!(improvement_in_buy(&execution(1, 2), &regular), 1.into());
+        assert_eq!(improvement_in_buy(&regular, &execution(1, 1)), 0.into());
+        assert_eq!(
+            improvement_in_buy(&execution(100, 101), &execution(1, 1)),
+            1.into()
+        );
+    }
+
+    #[test]
+    fn improvement_with_one_wei_buy() {
+        let regular = execution(100, 100);
+        assert_eq!(improvement_in_buy(&execution(100, 1), &regular), 0.into());
+        assert_eq!(improvement_in_buy(&regular, &execution(100, 1)), 99.into());
+        assert_eq!(
+            improvement_in_buy(&execution(100, 1), &execution(100, 0)),
+            1.into()
+        );
+    }
+
+    async fn participant(
+        id: u64,
+        score: u64,
+        order: &Order,
+        executed_buy: u64,
+    ) -> Participant<Unranked> {
+        let threshold = FairnessThreshold {
+            flat: eth::Ether(U256::zero()),
+            bps_of_order_value: None,
+        };
+        participant_with_threshold(id, score, order, executed_buy, Some(threshold)).await
+    }
+
+    async fn participant_with_threshold(
+        id: u64,
+        score: u64,
+        order: &Order,
+        executed_buy: u64,
+        threshold: Option<FairnessThreshold>,
+    ) -> Participant<Unranked> {
+        let solver = eth::H160::from_low_u64_be(id);
+        let solution = Solution::new(
+            id,
+            solver.into(),
+            Score::try_new(eth::Ether(U256::from(score))).unwrap(),
+            HashMap::from([(order.uid, execution(100, executed_buy))]),
+            Default::default(),
+            None,
+        );
+        let mut driver = offline_driver(&id.to_string(), solver).await;
+        driver.fairness_threshold = threshold;
+        Participant::new(solution, Arc::new(driver))
+    }
+
+    #[tokio::test]
+    async fn exempt_orders_dont_invalidate_solutions() {
+        let mut order = boundary::order::to_domain(Default::default(), vec![], None);
+        order.sell = execution(0, 0).sell;
+        order.buy = execution(0, 0).buy;
+        let prices = HashMap::from([(
+            order.buy.token,
+            Price::try_new(eth::Ether(U256::exp10(18))).unwrap(),
+        )]);
+        let auction = |order: &Order| Auction {
+            id: 0,
+            block: 0,
+            content_hash: ContentHash::new(std::slice::from_ref(order), &prices, &[]),
+            orders: Arc::new(vec![order.clone()]),
+            prices: Arc::new(prices.clone()),
+            surplus_capturing_jit_order_owners: vec![],
+            quote_only: false,
+            prioritized_orders: Default::default(),
+        };
+        // the highest scoring solution executes the order worse than the other
+        let participants = vec![
+            participant(1, 10, &order, 90).await,
+            participant(2, 5, &order, 100).await,
+        ];
+
+        let partitioned =
+            Config::default().partition_unfair_solutions(participants.clone(), &auction(&order));
+        assert_eq!(partitioned.kept.len(), 1);
+        assert_eq!(partitioned.discarded[0].solution().id(), 1);
+
+        order.fairness_exempt = true;
+        let partitioned =
+            Config::default().partition_unfair_solutions(participants, &auction(&order));
+        assert_eq!(partitioned.kept.len(), 2);
+        assert!(partitioned.discarded.is_empty());
+    }
+
+    /// Returns how many solutions are discarded when the highest scoring
+    /// solution executes an order worth `order_value` wei `improvement` wei
+    /// worse than the other solution.
+    async fn discarded(order_value: U256, improvement: u64, threshold: FairnessThreshold) -> usize {
+        discarded_with_floor(order_value, improvement, Some(threshold), None).await
+    }
+
+    /// Like [`discarded`] but with an optional driver threshold and a global
+    /// threshold floor.
+    async fn discarded_with_floor(
+        order_value: U256,
+        improvement: u64,
+        threshold: Option<FairnessThreshold>,
+        floor: Option<U256>,
+    ) -> usize {
+        let mut order = boundary::order::to_domain(Default::default(), vec![], None);
+        order.sell = execution(0, 0).sell;
+        order.buy = execution(0, 0).buy;
+        order.buy.amount = order_value.into();
+        // 1 buy token is worth 1 ETH
+        let prices = HashMap::from([(
+            order.buy.token,
+            Price::try_new(eth::Ether(U256::exp10(18))).unwrap(),
+        )]);
+        let auction = Auction {
+            id: 0,
+            block: 0,
+            content_hash: ContentHash::new(std::slice::from_ref(&order), &prices, &[]),
+            orders: Arc::new(vec![order.clone()]),
+            prices: Arc::new(prices),
+            surplus_capturing_jit_order_owners: vec![],
+            quote_only: false,
+            prioritized_orders: Default::default(),
+        };
+        let participants = vec![
+            participant_with_threshold(1, 10, &order, 1000, threshold).await,
+            participant_with_threshold(2, 5, &order, 1000 + improvement, threshold).await,
+        ];
+        Config {
+            fairness_threshold_floor: floor,
+            ..Default::default()
+        }
+        .partition_unfair_solutions(participants, &auction)
+        .discarded
+        .len()
+    }
+
+    #[tokio::test]
+    async fn relative_threshold_protects_small_orders() {
+        let flat = FairnessThreshold {
+            flat: eth::Ether(100.into()),
+            bps_of_order_value: None,
+        };
+        let relative = FairnessThreshold {
+            bps_of_order_value: Some(100),
+            ..flat
+        };
+
+        // 1% of the tiny order is 10 wei which is tighter than the flat 100 wei
+        assert_eq!(discarded(1000.into(), 20, flat).await, 0);
+        assert_eq!(discarded(1000.into(), 20, relative).await, 1);
+        assert_eq!(discarded(1000.into(), 10, relative).await, 0);
+    }
+
+    #[tokio::test]
+    async fn flat_threshold_caps_huge_orders() {
+        let relative = FairnessThreshold {
+            flat: eth::Ether(100.into()),
+            bps_of_order_value: Some(100),
+        };
+
+        // 1% of the huge order is way more than the flat 100 wei
+        assert_eq!(discarded(U256::exp10(24), 200, relative).await, 1);
+        assert_eq!(discarded(U256::exp10(24), 100, relative).await, 0);
+    }
+
+    #[tokio::test]
+    async fn floor_applies_to_drivers_without_threshold() {
+        let order_value = U256::exp10(18);
+
+        // without a floor drivers without a threshold are never checked
+        assert_eq!(discarded_with_floor(order_value, 1000, None, None).await, 0);
+        assert_eq!(
+            discarded_with_floor(order_value, 1000, None, Some(100.into())).await,
+            1
+        );
+        assert_eq!(
+            discarded_with_floor(order_value, 100, None, Some(100.into())).await,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn floor_caps_higher_driver_thresholds() {
+        let order_value = U256::exp10(18);
+        let lenient = FairnessThreshold {
+            flat: eth::Ether(1000.into()),
+            bps_of_order_value: None,
+        };
+
+        assert_eq!(
+            discarded_with_floor(order_value, 500, Some(lenient), None).await,
+            0
+        );
+        assert_eq!(
+            discarded_with_floor(order_value, 500, Some(lenient), Some(100.into())).await,
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn floor_keeps_stricter_driver_thresholds() {
+        let order_value = U256::exp10(18);
+        let strict = FairnessThreshold {
+            flat: eth::Ether(10.into()),
+            bps_of_order_value: None,
+        };
+
+        // the floor alone tolerates the improvement but the driver doesn't
+        assert_eq!(
+            discarded_with_floor(order_value, 50, None, Some(100.into())).await,
+            0
+        );
+        assert_eq!(
+            discarded_with_floor(order_value, 50, Some(strict), Some(100.into())).await,
+            1
+        );
+        assert_eq!(
+            discarded_with_floor(order_value, 10, Some(strict), Some(100.into())).await,
+            0
+        );
+    }
+
+    #[test]
+    fn orders_without_value_use_flat_threshold() {
+        let threshold = FairnessThreshold {
+            flat: eth::Ether(100.into()),
+            bps_of_order_value: Some(100),
+        };
+        assert_eq!(threshold.for_order(None), eth::Ether(100.into()));
+        assert_eq!(
+            threshold.for_order(Some(eth::Ether(1000.into()))),
+            eth::Ether(10.into())
+        );
+    }
+
+    async fn participant_filling(
+        id: u64,
+        score: u64,
+        orders: &[domain::OrderUid],
+        observe_only: bool,
+    ) -> Participant<Unranked> {
+        let solver = eth::H160::from_low_u64_be(id);
+        let solution = Solution::new(
+            id,
+            solver.into(),
+            Score::try_new(eth::Ether(U256::from(score))).unwrap(),
+            orders
+                .iter()
+                .map(|uid| (*uid, execution(100, 100)))
+                .collect(),
+            Default::default(),
+            None,
+        );
+        let mut driver = offline_driver(&id.to_string(), solver).await;
+        driver.observe_only = observe_only;
+        Participant::new(solution, Arc::new(driver))
+    }
+
+    #[tokio::test]
+    async fn ranks_by_objective() {
+        let order = |byte: u8| {
+            let mut order = boundary::order::to_domain(Default::default(), vec![], None);
+            order.uid = domain::OrderUid([byte; 56]);
+            order
+        };
+        let orders = vec![order(1), order(2)];
+        let (a, b) = (orders[0].uid, orders[1].uid);
+        let jit = domain::OrderUid([9; 56]);
+        let auction = Auction {
+            id: 0,
+            block: 0,
+            content_hash: ContentHash::new(&orders, &Default::default(), &[]),
+            orders: Arc::new(orders),
+            prices: Default::default(),
+            surplus_capturing_jit_order_owners: vec![],
+            quote_only: false,
+            prioritized_orders: Default::default(),
+        };
+        let participants = vec![
+            participant_filling(1, 10, &[a], false).await,
+            participant_filling(2, 5, &[a, b], false).await,
+            // JIT orders don't count towards the coverage
+            participant_filling(3, 7, &[a, jit], false).await,
+        ];
+        let ranked = |objective: Objective| {
+            let config = Config {
+                objective,
+                ..Default::default()
+            };
+            config
+                .arbitrate(participants.clone(), &auction)
+                .ranked()
+                .map(|participant| {
+                    (
+                        participant.solution().id(),
+                        participant.is_winner(),
+                        participant.solution().score().get().0.as_u64(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ranked(Objective::Score),
+            [(1, true, 10), (3, false, 7), (2, false, 5)]
+        );
+        // the scores stay the same, only the ranking changes
+        assert_eq!(
+            ranked(Objective::OrderCoverage),
+            [(2, true, 5), (1, false, 10), (3, false, 7)]
+        );
+
+        // the winner scored less than the others, so the reference can't be
+        // the best other score without making the reward negative
+        let config = Config {
+            objective: Objective::OrderCoverage,
+            ..Default::default()
+        };
+        let ranking = config.arbitrate(participants.clone(), &auction);
+        let reference_scores = config.compute_reference_scores(&ranking);
+        assert_eq!(
+            reference_scores.get(&eth::H160::from_low_u64_be(2).into()),
+            Some(&Score::try_new(eth::Ether(U256::from(5))).unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn observe_only_drivers_never_win() {
+        let mut order = boundary::order::to_domain(Default::default(), vec![], None);
+        order.uid = domain::OrderUid([1; 56]);
+        let a = order.uid;
+        let orders = vec![order];
+        let auction = Auction {
+            id: 0,
+            block: 0,
+            content_hash: ContentHash::new(&orders, &Default::default(), &[]),
+            orders: Arc::new(orders),
+            prices: Default::default(),
+            surplus_capturing_jit_order_owners: vec![],
+            quote_only: false,
+            prioritized_orders: Default::default(),
+        };
+        let participants = vec![
+            participant_filling(1, 10, &[a], false).await,
+            participant_filling(2, 5, &[a], false).await,
+            // would win if it wasn't observe-only
+            participant_filling(3, 20, &[a], true).await,
+        ];
+        let config = Config::default();
+        let ranking = config.arbitrate(participants, &auction);
+        let ids = |participants: Vec<&Participant<Ranked>>| {
+            participants
+                .into_iter()
+                .map(|participant| (participant.solution().id(), participant.is_winner()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(ranking.ranked().collect()), [(1, true), (2, false)]);
+        assert_eq!(ids(ranking.observe_only().collect()), [(3, false)]);
+        // observe-only solutions still get stored
+        assert_eq!(ranking.all().count(), 3);
+        // and don't affect the rewards of the winner
+        let reference_scores = config.compute_reference_scores(&ranking);
+        assert_eq!(
+            reference_scores.get(&eth::H160::from_low_u64_be(1).into()),
+            Some(&Score::try_new(eth::Ether(U256::from(5))).unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn quote_only_auctions_have_no_winners() {
+        let mut order = boundary::order::to_domain(Default::default(), vec![], None);
+        order.uid = domain::OrderUid([1; 56]);
+        let a = order.uid;
+        let orders = vec![order];
+        let auction = Auction {
+            id: 0,
+            block: 0,
+            content_hash: ContentHash::new(&orders, &Default::default(), &[]),
+            orders: Arc::new(orders),
+            prices: Default::default(),
+            surplus_capturing_jit_order_owners: vec![],
+            quote_only: true,
+            prioritized_orders: Default::default(),
+        };
+        let participants = vec![
+            participant_filling(1, 10, &[a], false).await,
+            participant_filling(2, 5, &[a], false).await,
+        ];
+        let config = Config::default();
+        let ranking = config.arbitrate(participants, &auction);
+
+        // the solutions still get ranked and stored
+        assert_eq!(
+            ranking
+                .ranked()
+                .map(|participant| participant.solution().id())
+                .collect::<Vec<_>>(),
+            [1, 2]
+        );
+        // but nobody is expected to settle them or gets rewarded for them
+        assert_eq!(ranking.winners().count(), 0);
+        assert!(config.compute_reference_scores(&ranking).is_empty());
+    }
+
+    #[tokio::test]
+    async fn tied_winners_get_no_negative_rewards() {
+        let mut order = boundary::order::to_domain(Default::default(), vec![], None);
+        order.uid = domain::OrderUid([1; 56]);
+        let a = order.uid;
+        let orders = vec![order];
+        let auction = Auction {
+            id: 0,
+            block: 0,
+            content_hash: ContentHash::new(&orders, &Default::default(), &[]),
+            orders: Arc::new(orders),
+            prices: Default::default(),
+            surplus_capturing_jit_order_owners: vec![],
+            quote_only: false,
+            prioritized_orders: Default::default(),
+        };
+        // tied within the epsilon so the (shuffled) input order decides
+        let participants = vec![
+            participant_filling(1, 100, &[a], false).await,
+            participant_filling(2, 101, &[a], false).await,
+        ];
+        let config = Config {
+            score_tie_epsilon: 1.into(),
+            ..Default::default()
+        };
+        let ranking = config.arbitrate(participants, &auction);
+
+        let winners: Vec<_> = ranking
+            .winners()
+            .map(|participant| participant.solution().id())
+            .collect();
+        assert_eq!(winners, [1]);
+        // the runner-up scored higher but the reference is capped at the
+        // winner's score
+        let reference_scores = config.compute_reference_scores(&ranking);
+        assert_eq!(
+            reference_scores.get(&eth::H160::from_low_u64_be(1).into()),
+            Some(&Score::try_new(eth::Ether(U256::from(100))).unwrap())
+        );
+    }
+}
@@ -1,12 +1,18 @@
 use {
     crate::domain::{
+        self,
         Auction,
-        competition::{Participant, Ranked, Score, Unranked},
-        eth,
+        competition::{Participant, Ranked, Score, Solution, Unranked, ranking},
+        eth::{self, WrappedNativeToken},
+    },
+    std::{
+        cmp::Reverse,
+        collections::{HashMap, HashSet},
+        num::NonZeroUsize,
     },
-    std::collections::HashMap,
 };
 
+pub mod coalition_fairness;
 pub mod combinatorial;
 pub mod max_score;
 
@@ -18,12 +24,19 @@ pub struct Ranking {
     /// check. Winners come before non-winners and higher total
     /// scores come before lower scores.
     ranked: Vec<Participant<Ranked>>,
+    /// Solutions of observe-only drivers ranked among themselves. They never
+    /// win and don't influence the fairness checks, winners or reference
+    /// scores of the other solutions.
+    observe_only: Vec<Participant<Ranked>>,
 }
 
 impl Ranking {
     /// All solutions including the ones that got filtered out.
     pub fn all(&self) -> impl Iterator<Item = &Participant<Ranked>> {
-        self.ranked.iter().chain(&self.filtered_out)
+        self.ranked
+            .iter()
+            .chain(&self.observe_only)
+            .chain(&self.filtered_out)
     }
 
     /// Enumerates all solutions. The index is used as solution UID.
@@ -41,10 +54,261 @@ impl Ranking {
         self.ranked.iter().filter(|p| !p.is_winner())
     }
 
+    /// Non-winners that would have won if they didn't trade a directed token
+    /// pair of a winner picked before them. Solutions that lost because the
+    /// winner slots ran out, their submission address already won as often as
+    /// it may or for any other reason aren't included.
+    pub fn blocked_by_token_conflicts(&self) -> impl Iterator<Item = &Participant<Ranked>> {
+        self.ranked
+            .iter()
+            .filter(|p| p.is_blocked_by_token_conflict())
+    }
+
     /// All solutions that passed the filtering step.
     pub fn ranked(&self) -> impl Iterator<Item = &Participant<Ranked>> {
         self.ranked.iter()
     }
+
+    /// Solutions of observe-only drivers that passed the filtering step.
+    pub fn observe_only(&self) -> impl Iterator<Item = &Participant<Ranked>> {
+        self.observe_only.iter()
+    }
+}
+
+/// Strategy used to decide which of the (sorted) solutions win.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// Only the solution with the highest score wins.
+    SingleWinner,
+    /// Solutions win one by one starting from the best one as long as
+    /// they don't trade a directed token pair of a previous winner.
+    Combinatorial,
+    /// Same as [`SelectionMode::Combinatorial`] but once `max_winners` are
+    /// selected up to `max_extra_winners` further solutions may still win if
+    /// they settle orders that no selected winner touches.
+    CombinatorialWithExtraWinners { max_extra_winners: usize },
+}
+
+/// Outcome of the winner selection for a single solution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Won,
+    /// Would have won if it didn't trade a directed token pair of a winner
+    /// that was selected before it.
+    BlockedByTokenConflict,
+    /// Lost for any other reason, e.g. because all winner slots were taken.
+    Lost,
+}
+
+impl Selection {
+    pub fn is_winner(self) -> bool {
+        matches!(self, Self::Won)
+    }
+}
+
+/// Computes which participants win the auction without modifying
+/// or reordering them. Returns one flag per participant (`true` for
+/// winners) in the same order as `sorted`.
+/// Assumes that `sorted` is sorted by score descendingly.
+/// If `weth` is set the native token is considered the same token as its
+/// wrapped counterpart when checking for overlapping solutions.
+pub fn select_winners(
+    sorted: &[Participant<Unranked>],
+    max_winners: usize,
+    max_winners_per_submission_address: Option<NonZeroUsize>,
+    weth: Option<WrappedNativeToken>,
+    mode: SelectionMode,
+) -> Vec<bool> {
+    selections(
+        sorted,
+        max_winners,
+        max_winners_per_submission_address,
+        weth,
+        mode,
+    )
+    .into_iter()
+    .map(Selection::is_winner)
+    .collect()
+}
+
+/// Same as [`select_winners`] but also tells why the non-winners lost.
+pub fn selections(
+    sorted: &[Participant<Unranked>],
+    max_winners: usize,
+    max_winners_per_submission_address: Option<NonZeroUsize>,
+    weth: Option<WrappedNativeToken>,
+    mode: SelectionMode,
+) -> Vec<Selection> {
+    select_winning_solutions(
+        sorted.iter().map(Participant::solution),
+        max_winners,
+        max_winners_per_submission_address,
+        weth,
+        mode,
+    )
+}
+
+/// Same as [`selections`] but works on any sequence of solutions
+/// so it can also be used on already ranked participants (e.g. when
+/// computing reference scores).
+fn select_winning_solutions<'a>(
+    solutions: impl Iterator<Item = &'a Solution>,
+    max_winners: usize,
+    max_winners_per_submission_address: Option<NonZeroUsize>,
+    weth: Option<WrappedNativeToken>,
+    mode: SelectionMode,
+) -> Vec<Selection> {
+    match mode {
+        SelectionMode::SingleWinner => solutions
+            .enumerate()
+            .map(|(index, _)| match index == 0 && max_winners > 0 {
+                true => Selection::Won,
+                false => Selection::Lost,
+            })
+            .collect(),
+        SelectionMode::Combinatorial => combinatorial::pick_winners(
+            solutions,
+            max_winners,
+            max_winners_per_submission_address,
+            weth,
+            0,
+        ),
+        SelectionMode::CombinatorialWithExtraWinners { max_extra_winners } => {
+            combinatorial::pick_winners(
+                solutions,
+                max_winners,
+                max_winners_per_submission_address,
+                weth,
+                max_extra_winners,
+            )
+        }
+    }
+}
+
+/// What solutions get ranked by before winners get picked. Only affects the
+/// ranking, the scores of the solutions stay the same.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Objective {
+    /// Solutions with higher scores come first.
+    #[default]
+    Score,
+    /// Solutions filling more distinct auction orders come first. Ties are
+    /// broken by score. Reference scores still get computed on scores, so
+    /// winners beating higher scores with their coverage get no reward.
+    OrderCoverage,
+}
+
+impl Objective {
+    /// Reorders participants that are already sorted by score according to
+    /// the objective.
+    fn apply<T>(self, participants: &mut [Participant<T>], auction: &Auction) {
+        match self {
+            Objective::Score => (),
+            Objective::OrderCoverage => {
+                let orders: HashSet<_> = auction.orders.iter().map(|order| order.uid).collect();
+                // stable sort to keep the score order between ties
+                participants.sort_by_key(|participant| {
+                    Reverse(filled_orders(participant.solution(), &orders))
+                });
+            }
+        }
+    }
+}
+
+/// Number of distinct auction orders the solution fills. JIT orders don't
+/// count.
+fn filled_orders(solution: &Solution, orders: &HashSet<domain::OrderUid>) -> usize {
+    solution
+        .orders()
+        .iter()
+        .filter(|(uid, execution)| orders.contains(uid) && !execution.executed_sell.0.is_zero())
+        .count()
+}
+
+impl From<crate::arguments::WinnerObjective> for Objective {
+    fn from(value: crate::arguments::WinnerObjective) -> Self {
+        match value {
+            crate::arguments::WinnerObjective::Score => Self::Score,
+            crate::arguments::WinnerObjective::OrderCoverage => Self::OrderCoverage,
+        }
+    }
+}
+
+/// How the winner slots remaining after the best solution get filled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Merging {
+    /// Solutions win greedily by score as long as they are disjoint with all
+    /// previously selected winners until `max_winners` are selected.
+    #[default]
+    Greedy,
+    /// Same as [`Merging::Greedy`] but up to `max_extra_winners` solutions may
+    /// win beyond `max_winners` if they settle orders that no selected winner
+    /// touches.
+    ExtraWinners { max_extra_winners: usize },
+}
+
+impl Merging {
+    pub fn new(mode: crate::arguments::WinnerMerging, max_extra_winners: usize) -> Self {
+        match mode {
+            crate::arguments::WinnerMerging::Greedy => Self::Greedy,
+            crate::arguments::WinnerMerging::ExtraWinners => {
+                Self::ExtraWinners { max_extra_winners }
+            }
+        }
+    }
+
+    /// How many winners may be selected on top of `max_winners`.
+    pub fn max_extra_winners(&self) -> usize {
+        match self {
+            Self::Greedy => 0,
+            Self::ExtraWinners { max_extra_winners } => *max_extra_winners,
+        }
+    }
+}
+
+/// Filters and ranks `participants`. Returns the filtered out and the ranked
+/// participants. With `without_winners` no participant gets marked as winner.
+fn rank<A: Arbitrator + ?Sized>(
+    arbitrator: &A,
+    participants: Vec<Participant<Unranked>>,
+    auction: &Auction,
+    without_winners: bool,
+) -> (Vec<Participant<Ranked>>, Vec<Participant<Ranked>>) {
+    let partitioned = arbitrator.partition_unfair_solutions(participants, auction);
+    let filtered_out = partitioned
+        .discarded
+        .into_iter()
+        .map(|participant| participant.rank(Ranked::FilteredOut))
+        .collect();
+
+    let mut kept = partitioned.kept;
+    arbitrator.objective().apply(&mut kept, auction);
+    let mut ranked = if without_winners {
+        kept.into_iter()
+            .map(|participant| participant.rank(Ranked::NonWinner))
+            .collect()
+    } else {
+        arbitrator.mark_winners(kept)
+    };
+    // high score before low score, arbitrators that don't compute scores
+    // rank by the reported ones
+    ranking::sort_with_ties(
+        &mut ranked,
+        |participant| {
+            participant
+                .solution()
+                .computed_score()
+                .copied()
+                .unwrap_or_else(|| participant.solution().score())
+                .get()
+                .0
+        },
+        arbitrator.score_tie_epsilon(),
+    );
+    arbitrator.objective().apply(&mut ranked, auction);
+    // winners before non-winners
+    ranked.sort_by_key(|participant| std::cmp::Reverse(participant.is_winner()));
+    (filtered_out, ranked)
 }
 
 pub struct PartitionedSolutions {
@@ -61,26 +325,20 @@ pub struct PartitionedSolutions {
 /// changing the ordering or the `participants.
 pub trait Arbitrator: Send + Sync + 'static {
     /// Runs the entire auction mechanism on the passed in solutions.
+    /// Solutions of observe-only drivers get ranked separately so they can't
+    /// affect the outcome for everyone else. Quote-only auctions never get
+    /// settled so nobody wins them.
     fn arbitrate(&self, participants: Vec<Participant<Unranked>>, auction: &Auction) -> Ranking {
-        let partitioned = self.partition_unfair_solutions(participants, auction);
-        let filtered_out = partitioned
-            .discarded
+        let (observed, participants): (Vec<_>, Vec<_>) = participants
             .into_iter()
-            .map(|participant| participant.rank(Ranked::FilteredOut))
-            .collect();
-
-        let mut ranked = self.mark_winners(partitioned.kept);
-        ranked.sort_by_key(|participant| {
-            (
-                // winners before non-winners
-                std::cmp::Reverse(participant.is_winner()),
-                // high score before low score
-                std::cmp::Reverse(participant.solution().computed_score().cloned()),
-            )
-        });
+            .partition(|participant| participant.driver().observe_only);
+        let (mut filtered_out, ranked) = rank(self, participants, auction, auction.quote_only);
+        let (observed_filtered_out, observe_only) = rank(self, observed, auction, true);
+        filtered_out.extend(observed_filtered_out);
         Ranking {
             filtered_out,
             ranked,
+            observe_only,
         }
     }
 
@@ -91,11 +349,18 @@ pub trait Arbitrator: Send + Sync + 'static {
         auction: &Auction,
     ) -> PartitionedSolutions;
 
-    /// Picks winners and sorts all solutions where winners come before
-    /// non-winners and higher scores come before lower scores.
+    /// Picks winners from solutions sorted by the [`Objective`] and sorts all
+    /// solutions where winners come before non-winners and higher scores come
+    /// before lower scores.
     fn mark_winners(&self, participants: Vec<Participant<Unranked>>) -> Vec<Participant<Ranked>>;
 
     /// Computes the reference scores which are used to compute
     /// rewards for the winning solvers.
     fn compute_reference_scores(&self, ranking: &Ranking) -> HashMap<eth::Address, Score>;
+
+    /// Scores within this distance of each other are considered tied.
+    fn score_tie_epsilon(&self) -> eth::U256;
+
+    /// What solutions get ranked by before winners get picked.
+    fn objective(&self) -> Objective;
 }
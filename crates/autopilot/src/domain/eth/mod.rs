@@ -1,6 +1,7 @@
 pub use primitive_types::{H160, H256, U256};
 use {
     crate::{domain, util::conv::U256Ext},
+    anyhow::Context,
     derive_more::{Display, From, Into},
 };
 
@@ -31,9 +32,44 @@ impl std::ops::Add<u64> for BlockNo {
 }
 
 /// A transaction ID, AKA transaction hash.
-#[derive(Debug, Copy, Clone, From, Default)]
+///
+/// Gets displayed as the full `0x`-prefixed hex string (unlike [`H256`],
+/// whose `Display` implementation abbreviates the hash) so that it can be
+/// copied from logs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, From, Into, Default)]
 pub struct TxId(pub H256);
 
+impl std::fmt::Display for TxId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+impl std::str::FromStr for TxId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix("0x").context("missing 0x prefix")?;
+        let mut bytes = [0; 32];
+        hex::decode_to_slice(digits, &mut bytes)
+            .map_err(|err| anyhow::anyhow!("expected 32 hex encoded bytes: {err}"))?;
+        Ok(Self(H256(bytes)))
+    }
+}
+
+impl serde::Serialize for TxId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for TxId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = std::borrow::Cow::<str>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 /// An ERC20 token address.
 ///
 /// https://eips.ethereum.org/EIPS/eip-20
@@ -154,6 +190,23 @@ impl num::Saturating for SellTokenAmount {
 #[derive(Debug, Default, Display, Clone, Copy, Ord, Eq, PartialOrd, PartialEq, From, Into)]
 pub struct Gas(pub U256);
 
+impl Gas {
+    /// Difference between this and `rhs` which can be negative. Saturates at
+    /// the bounds of [`SignedGas`].
+    pub fn signed_sub(self, rhs: Self) -> SignedGas {
+        let difference = |a: U256, b: U256| (a - b).min(U256::from(i64::MAX)).as_u64() as i64;
+        match self >= rhs {
+            true => SignedGas(difference(self.0, rhs.0)),
+            false => SignedGas(difference(rhs.0, self.0).saturating_neg()),
+        }
+    }
+}
+
+/// Like [`Gas`] but can be negative, e.g. to express the difference between
+/// two gas amounts.
+#[derive(Debug, Default, Display, Clone, Copy, Ord, Eq, PartialOrd, PartialEq, From, Into)]
+pub struct SignedGas(pub i64);
+
 /// The `effective_gas_price` as defined by EIP-1559.
 ///
 /// https://eips.ethereum.org/EIPS/eip-1559#specification
@@ -324,6 +377,14 @@ pub struct CallFrame {
     pub calls: Vec<CallFrame>,
 }
 
+/// A transaction that has not been sent yet.
+#[derive(Debug, Clone, Default)]
+pub struct Tx {
+    pub from: Address,
+    pub to: Address,
+    pub input: Vec<u8>,
+}
+
 /// Any type of on-chain transaction.
 #[derive(Debug, Clone, Default)]
 pub struct Transaction {
@@ -342,3 +403,46 @@ pub struct Transaction {
     /// Traces of all Calls contained in the transaction.
     pub trace_calls: CallFrame,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TX: &str = "0xc48dc0d43ffb43891d8c3ad7bcf05f11465518a2610869b20b0b4ccb61497634";
+
+    #[test]
+    fn tx_id_display_round_trip() {
+        let tx: TxId = TX.parse().unwrap();
+        assert_eq!(tx.to_string(), TX);
+        assert_eq!(tx.to_string().parse::<TxId>().unwrap(), tx);
+        // leading zeros are kept
+        assert_eq!(
+            TxId(H256::from_low_u64_be(1)).to_string(),
+            "0x0000000000000000000000000000000000000000000000000000000000000001"
+        );
+    }
+
+    #[test]
+    fn tx_id_rejects_malformed_hashes() {
+        for s in [
+            // missing prefix
+            &TX[2..],
+            // too short
+            &TX[..TX.len() - 2],
+            // too long
+            format!("{TX}00").as_str(),
+            // not hex
+            TX.replace('c', "g").as_str(),
+        ] {
+            assert!(s.parse::<TxId>().is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn tx_id_serde_round_trip() {
+        let tx: TxId = TX.parse().unwrap();
+        let json = serde_json::to_value(tx).unwrap();
+        assert_eq!(json, serde_json::json!(TX));
+        assert_eq!(serde_json::from_value::<TxId>(json).unwrap(), tx);
+    }
+}
@@ -23,9 +23,23 @@ pub struct Metrics {
     #[metric(labels("solver", "reason"))]
     pub banned_solver: prometheus::IntCounterVec,
 
+    /// Whether the solver is currently not allowed to participate in the
+    /// competition (1) or not (0).
+    #[metric(labels("solver"))]
+    pub deny_listed_solver: prometheus::IntGaugeVec,
+
     /// Tracks settlements that couldn't be matched to the database solutions.
     #[metric(labels("solver_address"))]
     pub inconsistent_settlements: prometheus::IntCounterVec,
+
+    /// Absolute relative deviation of the native prices implied by settlements
+    /// from the native prices of their auctions.
+    #[metric(buckets(0.001, 0.005, 0.01, 0.02, 0.05, 0.1, 0.2, 0.5, 1.0))]
+    pub native_price_deviation: prometheus::Histogram,
+
+    /// How many winners were selected beyond the maximum number of winners
+    /// per auction because they settle orders no other winner settles.
+    pub extra_winners: prometheus::IntCounter,
 }
 
 impl Metrics {
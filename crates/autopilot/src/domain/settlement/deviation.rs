@@ -0,0 +1,207 @@
+use {
+    super::transaction::Decoded,
+    crate::domain::{self, competition, eth},
+    std::collections::{HashMap, HashSet},
+};
+
+/// Relative difference between a promised and an executed clearing price that
+/// is still considered equal, in basis points. Encoding a solution may round
+/// its prices.
+const PRICE_TOLERANCE_BPS: u64 = 1;
+
+/// Differences between a winning solution and the settlement that actually got
+/// executed on-chain for it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Deviation {
+    /// Orders of the solution that didn't get settled.
+    pub dropped_orders: Vec<domain::OrderUid>,
+    /// Settled orders that weren't part of the solution. Dust executions the
+    /// autopilot dropped from the solution don't count.
+    pub added_orders: Vec<domain::OrderUid>,
+    /// Tokens whose uniform clearing price differs from the promised one by
+    /// more than [`PRICE_TOLERANCE_BPS`].
+    pub changed_prices: Vec<PriceChange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceChange {
+    pub token: eth::TokenAddress,
+    /// `None` if the solution didn't have a price for the token.
+    pub promised: Option<eth::U256>,
+    pub executed: eth::U256,
+}
+
+impl Deviation {
+    pub fn new(promised: &competition::Solution, executed: &Decoded) -> Self {
+        Self::from_executed(
+            promised,
+            executed.trades.iter().map(|trade| trade.uid).collect(),
+            executed.uniform_prices(),
+        )
+    }
+
+    fn from_executed(
+        promised: &competition::Solution,
+        executed_orders: HashSet<domain::OrderUid>,
+        executed_prices: HashMap<eth::TokenAddress, eth::U256>,
+    ) -> Self {
+        let mut dropped_orders: Vec<_> = promised
+            .order_ids()
+            .filter(|uid| !executed_orders.contains(uid))
+            .copied()
+            .collect();
+        dropped_orders.sort_by_key(|uid| uid.0);
+
+        let mut added_orders: Vec<_> = executed_orders
+            .into_iter()
+            .filter(|uid| {
+                !promised.orders().contains_key(uid) && !promised.dust_orders().contains(uid)
+            })
+            .collect();
+        added_orders.sort_by_key(|uid| uid.0);
+
+        let mut changed_prices: Vec<_> = executed_prices
+            .into_iter()
+            .filter_map(|(token, executed)| {
+                let promised = promised.prices().get(&token).map(|price| price.get().0);
+                let changed = promised.is_none_or(|promised| !within_tolerance(promised, executed));
+                changed.then_some(PriceChange {
+                    token,
+                    promised,
+                    executed,
+                })
+            })
+            .collect();
+        changed_prices.sort_by_key(|change| change.token);
+
+        Self {
+            dropped_orders,
+            added_orders,
+            changed_prices,
+        }
+    }
+
+    /// Whether the settlement executed exactly what the solution promised.
+    pub fn is_empty(&self) -> bool {
+        self.dropped_orders.is_empty()
+            && self.added_orders.is_empty()
+            && self.changed_prices.is_empty()
+    }
+}
+
+fn within_tolerance(promised: eth::U256, executed: eth::U256) -> bool {
+    let difference = promised.max(executed) - promised.min(executed);
+    difference.full_mul(10_000.into()) <= promised.full_mul(PRICE_TOLERANCE_BPS.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::domain::{auction, competition::Score},
+        primitive_types::{H160, U256},
+    };
+
+    fn token(id: u64) -> eth::TokenAddress {
+        H160::from_low_u64_be(id).into()
+    }
+
+    fn uid(id: u8) -> domain::OrderUid {
+        domain::OrderUid([id; 56])
+    }
+
+    fn solution(orders: &[u8], dust_orders: &[u8]) -> competition::Solution {
+        let order = competition::TradedOrder {
+            side: auction::order::Side::Sell,
+            sell: eth::Asset {
+                token: token(1),
+                amount: U256::from(100).into(),
+            },
+            buy: eth::Asset {
+                token: token(2),
+                amount: U256::from(100).into(),
+            },
+            executed_sell: U256::from(100).into(),
+            executed_buy: U256::from(100).into(),
+        };
+        competition::Solution::new(
+            0,
+            H160::zero().into(),
+            Score::try_new(eth::Ether(U256::one())).unwrap(),
+            orders.iter().map(|id| (uid(*id), order)).collect(),
+            [(token(1), 1_000_000_u64), (token(2), 2_000_000)]
+                .into_iter()
+                .map(|(token, price)| {
+                    let price = auction::Price::try_new(eth::Ether(price.into())).unwrap();
+                    (token, price)
+                })
+                .collect(),
+            None,
+        )
+        .with_dust_orders(dust_orders.iter().copied().map(uid).collect())
+    }
+
+    fn prices(prices: &[(u64, u64)]) -> HashMap<eth::TokenAddress, eth::U256> {
+        prices
+            .iter()
+            .map(|(id, price)| (token(*id), U256::from(*price)))
+            .collect()
+    }
+
+    #[test]
+    fn matching_settlement_has_no_deviation() {
+        let deviation = Deviation::from_executed(
+            &solution(&[1, 2], &[]),
+            HashSet::from([uid(1), uid(2)]),
+            prices(&[(1, 1_000_000), (2, 2_000_000)]),
+        );
+        assert!(deviation.is_empty());
+    }
+
+    #[test]
+    fn detects_dropped_and_added_orders() {
+        let deviation = Deviation::from_executed(
+            &solution(&[1, 2], &[]),
+            HashSet::from([uid(2), uid(3)]),
+            prices(&[(1, 1_000_000), (2, 2_000_000)]),
+        );
+        assert_eq!(deviation.dropped_orders, [uid(1)]);
+        assert_eq!(deviation.added_orders, [uid(3)]);
+        assert!(deviation.changed_prices.is_empty());
+    }
+
+    #[test]
+    fn ignores_dropped_dust_executions() {
+        let deviation = Deviation::from_executed(
+            &solution(&[1], &[2]),
+            HashSet::from([uid(1), uid(2)]),
+            prices(&[(1, 1_000_000), (2, 2_000_000)]),
+        );
+        assert!(deviation.is_empty());
+    }
+
+    #[test]
+    fn tolerates_rounded_prices() {
+        let deviation = Deviation::from_executed(
+            &solution(&[1], &[]),
+            HashSet::from([uid(1)]),
+            // 1 bps off is still fine, 2 bps are not
+            prices(&[(1, 1_000_100), (2, 1_999_600), (3, 5)]),
+        );
+        assert_eq!(
+            deviation.changed_prices,
+            [
+                PriceChange {
+                    token: token(2),
+                    promised: Some(2_000_000.into()),
+                    executed: 1_999_600.into(),
+                },
+                PriceChange {
+                    token: token(3),
+                    promised: None,
+                    executed: 5.into(),
+                },
+            ]
+        );
+    }
+}
@@ -20,18 +20,25 @@ use {
     database::{orders::OrderKind, solver_competition_v2::Solution},
     futures::TryFutureExt,
     number::conversions::big_decimal_to_u256,
-    std::collections::{HashMap, HashSet},
+    std::{
+        collections::{HashMap, HashSet},
+        ops::{Range, RangeInclusive},
+    },
 };
 
 mod auction;
+mod deviation;
 mod observer;
+mod price_deviation;
 mod trade;
 pub mod transaction;
 pub use {
     auction::Auction,
+    deviation::{Deviation, PriceChange},
     observer::Observer,
+    price_deviation::PriceDeviation,
     trade::{Trade, math},
-    transaction::Transaction,
+    transaction::{Decoded, Transaction},
 };
 
 /// A settled transaction together with the `Auction`, for which it was executed
@@ -77,6 +84,17 @@ impl Settlement {
         self.solution_uid
     }
 
+    /// How much the native prices implied by the settlement's clearing prices
+    /// deviate from the native prices of the auction. Trades worth less than
+    /// `min_volume` are ignored.
+    pub fn price_deviations(&self, min_volume: eth::Ether) -> Vec<PriceDeviation> {
+        price_deviation::price_deviations(
+            self.trades.iter().map(math::Trade::from),
+            &self.auction.prices,
+            min_volume,
+        )
+    }
+
     /// Total surplus for all trades in the settlement.
     pub fn surplus_in_ether(&self) -> eth::Ether {
         self.trades
@@ -338,9 +356,78 @@ pub struct ExecutionEnded {
     pub solution_uid: usize,
     pub end_timestamp: DateTime<Utc>,
     pub end_block: u64,
+    pub outcome_kind: ExecutionOutcomeKind,
+    /// Human readable outcome including the error of failed executions.
     pub outcome: String,
 }
 
+/// How a settlement execution ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionOutcomeKind {
+    Success,
+    Timeout,
+    Reorged,
+    /// The driver failed to settle the solution.
+    Failed,
+}
+
+/// A settlement execution with its end if it ended.
+#[derive(Debug, Clone)]
+pub struct ExecutionRecord {
+    pub auction_id: AuctionId,
+    pub solver: eth::Address,
+    pub solution_uid: usize,
+    pub start_timestamp: DateTime<Utc>,
+    pub start_block: u64,
+    pub deadline_block: u64,
+    pub end: Option<ExecutionEnd>,
+    /// The execution never ended although its deadline passed. This happens
+    /// when the settlement task crashed (e.g. because the autopilot
+    /// restarted).
+    pub orphaned: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExecutionEnd {
+    pub timestamp: DateTime<Utc>,
+    pub block: u64,
+    pub outcome: String,
+}
+
+/// Selects settlement executions. Executions have to match all set criteria.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionFilter {
+    pub solver: Option<eth::Address>,
+    pub auctions: Option<RangeInclusive<AuctionId>>,
+    pub outcome: Option<ExecutionOutcome>,
+    /// When the execution started.
+    pub started: Option<Range<DateTime<Utc>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionOutcome {
+    Success,
+    Timeout,
+    /// The driver failed to settle the solution.
+    Failed,
+    /// The execution didn't end yet.
+    Unfinished,
+    /// See [`ExecutionRecord::orphaned`].
+    Orphaned,
+}
+
+/// Execution progress of an order that is part of a winning solution.
+#[derive(Debug, Clone)]
+pub struct OrderExecutionStatus {
+    pub order_uid: OrderUid,
+    pub auction_id: AuctionId,
+    pub solver: eth::Address,
+    pub solution_uid: usize,
+    pub deadline_block: u64,
+    /// The settlement transaction, once it is known.
+    pub tx: Option<eth::TxId>,
+}
+
 #[cfg(test)]
 mod tests {
     use {
@@ -12,7 +12,11 @@
 
 use {
     crate::{
-        domain::settlement::{self},
+        domain::{
+            self,
+            eth,
+            settlement::{self},
+        },
         infra,
     },
     anyhow::{Result, anyhow},
@@ -22,13 +26,24 @@ use {
 pub struct Observer {
     eth: infra::Ethereum,
     persistence: infra::Persistence,
+    /// Trades worth less than this are ignored when comparing the prices
+    /// implied by a settlement with the native prices of its auction.
+    min_price_deviation_volume: eth::Ether,
 }
 
 impl Observer {
     /// Creates a new Observer and asynchronously schedules the first update
     /// run.
-    pub fn new(eth: infra::Ethereum, persistence: infra::Persistence) -> Self {
-        Self { eth, persistence }
+    pub fn new(
+        eth: infra::Ethereum,
+        persistence: infra::Persistence,
+        min_price_deviation_volume: eth::Ether,
+    ) -> Self {
+        Self {
+            eth,
+            persistence,
+            min_price_deviation_volume,
+        }
     }
 
     /// Fetches all the available missing data needed for bookkeeping.
@@ -63,7 +78,7 @@ impl Observer {
             return Ok(false);
         };
 
-        tracing::debug!(tx = ?event.transaction, "updating settlement details");
+        tracing::debug!(tx = %event.transaction, "updating settlement details");
 
         // Reconstruct the settlement transaction based on the transaction hash
         let transaction = match self.eth.transaction(event.transaction).await {
@@ -111,6 +126,7 @@ impl Observer {
                     }
                     settlement::transaction::Error::MissingAuctionId
                     | settlement::transaction::Error::Decoding(_)
+                    | settlement::transaction::Error::InvalidTokenIndex
                     | settlement::transaction::Error::SignatureRecover(_)
                     | settlement::transaction::Error::OrderUidRecover(_)
                     | settlement::transaction::Error::MissingSolver => {
@@ -138,8 +154,43 @@ impl Observer {
             ));
         }
 
+        if let Some(settlement) = &settlement {
+            self.store_price_deviations(event, auction_id, settlement)
+                .await;
+        }
+
         Ok(true)
     }
+
+    /// Records how much the native prices implied by the settlement deviate
+    /// from the native prices of its auction to detect regressions of the
+    /// native price estimator.
+    async fn store_price_deviations(
+        &self,
+        event: eth::SettlementEvent,
+        auction_id: domain::auction::Id,
+        settlement: &settlement::Settlement,
+    ) {
+        let deviations = settlement.price_deviations(self.min_price_deviation_volume);
+        for deviation in &deviations {
+            tracing::debug!(
+                ?auction_id,
+                token = ?deviation.token,
+                relative = deviation.relative,
+                "native price deviation"
+            );
+            domain::Metrics::get()
+                .native_price_deviation
+                .observe(deviation.relative.abs());
+        }
+        if let Err(err) = self
+            .persistence
+            .store_price_deviations(event, auction_id, &deviations)
+            .await
+        {
+            tracing::warn!(?err, ?auction_id, "failed to store native price deviations");
+        }
+    }
 }
 
 /// Whether Observer loop should retry on the given error.
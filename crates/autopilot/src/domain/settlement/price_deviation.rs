@@ -0,0 +1,188 @@
+//! Detection of native price estimator regressions by comparing the native
+//! prices of an auction with the prices implied by the clearing prices of the
+//! settlement that got executed for it.
+
+use {
+    super::math,
+    crate::domain::{
+        auction::{self, order::Side},
+        eth,
+    },
+    num::{BigRational, One, ToPrimitive},
+    number::conversions::{big_rational_to_u256, u256_to_big_rational},
+    std::collections::HashMap,
+};
+
+/// Deviation of the native price implied by a settlement from the native price
+/// of the auction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceDeviation {
+    pub token: eth::TokenAddress,
+    /// The native price of the token in the auction.
+    pub auction: auction::Price,
+    /// The native price implied by the uniform clearing prices.
+    pub implied: eth::U256,
+    /// `implied / auction - 1`
+    pub relative: f64,
+}
+
+/// Computes the [`PriceDeviation`] of every traded token that has a native
+/// price in the auction.
+///
+/// Clearing prices only determine the prices of tokens relative to each other,
+/// so they get anchored to the auction price of the token with the most traded
+/// volume. That token deviates by definition not at all and doesn't get
+/// reported. Trades worth less than `min_volume` are ignored since rounding
+/// makes their implied prices too noisy.
+pub fn price_deviations(
+    trades: impl IntoIterator<Item = math::Trade>,
+    prices: &auction::Prices,
+    min_volume: eth::Ether,
+) -> Vec<PriceDeviation> {
+    // traded volume and uniform clearing price per token
+    let mut tokens: HashMap<eth::TokenAddress, (eth::U256, eth::U256)> = HashMap::new();
+    for trade in trades {
+        let volume_token = match trade.side {
+            Side::Sell => trade.sell.token,
+            Side::Buy => trade.buy.token,
+        };
+        let Some(volume) = auction::price_in_eth(prices, volume_token, trade.executed.0) else {
+            continue;
+        };
+        if volume < min_volume.0 {
+            continue;
+        }
+        for (token, clearing) in [
+            (trade.sell.token, trade.prices.uniform.sell),
+            (trade.buy.token, trade.prices.uniform.buy),
+        ] {
+            if !prices.contains_key(&token) || clearing.is_zero() {
+                continue;
+            }
+            let (traded, _) = tokens.entry(token).or_insert((eth::U256::zero(), clearing));
+            *traded = traded.saturating_add(volume);
+        }
+    }
+
+    let Some((reference, (_, reference_clearing))) = tokens
+        .iter()
+        .max_by_key(|(token, (volume, _))| (*volume, **token))
+    else {
+        return vec![];
+    };
+    let reference_price = u256_to_big_rational(&prices[reference].get().0);
+    let reference_clearing = u256_to_big_rational(reference_clearing);
+
+    let mut deviations: Vec<_> = tokens
+        .iter()
+        .filter(|(token, _)| *token != reference)
+        .filter_map(|(token, (_, clearing))| {
+            let auction_price = prices[token];
+            let implied: BigRational =
+                &reference_price * u256_to_big_rational(clearing) / &reference_clearing;
+            let relative = (&implied / u256_to_big_rational(&auction_price.get().0)
+                - BigRational::one())
+            .to_f64()?;
+            Some(PriceDeviation {
+                token: *token,
+                auction: auction_price,
+                implied: big_rational_to_u256(&implied).ok()?,
+                relative,
+            })
+        })
+        .collect();
+    deviations.sort_by_key(|deviation| deviation.token);
+    deviations
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::domain::{
+            self,
+            settlement::transaction::{ClearingPrices, Prices},
+        },
+        primitive_types::H160,
+    };
+
+    fn token(id: u64) -> eth::TokenAddress {
+        H160::from_low_u64_be(id).into()
+    }
+
+    fn price(value: u64) -> auction::Price {
+        auction::Price::try_new(eth::Ether(eth::U256::exp10(18) * value)).unwrap()
+    }
+
+    /// Sells `executed` of `sell` for `buy` at the given uniform clearing
+    /// prices.
+    fn trade(sell: (u64, u64), buy: (u64, u64), executed: u64) -> math::Trade {
+        let asset = |id| eth::Asset {
+            token: token(id),
+            amount: eth::U256::zero().into(),
+        };
+        let prices = ClearingPrices {
+            sell: sell.1.into(),
+            buy: buy.1.into(),
+        };
+        math::Trade {
+            uid: domain::OrderUid([0; 56]),
+            sell: asset(sell.0),
+            buy: asset(buy.0),
+            side: Side::Sell,
+            executed: auction::order::TargetAmount(executed.into()),
+            prices: Prices {
+                uniform: prices,
+                custom: prices,
+            },
+        }
+    }
+
+    #[test]
+    fn compares_implied_prices() {
+        let prices = HashMap::from([
+            (token(1), price(1)),
+            (token(2), price(2)),
+            (token(3), price(4)),
+        ]);
+        let trades = [
+            // implies that token 2 is worth 3 times token 1
+            trade((1, 1), (2, 3), 100),
+            // token 3 trades at its auction price relative to token 1
+            trade((1, 1), (3, 4), 10),
+            // too small to be considered
+            trade((2, 1), (3, 1), 1),
+            // token 4 has no auction price
+            trade((1, 1), (4, 1), 100),
+        ];
+
+        let deviations = price_deviations(trades, &prices, eth::U256::from(5).into());
+
+        // token 1 was traded the most and is used as the reference
+        assert_eq!(
+            deviations,
+            [
+                PriceDeviation {
+                    token: token(2),
+                    auction: price(2),
+                    implied: eth::U256::exp10(18) * 3,
+                    relative: 0.5,
+                },
+                PriceDeviation {
+                    token: token(3),
+                    auction: price(4),
+                    implied: eth::U256::exp10(18) * 4,
+                    relative: 0.,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_settlements_without_priced_volume() {
+        let prices = HashMap::from([(token(1), price(1))]);
+        let trades = [trade((1, 1), (2, 1), 100), trade((3, 1), (4, 1), 100)];
+
+        assert!(price_deviations(trades, &prices, eth::U256::from(5).into()).is_empty());
+    }
+}
@@ -47,10 +47,6 @@ impl Trade {
         fee_policies: &HashMap<OrderUid, impl AsRef<[fee::Policy]>>,
         native_prices: &domain::auction::Prices,
     ) -> Result<eth::Ether, Error> {
-        let native_price_buy = native_prices
-            .get(&self.buy.token)
-            .ok_or(Error::MissingPrice(self.buy.token))?;
-
         let surplus_in_surplus_token = {
             let user_surplus = self.surplus_over_limit_price()?.0;
             let fees: eth::U256 = self.protocol_fees(fee_policies)?.into_iter().try_fold(
@@ -65,9 +61,9 @@ impl Trade {
                 .ok_or(Error::Math(Math::Overflow))?
         };
 
-        let score = match self.side {
-            // `surplus` of sell orders is already in buy tokens so we simply convert it to ETH
-            Side::Sell => native_price_buy.in_eth(eth::TokenAmount(surplus_in_surplus_token)),
+        let surplus_in_buy_tokens = match self.side {
+            // `surplus` of sell orders is already in buy tokens
+            Side::Sell => surplus_in_surplus_token,
             Side::Buy => {
                 // `surplus` of buy orders is in sell tokens. We start with following formula:
                 // buy_amount / sell_amount == buy_price / sell_price
@@ -78,19 +74,19 @@ impl Trade {
                 // to avoid loss of precision because we work with integers we first multiply
                 // and then divide:
                 // buy_amount = surplus * buy_price / sell_price
-                let surplus_in_buy_tokens: eth::U256 = surplus_in_surplus_token
+                surplus_in_surplus_token
                     .full_mul(self.buy.amount.0)
                     .checked_div(self.sell.amount.0.into())
                     .ok_or(Error::Math(Math::DivisionByZero))?
                     .try_into()
-                    .map_err(|_| Error::Math(Math::Overflow))?;
-
-                // Afterwards we convert the buy token surplus to the native token.
-                native_price_buy.in_eth(surplus_in_buy_tokens.into())
+                    .map_err(|_| Error::Math(Math::Overflow))?
             }
         };
 
-        Ok(score)
+        // Afterwards we convert the buy token surplus to the native token.
+        auction::price_in_eth(native_prices, self.buy.token, surplus_in_buy_tokens)
+            .map(eth::Ether)
+            .ok_or(Error::MissingPrice(self.buy.token))
     }
 
     /// A general surplus function.
@@ -152,11 +148,9 @@ impl Trade {
     pub fn surplus_in_ether(&self, prices: &auction::Prices) -> Result<eth::Ether, Error> {
         let surplus_amount = self.surplus_over_limit_price()?;
         let surplus_token = self.surplus_token();
-        let price = prices
-            .get(&surplus_token)
-            .ok_or(Error::MissingPrice(surplus_token))?;
-
-        Ok(price.in_eth(eth::TokenAmount(surplus_amount.0)))
+        auction::price_in_eth(prices, surplus_token, surplus_amount.0)
+            .map(eth::Ether)
+            .ok_or(Error::MissingPrice(surplus_token))
     }
 
     /// Total fee (protocol fee + network fee). Equal to a surplus difference
@@ -164,10 +158,9 @@ impl Trade {
     pub fn fee_in_ether(&self, prices: &auction::Prices) -> Result<eth::Ether, Error> {
         let fee = self.fee()?;
         let fee_token = self.surplus_token();
-        let price = prices
-            .get(&fee_token)
-            .ok_or(Error::MissingPrice(fee_token))?;
-        Ok(price.in_eth(eth::TokenAmount(fee.0)))
+        auction::price_in_eth(prices, fee_token, fee.0)
+            .map(eth::Ether)
+            .ok_or(Error::MissingPrice(fee_token))
     }
 
     /// Converts given surplus fee into sell token fee.
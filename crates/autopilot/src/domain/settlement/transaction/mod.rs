@@ -4,7 +4,10 @@ use {
         domain::{self, auction::order, eth},
     },
     ethcontract::{BlockId, common::FunctionExt},
-    std::{collections::HashSet, sync::LazyLock},
+    std::{
+        collections::{HashMap, HashSet},
+        sync::LazyLock,
+    },
 };
 
 mod tokenized;
@@ -84,22 +87,7 @@ impl Transaction {
         let block = BlockId::Number(transaction.block.0.into());
         let solver = find_solver_address(authenticator, callers, block).await?;
 
-        /// Number of bytes that may be appended to the calldata to store an
-        /// auction id.
-        const META_DATA_LEN: usize = 8;
-
-        let (data, metadata) = calldata.0.split_at(
-            calldata
-                .0
-                .len()
-                .checked_sub(META_DATA_LEN)
-                // should contain at META_DATA_LEN bytes for auction id
-                .ok_or(Error::MissingCalldata)?,
-        );
-        let metadata: Option<[u8; META_DATA_LEN]> = metadata.try_into().ok();
-        let auction_id = metadata
-            .map(crate::domain::auction::Id::from_be_bytes)
-            .ok_or(Error::MissingAuctionId)?;
+        let (data, auction_id) = split_metadata(&calldata.0)?;
         Ok(Self {
             hash: transaction.hash,
             auction_id,
@@ -108,69 +96,164 @@ impl Transaction {
             gas: transaction.gas,
             gas_price: transaction.gas_price,
             solver: solver.ok_or(Error::MissingSolver)?,
-            trades: {
-                let tokenized::Tokenized {
-                    tokens,
-                    clearing_prices,
-                    trades: decoded_trades,
-                    interactions: _interactions,
-                } = tokenized::Tokenized::try_new(&crate::util::Bytes(data.to_vec()))?;
-
-                let mut trades = Vec::with_capacity(decoded_trades.len());
-                for trade in decoded_trades {
-                    let flags = tokenized::TradeFlags(trade.8);
-                    let sell_token_index = trade.0.as_usize();
-                    let buy_token_index = trade.1.as_usize();
-                    let sell_token = tokens[sell_token_index];
-                    let buy_token = tokens[buy_token_index];
-                    let uniform_sell_token_index = tokens
-                        .iter()
-                        .position(|token| token == &sell_token)
-                        .unwrap();
-                    let uniform_buy_token_index =
-                        tokens.iter().position(|token| token == &buy_token).unwrap();
-                    trades.push(EncodedTrade {
-                        uid: tokenized::order_uid(&trade, &tokens, domain_separator)
-                            .map_err(Error::OrderUidRecover)?,
-                        sell: eth::Asset {
-                            token: sell_token.into(),
-                            amount: trade.3.into(),
-                        },
-                        buy: eth::Asset {
-                            token: buy_token.into(),
-                            amount: trade.4.into(),
-                        },
-                        side: flags.side(),
-                        receiver: trade.2.into(),
-                        valid_to: trade.5,
-                        app_data: domain::auction::order::AppDataHash(trade.6.0),
-                        fee_amount: trade.7.into(),
-                        sell_token_balance: flags.sell_token_balance().into(),
-                        buy_token_balance: flags.buy_token_balance().into(),
-                        partially_fillable: flags.partially_fillable(),
-                        signature: (boundary::Signature::from_bytes(
-                            flags.signing_scheme(),
-                            &trade.10.0,
-                        )
-                        .map_err(Error::SignatureRecover)?)
-                        .into(),
-                        executed: trade.9.into(),
-                        prices: Prices {
-                            uniform: ClearingPrices {
-                                sell: clearing_prices[uniform_sell_token_index].into(),
-                                buy: clearing_prices[uniform_buy_token_index].into(),
-                            },
-                            custom: ClearingPrices {
-                                sell: clearing_prices[sell_token_index].into(),
-                                buy: clearing_prices[buy_token_index].into(),
-                            },
-                        },
+            trades: Decoded::try_new(data, domain_separator)?.trades,
+        })
+    }
+}
+
+/// The arguments of a `settle()` call decoded from its calldata.
+#[derive(Debug, Clone)]
+pub struct Decoded {
+    pub tokens: Vec<eth::TokenAddress>,
+    /// Clearing prices indexed like `tokens`.
+    pub clearing_prices: Vec<eth::U256>,
+    pub trades: Vec<EncodedTrade>,
+    /// Pre-, intra- and post-interactions.
+    pub interactions: [Vec<Interaction>; 3],
+}
+
+impl Decoded {
+    /// Decodes `settle()` calldata that doesn't have any metadata appended.
+    pub fn try_new(
+        calldata: &[u8],
+        domain_separator: &eth::DomainSeparator,
+    ) -> Result<Self, Error> {
+        let tokenized::Tokenized {
+            tokens,
+            clearing_prices,
+            trades: decoded_trades,
+            interactions,
+        } = tokenized::Tokenized::try_new(&crate::util::Bytes(calldata.to_vec()))?;
+
+        let mut trades = Vec::with_capacity(decoded_trades.len());
+        for trade in decoded_trades {
+            let flags = tokenized::TradeFlags(trade.8);
+            let sell_token_index = trade.0.as_usize();
+            let buy_token_index = trade.1.as_usize();
+            let sell_token = *tokens
+                .get(sell_token_index)
+                .ok_or(Error::InvalidTokenIndex)?;
+            let buy_token = *tokens
+                .get(buy_token_index)
+                .ok_or(Error::InvalidTokenIndex)?;
+            let uniform_sell_token_index = tokens
+                .iter()
+                .position(|token| token == &sell_token)
+                .unwrap();
+            let uniform_buy_token_index =
+                tokens.iter().position(|token| token == &buy_token).unwrap();
+            let price = |index: usize| {
+                clearing_prices
+                    .get(index)
+                    .map(|price| price.0)
+                    .ok_or(Error::InvalidTokenIndex)
+            };
+            trades.push(EncodedTrade {
+                uid: tokenized::order_uid(&trade, &tokens, domain_separator)
+                    .map_err(Error::OrderUidRecover)?,
+                sell: eth::Asset {
+                    token: sell_token.into(),
+                    amount: trade.3.into(),
+                },
+                buy: eth::Asset {
+                    token: buy_token.into(),
+                    amount: trade.4.into(),
+                },
+                side: flags.side(),
+                receiver: trade.2.into(),
+                valid_to: trade.5,
+                app_data: domain::auction::order::AppDataHash(trade.6.0),
+                fee_amount: trade.7.into(),
+                sell_token_balance: flags.sell_token_balance().into(),
+                buy_token_balance: flags.buy_token_balance().into(),
+                partially_fillable: flags.partially_fillable(),
+                signature: (boundary::Signature::from_bytes(flags.signing_scheme(), &trade.10.0)
+                    .map_err(Error::SignatureRecover)?)
+                .into(),
+                executed: trade.9.into(),
+                prices: Prices {
+                    uniform: ClearingPrices {
+                        sell: price(uniform_sell_token_index)?,
+                        buy: price(uniform_buy_token_index)?,
+                    },
+                    custom: ClearingPrices {
+                        sell: price(sell_token_index)?,
+                        buy: price(buy_token_index)?,
+                    },
+                },
+            })
+        }
+
+        Ok(Self {
+            tokens: tokens.into_iter().map(Into::into).collect(),
+            clearing_prices: clearing_prices.into_iter().map(|price| price.0).collect(),
+            trades,
+            interactions: interactions.map(|interactions| {
+                interactions
+                    .into_iter()
+                    .map(|(target, value, calldata)| Interaction {
+                        target: target.into(),
+                        value: value.into(),
+                        calldata: calldata.0,
                     })
-                }
-                trades
-            },
+                    .collect()
+            }),
         })
     }
+
+    /// Decodes the `settle()` call executed by a mined settlement
+    /// transaction.
+    pub fn from_transaction(
+        transaction: &eth::Transaction,
+        domain_separator: &eth::DomainSeparator,
+        settlement_contract: eth::Address,
+    ) -> Result<Self, Error> {
+        let (trace, _) =
+            find_settlement_trace_and_callers(&transaction.trace_calls, settlement_contract)
+                .ok_or(Error::MissingCalldata)?;
+        let (data, _) = split_metadata(&trace.input.0)?;
+        Self::try_new(data, domain_separator)
+    }
+
+    /// The uniform clearing price of every token. Tokens can show up multiple
+    /// times because trades with custom prices get their own entries, so the
+    /// uniform price is the one of the first occurrence.
+    pub fn uniform_prices(&self) -> HashMap<eth::TokenAddress, eth::U256> {
+        let mut prices = HashMap::new();
+        for (token, price) in self.tokens.iter().zip(&self.clearing_prices) {
+            prices.entry(*token).or_insert(*price);
+        }
+        prices
+    }
+}
+
+/// An interaction executed as part of a settlement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interaction {
+    pub target: eth::Address,
+    pub value: eth::Ether,
+    pub calldata: Vec<u8>,
+}
+
+/// Splits the auction id appended to settlement calldata off the `settle()`
+/// calldata.
+fn split_metadata(calldata: &[u8]) -> Result<(&[u8], domain::auction::Id), Error> {
+    /// Number of bytes that may be appended to the calldata to store an
+    /// auction id.
+    const META_DATA_LEN: usize = 8;
+
+    let (data, metadata) = calldata.split_at(
+        calldata
+            .len()
+            .checked_sub(META_DATA_LEN)
+            // should contain at META_DATA_LEN bytes for auction id
+            .ok_or(Error::MissingCalldata)?,
+    );
+    let metadata: Option<[u8; META_DATA_LEN]> = metadata.try_into().ok();
+    let auction_id = metadata
+        .map(crate::domain::auction::Id::from_be_bytes)
+        .ok_or(Error::MissingAuctionId)?;
+    Ok((data, auction_id))
 }
 
 fn find_settlement_trace_and_callers(
@@ -271,6 +354,8 @@ pub enum Error {
     MissingAuctionId,
     #[error(transparent)]
     Decoding(#[from] tokenized::error::Decoding),
+    #[error("trade references a token that isn't part of the settlement")]
+    InvalidTokenIndex,
     #[error("failed to recover order uid {0}")]
     OrderUidRecover(tokenized::error::Uid),
     #[error("failed to recover signature {0}")]
@@ -278,3 +363,185 @@ pub enum Error {
     #[error("failed to check authentication {0}")]
     Authentication(#[source] ethcontract::errors::MethodError),
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        ethcontract::{Bytes, H160, U256, tokens::Tokenize},
+        rand::{Rng, SeedableRng, rngs::StdRng},
+    };
+
+    const DOMAIN_SEPARATOR: eth::DomainSeparator = eth::DomainSeparator([7; 32]);
+
+    /// Encodes `settle()` calldata the same way the driver does.
+    fn encode(solution: tokenized::Solution) -> Vec<u8> {
+        let function = contracts::GPv2Settlement::raw_contract()
+            .interface
+            .abi
+            .function("settle")
+            .unwrap();
+        let web3::ethabi::Token::Tuple(params) = solution.into_token() else {
+            unreachable!("solutions get tokenized as tuples");
+        };
+        function.encode_input(&params).unwrap()
+    }
+
+    /// Returns a random pre-signed trade together with the uid of the order
+    /// it settles.
+    fn random_trade(rng: &mut StdRng, tokens: &[H160]) -> (tokenized::Trade, domain::OrderUid) {
+        let owner = H160(rng.r#gen());
+        let sell_token_index = rng.gen_range(0..tokens.len());
+        let buy_token_index = rng.gen_range(0..tokens.len());
+        let sell_token_balance = [0x00, 0x08, 0x0c][rng.gen_range(0..3)];
+        let flags = 0x60 // pre-sign
+            | u8::from(rng.r#gen::<bool>()) // buy order
+            | (u8::from(rng.r#gen::<bool>()) << 1) // partially fillable
+            | sell_token_balance
+            | (u8::from(rng.r#gen::<bool>()) << 4); // internal buy token balance
+        let trade: tokenized::Trade = (
+            sell_token_index.into(),
+            buy_token_index.into(),
+            H160(rng.r#gen()),
+            rng.r#gen::<u128>().into(),
+            rng.r#gen::<u128>().into(),
+            rng.r#gen(),
+            Bytes(rng.r#gen()),
+            rng.r#gen::<u64>().into(),
+            flags.into(),
+            rng.r#gen::<u128>().into(),
+            Bytes(owner.0.to_vec()),
+        );
+
+        let flags = tokenized::TradeFlags(trade.8);
+        let order = model::order::OrderData {
+            sell_token: tokens[sell_token_index],
+            buy_token: tokens[buy_token_index],
+            receiver: Some(trade.2),
+            sell_amount: trade.3,
+            buy_amount: trade.4,
+            valid_to: trade.5,
+            app_data: app_data::AppDataHash(trade.6.0),
+            fee_amount: trade.7,
+            kind: match flags.side() {
+                order::Side::Buy => model::order::OrderKind::Buy,
+                order::Side::Sell => model::order::OrderKind::Sell,
+            },
+            partially_fillable: flags.partially_fillable(),
+            sell_token_balance: flags.sell_token_balance(),
+            buy_token_balance: flags.buy_token_balance(),
+        };
+        let uid = order.uid(&boundary::DomainSeparator(DOMAIN_SEPARATOR.0), &owner);
+        (trade, uid.into())
+    }
+
+    #[test]
+    fn decodes_encoded_settlements() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let mut tokens: Vec<H160> = (0..rng.gen_range(1..5))
+                .map(|_| H160(rng.r#gen()))
+                .collect();
+            // trades with custom prices reference duplicated tokens
+            tokens.push(tokens[0]);
+            let clearing_prices: Vec<U256> =
+                tokens.iter().map(|_| rng.r#gen::<u128>().into()).collect();
+            let (trades, uids): (Vec<_>, Vec<_>) = (0..rng.gen_range(0..5))
+                .map(|_| random_trade(&mut rng, &tokens))
+                .unzip();
+            let interactions: [Vec<tokenized::Interaction>; 3] = std::array::from_fn(|_| {
+                (0..rng.gen_range(0..3))
+                    .map(|_| {
+                        let calldata = (0..rng.gen_range(0..64)).map(|_| rng.r#gen()).collect();
+                        (
+                            H160(rng.r#gen()),
+                            rng.r#gen::<u64>().into(),
+                            Bytes(calldata),
+                        )
+                    })
+                    .collect()
+            });
+
+            let calldata = encode((
+                tokens.clone(),
+                clearing_prices.clone(),
+                trades.clone(),
+                interactions.clone(),
+            ));
+            let decoded = Decoded::try_new(&calldata, &DOMAIN_SEPARATOR).unwrap();
+
+            assert_eq!(
+                decoded.tokens,
+                tokens
+                    .iter()
+                    .copied()
+                    .map(eth::TokenAddress)
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(decoded.clearing_prices, clearing_prices);
+            assert_eq!(
+                decoded
+                    .trades
+                    .iter()
+                    .map(|trade| trade.uid)
+                    .collect::<Vec<_>>(),
+                uids
+            );
+            for (decoded, trade) in decoded.trades.iter().zip(&trades) {
+                assert_eq!(decoded.executed.0, trade.9);
+                assert_eq!(
+                    decoded.prices.custom.sell,
+                    clearing_prices[trade.0.as_usize()]
+                );
+                assert_eq!(
+                    decoded.prices.custom.buy,
+                    clearing_prices[trade.1.as_usize()]
+                );
+            }
+            // the duplicated token keeps the price of its first occurrence
+            assert_eq!(
+                decoded.uniform_prices()[&eth::TokenAddress(tokens[0])],
+                clearing_prices[0]
+            );
+            for (decoded, interactions) in decoded.interactions.iter().zip(&interactions) {
+                assert_eq!(
+                    decoded,
+                    &interactions
+                        .iter()
+                        .map(|(target, value, calldata)| Interaction {
+                            target: (*target).into(),
+                            value: (*value).into(),
+                            calldata: calldata.0.clone(),
+                        })
+                        .collect::<Vec<_>>()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_calldata() {
+        let calldata = encode((vec![], vec![], vec![], Default::default()));
+        assert!(Decoded::try_new(&calldata, &DOMAIN_SEPARATOR).is_ok());
+
+        assert!(matches!(
+            Decoded::try_new(&calldata[4..], &DOMAIN_SEPARATOR),
+            Err(Error::Decoding(_))
+        ));
+        assert!(matches!(
+            Decoded::try_new(&calldata[..calldata.len() - 1], &DOMAIN_SEPARATOR),
+            Err(Error::Decoding(_))
+        ));
+
+        // trade referencing a token that doesn't exist
+        let mut rng = StdRng::seed_from_u64(0);
+        let token = H160(rng.r#gen());
+        let (mut trade, _) = random_trade(&mut rng, &[token]);
+        trade.1 = 1.into();
+        let calldata = encode((vec![token], vec![1.into()], vec![trade], Default::default()));
+        assert!(matches!(
+            Decoded::try_new(&calldata, &DOMAIN_SEPARATOR),
+            Err(Error::InvalidTokenIndex)
+        ));
+    }
+}
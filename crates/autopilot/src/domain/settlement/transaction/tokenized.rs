@@ -42,7 +42,7 @@ impl Tokenized {
 }
 
 type Token = Address;
-type Trade = (
+pub(super) type Trade = (
     U256,            // sellTokenIndex
     U256,            // buyTokenIndex
     Address,         // receiver
@@ -55,8 +55,8 @@ type Trade = (
     U256,            // executedAmount
     Bytes<Vec<u8>>,  // signature
 );
-type Interaction = (Address, U256, Bytes<Vec<u8>>);
-type Solution = (Vec<Address>, Vec<U256>, Vec<Trade>, [Vec<Interaction>; 3]);
+pub(super) type Interaction = (Address, U256, Bytes<Vec<u8>>);
+pub(super) type Solution = (Vec<Address>, Vec<U256>, Vec<Trade>, [Vec<Interaction>; 3]);
 
 /// Recover order uid from order data and signature
 pub fn order_uid(
@@ -0,0 +1,580 @@
+//! Snapshot of the chain state an auction needs, fetched with a single batched
+//! JSON-RPC request instead of one round trip per piece of data.
+
+use {
+    super::{Error, Ethereum, buffers},
+    crate::domain::eth,
+    anyhow::{Context, anyhow},
+    ethcontract::jsonrpc::Call,
+    ethrpc::{block_stream::BlockInfo, multicall::MulticallExt},
+    gas_estimation::GasPrice1559,
+    itertools::Itertools,
+    primitive_types::{H160, H256, U256},
+    serde_json::Value,
+    std::{
+        collections::{HashMap, HashSet},
+        time::Instant,
+    },
+    web3::{
+        BatchTransport,
+        helpers,
+        types::{BlockId, BlockNumber, CallRequest},
+    },
+};
+
+/// Percentile of the priority fees paid in the block that gets requested from
+/// the fee history.
+const PRIORITY_FEE_PERCENTILE: f64 = 50.;
+
+/// Maximum number of `balanceOf` calls sent in a single batch so auctions with
+/// many tokens don't result in one huge request.
+const MAX_BATCH_SIZE: usize = 500;
+
+/// Chain state at a specific block.
+#[derive(Debug, Clone)]
+pub struct BatchedState {
+    pub block: u64,
+    pub hash: H256,
+    /// Balances of the settlement contract. The native token balance is
+    /// stored as [`eth::NATIVE_TOKEN`]. Tokens whose balance could not be
+    /// fetched are missing.
+    pub balances: HashMap<eth::TokenAddress, U256>,
+    /// `None` if the node failed to report the fee history.
+    pub fees: Option<Fees>,
+    /// All tokens whose balances were requested.
+    tokens: HashSet<eth::TokenAddress>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fees {
+    /// Base fee of the block following the snapshot's block.
+    pub base_fee: U256,
+    /// Median priority fee paid in the snapshot's block.
+    pub priority_fee: U256,
+}
+
+impl Fees {
+    /// Gas price of a transaction that should get included in the next block.
+    /// The max fee allows the base fee to double before the transaction
+    /// becomes unexecutable.
+    pub fn gas_price(&self) -> GasPrice1559 {
+        let base_fee = self.base_fee.to_f64_lossy();
+        let priority_fee = self.priority_fee.to_f64_lossy();
+        GasPrice1559 {
+            base_fee_per_gas: base_fee,
+            max_fee_per_gas: base_fee * 2. + priority_fee,
+            max_priority_fee_per_gas: priority_fee,
+        }
+    }
+}
+
+impl BatchedState {
+    /// Fetches the state at `block`. The block header, fee history and native
+    /// balance get fetched with a single batched request and the token
+    /// balances with batches of at most [`MAX_BATCH_SIZE`] calls. Falls back to
+    /// individual requests if the node rejects a batch.
+    pub async fn fetch(
+        eth: &Ethereum,
+        block: &BlockInfo,
+        tokens: impl IntoIterator<Item = eth::TokenAddress>,
+    ) -> Result<Self, Error> {
+        let tokens: Vec<_> = tokens
+            .into_iter()
+            .filter(|token| *token != eth::NATIVE_TOKEN)
+            .unique()
+            .collect();
+        let settlement = eth.contracts.settlement().address();
+
+        let (header, mut balances) = tokio::join!(
+            header(eth, block.number, settlement),
+            balances(eth, block.number, settlement, &tokens),
+        );
+        let (hash, fees, native) = header?;
+        if hash != block.hash {
+            return Err(Error::InvalidResponse(anyhow!(
+                "block {} has hash {hash:?} instead of {:?}, reorg?",
+                block.number,
+                block.hash
+            )));
+        }
+        balances.extend(native.map(|balance| (eth::NATIVE_TOKEN, balance)));
+        Ok(Self {
+            block: block.number,
+            hash,
+            balances,
+            fees,
+            tokens: tokens.into_iter().collect(),
+        })
+    }
+
+    /// Returns a copy of the snapshot that additionally contains the balances
+    /// of `tokens`. Only the balances that are not part of the snapshot yet get
+    /// fetched.
+    pub async fn extend(
+        &self,
+        eth: &Ethereum,
+        tokens: impl IntoIterator<Item = eth::TokenAddress>,
+    ) -> Self {
+        let missing = self.missing(tokens);
+        let settlement = eth.contracts.settlement().address();
+        let mut state = self.clone();
+        state
+            .balances
+            .extend(balances(eth, self.block, settlement, &missing).await);
+        state.tokens.extend(missing);
+        state
+    }
+
+    /// Whether the snapshot contains the balances of all `tokens`.
+    pub fn covers(&self, tokens: &HashSet<eth::TokenAddress>) -> bool {
+        tokens
+            .iter()
+            .all(|token| *token == eth::NATIVE_TOKEN || self.tokens.contains(token))
+    }
+
+    /// Tokens whose balances were not requested for this snapshot yet.
+    fn missing(
+        &self,
+        tokens: impl IntoIterator<Item = eth::TokenAddress>,
+    ) -> Vec<eth::TokenAddress> {
+        tokens
+            .into_iter()
+            .filter(|token| *token != eth::NATIVE_TOKEN && !self.tokens.contains(token))
+            .unique()
+            .collect()
+    }
+}
+
+type Header = (H256, Option<Fees>, Option<U256>);
+
+/// Fetches the block hash, the fee history and the settlement contract's native
+/// balance with a single batch.
+async fn header(eth: &Ethereum, block: u64, settlement: H160) -> Result<Header, Error> {
+    let transport = eth.unbuffered_web3.transport();
+    let requests = header_requests(transport, block, settlement);
+    match send(transport, requests).await {
+        Some(responses) => parse_header(responses),
+        None => fetch_header_individually(eth, block, settlement).await,
+    }
+}
+
+/// Fetches the settlement contract's balances of all `tokens` with one batch
+/// per [`MAX_BATCH_SIZE`] tokens. Tokens whose balance could not be fetched are
+/// missing.
+async fn balances(
+    eth: &Ethereum,
+    block: u64,
+    settlement: H160,
+    tokens: &[eth::TokenAddress],
+) -> HashMap<eth::TokenAddress, U256> {
+    let transport = eth.unbuffered_web3.transport();
+    let batches = tokens.chunks(MAX_BATCH_SIZE).map(|chunk| async move {
+        match batch_balances(transport, block, settlement, chunk).await {
+            Some(balances) => balances,
+            None => fetch_balances_individually(eth, block, settlement, chunk).await,
+        }
+    });
+    futures::future::join_all(batches)
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Fetches the balances of `tokens` with a single batch. Returns `None` if the
+/// node rejected the batch.
+async fn batch_balances(
+    transport: &impl BatchTransport,
+    block: u64,
+    settlement: H160,
+    tokens: &[eth::TokenAddress],
+) -> Option<HashMap<eth::TokenAddress, U256>> {
+    let requests = balance_requests(transport, block, settlement, tokens);
+    let responses = send(transport, requests).await?;
+    Some(parse_balances(responses, tokens))
+}
+
+/// Sends the requests as a single batch. Returns `None` if the node rejected
+/// the batch.
+async fn send(
+    transport: &impl BatchTransport,
+    requests: Vec<(web3::RequestId, Call)>,
+) -> Option<Vec<web3::Result<Value>>> {
+    Metrics::get().batch_size.observe(requests.len() as f64);
+    let start = Instant::now();
+    let responses = transport.send_batch(requests).await;
+    Metrics::latency("batch", start);
+    responses
+        .inspect_err(|err| tracing::debug!(?err, "batch request rejected"))
+        .ok()
+}
+
+/// Requests the block header, the fee history and the settlement contract's
+/// native balance.
+fn header_requests(
+    transport: &impl BatchTransport,
+    block: u64,
+    settlement: H160,
+) -> Vec<(web3::RequestId, Call)> {
+    let block = helpers::serialize(&BlockNumber::Number(block.into()));
+    vec![
+        transport.prepare(
+            "eth_getBlockByNumber",
+            vec![block.clone(), helpers::serialize(&false)],
+        ),
+        transport.prepare(
+            "eth_feeHistory",
+            vec![
+                helpers::serialize(&U256::one()),
+                block.clone(),
+                helpers::serialize(&[PRIORITY_FEE_PERCENTILE]),
+            ],
+        ),
+        transport.prepare(
+            "eth_getBalance",
+            vec![helpers::serialize(&settlement), block],
+        ),
+    ]
+}
+
+/// Requests one `balanceOf` call per token.
+fn balance_requests(
+    transport: &impl BatchTransport,
+    block: u64,
+    settlement: H160,
+    tokens: &[eth::TokenAddress],
+) -> Vec<(web3::RequestId, Call)> {
+    let block = helpers::serialize(&BlockNumber::Number(block.into()));
+    let calldata = buffers::balance_of_calldata(settlement);
+    tokens
+        .iter()
+        .map(|token| {
+            let call = CallRequest {
+                to: Some(token.0),
+                data: Some(calldata.clone().into()),
+                ..Default::default()
+            };
+            transport.prepare("eth_call", vec![helpers::serialize(&call), block.clone()])
+        })
+        .collect()
+}
+
+/// Parses the responses to the [`header_requests`]. Only the block header is
+/// required, everything else is omitted if it could not be fetched.
+fn parse_header(responses: Vec<web3::Result<Value>>) -> Result<Header, Error> {
+    let [header, fees, native] = <[_; 3]>::try_from(responses)
+        .map_err(|_| Error::InvalidResponse(anyhow!("unexpected number of responses")))?;
+
+    let header: web3::types::Block<H256> = parse(header)?;
+    let hash = header
+        .hash
+        .ok_or(Error::InvalidResponse(anyhow!("missing block hash")))?;
+    let fees = parse(fees)
+        .inspect_err(|err| tracing::debug!(?err, "failed to fetch fee history"))
+        .ok()
+        .and_then(|history| self::fees(&history));
+    let native = parse::<U256>(native)
+        .inspect_err(|err| tracing::debug!(?err, "failed to fetch native settlement buffer"))
+        .ok();
+    Ok((hash, fees, native))
+}
+
+/// Parses the responses to the [`balance_requests`]. Tokens whose balance
+/// could not be fetched are omitted.
+fn parse_balances(
+    responses: Vec<web3::Result<Value>>,
+    tokens: &[eth::TokenAddress],
+) -> HashMap<eth::TokenAddress, U256> {
+    if responses.len() != tokens.len() {
+        tracing::debug!(
+            responses = responses.len(),
+            tokens = tokens.len(),
+            "unexpected number of settlement buffer responses"
+        );
+        return Default::default();
+    }
+    tokens
+        .iter()
+        .zip(responses)
+        .filter_map(|(token, response)| {
+            let balance = parse::<web3::types::Bytes>(response)
+                .ok()
+                .and_then(|data| buffers::decode_balance(&data.0));
+            if balance.is_none() {
+                tracing::debug!(?token, "failed to fetch settlement buffer");
+            }
+            Some((*token, balance?))
+        })
+        .collect()
+}
+
+fn parse<T: serde::de::DeserializeOwned>(response: web3::Result<Value>) -> Result<T, Error> {
+    let value = response?;
+    serde_json::from_value(value)
+        .context("unexpected response format")
+        .map_err(Error::InvalidResponse)
+}
+
+fn fees(history: &web3::types::FeeHistory) -> Option<Fees> {
+    Some(Fees {
+        base_fee: *history.base_fee_per_gas.last()?,
+        priority_fee: *history.reward.as_ref()?.last()?.first()?,
+    })
+}
+
+/// Fetches the same data as [`header_requests`] with separate requests for
+/// nodes that don't support batches.
+async fn fetch_header_individually(
+    eth: &Ethereum,
+    block: u64,
+    settlement: H160,
+) -> Result<Header, Error> {
+    let start = Instant::now();
+    let number = BlockNumber::Number(block.into());
+    let web3 = eth.web3.eth();
+    let (header, fees, native) = tokio::join!(
+        web3.block(BlockId::Number(number)),
+        web3.fee_history(U256::one(), number, Some(vec![PRIORITY_FEE_PERCENTILE])),
+        web3.balance(settlement, Some(number)),
+    );
+    Metrics::latency("individual", start);
+
+    let hash = header?
+        .and_then(|header| header.hash)
+        .ok_or(Error::InvalidResponse(anyhow!("missing block {block}")))?;
+    let fees = fees
+        .inspect_err(|err| tracing::debug!(?err, "failed to fetch fee history"))
+        .ok()
+        .and_then(|history| self::fees(&history));
+    let native = native
+        .inspect_err(|err| tracing::debug!(?err, "failed to fetch native settlement buffer"))
+        .ok();
+    Ok((hash, fees, native))
+}
+
+/// Fetches the balances of `tokens` with multicalls for nodes that don't
+/// support batches.
+async fn fetch_balances_individually(
+    eth: &Ethereum,
+    block: u64,
+    settlement: H160,
+    tokens: &[eth::TokenAddress],
+) -> HashMap<eth::TokenAddress, U256> {
+    let start = Instant::now();
+    let block = Some(BlockId::Number(BlockNumber::Number(block.into())));
+    let mut balances = HashMap::new();
+    for (chunk, calls) in tokens
+        .chunks(buffers::MULTICALL_CHUNK_SIZE)
+        .zip(buffers::balance_of_calls(settlement, tokens))
+    {
+        let results = eth
+            .web3
+            .eth()
+            .multicall(calls, Default::default(), block)
+            .await;
+        for (token, result) in chunk.iter().zip(results) {
+            match result.ok().and_then(|data| buffers::decode_balance(&data)) {
+                Some(balance) => {
+                    balances.insert(*token, balance);
+                }
+                None => tracing::debug!(?token, "failed to fetch settlement buffer"),
+            }
+        }
+    }
+    Metrics::latency("individual", start);
+    balances
+}
+
+#[derive(prometheus_metric_storage::MetricStorage)]
+#[metric(subsystem = "batched_state")]
+struct Metrics {
+    /// Number of requests in a batch fetching the chain state of an auction.
+    #[metric(buckets(1, 3, 10, 50, 100, 250, 500))]
+    batch_size: prometheus::Histogram,
+
+    /// How long fetching the chain state of an auction took by whether it got
+    /// fetched with a batch or with individual requests.
+    #[metric(labels("mode"))]
+    latency: prometheus::HistogramVec,
+}
+
+impl Metrics {
+    fn get() -> &'static Self {
+        Metrics::instance(observe::metrics::get_storage_registry()).unwrap()
+    }
+
+    fn latency(mode: &str, start: Instant) {
+        Self::get()
+            .latency
+            .with_label_values(&[mode])
+            .observe(start.elapsed().as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    fn token(byte: u8) -> eth::TokenAddress {
+        eth::TokenAddress(H160([byte; 20]))
+    }
+
+    fn header(hash: H256) -> web3::Result<Value> {
+        Ok(json!({
+            "hash": hash,
+            "parentHash": H256::zero(),
+            "sha3Uncles": H256::zero(),
+            "miner": H160::zero(),
+            "stateRoot": H256::zero(),
+            "transactionsRoot": H256::zero(),
+            "receiptsRoot": H256::zero(),
+            "number": "0x10",
+            "gasUsed": "0x0",
+            "gasLimit": "0x0",
+            "extraData": "0x",
+            "logsBloom": null,
+            "timestamp": "0x0",
+            "difficulty": "0x0",
+            "uncles": [],
+            "transactions": [],
+            "size": null,
+            "mixHash": null,
+            "nonce": null,
+        }))
+    }
+
+    fn balance(value: u64) -> web3::Result<Value> {
+        Ok(json!(format!("0x{:064x}", value)))
+    }
+
+    #[test]
+    fn parses_header_responses() {
+        let hash = H256([1; 32]);
+        let responses = vec![
+            header(hash),
+            Ok(json!({
+                "oldestBlock": "0x10",
+                "baseFeePerGas": ["0x64", "0x6e"],
+                "gasUsedRatio": [0.6],
+                "reward": [["0x2"]],
+            })),
+            Ok(json!("0x5")),
+        ];
+
+        let (parsed_hash, fees, native) = parse_header(responses).unwrap();
+
+        assert_eq!(parsed_hash, hash);
+        assert_eq!(
+            fees,
+            Some(Fees {
+                base_fee: 110.into(),
+                priority_fee: 2.into(),
+            })
+        );
+        assert_eq!(native, Some(5.into()));
+    }
+
+    #[test]
+    fn requires_block_header() {
+        let responses = vec![
+            Err(web3::Error::Decoder("unknown block".into())),
+            Err(web3::Error::Decoder("unsupported".into())),
+            Ok(json!("0x5")),
+        ];
+        assert!(parse_header(responses).is_err());
+        assert!(parse_header(vec![header(H256::zero())]).is_err());
+    }
+
+    #[test]
+    fn tolerates_missing_fee_history() {
+        let responses = vec![
+            header(H256([1; 32])),
+            Err(web3::Error::Decoder("unsupported".into())),
+            Err(web3::Error::Decoder("unsupported".into())),
+        ];
+        let (_, fees, native) = parse_header(responses).unwrap();
+        assert_eq!(fees, None);
+        assert_eq!(native, None);
+    }
+
+    #[test]
+    fn parses_balance_responses() {
+        let responses = vec![
+            balance(7),
+            Err(web3::Error::Decoder("reverted".into())),
+            Ok(json!("0x")),
+        ];
+        let tokens = [token(1), token(2), token(3)];
+
+        // tokens whose balance couldn't be fetched are missing
+        assert_eq!(
+            parse_balances(responses, &tokens),
+            HashMap::from([(token(1), 7.into())])
+        );
+        // balances can't be attributed to tokens if responses are missing
+        assert!(parse_balances(vec![balance(7)], &tokens).is_empty());
+    }
+
+    #[tokio::test]
+    async fn fetches_balances_with_batch() {
+        let transport = ethrpc::mock::MockTransport::new();
+        let rejected = std::sync::atomic::AtomicBool::new(false);
+        transport
+            .mock()
+            .expect_execute_batch()
+            .returning(move |requests| {
+                if rejected.swap(true, std::sync::atomic::Ordering::SeqCst) {
+                    return Err(web3::Error::Unreachable);
+                }
+                assert!(requests.iter().all(|(method, _)| method == "eth_call"));
+                Ok((0..requests.len() as u64).map(balance).collect())
+            });
+        let tokens = [token(1), token(2)];
+
+        let balances = batch_balances(&transport, 16, H160([9; 20]), &tokens).await;
+        assert_eq!(
+            balances,
+            Some(HashMap::from([(token(1), 0.into()), (token(2), 1.into())]))
+        );
+
+        // rejected batches get retried with individual requests by the caller
+        assert_eq!(
+            batch_balances(&transport, 16, H160([9; 20]), &tokens).await,
+            None
+        );
+    }
+
+    #[test]
+    fn only_fetches_missing_tokens() {
+        let state = BatchedState {
+            block: 16,
+            hash: H256([1; 32]),
+            balances: HashMap::from([(token(1), 7.into())]),
+            fees: None,
+            tokens: HashSet::from([token(1), token(2)]),
+        };
+
+        assert_eq!(
+            state.missing([token(1), token(2), token(3), token(3), eth::NATIVE_TOKEN]),
+            vec![token(3)]
+        );
+        assert!(state.covers(&HashSet::from([token(2), eth::NATIVE_TOKEN])));
+        assert!(!state.covers(&HashSet::from([token(3)])));
+    }
+
+    #[test]
+    fn derives_gas_price_from_fees() {
+        let fees = Fees {
+            base_fee: 100.into(),
+            priority_fee: 2.into(),
+        };
+        assert_eq!(
+            fees.gas_price(),
+            GasPrice1559 {
+                base_fee_per_gas: 100.,
+                max_fee_per_gas: 202.,
+                max_priority_fee_per_gas: 2.,
+            }
+        );
+    }
+}
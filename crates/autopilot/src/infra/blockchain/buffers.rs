@@ -0,0 +1,93 @@
+//! Token balances of the settlement contract (buffers) which solvers can use
+//! to internalize trades.
+
+use {
+    crate::domain::eth,
+    ethrpc::multicall,
+    hex_literal::hex,
+    primitive_types::{H160, H256, U256},
+};
+
+/// Maximum number of `balanceOf` calls bundled into a single multicall.
+pub(super) const MULTICALL_CHUNK_SIZE: usize = 1000;
+
+/// Function selector of `balanceOf(address)`.
+const BALANCE_OF: [u8; 4] = hex!("70a08231");
+
+/// Calldata of `balanceOf(owner)`.
+pub(super) fn balance_of_calldata(owner: H160) -> Vec<u8> {
+    let mut data = BALANCE_OF.to_vec();
+    data.extend_from_slice(H256::from(owner).as_bytes());
+    data
+}
+
+/// Builds the `balanceOf(owner)` calls for all tokens split into chunks that
+/// can be executed in a single multicall each.
+pub(super) fn balance_of_calls(
+    owner: H160,
+    tokens: &[eth::TokenAddress],
+) -> Vec<Vec<multicall::Call>> {
+    let data = balance_of_calldata(owner);
+    tokens
+        .chunks(MULTICALL_CHUNK_SIZE)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .map(|token| multicall::Call {
+                    to: token.0,
+                    data: data.clone(),
+                    ..Default::default()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Decodes the return data of a `balanceOf` call.
+pub(super) fn decode_balance(data: &[u8]) -> Option<U256> {
+    (data.len() == 32).then(|| U256::from_big_endian(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_balance_calls() {
+        let owner = H160([1; 20]);
+        let tokens: Vec<_> = (0..2500_u64)
+            .map(|i| eth::TokenAddress(H160::from_low_u64_be(i)))
+            .collect();
+
+        let chunks = balance_of_calls(owner, &tokens);
+        assert_eq!(
+            chunks.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![1000, 1000, 500]
+        );
+
+        let calls: Vec<_> = chunks.into_iter().flatten().collect();
+        assert!(
+            calls
+                .iter()
+                .zip(&tokens)
+                .all(|(call, token)| call.to == token.0)
+        );
+        assert_eq!(
+            calls[0].data,
+            hex!("70a082310000000000000000000000000101010101010101010101010101010101010101")
+        );
+    }
+
+    #[test]
+    fn no_calls_without_tokens() {
+        assert!(balance_of_calls(H160::zero(), &[]).is_empty());
+    }
+
+    #[test]
+    fn decodes_balance() {
+        let mut data = [0; 32];
+        data[31] = 42;
+        assert_eq!(decode_balance(&data), Some(U256::from(42)));
+        assert_eq!(decode_balance(&data[1..]), None);
+    }
+}
@@ -0,0 +1,35 @@
+//! Gas estimates of interactions executed by the settlement contract.
+
+use {
+    crate::domain::{auction::order::Interaction, eth},
+    primitive_types::{H160, H256, U256},
+    std::collections::HashMap,
+};
+
+/// Identifies an interaction independent of where it appears in a solution.
+pub(super) type Key = (H160, U256, Vec<u8>);
+
+pub(super) fn key(interaction: &Interaction) -> Key {
+    (
+        interaction.target,
+        interaction.value,
+        interaction.call_data.clone(),
+    )
+}
+
+/// Gas estimates that were computed for the block with the given hash.
+#[derive(Debug, Default)]
+pub(super) struct Cache {
+    pub block: H256,
+    pub estimates: HashMap<Key, eth::Gas>,
+}
+
+impl Cache {
+    /// Drops all estimates if they were computed for a different block.
+    pub fn refresh(&mut self, block: H256) {
+        if self.block != block {
+            self.block = block;
+            self.estimates.clear();
+        }
+    }
+}
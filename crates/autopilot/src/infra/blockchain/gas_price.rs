@@ -0,0 +1,46 @@
+//! Gas prices derived from the fee history of the chain state snapshot.
+
+use {
+    super::Ethereum,
+    gas_estimation::{GasPrice1559, GasPriceEstimating},
+    std::{sync::Arc, time::Duration},
+};
+
+/// Estimates gas prices with the fees of the current block's
+/// [`super::BatchedState`] so they don't require a separate request per
+/// estimate. Uses the fallback estimator if the node didn't report the fees.
+pub struct GasPriceEstimator {
+    eth: Ethereum,
+    fallback: Arc<dyn GasPriceEstimating>,
+}
+
+impl GasPriceEstimator {
+    pub fn new(eth: Ethereum, fallback: Arc<dyn GasPriceEstimating>) -> Self {
+        Self { eth, fallback }
+    }
+}
+
+#[async_trait::async_trait]
+impl GasPriceEstimating for GasPriceEstimator {
+    async fn estimate_with_limits(
+        &self,
+        gas_limit: f64,
+        time_limit: Duration,
+    ) -> anyhow::Result<GasPrice1559> {
+        let fees = match self.eth.state([]).await {
+            Ok(state) => state.fees,
+            Err(err) => {
+                tracing::debug!(?err, "failed to fetch chain state for gas price");
+                None
+            }
+        };
+        match fees {
+            Some(fees) => Ok(fees.gas_price()),
+            None => {
+                self.fallback
+                    .estimate_with_limits(gas_limit, time_limit)
+                    .await
+            }
+        }
+    }
+}
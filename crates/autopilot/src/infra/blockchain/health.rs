@@ -0,0 +1,194 @@
+//! Probes whether the Ethereum node still follows the chain so that node
+//! problems can be told apart from problems of the autopilot itself.
+
+use {
+    super::{Error, Ethereum},
+    anyhow::anyhow,
+    std::time::{Duration, SystemTime, UNIX_EPOCH},
+    web3::types::{BlockId, BlockNumber, SyncState},
+};
+
+/// Result of probing the Ethereum node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeHealth {
+    /// Whether the node reports that it is still catching up with the chain.
+    pub syncing: bool,
+    /// How far the timestamp of the node's latest block lags behind the wall
+    /// clock.
+    pub block_age: Duration,
+    /// Number of peers the node is connected to. `None` if the node doesn't
+    /// expose it.
+    pub peers: Option<u64>,
+}
+
+impl NodeHealth {
+    /// Condenses the probe into a status. The node counts as stalled if its
+    /// latest block is older than `max_block_age` or it lost all its peers.
+    pub fn status(&self, max_block_age: Duration) -> NodeStatus {
+        if self.syncing {
+            NodeStatus::Syncing
+        } else if self.block_age > max_block_age || self.peers == Some(0) {
+            NodeStatus::Stalled
+        } else {
+            NodeStatus::Healthy
+        }
+    }
+}
+
+/// Coarse health of the Ethereum node.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NodeStatus {
+    #[default]
+    Healthy,
+    Syncing,
+    Stalled,
+    /// The node couldn't be probed at all.
+    Unreachable,
+}
+
+impl NodeStatus {
+    pub fn is_healthy(self) -> bool {
+        self == Self::Healthy
+    }
+
+    /// Status string reported by the liveness check.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Healthy => "node_healthy",
+            Self::Syncing => "node_syncing",
+            Self::Stalled => "node_stalled",
+            Self::Unreachable => "node_unreachable",
+        }
+    }
+}
+
+impl Ethereum {
+    /// Probes the node's sync status, the age of its latest block and its
+    /// peer count.
+    pub async fn node_health(&self) -> Result<NodeHealth, Error> {
+        let (syncing, block, peers) = futures::join!(
+            self.unbuffered_web3.eth().syncing(),
+            self.unbuffered_web3
+                .eth()
+                .block(BlockId::Number(BlockNumber::Latest)),
+            self.unbuffered_web3.net().peer_count(),
+        );
+        let block =
+            block?.ok_or_else(|| Error::InvalidResponse(anyhow!("missing latest block")))?;
+        let timestamp = UNIX_EPOCH + Duration::from_secs(block.timestamp.low_u64());
+        Ok(NodeHealth {
+            syncing: !matches!(syncing?, SyncState::NotSyncing),
+            block_age: SystemTime::now()
+                .duration_since(timestamp)
+                .unwrap_or_default(),
+            // not every node exposes the `net` namespace
+            peers: peers.ok().map(|peers| peers.low_u64()),
+        })
+    }
+
+    /// Probes the node, exports the result as metrics and condenses it into
+    /// a status. Nodes that don't answer within `timeout` are unreachable.
+    pub async fn probe_node(&self, max_block_age: Duration, timeout: Duration) -> NodeStatus {
+        let metrics = Metrics::get();
+        let status = match tokio::time::timeout(timeout, self.node_health()).await {
+            Ok(Ok(health)) => {
+                metrics.syncing.set(health.syncing.into());
+                metrics.block_age.set(health.block_age.as_secs_f64());
+                metrics.peers.set(
+                    health
+                        .peers
+                        .map_or(0, |peers| peers.try_into().unwrap_or(i64::MAX)),
+                );
+                health.status(max_block_age)
+            }
+            Ok(Err(err)) => {
+                tracing::warn!(?err, "failed to probe ethereum node");
+                metrics.peers.set(0);
+                NodeStatus::Unreachable
+            }
+            Err(_) => {
+                tracing::warn!(?timeout, "probing ethereum node timed out");
+                metrics.peers.set(0);
+                NodeStatus::Unreachable
+            }
+        };
+        for candidate in [
+            NodeStatus::Healthy,
+            NodeStatus::Syncing,
+            NodeStatus::Stalled,
+            NodeStatus::Unreachable,
+        ] {
+            metrics
+                .status
+                .with_label_values(&[candidate.as_str()])
+                .set((candidate == status).into());
+        }
+        if !status.is_healthy() {
+            tracing::warn!(status = status.as_str(), "ethereum node is unhealthy");
+        }
+        status
+    }
+}
+
+#[derive(prometheus_metric_storage::MetricStorage)]
+#[metric(subsystem = "ethereum_node")]
+struct Metrics {
+    /// Whether the node reports that it is syncing.
+    syncing: prometheus::IntGauge,
+
+    /// Age of the node's latest block in seconds.
+    block_age: prometheus::Gauge,
+
+    /// Number of peers of the node. 0 if the node doesn't expose them or
+    /// couldn't be probed so a stale count doesn't linger.
+    peers: prometheus::IntGauge,
+
+    /// Set to 1 for the status of the most recent probe and 0 otherwise.
+    #[metric(labels("status"))]
+    status: prometheus::IntGaugeVec,
+}
+
+impl Metrics {
+    fn get() -> &'static Self {
+        Metrics::instance(observe::metrics::get_storage_registry()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn condenses_probe_into_status() {
+        let max_block_age = Duration::from_secs(60);
+        let healthy = NodeHealth {
+            syncing: false,
+            block_age: Duration::from_secs(12),
+            peers: Some(20),
+        };
+        assert_eq!(healthy.status(max_block_age), NodeStatus::Healthy);
+        // nodes that don't expose their peers can still be healthy
+        let no_peer_count = NodeHealth {
+            peers: None,
+            ..healthy
+        };
+        assert_eq!(no_peer_count.status(max_block_age), NodeStatus::Healthy);
+
+        let syncing = NodeHealth {
+            syncing: true,
+            block_age: Duration::from_secs(3600),
+            ..healthy
+        };
+        assert_eq!(syncing.status(max_block_age), NodeStatus::Syncing);
+        let old_block = NodeHealth {
+            block_age: Duration::from_secs(61),
+            ..healthy
+        };
+        assert_eq!(old_block.status(max_block_age), NodeStatus::Stalled);
+        let no_peers = NodeHealth {
+            peers: Some(0),
+            ..healthy
+        };
+        assert_eq!(no_peers.status(max_block_age), NodeStatus::Stalled);
+    }
+}
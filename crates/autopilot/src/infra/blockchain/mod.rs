@@ -1,16 +1,36 @@
 use {
     self::contracts::Contracts,
-    crate::{boundary, domain::eth},
+    crate::{
+        boundary,
+        domain::{auction::order::Interaction, eth},
+    },
     chain::Chain,
     ethcontract::dyns::DynWeb3,
     ethrpc::{block_stream::CurrentBlockWatcher, extensions::DebugNamespace},
+    itertools::Itertools,
     primitive_types::U256,
-    std::time::Duration,
+    std::{
+        collections::{HashMap, HashSet},
+        sync::Arc,
+        time::Duration,
+    },
     thiserror::Error,
+    tokio::sync::Mutex,
     url::Url,
 };
 
+pub use self::{
+    batched::BatchedState,
+    gas_price::GasPriceEstimator,
+    health::{NodeHealth, NodeStatus},
+};
+
+pub mod batched;
+mod buffers;
 pub mod contracts;
+mod gas;
+mod gas_price;
+mod health;
 
 /// An Ethereum RPC connection.
 pub struct Rpc {
@@ -61,6 +81,8 @@ pub struct Ethereum {
     chain: Chain,
     current_block: CurrentBlockWatcher,
     contracts: Contracts,
+    state: Arc<Mutex<Option<Arc<BatchedState>>>>,
+    gas_estimates: Arc<Mutex<gas::Cache>>,
 }
 
 impl Ethereum {
@@ -88,6 +110,8 @@ impl Ethereum {
             unbuffered_web3,
             chain: *chain,
             contracts,
+            state: Default::default(),
+            gas_estimates: Default::default(),
         }
     }
 
@@ -95,6 +119,11 @@ impl Ethereum {
         &self.chain
     }
 
+    /// The average time between two blocks of the chain.
+    pub fn average_block_time(&self) -> Duration {
+        self.chain.block_time_in_ms()
+    }
+
     /// Returns a stream that monitors the block chain to inform about the
     /// current and new blocks.
     pub fn current_block(&self) -> &CurrentBlockWatcher {
@@ -105,6 +134,133 @@ impl Ethereum {
         &self.contracts
     }
 
+    /// Returns the chain state of the current block including the settlement
+    /// contract's balances of the given tokens. The state gets fetched with
+    /// batched requests and is cached until the next block so consecutive
+    /// auctions on the same block only fetch the balances of tokens that
+    /// weren't requested before.
+    pub async fn state(
+        &self,
+        tokens: impl IntoIterator<Item = eth::TokenAddress>,
+    ) -> Result<Arc<BatchedState>, Error> {
+        let block = *self.current_block.borrow();
+        let tokens: HashSet<_> = tokens.into_iter().collect();
+
+        let mut cache = self.state.lock().await;
+        let state = match cache.as_ref().filter(|state| state.hash == block.hash) {
+            Some(state) if state.covers(&tokens) => return Ok(state.clone()),
+            Some(state) => state.extend(self, tokens).await,
+            None => BatchedState::fetch(self, &block, tokens).await?,
+        };
+        let state = Arc::new(state);
+        *cache = Some(state.clone());
+        Ok(state)
+    }
+
+    /// Fetches the settlement contract's balances of the given tokens at the
+    /// current block. The settlement contract's native token balance gets
+    /// added to the balance of the wrapped native token since it can be
+    /// wrapped at any time. Tokens whose balance could not be fetched are
+    /// omitted.
+    pub async fn settlement_buffers(
+        &self,
+        tokens: impl IntoIterator<Item = eth::TokenAddress>,
+    ) -> Result<HashMap<eth::TokenAddress, U256>, Error> {
+        let weth = eth::TokenAddress(self.contracts.weth().address());
+        let tokens: Vec<_> = tokens.into_iter().unique().collect();
+        let state = self.state(tokens.iter().copied().chain([weth])).await?;
+
+        let native = state
+            .balances
+            .get(&eth::NATIVE_TOKEN)
+            .copied()
+            .unwrap_or_default();
+        Ok(tokens
+            .into_iter()
+            .filter(|token| *token != eth::NATIVE_TOKEN)
+            .filter_map(|token| {
+                let balance = *state.balances.get(&token)?;
+                if token == weth {
+                    return Some((token, balance.saturating_add(native)));
+                }
+                Some((token, balance))
+            })
+            .collect())
+    }
+
+    /// Estimates the gas of executing the interaction from the settlement
+    /// contract at the current block. The estimate includes the intrinsic gas
+    /// of a transaction. Estimates get cached until the next block.
+    pub async fn estimate_interaction_gas(
+        &self,
+        interaction: &Interaction,
+    ) -> Result<eth::Gas, Error> {
+        let block = *self.current_block.borrow();
+        let key = gas::key(interaction);
+        {
+            let mut cache = self.gas_estimates.lock().await;
+            cache.refresh(block.hash);
+            if let Some(gas) = cache.estimates.get(&key) {
+                return Ok(*gas);
+            }
+        }
+
+        let request = web3::types::CallRequest {
+            from: Some(self.contracts.settlement().address()),
+            to: Some(interaction.target),
+            value: Some(interaction.value),
+            data: Some(interaction.call_data.clone().into()),
+            ..Default::default()
+        };
+        let gas = eth::Gas(
+            self.web3
+                .eth()
+                .estimate_gas(
+                    request,
+                    Some(web3::types::BlockNumber::Number(block.number.into())),
+                )
+                .await?,
+        );
+
+        let mut cache = self.gas_estimates.lock().await;
+        // Don't pollute the cache if a new block arrived in the meantime.
+        if cache.block == block.hash {
+            cache.estimates.insert(key, gas);
+        }
+        Ok(gas)
+    }
+
+    /// Estimates the gas of executing the transaction at the current block.
+    pub async fn estimate_gas(&self, tx: &eth::Tx) -> Result<eth::Gas, Error> {
+        let block = self.current_block.borrow().number;
+        let request = web3::types::CallRequest {
+            from: Some(tx.from.0),
+            to: Some(tx.to.0),
+            data: Some(tx.input.clone().into()),
+            ..Default::default()
+        };
+        let gas = self
+            .web3
+            .eth()
+            .estimate_gas(
+                request,
+                Some(web3::types::BlockNumber::Number(block.into())),
+            )
+            .await?;
+        Ok(eth::Gas(gas))
+    }
+
+    /// Returns the hash of the block with the given number on the node's
+    /// current chain.
+    pub async fn block_hash(&self, number: u64) -> Result<Option<eth::H256>, Error> {
+        let block = self
+            .web3
+            .eth()
+            .block(web3::types::BlockNumber::Number(number.into()).into())
+            .await?;
+        Ok(block.and_then(|block| block.hash))
+    }
+
     pub async fn transaction(&self, hash: eth::TxId) -> Result<eth::Transaction, Error> {
         let (transaction, receipt, traces) = tokio::try_join!(
             self.web3.eth().transaction(hash.0.into()),
@@ -183,4 +339,6 @@ pub enum Error {
     TransactionNotFound,
     #[error("unsupported chain")]
     UnsupportedChain,
+    #[error("unexpected node response: {0}")]
+    InvalidResponse(anyhow::Error),
 }
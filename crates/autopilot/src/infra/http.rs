@@ -0,0 +1,491 @@
+//! Error classification and retry handling shared by the HTTP clients the
+//! autopilot uses to talk to other services (e.g. drivers).
+
+use {
+    reqwest::{StatusCode, header::HeaderMap},
+    serde::de::DeserializeOwned,
+    std::{
+        sync::Mutex,
+        time::{Duration, Instant},
+    },
+    thiserror::Error,
+    url::Url,
+};
+
+#[derive(Debug, Error)]
+pub enum HttpError {
+    #[error("failed to connect: {0}")]
+    Connect(#[source] reqwest::Error),
+    #[error("request timed out")]
+    Timeout,
+    #[error("bad status {code}: {body}")]
+    Status { code: StatusCode, body: String },
+    #[error("failed to decode response: {0}")]
+    Decode(#[source] anyhow::Error),
+    #[error("response exceeded size limit of {0} bytes")]
+    TooLarge(usize),
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl HttpError {
+    /// Label used to distinguish errors in metrics.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Connect(_) => "connect",
+            Self::Timeout => "request_timeout",
+            Self::Status { .. } => "bad_status",
+            Self::Decode(_) => "decode",
+            Self::TooLarge(_) => "too_large",
+            Self::Other(_) => "error",
+        }
+    }
+
+    /// Whether sending the same request again might succeed.
+    fn is_transient(&self) -> bool {
+        match self {
+            Self::Connect(_) | Self::Timeout => true,
+            Self::Status { code, .. } => {
+                code.is_server_error() || *code == StatusCode::TOO_MANY_REQUESTS
+            }
+            Self::Decode(_) | Self::TooLarge(_) | Self::Other(_) => false,
+        }
+    }
+}
+
+impl From<reqwest::Error> for HttpError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Self::Timeout
+        } else if err.is_connect() {
+            Self::Connect(err)
+        } else if err.is_decode() || err.is_body() {
+            Self::Decode(err.into())
+        } else {
+            Self::Other(err.into())
+        }
+    }
+}
+
+/// Whether a request can safely be sent multiple times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Idempotency {
+    Idempotent,
+    NonIdempotent,
+}
+
+/// Limits the number of retries across all requests of a client to a fraction
+/// of the requests sent. This avoids overloading a struggling server with
+/// retries.
+#[derive(Debug)]
+pub struct RetryBudget {
+    /// How many retries every request earns.
+    ratio: f64,
+    /// Maximum number of retries that can be saved up.
+    max: f64,
+    tokens: Mutex<f64>,
+}
+
+impl RetryBudget {
+    /// Creates a budget that allows `ratio` retries per request and starts
+    /// out with `max` retries available.
+    pub fn new(ratio: f64, max: f64) -> Self {
+        Self {
+            ratio,
+            max,
+            tokens: Mutex::new(max),
+        }
+    }
+
+    /// Records that a request was sent.
+    fn deposit(&self) {
+        let mut tokens = self.tokens.lock().unwrap();
+        *tokens = (*tokens + self.ratio).min(self.max);
+    }
+
+    /// Tries to take one retry out of the budget.
+    fn withdraw(&self) -> bool {
+        let mut tokens = self.tokens.lock().unwrap();
+        if *tokens < 1. {
+            return false;
+        }
+        *tokens -= 1.;
+        true
+    }
+}
+
+/// Decides whether failed requests get retried.
+#[derive(Debug)]
+pub struct RetryPolicy {
+    /// Maximum number of retries per request.
+    pub max_retries: u32,
+    /// How long to wait before sending a request again.
+    pub backoff: Duration,
+    pub budget: RetryBudget,
+}
+
+impl RetryPolicy {
+    /// Never retries any request.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            backoff: Duration::ZERO,
+            budget: RetryBudget::new(0., 0.),
+        }
+    }
+
+    /// Only idempotent requests that failed with a transient error get
+    /// retried and only if there is enough time left before the deadline and
+    /// the retry budget isn't exhausted.
+    fn should_retry(
+        &self,
+        err: &HttpError,
+        idempotency: Idempotency,
+        retries: u32,
+        remaining: Duration,
+    ) -> bool {
+        idempotency == Idempotency::Idempotent
+            && err.is_transient()
+            && retries < self.max_retries
+            && remaining > self.backoff
+            && self.budget.withdraw()
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            backoff: Duration::from_millis(100),
+            budget: RetryBudget::new(0.1, 10.),
+        }
+    }
+}
+
+/// A POST request with a JSON body.
+#[derive(Debug)]
+pub struct Request {
+    pub url: Url,
+    /// Already serialized JSON body.
+    pub body: Vec<u8>,
+    pub headers: HeaderMap,
+    /// Total time the request (including retries) may take. Falls back to
+    /// the client's default timeout if not set.
+    pub timeout: Option<Duration>,
+    pub idempotency: Idempotency,
+}
+
+/// Thin wrapper around [`reqwest::Client`] that classifies errors, enforces
+/// response size limits and retries requests according to a [`RetryPolicy`].
+#[derive(Debug)]
+pub struct Client {
+    inner: reqwest::Client,
+    retry_policy: RetryPolicy,
+    timeout: Duration,
+    response_size_limit: usize,
+}
+
+impl Client {
+    pub fn new(
+        inner: reqwest::Client,
+        retry_policy: RetryPolicy,
+        timeout: Duration,
+        response_size_limit: usize,
+    ) -> Self {
+        Self {
+            inner,
+            retry_policy,
+            timeout,
+            response_size_limit,
+        }
+    }
+
+    /// Sends the request and returns the body of the successful response.
+    pub async fn post(&self, request: &Request) -> Result<Vec<u8>, HttpError> {
+        let deadline = Instant::now() + request.timeout.unwrap_or(self.timeout);
+        let mut retries = 0;
+        loop {
+            self.retry_policy.budget.deposit();
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let err = match self.try_post(request, remaining).await {
+                Ok(body) => return Ok(body),
+                Err(err) => err,
+            };
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if !self
+                .retry_policy
+                .should_retry(&err, request.idempotency, retries, remaining)
+            {
+                return Err(err);
+            }
+            retries += 1;
+            tracing::debug!(?err, url = %request.url, retries, "retrying request");
+            tokio::time::sleep(self.retry_policy.backoff).await;
+        }
+    }
+
+    /// Sends the request and decodes the JSON body of the successful response.
+    pub async fn post_json<T: DeserializeOwned>(&self, request: &Request) -> Result<T, HttpError> {
+        let body = self.post(request).await?;
+        serde_json::from_slice(&body).map_err(|err| {
+            HttpError::Decode(anyhow::anyhow!(
+                "{err}, body {:?}",
+                String::from_utf8_lossy(&body)
+            ))
+        })
+    }
+
+    async fn try_post(&self, request: &Request, timeout: Duration) -> Result<Vec<u8>, HttpError> {
+        if timeout.is_zero() {
+            return Err(HttpError::Timeout);
+        }
+        let mut response = self
+            .inner
+            .post(request.url.clone())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .headers(request.headers.clone())
+            .body(request.body.clone())
+            .timeout(timeout)
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response_body_with_size_limit(&mut response, self.response_size_limit).await?;
+        tracing::trace!(%status, body = %String::from_utf8_lossy(&body), "response");
+        if !status.is_success() {
+            return Err(HttpError::Status {
+                code: status,
+                body: String::from_utf8_lossy(&body).into_owned(),
+            });
+        }
+        Ok(body)
+    }
+}
+
+/// Extracts the bytes of the response up to some size limit.
+///
+/// Returns an error if the byte limit was exceeded.
+async fn response_body_with_size_limit(
+    response: &mut reqwest::Response,
+    limit: usize,
+) -> Result<Vec<u8>, HttpError> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        let slice: &[u8] = &chunk;
+        if bytes.len() + slice.len() > limit {
+            return Err(HttpError::TooLarge(limit));
+        }
+        bytes.extend_from_slice(slice);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        axum::{Router, http::StatusCode as AxumStatusCode, routing::post},
+        std::{
+            net::SocketAddr,
+            sync::{
+                Arc,
+                atomic::{AtomicUsize, Ordering},
+            },
+        },
+    };
+
+    /// Starts a server on a random port that answers every request with the
+    /// given handler and counts the requests it received.
+    fn serve<F, Fut>(handler: F) -> (Url, Arc<AtomicUsize>)
+    where
+        F: Fn() -> Fut + Clone + Send + Sync + 'static,
+        Fut: std::future::Future<Output = (AxumStatusCode, String)> + Send + 'static,
+    {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let app = Router::new().route(
+            "/",
+            post(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                handler()
+            }),
+        );
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let url = format!("http://{}/", server.local_addr()).parse().unwrap();
+        tokio::spawn(server);
+        (url, hits)
+    }
+
+    fn client(retry_policy: RetryPolicy) -> Client {
+        Client::new(
+            reqwest::Client::new(),
+            retry_policy,
+            Duration::from_secs(5),
+            100,
+        )
+    }
+
+    fn request(url: Url, idempotency: Idempotency) -> Request {
+        Request {
+            url,
+            body: b"{}".to_vec(),
+            headers: Default::default(),
+            timeout: None,
+            idempotency,
+        }
+    }
+
+    #[tokio::test]
+    async fn success() {
+        let (url, hits) = serve(|| async { (AxumStatusCode::OK, "[1,2]".to_string()) });
+        let response: Vec<u8> = client(RetryPolicy::default())
+            .post_json(&request(url, Idempotency::Idempotent))
+            .await
+            .unwrap();
+        assert_eq!(response, vec![1, 2]);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn connect_error() {
+        // Bind and immediately drop a listener to get a port nobody listens on.
+        let addr: SocketAddr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let url = format!("http://{addr}/").parse().unwrap();
+        let err = client(RetryPolicy::none())
+            .post(&request(url, Idempotency::Idempotent))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HttpError::Connect(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn timeout_error() {
+        let (url, _) = serve(|| async {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            (AxumStatusCode::OK, String::new())
+        });
+        let err = client(RetryPolicy::none())
+            .post(&Request {
+                timeout: Some(Duration::from_millis(50)),
+                ..request(url, Idempotency::Idempotent)
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HttpError::Timeout), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn status_error() {
+        let (url, _) = serve(|| async { (AxumStatusCode::BAD_REQUEST, "nope".to_string()) });
+        let err = client(RetryPolicy::none())
+            .post(&request(url, Idempotency::Idempotent))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, HttpError::Status { code, body } if *code == StatusCode::BAD_REQUEST && body == "nope"),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn decode_error() {
+        let (url, _) = serve(|| async { (AxumStatusCode::OK, "not json".to_string()) });
+        let err = client(RetryPolicy::none())
+            .post_json::<Vec<u8>>(&request(url, Idempotency::Idempotent))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HttpError::Decode(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn too_large_error() {
+        let (url, _) = serve(|| async { (AxumStatusCode::OK, "a".repeat(101)) });
+        let err = client(RetryPolicy::none())
+            .post(&request(url, Idempotency::Idempotent))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HttpError::TooLarge(100)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn retries_only_idempotent_requests() {
+        let (url, hits) =
+            serve(|| async { (AxumStatusCode::INTERNAL_SERVER_ERROR, String::new()) });
+        let client = client(RetryPolicy {
+            max_retries: 2,
+            backoff: Duration::ZERO,
+            budget: RetryBudget::new(0., 10.),
+        });
+
+        let err = client
+            .post(&request(url.clone(), Idempotency::Idempotent))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HttpError::Status { .. }), "{err:?}");
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        client
+            .post(&request(url, Idempotency::NonIdempotent))
+            .await
+            .unwrap_err();
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let (url, hits) = serve(|| async { (AxumStatusCode::BAD_REQUEST, String::new()) });
+        client(RetryPolicy::default())
+            .post(&request(url, Idempotency::Idempotent))
+            .await
+            .unwrap_err();
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn retries_stop_when_budget_is_exhausted() {
+        let (url, hits) = serve(|| async { (AxumStatusCode::SERVICE_UNAVAILABLE, String::new()) });
+        // Budget allows for a single retry and requests don't earn new ones.
+        let client = client(RetryPolicy {
+            max_retries: 5,
+            backoff: Duration::ZERO,
+            budget: RetryBudget::new(0., 1.),
+        });
+
+        client
+            .post(&request(url.clone(), Idempotency::Idempotent))
+            .await
+            .unwrap_err();
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        client
+            .post(&request(url, Idempotency::Idempotent))
+            .await
+            .unwrap_err();
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn retry_budget_accounting() {
+        let budget = RetryBudget::new(0.5, 2.);
+        assert!(budget.withdraw());
+        assert!(budget.withdraw());
+        assert!(!budget.withdraw());
+
+        // every request earns half a retry
+        budget.deposit();
+        assert!(!budget.withdraw());
+        budget.deposit();
+        assert!(budget.withdraw());
+
+        // saved up retries are capped
+        for _ in 0..10 {
+            budget.deposit();
+        }
+        assert!(budget.withdraw());
+        assert!(budget.withdraw());
+        assert!(!budget.withdraw());
+    }
+}
@@ -1,11 +1,14 @@
 pub mod blockchain;
+pub mod http;
 pub mod persistence;
+pub mod run_report;
 pub mod shadow;
 pub mod solvers;
+pub mod status;
 
 pub use {
     blockchain::Ethereum,
     order_validation::banned,
     persistence::Persistence,
-    solvers::{Driver, notify_banned_solver},
+    solvers::{Driver, notify_banned_solver, record_notification},
 };
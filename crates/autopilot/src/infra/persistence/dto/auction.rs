@@ -2,32 +2,35 @@ use {
     super::order::Order,
     crate::{
         domain,
-        domain::{auction::Price, eth},
+        domain::{
+            auction::{ContentHash, Price},
+            eth,
+        },
     },
     number::serialization::HexOrDecimalU256,
     primitive_types::{H160, U256},
     serde::{Deserialize, Serialize},
     serde_with::serde_as,
-    std::collections::BTreeMap,
+    std::{collections::BTreeMap, sync::Arc},
 };
 
-pub fn from_domain(auction: domain::RawAuctionData) -> RawAuctionData {
+pub fn from_domain(auction: &domain::RawAuctionData) -> RawAuctionData {
     RawAuctionData {
         block: auction.block,
         orders: auction
             .orders
-            .into_iter()
+            .iter()
             .map(super::order::from_domain)
             .collect(),
         prices: auction
             .prices
-            .into_iter()
-            .map(|(key, value)| (key.into(), value.get().into()))
+            .iter()
+            .map(|(key, value)| ((*key).into(), value.get().into()))
             .collect(),
         surplus_capturing_jit_order_owners: auction
             .surplus_capturing_jit_order_owners
-            .into_iter()
-            .map(Into::into)
+            .iter()
+            .map(|owner| owner.0)
             .collect(),
     }
 }
@@ -57,29 +60,35 @@ pub struct Auction {
 
 impl Auction {
     pub fn try_into_domain(self) -> anyhow::Result<domain::Auction> {
+        let orders: Vec<_> = self
+            .auction
+            .orders
+            .into_iter()
+            .map(super::order::to_domain)
+            .collect();
+        let prices = self
+            .auction
+            .prices
+            .into_iter()
+            .map(|(key, value)| {
+                Price::try_new(value.into()).map(|price| (eth::TokenAddress(key), price))
+            })
+            .collect::<Result<_, _>>()?;
+        let surplus_capturing_jit_order_owners: Vec<_> = self
+            .auction
+            .surplus_capturing_jit_order_owners
+            .into_iter()
+            .map(Into::into)
+            .collect();
         Ok(domain::Auction {
             id: self.id,
             block: self.auction.block,
-            orders: self
-                .auction
-                .orders
-                .into_iter()
-                .map(super::order::to_domain)
-                .collect(),
-            prices: self
-                .auction
-                .prices
-                .into_iter()
-                .map(|(key, value)| {
-                    Price::try_new(value.into()).map(|price| (eth::TokenAddress(key), price))
-                })
-                .collect::<Result<_, _>>()?,
-            surplus_capturing_jit_order_owners: self
-                .auction
-                .surplus_capturing_jit_order_owners
-                .into_iter()
-                .map(Into::into)
-                .collect(),
+            content_hash: ContentHash::new(&orders, &prices, &surplus_capturing_jit_order_owners),
+            orders: Arc::new(orders),
+            prices: Arc::new(prices),
+            surplus_capturing_jit_order_owners,
+            quote_only: false,
+            prioritized_orders: Default::default(),
         })
     }
 }
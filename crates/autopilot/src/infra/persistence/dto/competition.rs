@@ -0,0 +1,162 @@
+//! Conversions from the domain competition types into the
+//! [`model::solver_competition`] DTOs that get stored in the
+//! `solver_competitions` table and served by the debug APIs.
+
+use {
+    crate::domain::{self, competition::Participant},
+    itertools::Itertools,
+    model::solver_competition::{CompetitionAuction, Order, Score, SolverSettlement},
+    std::cmp::Reverse,
+};
+
+pub fn order_from_domain(id: domain::OrderUid, order: &domain::competition::TradedOrder) -> Order {
+    Order::Colocated {
+        id: id.into(),
+        sell_amount: order.executed_sell.into(),
+        buy_amount: order.executed_buy.into(),
+    }
+}
+
+/// Converts a ranked participant into a [`SolverSettlement`]. `ranking`
+/// starts at 1 for the best solution.
+pub fn solution_from_domain(ranking: usize, participant: &Participant) -> SolverSettlement {
+    let solution = participant.solution();
+    SolverSettlement {
+        solver: participant.driver().name.clone(),
+        solver_address: solution.solver().0,
+        score: Some(Score::Solver(solution.score().get().0)),
+        ranking,
+        orders: solution
+            .orders()
+            .iter()
+            .map(|(id, order)| order_from_domain(*id, order))
+            .collect(),
+        clearing_prices: solution
+            .prices()
+            .iter()
+            .map(|(token, price)| (token.0, price.get().into()))
+            .collect(),
+        is_winner: participant.is_winner(),
+        filtered_out: participant.filtered_out(),
+    }
+}
+
+/// Sorts the participants enumerated by their solution uid (their position in
+/// the arbitrator's ranking) from worst to best like the solver competition
+/// table lists them: filtered out solutions first, then the ranked
+/// non-winners and the winners last, each group by score ascending. Consumers
+/// rely on the winner being the last solution. Solutions with the same score
+/// keep the order the arbitrator ranked them in.
+pub fn participants_worst_to_best<'a>(
+    participants: impl IntoIterator<Item = (usize, &'a Participant)>,
+) -> Vec<(usize, &'a Participant)> {
+    participants
+        .into_iter()
+        .sorted_by_key(|(uid, participant)| {
+            let group = match (participant.filtered_out(), participant.is_winner()) {
+                (true, _) => 0,
+                (false, false) => 1,
+                (false, true) => 2,
+            };
+            (group, participant.solution().score(), Reverse(*uid))
+        })
+        .collect()
+}
+
+/// Converts the participants enumerated by their solution uid into the
+/// solutions of the solver competition table, sorted from worst to best. The
+/// ranking is the solution uid + 1 so the best solution of the arbitrator is
+/// ranked 1.
+pub fn solutions_from_domain<'a>(
+    participants: impl IntoIterator<Item = (usize, &'a Participant)>,
+) -> Vec<SolverSettlement> {
+    participants_worst_to_best(participants)
+        .into_iter()
+        .map(|(uid, participant)| solution_from_domain(uid + 1, participant))
+        .collect()
+}
+
+pub fn auction_from_domain(auction: &domain::Auction) -> CompetitionAuction {
+    CompetitionAuction {
+        orders: auction
+            .orders
+            .iter()
+            .map(|order| order.uid.into())
+            .collect(),
+        prices: auction
+            .prices
+            .iter()
+            .map(|(key, value)| ((*key).into(), value.get().into()))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            domain::{
+                competition::{Ranked, Solution},
+                eth,
+            },
+            infra::solvers::fake::FakeDriver,
+        },
+        rand::{SeedableRng, rngs::StdRng, seq::SliceRandom},
+    };
+
+    async fn participant(id: u64, score: u64, rank: Ranked) -> Participant {
+        let driver = FakeDriver::start(Default::default())
+            .driver(&format!("solver{id}"))
+            .await;
+        let solution = Solution::new(
+            id,
+            eth::Address::default(),
+            domain::competition::Score::try_new(eth::Ether(score.into())).unwrap(),
+            Default::default(),
+            Default::default(),
+            None,
+        );
+        Participant::new(solution, driver).rank(rank)
+    }
+
+    #[tokio::test]
+    async fn ranks_solutions_independently_of_input_order() {
+        // in the order of the arbitrator's ranking, solutions 2 and 3 are tied
+        // and the winner scores lower than some of the solutions it beat
+        let participants = vec![
+            participant(1, 15, Ranked::Winner).await,
+            participant(2, 20, Ranked::NonWinner).await,
+            participant(3, 20, Ranked::NonWinner).await,
+            participant(4, 10, Ranked::NonWinner).await,
+            participant(5, 40, Ranked::FilteredOut).await,
+        ];
+
+        for seed in 0..10 {
+            let mut shuffled: Vec<_> = participants.iter().enumerate().collect();
+            shuffled.shuffle(&mut StdRng::seed_from_u64(seed));
+
+            let worst_to_best: Vec<_> = participants_worst_to_best(shuffled.iter().copied())
+                .into_iter()
+                .map(|(_, participant)| participant.solution().id())
+                .collect();
+            assert_eq!(worst_to_best, [5, 4, 3, 2, 1]);
+
+            // the ranking matches the solution uid
+            let rankings: Vec<_> = solutions_from_domain(shuffled)
+                .into_iter()
+                .map(|solution| (solution.solver, solution.ranking))
+                .collect();
+            assert_eq!(
+                rankings,
+                [
+                    ("solver5".to_string(), 5),
+                    ("solver4".to_string(), 4),
+                    ("solver3".to_string(), 3),
+                    ("solver2".to_string(), 2),
+                    ("solver1".to_string(), 1),
+                ]
+            );
+        }
+    }
+}
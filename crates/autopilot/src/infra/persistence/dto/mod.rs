@@ -1,6 +1,8 @@
 pub mod auction;
+pub mod competition;
 pub mod fee_policy;
 pub mod order;
 pub mod quote;
+pub mod reward_inputs;
 
 pub use auction::{Auction, AuctionId, RawAuctionData};
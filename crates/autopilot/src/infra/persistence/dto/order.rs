@@ -40,9 +40,13 @@ pub struct Order {
     #[serde(flatten)]
     pub signature: boundary::Signature,
     pub quote: Option<Quote>,
+    /// Whether the order is exempt from the fairness check. Auctions stored
+    /// before the exemption existed don't have the field.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fairness_exempt: bool,
 }
 
-pub fn from_domain(order: domain::Order) -> Order {
+pub fn from_domain(order: &domain::Order) -> Order {
     Order {
         uid: order.uid.into(),
         sell_token: order.sell.token.into(),
@@ -51,7 +55,8 @@ pub fn from_domain(order: domain::Order) -> Order {
         buy_amount: order.buy.amount.into(),
         protocol_fees: order
             .protocol_fees
-            .into_iter()
+            .iter()
+            .cloned()
             .map(FeePolicy::from_domain)
             .collect(),
         created: order.created,
@@ -61,18 +66,25 @@ pub fn from_domain(order: domain::Order) -> Order {
         owner: order.owner.into(),
         partially_fillable: order.partially_fillable,
         executed: order.executed.into(),
-        pre_interactions: order.pre_interactions.into_iter().map(Into::into).collect(),
+        pre_interactions: order
+            .pre_interactions
+            .iter()
+            .cloned()
+            .map(Into::into)
+            .collect(),
         post_interactions: order
             .post_interactions
-            .into_iter()
+            .iter()
+            .cloned()
             .map(Into::into)
             .collect(),
-        sell_token_balance: order.sell_token_balance.into(),
-        buy_token_balance: order.buy_token_balance.into(),
+        sell_token_balance: order.sell_token_balance.clone().into(),
+        buy_token_balance: order.buy_token_balance.clone().into(),
         class: boundary::OrderClass::Limit,
-        app_data: order.app_data.into(),
-        signature: order.signature.into(),
-        quote: order.quote.map(Quote::from_domain),
+        app_data: order.app_data.clone().into(),
+        signature: order.signature.clone().into(),
+        quote: order.quote.clone().map(Quote::from_domain),
+        fairness_exempt: order.fairness_exempt,
     }
 }
 
@@ -110,6 +122,7 @@ pub fn to_domain(order: Order) -> domain::Order {
         app_data: order.app_data.into(),
         signature: order.signature.into(),
         quote: order.quote.map(|q| q.to_domain(order.uid.into())),
+        fairness_exempt: order.fairness_exempt,
     }
 }
 
@@ -0,0 +1,125 @@
+use {
+    crate::domain::{competition::reward_inputs, eth},
+    number::serialization::HexOrDecimalU256,
+    primitive_types::{H160, H256, U256},
+    serde::{Deserialize, Serialize},
+    serde_with::serde_as,
+};
+
+pub fn winners_from_domain(winners: &[reward_inputs::Winner]) -> Vec<Winner> {
+    winners
+        .iter()
+        .map(|winner| Winner {
+            solver: winner.solver.0,
+            solution_uid: winner.solution_uid,
+            score: winner.score.0,
+            reference_score: winner.reference_score.map(|score| score.0),
+            settlement: winner.settlement.map(|settlement| Settlement {
+                tx_hash: settlement.tx.0,
+                gas_used: settlement.gas.map(|gas| gas.0),
+                gas_price: settlement.gas_price.map(|price| price.0.0),
+            }),
+            settlement_skipped: winner.settlement_skipped,
+        })
+        .collect()
+}
+
+pub fn winners_into_domain(winners: Vec<Winner>) -> Vec<reward_inputs::Winner> {
+    winners
+        .into_iter()
+        .map(|winner| reward_inputs::Winner {
+            solver: eth::Address(winner.solver),
+            solution_uid: winner.solution_uid,
+            score: winner.score.into(),
+            reference_score: winner.reference_score.map(Into::into),
+            settlement: winner
+                .settlement
+                .map(|settlement| reward_inputs::Settlement {
+                    tx: eth::TxId(settlement.tx_hash),
+                    gas: settlement.gas_used.map(eth::Gas),
+                    gas_price: settlement.gas_price.map(Into::into),
+                }),
+            settlement_skipped: winner.settlement_skipped,
+        })
+        .collect()
+}
+
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Winner {
+    pub solver: H160,
+    pub solution_uid: usize,
+    #[serde_as(as = "HexOrDecimalU256")]
+    pub score: U256,
+    #[serde_as(as = "Option<HexOrDecimalU256>")]
+    pub reference_score: Option<U256>,
+    /// `None` if the solver failed to settle the solution.
+    pub settlement: Option<Settlement>,
+    /// Whether the solution never got sent to the solver for settling.
+    #[serde(default)]
+    pub settlement_skipped: bool,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Settlement {
+    pub tx_hash: H256,
+    #[serde_as(as = "Option<HexOrDecimalU256>")]
+    pub gas_used: Option<U256>,
+    #[serde_as(as = "Option<HexOrDecimalU256>")]
+    pub gas_price: Option<U256>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn winners_roundtrip() {
+        let winners = vec![
+            Winner {
+                solver: H160([1; 20]),
+                solution_uid: 0,
+                score: 100.into(),
+                reference_score: Some(50.into()),
+                settlement: Some(Settlement {
+                    tx_hash: H256([2; 32]),
+                    gas_used: Some(21_000.into()),
+                    gas_price: Some(1_000_000_000.into()),
+                }),
+                settlement_skipped: false,
+            },
+            Winner {
+                solver: H160([3; 20]),
+                solution_uid: 1,
+                score: 80.into(),
+                reference_score: None,
+                settlement: None,
+                settlement_skipped: true,
+            },
+        ];
+
+        let json = serde_json::to_value(&winners).unwrap();
+        let deserialized: Vec<Winner> = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized, winners);
+        assert_eq!(
+            winners_from_domain(&winners_into_domain(deserialized)),
+            winners
+        );
+    }
+
+    #[test]
+    fn winners_stored_before_skipped_settlements_were_tracked() {
+        let json = serde_json::json!([{
+            "solver": H160([1; 20]),
+            "solutionUid": 0,
+            "score": "100",
+            "referenceScore": null,
+            "settlement": null,
+        }]);
+        let winners: Vec<Winner> = serde_json::from_value(json).unwrap();
+        assert!(!winners[0].settlement_skipped);
+    }
+}
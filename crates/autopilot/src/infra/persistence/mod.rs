@@ -6,7 +6,7 @@ use {
         infra::persistence::dto::AuctionId,
     },
     anyhow::Context,
-    bigdecimal::ToPrimitive,
+    bigdecimal::{BigDecimal, ToPrimitive},
     boundary::database::byte_array::ByteArray,
     chrono::{DateTime, Utc},
     database::{
@@ -27,13 +27,17 @@ use {
         SigningScheme as DomainSigningScheme,
     },
     futures::{StreamExt, TryStreamExt},
-    number::conversions::{big_decimal_to_u256, u256_to_big_decimal, u256_to_big_uint},
+    number::{
+        conversions::u256_to_big_uint,
+        db::{big_decimal_to_u256, i64_block_number, u256_to_big_decimal},
+    },
     primitive_types::H256,
     shared::db_order_conversions::full_order_into_model_order,
     std::{
         collections::{HashMap, HashSet},
         ops::DerefMut,
         sync::Arc,
+        time::{Duration, Instant},
     },
     tracing::Instrument,
 };
@@ -45,17 +49,59 @@ pub mod dto;
 pub struct Persistence {
     s3: Option<s3::Uploader>,
     postgres: Arc<Postgres>,
+    /// Writes of auction data taking longer than this get logged.
+    slow_write_threshold: Duration,
 }
 
 impl Persistence {
-    pub async fn new(config: Option<s3::Config>, postgres: Arc<Postgres>) -> Self {
+    pub async fn new(
+        config: Option<s3::Config>,
+        postgres: Arc<Postgres>,
+        slow_write_threshold: Duration,
+    ) -> Self {
         Self {
             s3: match config {
                 Some(config) => Some(s3::Uploader::new(config).await),
                 None => None,
             },
             postgres,
+            slow_write_threshold,
+        }
+    }
+
+    /// Runs a write of the auction's data and records how long it took and
+    /// how many rows it wrote. Measures the whole write including batching,
+    /// so it's done here instead of in the individual queries.
+    async fn instrumented_write<T, E>(
+        &self,
+        name: &'static str,
+        auction_id: domain::auction::Id,
+        rows: usize,
+        write: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let start = Instant::now();
+        let result = write.await;
+        let elapsed = start.elapsed();
+
+        let metrics = Metrics::get();
+        metrics
+            .database_queries
+            .with_label_values(&[name])
+            .observe(elapsed.as_secs_f64());
+        metrics
+            .rows_written
+            .with_label_values(&[name])
+            .set(i64::try_from(rows).unwrap_or(i64::MAX));
+        if elapsed > self.slow_write_threshold {
+            tracing::warn!(
+                write = name,
+                auction_id,
+                rows,
+                ?elapsed,
+                "slow database write"
+            );
         }
+        result
     }
 
     pub async fn db_transaction(
@@ -73,7 +119,7 @@ impl Persistence {
         &self,
         auction: &domain::RawAuctionData,
     ) -> Result<domain::auction::Id, DatabaseError> {
-        let auction = dto::auction::from_domain(auction.clone());
+        let auction = dto::auction::from_domain(auction);
         self.postgres
             .replace_current_auction(&auction)
             .await
@@ -129,9 +175,68 @@ impl Persistence {
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
         competition: &boundary::Competition,
     ) -> Result<(), DatabaseError> {
-        Postgres::save_competition(tx, competition)
-            .await
-            .map_err(DatabaseError)
+        self.instrumented_write(
+            "save_competition",
+            competition.auction_id,
+            competition.competition_table.solutions.len(),
+            Postgres::save_competition(tx, competition),
+        )
+        .await
+        .map_err(DatabaseError)
+    }
+
+    /// Records that the solutions of the auction got collected but the
+    /// competition data is not stored yet. Replaces any previous record. Only
+    /// the driver and hash of each solution get written to keep this cheap.
+    pub async fn save_competition_wip(
+        &self,
+        auction_id: domain::auction::Id,
+        solutions: &[(String, [u8; 32])],
+    ) -> Result<(), DatabaseError> {
+        let mut tx = self.postgres.pool.begin().await.context("begin")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["save_competition_wip"])
+            .start_timer();
+
+        let competition = database::competitions_in_progress::CompetitionInProgress {
+            auction_id,
+            drivers: solutions.iter().map(|(driver, _)| driver.clone()).collect(),
+            solution_hashes: solutions.iter().map(|(_, hash)| ByteArray(*hash)).collect(),
+            started_at: Utc::now(),
+        };
+        database::competitions_in_progress::replace(&mut tx, &competition).await?;
+        tx.commit().await.context("commit")?;
+        Ok(())
+    }
+
+    /// Removes the record of the competition in progress once its data got
+    /// stored.
+    pub async fn clear_competition_wip(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        auction_id: domain::auction::Id,
+    ) -> Result<(), DatabaseError> {
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["clear_competition_wip"])
+            .start_timer();
+        database::competitions_in_progress::delete(tx, auction_id).await?;
+        Ok(())
+    }
+
+    /// Loads the auction ids of competitions whose data never got stored.
+    pub async fn unfinished_competitions(&self) -> Result<Vec<domain::auction::Id>, DatabaseError> {
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["unfinished_competitions"])
+            .start_timer();
+        let competitions = database::competitions_in_progress::fetch_all(&mut ex).await?;
+        Ok(competitions
+            .into_iter()
+            .map(|competition| competition.auction_id)
+            .collect())
     }
 
     /// Save all valid solutions that participated in the competition for an
@@ -142,55 +247,54 @@ impl Persistence {
         auction_id: domain::auction::Id,
         solutions: impl Iterator<Item = &domain::competition::Participant>,
     ) -> Result<(), DatabaseError> {
-        let _timer = Metrics::get()
-            .database_queries
-            .with_label_values(&["save_solutions"])
-            .start_timer();
+        let solutions = solutions
+            .enumerate()
+            .map(|(uid, participant)| {
+                let solution = Solution {
+                    uid: uid.try_into().context("uid overflow")?,
+                    id: u256_to_big_decimal(&participant.solution().id().into()),
+                    solver: ByteArray(participant.solver_address().0.0),
+                    is_winner: participant.is_winner(),
+                    filtered_out: participant.filtered_out(),
+                    observe_only: participant.driver().observe_only,
+                    score: u256_to_big_decimal(&participant.solution().score().get().0),
+                    orders: participant
+                        .solution()
+                        .orders()
+                        .iter()
+                        .map(|(order_uid, order)| Order {
+                            uid: ByteArray(order_uid.0),
+                            sell_token: ByteArray(order.sell.token.0.0),
+                            buy_token: ByteArray(order.buy.token.0.0),
+                            limit_sell: u256_to_big_decimal(&order.sell.amount.0),
+                            limit_buy: u256_to_big_decimal(&order.buy.amount.0),
+                            executed_sell: u256_to_big_decimal(&order.executed_sell.0),
+                            executed_buy: u256_to_big_decimal(&order.executed_buy.0),
+                            side: order.side.into(),
+                        })
+                        .collect(),
+                    price_tokens: participant
+                        .solution()
+                        .prices()
+                        .keys()
+                        .map(|token| ByteArray(token.0.0))
+                        .collect(),
+                    price_values: participant
+                        .solution()
+                        .prices()
+                        .values()
+                        .map(|price| u256_to_big_decimal(&price.get().0))
+                        .collect(),
+                };
+                Ok::<_, DatabaseError>(solution)
+            })
+            .collect::<Result<Vec<_>, DatabaseError>>()?;
 
-        database::solver_competition_v2::save(
-            tx,
+        self.instrumented_write(
+            "save_solutions",
             auction_id,
-            &solutions
-                .enumerate()
-                .map(|(uid, participant)| {
-                    let solution = Solution {
-                        uid: uid.try_into().context("uid overflow")?,
-                        id: u256_to_big_decimal(&participant.solution().id().into()),
-                        solver: ByteArray(participant.solution().solver().0.0),
-                        is_winner: participant.is_winner(),
-                        filtered_out: participant.filtered_out(),
-                        score: u256_to_big_decimal(&participant.solution().score().get().0),
-                        orders: participant
-                            .solution()
-                            .orders()
-                            .iter()
-                            .map(|(order_uid, order)| Order {
-                                uid: ByteArray(order_uid.0),
-                                sell_token: ByteArray(order.sell.token.0.0),
-                                buy_token: ByteArray(order.buy.token.0.0),
-                                limit_sell: u256_to_big_decimal(&order.sell.amount.0),
-                                limit_buy: u256_to_big_decimal(&order.buy.amount.0),
-                                executed_sell: u256_to_big_decimal(&order.executed_sell.0),
-                                executed_buy: u256_to_big_decimal(&order.executed_buy.0),
-                                side: order.side.into(),
-                            })
-                            .collect(),
-                        price_tokens: participant
-                            .solution()
-                            .prices()
-                            .keys()
-                            .map(|token| ByteArray(token.0.0))
-                            .collect(),
-                        price_values: participant
-                            .solution()
-                            .prices()
-                            .values()
-                            .map(|price| u256_to_big_decimal(&price.get().0))
-                            .collect(),
-                    };
-                    Ok::<_, DatabaseError>(solution)
-                })
-                .collect::<Result<Vec<_>, DatabaseError>>()?,
+            solutions.len(),
+            database::solver_competition_v2::save(tx, auction_id, &solutions),
         )
         .await?;
 
@@ -204,18 +308,66 @@ impl Persistence {
         auction_id: AuctionId,
         surplus_capturing_jit_order_owners: &[domain::eth::Address],
     ) -> Result<(), DatabaseError> {
-        Postgres::save_surplus_capturing_jit_order_owners(
-            tx,
+        let owners: Vec<_> = surplus_capturing_jit_order_owners
+            .iter()
+            .map(|address| ByteArray(address.0.into()))
+            .collect();
+        self.instrumented_write(
+            "save_surplus_capturing_jit_order_owners",
             auction_id,
-            &surplus_capturing_jit_order_owners
-                .iter()
-                .map(|address| ByteArray(address.0.into()))
-                .collect::<Vec<_>>(),
+            owners.len(),
+            Postgres::save_surplus_capturing_jit_order_owners(tx, auction_id, &owners),
         )
         .await
         .map_err(DatabaseError)
     }
 
+    /// Saves the tokens that were trusted when the auction was sent to the
+    /// solvers.
+    pub async fn store_trusted_tokens(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        auction_id: domain::auction::Id,
+        hash: domain::auction::TrustedTokensHash,
+        trusted_tokens: &[eth::TokenAddress],
+    ) -> Result<(), DatabaseError> {
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["store_trusted_tokens"])
+            .start_timer();
+
+        let tokens = trusted_tokens
+            .iter()
+            .map(|token| ByteArray(token.0.0))
+            .collect::<Vec<_>>();
+        database::auction_trusted_tokens::insert(tx, auction_id, ByteArray(hash.0), &tokens)
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the tokens that were trusted when the auction was sent to the
+    /// solvers or `None` if they were not stored for that auction.
+    pub async fn load_trusted_tokens(
+        &self,
+        auction_id: domain::auction::Id,
+    ) -> Result<Option<Vec<eth::TokenAddress>>, DatabaseError> {
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["load_trusted_tokens"])
+            .start_timer();
+
+        let mut ex = self.postgres.pool.acquire().await?;
+        let tokens = database::auction_trusted_tokens::fetch(&mut ex, auction_id)
+            .await?
+            .map(|tokens| {
+                tokens
+                    .into_iter()
+                    .map(|token| eth::H160(token.0).into())
+                    .collect()
+            });
+        Ok(tokens)
+    }
+
     /// Inserts an order event for each order uid in the given set.
     /// Unique order uids are required to avoid inserting events with the same
     /// label within the same order_uid. If this function encounters an error it
@@ -244,26 +396,144 @@ impl Persistence {
         auction_id: domain::auction::Id,
         fee_policies: Vec<(domain::OrderUid, Vec<domain::fee::Policy>)>,
     ) -> anyhow::Result<()> {
+        let rows = fee_policies
+            .iter()
+            .map(|(_, policies)| policies.len())
+            .sum();
+        self.instrumented_write("store_fee_policies", auction_id, rows, async {
+            for chunk in fee_policies.chunks(self.postgres.config.insert_batch_size.get()) {
+                crate::database::fee_policies::insert_batch(tx, auction_id, chunk.iter().cloned())
+                    .await
+                    .context("fee_policies::insert_batch")?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Saves the external prices of the auction.
+    pub async fn store_auction_prices(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        auction_id: domain::auction::Id,
+        prices: &domain::auction::Prices,
+    ) -> Result<(), DatabaseError> {
+        let prices: Vec<_> = prices
+            .iter()
+            .map(|(token, price)| database::auction_prices::AuctionPrice {
+                auction_id,
+                token: ByteArray(token.0.0),
+                price: u256_to_big_decimal(&price.get().0),
+            })
+            .collect();
+        self.instrumented_write(
+            "store_auction_prices",
+            auction_id,
+            prices.len(),
+            database::auction_prices::insert(tx, &prices),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Stores the given kind of reward relevant auction data once more, e.g.
+    /// after [`Self::verify_auction_completeness`] found it to be incomplete.
+    /// Data that is already stored gets replaced instead of duplicated.
+    pub async fn repair_auction_data(
+        &self,
+        auction: &domain::Auction,
+        kind: AuctionDataKind,
+        fee_policies: &[(domain::OrderUid, Vec<domain::fee::Policy>)],
+    ) -> anyhow::Result<()> {
+        let mut tx = self.db_transaction().await?;
+        match kind {
+            AuctionDataKind::Prices => {
+                let prices: Vec<_> = auction
+                    .prices
+                    .iter()
+                    .map(|(token, price)| database::auction_prices::AuctionPrice {
+                        auction_id: auction.id,
+                        token: ByteArray(token.0.0),
+                        price: u256_to_big_decimal(&price.get().0),
+                    })
+                    .collect();
+                self.instrumented_write(
+                    "repair_auction_prices",
+                    auction.id,
+                    prices.len(),
+                    database::auction_prices::upsert(&mut tx, &prices),
+                )
+                .await?;
+            }
+            AuctionDataKind::FeePolicies => {
+                database::fee_policies::delete(&mut tx, auction.id).await?;
+                self.store_fee_policies(&mut tx, auction.id, fee_policies.to_vec())
+                    .await?;
+            }
+            AuctionDataKind::JitOwners => {
+                let owners: Vec<_> = auction
+                    .surplus_capturing_jit_order_owners
+                    .iter()
+                    .map(|address| ByteArray(address.0.into()))
+                    .collect();
+                self.instrumented_write(
+                    "repair_surplus_capturing_jit_order_owners",
+                    auction.id,
+                    owners.len(),
+                    database::surplus_capturing_jit_order_owners::upsert(
+                        &mut tx, auction.id, &owners,
+                    ),
+                )
+                .await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Checks that all the data required to compute solver rewards got stored
+    /// for the given auction. Returns the kinds of data that are missing or
+    /// differ from the expected data.
+    pub async fn verify_auction_completeness(
+        &self,
+        auction_id: domain::auction::Id,
+        expected: &ExpectedAuctionData,
+    ) -> Result<Vec<AuctionDataKind>, DatabaseError> {
         let _timer = Metrics::get()
             .database_queries
-            .with_label_values(&["store_fee_policies"])
+            .with_label_values(&["verify_auction_completeness"])
             .start_timer();
 
-        for chunk in fee_policies.chunks(self.postgres.config.insert_batch_size.get()) {
-            crate::database::fee_policies::insert_batch(tx, auction_id, chunk.iter().cloned())
-                .await
-                .context("fee_policies::insert_batch")?;
-        }
-
-        Ok(())
+        let mut ex = self.postgres.pool.acquire().await?;
+        let stored = StoredAuctionData {
+            prices: database::auction_prices::fetch(&mut ex, auction_id)
+                .await?
+                .into_iter()
+                .map(|price| (eth::TokenAddress(eth::H160(price.token.0)), price.price))
+                .collect(),
+            fee_policies: database::fee_policies::count_per_order(&mut ex, auction_id)
+                .await?
+                .into_iter()
+                .map(|(order, count)| (domain::OrderUid(order.0), count))
+                .collect(),
+            jit_owners: database::surplus_capturing_jit_order_owners::fetch(&mut ex, auction_id)
+                .await?
+                .map(|owners| {
+                    owners
+                        .into_iter()
+                        .map(|owner| eth::Address(eth::H160(owner.0)))
+                        .collect()
+                }),
+        };
+        Ok(expected.missing(&stored))
     }
 
-    /// For a given auction and solver, tries to find the settlement
-    /// transaction.
+    /// For a given auction and any of the solver's addresses, tries to find
+    /// the settlement transaction.
     pub async fn find_settlement_transaction(
         &self,
         auction_id: i64,
-        solver: eth::Address,
+        solvers: &[eth::Address],
     ) -> Result<Option<eth::TxId>, DatabaseError> {
         let _timer = Metrics::get()
             .database_queries
@@ -271,13 +541,42 @@ impl Persistence {
             .start_timer();
 
         let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
-        Ok(database::settlements::find_settlement_transaction(
+        let solvers: Vec<_> = solvers.iter().map(|solver| ByteArray(solver.0.0)).collect();
+        Ok(
+            database::settlements::find_settlement_transaction(&mut ex, auction_id, &solvers)
+                .await?
+                .map(|hash| H256(hash.0).into()),
+        )
+    }
+
+    /// For a given auction and solver, finds all settlement transactions that
+    /// got indexed within the (inclusive) block range. Unlike
+    /// [`Self::find_settlement_transaction`] this returns every match, which
+    /// is needed to reconcile settlements missed during an indexer gap.
+    pub async fn find_settlement_transaction_in_range(
+        &self,
+        auction_id: i64,
+        solver: eth::Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<eth::TxId>, DatabaseError> {
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["find_settlement_transaction_in_range"])
+            .start_timer();
+
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        Ok(database::settlements::find_settlement_transaction_in_range(
             &mut ex,
             auction_id,
             ByteArray(solver.0.0),
+            i64::try_from(from_block).unwrap_or(i64::MAX),
+            i64::try_from(to_block).unwrap_or(i64::MAX),
         )
         .await?
-        .map(|hash| H256(hash.0).into()))
+        .into_iter()
+        .map(|hash| H256(hash.0).into())
+        .collect())
     }
 
     /// Save auction related data to the database.
@@ -287,38 +586,38 @@ impl Persistence {
         auction: &domain::Auction,
         deadline: u64, // to become part of the auction struct
     ) -> Result<(), DatabaseError> {
-        let _timer = Metrics::get()
-            .database_queries
-            .with_label_values(&["save_auction"])
-            .start_timer();
-
-        database::auction::save(
-            tx,
-            database::auction::Auction {
-                id: auction.id,
-                block: i64::try_from(auction.block).context("block overflow")?,
-                deadline: i64::try_from(deadline).context("deadline overflow")?,
-                order_uids: auction
-                    .orders
-                    .iter()
-                    .map(|order| ByteArray(order.uid.0))
-                    .collect(),
-                price_tokens: auction
-                    .prices
-                    .keys()
-                    .map(|token| ByteArray(token.0.0))
-                    .collect(),
-                price_values: auction
-                    .prices
-                    .values()
-                    .map(|price| u256_to_big_decimal(&price.get().0))
-                    .collect(),
-                surplus_capturing_jit_order_owners: auction
-                    .surplus_capturing_jit_order_owners
-                    .iter()
-                    .map(|owner| ByteArray(owner.0.0))
-                    .collect(),
-            },
+        let row = database::auction::Auction {
+            id: auction.id,
+            block: i64_block_number(auction.block).context("block overflow")?,
+            deadline: i64::try_from(deadline).context("deadline overflow")?,
+            order_uids: auction
+                .orders
+                .iter()
+                .map(|order| ByteArray(order.uid.0))
+                .collect(),
+            price_tokens: auction
+                .prices
+                .keys()
+                .map(|token| ByteArray(token.0.0))
+                .collect(),
+            price_values: auction
+                .prices
+                .values()
+                .map(|price| u256_to_big_decimal(&price.get().0))
+                .collect(),
+            surplus_capturing_jit_order_owners: auction
+                .surplus_capturing_jit_order_owners
+                .iter()
+                .map(|owner| ByteArray(owner.0.0))
+                .collect(),
+            content_hash: Some(ByteArray(auction.content_hash.0)),
+        };
+        // the orders make up the bulk of the auction
+        self.instrumented_write(
+            "save_auction",
+            auction.id,
+            auction.orders.len(),
+            database::auction::save(tx, row),
         )
         .await?;
 
@@ -358,7 +657,7 @@ impl Persistence {
             .map(|price| {
                 let token = eth::H160(price.token.0).into();
                 let price = big_decimal_to_u256(&price.price)
-                    .ok_or(domain::auction::InvalidPrice)
+                    .map_err(|_| domain::auction::InvalidPrice)
                     .and_then(|p| domain::auction::Price::try_new(p.into()))
                     .map_err(|_err| error::Auction::InvalidPrice(token));
                 price.map(|price| (token, price))
@@ -445,6 +744,73 @@ impl Persistence {
         })
     }
 
+    /// Loads the `count` most recent competition auctions (oldest first) so
+    /// they can be solved again. Orders are loaded in their current state, so
+    /// they might have been filled or cancelled in the meantime.
+    pub async fn fetch_recent_auctions(
+        &self,
+        count: usize,
+    ) -> anyhow::Result<Vec<domain::Auction>> {
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["fetch_recent_auctions"])
+            .start_timer();
+
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let ids = database::auction::fetch_latest_ids(
+            &mut ex,
+            i64::try_from(count).context("auction count overflow")?,
+        )
+        .await?;
+
+        let mut auctions = Vec::with_capacity(ids.len());
+        for id in ids.into_iter().rev() {
+            let auction = self
+                .get_auction(id)
+                .await
+                .with_context(|| format!("failed to load auction {id}"))?;
+            let uids: Vec<_> = auction.orders.keys().map(|uid| ByteArray(uid.0)).collect();
+            let orders: Vec<model::order::Order> =
+                database::orders::full_orders_by_uids(&mut ex, &uids)
+                    .map(|result| match result {
+                        Ok(order) => full_order_into_model_order(order),
+                        Err(err) => Err(anyhow::Error::from(err)),
+                    })
+                    .try_collect()
+                    .await?;
+
+            let mut fee_policies = auction.orders;
+            let orders: Vec<_> = orders
+                .into_iter()
+                .map(|order| {
+                    let policies = fee_policies
+                        .remove(&domain::OrderUid(order.metadata.uid.0))
+                        .unwrap_or_default();
+                    boundary::order::to_domain(order, policies, None)
+                })
+                .collect();
+            let surplus_capturing_jit_order_owners: Vec<_> = auction
+                .surplus_capturing_jit_order_owners
+                .into_iter()
+                .collect();
+            auctions.push(domain::Auction {
+                id,
+                block: auction.block.0,
+                content_hash: domain::auction::ContentHash::new(
+                    &orders,
+                    &auction.prices,
+                    &surplus_capturing_jit_order_owners,
+                ),
+                orders: Arc::new(orders),
+                prices: Arc::new(auction.prices),
+                surplus_capturing_jit_order_owners,
+                quote_only: false,
+                prioritized_orders: Default::default(),
+            });
+        }
+        Ok(auctions)
+    }
+
     /// Computes solvable orders based on the latest observed block number,
     /// order creation timestamp, and minimum validity period.
     pub async fn solvable_orders_after(
@@ -867,6 +1233,20 @@ impl Persistence {
                 .context("solution uid overflow")?,
             event.end_timestamp,
             event.end_block.try_into().context("end block overflow")?,
+            match event.outcome_kind {
+                domain::settlement::ExecutionOutcomeKind::Success => {
+                    database::settlement_executions::OutcomeKind::Success
+                }
+                domain::settlement::ExecutionOutcomeKind::Timeout => {
+                    database::settlement_executions::OutcomeKind::Timeout
+                }
+                domain::settlement::ExecutionOutcomeKind::Reorged => {
+                    database::settlement_executions::OutcomeKind::Reorged
+                }
+                domain::settlement::ExecutionOutcomeKind::Failed => {
+                    database::settlement_executions::OutcomeKind::Failed
+                }
+            },
             event.outcome,
         )
         .await?;
@@ -874,91 +1254,887 @@ impl Persistence {
         Ok(())
     }
 
-    /// Finds solvers that won `last_auctions_count` consecutive auctions but
-    /// never settled any of them. The current block is used to prevent
-    /// selecting auctions with deadline after the current block since they
-    /// still can be settled.
-    pub async fn find_non_settling_solvers(
+    /// Stores how much the native prices implied by a settlement deviate from
+    /// the native prices of its auction.
+    pub async fn store_price_deviations(
         &self,
-        last_auctions_count: u32,
-        current_block: u64,
-    ) -> anyhow::Result<Vec<eth::Address>> {
+        event: domain::eth::SettlementEvent,
+        auction_id: domain::auction::Id,
+        deviations: &[domain::settlement::PriceDeviation],
+    ) -> Result<(), DatabaseError> {
         let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
         let _timer = Metrics::get()
             .database_queries
-            .with_label_values(&["find_non_settling_solvers"])
+            .with_label_values(&["store_price_deviations"])
             .start_timer();
 
-        Ok(database::solver_competition_v2::find_non_settling_solvers(
-            &mut ex,
-            last_auctions_count,
-            current_block,
-        )
-        .await
-        .context("failed to fetch non-settling solvers")?
-        .into_iter()
-        .map(|solver| eth::Address(solver.0.into()))
-        .collect())
+        let block_number = i64::try_from(event.block.0).context("block overflow")?;
+        let log_index = i64::try_from(event.log_index).context("log index overflow")?;
+        let deviations: Vec<_> = deviations
+            .iter()
+            .map(
+                |deviation| database::settlement_price_deviations::PriceDeviation {
+                    block_number,
+                    log_index,
+                    auction_id,
+                    token: ByteArray(deviation.token.0.0),
+                    auction_price: u256_to_big_decimal(&deviation.auction.get().0),
+                    implied_price: u256_to_big_decimal(&deviation.implied),
+                    deviation: deviation.relative,
+                },
+            )
+            .collect();
+        database::settlement_price_deviations::insert(&mut ex, &deviations).await?;
+        Ok(())
     }
 
-    /// Finds solvers that have a failure settling rate above the given
-    /// ratio. The current block is used to prevent selecting auctions with
-    /// deadline after the current block since they still can be settled.
-    pub async fn find_low_settling_solvers(
+    /// Records whether the mined settlement of an execution deviated from the
+    /// winning solution.
+    pub async fn store_settlement_deviation(
         &self,
-        last_auctions_count: u32,
-        current_block: u64,
-        max_failure_rate: f64,
-        min_wins_threshold: u32,
-    ) -> anyhow::Result<Vec<eth::Address>> {
+        auction_id: domain::auction::Id,
+        solver: eth::Address,
+        solution_uid: usize,
+        deviates: bool,
+    ) -> Result<(), DatabaseError> {
         let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
         let _timer = Metrics::get()
             .database_queries
-            .with_label_values(&["find_low_settling_solvers"])
+            .with_label_values(&["store_settlement_deviation"])
             .start_timer();
 
-        Ok(database::solver_competition_v2::find_low_settling_solvers(
+        database::settlement_executions::set_deviates_from_solution(
             &mut ex,
-            last_auctions_count,
-            current_block,
-            max_failure_rate,
-            min_wins_threshold,
+            auction_id,
+            ByteArray(solver.0.0),
+            solution_uid.try_into().context("solution uid overflow")?,
+            deviates,
         )
-        .await
-        .context("solver_competition::find_low_settling_solvers")?
-        .into_iter()
-        .map(|solver| eth::Address(solver.0.into()))
-        .collect())
+        .await?;
+        Ok(())
     }
 
-    pub async fn get_solver_winning_solutions(
+    /// Records an attempt to notify a solver and whether the notification got
+    /// delivered.
+    pub async fn store_notification(
         &self,
-        auction_id: domain::auction::Id,
         solver: eth::Address,
-    ) -> Result<Vec<Solution>, DatabaseError> {
+        kind: domain::competition::notification::Kind,
+        result: Result<(), String>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<(), DatabaseError> {
         let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
         let _timer = Metrics::get()
             .database_queries
-            .with_label_values(&["fetch_solver_winning_solutions"])
+            .with_label_values(&["store_notification"])
             .start_timer();
 
-        Ok(
-            database::solver_competition_v2::fetch_solver_winning_solutions(
-                &mut ex,
-                auction_id,
-                ByteArray(solver.0.0),
-            )
-            .await
-            .context("solver_competition::fetch_solver_winning_solutions")?,
-        )
-    }
-}
+        let notification = database::solver_notifications::Notification {
+            solver: ByteArray(solver.0.0),
+            kind: match kind {
+                domain::competition::notification::Kind::Banned => {
+                    database::solver_notifications::Kind::Banned
+                }
+                domain::competition::notification::Kind::CompetitionOutcome => {
+                    database::solver_notifications::Kind::CompetitionOutcome
+                }
+            },
+            timestamp,
+            error: result.err(),
+        };
+        database::solver_notifications::insert(&mut ex, &notification).await?;
+        Ok(())
+    }
+
+    /// Loads all notifications sent to `solver` in `[from, to)` from oldest to
+    /// newest.
+    pub async fn solver_notifications(
+        &self,
+        solver: eth::Address,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<domain::competition::notification::Notification>, DatabaseError> {
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["solver_notifications"])
+            .start_timer();
+
+        let notifications =
+            database::solver_notifications::fetch(&mut ex, &ByteArray(solver.0.0), from, to)
+                .await?;
+        Ok(notifications
+            .into_iter()
+            .map(
+                |notification| domain::competition::notification::Notification {
+                    solver: eth::H160(notification.solver.0).into(),
+                    kind: match notification.kind {
+                        database::solver_notifications::Kind::Banned => {
+                            domain::competition::notification::Kind::Banned
+                        }
+                        database::solver_notifications::Kind::CompetitionOutcome => {
+                            domain::competition::notification::Kind::CompetitionOutcome
+                        }
+                    },
+                    timestamp: notification.timestamp,
+                    result: notification.error.map_or(Ok(()), Err),
+                },
+            )
+            .collect())
+    }
+
+    /// Loads the settlement executions matching the filter. Executions that
+    /// didn't end before `current_block` although their deadline passed get
+    /// flagged as orphaned.
+    pub async fn load_settlement_executions(
+        &self,
+        filter: &domain::settlement::ExecutionFilter,
+        current_block: u64,
+    ) -> Result<Vec<domain::settlement::ExecutionRecord>, DatabaseError> {
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["load_settlement_executions"])
+            .start_timer();
+
+        let current_block = i64::try_from(current_block).context("current block overflow")?;
+        let filter = database::settlement_executions::Filter {
+            solver: filter.solver.map(|solver| ByteArray(solver.0.0)),
+            auctions: filter
+                .auctions
+                .as_ref()
+                .map(|auctions| (*auctions.start(), *auctions.end())),
+            outcome: filter.outcome.map(|outcome| match outcome {
+                domain::settlement::ExecutionOutcome::Success => {
+                    database::settlement_executions::Outcome::Success
+                }
+                domain::settlement::ExecutionOutcome::Timeout => {
+                    database::settlement_executions::Outcome::Timeout
+                }
+                domain::settlement::ExecutionOutcome::Failed => {
+                    database::settlement_executions::Outcome::Failed
+                }
+                domain::settlement::ExecutionOutcome::Unfinished => {
+                    database::settlement_executions::Outcome::Unfinished
+                }
+                domain::settlement::ExecutionOutcome::Orphaned => {
+                    database::settlement_executions::Outcome::Orphaned
+                }
+            }),
+            started: filter
+                .started
+                .as_ref()
+                .map(|started| (started.start, started.end)),
+        };
+
+        database::settlement_executions::load(&mut ex, &filter, current_block)
+            .await?
+            .into_iter()
+            .map(|execution| {
+                let end = match (
+                    execution.end_timestamp,
+                    execution.end_block,
+                    execution.outcome,
+                ) {
+                    (Some(timestamp), Some(block), Some(outcome)) => {
+                        Some(domain::settlement::ExecutionEnd {
+                            timestamp,
+                            block: block.try_into().context("end block is negative")?,
+                            outcome,
+                        })
+                    }
+                    _ => None,
+                };
+                Ok::<_, DatabaseError>(domain::settlement::ExecutionRecord {
+                    auction_id: execution.auction_id,
+                    solver: eth::H160(execution.solver.0).into(),
+                    solution_uid: execution
+                        .solution_uid
+                        .try_into()
+                        .context("solution uid is negative")?,
+                    start_timestamp: execution.start_timestamp,
+                    start_block: execution
+                        .start_block
+                        .try_into()
+                        .context("start block is negative")?,
+                    deadline_block: execution
+                        .deadline_block
+                        .try_into()
+                        .context("deadline block is negative")?,
+                    orphaned: end.is_none() && execution.deadline_block < current_block,
+                    end,
+                })
+            })
+            .collect()
+    }
+
+    /// Counts the settlement executions that started at or after `since` and
+    /// never ended although their deadline passed before `current_block`.
+    pub async fn count_orphaned_settlement_executions(
+        &self,
+        current_block: u64,
+        since: DateTime<Utc>,
+    ) -> Result<u64, DatabaseError> {
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["count_orphaned_settlement_executions"])
+            .start_timer();
+
+        let count = database::settlement_executions::count_orphans(
+            &mut ex,
+            i64::try_from(current_block).context("current block overflow")?,
+            since,
+        )
+        .await?;
+        Ok(count.try_into().context("negative count")?)
+    }
+
+    /// Deletes up to `limit` rows of the dataset that are older than `cutoff`.
+    /// Auction based data only gets deleted for auctions whose settlement
+    /// started before `cutoff`. Returns the number of deleted rows.
+    pub async fn prune(
+        &self,
+        data: RetainedData,
+        cutoff: DateTime<Utc>,
+        limit: u64,
+    ) -> Result<u64, DatabaseError> {
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["prune"])
+            .start_timer();
+
+        let limit = i64::try_from(limit).context("limit overflow")?;
+        let deleted = match data {
+            RetainedData::SolverCompetitions => {
+                match database::settlement_executions::last_auction_started_before(&mut ex, cutoff)
+                    .await?
+                {
+                    Some(last_auction) => {
+                        database::solver_competition::delete_up_to(&mut ex, last_auction, limit)
+                            .await?
+                    }
+                    None => 0,
+                }
+            }
+            RetainedData::AuctionPrices => {
+                match database::settlement_executions::last_auction_started_before(&mut ex, cutoff)
+                    .await?
+                {
+                    Some(last_auction) => {
+                        database::auction_prices::delete_up_to(&mut ex, last_auction, limit).await?
+                    }
+                    None => 0,
+                }
+            }
+            RetainedData::SettlementExecutions => {
+                database::settlement_executions::delete_batch_before(&mut ex, cutoff, limit).await?
+            }
+        };
+        Ok(deleted)
+    }
+
+    /// Counts the rows of the dataset that [`Persistence::prune`] would delete
+    /// for the same `cutoff`. Stops counting at `limit`.
+    pub async fn count_prunable(
+        &self,
+        data: RetainedData,
+        cutoff: DateTime<Utc>,
+        limit: u64,
+    ) -> Result<u64, DatabaseError> {
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["count_prunable"])
+            .start_timer();
+
+        let limit = i64::try_from(limit).context("limit overflow")?;
+        let count = match data {
+            RetainedData::SolverCompetitions => {
+                match database::settlement_executions::last_auction_started_before(&mut ex, cutoff)
+                    .await?
+                {
+                    Some(last_auction) => {
+                        database::solver_competition::count_up_to(&mut ex, last_auction, limit)
+                            .await?
+                    }
+                    None => 0,
+                }
+            }
+            RetainedData::AuctionPrices => {
+                match database::settlement_executions::last_auction_started_before(&mut ex, cutoff)
+                    .await?
+                {
+                    Some(last_auction) => {
+                        database::auction_prices::count_up_to(&mut ex, last_auction, limit).await?
+                    }
+                    None => 0,
+                }
+            }
+            RetainedData::SettlementExecutions => {
+                database::settlement_executions::count_before(&mut ex, cutoff, limit).await?
+            }
+        };
+        Ok(count.try_into().context("negative count")?)
+    }
+
+    /// Stores how every configured driver took part in the auction.
+    pub async fn save_participation(
+        &self,
+        auction_id: domain::auction::Id,
+        matrix: &domain::competition::participation::ParticipationMatrix,
+    ) -> Result<(), DatabaseError> {
+        let participations = matrix
+            .0
+            .iter()
+            .map(|participation| {
+                Ok(database::driver_participation::DriverParticipation {
+                    auction_id,
+                    driver: participation.driver.clone(),
+                    submission_address: ByteArray(participation.submission_address.0.0),
+                    outcome: participation.outcome.label().to_owned(),
+                    proposed_solutions: participation
+                        .outcome
+                        .proposed()
+                        .try_into()
+                        .context("proposed solutions overflow")?,
+                    accepted_solutions: participation
+                        .accepted
+                        .try_into()
+                        .context("accepted solutions overflow")?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["save_participation"])
+            .start_timer();
+
+        database::driver_participation::insert_batch(&mut ex, participations).await?;
+        Ok(())
+    }
+
+    /// Stores the orders the winning coalition of an auction executed worse
+    /// than a compatible losing solution.
+    pub async fn save_coalition_fairness_violations(
+        &self,
+        auction_id: domain::auction::Id,
+        violations: &[domain::competition::winner_selection::coalition_fairness::Violation],
+    ) -> Result<(), DatabaseError> {
+        let violations =
+            violations.iter().map(
+                |violation| database::coalition_fairness_violations::Violation {
+                    auction_id,
+                    order_uid: ByteArray(violation.order.0),
+                    winner: violation.winner.clone(),
+                    winner_executed_sell: u256_to_big_decimal(
+                        &violation.winning_execution.executed_sell.0,
+                    ),
+                    winner_executed_buy: u256_to_big_decimal(
+                        &violation.winning_execution.executed_buy.0,
+                    ),
+                    alternative: violation.alternative.clone(),
+                    alternative_executed_sell: u256_to_big_decimal(
+                        &violation.alternative_execution.executed_sell.0,
+                    ),
+                    alternative_executed_buy: u256_to_big_decimal(
+                        &violation.alternative_execution.executed_buy.0,
+                    ),
+                },
+            );
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["save_coalition_fairness_violations"])
+            .start_timer();
+
+        database::coalition_fairness_violations::insert_batch(&mut ex, violations).await?;
+        Ok(())
+    }
+
+    /// Stores the gas of a winning solution and where it comes from.
+    pub async fn store_solution_gas(
+        &self,
+        auction_id: domain::auction::Id,
+        solution_uid: usize,
+        gas: domain::eth::Gas,
+        source: domain::competition::gas::GasSource,
+    ) -> Result<(), DatabaseError> {
+        let gas = database::solution_gas::SolutionGas {
+            auction_id,
+            solution_uid: solution_uid.try_into().context("solution uid overflow")?,
+            gas: u256_to_big_decimal(&gas.0),
+            source: match source {
+                domain::competition::gas::GasSource::Reported => {
+                    database::solution_gas::GasSource::Reported
+                }
+                domain::competition::gas::GasSource::Estimated => {
+                    database::solution_gas::GasSource::Estimated
+                }
+                domain::competition::gas::GasSource::Fallback => {
+                    database::solution_gas::GasSource::Fallback
+                }
+            },
+        };
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["store_solution_gas"])
+            .start_timer();
+
+        database::solution_gas::insert(&mut ex, &gas).await?;
+        Ok(())
+    }
+
+    /// Stores the price improvements of the orders executed by the winners of
+    /// an auction.
+    pub async fn store_order_price_improvements(
+        &self,
+        auction_id: domain::auction::Id,
+        improvements: &[domain::competition::price_improvement::PriceImprovement],
+    ) -> Result<(), DatabaseError> {
+        let improvements = improvements.iter().map(|improvement| {
+            database::order_price_improvements::PriceImprovement {
+                auction_id,
+                order_uid: ByteArray(improvement.order.0),
+                improvement_bps: improvement.improvement_bps,
+            }
+        });
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["store_order_price_improvements"])
+            .start_timer();
+
+        database::order_price_improvements::insert_batch(&mut ex, improvements).await?;
+        Ok(())
+    }
+
+    /// Records that the given orders appeared in an auction for the first
+    /// time. Orders that already appeared in an earlier auction (e.g. before a
+    /// restart) keep their original record. Errors only get logged because the
+    /// data is only used to measure latencies.
+    pub fn store_first_auction_appearances(
+        &self,
+        auction_id: domain::auction::Id,
+        order_uids: Vec<domain::OrderUid>,
+    ) {
+        if order_uids.is_empty() {
+            return;
+        }
+        let db = self.postgres.clone();
+        let first_seen = Utc::now();
+        tokio::spawn(
+            async move {
+                let appearances = order_uids.into_iter().map(|uid| {
+                    database::order_settlement_latencies::FirstAppearance {
+                        order_uid: ByteArray(uid.0),
+                        auction_id,
+                        first_seen,
+                    }
+                });
+                let result = async {
+                    let mut ex = db.pool.acquire().await.context("acquire")?;
+                    let _timer = Metrics::get()
+                        .database_queries
+                        .with_label_values(&["store_first_auction_appearances"])
+                        .start_timer();
+                    database::order_settlement_latencies::insert_first_appearances(
+                        &mut ex,
+                        appearances,
+                    )
+                    .await?;
+                    anyhow::Ok(())
+                }
+                .await;
+                if let Err(err) = result {
+                    tracing::warn!(?err, "failed to store first auction appearances");
+                }
+            }
+            .instrument(tracing::Span::current()),
+        );
+    }
+
+    /// Records that the given orders got settled and returns how long it took
+    /// from their first appearance in an auction until `settled` for every
+    /// order settled for the first time.
+    pub async fn store_settlement_latencies(
+        &self,
+        auction_id: domain::auction::Id,
+        tx: eth::TxId,
+        order_uids: &HashSet<domain::OrderUid>,
+        settled: DateTime<Utc>,
+    ) -> Result<Vec<(domain::OrderUid, Duration)>, DatabaseError> {
+        let order_uids: Vec<_> = order_uids.iter().map(|uid| ByteArray(uid.0)).collect();
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["store_settlement_latencies"])
+            .start_timer();
+
+        let latencies = database::order_settlement_latencies::record_settlement(
+            &mut ex,
+            &order_uids,
+            auction_id,
+            ByteArray(tx.0.0),
+            settled,
+        )
+        .await?;
+        Ok(latencies
+            .into_iter()
+            .map(|(uid, seconds)| {
+                (
+                    domain::OrderUid(uid.0),
+                    Duration::try_from_secs_f64(seconds).unwrap_or_default(),
+                )
+            })
+            .collect())
+    }
+
+    /// Stores the execution status of all winning orders of an auction as a
+    /// single batch.
+    pub async fn store_order_execution_statuses(
+        &self,
+        statuses: Vec<domain::settlement::OrderExecutionStatus>,
+    ) -> Result<(), DatabaseError> {
+        let statuses = statuses
+            .into_iter()
+            .map(|status| {
+                Ok(database::order_execution_status::OrderExecutionStatus {
+                    order_uid: ByteArray(status.order_uid.0),
+                    auction_id: status.auction_id,
+                    solver: ByteArray(status.solver.0.0),
+                    solution_uid: status
+                        .solution_uid
+                        .try_into()
+                        .context("solution uid overflow")?,
+                    deadline_block: status
+                        .deadline_block
+                        .try_into()
+                        .context("deadline block overflow")?,
+                    tx_hash: status.tx.map(|tx| ByteArray(tx.0.0)),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["insert_order_execution_statuses"])
+            .start_timer();
+
+        database::order_execution_status::insert_batch(&mut ex, statuses).await?;
+        Ok(())
+    }
+
+    /// Records the settlement transaction of all orders of a winning solution.
+    pub async fn store_order_execution_tx(
+        &self,
+        auction_id: domain::auction::Id,
+        solver: eth::Address,
+        solution_uid: usize,
+        tx: eth::TxId,
+    ) -> Result<(), DatabaseError> {
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["update_order_execution_tx"])
+            .start_timer();
+
+        database::order_execution_status::set_tx_hash(
+            &mut ex,
+            auction_id,
+            ByteArray(solver.0.0),
+            solution_uid.try_into().context("solution uid overflow")?,
+            ByteArray(tx.0.0),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Loads the execution status of the order in the most recent auction it
+    /// won. Returns `None` if the order never was part of a winning solution.
+    pub async fn load_order_execution_status(
+        &self,
+        order_uid: domain::OrderUid,
+    ) -> anyhow::Result<Option<domain::settlement::OrderExecutionStatus>> {
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["load_order_execution_status"])
+            .start_timer();
+
+        let Some(status) =
+            database::order_execution_status::fetch_latest(&mut ex, &ByteArray(order_uid.0))
+                .await
+                .context("order_execution_status::fetch_latest")?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(domain::settlement::OrderExecutionStatus {
+            order_uid,
+            auction_id: status.auction_id,
+            solver: eth::Address(status.solver.0.into()),
+            solution_uid: status
+                .solution_uid
+                .try_into()
+                .context("negative solution uid")?,
+            deadline_block: status
+                .deadline_block
+                .try_into()
+                .context("negative deadline block")?,
+            tx: status.tx_hash.map(|tx| eth::TxId(H256(tx.0))),
+        }))
+    }
+
+    /// Finds solvers that won `last_auctions_count` consecutive auctions but
+    /// never settled any of them. The current block is used to prevent
+    /// selecting auctions with deadline after the current block since they
+    /// still can be settled.
+    pub async fn find_non_settling_solvers(
+        &self,
+        last_auctions_count: u32,
+        current_block: u64,
+    ) -> anyhow::Result<Vec<eth::Address>> {
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["find_non_settling_solvers"])
+            .start_timer();
+
+        Ok(database::solver_competition_v2::find_non_settling_solvers(
+            &mut ex,
+            last_auctions_count,
+            current_block,
+        )
+        .await
+        .context("failed to fetch non-settling solvers")?
+        .into_iter()
+        .map(|solver| eth::Address(solver.0.into()))
+        .collect())
+    }
+
+    /// Finds solvers that have a failure settling rate above the given
+    /// ratio. The current block is used to prevent selecting auctions with
+    /// deadline after the current block since they still can be settled.
+    pub async fn find_low_settling_solvers(
+        &self,
+        last_auctions_count: u32,
+        current_block: u64,
+        max_failure_rate: f64,
+        min_wins_threshold: u32,
+    ) -> anyhow::Result<Vec<eth::Address>> {
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["find_low_settling_solvers"])
+            .start_timer();
+
+        Ok(database::solver_competition_v2::find_low_settling_solvers(
+            &mut ex,
+            last_auctions_count,
+            current_block,
+            max_failure_rate,
+            min_wins_threshold,
+        )
+        .await
+        .context("solver_competition::find_low_settling_solvers")?
+        .into_iter()
+        .map(|solver| eth::Address(solver.0.into()))
+        .collect())
+    }
+
+    pub async fn get_solver_winning_solutions(
+        &self,
+        auction_id: domain::auction::Id,
+        solver: eth::Address,
+    ) -> Result<Vec<Solution>, DatabaseError> {
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["fetch_solver_winning_solutions"])
+            .start_timer();
+
+        Ok(
+            database::solver_competition_v2::fetch_solver_winning_solutions(
+                &mut ex,
+                auction_id,
+                ByteArray(solver.0.0),
+            )
+            .await
+            .context("solver_competition::fetch_solver_winning_solutions")?,
+        )
+    }
+
+    /// Stores everything the autopilot knows about an auction that is needed
+    /// to compute the solver rewards. Overwrites previously stored inputs of
+    /// the same auction.
+    pub async fn save_reward_inputs(
+        &self,
+        auction_id: domain::auction::Id,
+        inputs: &domain::competition::reward_inputs::RewardInputs,
+    ) -> Result<(), DatabaseError> {
+        let winners =
+            serde_json::to_value(dto::reward_inputs::winners_from_domain(&inputs.winners))
+                .context("serialize winners")?;
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["save_reward_inputs"])
+            .start_timer();
+
+        database::reward_inputs::upsert(
+            &mut ex,
+            &database::reward_inputs::RewardInputs {
+                auction_id,
+                block_deadline: inputs
+                    .block_deadline
+                    .try_into()
+                    .context("block deadline overflow")?,
+                competition_data_complete: inputs.competition_data_complete,
+                winners,
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Loads the stored reward inputs of all auctions in the given range,
+    /// ordered by auction id.
+    pub async fn load_reward_inputs(
+        &self,
+        auctions: std::ops::RangeInclusive<domain::auction::Id>,
+    ) -> anyhow::Result<
+        Vec<(
+            domain::auction::Id,
+            domain::competition::reward_inputs::RewardInputs,
+        )>,
+    > {
+        let mut ex = self.postgres.pool.acquire().await.context("acquire")?;
+        let _timer = Metrics::get()
+            .database_queries
+            .with_label_values(&["load_reward_inputs"])
+            .start_timer();
+
+        database::reward_inputs::fetch_range(&mut ex, *auctions.start(), *auctions.end())
+            .await
+            .context("reward_inputs::fetch_range")?
+            .into_iter()
+            .map(|row| {
+                let winners = serde_json::from_value(row.winners)
+                    .with_context(|| format!("invalid winners for auction {}", row.auction_id))?;
+                let inputs = domain::competition::reward_inputs::RewardInputs {
+                    block_deadline: row
+                        .block_deadline
+                        .try_into()
+                        .context("negative block deadline")?,
+                    competition_data_complete: row.competition_data_complete,
+                    winners: dto::reward_inputs::winners_into_domain(winners),
+                };
+                Ok((row.auction_id, inputs))
+            })
+            .collect()
+    }
+}
+
+/// Auction data that is required to compute solver rewards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum AuctionDataKind {
+    Prices,
+    FeePolicies,
+    JitOwners,
+}
+
+/// Datasets that only get kept for a configurable retention period. Order
+/// events aren't part of this since the periodic DB cleanup already prunes
+/// them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum RetainedData {
+    /// JSON blobs of the `solver_competitions` table.
+    SolverCompetitions,
+    AuctionPrices,
+    SettlementExecutions,
+}
+
+impl RetainedData {
+    /// Whether the data belongs to auctions and therefore gets pruned based
+    /// on the settlement executions of these auctions.
+    pub fn is_auction_based(&self) -> bool {
+        matches!(self, Self::SolverCompetitions | Self::AuctionPrices)
+    }
+}
+
+/// Reward relevant data that should have been stored for an auction.
+#[derive(Debug, Clone, Default)]
+pub struct ExpectedAuctionData {
+    prices: HashMap<eth::TokenAddress, BigDecimal>,
+    /// Number of fee policies of every order that has any.
+    fee_policies: HashMap<domain::OrderUid, i64>,
+    jit_owners: HashSet<eth::Address>,
+}
+
+/// Reward relevant data that is actually stored for an auction.
+#[derive(Debug, Clone, Default)]
+struct StoredAuctionData {
+    prices: HashMap<eth::TokenAddress, BigDecimal>,
+    fee_policies: HashMap<domain::OrderUid, i64>,
+    jit_owners: Option<HashSet<eth::Address>>,
+}
+
+impl ExpectedAuctionData {
+    pub fn new(
+        auction: &domain::Auction,
+        fee_policies: &[(domain::OrderUid, Vec<domain::fee::Policy>)],
+    ) -> Self {
+        Self {
+            prices: auction
+                .prices
+                .iter()
+                .map(|(token, price)| (*token, u256_to_big_decimal(&price.get().0)))
+                .collect(),
+            fee_policies: fee_policies
+                .iter()
+                .filter(|(_, policies)| !policies.is_empty())
+                .map(|(order, policies)| {
+                    (*order, i64::try_from(policies.len()).unwrap_or(i64::MAX))
+                })
+                .collect(),
+            jit_owners: auction
+                .surplus_capturing_jit_order_owners
+                .iter()
+                .copied()
+                .collect(),
+        }
+    }
+
+    fn missing(&self, stored: &StoredAuctionData) -> Vec<AuctionDataKind> {
+        let mut missing = Vec::new();
+        if self.prices != stored.prices {
+            missing.push(AuctionDataKind::Prices);
+        }
+        if self.fee_policies != stored.fee_policies {
+            missing.push(AuctionDataKind::FeePolicies);
+        }
+        if stored.jit_owners.as_ref() != Some(&self.jit_owners) {
+            missing.push(AuctionDataKind::JitOwners);
+        }
+        missing
+    }
+}
 
 #[derive(prometheus_metric_storage::MetricStorage)]
 struct Metrics {
     /// Timing of db queries.
     #[metric(name = "persistence_database_queries", labels("type"))]
     database_queries: prometheus::HistogramVec,
+
+    /// Number of rows written by the most recent write of auction data.
+    #[metric(name = "persistence_rows_written", labels("type"))]
+    rows_written: prometheus::IntGaugeVec,
 }
 
 impl Metrics {
@@ -992,3 +2168,89 @@ pub mod error {
         InvalidPrice(eth::TokenAddress),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expected() -> ExpectedAuctionData {
+        ExpectedAuctionData {
+            prices: HashMap::from([
+                (eth::TokenAddress(eth::H160([1; 20])), BigDecimal::from(1)),
+                (eth::TokenAddress(eth::H160([2; 20])), BigDecimal::from(2)),
+            ]),
+            fee_policies: HashMap::from([(domain::OrderUid([1; 56]), 2)]),
+            jit_owners: HashSet::from([eth::Address(eth::H160([3; 20]))]),
+        }
+    }
+
+    fn stored(expected: &ExpectedAuctionData) -> StoredAuctionData {
+        StoredAuctionData {
+            prices: expected.prices.clone(),
+            fee_policies: expected.fee_policies.clone(),
+            jit_owners: Some(expected.jit_owners.clone()),
+        }
+    }
+
+    #[test]
+    fn complete_auction_data() {
+        let expected = expected();
+        assert!(expected.missing(&stored(&expected)).is_empty());
+
+        // nothing expected apart from the (possibly empty) jit owners
+        let stored = StoredAuctionData {
+            jit_owners: Some(Default::default()),
+            ..Default::default()
+        };
+        assert!(ExpectedAuctionData::default().missing(&stored).is_empty());
+    }
+
+    #[test]
+    fn incomplete_auction_data() {
+        let expected = expected();
+
+        let mut partial = stored(&expected);
+        partial
+            .prices
+            .remove(&eth::TokenAddress(eth::H160([1; 20])));
+        assert_eq!(expected.missing(&partial), vec![AuctionDataKind::Prices]);
+
+        assert_eq!(
+            expected.missing(&StoredAuctionData::default()),
+            vec![
+                AuctionDataKind::Prices,
+                AuctionDataKind::FeePolicies,
+                AuctionDataKind::JitOwners
+            ]
+        );
+    }
+
+    #[test]
+    fn auction_data_differing_from_source() {
+        let expected = expected();
+
+        // same amount of data but different content
+        let mut differing = stored(&expected);
+        differing
+            .prices
+            .insert(eth::TokenAddress(eth::H160([1; 20])), BigDecimal::from(3));
+        differing.fee_policies = HashMap::from([(domain::OrderUid([2; 56]), 2)]);
+        differing.jit_owners = Some(HashSet::from([eth::Address(eth::H160([4; 20]))]));
+        assert_eq!(
+            expected.missing(&differing),
+            vec![
+                AuctionDataKind::Prices,
+                AuctionDataKind::FeePolicies,
+                AuctionDataKind::JitOwners
+            ]
+        );
+
+        // duplicated fee policies
+        let mut duplicated = stored(&expected);
+        duplicated.fee_policies.insert(domain::OrderUid([1; 56]), 4);
+        assert_eq!(
+            expected.missing(&duplicated),
+            vec![AuctionDataKind::FeePolicies]
+        );
+    }
+}
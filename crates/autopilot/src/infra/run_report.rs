@@ -0,0 +1,210 @@
+//! Structured record of everything the run loop decided in a single auction.
+//! Reports get dumped as versioned JSON documents for post-mortems.
+
+use {
+    crate::{
+        domain::{
+            self,
+            competition::{
+                self,
+                participation::ParticipationMatrix,
+                reward_inputs,
+                winner_selection::Ranking,
+            },
+        },
+        infra::persistence::dto,
+    },
+    chrono::{DateTime, Utc},
+    model::solver_competition::{CompetitionAuction, SolverSettlement},
+    number::serialization::HexOrDecimalU256,
+    primitive_types::{H160, U256},
+    serde::Serialize,
+    serde_with::{DurationMilliSeconds, serde_as},
+    std::{
+        collections::{BTreeMap, HashMap},
+        path::Path,
+        time::Duration,
+    },
+};
+
+/// Version of the report format. Bump whenever existing fields change their
+/// meaning or get removed.
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunReport {
+    version: u32,
+    auction_id: domain::auction::Id,
+    auction_block: u64,
+    started_at: DateTime<Utc>,
+    auction: CompetitionAuction,
+    drivers: Vec<Driver>,
+    /// Missing if no driver proposed a solution.
+    winner_selection: Option<WinnerSelection>,
+    /// Winners with the outcome of their settlement.
+    settlements: Vec<dto::reward_inputs::Winner>,
+}
+
+#[serde_as]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: String,
+    submission_address: H160,
+    outcome: &'static str,
+    proposed_solutions: usize,
+    accepted_solutions: usize,
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    solve_time_ms: Duration,
+    /// Proposed solutions that got discarded before entering the
+    /// competition.
+    filtered_solutions: Vec<FilteredSolution>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FilteredSolution {
+    /// Missing if the solution got discarded while being parsed.
+    solution_id: Option<u64>,
+    reason: &'static str,
+}
+
+#[serde_as]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WinnerSelection {
+    single_winner: bool,
+    /// All solutions that entered the competition, including the ones that
+    /// got filtered out as unfair.
+    solutions: Vec<SolverSettlement>,
+    #[serde_as(as = "BTreeMap<_, HexOrDecimalU256>")]
+    reference_scores: BTreeMap<H160, U256>,
+}
+
+impl RunReport {
+    pub fn new(auction: &domain::Auction, participation: &ParticipationMatrix) -> Self {
+        Self {
+            version: VERSION,
+            auction_id: auction.id,
+            auction_block: auction.block,
+            started_at: Utc::now(),
+            auction: dto::competition::auction_from_domain(auction),
+            drivers: participation
+                .0
+                .iter()
+                .map(|participation| Driver {
+                    name: participation.driver.clone(),
+                    submission_address: participation.submission_address.0,
+                    outcome: participation.outcome.label(),
+                    proposed_solutions: participation.outcome.proposed(),
+                    accepted_solutions: participation.accepted,
+                    solve_time_ms: participation.solve_time,
+                    filtered_solutions: participation
+                        .filtered
+                        .iter()
+                        .map(|filtered| FilteredSolution {
+                            solution_id: filtered.solution_id,
+                            reason: filtered.reason,
+                        })
+                        .collect(),
+                })
+                .collect(),
+            winner_selection: None,
+            settlements: Vec::new(),
+        }
+    }
+
+    pub fn record_ranking(
+        &mut self,
+        ranking: &Ranking,
+        reference_scores: &HashMap<domain::eth::Address, competition::Score>,
+        single_winner: bool,
+    ) {
+        self.winner_selection = Some(WinnerSelection {
+            single_winner,
+            solutions: ranking
+                .enumerated()
+                .map(|(index, participant)| {
+                    dto::competition::solution_from_domain(index + 1, participant)
+                })
+                .collect(),
+            reference_scores: reference_scores
+                .iter()
+                .map(|(solver, score)| (solver.0, score.get().0))
+                .collect(),
+        });
+    }
+
+    pub fn record_settlements(&mut self, winners: &[reward_inputs::Winner]) {
+        self.settlements = dto::reward_inputs::winners_from_domain(winners);
+    }
+
+    /// Writes the report to `{dir}/{auction_id}.json`.
+    pub async fn write(&self, dir: &Path) -> anyhow::Result<()> {
+        let json = serde_json::to_vec_pretty(self)?;
+        tokio::fs::write(dir.join(format!("{}.json", self.auction_id)), json).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::domain::{
+            auction::ContentHash,
+            competition::participation::{Filtered, Outcome, Participation},
+            eth,
+        },
+    };
+
+    #[test]
+    fn serializes_versioned_report() {
+        let auction = domain::Auction {
+            id: 7,
+            block: 100,
+            orders: Default::default(),
+            prices: Default::default(),
+            surplus_capturing_jit_order_owners: vec![],
+            content_hash: ContentHash::new(&[], &Default::default(), &[]),
+            quote_only: false,
+            prioritized_orders: Default::default(),
+        };
+        let participation = ParticipationMatrix(vec![Participation {
+            driver: "solver".to_string(),
+            submission_address: eth::Address(H160([1; 20])),
+            outcome: Outcome::Proposed(3),
+            solve_time: Duration::from_millis(1500),
+            accepted: 1,
+            filtered: vec![
+                Filtered {
+                    solution_id: None,
+                    reason: "zero_score",
+                },
+                Filtered {
+                    solution_id: Some(3),
+                    reason: "driver_gas_budget",
+                },
+            ],
+        }]);
+
+        let report = RunReport::new(&auction, &participation);
+        let json = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["version"], VERSION);
+        assert_eq!(json["auctionId"], 7);
+        assert_eq!(json["drivers"][0]["outcome"], "proposed");
+        assert_eq!(json["drivers"][0]["acceptedSolutions"], 1);
+        assert_eq!(json["drivers"][0]["solveTimeMs"], 1500);
+        assert_eq!(
+            json["drivers"][0]["filteredSolutions"],
+            serde_json::json!([
+                { "solutionId": null, "reason": "zero_score" },
+                { "solutionId": 3, "reason": "driver_gas_budget" },
+            ])
+        );
+        assert!(json["winnerSelection"].is_null());
+        assert_eq!(json["settlements"], serde_json::json!([]));
+    }
+}
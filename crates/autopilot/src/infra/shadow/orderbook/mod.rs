@@ -2,7 +2,8 @@
 
 use {
     crate::{domain, infra::persistence::dto},
-    reqwest::Url,
+    model::solver_competition_v2,
+    reqwest::{StatusCode, Url},
 };
 
 pub struct Orderbook {
@@ -28,4 +29,24 @@ impl Orderbook {
             .map(dto::Auction::try_into_domain)
             .map_err(Into::<anyhow::Error>::into)?
     }
+
+    /// Retrieves the result of the competition for the given auction. Returns
+    /// `None` if the competition is not stored yet.
+    pub async fn solver_competition(
+        &self,
+        auction_id: domain::auction::Id,
+    ) -> anyhow::Result<Option<solver_competition_v2::Response>> {
+        let response = self
+            .client
+            .get(shared::url::join(
+                &self.url,
+                &format!("api/v2/solver_competition/{auction_id}"),
+            ))
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
 }
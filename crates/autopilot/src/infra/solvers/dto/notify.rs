@@ -1,7 +1,10 @@
 use {
+    crate::domain,
     chrono::{DateTime, Utc},
+    number::serialization::HexOrDecimalU256,
+    primitive_types::U256,
     serde::Serialize,
-    serde_with::serde_as,
+    serde_with::{DisplayFromStr, serde_as},
 };
 
 #[serde_as]
@@ -12,6 +15,37 @@ pub enum Request {
         reason: BanReason,
         until: DateTime<Utc>,
     },
+    #[serde(rename_all = "camelCase")]
+    CompetitionOutcome {
+        #[serde_as(as = "DisplayFromStr")]
+        auction_id: i64,
+        rank: usize,
+        is_winner: bool,
+        #[serde_as(as = "Option<HexOrDecimalU256>")]
+        reference_score: Option<U256>,
+    },
+}
+
+impl Request {
+    pub fn kind(&self) -> domain::competition::notification::Kind {
+        match self {
+            Self::Banned { .. } => domain::competition::notification::Kind::Banned,
+            Self::CompetitionOutcome { .. } => {
+                domain::competition::notification::Kind::CompetitionOutcome
+            }
+        }
+    }
+}
+
+impl From<domain::competition::CompetitionOutcome> for Request {
+    fn from(outcome: domain::competition::CompetitionOutcome) -> Self {
+        Self::CompetitionOutcome {
+            auction_id: outcome.auction_id,
+            rank: outcome.rank,
+            is_winner: outcome.is_winner,
+            reference_score: outcome.reference_score.map(|score| score.get().0),
+        }
+    }
 }
 
 #[serde_as]
@@ -22,6 +56,9 @@ pub enum BanReason {
     UnsettledConsecutiveAuctions,
     /// Driver's settle failure rate is above the threshold.
     HighSettleFailureRate,
+    /// The solver's submission address is not allowed to settle by the
+    /// authenticator contract.
+    DenyListed,
 }
 
 impl BanReason {
@@ -29,6 +66,7 @@ impl BanReason {
         match self {
             BanReason::UnsettledConsecutiveAuctions => "non_settling",
             BanReason::HighSettleFailureRate => "high_settle_failure_rate",
+            BanReason::DenyListed => "deny_listed",
         }
     }
 }
@@ -1,5 +1,8 @@
 use {
-    serde::Serialize,
+    bytes_hex::BytesHex,
+    number::serialization::HexOrDecimalU256,
+    primitive_types::U256,
+    serde::{Deserialize, Serialize},
     serde_with::{serde_as, skip_serializing_none},
 };
 
@@ -15,4 +18,33 @@ pub struct Request {
     /// Auction ID in which the specified solution ID is competing.
     #[serde_as(as = "serde_with::DisplayFromStr")]
     pub auction_id: i64,
+    /// Set when the autopilot asks the driver to resubmit an already
+    /// requested settlement (e.g. with a higher gas price) because it
+    /// didn't appear on chain yet.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub rebroadcast: bool,
+    /// Calldata of the winning solution. Only set when the request gets
+    /// handed to a fallback submitter which then submits it instead of one of
+    /// its own solutions.
+    #[serde_as(as = "Option<BytesHex>")]
+    pub calldata: Option<Vec<u8>>,
+    /// Score the solution competed with. Lets the driver verify that the
+    /// solution ID refers to the solution that won.
+    #[serde_as(as = "HexOrDecimalU256")]
+    pub score: U256,
+}
+
+/// Body of the error response of a failed `/settle` request.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Error {
+    pub kind: String,
+}
+
+impl Error {
+    /// Whether the driver couldn't get the settlement transaction included
+    /// on-chain (e.g. because it reverted).
+    pub fn failed_to_submit(&self) -> bool {
+        self.kind == "FailedToSubmit"
+    }
 }
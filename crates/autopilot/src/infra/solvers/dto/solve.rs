@@ -4,6 +4,7 @@ use {
         domain::{self, eth},
         infra::persistence::dto::{self, order::Order},
     },
+    anyhow::{Context, ensure},
     chrono::{DateTime, Utc},
     itertools::Itertools,
     number::serialization::HexOrDecimalU256,
@@ -12,11 +13,46 @@ use {
     serde_with::{DisplayFromStr, serde_as},
     std::{
         collections::{HashMap, HashSet},
+        num::NonZeroUsize,
+        str::FromStr,
         sync::Arc,
         time::Duration,
     },
 };
 
+/// Version of the `/solve` schema spoken with a driver. Drivers that didn't
+/// migrate yet keep receiving the previous schema.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Version {
+    /// The original schema without an explicit version.
+    #[default]
+    V1,
+    /// Requests and responses state their version so mismatches get
+    /// detected instead of silently misinterpreting the payload.
+    V2,
+}
+
+impl Version {
+    fn number(self) -> u32 {
+        match self {
+            Self::V1 => 1,
+            Self::V2 => 2,
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" => Ok(Self::V1),
+            "2" => Ok(Self::V2),
+            _ => anyhow::bail!("unsupported solve dto version {s}"),
+        }
+    }
+}
+
 /// Cheaply clonable handle to an already JSON serialized
 /// request. The purpose of this is to make it ergonomic
 /// to serialize a request once and reuse the resulting
@@ -28,6 +64,38 @@ impl Request {
     pub fn as_str(&self) -> &str {
         self.0.get()
     }
+
+    /// Returns a copy of the request whose deadline is `time_limit` from now.
+    /// Only the top level of the already serialized request gets parsed again
+    /// so this is much cheaper than building a new request.
+    pub fn with_time_limit(&self, time_limit: Duration) -> Self {
+        self.with_field(
+            "deadline",
+            &(Utc::now() + chrono::Duration::from_std(time_limit).unwrap()),
+        )
+    }
+
+    /// Returns the request in the schema of `version`. Requests get built in
+    /// the [`Version::V1`] schema so older drivers receive exactly the same
+    /// bytes as before.
+    pub fn with_version(&self, version: Version) -> Self {
+        match version {
+            Version::V1 => self.clone(),
+            Version::V2 => self.with_field("version", &version.number()),
+        }
+    }
+
+    /// Returns a copy of the request with the top level field `name` set to
+    /// `value`.
+    fn with_field(&self, name: &str, value: &impl Serialize) -> Self {
+        let mut fields: HashMap<&str, &serde_json::value::RawValue> =
+            serde_json::from_str(self.as_str()).expect("request is a JSON object");
+        let value = serde_json::value::to_raw_value(value).expect("value is serializable");
+        fields.insert(name, &value);
+        Self(Arc::from(serde_json::value::to_raw_value(&fields).expect(
+            "only fails with non-string keys which we do not have",
+        )))
+    }
 }
 
 impl Request {
@@ -35,15 +103,16 @@ impl Request {
         auction: &domain::Auction,
         trusted_tokens: &HashSet<H160>,
         time_limit: Duration,
+        truncated: bool,
+        buffers: Option<&HashMap<eth::TokenAddress, U256>>,
+        ordering: domain::auction::OrderOrdering,
+        max_solutions: Option<NonZeroUsize>,
     ) -> Self {
+        let mut orders: Vec<_> = auction.orders.iter().collect();
+        ordering.apply(auction.id, &auction.prioritized_orders, &mut orders);
         let helper = RequestHelper {
             id: auction.id,
-            orders: auction
-                .orders
-                .clone()
-                .into_iter()
-                .map(dto::order::from_domain)
-                .collect(),
+            orders: orders.into_iter().map(dto::order::from_domain).collect(),
             tokens: auction
                 .prices
                 .iter()
@@ -65,6 +134,15 @@ impl Request {
                 .iter()
                 .map(|address| address.0)
                 .collect::<Vec<_>>(),
+            truncated,
+            quote_only: auction.quote_only,
+            buffers: buffers.map(|buffers| {
+                buffers
+                    .iter()
+                    .map(|(token, balance)| (token.0, *balance))
+                    .collect()
+            }),
+            max_solutions,
         };
         Self(Arc::from(serde_json::value::to_raw_value(&helper).expect(
             "only fails with non-string keys which we do not have",
@@ -73,12 +151,40 @@ impl Request {
 }
 
 impl Response {
+    /// Parses a response in the schema of `version`.
+    pub fn parse(body: &[u8], version: Version) -> anyhow::Result<Self> {
+        match version {
+            Version::V1 => serde_json::from_slice(body).context("invalid v1 response"),
+            Version::V2 => {
+                #[derive(Deserialize)]
+                struct Versioned {
+                    version: u32,
+                    #[serde(flatten)]
+                    response: Response,
+                }
+
+                let versioned: Versioned =
+                    serde_json::from_slice(body).context("invalid v2 response")?;
+                ensure!(
+                    versioned.version == version.number(),
+                    "expected a v{} response but got v{}",
+                    version.number(),
+                    versioned.version
+                );
+                Ok(versioned.response)
+            }
+        }
+    }
+
     pub fn into_domain(
         self,
+        prices: &domain::auction::Prices,
+        dust_threshold: eth::Ether,
+        weth: eth::WrappedNativeToken,
     ) -> Vec<Result<domain::competition::Solution, domain::competition::SolutionError>> {
         self.solutions
             .into_iter()
-            .map(Solution::into_domain)
+            .map(|solution| solution.into_domain(prices, dust_threshold, weth))
             .collect()
     }
 }
@@ -93,6 +199,22 @@ struct RequestHelper {
     pub orders: Vec<Order>,
     pub deadline: DateTime<Utc>,
     pub surplus_capturing_jit_order_owners: Vec<H160>,
+    /// Whether the driver only received a subset of the auction's orders.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// Whether the solutions only get used to assess price quality and will
+    /// never be settled, so drivers can skip expensive simulations.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub quote_only: bool,
+    /// Balances of the settlement contract that can be used to internalize
+    /// trades.
+    #[serde_as(as = "Option<HashMap<_, HexOrDecimalU256>>")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub buffers: Option<HashMap<H160, U256>>,
+    /// How many solutions the autopilot considers at most. Any further
+    /// solutions get discarded, so drivers should only return their best ones.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_solutions: Option<NonZeroUsize>,
 }
 
 #[serde_as]
@@ -106,24 +228,66 @@ pub struct Token {
 }
 
 impl Solution {
+    /// Converts the solution into its domain representation. Order executions
+    /// that are dust (see [`domain::competition::TradedOrder::is_dust`]) get
+    /// dropped and solutions consisting only of dust executions are rejected.
+    /// Solutions trading the native token or its wrapped counterpart must
+    /// provide a clearing price for exactly the token they trade since the
+    /// prices don't get folded when computing scores and fees.
     pub fn into_domain(
         self,
+        prices: &domain::auction::Prices,
+        dust_threshold: eth::Ether,
+        weth: eth::WrappedNativeToken,
     ) -> Result<domain::competition::Solution, domain::competition::SolutionError> {
+        let proposed = self.orders.len();
+        let mut dust_orders = HashSet::new();
+        let orders: HashMap<_, _> = self
+            .orders
+            .into_iter()
+            .map(|(uid, amounts)| (domain::OrderUid::from(uid), amounts.into_domain()))
+            .filter(|(uid, execution)| {
+                let is_dust = execution.is_dust(prices, dust_threshold);
+                if is_dust {
+                    tracing::debug!(
+                        solution = self.solution_id,
+                        ?uid,
+                        ?execution,
+                        "dropping dust execution"
+                    );
+                    dust_orders.insert(*uid);
+                }
+                !is_dust
+            })
+            .collect();
+        if proposed > 0 && orders.is_empty() {
+            return Err(domain::competition::SolutionError::DustExecution);
+        }
+        let wrapped = eth::TokenAddress::from(weth);
+        let is_native = |token: &eth::TokenAddress| token.as_erc20(weth) == wrapped;
+        let missing_native_price = orders
+            .values()
+            .flat_map(|order| [order.sell.token, order.buy.token])
+            .filter(is_native)
+            .any(|token| !self.clearing_prices.contains_key(&token.0));
+        if missing_native_price {
+            return Err(domain::competition::SolutionError::MissingNativePrice);
+        }
+
         Ok(domain::competition::Solution::new(
             self.solution_id,
             self.submission_address.into(),
             domain::competition::Score::try_new(self.score.into())?,
-            self.orders
-                .into_iter()
-                .map(|(o, amounts)| (o.into(), amounts.into_domain()))
-                .collect(),
+            orders,
             self.clearing_prices
                 .into_iter()
                 .map(|(token, price)| {
                     domain::auction::Price::try_new(price.into()).map(|price| (token.into(), price))
                 })
                 .collect::<Result<_, _>>()?,
-        ))
+            self.gas.map(|gas| eth::Gas(gas.into())),
+        )
+        .with_dust_orders(dust_orders))
     }
 }
 
@@ -203,3 +367,281 @@ pub struct Solution {
 pub struct Response {
     pub solutions: Vec<Solution>,
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::domain::auction::ContentHash};
+
+    #[test]
+    fn replaces_deadline() {
+        let auction = domain::Auction {
+            id: 7,
+            block: 100,
+            orders: Default::default(),
+            prices: Default::default(),
+            surplus_capturing_jit_order_owners: vec![],
+            content_hash: ContentHash::new(&[], &Default::default(), &[]),
+            quote_only: false,
+            prioritized_orders: Default::default(),
+        };
+        let request = Request::new(
+            &auction,
+            &HashSet::new(),
+            Duration::from_secs(60),
+            false,
+            None,
+            Default::default(),
+            None,
+        );
+
+        let padded = request.with_time_limit(Duration::from_secs(1));
+
+        let original: serde_json::Value = serde_json::from_str(request.as_str()).unwrap();
+        let mut padded: serde_json::Value = serde_json::from_str(padded.as_str()).unwrap();
+        let deadline: DateTime<Utc> = serde_json::from_value(padded["deadline"].take()).unwrap();
+        assert!(deadline <= Utc::now() + chrono::Duration::seconds(1));
+        // everything else stays untouched
+        padded["deadline"] = original["deadline"].clone();
+        assert_eq!(padded, original);
+    }
+
+    #[test]
+    fn marks_quote_only_auctions() {
+        let mut auction = domain::Auction {
+            id: 7,
+            block: 100,
+            orders: Default::default(),
+            prices: Default::default(),
+            surplus_capturing_jit_order_owners: vec![],
+            content_hash: ContentHash::new(&[], &Default::default(), &[]),
+            quote_only: false,
+            prioritized_orders: Default::default(),
+        };
+        let request = |auction: &domain::Auction| -> serde_json::Value {
+            let request = Request::new(
+                auction,
+                &HashSet::new(),
+                Duration::from_secs(60),
+                false,
+                None,
+                Default::default(),
+                None,
+            );
+            serde_json::from_str(request.as_str()).unwrap()
+        };
+
+        // regular auctions don't carry the flag at all
+        assert!(request(&auction).get("quoteOnly").is_none());
+
+        auction.quote_only = true;
+        assert_eq!(request(&auction)["quoteOnly"], true);
+    }
+
+    #[test]
+    fn announces_max_solutions() {
+        let auction = domain::Auction {
+            id: 7,
+            block: 100,
+            orders: Default::default(),
+            prices: Default::default(),
+            surplus_capturing_jit_order_owners: vec![],
+            content_hash: ContentHash::new(&[], &Default::default(), &[]),
+            quote_only: false,
+            prioritized_orders: Default::default(),
+        };
+        let request = |max_solutions| -> serde_json::Value {
+            let request = Request::new(
+                &auction,
+                &HashSet::new(),
+                Duration::from_secs(60),
+                false,
+                None,
+                Default::default(),
+                max_solutions,
+            );
+            serde_json::from_str(request.as_str()).unwrap()
+        };
+
+        assert!(request(None).get("maxSolutions").is_none());
+        assert_eq!(request(NonZeroUsize::new(3))["maxSolutions"], 3);
+    }
+
+    #[test]
+    fn previous_version_keeps_production_bytes() {
+        let auction = |quote_only: bool| domain::Auction {
+            id: 7,
+            block: 100,
+            orders: Default::default(),
+            prices: Default::default(),
+            surplus_capturing_jit_order_owners: vec![H160([1; 20]).into()],
+            content_hash: ContentHash::new(&[], &Default::default(), &[]),
+            quote_only,
+            prioritized_orders: Default::default(),
+        };
+        let buffers = HashMap::from([(eth::TokenAddress(H160([2; 20])), U256::from(100))]);
+        let trusted = HashSet::from([H160([3; 20])]);
+
+        for quote_only in [false, true] {
+            for truncated in [false, true] {
+                for buffers in [None, Some(&buffers)] {
+                    let production = Request::new(
+                        &auction(quote_only),
+                        &trusted,
+                        Duration::from_secs(60),
+                        truncated,
+                        buffers,
+                        Default::default(),
+                        NonZeroUsize::new(3),
+                    );
+
+                    let v1 = production.with_version(Version::V1);
+                    assert_eq!(v1.as_str(), production.as_str());
+
+                    // the next version only adds the version on top
+                    let v2 = production.with_version(Version::V2);
+                    let mut v2: serde_json::Value = serde_json::from_str(v2.as_str()).unwrap();
+                    assert_eq!(v2["version"], 2);
+                    v2.as_object_mut().unwrap().remove("version");
+                    let production: serde_json::Value =
+                        serde_json::from_str(production.as_str()).unwrap();
+                    assert_eq!(v2, production);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parses_responses_of_driver_version() {
+        let v1 = br#"{"solutions": []}"#;
+        let v2 = br#"{"version": 2, "solutions": []}"#;
+        let v3 = br#"{"version": 3, "solutions": []}"#;
+
+        assert!(Response::parse(v1, Version::V1).is_ok());
+        // unknown fields are ignored by older schemas
+        assert!(Response::parse(v2, Version::V1).is_ok());
+        assert!(Response::parse(v2, Version::V2).is_ok());
+        assert!(Response::parse(v1, Version::V2).is_err());
+        assert!(Response::parse(v3, Version::V2).is_err());
+    }
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!("1".parse::<Version>().unwrap(), Version::V1);
+        assert_eq!("2".parse::<Version>().unwrap(), Version::V2);
+        assert!("3".parse::<Version>().is_err());
+    }
+
+    fn solution(sell_token: H160, buy_token: H160, clearing_prices: &[H160]) -> Solution {
+        Solution {
+            solution_id: 1,
+            score: 1.into(),
+            submission_address: H160::zero(),
+            orders: HashMap::from([(
+                boundary::OrderUid([1; 56]),
+                TradedOrder {
+                    side: Side::Sell,
+                    sell_token,
+                    buy_token,
+                    limit_sell: 100.into(),
+                    limit_buy: 100.into(),
+                    executed_sell: 100.into(),
+                    executed_buy: 100.into(),
+                },
+            )]),
+            clearing_prices: clearing_prices
+                .iter()
+                .map(|token| (*token, 1.into()))
+                .collect(),
+            gas: None,
+        }
+    }
+
+    #[test]
+    fn requires_native_price() {
+        let weth = H160([0x11; 20]);
+        let eth = eth::NATIVE_TOKEN.0;
+        let token = H160([0x22; 20]);
+        let into_domain = |solution: Solution| {
+            solution.into_domain(&Default::default(), eth::Ether(0.into()), weth.into())
+        };
+
+        // buying ETH with only a WETH price
+        assert!(matches!(
+            into_domain(solution(token, eth, &[token, weth])),
+            Err(domain::competition::SolutionError::MissingNativePrice)
+        ));
+        // buying WETH with only an ETH price
+        assert!(matches!(
+            into_domain(solution(token, weth, &[token, eth])),
+            Err(domain::competition::SolutionError::MissingNativePrice)
+        ));
+        // the price of the traded token is required
+        assert!(into_domain(solution(token, eth, &[token, eth])).is_ok());
+        assert!(into_domain(solution(weth, token, &[token, weth])).is_ok());
+        for native in [eth, weth] {
+            assert!(matches!(
+                into_domain(solution(token, native, &[token])),
+                Err(domain::competition::SolutionError::MissingNativePrice)
+            ));
+            assert!(matches!(
+                into_domain(solution(native, token, &[token])),
+                Err(domain::competition::SolutionError::MissingNativePrice)
+            ));
+        }
+        // solutions without the native token don't need its price
+        assert!(into_domain(solution(token, H160([0x33; 20]), &[token])).is_ok());
+    }
+
+    /// Solution executing the order with uid `[i + 1; 56]` for
+    /// `executed[i]`.
+    fn solution_with_executions(executed: &[u64]) -> Solution {
+        Solution {
+            orders: executed
+                .iter()
+                .enumerate()
+                .map(|(index, executed)| {
+                    let order = TradedOrder {
+                        side: Side::Sell,
+                        sell_token: H160([0x22; 20]),
+                        buy_token: H160([0x33; 20]),
+                        limit_sell: 100.into(),
+                        limit_buy: 100.into(),
+                        executed_sell: (*executed).into(),
+                        executed_buy: (*executed).into(),
+                    };
+                    (boundary::OrderUid([index as u8 + 1; 56]), order)
+                })
+                .collect(),
+            ..solution(H160([0x22; 20]), H160([0x33; 20]), &[])
+        }
+    }
+
+    #[test]
+    fn drops_dust_executions() {
+        let weth = eth::WrappedNativeToken::from(H160([0x11; 20]));
+        let uid = |byte: u8| domain::OrderUid([byte; 56]);
+
+        let solution = solution_with_executions(&[100, 0])
+            .into_domain(&Default::default(), eth::Ether::default(), weth)
+            .unwrap();
+
+        assert_eq!(solution.order_ids().copied().collect::<Vec<_>>(), [uid(1)]);
+        assert_eq!(solution.dust_orders(), &HashSet::from([uid(2)]));
+    }
+
+    #[test]
+    fn rejects_solutions_with_only_dust_executions() {
+        let weth = eth::WrappedNativeToken::from(H160([0x11; 20]));
+
+        let result = solution_with_executions(&[0, 0]).into_domain(
+            &Default::default(),
+            eth::Ether::default(),
+            weth,
+        );
+
+        assert!(matches!(
+            result,
+            Err(domain::competition::SolutionError::DustExecution)
+        ));
+    }
+}
@@ -0,0 +1,221 @@
+//! In-process driver for tests. It serves `/solve`, `/settle` and `/notify` on
+//! an ephemeral port and answers according to a programmable [`Behavior`].
+
+use {
+    crate::{arguments::Account, domain::eth, infra},
+    axum::{Json, Router, extract::State, http::StatusCode, routing::post},
+    primitive_types::{H160, U256},
+    serde_json::json,
+    std::{
+        collections::HashSet,
+        sync::{Arc, Mutex},
+        time::Duration,
+    },
+    url::Url,
+};
+
+/// How the fake driver responds to requests.
+#[derive(Clone, Debug, Default)]
+pub struct Behavior {
+    /// Scores of the solutions returned by `/solve`. One solution per score.
+    pub scores: Vec<U256>,
+    /// Ids of the solutions returned by `/solve`. Defaults to the position of
+    /// the solution.
+    pub solution_ids: Option<Vec<u64>>,
+    /// Executed amounts of the order every solution returned by `/solve`
+    /// trades. Defaults to one more than the position of the solution, so the
+    /// solutions of a driver don't execute the same amounts.
+    pub executed: Option<Vec<u64>>,
+    /// Gas every solution returned by `/solve` reports.
+    pub gas: Option<u64>,
+    /// Address the solutions claim to get submitted from. Defaults to the
+    /// submission address of the driver.
+    pub submission_address: Option<H160>,
+    /// How long to wait before responding to `/solve`.
+    pub delay: Duration,
+    /// Respond to `/solve` with this status code instead of solutions.
+    pub error: Option<StatusCode>,
+    /// Never respond to `/settle` requests.
+    pub never_settle: bool,
+}
+
+#[derive(Clone)]
+struct Inner {
+    behavior: Arc<Mutex<Behavior>>,
+    submission_address: H160,
+    /// Bodies of all received `/notify` requests.
+    notifications: Arc<Mutex<Vec<serde_json::Value>>>,
+}
+
+pub struct FakeDriver {
+    inner: Inner,
+    url: Url,
+}
+
+impl FakeDriver {
+    /// Starts serving requests in a background task.
+    pub fn start(behavior: Behavior) -> Self {
+        let inner = Inner {
+            behavior: Arc::new(Mutex::new(behavior)),
+            submission_address: H160::random(),
+            notifications: Default::default(),
+        };
+        let app = Router::new()
+            .route("/solve", post(solve))
+            .route("/settle", post(settle))
+            .route("/notify", post(notify))
+            .with_state(inner.clone());
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(app.into_make_service());
+        let url = format!("http://{}/", server.local_addr()).parse().unwrap();
+        tokio::spawn(server);
+        Self { inner, url }
+    }
+
+    /// Changes how future requests get answered.
+    pub fn set_behavior(&self, behavior: Behavior) {
+        *self.inner.behavior.lock().unwrap() = behavior;
+    }
+
+    pub fn submission_address(&self) -> eth::Address {
+        self.inner.submission_address.into()
+    }
+
+    /// Bodies of the `/notify` requests received so far.
+    pub fn notifications(&self) -> Vec<serde_json::Value> {
+        self.inner.notifications.lock().unwrap().clone()
+    }
+
+    /// Builds an [`infra::Driver`] that sends its requests to this fake.
+    pub async fn driver(&self, name: &str) -> Arc<infra::Driver> {
+        self.build_driver(name, None, false, None).await
+    }
+
+    /// Like [`Self::driver`] but only allowed to trade `token_allow_list`.
+    pub async fn restricted_driver(
+        &self,
+        name: &str,
+        token_allow_list: Option<HashSet<eth::TokenAddress>>,
+    ) -> Arc<infra::Driver> {
+        self.build_driver(name, token_allow_list, false, None).await
+    }
+
+    /// Like [`Self::driver`] but its solutions never win.
+    pub async fn observe_only_driver(&self, name: &str) -> Arc<infra::Driver> {
+        self.build_driver(name, None, true, None).await
+    }
+
+    /// Like [`Self::driver`] but with a settlement gas budget.
+    pub async fn budgeted_driver(&self, name: &str, max_settlement_gas: u64) -> Arc<infra::Driver> {
+        self.build_driver(name, None, false, Some(eth::Gas(max_settlement_gas.into())))
+            .await
+    }
+
+    async fn build_driver(
+        &self,
+        name: &str,
+        token_allow_list: Option<HashSet<eth::TokenAddress>>,
+        observe_only: bool,
+        max_settlement_gas: Option<eth::Gas>,
+    ) -> Arc<infra::Driver> {
+        let mut driver = new_driver(self.url.clone(), name, self.inner.submission_address).await;
+        driver.max_settlement_gas = max_settlement_gas;
+        driver.token_allow_list = token_allow_list;
+        driver.observe_only = observe_only;
+        Arc::new(driver)
+    }
+}
+
+/// Builds an [`infra::Driver`] submitting from `submission_address` that
+/// isn't backed by a server, for tests that only need the driver's
+/// configuration. Its public fields can be adjusted as needed.
+pub async fn offline_driver(name: &str, submission_address: H160) -> infra::Driver {
+    new_driver(
+        "http://localhost".parse().unwrap(),
+        name,
+        submission_address,
+    )
+    .await
+}
+
+async fn new_driver(url: Url, name: &str, submission_address: H160) -> infra::Driver {
+    infra::Driver::try_new(
+        url,
+        name.to_string(),
+        None,
+        Account::Address(submission_address),
+        Vec::new(),
+        false,
+        None,
+        None,
+        None,
+        false,
+        Default::default(),
+    )
+    .await
+    .unwrap()
+}
+
+async fn solve(State(inner): State<Inner>) -> (StatusCode, Json<serde_json::Value>) {
+    let behavior = inner.behavior.lock().unwrap().clone();
+    tokio::time::sleep(behavior.delay).await;
+    if let Some(code) = behavior.error {
+        return (
+            code,
+            Json(json!({ "kind": "error", "description": "fake error" })),
+        );
+    }
+    let submission_address = behavior
+        .submission_address
+        .unwrap_or(inner.submission_address);
+    let solutions: Vec<_> = behavior
+        .scores
+        .iter()
+        .enumerate()
+        .map(|(index, score)| {
+            let id = behavior
+                .solution_ids
+                .as_ref()
+                .map_or(index as u64, |ids| ids[index]);
+            let executed = behavior
+                .executed
+                .as_ref()
+                .map_or(index as u64 + 1, |executed| executed[index]);
+            json!({
+                "solutionId": id,
+                "score": score.to_string(),
+                "submissionAddress": submission_address,
+                "orders": {
+                    format!("0x{}", hex::encode([1_u8; 56])): {
+                        "side": "sell",
+                        "sellToken": H160([2; 20]),
+                        "buyToken": H160([3; 20]),
+                        "limitSell": "1000",
+                        "limitBuy": "1000",
+                        "executedSell": executed.to_string(),
+                        "executedBuy": executed.to_string(),
+                    },
+                },
+                "clearingPrices": {},
+                "gas": behavior.gas,
+            })
+        })
+        .collect();
+    (StatusCode::OK, Json(json!({ "solutions": solutions })))
+}
+
+async fn notify(
+    State(inner): State<Inner>,
+    Json(notification): Json<serde_json::Value>,
+) -> StatusCode {
+    inner.notifications.lock().unwrap().push(notification);
+    StatusCode::OK
+}
+
+async fn settle(State(inner): State<Inner>) -> StatusCode {
+    let never_settle = inner.behavior.lock().unwrap().never_settle;
+    if never_settle {
+        std::future::pending::<()>().await;
+    }
+    StatusCode::OK
+}
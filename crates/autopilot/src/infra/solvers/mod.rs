@@ -1,20 +1,42 @@
 use {
     self::dto::{reveal, settle, solve},
-    crate::{arguments::Account, domain::eth, infra::solvers::dto::notify, util},
-    anyhow::{Context, Result, anyhow},
+    crate::{
+        arguments::Account,
+        domain::{
+            competition::{CompetitionOutcome, FairnessThreshold, notification},
+            eth,
+        },
+        infra::{
+            self,
+            http::{self, HttpError, Idempotency, RetryPolicy},
+            solvers::dto::notify,
+        },
+        util,
+    },
+    anyhow::{Context, ensure},
     chrono::{DateTime, Utc},
     observe::tracing::tracing_headers,
-    reqwest::{Client, StatusCode},
-    std::{sync::Arc, time::Duration},
+    reqwest::header::HeaderValue,
+    std::{
+        collections::HashSet,
+        num::NonZeroUsize,
+        str::FromStr,
+        sync::{Arc, Mutex},
+        time::Duration,
+    },
     thiserror::Error,
     tracing::instrument,
     url::Url,
 };
 
 pub mod dto;
+#[cfg(test)]
+pub mod fake;
 
 const RESPONSE_SIZE_LIMIT: usize = 10_000_000;
 const RESPONSE_TIME_LIMIT: Duration = Duration::from_secs(60);
+/// Weight of the most recent sample in the latency estimate.
+const LATENCY_SMOOTHING: f64 = 0.2;
 
 pub struct Driver {
     pub name: String,
@@ -22,10 +44,33 @@ pub struct Driver {
     // An optional threshold used to check "fairness" of provided solutions. If specified, a
     // winning solution should be discarded if it contains at least one order, which
     // another driver solved with surplus exceeding this driver's surplus by `threshold`
-    pub fairness_threshold: Option<eth::Ether>,
+    pub fairness_threshold: Option<FairnessThreshold>,
     pub submission_address: eth::Address,
+    /// Further addresses the driver may submit solutions from, e.g. while its
+    /// key gets rotated.
+    pub additional_submission_addresses: Vec<SubmissionAddress>,
     pub requested_timeout_on_problems: bool,
-    client: Client,
+    /// Maximum number of orders the driver is able to handle. Bigger auctions
+    /// get truncated before they are sent to the driver.
+    pub max_orders: Option<NonZeroUsize>,
+    /// Maximum amount of gas a settlement of this driver may use. Solutions
+    /// exceeding it get discarded before they can win the auction.
+    pub max_settlement_gas: Option<eth::Gas>,
+    /// Tokens the driver is allowed to trade. Orders trading other tokens
+    /// don't get sent to the driver.
+    pub token_allow_list: Option<HashSet<eth::TokenAddress>>,
+    /// Solutions of observe-only drivers get ranked and stored for comparison
+    /// but never win the auction.
+    pub observe_only: bool,
+    /// Version of the `/solve` schema the driver understands.
+    pub dto_version: solve::Version,
+    /// Artificial delay before forwarding `/solve` and `/settle` requests to
+    /// exercise timeout handling. Only honored with the `chaos` feature.
+    pub inject_latency: Option<std::time::Duration>,
+    /// Exponentially weighted moving average of how much longer than its
+    /// time budget it takes for the driver's responses to arrive.
+    latency: Mutex<Option<Duration>>,
+    client: http::Client,
 }
 
 #[derive(Error, Debug)]
@@ -36,14 +81,79 @@ pub enum Error {
     FailedToBuildClient(#[source] reqwest::Error),
 }
 
+/// An address a driver may submit solutions from during an optional window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubmissionAddress {
+    pub address: eth::Address,
+    /// Inclusive start of the window. Unbounded if not set.
+    pub valid_from: Option<DateTime<Utc>>,
+    /// Exclusive end of the window. Unbounded if not set.
+    pub valid_to: Option<DateTime<Utc>>,
+}
+
+impl SubmissionAddress {
+    pub fn is_valid_at(&self, time: DateTime<Utc>) -> bool {
+        self.valid_from.is_none_or(|from| from <= time) && self.valid_to.is_none_or(|to| time < to)
+    }
+}
+
+/// Parses `<address>` or `<address>@<from>..<to>` where `from` and `to` are
+/// RFC 3339 timestamps that can be left empty.
+impl FromStr for SubmissionAddress {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (address, window) = match value.split_once('@') {
+            Some((address, window)) => (address, Some(window)),
+            None => (value, None),
+        };
+        let address = eth::Address(address.parse().context("failed to parse address")?);
+        let Some(window) = window else {
+            return Ok(Self {
+                address,
+                valid_from: None,
+                valid_to: None,
+            });
+        };
+        let (from, to) = window
+            .split_once("..")
+            .context("validity window must have the format <from>..<to>")?;
+        let parse = |timestamp: &str| {
+            (!timestamp.is_empty())
+                .then(|| {
+                    DateTime::parse_from_rfc3339(timestamp).map(|time| time.with_timezone(&Utc))
+                })
+                .transpose()
+                .context("failed to parse timestamp")
+        };
+        let (valid_from, valid_to) = (parse(from)?, parse(to)?);
+        ensure!(
+            valid_from.zip(valid_to).is_none_or(|(from, to)| from < to),
+            "validity window must not be empty"
+        );
+        Ok(Self {
+            address,
+            valid_from,
+            valid_to,
+        })
+    }
+}
+
 impl Driver {
     #[instrument(skip_all)]
+    #[allow(clippy::too_many_arguments)]
     pub async fn try_new(
         url: Url,
         name: String,
-        fairness_threshold: Option<eth::Ether>,
+        fairness_threshold: Option<FairnessThreshold>,
         submission_account: Account,
+        additional_submission_addresses: Vec<SubmissionAddress>,
         requested_timeout_on_problems: bool,
+        max_orders: Option<NonZeroUsize>,
+        max_settlement_gas: Option<eth::Gas>,
+        token_allow_list: Option<HashSet<eth::TokenAddress>>,
+        observe_only: bool,
+        dto_version: solve::Version,
     ) -> Result<Self, Error> {
         let submission_address = match submission_account {
             Account::Kms(key_id) => {
@@ -64,6 +174,12 @@ impl Driver {
             ?url,
             ?fairness_threshold,
             ?submission_address,
+            ?additional_submission_addresses,
+            ?max_orders,
+            ?max_settlement_gas,
+            ?token_allow_list,
+            observe_only,
+            ?dto_version,
             "Creating solver"
         );
 
@@ -71,134 +187,250 @@ impl Driver {
             name,
             url,
             fairness_threshold,
-            client: Client::builder()
-                .timeout(RESPONSE_TIME_LIMIT)
-                .build()
-                .map_err(Error::FailedToBuildClient)?,
+            client: http::Client::new(
+                reqwest::Client::builder()
+                    .timeout(RESPONSE_TIME_LIMIT)
+                    .build()
+                    .map_err(Error::FailedToBuildClient)?,
+                RetryPolicy::default(),
+                RESPONSE_TIME_LIMIT,
+                RESPONSE_SIZE_LIMIT,
+            ),
             submission_address: submission_address.into(),
+            additional_submission_addresses,
             requested_timeout_on_problems,
+            max_orders,
+            max_settlement_gas,
+            token_allow_list,
+            observe_only,
+            dto_version,
+            inject_latency: None,
+            latency: Default::default(),
         })
     }
 
-    pub async fn solve(&self, request: solve::Request) -> Result<solve::Response> {
-        self.request_response("solve", request).await
+    /// Whether the driver may submit solutions from `address` at `time`. The
+    /// main submission address is always allowed.
+    pub fn allows_submission_address(&self, address: eth::Address, time: DateTime<Utc>) -> bool {
+        address == self.submission_address
+            || self
+                .additional_submission_addresses
+                .iter()
+                .any(|allowed| allowed.address == address && allowed.is_valid_at(time))
     }
 
-    pub async fn reveal(&self, request: reveal::Request) -> Result<reveal::Response> {
-        self.request_response("reveal", request).await
+    /// All addresses the driver may submit solutions from at `time`.
+    pub fn submission_addresses(&self, time: DateTime<Utc>) -> Vec<eth::Address> {
+        std::iter::once(self.submission_address)
+            .chain(
+                self.additional_submission_addresses
+                    .iter()
+                    .filter(|allowed| allowed.is_valid_at(time))
+                    .map(|allowed| allowed.address),
+            )
+            .collect()
     }
 
-    pub async fn settle(
-        &self,
-        request: &settle::Request,
-        timeout: std::time::Duration,
-    ) -> Result<()> {
-        let url = util::join(&self.url, "settle");
-        tracing::trace!(
-            path=&url.path(),
-            body=%serde_json::to_string_pretty(request).unwrap(),
-            "solver request",
-        );
+    /// Current estimate of the network latency to the driver.
+    pub fn latency(&self) -> Duration {
+        self.latency.lock().unwrap().unwrap_or_default()
+    }
+
+    /// Updates the latency estimate with a new sample and returns the new
+    /// estimate.
+    pub fn observe_latency(&self, sample: Duration) -> Duration {
+        let mut latency = self.latency.lock().unwrap();
+        let estimate = match *latency {
+            Some(estimate) => {
+                estimate.mul_f64(1. - LATENCY_SMOOTHING) + sample.mul_f64(LATENCY_SMOOTHING)
+            }
+            None => sample,
+        };
+        *latency = Some(estimate);
+        estimate
+    }
 
+    /// Sends the request in the driver's schema version and parses the
+    /// response accordingly.
+    pub async fn solve(&self, request: solve::Request) -> Result<solve::Response, HttpError> {
+        self.injected_latency().await;
+        let request = request.with_version(self.dto_version);
+        let body = request.as_str().as_bytes().to_vec();
         let response = self
             .client
-            .post(url)
-            .json(request)
-            .timeout(timeout)
-            .header("X-REQUEST-ID", request.auction_id.to_string())
-            .headers(tracing_headers())
-            .send()
+            .post(&self.request("solve", body, None, Idempotency::Idempotent))
+            .await?;
+        solve::Response::parse(&response, self.dto_version).map_err(|err| {
+            HttpError::Decode(err.context(format!("body {:?}", String::from_utf8_lossy(&response))))
+        })
+    }
+
+    /// Waits for [`Self::inject_latency`] in builds with the `chaos` feature.
+    async fn injected_latency(&self) {
+        #[cfg(feature = "chaos")]
+        if let Some(latency) = self.inject_latency {
+            tracing::debug!(driver = %self.name, ?latency, "injecting latency");
+            tokio::time::sleep(latency).await;
+        }
+    }
+
+    pub async fn reveal(&self, request: reveal::Request) -> Result<reveal::Response, HttpError> {
+        let body = serialize(&request)?;
+        self.client
+            .post_json(&self.request("reveal", body, None, Idempotency::Idempotent))
             .await
-            .context("send")?;
-        let status = response.status();
+    }
 
-        tracing::trace!(%status, "solver response");
+    pub async fn settle(
+        &self,
+        request: &settle::Request,
+        timeout: std::time::Duration,
+    ) -> Result<(), HttpError> {
+        self.injected_latency().await;
+        let mut http_request = self.request(
+            "settle",
+            serialize(request)?,
+            Some(timeout),
+            Idempotency::NonIdempotent,
+        );
+        http_request
+            .headers
+            .insert("X-REQUEST-ID", HeaderValue::from(request.auction_id));
+        self.client.post(&http_request).await?;
+        Ok(())
+    }
 
-        if status != StatusCode::OK {
-            let text = response.text().await.context("read error response body")?;
-            return Err(anyhow!("bad status {status}: {text}"));
-        }
+    pub async fn notify(&self, request: notify::Request) -> Result<(), HttpError> {
+        // The driver responds with an empty body so there is nothing to parse.
+        let body = serialize(&request)?;
+        self.client
+            .post(&self.request("notify", body, None, Idempotency::NonIdempotent))
+            .await?;
         Ok(())
     }
 
-    pub async fn notify(&self, request: notify::Request) -> Result<()> {
-        self.request_response("notify", request).await
+    /// Informs the driver about how it performed in the competition.
+    pub async fn notify_outcome(&self, outcome: CompetitionOutcome) -> Result<(), HttpError> {
+        self.notify(outcome.into()).await
     }
 
-    async fn request_response<Response, Request>(
+    fn request(
         &self,
         path: &str,
-        request: Request,
-    ) -> Result<Response>
-    where
-        Response: serde::de::DeserializeOwned,
-        Request: serde::Serialize + Send + Sync + 'static,
-    {
+        body: Vec<u8>,
+        timeout: Option<std::time::Duration>,
+        idempotency: Idempotency,
+    ) -> http::Request {
         let url = util::join(&self.url, path);
         tracing::trace!(
-            path=&url.path(),
-            body=%serde_json::to_string_pretty(&request).unwrap(),
+            path = &url.path(),
+            body = %String::from_utf8_lossy(&body),
             "solver request",
         );
-        let mut request = {
-            let builder = self.client.post(url.clone()).headers(tracing_headers());
-            // If the payload is very big then serializing it will block the
-            // executor a long time (mostly relevant for solve requests).
-            // That's why we always do it on a thread specifically for
-            // running blocking tasks.
-            tokio::task::spawn_blocking(move || builder.json(&request))
-                .await
-                .context("failed to build request")?
-        };
-
-        if let Some(request_id) = observe::distributed_tracing::request_id::from_current_span() {
-            request = request.header("X-REQUEST-ID", request_id);
+        let mut headers = tracing_headers();
+        if let Some(request_id) = observe::distributed_tracing::request_id::from_current_span()
+            .and_then(|request_id| HeaderValue::from_str(&request_id).ok())
+        {
+            headers.insert("X-REQUEST-ID", request_id);
         }
-
-        let mut response = request.send().await.context("send")?;
-        let status = response.status().as_u16();
-        let body = response_body_with_size_limit(&mut response, RESPONSE_SIZE_LIMIT)
-            .await
-            .context("body")?;
-        let text = String::from_utf8_lossy(&body);
-        tracing::trace!(%status, body=%text, "solver response");
-        let context = || format!("url {url}, body {text:?}");
-        if status != 200 {
-            return Err(anyhow!("bad status {status}, {}", context()));
+        http::Request {
+            url,
+            body,
+            headers,
+            timeout,
+            idempotency,
         }
-        serde_json::from_slice(&body).with_context(|| format!("bad json {}", context()))
     }
 }
 
-/// Extracts the bytes of the response up to some size limit.
-///
-/// Returns an error if the byte limit was exceeded.
-pub async fn response_body_with_size_limit(
-    response: &mut reqwest::Response,
-    limit: usize,
-) -> Result<Vec<u8>> {
-    let mut bytes = Vec::new();
-    while let Some(chunk) = response.chunk().await? {
-        let slice: &[u8] = &chunk;
-        if bytes.len() + slice.len() > limit {
-            return Err(anyhow!("size limit exceeded"));
-        }
-        bytes.extend_from_slice(slice);
-    }
-    Ok(bytes)
+fn serialize(request: &impl serde::Serialize) -> Result<Vec<u8>, HttpError> {
+    serde_json::to_vec(request).map_err(|err| HttpError::Other(err.into()))
 }
 
-/// Notifies the non-settling driver in a fire-and-forget manner.
+/// Notifies the non-settling driver in a fire-and-forget manner. The attempt
+/// gets recorded if `persistence` is set.
 pub fn notify_banned_solver(
     non_settling_driver: Arc<Driver>,
     reason: notify::BanReason,
     banned_until: DateTime<Utc>,
+    persistence: Option<infra::Persistence>,
 ) {
     let request = notify::Request::Banned {
         reason,
         until: banned_until,
     };
     tokio::spawn(async move {
-        let _ = non_settling_driver.notify(request).await;
+        let kind = request.kind();
+        let timestamp = Utc::now();
+        let result = non_settling_driver.notify(request).await;
+        if let Some(persistence) = persistence {
+            record_notification(&persistence, &non_settling_driver, kind, timestamp, &result).await;
+        }
     });
 }
+
+/// Persists that the driver's solver was sent a notification at `timestamp`
+/// and whether it got delivered so solvers can't claim they were never
+/// informed. Failing to persist it only gets logged.
+pub async fn record_notification(
+    persistence: &infra::Persistence,
+    driver: &Driver,
+    kind: notification::Kind,
+    timestamp: DateTime<Utc>,
+    result: &Result<(), HttpError>,
+) {
+    let result = result.as_ref().map_err(ToString::to_string).copied();
+    if let Err(err) = persistence
+        .store_notification(driver.submission_address, kind, result, timestamp)
+        .await
+    {
+        tracing::warn!(?err, driver = %driver.name, ?kind, "failed to store solver notification");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, primitive_types::H160};
+
+    #[tokio::test]
+    async fn accepts_submission_addresses_within_their_window() {
+        let time = |timestamp| {
+            DateTime::parse_from_rfc3339(timestamp)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let address = |id| eth::Address(H160::from_low_u64_be(id));
+        // the key gets rotated from `old` to `new` with both being valid on
+        // the 2nd of January
+        let old: SubmissionAddress = "0x0000000000000000000000000000000000000002@..2025-01-03T00:\
+                                      00:00Z"
+            .parse()
+            .unwrap();
+        let new: SubmissionAddress = "0x0000000000000000000000000000000000000003@2025-01-02T00:00:\
+                                      00Z.."
+            .parse()
+            .unwrap();
+        let mut driver = fake::offline_driver("driver", address(1).0).await;
+        driver.additional_submission_addresses = vec![old, new];
+
+        let before = time("2025-01-01T00:00:00Z");
+        let overlap = time("2025-01-02T12:00:00Z");
+        let after = time("2025-01-03T00:00:00Z");
+
+        assert!(driver.allows_submission_address(address(2), before));
+        assert!(!driver.allows_submission_address(address(3), before));
+        assert!(driver.allows_submission_address(address(2), overlap));
+        assert!(driver.allows_submission_address(address(3), overlap));
+        assert!(!driver.allows_submission_address(address(2), after));
+        assert!(driver.allows_submission_address(address(3), after));
+        // the main submission address is always allowed
+        assert!(driver.allows_submission_address(address(1), after));
+        assert!(!driver.allows_submission_address(address(4), overlap));
+
+        assert_eq!(
+            driver.submission_addresses(overlap),
+            [address(1), address(2), address(3)]
+        );
+        assert_eq!(driver.submission_addresses(after), [address(1), address(3)]);
+    }
+}
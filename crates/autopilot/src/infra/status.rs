@@ -0,0 +1,98 @@
+//! Snapshots of the run loop's state served at `/status/{name}` next to the
+//! metrics.
+
+use {
+    crate::domain::{self, competition::participation::ParticipationMatrix},
+    serde::Serialize,
+    std::sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+/// Driver participation in the most recent auction.
+#[derive(Debug, Default)]
+pub struct LatestParticipation(Mutex<Option<(domain::auction::Id, ParticipationMatrix)>>);
+
+impl LatestParticipation {
+    pub fn update(&self, auction_id: domain::auction::Id, matrix: ParticipationMatrix) {
+        *self.0.lock().unwrap() = Some((auction_id, matrix));
+    }
+}
+
+impl observe::metrics::StatusReporting for LatestParticipation {
+    fn status(&self) -> serde_json::Value {
+        let lock = self.0.lock().unwrap();
+        let Some((auction_id, matrix)) = lock.as_ref() else {
+            return serde_json::Value::Null;
+        };
+        let status = Status {
+            auction_id: *auction_id,
+            drivers: matrix
+                .0
+                .iter()
+                .map(|participation| Driver {
+                    name: &participation.driver,
+                    submission_address: participation.submission_address.0,
+                    outcome: participation.outcome.label(),
+                    proposed_solutions: participation.outcome.proposed(),
+                    accepted_solutions: participation.accepted,
+                })
+                .collect(),
+        };
+        serde_json::to_value(status).expect("participation is serializable")
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Status<'a> {
+    auction_id: domain::auction::Id,
+    drivers: Vec<Driver<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver<'a> {
+    name: &'a str,
+    submission_address: primitive_types::H160,
+    outcome: &'static str,
+    proposed_solutions: usize,
+    accepted_solutions: usize,
+}
+
+/// Health of the run loop beyond what the liveness probe reports.
+#[derive(Debug, Default)]
+pub struct RunLoopDiagnostics {
+    /// Consecutive auctions in which no driver was allowed to participate and
+    /// answered in time.
+    pub auctions_without_drivers: AtomicUsize,
+    /// Auction whose solutions got collected but whose competition data
+    /// wasn't stored (yet).
+    pub competition_in_progress: Mutex<Option<domain::auction::Id>>,
+    /// Most recent reorg noticed while building an auction.
+    pub last_reorg: Mutex<Option<Reorg>>,
+}
+
+/// A reorg noticed by comparing the block an auction got built on with the
+/// block of the previous auction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reorg {
+    /// Block the chain reorged to.
+    pub block: u64,
+    /// Previously observed block that is no longer part of the chain.
+    pub orphaned: primitive_types::H256,
+    /// How many blocks got replaced, if it can be determined.
+    pub depth: Option<u64>,
+}
+
+impl observe::metrics::StatusReporting for RunLoopDiagnostics {
+    fn status(&self) -> serde_json::Value {
+        serde_json::json!({
+            "auctionsWithoutDrivers": self.auctions_without_drivers.load(Ordering::Relaxed),
+            "competitionInProgress": *self.competition_in_progress.lock().unwrap(),
+            "lastReorg": *self.last_reorg.lock().unwrap(),
+        })
+    }
+}
@@ -1,3 +1,4 @@
+pub mod admin;
 pub mod arguments;
 pub mod boundary;
 pub mod database;
@@ -10,32 +10,194 @@ use {
             },
         },
         event_updater::EventUpdater,
+        infra::{self, persistence::RetainedData},
     },
     anyhow::Result,
     ethrpc::block_stream::{BlockInfo, CurrentBlockWatcher, into_stream},
     futures::StreamExt,
     prometheus::{
         HistogramVec,
+        IntCounter,
         IntCounterVec,
+        IntGauge,
+        IntGaugeVec,
         core::{AtomicU64, GenericGauge},
     },
     shared::maintenance::Maintaining,
-    std::{future::Future, sync::Arc},
+    std::{
+        collections::HashSet,
+        sync::{Arc, RwLock},
+        time::Duration,
+    },
     tokio::sync::Mutex,
 };
 
+/// A named unit of work that has to run on every new block.
+#[async_trait::async_trait]
+pub trait MaintenanceTask: Send + Sync {
+    /// Name used to toggle the task and to label its metrics.
+    fn name(&self) -> &str;
+
+    async fn update(&self, block: &BlockInfo) -> Result<()>;
+}
+
+/// Adapter to run components implementing [`Maintaining`] as a
+/// [`MaintenanceTask`] with a given name.
+struct Named {
+    name: &'static str,
+    inner: Vec<Arc<dyn Maintaining>>,
+}
+
+#[async_trait::async_trait]
+impl MaintenanceTask for Named {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    async fn update(&self, _: &BlockInfo) -> Result<()> {
+        futures::future::try_join_all(self.inner.iter().map(|task| task.run_maintenance())).await?;
+        Ok(())
+    }
+}
+
+/// Only settlement executions that started within this window count towards
+/// the orphan gauge so it recovers once crashed settlement tasks stop
+/// happening.
+const ORPHAN_WINDOW: chrono::Duration = chrono::Duration::hours(24);
+
+/// Tracks settlement executions that never ended although their deadline
+/// passed, which indicates crashed settlement tasks.
+struct OrphanedSettlementExecutions {
+    persistence: infra::Persistence,
+}
+
+#[async_trait::async_trait]
+impl MaintenanceTask for OrphanedSettlementExecutions {
+    fn name(&self) -> &str {
+        "orphaned_settlement_executions"
+    }
+
+    async fn update(&self, block: &BlockInfo) -> Result<()> {
+        let orphans = self
+            .persistence
+            .count_orphaned_settlement_executions(block.number, chrono::Utc::now() - ORPHAN_WINDOW)
+            .await?;
+        metrics()
+            .orphaned_settlement_executions
+            .set(orphans.try_into().unwrap_or(i64::MAX));
+        Ok(())
+    }
+}
+
+/// Upper bound for counting the rows that are left to prune to keep the
+/// backlog estimate cheap.
+const RETENTION_BACKLOG_LIMIT: u64 = 1_000_000;
+
+/// How long the data of each dataset gets kept and how much of it may be
+/// deleted per block.
+#[derive(Debug, Clone)]
+pub struct RetentionConfig {
+    /// Datasets without a retention period are kept forever.
+    pub periods: Vec<(RetainedData, Duration)>,
+    /// Maximum number of rows deleted by a single query.
+    pub batch_size: u64,
+    /// Maximum number of rows deleted per block over all datasets.
+    pub rows_per_block: u64,
+}
+
+impl RetentionConfig {
+    /// Settlement executions determine the age of auctions so they have to be
+    /// kept at least as long as any auction based data. They also get pruned
+    /// last so auction based data never loses its reference point mid block.
+    fn normalized(mut self) -> Self {
+        let longest_auction_retention = self
+            .periods
+            .iter()
+            .filter(|(data, _)| data.is_auction_based())
+            .map(|(_, period)| *period)
+            .max();
+        if let Some(longest) = longest_auction_retention {
+            for (data, period) in &mut self.periods {
+                if *data == RetainedData::SettlementExecutions && *period < longest {
+                    tracing::warn!(
+                        configured = ?period,
+                        extended = ?longest,
+                        "extending settlement executions retention to cover auction based data"
+                    );
+                    *period = longest;
+                }
+            }
+        }
+        self.periods
+            .sort_by_key(|(data, _)| *data == RetainedData::SettlementExecutions);
+        self
+    }
+}
+
+/// Deletes data that is older than its retention period. Deletes in small
+/// batches and at most [`RetentionConfig::rows_per_block`] rows per block so
+/// pruning old data never competes with the auction path.
+struct DataRetention {
+    persistence: infra::Persistence,
+    config: RetentionConfig,
+}
+
+#[async_trait::async_trait]
+impl MaintenanceTask for DataRetention {
+    fn name(&self) -> &str {
+        "data_retention"
+    }
+
+    async fn update(&self, _: &BlockInfo) -> Result<()> {
+        let mut budget = self.config.rows_per_block;
+        for (data, period) in &self.config.periods {
+            let label: &str = (*data).into();
+            let cutoff = chrono::Utc::now() - chrono::Duration::from_std(*period)?;
+
+            let mut drained = false;
+            while budget > 0 {
+                let limit = budget.min(self.config.batch_size);
+                let deleted = self.persistence.prune(*data, cutoff, limit).await?;
+                metrics()
+                    .retention_rows_pruned
+                    .with_label_values(&[label])
+                    .inc_by(deleted);
+                budget = budget.saturating_sub(deleted);
+                if deleted < limit {
+                    drained = true;
+                    break;
+                }
+            }
+
+            let backlog = if drained {
+                0
+            } else {
+                self.persistence
+                    .count_prunable(*data, cutoff, RETENTION_BACKLOG_LIMIT)
+                    .await?
+            };
+            metrics()
+                .retention_backlog
+                .with_label_values(&[label])
+                .set(backlog.try_into().unwrap_or(i64::MAX));
+        }
+        Ok(())
+    }
+}
+
 /// Coordinates all the updates that need to run a new block
 /// to ensure a consistent view of the system.
 pub struct Maintenance {
-    /// Indexes and persists all events emited by the settlement contract.
-    settlement_indexer: EventUpdater<Indexer, GPv2SettlementContract>,
-    /// Indexes ethflow orders (orders selling native ETH).
-    ethflow_indexer: Option<EthflowIndexer>,
-    /// Used for periodic cleanup tasks to not have the DB overflow with old
-    /// data.
-    db_cleanup: Postgres,
-    /// All indexing tasks to keep cow amms up to date.
-    cow_amm_indexer: Vec<Arc<dyn Maintaining>>,
+    /// Tasks that have to be completed before the system can work with a
+    /// new block. E.g. indexing and persisting all events emitted by the
+    /// settlement contract, indexing ethflow orders and periodically cleaning
+    /// up the DB to not have it overflow with old data.
+    tasks: Vec<Arc<dyn MaintenanceTask>>,
+    /// Tasks that run in a background task on every new block and are not
+    /// awaited by [`Maintenance::update`] (e.g. keeping cow amms up to date).
+    background_tasks: Vec<Arc<dyn MaintenanceTask>>,
+    /// Names of tasks that should currently be skipped.
+    disabled: RwLock<HashSet<String>>,
     /// On which block we last ran an update successfully.
     last_processed: Mutex<BlockInfo>,
 }
@@ -46,29 +208,43 @@ impl Maintenance {
         db_cleanup: Postgres,
     ) -> Self {
         Self {
-            settlement_indexer,
-            db_cleanup,
-            cow_amm_indexer: Default::default(),
-            ethflow_indexer: None,
+            tasks: vec![
+                Arc::new(Named {
+                    name: "settlement_indexer",
+                    inner: vec![Arc::new(settlement_indexer)],
+                }),
+                Arc::new(Named {
+                    name: "db_cleanup",
+                    inner: vec![Arc::new(db_cleanup)],
+                }),
+            ],
+            background_tasks: Default::default(),
+            disabled: Default::default(),
             last_processed: Default::default(),
         }
     }
 
     /// Runs all update tasks in a coordinated manner to ensure the system
-    /// has a consistent state.
-    pub async fn update(&self, new_block: &BlockInfo) {
+    /// has a consistent state. Returns quickly without doing anything if the
+    /// block was already processed (e.g. by a concurrent caller that
+    /// observed the same block). Returns whether the tasks ran.
+    pub async fn update(&self, new_block: &BlockInfo) -> bool {
         let mut last_block = self.last_processed.lock().await;
         metrics().last_seen_block.set(new_block.number);
-        if last_block.number > new_block.number || last_block.hash == new_block.hash {
+        if last_block.hash == new_block.hash {
+            metrics().maintenance_skipped_duplicate.inc();
+            return false;
+        }
+        if last_block.number > new_block.number {
             // `new_block` is neither newer than `last_block` nor a reorg
-            return;
+            return false;
         }
 
         let start = std::time::Instant::now();
-        if let Err(err) = self.update_inner().await {
-            tracing::warn!(?err, block = new_block.number, "failed to run maintenance");
+        if !self.run_tasks(&self.tasks, new_block).await {
+            tracing::warn!(block = new_block.number, "failed to run maintenance");
             metrics().updates.with_label_values(&["error"]).inc();
-            return;
+            return true;
         }
         tracing::info!(
             block = new_block.number,
@@ -79,80 +255,171 @@ impl Maintenance {
         metrics().updates.with_label_values(&["success"]).inc();
         metrics().last_updated_block.set(new_block.number);
         *last_block = *new_block;
+        true
     }
 
-    async fn update_inner(&self) -> Result<()> {
-        // All these can run independently of each other.
-        tokio::try_join!(
-            Self::timed_future(
-                "settlement_indexer",
-                self.settlement_indexer.run_maintenance()
-            ),
-            Self::timed_future("db_cleanup", self.db_cleanup.run_maintenance()),
-            Self::timed_future("ethflow_indexer", self.index_ethflow_orders()),
-        )?;
+    /// Like [`Maintenance::update`] but also runs the tasks if `new_block`
+    /// isn't newer than the last processed block, e.g. because a reorg
+    /// replaced the processed blocks with fewer new ones.
+    pub async fn force_update(&self, new_block: &BlockInfo) -> bool {
+        *self.last_processed.lock().await = BlockInfo::default();
+        self.update(new_block).await
+    }
 
-        Ok(())
+    /// Runs all enabled tasks concurrently. A failing task doesn't prevent
+    /// the other tasks from running. Returns whether all tasks succeeded.
+    async fn run_tasks(&self, tasks: &[Arc<dyn MaintenanceTask>], block: &BlockInfo) -> bool {
+        let results = futures::future::join_all(
+            tasks
+                .iter()
+                .filter(|task| !self.is_disabled(task.name()))
+                .map(|task| Self::run_task(task.as_ref(), block)),
+        )
+        .await;
+        results.into_iter().all(|success| success)
+    }
+
+    /// Runs a single task and collects runtime metrics.
+    async fn run_task(task: &dyn MaintenanceTask, block: &BlockInfo) -> bool {
+        let name = task.name();
+        let _timer = metrics()
+            .maintenance_stage_time
+            .with_label_values(&[name])
+            .start_timer();
+        match task.update(block).await {
+            Ok(()) => true,
+            Err(err) => {
+                tracing::warn!(
+                    ?err,
+                    task = name,
+                    block = block.number,
+                    "maintenance task failed"
+                );
+                metrics().task_failures.with_label_values(&[name]).inc();
+                false
+            }
+        }
     }
 
     /// Registers all maintenance tasks that are necessary to correctly support
     /// ethflow orders.
     pub fn with_ethflow(&mut self, ethflow_indexer: EthflowIndexer) {
-        self.ethflow_indexer = Some(ethflow_indexer);
+        self.tasks.push(Arc::new(Named {
+            name: "ethflow_indexer",
+            inner: vec![Arc::new(ethflow_indexer)],
+        }));
     }
 
     pub fn with_cow_amms(&mut self, registry: &cow_amm::Registry) {
-        self.cow_amm_indexer = registry.maintenance_tasks().clone();
+        // TODO: move this into the regular tasks once we store cow amms in
+        // the DB to avoid incredibly slow restarts.
+        self.background_tasks.push(Arc::new(Named {
+            name: "cow_amm_indexer",
+            inner: registry.maintenance_tasks().clone(),
+        }));
+    }
+
+    /// Keeps track of settlement executions that were orphaned. Only runs in
+    /// the background since it isn't needed for a consistent state.
+    pub fn with_orphan_detection(&mut self, persistence: infra::Persistence) {
+        self.background_tasks
+            .push(Arc::new(OrphanedSettlementExecutions { persistence }));
+    }
+
+    /// Prunes data older than the configured retention periods. Only runs in
+    /// the background since it isn't needed for a consistent state.
+    pub fn with_data_retention(
+        &mut self,
+        persistence: infra::Persistence,
+        config: RetentionConfig,
+    ) {
+        if config.periods.is_empty() {
+            return;
+        }
+        self.background_tasks.push(Arc::new(DataRetention {
+            persistence,
+            config: config.normalized(),
+        }));
     }
 
-    async fn index_ethflow_orders(&self) -> Result<()> {
-        if let Some(indexer) = &self.ethflow_indexer {
-            return indexer.run_maintenance().await;
+    /// Skips the tasks with the given names until they get enabled again.
+    /// Nothing changes if any of the names is unknown.
+    pub fn disable<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), UnknownTasks> {
+        let names = self.known(names)?;
+        let mut disabled = self.disabled.write().unwrap();
+        for name in names {
+            tracing::info!(task = name, "disabling maintenance task");
+            disabled.insert(name.to_owned());
         }
         Ok(())
     }
 
-    /// Runs the future and collects runtime metrics.
-    async fn timed_future<T>(label: &str, fut: impl Future<Output = T>) -> T {
-        let _timer = metrics()
-            .maintenance_stage_time
-            .with_label_values(&[label])
-            .start_timer();
-        fut.await
+    /// Resumes running the tasks with the given names. Nothing changes if any
+    /// of the names is unknown.
+    pub fn enable<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> Result<(), UnknownTasks> {
+        let names = self.known(names)?;
+        let mut disabled = self.disabled.write().unwrap();
+        for name in names {
+            tracing::info!(task = name, "enabling maintenance task");
+            disabled.remove(name);
+        }
+        Ok(())
+    }
+
+    /// Returns the names if all of them belong to registered tasks.
+    fn known<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Vec<&'a str>, UnknownTasks> {
+        let names: Vec<_> = names.into_iter().collect();
+        let unknown: Vec<_> = names
+            .iter()
+            .filter(|name| {
+                !self
+                    .tasks
+                    .iter()
+                    .chain(&self.background_tasks)
+                    .any(|task| task.name() == **name)
+            })
+            .map(|name| name.to_string())
+            .collect();
+        match unknown.is_empty() {
+            true => Ok(names),
+            false => Err(UnknownTasks(unknown)),
+        }
+    }
+
+    fn is_disabled(&self, name: &str) -> bool {
+        self.disabled.read().unwrap().contains(name)
     }
 
-    /// Spawns a background task that runs on every new block but also
-    /// at least after every `update_interval`.
-    pub fn spawn_cow_amm_indexing_task(self_: Arc<Self>, current_block: CurrentBlockWatcher) {
+    /// Spawns a background task that runs all background maintenance tasks
+    /// on every new block.
+    pub fn spawn_background_tasks(self_: Arc<Self>, current_block: CurrentBlockWatcher) {
         tokio::task::spawn(async move {
             let mut stream = into_stream(current_block);
             loop {
-                let _ = match stream.next().await {
+                let block = match stream.next().await {
                     Some(block) => {
                         metrics().last_seen_block.set(block.number);
                         block
                     }
                     None => panic!("block stream terminated unexpectedly"),
                 };
-
-                // TODO: move this back into `Self::update_inner()` once we
-                // store cow amms in the DB to avoid incredibly slow restarts.
-                let _ = Self::timed_future(
-                    "cow_amm_indexer",
-                    futures::future::try_join_all(
-                        self_
-                            .cow_amm_indexer
-                            .iter()
-                            .cloned()
-                            .map(|indexer| async move { indexer.run_maintenance().await }),
-                    ),
-                )
-                .await;
+                self_.run_tasks(&self_.background_tasks, &block).await;
             }
         });
     }
 }
 
+/// Names that don't belong to any registered maintenance task.
+#[derive(Debug, thiserror::Error)]
+#[error("unknown maintenance tasks: {0:?}")]
+pub struct UnknownTasks(pub Vec<String>);
+
 type EthflowIndexer =
     EventUpdater<OnchainOrderParser<EthFlowData, EthFlowDataForDb>, CoWSwapOnchainOrdersContract>;
 
@@ -175,8 +442,146 @@ struct Metrics {
         buckets(0.01, 0.05, 0.1, 0.25, 0.5, 0.75, 1, 1.5, 2.0, 2.5, 3, 3.5, 4)
     )]
     maintenance_stage_time: HistogramVec,
+
+    /// Autopilot maintenance task failure counter
+    #[metric(labels("task"))]
+    task_failures: IntCounterVec,
+
+    /// Number of maintenance updates that were skipped because the block was
+    /// already processed.
+    maintenance_skipped_duplicate: IntCounter,
+
+    /// Number of settlement executions that never ended although their
+    /// deadline passed.
+    orphaned_settlement_executions: IntGauge,
+
+    /// Number of rows deleted because they exceeded their retention period.
+    #[metric(labels("dataset"))]
+    retention_rows_pruned: IntCounterVec,
+
+    /// Estimated number of rows that exceeded their retention period but
+    /// haven't been deleted yet.
+    #[metric(labels("dataset"))]
+    retention_backlog: IntGaugeVec,
 }
 
 fn metrics() -> &'static Metrics {
     Metrics::instance(observe::metrics::get_storage_registry()).unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, primitive_types::H256, std::sync::atomic::AtomicUsize};
+
+    #[derive(Default)]
+    struct Counter(AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl MaintenanceTask for Counter {
+        fn name(&self) -> &str {
+            "counter"
+        }
+
+        async fn update(&self, _: &BlockInfo) -> Result<()> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn settlement_executions_outlive_auction_based_data() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        let config = RetentionConfig {
+            periods: vec![
+                (RetainedData::SettlementExecutions, day),
+                (RetainedData::SolverCompetitions, 30 * day),
+                (RetainedData::AuctionPrices, 7 * day),
+            ],
+            batch_size: 1,
+            rows_per_block: 1,
+        }
+        .normalized();
+
+        assert_eq!(
+            config.periods,
+            vec![
+                (RetainedData::SolverCompetitions, 30 * day),
+                (RetainedData::AuctionPrices, 7 * day),
+                (RetainedData::SettlementExecutions, 30 * day),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn skips_already_processed_blocks() {
+        let counter = Arc::new(Counter::default());
+        let maintenance = Maintenance {
+            tasks: vec![counter.clone()],
+            background_tasks: Default::default(),
+            disabled: Default::default(),
+            last_processed: Default::default(),
+        };
+        let block = |number: u64, hash: u8| BlockInfo {
+            number,
+            hash: H256([hash; 32]),
+            ..Default::default()
+        };
+        let runs = || counter.0.load(std::sync::atomic::Ordering::SeqCst);
+
+        assert!(maintenance.update(&block(1, 1)).await);
+        assert_eq!(runs(), 1);
+
+        // e.g. concurrent waiters observing the same block
+        assert!(!maintenance.update(&block(1, 1)).await);
+        assert_eq!(runs(), 1);
+
+        // reorgs and new blocks get processed
+        assert!(maintenance.update(&block(1, 2)).await);
+        assert!(maintenance.update(&block(2, 3)).await);
+        assert_eq!(runs(), 3);
+
+        // older blocks get ignored
+        assert!(!maintenance.update(&block(1, 4)).await);
+        assert_eq!(runs(), 3);
+
+        // unless the update is forced
+        assert!(maintenance.force_update(&block(1, 4)).await);
+        assert_eq!(runs(), 4);
+        assert!(!maintenance.update(&block(1, 4)).await);
+        assert_eq!(runs(), 4);
+    }
+
+    #[tokio::test]
+    async fn toggles_known_tasks_only() {
+        let counter = Arc::new(Counter::default());
+        let maintenance = Maintenance {
+            tasks: vec![counter.clone()],
+            background_tasks: Default::default(),
+            disabled: Default::default(),
+            last_processed: Default::default(),
+        };
+        let block = |number: u64| BlockInfo {
+            number,
+            ..Default::default()
+        };
+        let runs = || counter.0.load(std::sync::atomic::Ordering::SeqCst);
+
+        maintenance.disable(["counter"]).unwrap();
+        assert!(maintenance.update(&block(1)).await);
+        assert_eq!(runs(), 0);
+
+        // a typo doesn't enable anything
+        let err = maintenance.enable(["counter", "countr"]).unwrap_err();
+        assert_eq!(err.0, ["countr"]);
+        assert!(maintenance.update(&block(2)).await);
+        assert_eq!(runs(), 0);
+
+        maintenance.enable(["counter"]).unwrap();
+        assert!(maintenance.update(&block(3)).await);
+        assert_eq!(runs(), 1);
+
+        assert!(maintenance.disable(["unknown"]).is_err());
+        assert!(maintenance.update(&block(4)).await);
+        assert_eq!(runs(), 2);
+    }
+}
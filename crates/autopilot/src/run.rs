@@ -15,7 +15,10 @@ use {
                 event_retriever::CoWSwapOnchainOrdersContract,
             },
         },
-        domain::{self, competition::SolverParticipationGuard},
+        domain::{
+            self,
+            competition::{self, SolverParticipationGuard},
+        },
         event_updater::EventUpdater,
         infra,
         maintenance::Maintenance,
@@ -59,7 +62,12 @@ use {
         token_list::{AutoUpdatingTokenList, TokenListConfiguration},
     },
     std::{
-        sync::{Arc, RwLock},
+        collections::HashMap,
+        sync::{
+            Arc,
+            RwLock,
+            atomic::{AtomicBool, Ordering},
+        },
         time::{Duration, Instant},
     },
     tracing::{Instrument, info_span, instrument},
@@ -69,15 +77,34 @@ use {
 pub struct Liveness {
     max_auction_age: Duration,
     last_auction_time: RwLock<Instant>,
+    /// Set while no driver could participate in too many consecutive
+    /// auctions.
+    drivers_unavailable: AtomicBool,
+    /// Set while the solvable orders cache wasn't populated for too long
+    /// after startup.
+    auction_cache_unpopulated: AtomicBool,
+    /// Result of the most recent probe of the Ethereum node.
+    node_status: RwLock<infra::blockchain::NodeStatus>,
 }
 
 #[async_trait::async_trait]
 impl LivenessChecking for Liveness {
     async fn is_alive(&self) -> bool {
+        if self.drivers_unavailable.load(Ordering::Relaxed)
+            || self.auction_cache_unpopulated.load(Ordering::Relaxed)
+        {
+            return false;
+        }
         let last_auction_time = self.last_auction_time.read().unwrap();
         let auction_age = last_auction_time.elapsed();
         auction_age <= self.max_auction_age
     }
+
+    async fn status(&self) -> Option<String> {
+        // an unhealthy node explains why no new auctions get cut
+        let node_status = self.node_status();
+        (!node_status.is_healthy()).then(|| node_status.as_str().to_string())
+    }
 }
 
 impl Liveness {
@@ -85,12 +112,55 @@ impl Liveness {
         Liveness {
             max_auction_age,
             last_auction_time: RwLock::new(Instant::now()),
+            drivers_unavailable: AtomicBool::new(false),
+            auction_cache_unpopulated: AtomicBool::new(false),
+            node_status: Default::default(),
         }
     }
 
     pub fn auction(&self) {
         *self.last_auction_time.write().unwrap() = Instant::now();
     }
+
+    pub fn drivers_unavailable(&self, unavailable: bool) {
+        self.drivers_unavailable
+            .store(unavailable, Ordering::Relaxed);
+    }
+
+    pub fn auction_cache_unpopulated(&self, unpopulated: bool) {
+        self.auction_cache_unpopulated
+            .store(unpopulated, Ordering::Relaxed);
+    }
+
+    pub fn set_node_status(&self, status: infra::blockchain::NodeStatus) {
+        *self.node_status.write().unwrap() = status;
+    }
+
+    /// Status of the Ethereum node as of the most recent probe.
+    pub fn node_status(&self) -> infra::blockchain::NodeStatus {
+        *self.node_status.read().unwrap()
+    }
+
+    /// Probes the Ethereum node every `interval` in a background task and
+    /// records its status. Probes taking longer than `interval` count as
+    /// unreachable.
+    pub fn spawn_node_probe(
+        self: &Arc<Self>,
+        eth: infra::Ethereum,
+        interval: Duration,
+        max_block_age: Duration,
+    ) {
+        let liveness = self.clone();
+        tokio::task::spawn(
+            async move {
+                loop {
+                    liveness.set_node_status(eth.probe_node(max_block_age, interval).await);
+                    tokio::time::sleep(interval).await;
+                }
+            }
+            .instrument(info_span!("node_probe")),
+        );
+    }
 }
 
 /// Creates Web3 transport based on the given config.
@@ -267,15 +337,18 @@ pub async fn run(args: Arguments) {
         eth.current_block().clone(),
     );
 
-    let gas_price_estimator = Arc::new(
-        shared::gas_price_estimation::create_priority_estimator(
-            &http_factory,
-            &web3,
-            args.shared.gas_estimators.as_slice(),
-        )
-        .await
-        .expect("failed to create gas price estimator"),
-    );
+    let gas_price_estimator = Arc::new(infra::blockchain::GasPriceEstimator::new(
+        eth.clone(),
+        Arc::new(
+            shared::gas_price_estimation::create_priority_estimator(
+                &http_factory,
+                &web3,
+                args.shared.gas_estimators.as_slice(),
+            )
+            .await
+            .expect("failed to create gas price estimator"),
+        ),
+    ));
 
     let baseline_sources = args
         .shared
@@ -425,12 +498,18 @@ pub async fn run(args: Arguments) {
     let (competition_updates_sender, competition_updates_receiver) =
         tokio::sync::mpsc::unbounded_channel();
 
-    let persistence =
-        infra::persistence::Persistence::new(args.s3.into().unwrap(), Arc::new(db.clone()))
-            .instrument(info_span!("persistence_init"))
-            .await;
-    let settlement_observer =
-        crate::domain::settlement::Observer::new(eth.clone(), persistence.clone());
+    let persistence = infra::persistence::Persistence::new(
+        args.s3.into().unwrap(),
+        Arc::new(db.clone()),
+        args.db_slow_write_threshold,
+    )
+    .instrument(info_span!("persistence_init"))
+    .await;
+    let settlement_observer = crate::domain::settlement::Observer::new(
+        eth.clone(),
+        persistence.clone(),
+        args.min_price_deviation_volume.into(),
+    );
     let settlement_contract_start_index = match contracts::GPv2Settlement::raw_contract()
         .networks
         .get(&chain_id.to_string())
@@ -516,10 +595,36 @@ pub async fn run(args: Arguments) {
         cow_amm_registry.clone(),
         args.run_loop_native_price_timeout,
         eth.contracts().settlement().address(),
+        args.fairness_exempt_owners.into_iter().collect(),
     );
 
     let liveness = Arc::new(Liveness::new(args.max_auction_age));
-    observe::metrics::serve_metrics(liveness.clone(), args.metrics_address);
+    liveness.spawn_node_probe(
+        eth.clone(),
+        args.node_health_interval,
+        args.max_node_block_age,
+    );
+    let participation = Arc::new(infra::status::LatestParticipation::default());
+    let diagnostics = Arc::new(infra::status::RunLoopDiagnostics::default());
+    let settlement_executor = Arc::new(run_loop::SettlementExecutor::default());
+    observe::metrics::serve_metrics_with_status(
+        liveness.clone(),
+        HashMap::from([
+            (
+                "participation",
+                participation.clone() as Arc<dyn observe::metrics::StatusReporting>,
+            ),
+            (
+                "runloop",
+                diagnostics.clone() as Arc<dyn observe::metrics::StatusReporting>,
+            ),
+            (
+                "settlements",
+                settlement_executor.clone() as Arc<dyn observe::metrics::StatusReporting>,
+            ),
+        ]),
+        args.metrics_address,
+    );
 
     let order_events_cleaner_config = crate::periodic_db_cleanup::OrderEventsCleanerConfig::new(
         args.order_events_cleanup_interval,
@@ -549,6 +654,31 @@ pub async fn run(args: Arguments) {
 
     let mut maintenance = Maintenance::new(settlement_event_indexer, db.clone());
     maintenance.with_cow_amms(&cow_amm_registry);
+    maintenance.with_orphan_detection(persistence.clone());
+    maintenance.with_data_retention(
+        persistence.clone(),
+        crate::maintenance::RetentionConfig {
+            periods: [
+                (
+                    infra::persistence::RetainedData::SolverCompetitions,
+                    args.solver_competition_retention,
+                ),
+                (
+                    infra::persistence::RetainedData::AuctionPrices,
+                    args.auction_prices_retention,
+                ),
+                (
+                    infra::persistence::RetainedData::SettlementExecutions,
+                    args.settlement_executions_retention,
+                ),
+            ]
+            .into_iter()
+            .filter_map(|(data, period)| Some((data, period?)))
+            .collect(),
+            batch_size: args.retention_batch_size,
+            rows_per_block: args.retention_rows_per_block,
+        },
+    );
 
     if !args.ethflow_contracts.is_empty() {
         let ethflow_refund_start_block = determine_ethflow_refund_indexing_start(
@@ -612,27 +742,91 @@ pub async fn run(args: Arguments) {
             service_maintainer.run_maintenance_on_new_block(eth.current_block().clone()),
         );
     }
+    // only now all maintenance tasks are registered
+    maintenance
+        .disable(args.disabled_maintenance_tasks.iter().map(String::as_str))
+        .expect("invalid disabled maintenance tasks");
 
     let run_loop_config = run_loop::Config {
         submission_deadline: args.submission_deadline as u64,
         max_settlement_transaction_wait: args.max_settlement_transaction_wait,
         solve_deadline: args.solve_deadline,
+        max_solve_deadline_padding: args.max_solve_deadline_padding,
         max_run_loop_delay: args.max_run_loop_delay,
+        post_processing_timeout: args.post_processing_timeout,
         combinatorial_auctions_cutover: args.combinatorial_auctions_cutover,
         max_winners_per_auction: args.max_winners_per_auction,
+        max_winners_per_submission_address: args.max_winners_per_submission_address,
+        conflict_deferral_auctions: args.conflict_deferral_auctions,
+        allowed_submission_addresses: args
+            .allowed_submission_addresses
+            .map(|addresses| addresses.into_iter().map(Into::into).collect()),
+        treat_native_as_weth: args.treat_native_as_weth,
         max_solutions_per_solver: args.max_solutions_per_solver,
+        max_solutions_per_response: args.max_solutions_per_response,
+        reject_duplicate_solution_ids: args.reject_duplicate_solution_ids,
+        persist_competitions_without_winners: args.persist_competitions_without_winners,
+        tracked_token_pairs: args
+            .tracked_token_pairs
+            .into_iter()
+            .map(|pair| (pair.sell.into(), pair.buy.into()))
+            .collect(),
+        settle_rebroadcast: args.settle_rebroadcast_after_blocks.map(|after_blocks| {
+            run_loop::SettleRebroadcast {
+                after_blocks,
+                max_remaining_blocks: args.settle_rebroadcast_max_remaining_blocks,
+            }
+        }),
+        order_expiry_margin: args.order_expiry_margin,
+        min_order_validity_blocks: args.min_order_validity_blocks,
+        max_auction_reuse_blocks: args.max_auction_reuse_blocks,
+        replay_recent_auctions: args.replay_recent_auctions,
+        dust_execution_threshold: args.dust_execution_threshold.into(),
+        fallback_submitter: args.fallback_submitter,
+        winner_concentration_window: args.winner_concentration_window,
+        run_report_dir: args.run_report_dir,
+        quote_only_interval: args.quote_only_interval,
+        participation_update_interval: args.participation_update_interval,
+        max_auctions_without_drivers: args.max_auctions_without_drivers,
+        max_auction_cache_startup_delay: args.max_auction_cache_startup_delay,
+        settlement_confirmation_blocks: args.settlement_confirmation_blocks,
+        in_flight_reorg_protection_blocks: args.in_flight_reorg_protection_blocks,
+        gas_estimation_timeout: args.gas_estimation_timeout,
+        fallback_settlement_gas: domain::eth::Gas(args.fallback_settlement_gas.into()),
+        score_tie_epsilon: args.score_tie_epsilon,
+        fairness_threshold_floor: args.fairness_threshold_floor,
+        order_ordering: args.order_ordering.into(),
+        winner_objective: args.winner_objective.into(),
+        winner_merging: competition::winner_selection::Merging::new(
+            args.winner_merging,
+            args.max_extra_winners,
+        ),
+        max_protocol_fee_bps: args.max_protocol_fee_bps,
+        early_finish_fraction: args.early_finish_fraction,
+        early_finish_grace: args.early_finish_grace,
     };
 
     let drivers_futures = args
         .drivers
         .into_iter()
         .map(|driver| async move {
+            let fairness_threshold = driver.fairness_threshold_policy();
             infra::Driver::try_new(
                 driver.url,
                 driver.name.clone(),
-                driver.fairness_threshold.map(Into::into),
+                fairness_threshold,
                 driver.submission_account,
+                driver.additional_submission_addresses,
                 driver.requested_timeout_on_problems,
+                driver.max_orders,
+                driver
+                    .max_settlement_gas
+                    .map(|gas| domain::eth::Gas(gas.into())),
+                driver
+                    .token_allow_list
+                    .map(|tokens| tokens.into_iter().map(domain::eth::TokenAddress).collect()),
+                driver.observe_only,
+                driver.dto_version,
             )
             .await
             .map(Arc::new)
@@ -649,7 +843,10 @@ pub async fn run(args: Arguments) {
     let solver_participation_guard = SolverParticipationGuard::new(
         eth.clone(),
         persistence.clone(),
-        competition_updates_receiver,
+        competition::coalesce_updates(
+            competition_updates_receiver,
+            run_loop_config.participation_update_interval,
+        ),
         args.db_based_solver_participation_guard,
         drivers.iter().cloned(),
     );
@@ -665,9 +862,48 @@ pub async fn run(args: Arguments) {
         liveness.clone(),
         Arc::new(maintenance),
         competition_updates_sender,
-    );
+        participation,
+        diagnostics,
+        settlement_executor,
+    )
+    .expect("invalid run loop configuration");
+    let run = Arc::new(run);
+    if let Some(token) = args.admin_token {
+        crate::admin::serve(run.clone(), token, args.admin_address);
+    }
     drop(startup_span_guard);
-    run.run_forever().await;
+    let run_loop = tokio::task::spawn(run.clone().run_forever());
+    shutdown_signal().await;
+    // stop starting new settlements before cancelling the active ones
+    run_loop.abort();
+    run.shutdown().await;
+}
+
+#[cfg(unix)]
+async fn shutdown_signal() {
+    // Intercept main signals for graceful shutdown
+    // Kubernetes sends sigterm, whereas locally sigint (ctrl-c) is most common
+    let sigterm = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .unwrap()
+            .recv()
+            .await
+    };
+    let sigint = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt())
+            .unwrap()
+            .recv()
+            .await;
+    };
+    futures::pin_mut!(sigint);
+    futures::pin_mut!(sigterm);
+    futures::future::select(sigterm, sigint).await;
+}
+
+#[cfg(windows)]
+async fn shutdown_signal() {
+    // We don't support signal handling on windows
+    std::future::pending().await
 }
 
 async fn shadow_mode(args: Arguments) -> ! {
@@ -678,14 +914,25 @@ async fn shadow_mode(args: Arguments) -> ! {
         args.shadow.expect("missing shadow mode configuration"),
     );
 
+    // the configured addresses are the ones the drivers use in production
+    let production_addresses: HashMap<_, _> = args
+        .drivers
+        .iter()
+        .filter_map(|driver| match driver.submission_account {
+            Account::Address(address) => Some((driver.name.clone(), address)),
+            Account::Kms(_) => None,
+        })
+        .collect();
+
     let drivers_futures = args
         .drivers
         .into_iter()
         .map(|driver| async move {
+            let fairness_threshold = driver.fairness_threshold_policy();
             infra::Driver::try_new(
                 driver.url,
                 driver.name.clone(),
-                driver.fairness_threshold.map(Into::into),
+                fairness_threshold,
                 // HACK: the auction logic expects all drivers
                 // to use a different submission address. But
                 // in the shadow environment all drivers use
@@ -695,7 +942,17 @@ async fn shadow_mode(args: Arguments) -> ! {
                 // this address for anything important so we
                 // can simply generate random addresses here.
                 Account::Address(H160::random()),
+                Vec::new(),
                 driver.requested_timeout_on_problems,
+                driver.max_orders,
+                driver
+                    .max_settlement_gas
+                    .map(|gas| domain::eth::Gas(gas.into())),
+                driver
+                    .token_allow_list
+                    .map(|tokens| tokens.into_iter().map(domain::eth::TokenAddress).collect()),
+                driver.observe_only,
+                driver.dto_version,
             )
             .await
             .map(Arc::new)
@@ -752,6 +1009,17 @@ async fn shadow_mode(args: Arguments) -> ! {
     .await
     .expect("couldn't initialize current block stream");
 
+    let comparison = match args.shadow_candidate_driver {
+        Some(candidate) => Some(shadow::ProductionComparison {
+            candidate,
+            production_addresses,
+            db: Postgres::new(args.db_url.as_str(), args.insert_batch_size)
+                .await
+                .unwrap(),
+        }),
+        None => None,
+    };
+
     let shadow = shadow::RunLoop::new(
         orderbook,
         drivers,
@@ -761,6 +1029,7 @@ async fn shadow_mode(args: Arguments) -> ! {
         current_block,
         args.max_winners_per_auction,
         weth.address().into(),
+        comparison,
     );
     shadow.run_forever().await;
 }
@@ -1,4 +1,5 @@
 use {
+    self::settlement_executor::{ExecutionRequest, Handoff},
     crate::{
         database::competition::{Competition, LegacyScore},
         domain::{
@@ -11,63 +12,305 @@ use {
                 SolutionError,
                 SolverParticipationGuard,
                 Unranked,
+                deferred_orders::DeferredOrders,
+                gas::GasSource,
+                participation::{self, ParticipationMatrix},
+                reward_inputs,
+                winner_concentration::WinnerConcentration,
                 winner_selection::{self, Ranking},
             },
             eth::{self, TxId},
-            settlement::{ExecutionEnded, ExecutionStarted},
+            settlement::{
+                self,
+                ExecutionEnded,
+                ExecutionOutcomeKind,
+                ExecutionStarted,
+                OrderExecutionStatus,
+            },
         },
         infra::{
             self,
-            solvers::dto::{settle, solve},
+            http::HttpError,
+            persistence::{AuctionDataKind, ExpectedAuctionData, dto},
+            run_report::RunReport,
+            solvers::dto::{reveal, settle, solve},
         },
-        maintenance::Maintenance,
+        maintenance::{self, Maintenance},
         run::Liveness,
         solvable_orders::SolvableOrdersCache,
+        util::log_limiter::{LogLimiter, Occurrence},
     },
     ::observe::metrics,
     anyhow::{Context, Result},
     database::order_events::OrderEventLabel,
     ethrpc::block_stream::BlockInfo,
-    futures::FutureExt,
+    futures::{FutureExt, StreamExt, future::BoxFuture},
     itertools::Itertools,
-    model::solver_competition::{
-        CompetitionAuction,
-        Order,
-        Score,
-        SolverCompetitionDB,
-        SolverSettlement,
-    },
+    model::solver_competition::SolverCompetitionDB,
     num::ToPrimitive,
-    primitive_types::H256,
-    rand::seq::SliceRandom,
+    primitive_types::{H160, U256},
+    rand::{RngCore, SeedableRng, rngs::StdRng, seq::SliceRandom},
     shared::token_list::AutoUpdatingTokenList,
     std::{
         collections::{HashMap, HashSet},
-        num::NonZeroUsize,
-        sync::Arc,
+        num::{NonZeroU64, NonZeroUsize},
+        path::PathBuf,
+        sync::{
+            Arc,
+            LazyLock,
+            OnceLock,
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+        },
         time::{Duration, Instant},
     },
-    tokio::sync::Mutex,
+    tokio::{
+        sync::{Mutex, watch},
+        task::JoinHandle,
+    },
     tracing::{Instrument, instrument},
 };
 
+pub use self::settlement_executor::{ActiveExecution, SettlementExecutor};
+
+mod settlement_executor;
+
+/// For how long repeated identical failures only get logged at debug level.
+const LOG_LIMIT_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Failed `/solve` requests by driver and error kind.
+static SOLVE_ERRORS: LazyLock<LogLimiter<(String, &'static str)>> =
+    LazyLock::new(|| LogLimiter::new("solve", LOG_LIMIT_WINDOW));
+/// Failed `/settle` requests by driver and error kind.
+static SETTLE_ERRORS: LazyLock<LogLimiter<(String, &'static str)>> =
+    LazyLock::new(|| LogLimiter::new("settle", LOG_LIMIT_WINDOW));
+/// Failed post-processing steps.
+static POST_PROCESSING_ERRORS: LazyLock<LogLimiter<&'static str>> =
+    LazyLock::new(|| LogLimiter::new("post_processing", LOG_LIMIT_WINDOW));
+
+/// Creates the random number generator used to shuffle the solutions of an
+/// auction before ties between them get broken.
+pub type RngFactory = Arc<dyn Fn(domain::auction::Id) -> Box<dyn RngCore> + Send + Sync>;
+
+/// Thread local randomness that doesn't depend on the auction.
+pub fn thread_rng() -> RngFactory {
+    Arc::new(|_| Box::new(rand::thread_rng()))
+}
+
+/// Randomness seeded from the auction id which makes competitions
+/// reproducible.
+pub fn seeded_rng() -> RngFactory {
+    Arc::new(|auction| {
+        Box::new(StdRng::seed_from_u64(u64::from_be_bytes(
+            auction.to_be_bytes(),
+        )))
+    })
+}
+
 pub struct Config {
     pub submission_deadline: u64,
     pub max_settlement_transaction_wait: Duration,
     pub solve_deadline: Duration,
+    /// Upper bound for how much earlier than `solve_deadline` a driver gets
+    /// asked to respond to account for the network latency to it. The
+    /// autopilot still waits for the full `solve_deadline`.
+    pub max_solve_deadline_padding: Duration,
     /// How much time past observing the current block the runloop is
     /// allowed to start before it has to re-synchronize to the blockchain
     /// by waiting for the next block to appear.
     pub max_run_loop_delay: Duration,
+    /// Upper bound for storing the competition data of an auction. Guards the
+    /// run loop against hanging on an unresponsive database.
+    pub post_processing_timeout: Duration,
     pub combinatorial_auctions_cutover: Option<chrono::DateTime<chrono::Utc>>,
     pub max_winners_per_auction: NonZeroUsize,
+    /// How many winners of an auction may share the same submission address.
+    /// Several drivers can map to the same on-chain solver and settling their
+    /// solutions concurrently risks nonce contention.
+    pub max_winners_per_submission_address: Option<NonZeroUsize>,
+    /// For how many auctions the orders of ranked solutions that only lost
+    /// due to token conflicts with the winners get prioritized when auctions
+    /// get truncated or ordered. Such orders aren't treated specially if
+    /// `None`.
+    pub conflict_deferral_auctions: Option<NonZeroUsize>,
+    /// Submission addresses solutions may come from regardless of the driver
+    /// configuration. All addresses are allowed if not set.
+    pub allowed_submission_addresses: Option<HashSet<eth::Address>>,
+    /// Whether winner selection considers solutions trading the native token
+    /// to overlap with solutions trading its wrapped counterpart.
+    pub treat_native_as_weth: bool,
     pub max_solutions_per_solver: NonZeroUsize,
+    /// Responses of drivers containing more solutions get rejected without
+    /// processing any of them.
+    pub max_solutions_per_response: NonZeroUsize,
+    /// Whether responses containing several solutions with the same id get
+    /// rejected as a whole. Otherwise only the first solution with each id
+    /// is kept.
+    pub reject_duplicate_solution_ids: bool,
+    /// Whether the competition data of auctions that produced solutions but
+    /// no winners still gets stored for auditing.
+    pub persist_competitions_without_winners: bool,
+    /// Directed token pairs whose settlement outcomes get tracked
+    /// individually.
+    pub tracked_token_pairs: HashSet<(eth::TokenAddress, eth::TokenAddress)>,
+    /// When set, drivers get asked once to rebroadcast settlements that are
+    /// stuck (e.g. because they are underpriced).
+    pub settle_rebroadcast: Option<SettleRebroadcast>,
+    /// How many blocks have to be built on top of the block a settlement
+    /// transaction was found in before the settlement counts as successful.
+    /// The transaction gets checked again afterwards to detect reorgs.
+    pub settlement_confirmation_blocks: u64,
+    /// For how many blocks the orders of a successful settlement stay in
+    /// flight so they can't get settled again if the settlement gets
+    /// reorged. Settlements that disappear from the chain keep their orders
+    /// in flight until they are confirmed again or stay gone for as many
+    /// blocks.
+    pub in_flight_reorg_protection_blocks: u64,
+    /// How long estimating the gas of a winning solution whose driver didn't
+    /// report it may take before [`Config::fallback_settlement_gas`] gets
+    /// recorded instead.
+    pub gas_estimation_timeout: Duration,
+    /// Gas recorded for winning solutions whose gas could neither be taken
+    /// from the driver nor estimated. The gas of the hooks of their orders
+    /// gets added on top.
+    pub fallback_settlement_gas: eth::Gas,
+    /// Orders expiring less than this long after the projected submission
+    /// deadline don't get sent to the drivers.
+    pub order_expiry_margin: Duration,
+    /// Number of blocks orders need to remain valid for after the submission
+    /// deadline to be included in the auction.
+    pub min_order_validity_blocks: u64,
+    /// For how many blocks an auction whose content didn't change gets skipped
+    /// before it is sent to the drivers again.
+    pub max_auction_reuse_blocks: u64,
+    /// How many of the most recent auctions get solved again (without
+    /// settling them) when the run loop starts.
+    pub replay_recent_auctions: usize,
+    /// Order executions worth less than this (on either side) get dropped
+    /// from proposed solutions.
+    pub dust_execution_threshold: eth::Ether,
+    /// Name of the driver that takes over the submission of a winning
+    /// solution when the `/settle` request to the winning driver times out.
+    pub fallback_submitter: Option<String>,
+    /// Over how many of the most recent auctions the concentration of winners
+    /// gets measured.
+    pub winner_concentration_window: NonZeroUsize,
+    /// Directory the report of every auction's run gets written to. No
+    /// reports are written if `None`.
+    pub run_report_dir: Option<PathBuf>,
+    /// Every auction whose id is a multiple of this gets solved without
+    /// settling any of its solutions to measure the quality of the solvers'
+    /// prices. No quote-only auctions get run if `None`.
+    pub quote_only_interval: Option<NonZeroU64>,
+    /// Minimum time between two competition updates the solver participation
+    /// guard gets notified about.
+    pub participation_update_interval: Duration,
+    /// Once this fraction of the drivers responded the remaining ones only get
+    /// `early_finish_grace` more time to respond. All drivers get the full
+    /// solve deadline if `None`.
+    pub early_finish_fraction: Option<f64>,
+    /// How long drivers that didn't respond yet still get once
+    /// `early_finish_fraction` of the drivers responded.
+    pub early_finish_grace: Duration,
+    /// Enables strict mode: the run loop refuses to start without drivers and
+    /// reports itself as unhealthy once no driver was allowed to participate
+    /// and answered in time for this many consecutive auctions.
+    pub max_auctions_without_drivers: Option<NonZeroUsize>,
+    /// The run loop reports itself as unhealthy if the solvable orders cache
+    /// still hasn't built its first auction this long after startup. This
+    /// most likely indicates a broken order ingestion.
+    pub max_auction_cache_startup_delay: Option<Duration>,
+    /// Scores within this distance of each other are considered tied and keep
+    /// the random order the solutions were collected in.
+    pub score_tie_epsilon: U256,
+    /// Fairness threshold (in wei) every solution has to respect regardless
+    /// of its driver's own threshold. Stricter driver thresholds still apply.
+    pub fairness_threshold_floor: Option<U256>,
+    /// Order in which the orders of an auction get sent to the drivers.
+    pub order_ordering: domain::auction::OrderOrdering,
+    /// What solutions get ranked by before winners get picked.
+    pub winner_objective: winner_selection::Objective,
+    /// How winner slots get filled after the best solution.
+    pub winner_merging: winner_selection::Merging,
+    /// Protocol fees of winning solutions above this share of an order's
+    /// volume (in basis points) get reported. Unchecked if not set.
+    pub max_protocol_fee_bps: Option<u32>,
+}
+
+/// Determines when the autopilot asks a driver to rebroadcast its
+/// settlement transaction.
+#[derive(Debug, Clone, Copy)]
+pub struct SettleRebroadcast {
+    /// How many blocks need to pass without the settlement appearing
+    /// on chain.
+    pub after_blocks: u64,
+    /// Only rebroadcast if fewer than this many blocks are left until the
+    /// submission deadline.
+    pub max_remaining_blocks: u64,
 }
 
 impl Config {
+    /// Checks that the configuration allows the run loop to make progress.
+    pub fn validate(&self) -> Result<(), InvalidConfig> {
+        if self.submission_deadline == 0 {
+            return Err(InvalidConfig::SubmissionDeadline);
+        }
+        if self.solve_deadline.is_zero() {
+            return Err(InvalidConfig::SolveDeadline);
+        }
+        if self.max_solve_deadline_padding >= self.solve_deadline {
+            return Err(InvalidConfig::SolveDeadlinePadding);
+        }
+        if self.max_settlement_transaction_wait.is_zero() {
+            return Err(InvalidConfig::MaxSettlementTransactionWait);
+        }
+        if self
+            .settle_rebroadcast
+            .is_some_and(|rebroadcast| rebroadcast.after_blocks >= self.submission_deadline)
+        {
+            return Err(InvalidConfig::SettleRebroadcast);
+        }
+        if self.max_auction_reuse_blocks == 0 {
+            return Err(InvalidConfig::MaxAuctionReuseBlocks);
+        }
+        if self
+            .run_report_dir
+            .as_ref()
+            .is_some_and(|dir| !dir.is_dir())
+        {
+            return Err(InvalidConfig::RunReportDir);
+        }
+        if self
+            .early_finish_fraction
+            .is_some_and(|fraction| !(fraction > 0. && fraction <= 1.))
+        {
+            return Err(InvalidConfig::EarlyFinishFraction);
+        }
+        if self.max_solutions_per_response < self.max_solutions_per_solver {
+            return Err(InvalidConfig::MaxSolutionsPerResponse);
+        }
+        Ok(())
+    }
+
+    /// Checks that the run loop can make progress with the configured
+    /// drivers.
+    pub fn validate_drivers(&self, drivers: &[Arc<infra::Driver>]) -> Result<(), InvalidConfig> {
+        if self.max_auctions_without_drivers.is_some() && drivers.is_empty() {
+            return Err(InvalidConfig::NoDrivers);
+        }
+        if let Some(name) = self
+            .fallback_submitter
+            .as_ref()
+            .filter(|name| !drivers.iter().any(|driver| &driver.name == *name))
+        {
+            return Err(InvalidConfig::FallbackSubmitter(name.clone()));
+        }
+        Ok(())
+    }
+
     fn single_winner(&self) -> bool {
-        // Always single winner if max_winners is 1
-        if self.max_winners_per_auction.get() == 1 {
+        // Always single winner if max_winners is 1 and no extra winners are
+        // allowed
+        if self.max_winners_per_auction.get() == 1 && self.winner_merging.max_extra_winners() == 0 {
             return true;
         }
 
@@ -89,12 +332,27 @@ pub struct RunLoop {
     solver_participation_guard: SolverParticipationGuard,
     solvable_orders_cache: Arc<SolvableOrdersCache>,
     trusted_tokens: AutoUpdatingTokenList,
-    in_flight_orders: Arc<Mutex<HashSet<OrderUid>>>,
+    in_flight_orders: Arc<Mutex<InFlightOrders>>,
+    /// Set by an operator to abandon the current auction before it gets
+    /// settled.
+    skip_auction: AtomicBool,
+    /// Settlements of winning solutions that are currently being executed.
+    settlement_executor: Arc<SettlementExecutor>,
     liveness: Arc<Liveness>,
     /// Maintenance tasks that should run before every runloop to have
     /// the most recent data available.
     maintenance: Arc<Maintenance>,
     competition_updates_sender: tokio::sync::mpsc::UnboundedSender<()>,
+    /// Winners of the most recent auctions.
+    winner_concentration: std::sync::Mutex<WinnerConcentration>,
+    /// Orders deferred due to token conflicts with the winners and in how
+    /// many more auctions they get prioritized.
+    deferred_orders: std::sync::Mutex<DeferredOrders>,
+    /// Driver participation in the most recent auction.
+    participation: Arc<infra::status::LatestParticipation>,
+    diagnostics: Arc<infra::status::RunLoopDiagnostics>,
+    started_at: Instant,
+    rng: RngFactory,
 }
 
 impl RunLoop {
@@ -110,8 +368,16 @@ impl RunLoop {
         liveness: Arc<Liveness>,
         maintenance: Arc<Maintenance>,
         competition_updates_sender: tokio::sync::mpsc::UnboundedSender<()>,
-    ) -> Self {
-        Self {
+        participation: Arc<infra::status::LatestParticipation>,
+        diagnostics: Arc<infra::status::RunLoopDiagnostics>,
+        settlement_executor: Arc<SettlementExecutor>,
+    ) -> Result<Self, InvalidConfig> {
+        config.validate()?;
+        config.validate_drivers(&drivers)?;
+        Ok(Self {
+            winner_concentration: std::sync::Mutex::new(WinnerConcentration::new(
+                config.winner_concentration_window,
+            )),
             config,
             eth,
             persistence,
@@ -120,65 +386,181 @@ impl RunLoop {
             solvable_orders_cache,
             trusted_tokens,
             in_flight_orders: Default::default(),
+            skip_auction: Default::default(),
+            settlement_executor,
+            deferred_orders: Default::default(),
             liveness,
             maintenance,
             competition_updates_sender,
-        }
+            participation,
+            diagnostics,
+            started_at: Instant::now(),
+            rng: thread_rng(),
+        })
     }
 
-    pub async fn run_forever(self) -> ! {
-        Maintenance::spawn_cow_amm_indexing_task(
+    /// Replaces the source of randomness used to break ties between
+    /// solutions.
+    pub fn with_rng(self, rng: RngFactory) -> Self {
+        Self { rng, ..self }
+    }
+
+    pub async fn run_forever(self: Arc<Self>) -> ! {
+        Maintenance::spawn_background_tasks(
             self.maintenance.clone(),
             self.eth.current_block().clone(),
         );
+        self.report_unfinished_competitions().await;
+        self.replay_recent_auctions().await;
         let mut last_auction = None;
         let mut last_block = None;
-        let self_arc = Arc::new(self);
         loop {
-            let auction = self_arc
-                .next_auction(&mut last_auction, &mut last_block)
-                .await;
+            let auction = self.next_auction(&mut last_auction, &mut last_block).await;
             if let Some(auction) = auction {
                 let auction_id = auction.id;
-                self_arc
-                    .single_run(auction)
+                self.single_run(auction)
                     .instrument(tracing::info_span!("auction", auction_id))
                     .await
             };
         }
     }
 
+    /// Makes the run loop abandon the current auction before any of its
+    /// solutions get settled. If no auction is in progress the next one gets
+    /// skipped.
+    pub fn skip_current_auction(&self) {
+        tracing::warn!("operator requested to skip the current auction");
+        self.skip_auction.store(true, Ordering::SeqCst);
+        Metrics::admin_action("skip_auction");
+    }
+
+    /// Enables or disables maintenance tasks, e.g. to pause a misbehaving
+    /// indexer during an incident.
+    pub fn toggle_maintenance_tasks(
+        &self,
+        names: &[String],
+        enabled: bool,
+    ) -> Result<(), maintenance::UnknownTasks> {
+        let tasks = names.iter().map(String::as_str);
+        match enabled {
+            true => self.maintenance.enable(tasks)?,
+            false => self.maintenance.disable(tasks)?,
+        }
+        tracing::warn!(?names, enabled, "operator toggled maintenance tasks");
+        Metrics::admin_action("toggle_maintenance_tasks");
+        Ok(())
+    }
+
+    /// Releases in-flight orders so they can get settled again, e.g. when a
+    /// driver keeps them locked long past the deadline. Returns the orders
+    /// that were in flight.
+    pub async fn release_in_flight(&self, release: &InFlightRelease) -> Vec<OrderUid> {
+        let released = self.in_flight_orders.lock().await.release(release);
+        tracing::warn!(?release, ?released, "operator released in-flight orders");
+        Metrics::admin_action("release_in_flight");
+        Metrics::get()
+            .released_in_flight_orders
+            .inc_by(released.len().try_into().unwrap_or(u64::MAX));
+        released
+    }
+
+    /// Detects whether `current` isn't a descendant of the previously observed
+    /// block `previous`. If blocks got skipped in between, the hash of the
+    /// block at the previously observed height gets fetched to tell whether
+    /// that block is still part of the chain.
+    async fn reorg_since(
+        &self,
+        previous: &BlockInfo,
+        current: &BlockInfo,
+    ) -> Option<infra::status::Reorg> {
+        let ancestor = match current.number.checked_sub(previous.number) {
+            Some(gap) if gap > 1 => match self.eth.block_hash(previous.number).await {
+                Ok(hash) => hash,
+                Err(err) => {
+                    tracing::warn!(?err, "failed to fetch ancestor block to check for reorgs");
+                    None
+                }
+            },
+            _ => None,
+        };
+        detect_reorg(previous, current, ancestor)
+    }
+
+    /// Consumes an operator's request to skip the current auction.
+    fn should_skip_auction(&self, auction_id: Id) -> bool {
+        let skip = self.skip_auction.swap(false, Ordering::SeqCst);
+        if skip {
+            tracing::warn!(auction_id, "skipping auction as requested by operator");
+        }
+        skip
+    }
+
     /// Sleeps until the next auction is supposed to start, builds it and
     /// returns it.
     #[instrument(skip(self, prev_auction), fields(prev_auction = prev_auction.as_ref().map(|a| a.id)))]
     async fn next_auction(
         &self,
         prev_auction: &mut Option<domain::Auction>,
-        prev_block: &mut Option<H256>,
+        prev_block: &mut Option<BlockInfo>,
     ) -> Option<domain::Auction> {
         // wait for appropriate time to start building the auction
         let start_block = {
             let current_block = *self.eth.current_block().borrow();
             let time_since_last_block = current_block.observed_at.elapsed();
             let auction_block = if time_since_last_block > self.config.max_run_loop_delay {
-                if prev_block.is_some_and(|prev_block| prev_block != current_block.hash) {
+                if prev_block.is_some_and(|prev_block| prev_block.hash != current_block.hash) {
                     // don't emit warning if we finished prev run loop within the same block
                     tracing::warn!(
                         missed_by = ?time_since_last_block - self.config.max_run_loop_delay,
                         "missed optimal auction start, wait for new block"
                     );
                 }
+                let node_status = self.liveness.node_status();
+                if !node_status.is_healthy() {
+                    tracing::warn!(
+                        node_status = node_status.as_str(),
+                        "waiting for a new block while the ethereum node is unhealthy, this is \
+                         most likely a node problem"
+                    );
+                }
                 ethrpc::block_stream::next_block(self.eth.current_block()).await
             } else {
                 current_block
             };
 
-            self.run_maintenance(&auction_block).await;
-            match self
-                .solvable_orders_cache
-                .update(auction_block.number)
-                .await
-            {
+            // The cached orders and prices might be based on orphaned blocks
+            // so everything gets rebuilt from scratch.
+            let reorg = match prev_block {
+                Some(prev_block) => self.reorg_since(prev_block, &auction_block).await,
+                None => None,
+            };
+            // remembered right away so a reorg only gets handled once even if
+            // no auction gets cut
+            *prev_block = Some(auction_block);
+            if let Some(reorg) = reorg {
+                tracing::warn!(?reorg, "detected reorg, refreshing all state");
+                Metrics::reorg_detected(reorg.depth);
+                *self.diagnostics.last_reorg.lock().unwrap() = Some(reorg);
+                let start = Instant::now();
+                if self.maintenance.force_update(&auction_block).await {
+                    Metrics::ran_maintenance(start.elapsed());
+                }
+            } else {
+                self.run_maintenance(&auction_block).await;
+            }
+            let update = match reorg {
+                Some(_) => {
+                    self.solvable_orders_cache
+                        .full_update(auction_block.number)
+                        .await
+                }
+                None => {
+                    self.solvable_orders_cache
+                        .update(auction_block.number)
+                        .await
+                }
+            };
+            match update {
                 Ok(()) => {
                     tracing::trace!("solvable orders cache updated");
                     self.solvable_orders_cache.track_auction_update("success");
@@ -194,37 +576,73 @@ impl RunLoop {
         let auction = self.cut_auction().await?;
         tracing::trace!(auction_id = ?auction.id, "auction cut");
 
-        // Only run the solvers if the auction or block has changed.
-        let previous = prev_auction.replace(auction.clone());
-        if previous.as_ref() == Some(&auction)
-            && prev_block.replace(start_block.hash) == Some(start_block.hash)
+        // Only run the solvers if the auction changed or the previous run is
+        // too long ago.
+        if prev_auction
+            .as_ref()
+            .is_some_and(|previous| self.is_reusable(previous, &auction))
         {
+            tracing::debug!(content_hash = ?auction.content_hash, "skipping duplicate auction");
+            Metrics::duplicate_auction_skipped();
             return None;
         }
 
-        observe::log_auction_delta(&previous, &auction);
+        let previous = prev_auction.replace(auction.clone());
+        let added = observe::log_auction_delta(&previous, &auction);
+        // After a restart all orders count as added but the ones that were
+        // already seen before keep their first appearance.
+        self.persistence
+            .store_first_auction_appearances(auction.id, added);
         self.liveness.auction();
         Metrics::auction_ready(start_block.observed_at);
         Some(auction)
     }
 
+    /// Whether the results of solving `previous` are still good enough to not
+    /// solve `auction` again.
+    /// Quote-only auctions never get settled so they can't be reused.
+    fn is_reusable(&self, previous: &domain::Auction, auction: &domain::Auction) -> bool {
+        !previous.quote_only
+            && previous.content_hash == auction.content_hash
+            && auction.block.saturating_sub(previous.block) < self.config.max_auction_reuse_blocks
+    }
+
     /// Runs maintenance on all components to ensure the system uses
     /// the latest available state.
     async fn run_maintenance(&self, block: &BlockInfo) {
         let start = Instant::now();
-        self.maintenance.update(block).await;
-        Metrics::ran_maintenance(start.elapsed());
+        if self.maintenance.update(block).await {
+            Metrics::ran_maintenance(start.elapsed());
+        }
     }
 
     async fn cut_auction(&self) -> Option<domain::Auction> {
         let auction = match self.solvable_orders_cache.current_auction().await {
-            Some(auction) => auction,
+            Some((auction, built_at)) => {
+                self.liveness.auction_cache_unpopulated(false);
+                Metrics::current_auction(&auction, built_at.elapsed());
+                auction
+            }
             None => {
-                tracing::debug!("no current auction");
+                // The cache was never populated which is expected for a short
+                // while after startup but not for long.
+                let since_startup = self.started_at.elapsed();
+                let unhealthy = self
+                    .config
+                    .max_auction_cache_startup_delay
+                    .is_some_and(|max| since_startup > max);
+                if unhealthy {
+                    tracing::warn!(?since_startup, "solvable orders cache was never populated");
+                } else {
+                    tracing::debug!("no current auction");
+                }
+                self.liveness.auction_cache_unpopulated(unhealthy);
+                Metrics::auction_skipped("unpopulated_cache");
                 return None;
             }
         };
         let auction = self.remove_in_flight_orders(auction).await;
+        let auction = self.remove_orders_near_expiry(auction);
 
         let id = match self.persistence.replace_current_auction(&auction).await {
             Ok(id) => {
@@ -241,78 +659,395 @@ impl RunLoop {
             // Updating liveness probe to not report unhealthy due to this optimization
             self.liveness.auction();
             tracing::debug!("skipping empty auction");
+            Metrics::auction_skipped("empty");
             return None;
         }
 
-        Some(domain::Auction {
+        let quote_only = self
+            .config
+            .quote_only_interval
+            .is_some_and(|interval| u64::try_from(id).is_ok_and(|id| id % interval.get() == 0));
+        if quote_only {
+            tracing::info!(auction_id = id, "running quote-only auction");
+        }
+
+        let auction = domain::Auction {
             id,
             block: auction.block,
+            content_hash: domain::auction::ContentHash::new(
+                &auction.orders,
+                &auction.prices,
+                &auction.surplus_capturing_jit_order_owners,
+            ),
             orders: auction.orders,
             prices: auction.prices,
             surplus_capturing_jit_order_owners: auction.surplus_capturing_jit_order_owners,
-        })
+            quote_only,
+            prioritized_orders: Arc::new(
+                self.deferred_orders
+                    .lock()
+                    .unwrap()
+                    .next_auction(!quote_only),
+            ),
+        };
+        match auction.total_volume_eth() {
+            Some(volume) => Metrics::auction_volume(volume),
+            None => tracing::debug!(auction_id = id, "missing prices for auction volume"),
+        }
+        Some(auction)
+    }
+
+    /// Defers the orders of ranked solutions that only lost due to token
+    /// conflicts with the winners to the next auctions. Orders a winner
+    /// settles aren't deferred.
+    fn defer_conflicting_orders(&self, ranking: &Ranking) {
+        let Some(auctions) = self.config.conflict_deferral_auctions else {
+            return;
+        };
+        defer_blocked_orders(
+            &mut self.deferred_orders.lock().unwrap(),
+            ranking.winners(),
+            ranking.blocked_by_token_conflicts(),
+            auctions,
+        );
+    }
+
+    fn arbitrator(
+        &self,
+        is_single_winner_selection: bool,
+    ) -> Box<dyn winner_selection::Arbitrator> {
+        if is_single_winner_selection {
+            Box::new(winner_selection::max_score::Config {
+                score_tie_epsilon: self.config.score_tie_epsilon,
+                objective: self.config.winner_objective,
+                fairness_threshold_floor: self.config.fairness_threshold_floor,
+            })
+        } else {
+            Box::new(winner_selection::combinatorial::Config {
+                max_winners: self.config.max_winners_per_auction.get(),
+                max_winners_per_submission_address: self.config.max_winners_per_submission_address,
+                weth: self.eth.contracts().wrapped_native_token(),
+                treat_native_as_weth: self.config.treat_native_as_weth,
+                score_tie_epsilon: self.config.score_tie_epsilon,
+                objective: self.config.winner_objective,
+                merging: self.config.winner_merging,
+                fairness_threshold_floor: self.config.fairness_threshold_floor,
+            })
+        }
+    }
+
+    /// Reports competitions whose data never got stored because the process
+    /// died before post-processing them.
+    async fn report_unfinished_competitions(&self) {
+        let auction_ids = match self.persistence.unfinished_competitions().await {
+            Ok(auction_ids) => auction_ids,
+            Err(err) => {
+                tracing::warn!(?err, "failed to load unfinished competitions");
+                return;
+            }
+        };
+        for auction_id in &auction_ids {
+            tracing::warn!(
+                auction_id,
+                "solutions of auction were lost before being stored"
+            );
+        }
+        Metrics::get()
+            .competitions_lost
+            .inc_by(auction_ids.len().try_into().unwrap_or(u64::MAX));
+        *self.diagnostics.competition_in_progress.lock().unwrap() = auction_ids.last().copied();
+    }
+
+    /// Records the competition before it gets ranked so solutions lost to a
+    /// crash before post-processing can be detected on startup. This is best
+    /// effort and never stops the competition.
+    ///
+    /// The record gets written in the background so it doesn't delay the
+    /// ranking. The returned handle has to be awaited before the record gets
+    /// cleared, otherwise a late write could outlive the competition.
+    fn save_competition_wip(
+        self: &Arc<Self>,
+        auction_id: domain::auction::Id,
+        solutions: &[competition::Participant<Unranked>],
+    ) -> JoinHandle<()> {
+        let solutions: Vec<_> = solutions
+            .iter()
+            .map(|participant| {
+                (
+                    participant.driver().name.clone(),
+                    participant.solution().hash(),
+                )
+            })
+            .collect();
+        let self_ = self.clone();
+        let fut = async move {
+            if let Err(err) = self_
+                .persistence
+                .save_competition_wip(auction_id, &solutions)
+                .await
+            {
+                tracing::warn!(?err, "failed to save competition in progress");
+                return;
+            }
+            *self_.diagnostics.competition_in_progress.lock().unwrap() = Some(auction_id);
+        }
+        .instrument(tracing::Span::current());
+
+        tokio::spawn(fut)
+    }
+
+    /// Removes the record of a competition whose data intentionally doesn't
+    /// get stored.
+    async fn clear_competition_wip(&self, auction_id: domain::auction::Id) {
+        let result = async {
+            let mut tx = self.persistence.db_transaction().await?;
+            self.persistence
+                .clear_competition_wip(&mut tx, auction_id)
+                .await?;
+            tx.commit().await?;
+            anyhow::Ok(())
+        }
+        .await;
+        if let Err(err) = result {
+            tracing::warn!(?err, "failed to clear competition in progress");
+            return;
+        }
+        *self.diagnostics.competition_in_progress.lock().unwrap() = None;
+    }
+
+    /// Solves the most recent auctions again before the run loop goes live.
+    /// This warms up caches (e.g. in the drivers) and surfaces configuration
+    /// problems early. Replays never settle anything or store competition
+    /// data.
+    async fn replay_recent_auctions(&self) {
+        if self.config.replay_recent_auctions == 0 {
+            return;
+        }
+        let auctions = match self
+            .persistence
+            .fetch_recent_auctions(self.config.replay_recent_auctions)
+            .await
+        {
+            Ok(auctions) => auctions,
+            Err(err) => {
+                tracing::warn!(?err, "failed to load auctions to replay");
+                Metrics::auction_replayed("load_failed");
+                return;
+            }
+        };
+        for auction in auctions {
+            let auction_id = auction.id;
+            self.replay(auction)
+                .instrument(tracing::info_span!("replay", auction_id))
+                .await;
+            // Replays can take a while so make sure the process doesn't get
+            // considered dead in the meantime.
+            self.liveness.auction();
+        }
+    }
+
+    async fn replay(&self, auction: domain::Auction) {
+        tracing::info!(
+            replay = true,
+            orders = auction.orders.len(),
+            "replaying auction"
+        );
+        let trusted_tokens = self.trusted_tokens.all();
+        let (solutions, _) = self
+            .fetch_solutions(&auction, &trusted_tokens, AuctionKind::Replay)
+            .await;
+        if solutions.is_empty() {
+            tracing::info!(replay = true, "replayed auction without solutions");
+            Metrics::auction_replayed("no_solutions");
+            return;
+        }
+
+        let winner_selection = self.arbitrator(self.config.single_winner());
+        let ranking = winner_selection.arbitrate(solutions, &auction);
+        let winners: Vec<_> = ranking
+            .winners()
+            .map(|winner| (winner.driver().name.clone(), winner.solution().score()))
+            .collect();
+        tracing::info!(
+            replay = true,
+            solutions = ranking.enumerated().count(),
+            ?winners,
+            "replayed auction"
+        );
+        Metrics::auction_replayed("solved");
     }
 
     #[instrument(skip_all, fields(auction_id = auction.id, auction_block = auction.block, auction_orders = auction.orders.len()))]
     async fn single_run(self: &Arc<Self>, auction: domain::Auction) {
         let single_run_start = Instant::now();
+        if self.should_skip_auction(auction.id) {
+            return;
+        }
         tracing::info!(auction_id = ?auction.id, "solving");
+        // Summarize failures that stopped repeating.
+        SOLVE_ERRORS.flush();
+        SETTLE_ERRORS.flush();
+        POST_PROCESSING_ERRORS.flush();
 
         // Mark all auction orders as `Ready` for competition
         self.persistence
             .store_order_events(auction.orders.iter().map(|o| o.uid), OrderEventLabel::Ready);
         tracing::trace!(auction_id = ?auction.id, "orders marked as ready");
 
+        // Snapshot the trusted tokens so the same set gets sent to all drivers
+        // and stored with the auction.
+        let trusted_tokens = self.trusted_tokens.all();
+
         // Collect valid solutions from all drivers
-        let solutions = self.fetch_solutions(&auction).await;
+        let (solutions, participation) = self
+            .fetch_solutions(&auction, &trusted_tokens, AuctionKind::of(&auction))
+            .await;
+        self.participation.update(auction.id, participation.clone());
+        // stored right away since auctions without solutions or winners
+        // never reach the post-processing
+        self.store_participation(auction.id, participation.clone());
+        self.track_driver_availability(&participation);
+        let mut report = self
+            .config
+            .run_report_dir
+            .is_some()
+            .then(|| RunReport::new(&auction, &participation));
         observe::solutions(&solutions);
         if solutions.is_empty() {
+            self.write_run_report(report);
             return;
         }
+        let competition_wip = self.save_competition_wip(auction.id, &solutions);
 
         // Build the winner selection implementation.
         // We only compute this once to ensure consistency throughout the entire
         // auction.
         let is_single_winner_selection = self.config.single_winner();
         tracing::info!(auction_id = ?auction.id, ?is_single_winner_selection, "winner selection implementation");
-        let winner_selection: Box<dyn winner_selection::Arbitrator> = if is_single_winner_selection
-        {
-            Box::new(winner_selection::max_score::Config)
-        } else {
-            Box::new(winner_selection::combinatorial::Config {
-                max_winners: self.config.max_winners_per_auction.get(),
-                weth: self.eth.contracts().wrapped_native_token(),
-            })
-        };
+        let winner_selection = self.arbitrator(is_single_winner_selection);
 
         let ranking = winner_selection.arbitrate(solutions, &auction);
+        let reference_scores = winner_selection.compute_reference_scores(&ranking);
+        if let Some(report) = &mut report {
+            report.record_ranking(&ranking, &reference_scores, is_single_winner_selection);
+        }
 
         // Count and record the number of winners
         let num_winners = ranking.winners().count();
         if let Some(num_winners_f64) = num_winners.to_f64() {
             Metrics::get().auction_winners.observe(num_winners_f64);
         }
+        // Nobody wins quote-only auctions on purpose. Other auctions without
+        // winners are expected too so they get classified here instead of
+        // through a post-processing error. Post-processing stores them like
+        // any other competition and never fails because of missing winners.
+        if num_winners == 0 && !auction.quote_only {
+            tracing::info!(
+                auction_id = ?auction.id,
+                solutions = ranking.enumerated().count(),
+                "competition produced no winners"
+            );
+            Metrics::no_winners();
+            if !self.config.persist_competitions_without_winners {
+                Metrics::post_processing("no_winners");
+                // nothing gets stored for this competition on purpose
+                competition_wip.await.ok();
+                self.clear_competition_wip(auction.id).await;
+                self.write_run_report(report);
+                return;
+            }
+        }
 
         let competition_simulation_block = self.eth.current_block().borrow().number;
         let block_deadline = competition_simulation_block + self.config.submission_deadline;
 
+        // post-processing clears the record of the competition in progress
+        competition_wip.await.ok();
         // Post-processing should not be executed asynchronously since it includes steps
         // of storing all the competition/auction-related data to the DB.
-        if let Err(err) = self
-            .post_processing(
-                &auction,
-                competition_simulation_block,
+        let post_processing = self.post_processing(
+            &auction,
+            competition_simulation_block,
+            &ranking,
+            &reference_scores,
+            &trusted_tokens,
+            block_deadline,
+            is_single_winner_selection,
+        );
+        let competition_data_complete = match with_post_processing_timeout(
+            self.config.post_processing_timeout,
+            post_processing,
+        )
+        .await
+        {
+            Ok(complete) => complete,
+            Err(err) => {
+                Metrics::post_processing("error");
+                match POST_PROCESSING_ERRORS.record("post_processing") {
+                    Occurrence::First => {
+                        tracing::error!(?err, "failed to post-process competition")
+                    }
+                    Occurrence::Repeated(occurrences) => {
+                        tracing::debug!(?err, occurrences, "failed to post-process competition")
+                    }
+                }
+                // the winners never get to settle but their reward inputs
+                // still have to be stored so they don't get penalized
+                self.skip_settlements(
+                    auction.id,
+                    &ranking,
+                    &reference_scores,
+                    block_deadline,
+                    false,
+                    report,
+                );
+                return;
+            }
+        };
+        tracing::trace!(auction_id = ?auction.id, "post-processing completed");
+        Metrics::post_processing(if num_winners == 0 && !auction.quote_only {
+            "no_winners"
+        } else {
+            "success"
+        });
+        *self.diagnostics.competition_in_progress.lock().unwrap() = None;
+
+        // Quote-only auctions and auctions without winners end here. None of
+        // the solutions get settled so all proposed orders only count as
+        // `Considered`.
+        if auction.quote_only || num_winners == 0 {
+            self.persistence.store_order_events(
+                ranking
+                    .all()
+                    .flat_map(|p| p.solution().order_ids().copied())
+                    .unique(),
+                OrderEventLabel::Considered,
+            );
+            tracing::info!(
+                auction_id = ?auction.id,
+                quote_only = auction.quote_only,
+                "competition completed without settlement"
+            );
+            self.write_run_report(report);
+            return;
+        }
+
+        if self.should_skip_auction(auction.id) {
+            self.skip_settlements(
+                auction.id,
                 &ranking,
+                &reference_scores,
                 block_deadline,
-                winner_selection,
-                is_single_winner_selection,
-            )
-            .await
-        {
-            tracing::error!(?err, "failed to post-process competition");
+                competition_data_complete,
+                report,
+            );
             return;
         }
-        tracing::trace!(auction_id = ?auction.id, "post-processing completed");
+
+        self.record_winners(&ranking);
+        self.defer_conflicting_orders(&ranking);
+
+        self.notify_outcomes(auction.id, &ranking, &reference_scores);
 
         // Mark all winning orders as `Executing`
         let winning_orders = ranking
@@ -321,6 +1056,7 @@ impl RunLoop {
             .collect::<HashSet<_>>();
         self.persistence
             .store_order_events(winning_orders.clone(), OrderEventLabel::Executing);
+        self.store_order_execution_statuses(auction.id, &ranking, block_deadline);
 
         // Mark the rest as `Considered` for execution
         self.persistence.store_order_events(
@@ -332,124 +1068,539 @@ impl RunLoop {
         );
         tracing::trace!(auction_id = ?auction.id, "orders marked as considered");
 
-        for (solution_uid, winner) in ranking
+        let winners: Vec<_> = ranking
             .enumerated()
             .filter(|(_, participant)| participant.is_winner())
+            .collect();
+        let fallback = self.fallback_submitter(&ranking);
+        // Drivers can't reveal solutions anymore once they settled them, so
+        // missing gas has to get estimated and the calldata for the fallback
+        // submitter revealed before any of them gets settled.
+        let winners_gas = futures::future::join_all(winners.iter().map(|(_, winner)| {
+            futures::future::join(
+                self.solution_gas(&auction, winner.driver(), winner.solution()),
+                self.handoff(
+                    auction.id,
+                    winner.driver(),
+                    winner.solution(),
+                    fallback.as_ref(),
+                ),
+            )
+        }))
+        .await;
+
+        let mut settlements = Vec::new();
+        for ((solution_uid, winner), ((gas, gas_source), handoff)) in
+            winners.into_iter().zip(winners_gas)
         {
             let (driver, solution) = (winner.driver(), winner.solution());
             tracing::info!(driver = %driver.name, solution = %solution.id(), "winner");
 
-            self.start_settlement_execution(
-                auction.id,
-                single_run_start,
-                driver,
-                solution,
-                solution_uid,
-                block_deadline,
-            )
-            .await;
+            let settlement = self
+                .start_settlement_execution(ExecutionRequest {
+                    auction_id: auction.id,
+                    driver: driver.clone(),
+                    solution: solution.clone(),
+                    solution_uid,
+                    block_deadline,
+                    single_run_start,
+                    gas,
+                    handoff,
+                })
+                .await;
+            self.store_solution_gas(auction.id, solution_uid, gas, gas_source);
+            let winner = reward_winner(solution_uid, solution, &reference_scores, false);
+            settlements.push((winner, Some(settlement)));
         }
         tracing::trace!(auction_id = ?auction.id, "settlement execution started");
         observe::unsettled(&ranking, &auction);
+
+        self.store_reward_inputs(
+            auction.id,
+            block_deadline,
+            competition_data_complete,
+            settlements,
+            report,
+        );
     }
 
-    /// Starts settlement execution in a background task. The function is async
-    /// only to get access to the locks.
-    async fn start_settlement_execution(
+    /// Stores the reward inputs of an auction whose winners never get asked
+    /// to settle. Auctions without winners have nothing to store.
+    fn skip_settlements(
         self: &Arc<Self>,
         auction_id: Id,
-        single_run_start: Instant,
-        driver: &Arc<infra::Driver>,
-        solution: &Solution,
-        solution_uid: usize,
+        ranking: &Ranking,
+        reference_scores: &HashMap<eth::Address, competition::Score>,
         block_deadline: u64,
+        competition_data_complete: bool,
+        report: Option<RunReport>,
     ) {
-        let solved_order_uids: HashSet<_> = solution.orders().keys().cloned().collect();
-        self.in_flight_orders
-            .lock()
-            .await
-            .extend(solved_order_uids.clone());
+        let winners: Vec<_> = ranking
+            .enumerated()
+            .filter(|(_, participant)| participant.is_winner())
+            .map(|(solution_uid, participant)| {
+                let winner =
+                    reward_winner(solution_uid, participant.solution(), reference_scores, true);
+                (winner, None)
+            })
+            .collect();
+        if winners.is_empty() {
+            self.write_run_report(report);
+            return;
+        }
+        self.store_reward_inputs(
+            auction_id,
+            block_deadline,
+            competition_data_complete,
+            winners,
+            report,
+        );
+    }
 
-        let solution_id = solution.id();
-        let solver = solution.solver();
+    /// Once all winners finished settling, stores the data needed to compute
+    /// the solver rewards of the auction. Winners that failed to settle get
+    /// stored without a settlement so they can be penalized, unless their
+    /// settlement never got started. The settlement outcomes also complete
+    /// the run report.
+    fn store_reward_inputs(
+        self: &Arc<Self>,
+        auction_id: Id,
+        block_deadline: u64,
+        competition_data_complete: bool,
+        settlements: Vec<(reward_inputs::Winner, Option<JoinHandle<Option<TxId>>>)>,
+        report: Option<RunReport>,
+    ) {
         let self_ = self.clone();
-        let driver_ = driver.clone();
-
-        let settle_fut = async move {
-            tracing::info!(driver = %driver_.name, solution = %solution_id, "settling");
-            let submission_start = Instant::now();
+        let fut = async move {
+            let mut winners = Vec::with_capacity(settlements.len());
+            for (mut winner, settlement) in settlements {
+                let tx = match settlement {
+                    Some(settlement) => settlement.await.ok().flatten(),
+                    None => None,
+                };
+                if let Some(tx) = tx {
+                    let transaction = self_
+                        .eth
+                        .transaction(tx)
+                        .await
+                        .inspect_err(|err| {
+                            tracing::warn!(?err, %tx, "failed to fetch settlement transaction");
+                        })
+                        .ok();
+                    winner.settlement = Some(reward_inputs::Settlement {
+                        tx,
+                        gas: transaction.as_ref().map(|transaction| transaction.gas),
+                        gas_price: transaction.map(|transaction| transaction.gas_price),
+                    });
+                }
+                winners.push(winner);
+            }
 
-            match self_
-                .settle(
-                    &driver_,
-                    solved_order_uids.clone(),
-                    solver,
-                    auction_id,
-                    solution_id,
-                    solution_uid,
-                    block_deadline,
-                )
+            let inputs = reward_inputs::RewardInputs {
+                block_deadline,
+                competition_data_complete,
+                winners,
+            };
+            if let Err(err) = self_
+                .persistence
+                .save_reward_inputs(auction_id, &inputs)
                 .await
             {
-                Ok(tx_hash) => {
-                    Metrics::settle_ok(
-                        &driver_,
-                        solved_order_uids.len(),
-                        submission_start.elapsed(),
-                    );
-                    tracing::debug!(?tx_hash, driver = %driver_.name, ?solver, "solution settled");
+                tracing::error!(?err, auction_id, "failed to store reward inputs");
+            }
+
+            self_.write_run_report(report.map(|mut report| {
+                report.record_settlements(&inputs.winners);
+                report
+            }));
+        }
+        .instrument(tracing::Span::current());
+
+        tokio::spawn(fut);
+    }
+
+    /// Determines the gas of a winning solution. Solutions without driver
+    /// reported gas get it estimated based on their revealed calldata. This has
+    /// to happen before the solution gets settled because the driver forgets it
+    /// afterwards, so it is bounded by [`Config::gas_estimation_timeout`] to
+    /// not delay the settlement for long.
+    async fn solution_gas(
+        &self,
+        auction: &domain::Auction,
+        driver: &infra::Driver,
+        solution: &Solution,
+    ) -> (eth::Gas, GasSource) {
+        let estimate = async {
+            let calldata = driver
+                .reveal(reveal::Request {
+                    solution_id: solution.id(),
+                    auction_id: auction.id,
+                })
+                .await
+                .map_err(|err| ("reveal", anyhow::Error::from(err)))?
+                .calldata
+                .uninternalized;
+            let tx = eth::Tx {
+                from: solution.solver(),
+                to: self.eth.contracts().settlement().address().into(),
+                input: calldata,
+            };
+            self.eth
+                .estimate_gas(&tx)
+                .await
+                .map_err(|err| ("estimate", anyhow::Error::from(err)))
+        };
+        match reported_or_estimated_gas(
+            solution.gas(),
+            estimate,
+            self.config.gas_estimation_timeout,
+        )
+        .await
+        {
+            Ok(gas) => gas,
+            Err((reason, err)) => {
+                tracing::debug!(?err, driver = %driver.name, "failed to estimate solution gas");
+                Metrics::gas_estimation_failed(driver, reason);
+                let hooks = self.hooks_gas(auction, solution).await;
+                let gas = self
+                    .config
+                    .fallback_settlement_gas
+                    .0
+                    .saturating_add(hooks.0);
+                (eth::Gas(gas), GasSource::Fallback)
+            }
+        }
+    }
+
+    /// Gas of the pre- and post-interactions of the orders the solution
+    /// executes. They come on top of the fallback settlement gas. Falls back
+    /// to a lower bound based on their calldata if estimating them fails.
+    async fn hooks_gas(&self, auction: &domain::Auction, solution: &Solution) -> eth::Gas {
+        let interactions: Vec<_> = auction
+            .orders
+            .iter()
+            .filter(|order| solution.orders().contains_key(&order.uid))
+            .flat_map(|order| {
+                order
+                    .pre_interactions
+                    .iter()
+                    .chain(&order.post_interactions)
+            })
+            .cloned()
+            .collect();
+        if interactions.is_empty() {
+            return eth::Gas::default();
+        }
+        let estimate = competition::gas::estimate_interactions_gas(&interactions, &self.eth);
+        match tokio::time::timeout(self.config.gas_estimation_timeout, estimate).await {
+            Ok(Ok(gas)) => gas,
+            Ok(Err(err)) => {
+                tracing::debug!(?err, "failed to estimate gas of order hooks");
+                competition::gas::interactions_gas_lower_bound(&interactions)
+            }
+            Err(_) => competition::gas::interactions_gas_lower_bound(&interactions),
+        }
+    }
+
+    /// Stores the gas of a winning solution in the background.
+    fn store_solution_gas(
+        &self,
+        auction_id: Id,
+        solution_uid: usize,
+        gas: eth::Gas,
+        source: GasSource,
+    ) {
+        let persistence = self.persistence.clone();
+        let fut = async move {
+            if let Err(err) = persistence
+                .store_solution_gas(auction_id, solution_uid, gas, source)
+                .await
+            {
+                match POST_PROCESSING_ERRORS.record("store_solution_gas") {
+                    Occurrence::First => {
+                        tracing::warn!(?err, "failed to store solution gas")
+                    }
+                    Occurrence::Repeated(occurrences) => {
+                        tracing::debug!(?err, occurrences, "failed to store solution gas")
+                    }
                 }
-                Err(err) => {
-                    Metrics::settle_err(&driver_, submission_start.elapsed(), &err);
-                    tracing::warn!(?err, driver = %driver_.name, "settlement failed");
+            }
+        }
+        .instrument(tracing::Span::current());
+
+        tokio::spawn(fut);
+    }
+
+    /// Stores how the drivers took part in the auction in the background.
+    /// Participation is informational and not needed to compute rewards.
+    fn store_participation(&self, auction_id: Id, participation: ParticipationMatrix) {
+        let persistence = self.persistence.clone();
+        let fut = async move {
+            if let Err(err) = persistence
+                .save_participation(auction_id, &participation)
+                .await
+            {
+                match POST_PROCESSING_ERRORS.record("store_participation") {
+                    Occurrence::First => {
+                        tracing::warn!(?err, "failed to store driver participation")
+                    }
+                    Occurrence::Repeated(occurrences) => {
+                        tracing::debug!(?err, occurrences, "failed to store driver participation")
+                    }
                 }
             }
-            Metrics::single_run_completed(single_run_start.elapsed());
         }
         .instrument(tracing::Span::current());
 
-        tokio::spawn(settle_fut);
+        tokio::spawn(fut);
+    }
+
+    /// Writes the run report in the background if reports are enabled.
+    fn write_run_report(&self, report: Option<RunReport>) {
+        let Some((report, dir)) = report.zip(self.config.run_report_dir.clone()) else {
+            return;
+        };
+        let fut = async move {
+            if let Err(err) = report.write(&dir).await {
+                tracing::warn!(?err, "failed to write run report");
+            }
+        }
+        .instrument(tracing::Span::current());
+
+        tokio::spawn(fut);
+    }
+
+    /// Updates the winner concentration metric with the winners of the
+    /// auction.
+    fn record_winners(&self, ranking: &Ranking) {
+        let mut concentration = self.winner_concentration.lock().unwrap();
+        concentration.record(
+            ranking
+                .winners()
+                .map(|participant| participant.solver_address())
+                .collect(),
+        );
+        Metrics::get()
+            .winner_concentration_hhi
+            .set(concentration.hhi());
+    }
+
+    /// Marks the orders of the solution as in flight and submits its
+    /// settlement to the [`SettlementExecutor`]. The function is async only to
+    /// get access to the locks.
+    async fn start_settlement_execution(
+        self: &Arc<Self>,
+        request: ExecutionRequest,
+    ) -> JoinHandle<Option<TxId>> {
+        self.in_flight_orders.lock().await.insert(
+            request.auction_id,
+            request.solution.orders().keys().copied(),
+        );
+        let self_ = self.clone();
+        self.settlement_executor.submit(request, |request| {
+            async move { self_.execute_settlement(request).await }
+                .instrument(tracing::Span::current())
+        })
+    }
+
+    /// Settles the solution and records the outcome.
+    async fn execute_settlement(&self, request: ExecutionRequest) -> Option<TxId> {
+        let ExecutionRequest {
+            auction_id,
+            driver,
+            solution,
+            solution_uid,
+            block_deadline,
+            single_run_start,
+            gas,
+            handoff,
+        } = request;
+        let solved_order_uids: HashSet<_> = solution.orders().keys().cloned().collect();
+        let solution_id = solution.id();
+        let solver = solution.solver();
+        let token_pairs = token_pair_labels(&solution, &self.config.tracked_token_pairs);
+
+        tracing::info!(driver = %driver.name, solution = %solution_id, "settling");
+        let submission_start = Instant::now();
+
+        let tx_hash = match self
+            .settle(
+                &driver,
+                solved_order_uids.clone(),
+                solver,
+                auction_id,
+                solution_id,
+                solution.score(),
+                solution_uid,
+                block_deadline,
+                gas,
+                handoff,
+            )
+            .await
+        {
+            Ok(tx_hash) => {
+                Metrics::settle_ok(
+                    &driver,
+                    solved_order_uids.len(),
+                    submission_start.elapsed(),
+                    &token_pairs,
+                );
+                tracing::debug!(%tx_hash, driver = %driver.name, ?solver, "solution settled");
+                self.record_settlement_latencies(auction_id, tx_hash, &solved_order_uids)
+                    .await;
+                self.verify_settlement(&driver, solution, auction_id, solution_uid, tx_hash);
+                Some(tx_hash)
+            }
+            Err(err) => {
+                Metrics::settle_err(&driver, submission_start.elapsed(), &err, &token_pairs);
+                match SETTLE_ERRORS.record((driver.name.clone(), err.label())) {
+                    Occurrence::First => {
+                        tracing::warn!(?err, driver = %driver.name, "settlement failed")
+                    }
+                    Occurrence::Repeated(occurrences) => tracing::debug!(
+                        ?err,
+                        driver = %driver.name,
+                        occurrences,
+                        "settlement failed"
+                    ),
+                }
+                None
+            }
+        };
+        Metrics::single_run_completed(single_run_start.elapsed());
+        tx_hash
+    }
+
+    /// Settlements of winning solutions that are currently being executed.
+    pub fn active_executions(&self) -> Vec<ActiveExecution> {
+        self.settlement_executor.active_executions()
+    }
+
+    /// Aborts the settlements of the auction that are still being executed
+    /// and releases their orders. Returns how many settlements got cancelled.
+    pub async fn cancel_settlements(&self, auction_id: Id) -> usize {
+        let cancelled = self.settlement_executor.cancel(auction_id);
+        let released = self.in_flight_orders.lock().await.cancel(auction_id);
+        if cancelled > 0 || !released.is_empty() {
+            tracing::info!(auction_id, cancelled, ?released, "cancelled settlements");
+        }
+        cancelled
+    }
+
+    /// Cancels all settlements that are still being executed so that no
+    /// outcome gets recorded for settlements the process can no longer follow.
+    pub async fn shutdown(&self) {
+        let auctions: HashSet<_> = self
+            .active_executions()
+            .into_iter()
+            .map(|execution| execution.auction_id)
+            .collect();
+        tracing::info!(?auctions, "shutting down, cancelling active settlements");
+        for auction_id in auctions {
+            self.cancel_settlements(auction_id).await;
+        }
+    }
+
+    /// Stores how long the settled orders took from their first appearance
+    /// in an auction until the settlement got observed.
+    async fn record_settlement_latencies(
+        &self,
+        auction_id: Id,
+        tx_hash: TxId,
+        order_uids: &HashSet<domain::OrderUid>,
+    ) {
+        match self
+            .persistence
+            .store_settlement_latencies(auction_id, tx_hash, order_uids, chrono::Utc::now())
+            .await
+        {
+            Ok(latencies) => {
+                for (_, latency) in latencies {
+                    Metrics::auction_to_settlement_latency(latency);
+                }
+            }
+            Err(err) => tracing::warn!(?err, "failed to store settlement latencies"),
+        }
     }
 
     #[instrument(skip_all)]
+    #[allow(clippy::too_many_arguments)]
     async fn post_processing(
         &self,
         auction: &domain::Auction,
         competition_simulation_block: u64,
         ranking: &Ranking,
+        reference_scores: &HashMap<eth::Address, competition::Score>,
+        trusted_tokens: &HashSet<H160>,
         block_deadline: u64,
-        winner_selection: Box<dyn winner_selection::Arbitrator>,
         is_single_winner_selection: bool,
-    ) -> Result<()> {
+    ) -> Result<bool> {
         let start = Instant::now();
-        let reference_scores = winner_selection.compute_reference_scores(ranking);
         // TODO: Needs to be removed once other teams fully migrated to the
         // reference_scores table
-        let legacy_score = {
-            let Some(winning_solution) = ranking
-                .winners()
-                .nth(0)
-                .map(|participant| participant.solution())
-            else {
-                return Err(anyhow::anyhow!("no winners found"));
-            };
-            let winner = winning_solution.solver().into();
-            let winning_score = winning_solution.score().get().0;
-            let reference_score = ranking
-                .ranked()
-                .nth(1)
-                .map(|participant| participant.solution().score().get().0)
-                .unwrap_or_default();
-            is_single_winner_selection.then_some(LegacyScore {
-                winner,
-                winning_score,
-                reference_score,
-            })
+        // Auctions without winners have no legacy score.
+        let legacy_score = ranking
+            .winners()
+            .nth(0)
+            .map(|participant| participant.solution())
+            .and_then(|winning_solution| {
+                let winner = winning_solution.solver().into();
+                let winning_score = winning_solution.score().get().0;
+                let reference_score = ranking
+                    .ranked()
+                    .nth(1)
+                    .map(|participant| participant.solution().score().get().0)
+                    .unwrap_or_default();
+                is_single_winner_selection.then_some(LegacyScore {
+                    winner,
+                    winning_score,
+                    reference_score,
+                })
+            });
+
+        let price_spreads = competition::price_spread::price_spreads(
+            ranking
+                .all()
+                .filter(|participant| !participant.driver().observe_only)
+                .map(|participant| participant.solution()),
+            &auction.prices,
+        );
+        observe::price_spreads(auction.id, &price_spreads);
+        Metrics::price_spreads(&price_spreads);
+
+        let duplicate_executions =
+            competition::duplicate_execution::duplicate_executions(ranking.all());
+        observe::duplicate_executions(auction.id, &duplicate_executions);
+        Metrics::duplicate_executions(&duplicate_executions);
+
+        // only a coalition of several winners can be audited
+        let coalition_fairness_violations = if is_single_winner_selection {
+            Vec::new()
+        } else {
+            winner_selection::coalition_fairness::audit(
+                ranking,
+                self.config
+                    .treat_native_as_weth
+                    .then(|| self.eth.contracts().wrapped_native_token()),
+            )
         };
+        observe::coalition_fairness_violations(auction.id, &coalition_fairness_violations);
+        Metrics::coalition_fairness_violations(&coalition_fairness_violations);
+
+        let price_improvements = competition::price_improvement::price_improvements(
+            ranking.winners().map(|participant| participant.solution()),
+            &auction.prices,
+        );
+
+        for winner in ranking.winners() {
+            if let Some(savings) = winner.solution().batching_gas_savings() {
+                Metrics::batch_gas_savings(&winner.driver().name, savings);
+            }
+        }
 
         let participants = ranking
             .all()
-            .map(|participant| participant.solution().solver().into())
+            .map(|participant| participant.solver_address().into())
             .collect::<HashSet<_>>();
         let mut fee_policies = Vec::new();
         for order_id in ranking
@@ -470,59 +1621,48 @@ impl RunLoop {
                 }
             }
         }
+        if let Some(max_bps) = self.config.max_protocol_fee_bps {
+            let policies: HashMap<_, _> = fee_policies
+                .iter()
+                .map(|(uid, policies)| (*uid, policies))
+                .collect();
+            for participant in ranking.winners() {
+                for order in competition::protocol_fee_cap::orders_exceeding_cap(
+                    participant.solution(),
+                    &policies,
+                    max_bps,
+                ) {
+                    Metrics::protocol_fee_exceeded(&participant.driver().name, &order);
+                }
+            }
+        }
 
-        let mut solutions: Vec<_> = ranking
-            .enumerated()
-            .map(|(index, participant)| SolverSettlement {
-                solver: participant.driver().name.clone(),
-                solver_address: participant.solution().solver().0,
-                score: Some(Score::Solver(participant.solution().score().get().0)),
-                ranking: index + 1,
-                orders: participant
-                    .solution()
-                    .orders()
-                    .iter()
-                    .map(|(id, order)| Order::Colocated {
-                        id: (*id).into(),
-                        sell_amount: order.executed_sell.into(),
-                        buy_amount: order.executed_buy.into(),
-                    })
-                    .collect(),
-                clearing_prices: participant
-                    .solution()
-                    .prices()
-                    .iter()
-                    .map(|(token, price)| (token.0, price.get().into()))
-                    .collect(),
-                is_winner: participant.is_winner(),
-                filtered_out: participant.filtered_out(),
-            })
+        // solutions of observe-only drivers only get stored for comparison and
+        // are not part of the public competition data
+        let solutions = dto::competition::solutions_from_domain(
+            ranking
+                .enumerated()
+                .filter(|(_, participant)| !participant.driver().observe_only),
+        );
+
+        let trusted_tokens: Vec<_> = trusted_tokens
+            .iter()
+            .map(|token| eth::TokenAddress(*token))
             .collect();
-        // reverse as solver competition table is sorted from worst to best,
-        // so we need to keep the ordering for backwards compatibility
-        solutions.reverse();
+        let trusted_tokens_hash = domain::auction::TrustedTokensHash::new(&trusted_tokens);
 
         let competition_table = SolverCompetitionDB {
             auction_start_block: auction.block,
             competition_simulation_block,
-            auction: CompetitionAuction {
-                orders: auction
-                    .orders
-                    .iter()
-                    .map(|order| order.uid.into())
-                    .collect(),
-                prices: auction
-                    .prices
-                    .iter()
-                    .map(|(key, value)| ((*key).into(), value.get().into()))
-                    .collect(),
-            },
+            auction: dto::competition::auction_from_domain(auction),
             solutions,
+            quote_only: auction.quote_only,
+            trusted_tokens_hash: Some(eth::H256(trusted_tokens_hash.0)),
         };
         let competition = Competition {
             auction_id: auction.id,
             legacy: legacy_score,
-            reference_scores,
+            reference_scores: reference_scores.clone(),
             participants,
             prices: auction
                 .prices
@@ -547,6 +1687,9 @@ impl RunLoop {
         self.persistence
             .save_competition(&mut tx, &competition)
             .await?;
+        self.persistence
+            .clear_competition_wip(&mut tx, auction.id)
+            .await?;
         self.persistence
             .save_surplus_capturing_jit_order_owners(
                 &mut tx,
@@ -555,12 +1698,62 @@ impl RunLoop {
             )
             .await?;
 
+        let expected = ExpectedAuctionData::new(auction, &fee_policies);
         self.persistence
-            .store_fee_policies(&mut tx, auction.id, fee_policies)
+            .store_fee_policies(&mut tx, auction.id, fee_policies.clone())
+            .await?;
+
+        self.persistence
+            .store_trusted_tokens(&mut tx, auction.id, trusted_tokens_hash, &trusted_tokens)
             .await?;
 
         tx.commit().await?;
 
+        // The violations are only used for monitoring so failing to store them
+        // doesn't fail the post-processing.
+        if let Err(err) = self
+            .persistence
+            .save_coalition_fairness_violations(auction.id, &coalition_fairness_violations)
+            .await
+        {
+            match POST_PROCESSING_ERRORS.record("store_coalition_fairness_violations") {
+                Occurrence::First => {
+                    tracing::warn!(?err, "failed to store coalition fairness violations")
+                }
+                Occurrence::Repeated(occurrences) => {
+                    tracing::debug!(
+                        ?err,
+                        occurrences,
+                        "failed to store coalition fairness violations"
+                    )
+                }
+            }
+        }
+
+        // Price improvements are only used for analytics.
+        if let Err(err) = self
+            .persistence
+            .store_order_price_improvements(auction.id, &price_improvements)
+            .await
+        {
+            match POST_PROCESSING_ERRORS.record("store_order_price_improvements") {
+                Occurrence::First => {
+                    tracing::warn!(?err, "failed to store order price improvements")
+                }
+                Occurrence::Repeated(occurrences) => {
+                    tracing::debug!(
+                        ?err,
+                        occurrences,
+                        "failed to store order price improvements"
+                    )
+                }
+            }
+        }
+
+        let complete = self
+            .ensure_auction_completeness(auction, &expected, &fee_policies)
+            .await;
+
         if let Err(err) = self.competition_updates_sender.send(()) {
             tracing::error!(?err, "failed to notify solver participation guard");
         }
@@ -568,169 +1761,237 @@ impl RunLoop {
         tracing::trace!(auction_id = ?auction.id, "auction results saved atomically");
 
         Metrics::post_processed(start.elapsed());
-        Ok(())
+        Ok(complete)
     }
 
-    /// Runs the solver competition, making all configured drivers participate.
-    /// Returns all fair solutions sorted by their score (best to worst).
-    #[instrument(skip_all)]
-    async fn fetch_solutions(
+    /// Verifies that all data required to compute solver rewards got stored.
+    /// Missing or differing data gets written once more and verified again.
+    /// If it's still incomplete the incident gets reported loudly since solver
+    /// rewards can't be computed otherwise. Returns whether all data is known
+    /// to be stored.
+    async fn ensure_auction_completeness(
         &self,
         auction: &domain::Auction,
-    ) -> Vec<competition::Participant<Unranked>> {
-        let request = solve::Request::new(
-            auction,
-            &self.trusted_tokens.all(),
-            self.config.solve_deadline,
-        );
-
-        let mut solutions = futures::future::join_all(
-            self.drivers
-                .iter()
-                .map(|driver| self.solve(driver.clone(), request.clone())),
-        )
-        .await
-        .into_iter()
-        .flatten()
-        .collect::<Vec<_>>();
-
-        let mut counter = HashMap::new();
-        solutions.retain(|participant| {
-            let submission_address = participant.driver().submission_address;
-            let is_solution_from_driver = participant.solution().solver() == submission_address;
-
-            // Filter out solutions that don't come from their corresponding submission
-            // address
-            if !is_solution_from_driver {
+        expected: &ExpectedAuctionData,
+        fee_policies: &[(domain::OrderUid, Vec<domain::fee::Policy>)],
+    ) -> bool {
+        let missing = match self
+            .persistence
+            .verify_auction_completeness(auction.id, expected)
+            .await
+        {
+            Ok(missing) => missing,
+            Err(err) => {
                 tracing::warn!(
-                    driver = participant.driver().name,
-                    ?submission_address,
-                    "the solution received is not from the driver submission address"
+                    ?err,
+                    auction_id = auction.id,
+                    "failed to verify auction data"
                 );
                 return false;
             }
+        };
+        if missing.is_empty() {
+            return true;
+        }
 
-            // limit number of solutions per solver
-            let driver = participant.driver().name.clone();
-            let count = counter.entry(driver).or_insert(0);
-            *count += 1;
-            *count <= self.config.max_solutions_per_solver.get()
-        });
-
-        // Shuffle so that sorting randomly splits ties.
-        solutions.shuffle(&mut rand::thread_rng());
-        solutions
-    }
-
-    /// Sends a `/solve` request to the driver and manages all error cases and
-    /// records metrics and logs appropriately.
-    #[instrument(skip_all, fields(driver = driver.name))]
-    async fn solve(
-        &self,
-        driver: Arc<infra::Driver>,
-        request: solve::Request,
-    ) -> Vec<competition::Participant<Unranked>> {
-        let start = Instant::now();
-        let result = self.try_solve(Arc::clone(&driver), request).await;
-        let solutions = match result {
-            Ok(solutions) => {
-                Metrics::solve_ok(&driver, start.elapsed());
-                solutions
+        for kind in &missing {
+            tracing::warn!(
+                ?kind,
+                auction_id = auction.id,
+                "auction data incomplete; retrying"
+            );
+            if let Err(err) = self
+                .persistence
+                .repair_auction_data(auction, *kind, fee_policies)
+                .await
+            {
+                tracing::warn!(
+                    ?err,
+                    ?kind,
+                    auction_id = auction.id,
+                    "failed to repair auction data"
+                );
             }
+        }
+
+        // the retried writes get verified as well since they might have
+        // succeeded without completing the data
+        let missing = match self
+            .persistence
+            .verify_auction_completeness(auction.id, expected)
+            .await
+        {
+            Ok(still_missing) => still_missing,
             Err(err) => {
-                Metrics::solve_err(&driver, start.elapsed(), &err);
-                tracing::debug!(?err, driver = %driver.name, "solver didn't provide solutions");
-                vec![]
+                tracing::warn!(
+                    ?err,
+                    auction_id = auction.id,
+                    "failed to verify repaired auction data"
+                );
+                missing
             }
         };
+        for kind in &missing {
+            tracing::error!(
+                ?kind,
+                auction_id = auction.id,
+                "auction data is incomplete; solver rewards can't be computed"
+            );
+            Metrics::incomplete_auction_data(*kind);
+        }
+        missing.is_empty()
+    }
 
-        solutions
-            .into_iter()
-            .filter_map(|solution| match solution {
-                Ok(solution) => {
-                    Metrics::solution_ok(&driver);
-                    Some(competition::Participant::new(solution, driver.clone()))
-                }
-                Err(err) => {
-                    Metrics::solution_err(&driver, &err);
-                    tracing::debug!(?err, driver = %driver.name, "invalid proposed solution");
-                    None
-                }
-            })
-            .collect()
+    /// Counts the consecutive auctions in which all drivers were deny listed or
+    /// unreachable. In strict mode the autopilot reports itself as unhealthy
+    /// once there were too many of them in a row, so misconfigured deployments
+    /// don't silently run empty competitions.
+    fn track_driver_availability(&self, participation: &ParticipationMatrix) {
+        track_driver_availability(
+            &self.diagnostics,
+            &self.liveness,
+            self.config.max_auctions_without_drivers,
+            participation,
+        );
     }
 
-    /// Sends `/solve` request to the driver and forwards errors to the caller.
-    async fn try_solve(
+    /// Informs every participating driver about its rank, whether it won and
+    /// its reference score. Failures are not fatal and only get metered and
+    /// persisted.
+    fn notify_outcomes(
         &self,
-        driver: Arc<infra::Driver>,
-        request: solve::Request,
-    ) -> Result<Vec<Result<competition::Solution, domain::competition::SolutionError>>, SolveError>
-    {
-        let (can_participate, response) = {
-            let driver = driver.clone();
-            let guard = self.solver_participation_guard.clone();
-            let mut handle = tokio::task::spawn(async move {
-                let fetch_response = driver.solve(request);
-                let check_allowed = guard.can_participate(&driver.submission_address);
-                tokio::join!(check_allowed, fetch_response)
-            });
-            tokio::time::timeout(self.config.solve_deadline, &mut handle)
-                .await
-                .map_err(|_| {
-                    // Abort the background task to prevent memory leaks
-                    handle.abort();
-                    SolveError::Timeout
-                })?
-                .context("could not finish the task")
-                .map_err(SolveError::Failure)?
-        };
-
-        let response = match (can_participate, response) {
-            (Ok(true), Ok(response)) => response,
-            (Ok(false), _) => return Err(SolveError::SolverDenyListed),
-            (Err(err), _) => {
-                tracing::error!(
-                    ?err,
-                    driver = %driver.name,
-                    ?driver.submission_address,
-                    "solver participation check failed"
-                );
-                return Err(SolveError::SolverDenyListed);
+        auction_id: Id,
+        ranking: &Ranking,
+        reference_scores: &HashMap<eth::Address, competition::Score>,
+    ) {
+        let mut notified = HashSet::new();
+        // Participants are sorted from best to worst so the first solution
+        // of every driver determines its outcome.
+        for (index, participant) in ranking.enumerated() {
+            let driver = participant.driver();
+            if !notified.insert(driver.name.clone()) {
+                continue;
             }
-            (_, Err(err)) => return Err(SolveError::Failure(err)),
-        };
-
-        if response.solutions.is_empty() {
-            return Err(SolveError::NoSolutions);
+            let outcome = competition::CompetitionOutcome {
+                auction_id,
+                rank: index + 1,
+                is_winner: participant.is_winner(),
+                reference_score: reference_scores.get(&participant.solver_address()).copied(),
+            };
+            let driver = driver.clone();
+            let persistence = self.persistence.clone();
+            tokio::spawn(
+                async move {
+                    let timestamp = chrono::Utc::now();
+                    let result = driver.notify_outcome(outcome).await;
+                    infra::record_notification(
+                        &persistence,
+                        &driver,
+                        competition::notification::Kind::CompetitionOutcome,
+                        timestamp,
+                        &result,
+                    )
+                    .await;
+                    if let Err(err) = &result {
+                        tracing::debug!(
+                            ?err,
+                            driver = %driver.name,
+                            "failed to notify driver about competition outcome"
+                        );
+                    }
+                    Metrics::notify_outcome(&driver, result.is_ok());
+                }
+                .instrument(tracing::Span::current()),
+            );
         }
-        Ok(response.into_domain())
     }
 
-    /// Execute the solver's solution. Returns Ok when the corresponding
-    /// transaction has been mined.
-    #[allow(clippy::too_many_arguments)]
+    /// Fetches the settlement contract's balances of all tokens in the auction.
+    /// Buffers are only informational for the drivers so failing to fetch them
+    /// doesn't prevent the auction from being solved.
+    async fn settlement_buffers(
+        &self,
+        auction: &domain::Auction,
+    ) -> Option<HashMap<eth::TokenAddress, U256>> {
+        let tokens = auction.prices.keys().copied().chain(
+            auction
+                .orders
+                .iter()
+                .flat_map(|order| [order.sell.token, order.buy.token]),
+        );
+        match self.eth.settlement_buffers(tokens).await {
+            Ok(buffers) => Some(buffers),
+            Err(err) => {
+                tracing::warn!(?err, "failed to fetch settlement buffers");
+                None
+            }
+        }
+    }
+
+    /// Runs the solver competition, making all configured drivers participate.
+    /// Returns all fair solutions sorted by their score (best to worst) and
+    /// how each driver took part.
+    #[instrument(skip_all)]
+    async fn fetch_solutions(
+        &self,
+        auction: &domain::Auction,
+        trusted_tokens: &HashSet<H160>,
+        kind: AuctionKind,
+    ) -> (Vec<competition::Participant<Unranked>>, ParticipationMatrix) {
+        let buffers = self.settlement_buffers(auction).await;
+        collect_solutions(
+            &self.config,
+            &self.drivers,
+            &self.solver_participation_guard,
+            auction,
+            trusted_tokens,
+            buffers.as_ref(),
+            self.eth.contracts().wrapped_native_token(),
+            &self.rng,
+            kind,
+        )
+        .await
+    }
+
+    /// Execute the solver's solution. Returns Ok when the corresponding
+    /// transaction has been mined.
+    #[allow(clippy::too_many_arguments)]
     async fn settle(
         &self,
-        driver: &infra::Driver,
+        driver: &Arc<infra::Driver>,
         solved_order_uids: HashSet<OrderUid>,
         solver: eth::Address,
         auction_id: i64,
         solution_id: u64,
+        score: competition::Score,
         solution_uid: usize,
         submission_deadline_latest_block: u64,
+        gas: eth::Gas,
+        handoff: Option<Handoff>,
     ) -> Result<TxId, SettleError> {
         let settle = async move {
             let current_block = self.eth.current_block().borrow().number;
-            anyhow::ensure!(
-                current_block < submission_deadline_latest_block,
-                "submission deadline was missed"
-            );
+            if current_block >= submission_deadline_latest_block {
+                return Err(SettleError::Other(anyhow::anyhow!(
+                    "submission deadline was missed"
+                )));
+            }
+            // the budget gets checked again with the estimated gas since
+            // solutions without reported gas only got checked with the fallback
+            if exceeds_gas_budget(driver, gas) {
+                Metrics::solution_filtered(driver, "driver_gas_budget", AuctionKind::Regular);
+                return Err(SettleError::Other(anyhow::anyhow!(
+                    "settlement gas {gas:?} exceeds the driver's budget"
+                )));
+            }
 
             let request = settle::Request {
                 solution_id,
                 submission_deadline_latest_block,
                 auction_id,
+                rebroadcast: false,
+                calldata: None,
+                score: score.get().0,
             };
 
             self.store_execution_started(
@@ -743,34 +2004,343 @@ impl RunLoop {
             driver
                 .settle(&request, self.config.max_settlement_transaction_wait)
                 .await
+                .map_err(SettleError::Http)
         }
         .boxed();
 
+        let rebroadcasted = AtomicBool::new(false);
+        let handed_off = OnceLock::new();
         let wait_for_settlement_transaction = self
-            .wait_for_settlement_transaction(auction_id, solver, submission_deadline_latest_block)
+            .wait_for_settlement_transaction(
+                driver,
+                auction_id,
+                solution_id,
+                score,
+                solver,
+                submission_deadline_latest_block,
+                &rebroadcasted,
+                &handed_off,
+            )
             .boxed();
 
         // Wait for either the settlement transaction to be mined or the driver returned
         // a result.
-        let result = match futures::future::select(wait_for_settlement_transaction, settle).await {
-            futures::future::Either::Left((res, _)) => res,
-            futures::future::Either::Right((driver_result, wait_for_settlement_transaction)) => {
-                match driver_result {
-                    Ok(_) => wait_for_settlement_transaction.await,
-                    Err(err) => Err(SettleError::Other(err)),
+        let (result, submitter) = settlement_executor::drive(
+            settle,
+            wait_for_settlement_transaction,
+            |err, wait_for_settlement_transaction| {
+                // Only timeouts get handed off since the winning driver might
+                // simply be unable to land the transaction in time.
+                let handoff = match handoff {
+                    Some(handoff) if matches!(err, SettleError::Http(HttpError::Timeout)) => {
+                        handoff
+                    }
+                    _ => return Err(err),
+                };
+                let request = settle::Request {
+                    solution_id,
+                    submission_deadline_latest_block,
+                    auction_id,
+                    rebroadcast: false,
+                    calldata: Some(handoff.calldata),
+                    score: score.get().0,
+                };
+                let handed_off = &handed_off;
+                Ok(async move {
+                    self.settle_via_fallback(
+                        &handoff.driver,
+                        request,
+                        handed_off,
+                        wait_for_settlement_transaction,
+                    )
+                    .await
+                })
+            },
+        )
+        .await;
+
+        if rebroadcasted.load(Ordering::Relaxed) {
+            Metrics::settle_rebroadcast_outcome(driver, &result);
+        }
+        Metrics::settle_submitter(driver, submitter, &result);
+        self.store_execution_ended(
+            solver,
+            auction_id,
+            solution_uid,
+            &solved_order_uids,
+            &result,
+        );
+
+        match &result {
+            Ok(transaction) if self.config.in_flight_reorg_protection_blocks > 0 => {
+                self.protect_from_reorgs(
+                    driver,
+                    handed_off.get(),
+                    solver,
+                    auction_id,
+                    *transaction,
+                    solved_order_uids,
+                );
+            }
+            // Clean up the in-flight orders regardless the result.
+            _ => {
+                self.in_flight_orders
+                    .lock()
+                    .await
+                    .finish(auction_id, &solved_order_uids);
+            }
+        }
+
+        result
+    }
+
+    /// Keeps the orders of a successful settlement in flight until the
+    /// settlement transaction is buried under
+    /// [`Config::in_flight_reorg_protection_blocks`] blocks. If the
+    /// transaction disappears from the chain in the meantime the orders stay
+    /// protected until it is confirmed again (and buried deep enough) or it
+    /// stayed gone for as many blocks. Otherwise the orders could get settled
+    /// a second time if the reorged transaction gets mined again.
+    fn protect_from_reorgs(
+        &self,
+        driver: &Arc<infra::Driver>,
+        fallback: Option<&Arc<infra::Driver>>,
+        solver: eth::Address,
+        auction_id: i64,
+        transaction: TxId,
+        orders: HashSet<OrderUid>,
+    ) {
+        let depth = self.config.in_flight_reorg_protection_blocks;
+        let current_block = self.eth.current_block().clone();
+        let persistence = self.persistence.clone();
+        let in_flight_orders = self.in_flight_orders.clone();
+        let driver = driver.clone();
+        let solvers = settlement_addresses(&driver, solver, fallback.map(Arc::as_ref));
+
+        let protection = async move {
+            let mut protection = ReorgProtection::new(current_block.borrow().number, depth);
+            loop {
+                let block = ethrpc::block_stream::next_block(&current_block).await;
+                let on_chain = match persistence
+                    .find_settlement_transaction(auction_id, &solvers)
+                    .await
+                {
+                    Ok(transaction) => transaction.is_some(),
+                    Err(err) => {
+                        tracing::warn!(?err, "failed to check settlement for reorgs");
+                        continue;
+                    }
+                };
+                match protection.observe(block.number, on_chain) {
+                    ReorgEvent::None => {}
+                    ReorgEvent::Reorged => {
+                        tracing::warn!(
+                            ?transaction,
+                            driver = %driver.name,
+                            "settlement got reorged, keeping its orders in flight"
+                        );
+                        Metrics::settlement_reorged(&driver);
+                    }
+                    ReorgEvent::Reconfirmed => {
+                        tracing::info!(?transaction, "reorged settlement got confirmed again");
+                    }
+                    ReorgEvent::Released => break,
                 }
             }
-        };
+            in_flight_orders.lock().await.finish(auction_id, &orders);
+        }
+        .instrument(tracing::info_span!("reorg_protection", auction_id));
+        tokio::spawn(protection);
+    }
 
-        self.store_execution_ended(solver, auction_id, solution_uid, &result);
+    /// Compares the mined settlement transaction with the solution that won
+    /// the competition and records whether the settlement deviated from it.
+    /// Runs in the background since it doesn't affect the settlement anymore.
+    fn verify_settlement(
+        &self,
+        driver: &infra::Driver,
+        solution: Solution,
+        auction_id: i64,
+        solution_uid: usize,
+        tx: TxId,
+    ) {
+        let eth = self.eth.clone();
+        let persistence = self.persistence.clone();
+        let driver = driver.name.clone();
+        let verification = async move {
+            let transaction = match eth.transaction(tx).await {
+                Ok(transaction) => transaction,
+                Err(err) => {
+                    tracing::warn!(?err, %tx, "failed to fetch settlement transaction");
+                    return;
+                }
+            };
+            let decoded = match settlement::Decoded::from_transaction(
+                &transaction,
+                eth.contracts().settlement_domain_separator(),
+                eth.contracts().settlement().address().into(),
+            ) {
+                Ok(decoded) => decoded,
+                Err(err) => {
+                    tracing::warn!(?err, %tx, "failed to decode settlement transaction");
+                    return;
+                }
+            };
 
-        // Clean up the in-flight orders regardless the result.
-        self.in_flight_orders
-            .lock()
-            .await
-            .retain(|order| !solved_order_uids.contains(order));
+            let deviation = settlement::Deviation::new(&solution, &decoded);
+            if !deviation.is_empty() {
+                tracing::warn!(
+                    %driver,
+                    %tx,
+                    ?deviation,
+                    "settlement deviates from the winning solution"
+                );
+                Metrics::settlement_deviation(&driver, &deviation);
+            }
+            if let Err(err) = persistence
+                .store_settlement_deviation(
+                    auction_id,
+                    solution.solver(),
+                    solution_uid,
+                    !deviation.is_empty(),
+                )
+                .await
+            {
+                tracing::warn!(?err, "failed to store settlement deviation");
+            }
+        }
+        .instrument(tracing::Span::current());
+        tokio::spawn(verification);
+    }
 
-        result
+    /// Returns the driver that may take over the submission of the auction's
+    /// winning solutions. It doesn't take over anything if it won the auction
+    /// itself since it is busy submitting its own solutions then.
+    fn fallback_submitter(&self, ranking: &Ranking) -> Option<Arc<infra::Driver>> {
+        let name = self.config.fallback_submitter.as_ref()?;
+        if ranking
+            .winners()
+            .any(|winner| &winner.driver().name == name)
+        {
+            return None;
+        }
+        self.drivers
+            .iter()
+            .find(|driver| &driver.name == name)
+            .cloned()
+    }
+
+    /// Reveals the calldata of a winning solution so that the `fallback`
+    /// submitter can submit it if the winning driver fails to. Bounded by
+    /// [`Config::gas_estimation_timeout`] to not delay the settlement for long.
+    async fn handoff(
+        &self,
+        auction_id: Id,
+        driver: &infra::Driver,
+        solution: &Solution,
+        fallback: Option<&Arc<infra::Driver>>,
+    ) -> Option<Handoff> {
+        let fallback = fallback?;
+        let reveal = driver.reveal(reveal::Request {
+            solution_id: solution.id(),
+            auction_id,
+        });
+        match tokio::time::timeout(self.config.gas_estimation_timeout, reveal).await {
+            Ok(Ok(revealed)) => Some(Handoff {
+                driver: fallback.clone(),
+                calldata: revealed.calldata.uninternalized,
+            }),
+            Ok(Err(err)) => {
+                tracing::debug!(
+                    ?err,
+                    driver = %driver.name,
+                    "failed to reveal calldata for the fallback submitter"
+                );
+                None
+            }
+            Err(_) => {
+                tracing::debug!(
+                    driver = %driver.name,
+                    "revealing calldata for the fallback submitter timed out"
+                );
+                None
+            }
+        }
+    }
+
+    /// Hands the settlement of a winning solution to the fallback submitter
+    /// and waits until the settlement transaction got mined. The original
+    /// submission deadline still applies.
+    async fn settle_via_fallback(
+        &self,
+        fallback: &Arc<infra::Driver>,
+        request: settle::Request,
+        handed_off: &OnceLock<Arc<infra::Driver>>,
+        wait_for_settlement_transaction: BoxFuture<'_, Result<TxId, SettleError>>,
+    ) -> Result<TxId, SettleError> {
+        let current_block = self.eth.current_block().borrow().number;
+        if current_block >= request.submission_deadline_latest_block {
+            return Err(SettleError::Timeout);
+        }
+        // from now on the settlement may also get submitted from the fallback
+        // submitter's addresses
+        handed_off.get_or_init(|| fallback.clone());
+
+        tracing::info!(
+            fallback = %fallback.name,
+            solution = %request.solution_id,
+            "handing settlement to fallback submitter"
+        );
+        let handoff = async {
+            fallback
+                .settle(&request, self.config.max_settlement_transaction_wait)
+                .await
+                .map_err(SettleError::Http)
+        }
+        .boxed();
+
+        match futures::future::select(wait_for_settlement_transaction, handoff).await {
+            futures::future::Either::Left((res, _)) => res,
+            futures::future::Either::Right((Ok(_), wait_for_settlement_transaction)) => {
+                wait_for_settlement_transaction.await
+            }
+            futures::future::Either::Right((Err(err), _)) => Err(err),
+        }
+    }
+
+    /// Stores which solver is going to settle each winning order in the DB in a
+    /// background task to not block the runloop.
+    fn store_order_execution_statuses(
+        &self,
+        auction_id: Id,
+        ranking: &Ranking,
+        block_deadline: u64,
+    ) {
+        let statuses: Vec<_> = ranking
+            .enumerated()
+            .filter(|(_, participant)| participant.is_winner())
+            .flat_map(|(solution_uid, participant)| {
+                let solver = participant.solver_address();
+                participant
+                    .solution()
+                    .order_ids()
+                    .map(move |order_uid| OrderExecutionStatus {
+                        order_uid: *order_uid,
+                        auction_id,
+                        solver,
+                        solution_uid,
+                        deadline_block: block_deadline,
+                        tx: None,
+                    })
+            })
+            .collect();
+
+        let persistence = self.persistence.clone();
+        tokio::spawn(async move {
+            if let Err(err) = persistence.store_order_execution_statuses(statuses).await {
+                tracing::error!(?err, "failed to store order execution statuses");
+            }
+        });
     }
 
     /// Stores settlement execution started event in the DB in a background task
@@ -804,21 +2374,36 @@ impl RunLoop {
     }
 
     /// Stores settlement execution ended event in the DB in a background task
-    /// to not block the runloop.
+    /// to not block the runloop. If the settlement failed the solved orders get
+    /// an event so they don't appear to be executing until the next auction.
     fn store_execution_ended(
         &self,
         solver: eth::Address,
         auction_id: i64,
         solution_uid: usize,
+        solved_order_uids: &HashSet<OrderUid>,
         result: &Result<TxId, SettleError>,
     ) {
+        if let Err(err) = result {
+            self.persistence
+                .store_order_events(solved_order_uids.iter().copied(), err.order_event());
+        }
         let end_timestamp = chrono::Utc::now();
         let current_block = self.eth.current_block().borrow().number;
         let persistence = self.persistence.clone();
-        let outcome = match result {
-            Ok(_) => "success".to_string(),
-            Err(SettleError::Timeout) => "timeout".to_string(),
-            Err(SettleError::Other(err)) => format!("driver failed: {err}"),
+        let tx = result.as_ref().ok().copied();
+        let (outcome_kind, outcome) = match result {
+            Ok(_) => (ExecutionOutcomeKind::Success, "success".to_string()),
+            Err(SettleError::Timeout) => (ExecutionOutcomeKind::Timeout, "timeout".to_string()),
+            Err(SettleError::Reorged) => (ExecutionOutcomeKind::Reorged, "reorged".to_string()),
+            Err(SettleError::Other(err)) => (
+                ExecutionOutcomeKind::Failed,
+                format!("driver failed: {err}"),
+            ),
+            Err(SettleError::Http(err)) => (
+                ExecutionOutcomeKind::Failed,
+                format!("driver failed: {err}"),
+            ),
         };
 
         tokio::spawn(async move {
@@ -828,6 +2413,7 @@ impl RunLoop {
                 solution_uid,
                 end_timestamp,
                 end_block: current_block,
+                outcome_kind,
                 outcome,
             };
             if let Err(err) = persistence
@@ -836,6 +2422,16 @@ impl RunLoop {
             {
                 tracing::error!(?err, "failed to update settlement execution event");
             }
+
+            let Some(tx) = tx else {
+                return;
+            };
+            if let Err(err) = persistence
+                .store_order_execution_tx(auction_id, solver, solution_uid, tx)
+                .await
+            {
+                tracing::error!(?err, "failed to update order execution status");
+            }
         });
     }
 
@@ -845,11 +2441,17 @@ impl RunLoop {
     /// Returns None if no transaction was found within the deadline or the task
     /// is cancelled.
     #[instrument(skip_all)]
+    #[allow(clippy::too_many_arguments)]
     async fn wait_for_settlement_transaction(
         &self,
+        driver: &Arc<infra::Driver>,
         auction_id: i64,
+        solution_id: u64,
+        score: competition::Score,
         solver: eth::Address,
         submission_deadline_latest_block: u64,
+        rebroadcasted: &AtomicBool,
+        handed_off: &OnceLock<Arc<infra::Driver>>,
     ) -> Result<eth::TxId, SettleError> {
         let current = self.eth.current_block().borrow().number;
         tracing::debug!(%current, deadline=%submission_deadline_latest_block, %auction_id, "waiting for tag");
@@ -859,12 +2461,24 @@ impl RunLoop {
             // it's possible to find the tx in the DB in the next line.
             self.run_maintenance(&block).await;
 
+            let fallback = handed_off.get();
+            let solvers = settlement_addresses(driver, solver, fallback.map(Arc::as_ref));
             match self
                 .persistence
-                .find_settlement_transaction(auction_id, solver)
+                .find_settlement_transaction(auction_id, &solvers)
                 .await
             {
-                Ok(Some(transaction)) => return Ok(transaction),
+                Ok(Some(transaction)) => {
+                    return self
+                        .wait_for_confirmations(
+                            driver,
+                            auction_id,
+                            &solvers,
+                            transaction,
+                            block.number,
+                        )
+                        .await;
+                }
                 Ok(None) => {}
                 Err(err) => {
                     tracing::warn!(
@@ -878,10 +2492,107 @@ impl RunLoop {
             if block.number >= submission_deadline_latest_block {
                 break;
             }
+            // the fallback submitter can't be asked to rebroadcast
+            if fallback.is_some() {
+                continue;
+            }
+            self.maybe_rebroadcast(
+                driver,
+                settle::Request {
+                    solution_id,
+                    submission_deadline_latest_block,
+                    auction_id,
+                    rebroadcast: true,
+                    calldata: None,
+                    score: score.get().0,
+                },
+                block.number.saturating_sub(current),
+                submission_deadline_latest_block - block.number,
+                rebroadcasted,
+            );
         }
         Err(SettleError::Timeout)
     }
 
+    /// Waits until [`Config::settlement_confirmation_blocks`] blocks got built
+    /// on top of `found_in_block` and checks that the settlement transaction
+    /// is still part of the chain.
+    async fn wait_for_confirmations(
+        &self,
+        driver: &infra::Driver,
+        auction_id: i64,
+        solvers: &[eth::Address],
+        transaction: TxId,
+        found_in_block: u64,
+    ) -> Result<TxId, SettleError> {
+        let blocks = futures::stream::unfold((), move |()| async move {
+            let block = ethrpc::block_stream::next_block(self.eth.current_block()).await;
+            // the settlement events of reorged blocks get removed during maintenance
+            self.run_maintenance(&block).await;
+            Some((block.number, ()))
+        });
+        let result = confirm_settlement(
+            blocks,
+            transaction,
+            found_in_block,
+            self.config.settlement_confirmation_blocks,
+            self.persistence
+                .find_settlement_transaction(auction_id, solvers),
+        )
+        .await;
+        if let Err(SettleError::Reorged) = result {
+            tracing::warn!(
+                ?auction_id,
+                ?transaction,
+                driver = %driver.name,
+                "settlement transaction got reorged"
+            );
+            Metrics::settlement_reorged(driver);
+        }
+        result
+    }
+
+    /// Asks the driver to rebroadcast its settlement if it didn't show up on
+    /// chain for a while and the deadline is getting close. The request
+    /// reuses the original solution id and deadline so it can't result in a
+    /// second settlement. Happens at most once per settlement.
+    fn maybe_rebroadcast(
+        &self,
+        driver: &Arc<infra::Driver>,
+        request: settle::Request,
+        blocks_waited: u64,
+        blocks_left: u64,
+        rebroadcasted: &AtomicBool,
+    ) {
+        let Some(config) = self.config.settle_rebroadcast else {
+            return;
+        };
+        if blocks_waited < config.after_blocks || blocks_left >= config.max_remaining_blocks {
+            return;
+        }
+        if rebroadcasted.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        let driver = driver.clone();
+        let timeout = self.config.max_settlement_transaction_wait;
+        tokio::spawn(
+            async move {
+                tracing::info!(
+                    driver = %driver.name,
+                    solution = %request.solution_id,
+                    "requesting settlement rebroadcast"
+                );
+                let result = driver.settle(&request, timeout).await;
+                if let Err(err) = &result {
+                    tracing::debug!(?err, driver = %driver.name, "settlement rebroadcast failed");
+                }
+                Metrics::settle_rebroadcast(&driver, result.is_ok());
+            }
+            .instrument(tracing::Span::current()),
+        );
+    }
+
     /// Removes orders that are currently being settled to avoid solvers trying
     /// to fill an order a second time.
     async fn remove_in_flight_orders(
@@ -893,10 +2604,19 @@ impl RunLoop {
             return auction;
         };
 
-        auction.orders.retain(|o| !in_flight.contains(&o.uid));
+        // the orders are shared with the cached auction so build a new list
+        // instead of modifying them in place
+        auction.orders = Arc::new(
+            auction
+                .orders
+                .iter()
+                .filter(|o| !in_flight.contains(&o.uid))
+                .cloned()
+                .collect(),
+        );
         auction
             .surplus_capturing_jit_order_owners
-            .retain(|owner| !in_flight.iter().any(|i| i.owner() == *owner));
+            .retain(|owner| !in_flight.uids().any(|i| i.owner() == *owner));
         tracing::debug!(
             orders = ?in_flight,
             "filtered out in-flight orders and surplus_capturing_jit_order_owners"
@@ -904,262 +2624,3081 @@ impl RunLoop {
 
         auction
     }
-}
 
-#[derive(Debug, thiserror::Error)]
-enum SolveError {
-    #[error("the solver timed out")]
-    Timeout,
-    #[error("driver did not propose any solutions")]
-    NoSolutions,
-    #[error(transparent)]
-    Failure(anyhow::Error),
-    #[error("the solver got deny listed")]
-    SolverDenyListed,
+    /// Removes orders that will already be expired (or are about to expire)
+    /// by the time the settlement could be submitted at the latest.
+    fn remove_orders_near_expiry(
+        &self,
+        mut auction: domain::RawAuctionData,
+    ) -> domain::RawAuctionData {
+        let deadline_timestamp = expiry_deadline(
+            &self.config,
+            self.eth.current_block().borrow().timestamp,
+            self.eth.average_block_time(),
+        );
+        let uids = auction.remove_orders_expiring_before(deadline_timestamp);
+        if uids.is_empty() {
+            return auction;
+        }
+
+        tracing::debug!(
+            orders = ?uids,
+            deadline_timestamp,
+            "filtered out orders close to expiry"
+        );
+        Metrics::orders_dropped_near_expiry(uids.len());
+        self.persistence
+            .store_order_events(uids, OrderEventLabel::Filtered);
+        auction
+    }
 }
 
-#[derive(Debug, thiserror::Error)]
-enum SettleError {
-    #[error(transparent)]
-    Other(anyhow::Error),
-    #[error("settlement transaction await reached deadline")]
-    Timeout,
+/// Aborts `post_processing` if it takes longer than `timeout` so a hanging
+/// database connection can't block the run loop.
+async fn with_post_processing_timeout(
+    timeout: Duration,
+    post_processing: impl Future<Output = Result<bool>>,
+) -> Result<bool> {
+    tokio::time::timeout(timeout, post_processing)
+        .await
+        .unwrap_or_else(|_| {
+            // writes are best-effort, the transaction storing the competition
+            // data gets rolled back when it is dropped
+            tracing::error!(?timeout, "post-processing timed out");
+            Metrics::post_processing_timed_out();
+            Err(anyhow::anyhow!("post-processing timed out"))
+        })
 }
 
-#[derive(prometheus_metric_storage::MetricStorage)]
-#[metric(subsystem = "runloop")]
-struct Metrics {
-    /// Tracks the last executed auction.
-    auction: prometheus::IntGauge,
+/// Waits until `confirmations` of the block numbers in `blocks` were built on
+/// top of `found_in_block` and then checks with `find` that the settlement
+/// transaction is still part of the chain.
+async fn confirm_settlement<E: Into<anyhow::Error>>(
+    blocks: impl futures::Stream<Item = u64>,
+    transaction: TxId,
+    found_in_block: u64,
+    confirmations: u64,
+    find: impl Future<Output = Result<Option<TxId>, E>>,
+) -> Result<TxId, SettleError> {
+    let confirmed_block = found_in_block.saturating_add(confirmations);
+    if confirmed_block == found_in_block {
+        return Ok(transaction);
+    }
+    let mut blocks = std::pin::pin!(blocks);
+    loop {
+        match blocks.next().await {
+            Some(block) if block >= confirmed_block => break,
+            Some(_) => {}
+            None => {
+                return Err(SettleError::Other(anyhow::anyhow!(
+                    "block stream ended before the settlement got confirmed"
+                )));
+            }
+        }
+    }
 
-    /// Tracks the number of winners per auction.
-    #[metric(buckets(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10))]
-    auction_winners: prometheus::Histogram,
+    match find.await {
+        Ok(Some(transaction)) => Ok(transaction),
+        Ok(None) => Err(SettleError::Reorged),
+        Err(err) => Err(SettleError::Other(err.into())),
+    }
+}
 
-    /// Tracks the duration of successful driver `/solve` requests.
-    #[metric(
-        labels("driver", "result"),
-        buckets(
-            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20
-        )
-    )]
-    solve: prometheus::HistogramVec,
+/// Timestamp until which orders have to stay valid to be included in the
+/// auction. The timestamp of the submission deadline gets estimated from the
+/// current block's timestamp using the average block time.
+fn expiry_deadline(config: &Config, current_timestamp: u64, block_time: Duration) -> u64 {
+    let blocks = config
+        .submission_deadline
+        .saturating_add(config.min_order_validity_blocks);
+    let time_until_deadline =
+        block_time * u32::try_from(blocks).unwrap_or(u32::MAX) + config.order_expiry_margin;
+    current_timestamp.saturating_add(time_until_deadline.as_secs())
+}
 
-    /// Tracks driver solutions.
-    #[metric(labels("driver", "result"))]
-    solutions: prometheus::IntCounterVec,
+/// Returns the reported gas if there is one and otherwise awaits the
+/// estimate for at most `timeout`. Errors contain the reason for the metrics.
+async fn reported_or_estimated_gas(
+    reported: Option<eth::Gas>,
+    estimate: impl Future<Output = Result<eth::Gas, (&'static str, anyhow::Error)>>,
+    timeout: Duration,
+) -> Result<(eth::Gas, GasSource), (&'static str, anyhow::Error)> {
+    if let Some(gas) = reported {
+        return Ok((gas, GasSource::Reported));
+    }
+    match tokio::time::timeout(timeout, estimate).await {
+        Ok(estimate) => estimate.map(|gas| (gas, GasSource::Estimated)),
+        Err(_) => Err(("timeout", anyhow::anyhow!("gas estimation timed out"))),
+    }
+}
+
+/// The reward inputs of a winning solution before its settlement is known.
+fn reward_winner(
+    solution_uid: usize,
+    solution: &Solution,
+    reference_scores: &HashMap<eth::Address, competition::Score>,
+    settlement_skipped: bool,
+) -> reward_inputs::Winner {
+    reward_inputs::Winner {
+        solver: solution.solver(),
+        solution_uid,
+        score: *solution.score().get(),
+        reference_score: reference_scores
+            .get(&solution.solver())
+            .map(|score| *score.get()),
+        settlement: None,
+        settlement_skipped,
+    }
+}
+
+/// Counts the consecutive auctions without a responding driver in
+/// `diagnostics` and marks the drivers as unavailable in `liveness` once there
+/// were `max_auctions_without_drivers` of them.
+fn track_driver_availability(
+    diagnostics: &infra::status::RunLoopDiagnostics,
+    liveness: &Liveness,
+    max_auctions_without_drivers: Option<NonZeroUsize>,
+    participation: &ParticipationMatrix,
+) {
+    let count = if participation.any_responded() {
+        diagnostics
+            .auctions_without_drivers
+            .store(0, Ordering::Relaxed);
+        0
+    } else {
+        diagnostics
+            .auctions_without_drivers
+            .fetch_add(1, Ordering::Relaxed)
+            + 1
+    };
+    Metrics::auctions_without_drivers(count);
+    let Some(max) = max_auctions_without_drivers else {
+        return;
+    };
+    let unavailable = count >= max.get();
+    if count == max.get() {
+        tracing::error!(
+            auctions = count,
+            "no driver could participate in consecutive auctions"
+        );
+    }
+    Metrics::drivers_unavailable(unavailable);
+    liveness.drivers_unavailable(unavailable);
+}
+
+/// Defers the orders of the `blocked` solutions the `winners` don't settle so
+/// the next `auctions` auctions prioritize them.
+fn defer_blocked_orders<'a>(
+    deferred: &mut DeferredOrders,
+    winners: impl Iterator<Item = &'a competition::Participant<competition::Ranked>>,
+    blocked: impl Iterator<Item = &'a competition::Participant<competition::Ranked>>,
+    auctions: NonZeroUsize,
+) {
+    let settled: HashSet<_> = winners
+        .flat_map(|participant| participant.solution().order_ids())
+        .collect();
+    for participant in blocked {
+        Metrics::deferred_due_to_conflict(participant.driver());
+        deferred.defer(
+            participant
+                .solution()
+                .order_ids()
+                .filter(|uid| !settled.contains(uid))
+                .copied(),
+            auctions,
+        );
+    }
+}
+
+/// Metric labels of the directed token pairs the solution trades. Pairs that
+/// aren't tracked share the label `other` to bound the metric cardinality.
+fn token_pair_labels(
+    solution: &Solution,
+    tracked: &HashSet<(eth::TokenAddress, eth::TokenAddress)>,
+) -> HashSet<String> {
+    solution
+        .orders()
+        .values()
+        .map(|order| {
+            let pair = (order.sell.token, order.buy.token);
+            if tracked.contains(&pair) {
+                format!("{:?}/{:?}", pair.0.0, pair.1.0)
+            } else {
+                "other".to_string()
+            }
+        })
+        .collect()
+}
+
+/// Asks all drivers to solve the auction and returns the solutions that
+/// passed all sanity checks in random order together with how each driver
+/// took part.
+#[allow(clippy::too_many_arguments)]
+async fn collect_solutions(
+    config: &Config,
+    drivers: &[Arc<infra::Driver>],
+    guard: &SolverParticipationGuard,
+    auction: &domain::Auction,
+    trusted_tokens: &HashSet<H160>,
+    buffers: Option<&HashMap<eth::TokenAddress, U256>>,
+    weth: eth::WrappedNativeToken,
+    rng: &RngFactory,
+    kind: AuctionKind,
+) -> (Vec<competition::Participant<Unranked>>, ParticipationMatrix) {
+    let request = solve::Request::new(
+        auction,
+        trusted_tokens,
+        config.solve_deadline,
+        false,
+        buffers,
+        config.order_ordering,
+        Some(config.max_solutions_per_solver),
+    );
+
+    // Once enough drivers responded the remaining ones only get a short grace
+    // period instead of the full solve deadline to trim tail latency.
+    let (cutoff_sender, cutoff) = watch::channel(None);
+    let early_finish_after = config
+        .early_finish_fraction
+        .map(|fraction| (drivers.len() as f64 * fraction).ceil() as usize);
+    let responded = AtomicUsize::new(0);
+    let (cutoff_sender, responded) = (&cutoff_sender, &responded);
+
+    // Drivers with a limited capacity or token universe only get to see a
+    // subset of the orders. Winner selection still uses the full auction.
+    let responses = futures::future::join_all(drivers.iter().map(|driver| {
+        // Distant drivers get asked to respond a bit earlier so that their
+        // responses still arrive before the deadline.
+        let padding = driver.latency().min(config.max_solve_deadline_padding);
+        let time_limit = config.solve_deadline - padding;
+        let restricted = driver
+            .token_allow_list
+            .as_ref()
+            .map(|tokens| auction.restricted_to_tokens(tokens));
+        let driver_auction = restricted.as_ref().unwrap_or(auction);
+        let has_orders = restricted
+            .as_ref()
+            .is_none_or(|restricted| !restricted.orders.is_empty());
+        let response = has_orders.then(|| {
+            let request = match driver
+                .max_orders
+                .and_then(|max_orders| driver_auction.truncated(max_orders.get()))
+            {
+                Some(truncated) => {
+                    tracing::debug!(
+                        driver = %driver.name,
+                        orders = truncated.orders.len(),
+                        "sending truncated auction"
+                    );
+                    solve::Request::new(
+                        &truncated,
+                        trusted_tokens,
+                        time_limit,
+                        true,
+                        buffers,
+                        config.order_ordering,
+                        Some(config.max_solutions_per_solver),
+                    )
+                }
+                None if restricted.is_some() => {
+                    let filtered = driver_auction.orders.len() < auction.orders.len();
+                    if filtered {
+                        tracing::debug!(
+                            driver = %driver.name,
+                            orders = driver_auction.orders.len(),
+                            "sending auction filtered by token allow list"
+                        );
+                    }
+                    solve::Request::new(
+                        driver_auction,
+                        trusted_tokens,
+                        time_limit,
+                        filtered,
+                        buffers,
+                        config.order_ordering,
+                        Some(config.max_solutions_per_solver),
+                    )
+                }
+                None if padding.is_zero() => request.clone(),
+                None => request.with_time_limit(time_limit),
+            };
+            solve(
+                config,
+                guard,
+                driver.clone(),
+                request,
+                time_limit,
+                kind,
+                &auction.prices,
+                weth,
+                cutoff.clone(),
+            )
+        });
+        async move {
+            let response = match response {
+                Some(response) => response.await,
+                None => {
+                    // not having anything to solve doesn't count as not
+                    // finding solutions
+                    tracing::debug!(
+                        driver = %driver.name,
+                        "skipping driver without orders trading allowed tokens"
+                    );
+                    let participation = participation::Participation {
+                        driver: driver.name.clone(),
+                        submission_address: driver.submission_address,
+                        outcome: participation::Outcome::Skipped,
+                        solve_time: Duration::ZERO,
+                        accepted: 0,
+                        filtered: vec![],
+                    };
+                    (vec![], participation)
+                }
+            };
+            let responded = responded.fetch_add(1, Ordering::Relaxed) + 1;
+            if early_finish_after
+                .is_some_and(|threshold| responded == threshold && responded < drivers.len())
+            {
+                tracing::debug!(
+                    responded,
+                    grace = ?config.early_finish_grace,
+                    "enough drivers responded, starting grace period for the remaining ones"
+                );
+                cutoff_sender.send_replace(Some(
+                    tokio::time::Instant::now() + config.early_finish_grace,
+                ));
+            }
+            response
+        }
+    }))
+    .await;
+    let (solutions, participations): (Vec<_>, Vec<_>) = responses.into_iter().unzip();
+    let mut solutions = solutions.into_iter().flatten().collect::<Vec<_>>();
+
+    let mut counter = HashMap::new();
+    let mut filtered = HashMap::<String, Vec<participation::Filtered>>::new();
+    let mut filter = |participant: &competition::Participant<Unranked>, reason| {
+        filtered
+            .entry(participant.driver().name.clone())
+            .or_default()
+            .push(participation::Filtered {
+                solution_id: Some(participant.solution().id()),
+                reason,
+            });
+        false
+    };
+    solutions.retain(|participant| {
+        let solver_address = participant.solver_address();
+        let is_solution_from_driver = participant
+            .driver()
+            .allows_submission_address(solver_address, chrono::Utc::now());
+
+        // Filter out solutions that don't come from one of the currently valid
+        // submission addresses of their driver
+        if !is_solution_from_driver {
+            tracing::warn!(
+                driver = participant.driver().name,
+                submission_address = ?participant.submission_address(),
+                ?solver_address,
+                "the solution received is not from the driver submission address"
+            );
+            Metrics::solution_filtered(participant.driver(), "submission_address", kind);
+            return filter(participant, "submission_address");
+        }
+
+        // Filter out solutions from submission addresses that are not allowed
+        // globally in case a driver got misconfigured
+        if config
+            .allowed_submission_addresses
+            .as_ref()
+            .is_some_and(|allowed| !allowed.contains(&solver_address))
+        {
+            tracing::warn!(
+                driver = participant.driver().name,
+                ?solver_address,
+                "the solution received is from a disallowed submission address"
+            );
+            Metrics::solution_filtered(participant.driver(), "disallowed_submitter", kind);
+            return filter(participant, "disallowed_submitter");
+        }
+
+        // Filter out solutions that exceed the gas budget of their driver. Solutions
+        // without reported gas are assumed to need the fallback amount since
+        // estimating it would require revealing every solution.
+        let gas = participant
+            .solution()
+            .gas()
+            .unwrap_or(config.fallback_settlement_gas);
+        if exceeds_gas_budget(participant.driver(), gas) {
+            tracing::debug!(
+                driver = participant.driver().name,
+                solution = participant.solution().id(),
+                ?gas,
+                max_gas = ?participant.driver().max_settlement_gas,
+                "solution exceeds the driver's settlement gas budget"
+            );
+            Metrics::solution_filtered(participant.driver(), "driver_gas_budget", kind);
+            return filter(participant, "driver_gas_budget");
+        }
+
+        // limit number of solutions per solver; drivers get told the limit
+        // but aren't trusted to respect it
+        let driver = participant.driver().name.clone();
+        let count = counter.entry(driver).or_insert(0);
+        *count += 1;
+        *count <= config.max_solutions_per_solver.get()
+            || filter(participant, "max_solutions_per_solver")
+    });
+
+    let participation = ParticipationMatrix(
+        participations
+            .into_iter()
+            .map(|mut participation| {
+                participation
+                    .filtered
+                    .extend(filtered.remove(&participation.driver).unwrap_or_default());
+                participation::Participation {
+                    // the counter also includes solutions exceeding the limit
+                    accepted: counter
+                        .get(&participation.driver)
+                        .copied()
+                        .unwrap_or_default()
+                        .min(config.max_solutions_per_solver.get()),
+                    ..participation
+                }
+            })
+            .collect(),
+    );
+
+    // Shuffle so that sorting randomly splits ties between solutions that
+    // `competition::ranking::compare` considers equal.
+    solutions.shuffle(&mut rng(auction.id));
+    (solutions, participation)
+}
+
+/// Sends a `/solve` request to the driver and manages all error cases and
+/// records metrics and logs appropriately. `time_limit` is the time budget
+/// communicated to the driver in the request. Solve times of quote-only
+/// auctions get tracked separately. Drivers that didn't respond before the
+/// grace period started by `cutoff` expired count as timed out.
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip_all, fields(driver = driver.name))]
+async fn solve(
+    config: &Config,
+    guard: &SolverParticipationGuard,
+    driver: Arc<infra::Driver>,
+    request: solve::Request,
+    time_limit: Duration,
+    kind: AuctionKind,
+    prices: &domain::auction::Prices,
+    weth: eth::WrappedNativeToken,
+    cutoff: watch::Receiver<Option<tokio::time::Instant>>,
+) -> (
+    Vec<competition::Participant<Unranked>>,
+    participation::Participation,
+) {
+    let start = Instant::now();
+    let result = try_solve(
+        config,
+        guard,
+        Arc::clone(&driver),
+        request,
+        prices,
+        weth,
+        cutoff.clone(),
+        kind,
+    )
+    .await;
+    // Responses arriving after the communicated deadline indicate how long
+    // they spent in transit. A timeout means the latency is at least as big
+    // as the largest padding we are willing to apply. Drivers cut off by an
+    // early finish didn't necessarily miss the deadline so they are skipped.
+    let latency_sample = match &result {
+        Ok(_) | Err(SolveError::NoSolutions) => Some(start.elapsed().saturating_sub(time_limit)),
+        Err(SolveError::Timeout) if cutoff.borrow().is_none() => {
+            Some(config.max_solve_deadline_padding)
+        }
+        Err(_) => None,
+    };
+    // replays must not affect the padding of live auctions
+    if let Some(sample) = latency_sample.filter(|_| kind != AuctionKind::Replay) {
+        Metrics::solve_latency(&driver, driver.observe_latency(sample));
+    }
+    let (solutions, outcome) = match result {
+        Ok(solutions) => {
+            Metrics::solve_ok(&driver, start.elapsed(), kind);
+            let proposed = solutions.len();
+            (solutions, participation::Outcome::Proposed(proposed))
+        }
+        Err(err) => {
+            Metrics::solve_err(&driver, start.elapsed(), kind, &err);
+            match err {
+                // not proposing solutions is business as usual
+                SolveError::NoSolutions => {
+                    tracing::debug!(driver = %driver.name, "solver didn't provide solutions")
+                }
+                // replays already get summarized on their own
+                _ if kind == AuctionKind::Replay => {
+                    tracing::debug!(?err, driver = %driver.name, replay = true, "solve error")
+                }
+                _ => match SOLVE_ERRORS.record((driver.name.clone(), err.label())) {
+                    Occurrence::First => {
+                        tracing::warn!(?err, driver = %driver.name, "solve error")
+                    }
+                    Occurrence::Repeated(occurrences) => tracing::debug!(
+                        ?err,
+                        driver = %driver.name,
+                        occurrences,
+                        "solve error"
+                    ),
+                },
+            }
+            (vec![], participation::Outcome::from(&err))
+        }
+    };
+    let solve_time = start.elapsed();
+
+    let mut filtered = Vec::new();
+    let solutions = solutions
+        .into_iter()
+        .filter_map(|solution| match solution {
+            Ok(solution) => {
+                Metrics::solution_ok(&driver, kind);
+                Some(competition::Participant::new(solution, driver.clone()))
+            }
+            Err(err) => {
+                Metrics::solution_err(&driver, &err, kind);
+                tracing::debug!(?err, driver = %driver.name, "invalid proposed solution");
+                filtered.push(participation::Filtered {
+                    solution_id: None,
+                    reason: solution_error_label(&err),
+                });
+                None
+            }
+        })
+        .collect();
+    let participation = participation::Participation {
+        driver: driver.name.clone(),
+        submission_address: driver.submission_address,
+        outcome,
+        solve_time,
+        // filled in once the solutions passed all checks
+        accepted: 0,
+        filtered,
+    };
+    (solutions, participation)
+}
+
+/// Sends `/solve` request to the driver and forwards errors to the caller.
+async fn try_solve(
+    config: &Config,
+    guard: &SolverParticipationGuard,
+    driver: Arc<infra::Driver>,
+    request: solve::Request,
+    prices: &domain::auction::Prices,
+    weth: eth::WrappedNativeToken,
+    cutoff: watch::Receiver<Option<tokio::time::Instant>>,
+    kind: AuctionKind,
+) -> Result<Vec<Result<competition::Solution, domain::competition::SolutionError>>, SolveError> {
+    let (can_participate, response) = {
+        let driver = driver.clone();
+        let guard = guard.clone();
+        let mut handle = tokio::task::spawn(async move {
+            let fetch_response = driver.solve(request);
+            let check_allowed = guard.can_participate(&driver.submission_address);
+            tokio::join!(check_allowed, fetch_response)
+        });
+        let response = tokio::select! {
+            response = tokio::time::timeout(config.solve_deadline, &mut handle) => response.ok(),
+            _ = grace_period(cutoff) => None,
+        };
+        response
+            .ok_or_else(|| {
+                // Abort the background task to prevent memory leaks
+                handle.abort();
+                SolveError::Timeout
+            })?
+            .context("could not finish the task")
+            .map_err(SolveError::Failure)?
+    };
+
+    let response = match (can_participate, response) {
+        (Ok(true), Ok(response)) => response,
+        (Ok(false), _) => return Err(SolveError::SolverDenyListed),
+        (Err(err), _) => {
+            tracing::error!(
+                ?err,
+                driver = %driver.name,
+                ?driver.submission_address,
+                "solver participation check failed"
+            );
+            return Err(SolveError::SolverDenyListed);
+        }
+        (_, Err(HttpError::Timeout)) => return Err(SolveError::Timeout),
+        (_, Err(err)) => return Err(SolveError::Http(err)),
+    };
+
+    if response.solutions.is_empty() {
+        return Err(SolveError::NoSolutions);
+    }
+    // Checked before any solution gets processed so that huge responses
+    // can't waste resources. The response body size is limited separately.
+    let max = config.max_solutions_per_response.get();
+    if response.solutions.len() > max {
+        Metrics::oversized_response(&driver, kind);
+        return Err(SolveError::Failure(anyhow::anyhow!(
+            "response contains {} solutions but at most {max} are allowed",
+            response.solutions.len()
+        )));
+    }
+    let mut response = response;
+    ensure_unique_solution_ids(
+        &mut response.solutions,
+        config.reject_duplicate_solution_ids,
+    )?;
+    let mut solutions = response.into_domain(prices, config.dust_execution_threshold, weth);
+    reject_duplicate_executions(&mut solutions);
+    Ok(solutions)
+}
+
+fn solution_error_label(err: &SolutionError) -> &'static str {
+    match err {
+        SolutionError::ZeroScore(_) => "zero_score",
+        SolutionError::InvalidPrice(_) => "invalid_price",
+        SolutionError::SolverDenyListed => "solver_deny_listed",
+        SolutionError::DustExecution => "dust_execution",
+        SolutionError::MissingNativePrice => "missing_native_price",
+        SolutionError::DuplicateExecution => "duplicate",
+    }
+}
+
+/// Drivers sometimes propose several solutions that execute exactly the same
+/// orders for the same amounts and only differ in their score. Only the best
+/// of them is kept so the others don't use up the driver's solution budget.
+/// Solutions executing different amounts of the same orders all survive.
+fn reject_duplicate_executions(solutions: &mut [Result<competition::Solution, SolutionError>]) {
+    let mut best = HashMap::new();
+    for (index, solution) in solutions.iter().enumerate() {
+        let Ok(solution) = solution else {
+            continue;
+        };
+        let mut executions: Vec<_> = solution
+            .orders()
+            .iter()
+            .map(|(uid, order)| (uid.0, order.executed_sell.0, order.executed_buy.0))
+            .collect();
+        executions.sort();
+        let best_index = best.entry(executions).or_insert(index);
+        let is_better = solutions[*best_index]
+            .as_ref()
+            .is_ok_and(|best| solution.score() > best.score());
+        if is_better {
+            *best_index = index;
+        }
+    }
+
+    let keep: HashSet<usize> = best.into_values().collect();
+    for (index, solution) in solutions.iter_mut().enumerate() {
+        if solution.is_ok() && !keep.contains(&index) {
+            *solution = Err(SolutionError::DuplicateExecution);
+        }
+    }
+}
+
+/// Resolves once the grace period started after enough drivers responded
+/// expired. Never resolves if no grace period gets started.
+async fn grace_period(mut cutoff: watch::Receiver<Option<tokio::time::Instant>>) {
+    match cutoff
+        .wait_for(Option::is_some)
+        .await
+        .map(|deadline| *deadline)
+    {
+        Ok(Some(deadline)) => tokio::time::sleep_until(deadline).await,
+        // the sender only gets dropped once all drivers responded
+        _ => std::future::pending().await,
+    }
+}
+
+/// Drivers get asked to settle solutions by their id so ids within a single
+/// response have to be unique. In `strict` mode a response with duplicate ids
+/// gets rejected, otherwise only the first solution with each id is kept.
+fn ensure_unique_solution_ids(
+    solutions: &mut Vec<solve::Solution>,
+    strict: bool,
+) -> Result<(), SolveError> {
+    let mut seen = HashSet::new();
+    let duplicates: Vec<_> = solutions
+        .iter()
+        .map(|solution| solution.solution_id)
+        .filter(|id| !seen.insert(*id))
+        .unique()
+        .collect();
+    if duplicates.is_empty() {
+        return Ok(());
+    }
+    if strict {
+        return Err(SolveError::DuplicateSolutionIds(duplicates));
+    }
+    tracing::warn!(?duplicates, "dropping solutions with duplicate ids");
+    let mut seen = HashSet::new();
+    solutions.retain(|solution| seen.insert(solution.solution_id));
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InvalidConfig {
+    #[error(
+        "submission deadline has to be at least 1 block, otherwise no settlement can be submitted"
+    )]
+    SubmissionDeadline,
+    #[error("solve deadline has to be greater than 0")]
+    SolveDeadline,
+    #[error("solve deadline padding has to be smaller than the solve deadline")]
+    SolveDeadlinePadding,
+    #[error("max settlement transaction wait has to be greater than 0")]
+    MaxSettlementTransactionWait,
+    #[error("settlement rebroadcasts have to be requested before the submission deadline")]
+    SettleRebroadcast,
+    #[error("max auction reuse blocks has to be at least 1")]
+    MaxAuctionReuseBlocks,
+    #[error("fallback submitter {0} is not a configured driver")]
+    FallbackSubmitter(String),
+    #[error("run report directory has to be an existing directory")]
+    RunReportDir,
+    #[error("early finish fraction has to be greater than 0 and at most 1")]
+    EarlyFinishFraction,
+    #[error("max solutions per response has to be at least max solutions per solver")]
+    MaxSolutionsPerResponse,
+    #[error("strict mode requires at least 1 configured driver")]
+    NoDrivers,
+}
+
+impl SolveError {
+    fn label(&self) -> &'static str {
+        match self {
+            SolveError::Timeout => "timeout",
+            SolveError::NoSolutions => "no_solutions",
+            SolveError::Failure(_) => "error",
+            SolveError::Http(err) => err.label(),
+            SolveError::SolverDenyListed => "deny_listed",
+            SolveError::DuplicateSolutionIds(_) => "duplicate_solution_ids",
+        }
+    }
+}
+
+impl From<&SolveError> for participation::Outcome {
+    fn from(err: &SolveError) -> Self {
+        match err {
+            SolveError::Timeout => Self::Timeout,
+            SolveError::NoSolutions => Self::NoSolutions,
+            SolveError::SolverDenyListed => Self::DenyListed,
+            SolveError::Failure(_) | SolveError::Http(_) | SolveError::DuplicateSolutionIds(_) => {
+                Self::Error
+            }
+        }
+    }
+}
+
+/// What an auction gets solved for. Replays only get tracked by the `solve`
+/// metric (with their own label) so they don't distort the statistics of the
+/// live competitions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AuctionKind {
+    Regular,
+    QuoteOnly,
+    /// A recent auction solved again on startup, see
+    /// [`Config::replay_recent_auctions`].
+    Replay,
+}
+
+impl AuctionKind {
+    fn of(auction: &domain::Auction) -> Self {
+        match auction.quote_only {
+            true => Self::QuoteOnly,
+            false => Self::Regular,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Regular => "regular",
+            Self::QuoteOnly => "quote_only",
+            Self::Replay => "replay",
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum SolveError {
+    #[error("the solver timed out")]
+    Timeout,
+    #[error("driver did not propose any solutions")]
+    NoSolutions,
+    #[error(transparent)]
+    Failure(anyhow::Error),
+    #[error(transparent)]
+    Http(HttpError),
+    #[error("the solver got deny listed")]
+    SolverDenyListed,
+    #[error("the response contains several solutions with the ids {0:?}")]
+    DuplicateSolutionIds(Vec<u64>),
+}
+
+/// Who submitted a settlement.
+#[derive(Debug, Clone, Copy, strum::IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+enum Submitter {
+    /// The driver that won the auction.
+    Primary,
+    /// The configured fallback submitter after the winning driver timed out.
+    Fallback,
+}
+
+/// Orders that are currently getting settled together with the auction
+/// settling them.
+#[derive(Debug, Default)]
+struct InFlightOrders(HashMap<OrderUid, Id>);
+
+impl InFlightOrders {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn contains(&self, order: &OrderUid) -> bool {
+        self.0.contains_key(order)
+    }
+
+    fn uids(&self) -> impl Iterator<Item = &OrderUid> {
+        self.0.keys()
+    }
+
+    fn insert(&mut self, auction: Id, orders: impl IntoIterator<Item = OrderUid>) {
+        self.0
+            .extend(orders.into_iter().map(|order| (order, auction)));
+    }
+
+    /// Removes the orders once the settlement of `auction` finished. Orders
+    /// that got released manually and picked up by a later auction in the
+    /// meantime stay in flight.
+    fn finish(&mut self, auction: Id, orders: &HashSet<OrderUid>) {
+        self.0
+            .retain(|order, settling| !(orders.contains(order) && *settling == auction));
+    }
+
+    /// Removes all orders settled by `auction`, e.g. because its settlements
+    /// got cancelled, and returns them.
+    fn cancel(&mut self, auction: Id) -> Vec<OrderUid> {
+        let cancelled: Vec<_> = self
+            .0
+            .iter()
+            .filter(|(_, settling)| **settling == auction)
+            .map(|(order, _)| *order)
+            .collect();
+        for order in &cancelled {
+            self.0.remove(order);
+        }
+        cancelled
+    }
+
+    /// Removes the selected orders regardless of which auction settles them
+    /// and returns the ones that were in flight.
+    fn release(&mut self, release: &InFlightRelease) -> Vec<OrderUid> {
+        match release {
+            InFlightRelease::All => self.0.drain().map(|(order, _)| order).collect(),
+            InFlightRelease::Orders(orders) => orders
+                .iter()
+                .filter(|order| self.0.remove(order).is_some())
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+/// Which in-flight orders an operator wants to release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InFlightRelease {
+    All,
+    Orders(HashSet<OrderUid>),
+}
+
+/// Tracks whether the orders of a successful settlement still have to be
+/// protected from getting settled again.
+#[derive(Debug)]
+struct ReorgProtection {
+    depth: u64,
+    /// Block in which the settlement last appeared or disappeared.
+    last_change: u64,
+    reorged: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ReorgEvent {
+    None,
+    /// The settlement disappeared from the chain.
+    Reorged,
+    /// A reorged settlement appeared on chain again.
+    Reconfirmed,
+    /// The orders don't need to be protected anymore.
+    Released,
+}
+
+impl ReorgProtection {
+    fn new(found_in_block: u64, depth: u64) -> Self {
+        Self {
+            depth,
+            last_change: found_in_block,
+            reorged: false,
+        }
+    }
+
+    /// Updates the protection with whether the settlement is on chain as of
+    /// `block`.
+    fn observe(&mut self, block: u64, on_chain: bool) -> ReorgEvent {
+        let event = match (self.reorged, on_chain) {
+            (false, false) => ReorgEvent::Reorged,
+            (true, true) => ReorgEvent::Reconfirmed,
+            _ => ReorgEvent::None,
+        };
+        if event != ReorgEvent::None {
+            self.reorged = !on_chain;
+            self.last_change = block;
+        }
+        if block >= self.last_change.saturating_add(self.depth) {
+            return ReorgEvent::Released;
+        }
+        event
+    }
+}
+
+/// Detects whether `current` isn't a descendant of the previously observed
+/// block `previous`. Blocks more than one block ahead of `previous` can only
+/// be checked with the `ancestor` hash, i.e. the hash of the block at the
+/// height of `previous` on the chain of `current`.
+fn detect_reorg(
+    previous: &BlockInfo,
+    current: &BlockInfo,
+    ancestor: Option<eth::H256>,
+) -> Option<infra::status::Reorg> {
+    if current.hash == previous.hash {
+        return None;
+    }
+    let depth = match current.number.checked_sub(previous.number) {
+        // the replacement chain might already be longer than one block so
+        // only the parent hash tells that a reorg happened
+        Some(1) if current.parent_hash != previous.hash => None,
+        Some(1) => return None,
+        // different block at the same or a lower height
+        None | Some(0) => Some(previous.number - current.number + 1),
+        // the previously observed block got replaced by a different one but
+        // it's unknown how many blocks before it got replaced as well
+        Some(_) if ancestor.is_some_and(|ancestor| ancestor != previous.hash) => None,
+        Some(_) => return None,
+    };
+    Some(infra::status::Reorg {
+        block: current.number,
+        orphaned: previous.hash,
+        depth,
+    })
+}
+
+/// Whether a settlement using `gas` exceeds the settlement gas budget of the
+/// driver.
+fn exceeds_gas_budget(driver: &infra::Driver, gas: eth::Gas) -> bool {
+    driver
+        .max_settlement_gas
+        .is_some_and(|max_gas| gas > max_gas)
+}
+
+/// All addresses a settlement of `solver` proposed by `driver` may get
+/// submitted from. The driver may submit from any of its currently valid
+/// addresses (e.g. during a key rotation) so all of them have to be checked.
+/// Once the settlement got handed to the `fallback` submitter its addresses
+/// count as well.
+fn settlement_addresses(
+    driver: &infra::Driver,
+    solver: eth::Address,
+    fallback: Option<&infra::Driver>,
+) -> Vec<eth::Address> {
+    let now = chrono::Utc::now();
+    let mut solvers = driver.submission_addresses(now);
+    let fallback = fallback
+        .map(|fallback| fallback.submission_addresses(now))
+        .unwrap_or_default();
+    for address in std::iter::once(solver).chain(fallback) {
+        if !solvers.contains(&address) {
+            solvers.push(address);
+        }
+    }
+    solvers
+}
+
+#[derive(Debug, thiserror::Error)]
+enum SettleError {
+    #[error(transparent)]
+    Other(anyhow::Error),
+    #[error(transparent)]
+    Http(HttpError),
+    #[error("settlement transaction await reached deadline")]
+    Timeout,
+    #[error("settlement transaction got reorged")]
+    Reorged,
+}
+
+impl SettleError {
+    fn label(&self) -> &'static str {
+        match self {
+            SettleError::Other(_) => "error",
+            SettleError::Http(err) => err.label(),
+            SettleError::Timeout => "timeout",
+            SettleError::Reorged => "reorged",
+        }
+    }
+
+    /// Event for the orders of the settlement. Orders of settlements that
+    /// could not be submitted get marked as reverted, all other failures
+    /// (e.g. timeouts) make the orders ready for the next auction again.
+    fn order_event(&self) -> OrderEventLabel {
+        let reverted = match self {
+            SettleError::Http(HttpError::Status { body, .. }) => {
+                serde_json::from_str::<settle::Error>(body).is_ok_and(|err| err.failed_to_submit())
+            }
+            SettleError::Http(_)
+            | SettleError::Other(_)
+            | SettleError::Timeout
+            | SettleError::Reorged => false,
+        };
+        if reverted {
+            OrderEventLabel::Reverted
+        } else {
+            OrderEventLabel::Ready
+        }
+    }
+}
+
+#[derive(prometheus_metric_storage::MetricStorage)]
+#[metric(subsystem = "runloop")]
+struct Metrics {
+    /// Tracks the last executed auction.
+    auction: prometheus::IntGauge,
+
+    /// Time since the cached auction was built when the run loop picked it
+    /// up.
+    current_auction_age_seconds: prometheus::Gauge,
+
+    /// Number of orders in the cached auction when the run loop picked it up.
+    current_auction_orders: prometheus::IntGauge,
+
+    /// Herfindahl-Hirschman index of the auction wins per solver over the
+    /// most recent auctions.
+    winner_concentration_hhi: prometheus::Gauge,
+
+    /// Tracks the number of winners per auction.
+    #[metric(buckets(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10))]
+    auction_winners: prometheus::Histogram,
+
+    /// Consecutive auctions in which all drivers were deny listed or
+    /// unreachable.
+    auctions_without_drivers: prometheus::IntGauge,
+
+    /// Set to 1 in strict mode while no driver could participate in too many
+    /// consecutive auctions.
+    drivers_unavailable: prometheus::IntGauge,
+
+    /// Counts auctions that weren't run, labeled by whether the solvable
+    /// orders cache was never populated or the auction had no orders.
+    #[metric(labels("reason"))]
+    auctions_skipped: prometheus::IntCounterVec,
+
+    /// Tracks the duration of successful driver `/solve` requests.
+    #[metric(
+        labels("driver", "result", "auction"),
+        buckets(
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20
+        )
+    )]
+    solve: prometheus::HistogramVec,
+
+    /// Current estimate of the network latency to each driver in seconds.
+    #[metric(labels("driver"))]
+    solve_latency_estimate: prometheus::GaugeVec,
+
+    /// Tracks driver solutions. Valid solutions of observe-only drivers are
+    /// counted with the result `observe_only`.
+    #[metric(labels("driver", "result"))]
+    solutions: prometheus::IntCounterVec,
+
+    /// Counts driver responses rejected for containing too many solutions.
+    #[metric(labels("driver"))]
+    oversized_responses: prometheus::IntCounterVec,
+
+    /// Counts ranked solutions that only lost due to token conflicts with the
+    /// winners and whose orders got prioritized in the next auctions.
+    #[metric(labels("driver"))]
+    deferred_due_to_conflict: prometheus::IntCounterVec,
+
+    /// Tracks the result of driver `/reveal` requests.
+    #[metric(labels("driver", "result"))]
+    reveal: prometheus::HistogramVec,
+
+    /// Tracks the times and results of driver `/settle` requests.
+    #[metric(
+        labels("driver", "result"),
+        buckets(0, 3, 6, 9, 12, 15, 18, 21, 24, 27, 30, 33, 36, 39, 42, 45, 48)
+    )]
+    settle: prometheus::HistogramVec,
+
+    /// Settlement transactions that disappeared from the chain while waiting
+    /// for confirmations.
+    #[metric(labels("driver"))]
+    settlements_reorged: prometheus::IntCounterVec,
+
+    /// Failures to estimate the gas of winning solutions whose driver didn't
+    /// report it.
+    #[metric(labels("driver", "reason"))]
+    gas_estimation_failures: prometheus::IntCounterVec,
+
+    /// Tracks the results of notifying drivers about the competition outcome.
+    #[metric(labels("driver", "result"))]
+    notify_outcome: prometheus::IntCounterVec,
+
+    /// Tracks the number of settlement rebroadcast requests sent to drivers.
+    #[metric(labels("driver", "result"))]
+    settle_rebroadcast: prometheus::IntCounterVec,
+
+    /// Tracks the eventual outcome of settlements that were rebroadcasted.
+    #[metric(labels("driver", "result"))]
+    settle_rebroadcast_outcome: prometheus::IntCounterVec,
+
+    /// Tracks the outcome of settlements depending on whether the winning
+    /// driver or the fallback submitter submitted them.
+    #[metric(labels("driver", "submitter", "result"))]
+    settle_submitter: prometheus::IntCounterVec,
+
+    /// Tracks auctions that produced solutions but no winners.
+    no_winners: prometheus::IntCounter,
+
+    /// Tracks the number of orders that were removed from the auction because
+    /// they expire before (or shortly after) the submission deadline.
+    orders_dropped_near_expiry: prometheus::IntCounter,
+
+    /// Tracks auctions for which reward relevant data could not be stored.
+    #[metric(labels("kind"))]
+    incomplete_auction_data: prometheus::IntCounterVec,
+
+    /// Tracks the ratio of clearing prices (normalized per solution) to
+    /// external prices per token across all solutions of an auction.
+    #[metric(
+        labels("bound"),
+        buckets(0.5, 0.8, 0.9, 0.95, 0.99, 1.0, 1.01, 1.05, 1.1, 1.25, 2.0)
+    )]
+    clearing_price_ratio: prometheus::HistogramVec,
+
+    /// Counts orders for which different drivers proposed the exact same
+    /// execution per involved driver. The orders themselves only get logged.
+    #[metric(labels("driver"))]
+    duplicate_cross_driver_execution: prometheus::IntCounterVec,
+
+    /// Tracks how much gas winning solutions save (or lose) by settling their
+    /// orders together instead of one by one.
+    #[metric(
+        labels("driver", "direction"),
+        buckets(0, 25_000, 50_000, 100_000, 200_000, 500_000, 1_000_000, 2_000_000)
+    )]
+    batch_gas_savings: prometheus::HistogramVec,
+
+    /// Counts orders a winner executed worse than a losing solution that
+    /// could have won alongside the other winners.
+    #[metric(labels("driver"))]
+    coalition_fairness_violations: prometheus::IntCounterVec,
+
+    /// Tracks auctions that were solved again on startup.
+    #[metric(labels("outcome"))]
+    auction_replays: prometheus::IntCounterVec,
+
+    /// Tracks the number of solutions that were discarded before ranking.
+    #[metric(labels("driver", "reason"))]
+    solution_filtered: prometheus::IntCounterVec,
+
+    /// Tracks the number of auctions that were not sent to the drivers because
+    /// the same auction was already solved recently.
+    duplicate_auctions_skipped: prometheus::IntCounter,
+
+    /// Tracks the number of orders that were part of some but not the winning
+    /// solutions.
+    matched_unsettled: prometheus::IntCounter,
+
+    /// Counts competitions found on startup whose solutions were never stored
+    /// because the process died while handling them.
+    competitions_lost: prometheus::IntCounter,
+
+    /// Counts orders for which a winning solution implies a protocol fee
+    /// above the configured share of the order's volume per winning driver.
+    /// The orders themselves only get logged.
+    #[metric(labels("driver"))]
+    protocol_fee_exceeded: prometheus::IntCounterVec,
+
+    /// Tracks the number of orders that were settled together with the
+    /// settling driver.
+    #[metric(labels("driver"))]
+    settled: prometheus::IntCounterVec,
+
+    /// Counts settled and failed settlements per directed token pair
+    /// (`<sell>/<buy>`) of their orders. Untracked pairs are counted as
+    /// `other`.
+    #[metric(labels("pair", "result"))]
+    token_pair_settlements: prometheus::IntCounterVec,
+
+    /// Counts the actions operators triggered via the admin API.
+    #[metric(labels("action"))]
+    admin_actions: prometheus::IntCounterVec,
+
+    /// Counts in-flight orders released by operators.
+    released_in_flight_orders: prometheus::IntCounter,
+
+    /// Tracks mined settlements that deviated from the winning solution by
+    /// the kind of deviation.
+    #[metric(labels("driver", "kind"))]
+    settlement_deviations: prometheus::IntCounterVec,
+
+    /// Counts reorgs noticed while building auctions by how many blocks got
+    /// replaced (`unknown` if that couldn't be determined).
+    #[metric(labels("depth"))]
+    reorgs_detected: prometheus::IntCounterVec,
+
+    /// Tracks the number of database errors.
+    #[metric(labels("error_type"))]
+    db_metric_error: prometheus::IntCounterVec,
+
+    /// Tracks the time spent in post-processing after the auction has been
+    /// solved and before sending a `settle` request.
+    auction_postprocessing_time: prometheus::Histogram,
+
+    /// Tracks how often post-processing got aborted because it took too long.
+    auction_postprocessing_timeouts: prometheus::IntCounter,
+
+    /// Tracks the results of post-processing competitions. Competitions
+    /// without winners are counted as `no_winners` whether or not they got
+    /// stored.
+    #[metric(labels("result"))]
+    post_processing: prometheus::IntCounterVec,
+
+    /// Tracks the time spent running maintenance. This mostly consists of
+    /// indexing new events.
+    #[metric(buckets(0.01, 0.05, 0.1, 0.2, 0.5, 1, 1.5, 2, 2.5, 5))]
+    service_maintenance_time: prometheus::Histogram,
+
+    /// Total time spent in a single run of the run loop.
+    #[metric(buckets(0, 3, 6, 9, 12, 15, 18, 21, 24, 27, 30, 33, 36, 39, 42, 45, 48))]
+    single_run_time: prometheus::Histogram,
+
+    /// Time from an order's first appearance in an auction until its
+    /// settlement got observed.
+    #[metric(buckets(15, 30, 45, 60, 90, 120, 180, 240, 300, 360, 420, 480, 540, 600))]
+    auction_to_settlement_latency: prometheus::Histogram,
+
+    /// Volume in ETH the cut auctions could settle at most (see
+    /// [`domain::Auction::total_volume_eth`]).
+    #[metric(buckets(0, 1, 10, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 50_000))]
+    auction_volume_eth: prometheus::Histogram,
+
+    /// Time difference between the current block and when the single run
+    /// function is started.
+    #[metric(buckets(0, 0.25, 0.5, 0.75, 1, 1.5, 2, 2.5, 3, 4, 5, 6))]
+    current_block_delay: prometheus::Histogram,
+}
+
+impl Metrics {
+    fn get() -> &'static Self {
+        Metrics::instance(metrics::get_storage_registry()).unwrap()
+    }
+
+    fn auction(auction_id: domain::auction::Id) {
+        Self::get().auction.set(auction_id)
+    }
+
+    fn current_auction(auction: &domain::RawAuctionData, age: Duration) {
+        let metrics = Self::get();
+        metrics.current_auction_age_seconds.set(age.as_secs_f64());
+        metrics
+            .current_auction_orders
+            .set(i64::try_from(auction.orders.len()).unwrap_or(i64::MAX));
+    }
+
+    fn auctions_without_drivers(count: usize) {
+        Self::get()
+            .auctions_without_drivers
+            .set(i64::try_from(count).unwrap_or(i64::MAX))
+    }
+
+    fn drivers_unavailable(unavailable: bool) {
+        Self::get().drivers_unavailable.set(unavailable.into())
+    }
+
+    fn solve_ok(driver: &infra::Driver, elapsed: Duration, kind: AuctionKind) {
+        Self::get()
+            .solve
+            .with_label_values(&[&driver.name, "success", kind.label()])
+            .observe(elapsed.as_secs_f64())
+    }
+
+    fn solve_err(driver: &infra::Driver, elapsed: Duration, kind: AuctionKind, err: &SolveError) {
+        Self::get()
+            .solve
+            .with_label_values(&[&driver.name, err.label(), kind.label()])
+            .observe(elapsed.as_secs_f64())
+    }
+
+    fn solve_latency(driver: &infra::Driver, estimate: Duration) {
+        Self::get()
+            .solve_latency_estimate
+            .with_label_values(&[&driver.name])
+            .set(estimate.as_secs_f64())
+    }
+
+    fn auction_skipped(reason: &str) {
+        Self::get()
+            .auctions_skipped
+            .with_label_values(&[reason])
+            .inc();
+    }
+
+    fn no_winners() {
+        Self::get().no_winners.inc();
+    }
+
+    fn solution_ok(driver: &infra::Driver, kind: AuctionKind) {
+        if kind == AuctionKind::Replay {
+            return;
+        }
+        let label = if driver.observe_only {
+            "observe_only"
+        } else {
+            "success"
+        };
+        Self::get()
+            .solutions
+            .with_label_values(&[&driver.name, label])
+            .inc();
+    }
+
+    fn oversized_response(driver: &infra::Driver, kind: AuctionKind) {
+        if kind == AuctionKind::Replay {
+            return;
+        }
+        Self::get()
+            .oversized_responses
+            .with_label_values(&[&driver.name])
+            .inc();
+    }
+
+    fn deferred_due_to_conflict(driver: &infra::Driver) {
+        Self::get()
+            .deferred_due_to_conflict
+            .with_label_values(&[&driver.name])
+            .inc();
+    }
+
+    fn solution_err(driver: &infra::Driver, err: &SolutionError, kind: AuctionKind) {
+        if kind == AuctionKind::Replay {
+            return;
+        }
+        Self::get()
+            .solutions
+            .with_label_values(&[&driver.name, solution_error_label(err)])
+            .inc();
+    }
+
+    fn settle_ok(
+        driver: &infra::Driver,
+        settled_order_count: usize,
+        elapsed: Duration,
+        token_pairs: &HashSet<String>,
+    ) {
+        Self::get()
+            .settle
+            .with_label_values(&[&driver.name, "success"])
+            .observe(elapsed.as_secs_f64());
+        Self::get()
+            .settled
+            .with_label_values(&[&driver.name])
+            .inc_by(settled_order_count.try_into().unwrap_or(u64::MAX));
+        Self::token_pair_settlements(token_pairs, "settled");
+    }
+
+    fn settle_err(
+        driver: &infra::Driver,
+        elapsed: Duration,
+        err: &SettleError,
+        token_pairs: &HashSet<String>,
+    ) {
+        Self::get()
+            .settle
+            .with_label_values(&[&driver.name, err.label()])
+            .observe(elapsed.as_secs_f64());
+        Self::token_pair_settlements(token_pairs, "failed");
+    }
+
+    fn auction_volume(volume: eth::U256) {
+        Self::get()
+            .auction_volume_eth
+            .observe(volume.to_f64_lossy() / 1e18);
+    }
+
+    fn auction_to_settlement_latency(latency: Duration) {
+        Self::get()
+            .auction_to_settlement_latency
+            .observe(latency.as_secs_f64());
+    }
+
+    fn admin_action(action: &str) {
+        Self::get().admin_actions.with_label_values(&[action]).inc();
+    }
+
+    fn token_pair_settlements(token_pairs: &HashSet<String>, result: &str) {
+        for pair in token_pairs {
+            Self::get()
+                .token_pair_settlements
+                .with_label_values(&[pair, result])
+                .inc();
+        }
+    }
+
+    fn settlement_deviation(driver: &str, deviation: &settlement::Deviation) {
+        let kinds = [
+            ("dropped_orders", !deviation.dropped_orders.is_empty()),
+            ("added_orders", !deviation.added_orders.is_empty()),
+            ("changed_prices", !deviation.changed_prices.is_empty()),
+        ];
+        for (kind, deviated) in kinds {
+            if deviated {
+                Self::get()
+                    .settlement_deviations
+                    .with_label_values(&[driver, kind])
+                    .inc();
+            }
+        }
+    }
+
+    fn notify_outcome(driver: &infra::Driver, success: bool) {
+        let label = if success { "success" } else { "error" };
+        Self::get()
+            .notify_outcome
+            .with_label_values(&[&driver.name, label])
+            .inc();
+    }
+
+    fn settlement_reorged(driver: &infra::Driver) {
+        Self::get()
+            .settlements_reorged
+            .with_label_values(&[&driver.name])
+            .inc();
+    }
+
+    fn gas_estimation_failed(driver: &infra::Driver, reason: &str) {
+        Self::get()
+            .gas_estimation_failures
+            .with_label_values(&[&driver.name, reason])
+            .inc();
+    }
+
+    fn settle_rebroadcast(driver: &infra::Driver, success: bool) {
+        let label = if success { "success" } else { "error" };
+        Self::get()
+            .settle_rebroadcast
+            .with_label_values(&[&driver.name, label])
+            .inc();
+    }
+
+    fn settle_rebroadcast_outcome(driver: &infra::Driver, result: &Result<TxId, SettleError>) {
+        let label = match result {
+            Ok(_) => "success",
+            Err(SettleError::Other(_)) => "error",
+            Err(SettleError::Http(err)) => err.label(),
+            Err(SettleError::Timeout) => "timeout",
+            Err(SettleError::Reorged) => "reorged",
+        };
+        Self::get()
+            .settle_rebroadcast_outcome
+            .with_label_values(&[&driver.name, label])
+            .inc();
+    }
+
+    fn settle_submitter(
+        driver: &infra::Driver,
+        submitter: Submitter,
+        result: &Result<TxId, SettleError>,
+    ) {
+        let label = match result {
+            Ok(_) => "success",
+            Err(SettleError::Other(_)) => "error",
+            Err(SettleError::Http(err)) => err.label(),
+            Err(SettleError::Timeout) => "timeout",
+            Err(SettleError::Reorged) => "reorged",
+        };
+        Self::get()
+            .settle_submitter
+            .with_label_values(&[&driver.name, submitter.into(), label])
+            .inc();
+    }
+
+    fn orders_dropped_near_expiry(count: usize) {
+        Self::get()
+            .orders_dropped_near_expiry
+            .inc_by(count.try_into().unwrap_or(u64::MAX));
+    }
+
+    fn incomplete_auction_data(kind: AuctionDataKind) {
+        Self::get()
+            .incomplete_auction_data
+            .with_label_values(&[kind.into()])
+            .inc();
+    }
+
+    fn price_spreads(spreads: &HashMap<eth::TokenAddress, competition::price_spread::PriceSpread>) {
+        let metric = &Self::get().clearing_price_ratio;
+        for spread in spreads.values() {
+            metric.with_label_values(&["min"]).observe(spread.min_ratio);
+            metric.with_label_values(&["max"]).observe(spread.max_ratio);
+        }
+    }
+
+    fn duplicate_executions(duplicates: &HashMap<domain::OrderUid, Vec<&str>>) {
+        let metric = &Self::get().duplicate_cross_driver_execution;
+        for driver in duplicates.values().flatten() {
+            metric.with_label_values(&[driver]).inc();
+        }
+    }
+
+    fn batch_gas_savings(driver: &str, savings: eth::SignedGas) {
+        let direction = match savings.0 >= 0 {
+            true => "saved",
+            false => "lost",
+        };
+        Self::get()
+            .batch_gas_savings
+            .with_label_values(&[driver, direction])
+            .observe(savings.0.unsigned_abs() as f64);
+    }
+
+    fn coalition_fairness_violations(
+        violations: &[winner_selection::coalition_fairness::Violation],
+    ) {
+        let metric = &Self::get().coalition_fairness_violations;
+        for violation in violations {
+            metric.with_label_values(&[&violation.winner]).inc();
+        }
+    }
+
+    fn auction_replayed(outcome: &str) {
+        Self::get()
+            .auction_replays
+            .with_label_values(&[outcome])
+            .inc();
+    }
+
+    fn solution_filtered(driver: &infra::Driver, reason: &str, kind: AuctionKind) {
+        if kind == AuctionKind::Replay {
+            return;
+        }
+        Self::get()
+            .solution_filtered
+            .with_label_values(&[&driver.name, reason])
+            .inc();
+    }
+
+    fn duplicate_auction_skipped() {
+        Self::get().duplicate_auctions_skipped.inc();
+    }
+
+    fn protocol_fee_exceeded(driver: &str, order: &domain::OrderUid) {
+        tracing::warn!(
+            driver,
+            ?order,
+            "winning solution implies excessive protocol fee"
+        );
+        Self::get()
+            .protocol_fee_exceeded
+            .with_label_values(&[driver])
+            .inc();
+    }
+
+    fn matched_unsettled(unsettled: HashSet<&domain::OrderUid>) {
+        if !unsettled.is_empty() {
+            tracing::debug!(?unsettled, "some orders were matched but not settled");
+        }
+        Self::get().matched_unsettled.inc_by(unsettled.len() as u64);
+    }
+
+    fn post_processed(elapsed: Duration) {
+        Self::get()
+            .auction_postprocessing_time
+            .observe(elapsed.as_secs_f64());
+    }
+
+    fn post_processing_timed_out() {
+        Self::get().auction_postprocessing_timeouts.inc();
+    }
+
+    fn post_processing(result: &str) {
+        Self::get()
+            .post_processing
+            .with_label_values(&[result])
+            .inc();
+    }
+
+    fn reorg_detected(depth: Option<u64>) {
+        let depth = depth.map_or_else(|| "unknown".to_string(), |depth| depth.to_string());
+        Self::get()
+            .reorgs_detected
+            .with_label_values(&[&depth])
+            .inc();
+    }
+
+    fn ran_maintenance(elapsed: Duration) {
+        Self::get()
+            .service_maintenance_time
+            .observe(elapsed.as_secs_f64());
+    }
+
+    fn single_run_completed(elapsed: Duration) {
+        Self::get().single_run_time.observe(elapsed.as_secs_f64());
+    }
+
+    fn auction_ready(init_block_timestamp: Instant) {
+        Self::get()
+            .current_block_delay
+            .observe(init_block_timestamp.elapsed().as_secs_f64())
+    }
+}
+
+pub mod observe {
+    use {
+        crate::domain::{
+            self,
+            competition::{
+                Unranked,
+                price_spread::PriceSpread,
+                winner_selection::{self, Ranking},
+            },
+        },
+        std::collections::{HashMap, HashSet},
+    };
+
+    /// Logs which orders were added and removed compared to the previous
+    /// auction and returns the added ones.
+    pub fn log_auction_delta(
+        previous: &Option<domain::Auction>,
+        current: &domain::Auction,
+    ) -> Vec<domain::OrderUid> {
+        let previous_uids = match previous {
+            Some(previous) => previous
+                .orders
+                .iter()
+                .map(|order| order.uid)
+                .collect::<HashSet<_>>(),
+            None => HashSet::new(),
+        };
+        let current_uids = current
+            .orders
+            .iter()
+            .map(|order| order.uid)
+            .collect::<HashSet<_>>();
+        let added = current_uids.difference(&previous_uids);
+        let removed = previous_uids.difference(&current_uids);
+        tracing::debug!(
+            id = current.id,
+            added = ?added,
+            "New orders in auction"
+        );
+        tracing::debug!(
+            id = current.id,
+            removed = ?removed,
+            "Orders no longer in auction"
+        );
+        added.copied().collect()
+    }
+
+    pub fn price_spreads(
+        auction_id: domain::auction::Id,
+        spreads: &HashMap<domain::eth::TokenAddress, PriceSpread>,
+    ) {
+        for (token, spread) in spreads {
+            tracing::debug!(
+                auction_id,
+                ?token,
+                min_ratio = spread.min_ratio,
+                max_ratio = spread.max_ratio,
+                external = ?spread.external.get(),
+                "clearing price spread"
+            );
+        }
+    }
+
+    pub fn duplicate_executions(
+        auction_id: domain::auction::Id,
+        duplicates: &HashMap<domain::OrderUid, Vec<&str>>,
+    ) {
+        for (order, drivers) in duplicates {
+            tracing::debug!(
+                auction_id,
+                ?order,
+                ?drivers,
+                "different drivers proposed identical order execution"
+            );
+        }
+    }
+
+    pub fn coalition_fairness_violations(
+        auction_id: domain::auction::Id,
+        violations: &[winner_selection::coalition_fairness::Violation],
+    ) {
+        for violation in violations {
+            tracing::debug!(
+                auction_id,
+                order = ?violation.order,
+                winner = violation.winner,
+                alternative = violation.alternative,
+                "winning coalition executed order worse than a compatible solution"
+            );
+        }
+    }
+
+    pub fn solutions(solutions: &[domain::competition::Participant<Unranked>]) {
+        if solutions.is_empty() {
+            tracing::info!("no solutions for auction");
+        }
+        for participant in solutions {
+            if participant.solution().is_empty() {
+                tracing::debug!(
+                    driver = %participant.driver().name,
+                    solution = %participant.solution().id(),
+                    "proposed empty solution"
+                );
+                continue;
+            }
+            tracing::debug!(
+                driver = %participant.driver().name,
+                orders = ?participant.solution().order_ids(),
+                solution = %participant.solution().id(),
+                "proposed solution"
+            );
+        }
+    }
+
+    /// Records metrics for the matched but unsettled orders.
+    pub fn unsettled(ranking: &Ranking, auction: &domain::Auction) {
+        let mut non_winning_orders = {
+            let winning_orders = ranking
+                .winners()
+                .flat_map(|p| p.solution().order_ids())
+                .collect::<HashSet<_>>();
+            ranking
+                .ranked()
+                .filter(|p| !p.solution().is_empty())
+                .flat_map(|p| p.solution().order_ids())
+                .filter(|uid| !winning_orders.contains(uid))
+                .collect::<HashSet<_>>()
+        };
+        // Report orders that were part of a non-winning solution candidate
+        // but only if they were part of the auction (filter out jit orders)
+        let auction_uids = auction.orders.iter().map(|o| o.uid).collect::<HashSet<_>>();
+        non_winning_orders.retain(|uid| auction_uids.contains(uid));
+        super::Metrics::matched_unsettled(non_winning_orders);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            domain::{auction::ContentHash, competition::winner_selection::Arbitrator},
+            infra::solvers::fake::{Behavior, FakeDriver, offline_driver},
+        },
+        ::observe::metrics::LivenessChecking,
+        primitive_types::H256,
+    };
+
+    fn config() -> Config {
+        Config {
+            submission_deadline: 5,
+            max_settlement_transaction_wait: Duration::from_secs(60),
+            solve_deadline: Duration::from_secs(15),
+            max_solve_deadline_padding: Duration::ZERO,
+            max_run_loop_delay: Duration::from_secs(2),
+            post_processing_timeout: Duration::from_secs(20),
+            combinatorial_auctions_cutover: None,
+            max_winners_per_auction: NonZeroUsize::new(1).unwrap(),
+            max_winners_per_submission_address: None,
+            conflict_deferral_auctions: None,
+            allowed_submission_addresses: None,
+            treat_native_as_weth: true,
+            max_solutions_per_solver: NonZeroUsize::new(1).unwrap(),
+            max_solutions_per_response: NonZeroUsize::new(100).unwrap(),
+            reject_duplicate_solution_ids: true,
+            persist_competitions_without_winners: true,
+            tracked_token_pairs: Default::default(),
+            settle_rebroadcast: None,
+            settlement_confirmation_blocks: 0,
+            in_flight_reorg_protection_blocks: 0,
+            gas_estimation_timeout: Duration::from_secs(1),
+            fallback_settlement_gas: eth::Gas(300_000.into()),
+            order_expiry_margin: Duration::ZERO,
+            min_order_validity_blocks: 0,
+            max_auction_reuse_blocks: 1,
+            replay_recent_auctions: 0,
+            dust_execution_threshold: eth::Ether::default(),
+            fallback_submitter: None,
+            winner_concentration_window: NonZeroUsize::new(100).unwrap(),
+            run_report_dir: None,
+            quote_only_interval: None,
+            participation_update_interval: Duration::ZERO,
+            early_finish_fraction: None,
+            early_finish_grace: Duration::ZERO,
+            max_auctions_without_drivers: None,
+            max_auction_cache_startup_delay: None,
+            score_tie_epsilon: U256::zero(),
+            fairness_threshold_floor: None,
+            order_ordering: Default::default(),
+            winner_objective: Default::default(),
+            winner_merging: Default::default(),
+            max_protocol_fee_bps: None,
+        }
+    }
+
+    #[test]
+    fn marks_orders_of_failed_settlements() {
+        let status = |body: &str| {
+            SettleError::Http(HttpError::Status {
+                code: reqwest::StatusCode::BAD_REQUEST,
+                body: body.to_string(),
+            })
+        };
+
+        // the driver could not get the settlement included
+        assert_eq!(
+            status(r#"{"kind":"FailedToSubmit","description":"..."}"#).order_event(),
+            OrderEventLabel::Reverted
+        );
+        // the orders simply didn't get settled in time
+        assert_eq!(SettleError::Timeout.order_event(), OrderEventLabel::Ready);
+        assert_eq!(SettleError::Reorged.order_event(), OrderEventLabel::Ready);
+        assert_eq!(
+            status(r#"{"kind":"DeadlineExceeded","description":"..."}"#).order_event(),
+            OrderEventLabel::Ready
+        );
+        assert_eq!(status("not json").order_event(), OrderEventLabel::Ready);
+        assert_eq!(
+            SettleError::Http(HttpError::Timeout).order_event(),
+            OrderEventLabel::Ready
+        );
+    }
+
+    #[test]
+    fn reorg_protection_keeps_orders_until_settlement_is_buried() {
+        let mut protection = ReorgProtection::new(10, 3);
+        assert_eq!(protection.observe(11, true), ReorgEvent::None);
+        assert_eq!(protection.observe(12, true), ReorgEvent::None);
+        assert_eq!(protection.observe(13, true), ReorgEvent::Released);
+
+        // a reorg restarts the protection
+        let mut protection = ReorgProtection::new(10, 3);
+        assert_eq!(protection.observe(12, false), ReorgEvent::Reorged);
+        assert_eq!(protection.observe(13, false), ReorgEvent::None);
+        assert_eq!(protection.observe(14, true), ReorgEvent::Reconfirmed);
+        assert_eq!(protection.observe(16, true), ReorgEvent::None);
+        assert_eq!(protection.observe(17, true), ReorgEvent::Released);
+
+        // settlements that stay gone eventually release their orders
+        let mut protection = ReorgProtection::new(10, 3);
+        assert_eq!(protection.observe(11, false), ReorgEvent::Reorged);
+        assert_eq!(protection.observe(13, false), ReorgEvent::None);
+        assert_eq!(protection.observe(14, false), ReorgEvent::Released);
+    }
+
+    #[test]
+    fn released_orders_survive_finishing_settlements() {
+        let (a, b) = (OrderUid([1; 56]), OrderUid([2; 56]));
+        let mut in_flight = InFlightOrders::default();
+        in_flight.insert(1, [a, b]);
+
+        // an operator releases `a` while its settlement is stuck
+        let released = in_flight.release(&InFlightRelease::Orders(HashSet::from([a])));
+        assert_eq!(released, [a]);
+        assert!(!in_flight.contains(&a));
+        // releasing it again is a no-op
+        assert!(
+            in_flight
+                .release(&InFlightRelease::Orders(HashSet::from([a])))
+                .is_empty()
+        );
+
+        // the next auction settles `a` before the stuck settlement finishes
+        in_flight.insert(2, [a]);
+        in_flight.finish(1, &HashSet::from([a, b]));
+        assert!(in_flight.contains(&a));
+        assert!(!in_flight.contains(&b));
+
+        in_flight.finish(2, &HashSet::from([a]));
+        assert!(in_flight.is_empty());
+    }
+
+    #[test]
+    fn cancelling_auction_releases_its_orders() {
+        let (a, b, c) = (OrderUid([1; 56]), OrderUid([2; 56]), OrderUid([3; 56]));
+        let mut in_flight = InFlightOrders::default();
+        in_flight.insert(1, [a, b]);
+        in_flight.insert(2, [c]);
+
+        let cancelled: HashSet<_> = in_flight.cancel(1).into_iter().collect();
+        assert_eq!(cancelled, HashSet::from([a, b]));
+        assert!(!in_flight.contains(&a) && !in_flight.contains(&b));
+        // orders of other auctions stay in flight
+        assert!(in_flight.contains(&c));
+        assert!(in_flight.cancel(1).is_empty());
+    }
+
+    #[tokio::test]
+    async fn releasing_all_races_with_finishing_settlement() {
+        let orders: HashSet<_> = (0..100).map(|i| OrderUid([i; 56])).collect();
+        for _ in 0..10 {
+            let in_flight = Arc::new(Mutex::new(InFlightOrders::default()));
+            in_flight.lock().await.insert(1, orders.iter().copied());
+
+            let finish = {
+                let in_flight = in_flight.clone();
+                let orders = orders.clone();
+                tokio::spawn(async move { in_flight.lock().await.finish(1, &orders) })
+            };
+            let release = {
+                let in_flight = in_flight.clone();
+                tokio::spawn(async move { in_flight.lock().await.release(&InFlightRelease::All) })
+            };
+            finish.await.unwrap();
+            let released = release.await.unwrap();
+
+            // whoever comes first takes care of all the orders
+            assert!(released.is_empty() || released.len() == orders.len());
+            assert!(in_flight.lock().await.is_empty());
+        }
+    }
+
+    #[test]
+    fn labels_tracked_token_pairs() {
+        let token = |byte: u8| eth::TokenAddress(H160([byte; 20]));
+        let trade = |sell: u8, buy: u8| competition::TradedOrder {
+            side: domain::auction::order::Side::Sell,
+            sell: eth::Asset {
+                token: token(sell),
+                amount: U256::from(100).into(),
+            },
+            buy: eth::Asset {
+                token: token(buy),
+                amount: U256::from(100).into(),
+            },
+            executed_sell: U256::from(100).into(),
+            executed_buy: U256::from(100).into(),
+        };
+        let solution = Solution::new(
+            0,
+            eth::Address(H160::zero()),
+            competition::Score::try_new(eth::Ether(1.into())).unwrap(),
+            HashMap::from([
+                (OrderUid([1; 56]), trade(1, 2)),
+                (OrderUid([2; 56]), trade(1, 2)),
+                (OrderUid([3; 56]), trade(2, 1)),
+                (OrderUid([4; 56]), trade(3, 4)),
+            ]),
+            Default::default(),
+            None,
+        );
+        let tracked = HashSet::from([(token(1), token(2)), (token(5), token(6))]);
+
+        assert_eq!(
+            token_pair_labels(&solution, &tracked),
+            HashSet::from([
+                format!("{:?}/{:?}", H160([1; 20]), H160([2; 20])),
+                "other".to_string(),
+            ])
+        );
+        assert_eq!(
+            token_pair_labels(&solution, &HashSet::new()),
+            HashSet::from(["other".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn blocked_orders_get_prioritized_in_next_auctions() {
+        let trade = |sell: u8, buy: u8| competition::TradedOrder {
+            side: domain::auction::order::Side::Sell,
+            sell: eth::Asset {
+                token: H160([sell; 20]).into(),
+                amount: U256::from(100).into(),
+            },
+            buy: eth::Asset {
+                token: H160([buy; 20]).into(),
+                amount: U256::from(100).into(),
+            },
+            executed_sell: U256::from(100).into(),
+            executed_buy: U256::from(100).into(),
+        };
+        let participant = |driver: infra::Driver, orders: &[(u8, (u8, u8))]| {
+            let solution = Solution::new(
+                0,
+                driver.submission_address,
+                competition::Score::try_new(eth::Ether(1.into())).unwrap(),
+                orders
+                    .iter()
+                    .map(|(uid, (sell, buy))| (OrderUid([*uid; 56]), trade(*sell, *buy)))
+                    .collect(),
+                Default::default(),
+                None,
+            );
+            competition::Participant::new(solution, Arc::new(driver))
+        };
+        // None of the orders are part of the auction so all scores tie and
+        // the solutions get picked in the order of their submission addresses.
+        let participants = vec![
+            participant(
+                offline_driver("winner", H160([1; 20])).await,
+                &[(1, (1, 2))],
+            ),
+            participant(
+                offline_driver("deferred", H160([2; 20])).await,
+                &[(1, (1, 2)), (2, (1, 2))],
+            ),
+            participant(
+                offline_driver("unrelated", H160([3; 20])).await,
+                &[(3, (3, 4))],
+            ),
+            // conflicts as well but all winner slots are taken already
+            participant(offline_driver("late", H160([4; 20])).await, &[(4, (1, 2))]),
+        ];
+        let arbitrator = winner_selection::combinatorial::Config {
+            max_winners: 2,
+            max_winners_per_submission_address: None,
+            weth: weth(),
+            treat_native_as_weth: true,
+            score_tie_epsilon: U256::zero(),
+            objective: Default::default(),
+            merging: Default::default(),
+            fairness_threshold_floor: None,
+        };
+        let ranking = arbitrator.arbitrate(participants, &auction());
+        let deferrals = |driver: &str| {
+            Metrics::get()
+                .deferred_due_to_conflict
+                .with_label_values(&[driver])
+                .get()
+        };
+        let auctions = NonZeroUsize::new(2).unwrap();
+
+        let mut deferred = DeferredOrders::default();
+        defer_blocked_orders(
+            &mut deferred,
+            ranking.winners(),
+            ranking.blocked_by_token_conflicts(),
+            auctions,
+        );
+
+        assert_eq!(deferrals("deferred"), 1);
+        assert_eq!(deferrals("late"), 0);
+        // the order the winner settles doesn't need to be prioritized
+        for _ in 0..auctions.get() {
+            assert_eq!(
+                deferred.next_auction(true),
+                HashSet::from([OrderUid([2; 56])])
+            );
+        }
+        assert!(deferred.next_auction(true).is_empty());
+    }
+
+    #[test]
+    fn detects_reorgs() {
+        let block = |number: u64, hash: u8, parent: u8| BlockInfo {
+            number,
+            hash: H256([hash; 32]),
+            parent_hash: H256([parent; 32]),
+            ..Default::default()
+        };
+        let reorg = |block: u64, depth: Option<u64>| {
+            Some(infra::status::Reorg {
+                block,
+                orphaned: H256([1; 32]),
+                depth,
+            })
+        };
+        let previous = block(10, 1, 0);
+
+        // same block or regular progress
+        assert_eq!(detect_reorg(&previous, &previous, None), None);
+        assert_eq!(detect_reorg(&previous, &block(11, 2, 1), None), None);
+        // skipped blocks are checked with the hash at the previous height
+        assert_eq!(detect_reorg(&previous, &block(13, 2, 9), None), None);
+        assert_eq!(
+            detect_reorg(&previous, &block(13, 2, 9), Some(H256([1; 32]))),
+            None
+        );
+        assert_eq!(
+            detect_reorg(&previous, &block(13, 2, 9), Some(H256([3; 32]))),
+            reorg(13, None)
+        );
+
+        // the next block doesn't build on the previous one
+        assert_eq!(
+            detect_reorg(&previous, &block(11, 2, 9), None),
+            reorg(11, None)
+        );
+        // the previous block got replaced
+        assert_eq!(
+            detect_reorg(&previous, &block(10, 2, 9), None),
+            reorg(10, Some(1))
+        );
+        // the chain got shorter
+        assert_eq!(
+            detect_reorg(&previous, &block(8, 2, 9), None),
+            reorg(8, Some(3))
+        );
+    }
+
+    #[test]
+    fn validates_config() {
+        assert!(config().validate().is_ok());
+
+        let invalid = Config {
+            submission_deadline: 0,
+            ..config()
+        };
+        assert!(matches!(
+            invalid.validate(),
+            Err(InvalidConfig::SubmissionDeadline)
+        ));
+
+        let invalid = Config {
+            solve_deadline: Duration::ZERO,
+            ..config()
+        };
+        assert!(matches!(
+            invalid.validate(),
+            Err(InvalidConfig::SolveDeadline)
+        ));
+
+        let invalid = Config {
+            max_solve_deadline_padding: Duration::from_secs(15),
+            ..config()
+        };
+        assert!(matches!(
+            invalid.validate(),
+            Err(InvalidConfig::SolveDeadlinePadding)
+        ));
+
+        let invalid = Config {
+            settle_rebroadcast: Some(SettleRebroadcast {
+                after_blocks: 5,
+                max_remaining_blocks: 2,
+            }),
+            ..config()
+        };
+        assert!(matches!(
+            invalid.validate(),
+            Err(InvalidConfig::SettleRebroadcast)
+        ));
+
+        let invalid = Config {
+            max_auction_reuse_blocks: 0,
+            ..config()
+        };
+        assert!(matches!(
+            invalid.validate(),
+            Err(InvalidConfig::MaxAuctionReuseBlocks)
+        ));
+
+        let invalid = Config {
+            run_report_dir: Some(PathBuf::from("/this/directory/does/not/exist")),
+            ..config()
+        };
+        assert!(matches!(
+            invalid.validate(),
+            Err(InvalidConfig::RunReportDir)
+        ));
+
+        for fraction in [0., 1.5, f64::NAN] {
+            let invalid = Config {
+                early_finish_fraction: Some(fraction),
+                ..config()
+            };
+            assert!(matches!(
+                invalid.validate(),
+                Err(InvalidConfig::EarlyFinishFraction)
+            ));
+        }
+
+        let invalid = Config {
+            max_solutions_per_solver: NonZeroUsize::new(3).unwrap(),
+            max_solutions_per_response: NonZeroUsize::new(2).unwrap(),
+            ..config()
+        };
+        assert!(matches!(
+            invalid.validate(),
+            Err(InvalidConfig::MaxSolutionsPerResponse)
+        ));
+    }
+
+    #[tokio::test]
+    async fn strict_mode_requires_drivers() {
+        let strict = Config {
+            max_auctions_without_drivers: NonZeroUsize::new(3),
+            ..config()
+        };
+        assert!(config().validate_drivers(&[]).is_ok());
+        assert!(matches!(
+            strict.validate_drivers(&[]),
+            Err(InvalidConfig::NoDrivers)
+        ));
+
+        let fake = FakeDriver::start(Default::default());
+        assert!(
+            strict
+                .validate_drivers(&[fake.driver("driver").await])
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn tracks_driver_availability() {
+        let matrix = |outcome| {
+            ParticipationMatrix(vec![participation::Participation {
+                driver: "driver".to_string(),
+                submission_address: eth::Address(H160::zero()),
+                outcome,
+                solve_time: Duration::ZERO,
+                accepted: 0,
+                filtered: vec![],
+            }])
+        };
+        let unavailable = matrix(participation::Outcome::Timeout);
+        let available = matrix(participation::Outcome::NoSolutions);
+        let diagnostics = infra::status::RunLoopDiagnostics::default();
+        let liveness = Liveness::new(Duration::from_secs(60));
+        let max = NonZeroUsize::new(2);
+        let track = |participation: &ParticipationMatrix| {
+            track_driver_availability(&diagnostics, &liveness, max, participation);
+            diagnostics.auctions_without_drivers.load(Ordering::Relaxed)
+        };
+
+        assert_eq!(track(&unavailable), 1);
+        assert!(liveness.is_alive().await);
+        // deny listed drivers don't count as available either
+        assert_eq!(track(&matrix(participation::Outcome::DenyListed)), 2);
+        assert!(!liveness.is_alive().await);
+        assert_eq!(track(&ParticipationMatrix::default()), 3);
+        assert!(!liveness.is_alive().await);
+
+        // a single responding driver resets the count
+        assert_eq!(track(&available), 0);
+        assert!(liveness.is_alive().await);
+
+        // without strict mode the auctions only get counted
+        for count in 1..=3 {
+            track_driver_availability(&diagnostics, &liveness, None, &unavailable);
+            assert_eq!(
+                diagnostics.auctions_without_drivers.load(Ordering::Relaxed),
+                count
+            );
+        }
+        assert!(liveness.is_alive().await);
+    }
+
+    fn auction() -> domain::Auction {
+        domain::Auction {
+            id: 1,
+            block: 0,
+            orders: Default::default(),
+            prices: Default::default(),
+            surplus_capturing_jit_order_owners: vec![],
+            content_hash: ContentHash::new(&[], &Default::default(), &[]),
+            quote_only: false,
+            prioritized_orders: Default::default(),
+        }
+    }
+
+    async fn run_competition(
+        config: &Config,
+        drivers: &[Arc<infra::Driver>],
+    ) -> Vec<competition::Participant<Unranked>> {
+        collect_solutions(
+            config,
+            drivers,
+            &SolverParticipationGuard::allow_all(),
+            &auction(),
+            &HashSet::new(),
+            None,
+            weth(),
+            &seeded_rng(),
+            AuctionKind::Regular,
+        )
+        .await
+        .0
+    }
+
+    fn weth() -> eth::WrappedNativeToken {
+        H160([0x11; 20]).into()
+    }
+
+    fn scores(scores: &[u64]) -> Vec<U256> {
+        scores.iter().copied().map(U256::from).collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn post_processing_times_out() {
+        let timeout = Duration::from_secs(20);
+        let timeouts = || Metrics::get().auction_postprocessing_timeouts.get();
+
+        let before = timeouts();
+        let result = with_post_processing_timeout(timeout, async { Ok(true) }).await;
+        assert!(result.unwrap());
+        assert_eq!(timeouts(), before);
+
+        let start = tokio::time::Instant::now();
+        let result = with_post_processing_timeout(timeout, std::future::pending()).await;
+        assert!(result.is_err());
+        assert_eq!(start.elapsed(), timeout);
+        assert_eq!(timeouts(), before + 1);
+
+        // errors of post-processing itself aren't timeouts
+        let result =
+            with_post_processing_timeout(timeout, async { Err(anyhow::anyhow!("db error")) }).await;
+        assert!(result.is_err());
+        assert_eq!(timeouts(), before + 1);
+    }
+
+    #[tokio::test]
+    async fn handed_off_settlements_get_searched_from_the_fallback_addresses() {
+        let address = |byte| eth::Address(H160([byte; 20]));
+        let winner = offline_driver("winner", H160([1; 20])).await;
+        let fallback = offline_driver("fallback", H160([2; 20])).await;
+
+        assert_eq!(
+            settlement_addresses(&winner, address(3), None),
+            vec![address(1), address(3)]
+        );
+        assert_eq!(
+            settlement_addresses(&winner, address(3), Some(&fallback)),
+            vec![address(1), address(3), address(2)]
+        );
+        // the fallback submitter settling its own solution
+        assert_eq!(
+            settlement_addresses(&fallback, address(2), Some(&fallback)),
+            vec![address(2)]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waits_for_settlement_confirmations() {
+        let tx = TxId(H256::repeat_byte(1));
+        let found = |tx: Option<TxId>| std::future::ready(anyhow::Ok(tx));
+
+        // no confirmations required
+        let result = confirm_settlement(futures::stream::pending(), tx, 10, 0, found(None)).await;
+        assert_eq!(result.unwrap(), tx);
+
+        // still waiting for the second confirmation
+        let blocks = futures::stream::iter([11]).chain(futures::stream::pending());
+        let result = tokio::time::timeout(
+            Duration::from_secs(60),
+            confirm_settlement(blocks, tx, 10, 2, found(Some(tx))),
+        )
+        .await;
+        assert!(result.is_err());
+
+        // skipped blocks count as confirmations as well
+        let blocks = futures::stream::iter([13]);
+        let result = confirm_settlement(blocks, tx, 10, 2, found(Some(tx))).await;
+        assert_eq!(result.unwrap(), tx);
+    }
+
+    #[tokio::test]
+    async fn detects_settlements_reorged_while_waiting_for_confirmations() {
+        let tx = TxId(H256::repeat_byte(1));
+
+        // the transaction disappeared after the reorg
+        let blocks = futures::stream::iter([11, 12]);
+        let result =
+            confirm_settlement(blocks, tx, 10, 2, std::future::ready(anyhow::Ok(None))).await;
+        assert!(matches!(result, Err(SettleError::Reorged)));
+
+        // the transaction got included again in a different block
+        let reincluded = TxId(H256::repeat_byte(2));
+        let blocks = futures::stream::iter([11, 12]);
+        let result = confirm_settlement(
+            blocks,
+            tx,
+            10,
+            2,
+            std::future::ready(anyhow::Ok(Some(reincluded))),
+        )
+        .await;
+        assert_eq!(result.unwrap(), reincluded);
+
+        let blocks = futures::stream::iter([11, 12]);
+        let result = confirm_settlement(
+            blocks,
+            tx,
+            10,
+            2,
+            std::future::ready(Err(anyhow::anyhow!("db unavailable"))),
+        )
+        .await;
+        assert!(matches!(result, Err(SettleError::Other(_))));
+    }
+
+    #[test]
+    fn orders_have_to_outlive_the_submission_deadline() {
+        let block_time = Duration::from_secs(12);
+        let config = Config {
+            submission_deadline: 5,
+            ..config()
+        };
+        assert_eq!(expiry_deadline(&config, 1_000, block_time), 1_060);
+
+        // the safety margin pushes the deadline further out
+        let config = Config {
+            order_expiry_margin: Duration::from_secs(30),
+            ..config
+        };
+        assert_eq!(expiry_deadline(&config, 1_000, block_time), 1_090);
+    }
+
+    #[test]
+    fn orders_have_to_stay_valid_for_min_validity_blocks() {
+        let block_time = Duration::from_secs(12);
+        let config = Config {
+            submission_deadline: 5,
+            min_order_validity_blocks: 2,
+            ..config()
+        };
+        assert_eq!(expiry_deadline(&config, 1_000, block_time), 1_084);
+
+        // huge values saturate instead of overflowing
+        let config = Config {
+            min_order_validity_blocks: u64::MAX,
+            ..config
+        };
+        assert_eq!(
+            expiry_deadline(&config, 1_000, Duration::from_secs(1)),
+            1_000 + u64::from(u32::MAX)
+        );
+    }
+
+    #[tokio::test]
+    async fn estimates_missing_gas() {
+        let timeout = Duration::from_millis(100);
+        let gas = |value: u64| eth::Gas(value.into());
+
+        // reported gas doesn't wait for an estimate
+        let estimate = std::future::pending();
+        let result = reported_or_estimated_gas(Some(gas(1)), estimate, timeout).await;
+        assert_eq!(result.unwrap(), (gas(1), GasSource::Reported));
+
+        let estimate = std::future::ready(Ok(gas(2)));
+        let result = reported_or_estimated_gas(None, estimate, timeout).await;
+        assert_eq!(result.unwrap(), (gas(2), GasSource::Estimated));
+
+        let estimate = std::future::ready(Err(("reveal", anyhow::anyhow!("solution not found"))));
+        let result = reported_or_estimated_gas(None, estimate, timeout).await;
+        assert_eq!(result.unwrap_err().0, "reveal");
+
+        // a hanging estimate can't delay the settlement for longer than the
+        // timeout
+        let estimate = std::future::pending();
+        let result = reported_or_estimated_gas(None, estimate, timeout).await;
+        assert_eq!(result.unwrap_err().0, "timeout");
+    }
+
+    #[tokio::test]
+    async fn estimates_driver_latency() {
+        let fast = FakeDriver::start(Behavior::default());
+        let slow = FakeDriver::start(Behavior {
+            delay: Duration::from_secs(5),
+            ..Default::default()
+        });
+        let config = Config {
+            solve_deadline: Duration::from_millis(500),
+            max_solve_deadline_padding: Duration::from_millis(200),
+            ..config()
+        };
+        let drivers = [fast.driver("fast").await, slow.driver("slow").await];
+
+        run_competition(&config, &drivers).await;
+
+        // responses within the deadline don't need any padding
+        assert_eq!(drivers[0].latency(), Duration::ZERO);
+        // timeouts push the estimate towards the maximum padding
+        assert_eq!(drivers[1].latency(), Duration::from_millis(200));
+
+        assert_eq!(
+            drivers[1].observe_latency(Duration::ZERO),
+            Duration::from_millis(160)
+        );
+    }
 
-    /// Tracks the result of driver `/reveal` requests.
-    #[metric(labels("driver", "result"))]
-    reveal: prometheus::HistogramVec,
+    #[tokio::test]
+    async fn ignores_drivers_exceeding_solve_deadline() {
+        let fast = FakeDriver::start(Behavior {
+            scores: scores(&[1]),
+            ..Default::default()
+        });
+        let slow = FakeDriver::start(Behavior {
+            scores: scores(&[2]),
+            delay: Duration::from_secs(5),
+            ..Default::default()
+        });
+        let config = Config {
+            solve_deadline: Duration::from_millis(500),
+            ..config()
+        };
+        let drivers = [fast.driver("fast").await, slow.driver("slow").await];
 
-    /// Tracks the times and results of driver `/settle` requests.
-    #[metric(
-        labels("driver", "result"),
-        buckets(0, 3, 6, 9, 12, 15, 18, 21, 24, 27, 30, 33, 36, 39, 42, 45, 48)
-    )]
-    settle: prometheus::HistogramVec,
+        let start = Instant::now();
+        let solutions = run_competition(&config, &drivers).await;
 
-    /// Tracks the number of orders that were part of some but not the winning
-    /// solutions.
-    matched_unsettled: prometheus::IntCounter,
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].driver().name, "fast");
+    }
 
-    /// Tracks the number of orders that were settled together with the
-    /// settling driver.
-    #[metric(labels("driver"))]
-    settled: prometheus::IntCounterVec,
+    #[tokio::test]
+    async fn finishes_early_once_enough_drivers_responded() {
+        let fast = FakeDriver::start(Behavior {
+            scores: scores(&[1]),
+            ..Default::default()
+        });
+        let slow = FakeDriver::start(Behavior {
+            scores: scores(&[2]),
+            delay: Duration::from_secs(5),
+            ..Default::default()
+        });
+        let config = Config {
+            early_finish_fraction: Some(0.5),
+            early_finish_grace: Duration::from_millis(100),
+            ..config()
+        };
+        let drivers = [fast.driver("fast").await, slow.driver("slow").await];
 
-    /// Tracks the number of database errors.
-    #[metric(labels("error_type"))]
-    db_metric_error: prometheus::IntCounterVec,
+        let start = Instant::now();
+        let (solutions, participations) = collect_solutions(
+            &config,
+            &drivers,
+            &SolverParticipationGuard::allow_all(),
+            &auction(),
+            &HashSet::new(),
+            None,
+            weth(),
+            &seeded_rng(),
+            AuctionKind::Regular,
+        )
+        .await;
 
-    /// Tracks the time spent in post-processing after the auction has been
-    /// solved and before sending a `settle` request.
-    auction_postprocessing_time: prometheus::Histogram,
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].driver().name, "fast");
+        let timed_out = participations
+            .0
+            .iter()
+            .find(|participation| participation.driver == "slow")
+            .unwrap();
+        assert_eq!(timed_out.outcome, participation::Outcome::Timeout);
+        // being cut off doesn't say anything about the driver's latency
+        assert_eq!(drivers[1].latency(), Duration::ZERO);
+    }
 
-    /// Tracks the time spent running maintenance. This mostly consists of
-    /// indexing new events.
-    #[metric(buckets(0.01, 0.05, 0.1, 0.2, 0.5, 1, 1.5, 2, 2.5, 5))]
-    service_maintenance_time: prometheus::Histogram,
+    #[tokio::test]
+    async fn seeded_rng_makes_competitions_reproducible() {
+        let fake = FakeDriver::start(Behavior {
+            scores: scores(&[1, 1, 1, 1, 1, 1, 1, 1]),
+            ..Default::default()
+        });
+        let drivers = [fake.driver("fake").await];
+        let config = Config {
+            max_solutions_per_solver: NonZeroUsize::new(8).unwrap(),
+            ..config()
+        };
+        let ids = || async {
+            run_competition(&config, &drivers)
+                .await
+                .iter()
+                .map(|participant| participant.solution().id())
+                .collect::<Vec<_>>()
+        };
 
-    /// Total time spent in a single run of the run loop.
-    #[metric(buckets(0, 3, 6, 9, 12, 15, 18, 21, 24, 27, 30, 33, 36, 39, 42, 45, 48))]
-    single_run_time: prometheus::Histogram,
+        let first = ids().await;
+        assert_eq!(first.len(), 8);
+        assert_eq!(ids().await, first);
+    }
 
-    /// Time difference between the current block and when the single run
-    /// function is started.
-    #[metric(buckets(0, 0.25, 0.5, 0.75, 1, 1.5, 2, 2.5, 3, 4, 5, 6))]
-    current_block_delay: prometheus::Histogram,
-}
+    #[tokio::test]
+    async fn caps_solutions_per_solver() {
+        let fake = FakeDriver::start(Behavior {
+            scores: scores(&[1, 2, 3]),
+            ..Default::default()
+        });
+        let other = FakeDriver::start(Behavior {
+            scores: scores(&[4]),
+            ..Default::default()
+        });
+        let config = Config {
+            max_solutions_per_solver: NonZeroUsize::new(2).unwrap(),
+            ..config()
+        };
+        let drivers = [fake.driver("fake").await, other.driver("other").await];
 
-impl Metrics {
-    fn get() -> &'static Self {
-        Metrics::instance(metrics::get_storage_registry()).unwrap()
+        let solutions = run_competition(&config, &drivers).await;
+
+        let count = |name: &str| {
+            solutions
+                .iter()
+                .filter(|participant| participant.driver().name == name)
+                .count()
+        };
+        assert_eq!(count("fake"), 2);
+        assert_eq!(count("other"), 1);
     }
 
-    fn auction(auction_id: domain::auction::Id) {
-        Self::get().auction.set(auction_id)
+    #[tokio::test]
+    async fn records_filtered_solutions() {
+        let fake = FakeDriver::start(Behavior {
+            scores: scores(&[0, 1]),
+            gas: Some(300_000),
+            ..Default::default()
+        });
+        let drivers = [fake.budgeted_driver("budgeted", 200_000).await];
+
+        let (solutions, participation) = collect_solutions(
+            &config(),
+            &drivers,
+            &SolverParticipationGuard::allow_all(),
+            &auction(),
+            &HashSet::new(),
+            None,
+            weth(),
+            &seeded_rng(),
+            AuctionKind::Regular,
+        )
+        .await;
+
+        assert!(solutions.is_empty());
+        assert_eq!(
+            participation.0[0].filtered,
+            [
+                participation::Filtered {
+                    solution_id: None,
+                    reason: "zero_score",
+                },
+                participation::Filtered {
+                    solution_id: Some(1),
+                    reason: "driver_gas_budget",
+                },
+            ]
+        );
     }
 
-    fn solve_ok(driver: &infra::Driver, elapsed: Duration) {
-        Self::get()
-            .solve
-            .with_label_values(&[&driver.name, "success"])
-            .observe(elapsed.as_secs_f64())
+    #[tokio::test]
+    async fn rejects_duplicate_solution_ids() {
+        let fake = FakeDriver::start(Behavior {
+            scores: scores(&[1, 2, 3]),
+            solution_ids: Some(vec![0, 0, 1]),
+            ..Default::default()
+        });
+        let config = Config {
+            max_solutions_per_solver: NonZeroUsize::new(3).unwrap(),
+            ..config()
+        };
+        let drivers = [fake.driver("fake").await];
+
+        assert!(run_competition(&config, &drivers).await.is_empty());
     }
 
-    fn solve_err(driver: &infra::Driver, elapsed: Duration, err: &SolveError) {
-        let label = match err {
-            SolveError::Timeout => "timeout",
-            SolveError::NoSolutions => "no_solutions",
-            SolveError::Failure(_) => "error",
-            SolveError::SolverDenyListed => "deny_listed",
+    #[tokio::test]
+    async fn keeps_first_of_duplicate_solution_ids() {
+        let fake = FakeDriver::start(Behavior {
+            scores: scores(&[1, 2, 3]),
+            solution_ids: Some(vec![0, 0, 1]),
+            ..Default::default()
+        });
+        let config = Config {
+            max_solutions_per_solver: NonZeroUsize::new(3).unwrap(),
+            reject_duplicate_solution_ids: false,
+            ..config()
         };
-        Self::get()
-            .solve
-            .with_label_values(&[&driver.name, label])
-            .observe(elapsed.as_secs_f64())
+        let drivers = [fake.driver("fake").await];
+
+        let mut solutions: Vec<_> = run_competition(&config, &drivers)
+            .await
+            .iter()
+            .map(|participant| {
+                (
+                    participant.solution().id(),
+                    participant.solution().score().get().0,
+                )
+            })
+            .collect();
+        solutions.sort();
+        assert_eq!(solutions, [(0, U256::from(1)), (1, U256::from(3))]);
     }
 
-    fn solution_ok(driver: &infra::Driver) {
-        Self::get()
-            .solutions
-            .with_label_values(&[&driver.name, "success"])
-            .inc();
+    #[tokio::test]
+    async fn rejects_oversized_responses() {
+        let oversized = FakeDriver::start(Behavior {
+            scores: scores(&[1, 2, 3]),
+            ..Default::default()
+        });
+        let other = FakeDriver::start(Behavior {
+            scores: scores(&[4, 5]),
+            ..Default::default()
+        });
+        let config = Config {
+            max_solutions_per_solver: NonZeroUsize::new(2).unwrap(),
+            max_solutions_per_response: NonZeroUsize::new(2).unwrap(),
+            ..config()
+        };
+        let drivers = [
+            oversized.driver("oversized").await,
+            other.driver("other").await,
+        ];
+
+        let solutions = run_competition(&config, &drivers).await;
+
+        assert_eq!(solutions.len(), 2);
+        assert!(
+            solutions
+                .iter()
+                .all(|participant| participant.driver().name == "other")
+        );
     }
 
-    fn solution_err(driver: &infra::Driver, err: &SolutionError) {
-        let label = match err {
-            SolutionError::ZeroScore(_) => "zero_score",
-            SolutionError::InvalidPrice(_) => "invalid_price",
-            SolutionError::SolverDenyListed => "solver_deny_listed",
+    #[tokio::test]
+    async fn keeps_best_of_duplicate_executions() {
+        let fake = FakeDriver::start(Behavior {
+            scores: scores(&[1, 3, 2, 4]),
+            // the first three solutions only differ in their score, the last
+            // one executes a different amount of the same order
+            executed: Some(vec![5, 5, 5, 6]),
+            ..Default::default()
+        });
+        // the same execution as the duplicates but from another driver
+        let other = FakeDriver::start(Behavior {
+            scores: scores(&[1]),
+            executed: Some(vec![5]),
+            ..Default::default()
+        });
+        let config = Config {
+            max_solutions_per_solver: NonZeroUsize::new(4).unwrap(),
+            ..config()
         };
-        Self::get()
-            .solutions
-            .with_label_values(&[&driver.name, label])
-            .inc();
+        let drivers = [fake.driver("fake").await, other.driver("other").await];
+
+        let mut solutions: Vec<_> = run_competition(&config, &drivers)
+            .await
+            .iter()
+            .map(|participant| {
+                (
+                    participant.driver().name.clone(),
+                    participant.solution().score().get().0,
+                )
+            })
+            .collect();
+        solutions.sort();
+        assert_eq!(
+            solutions,
+            [
+                ("fake".to_string(), U256::from(3)),
+                ("fake".to_string(), U256::from(4)),
+                ("other".to_string(), U256::from(1)),
+            ]
+        );
     }
 
-    fn settle_ok(driver: &infra::Driver, settled_order_count: usize, elapsed: Duration) {
-        Self::get()
-            .settle
-            .with_label_values(&[&driver.name, "success"])
-            .observe(elapsed.as_secs_f64());
-        Self::get()
-            .settled
-            .with_label_values(&[&driver.name])
-            .inc_by(settled_order_count.try_into().unwrap_or(u64::MAX));
+    #[tokio::test]
+    async fn filters_solutions_from_wrong_submission_address() {
+        let honest = FakeDriver::start(Behavior {
+            scores: scores(&[1]),
+            ..Default::default()
+        });
+        let impostor = FakeDriver::start(Behavior {
+            scores: scores(&[2]),
+            submission_address: Some(H160::random()),
+            ..Default::default()
+        });
+        let drivers = [
+            honest.driver("honest").await,
+            impostor.driver("impostor").await,
+        ];
+
+        let solutions = run_competition(&config(), &drivers).await;
+
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].driver().name, "honest");
+        assert_eq!(solutions[0].solver_address(), honest.submission_address());
     }
 
-    fn settle_err(driver: &infra::Driver, elapsed: Duration, err: &SettleError) {
-        let label = match err {
-            SettleError::Other(_) => "error",
-            SettleError::Timeout => "timeout",
+    #[tokio::test]
+    async fn filters_solutions_from_disallowed_submission_address() {
+        let allowed = FakeDriver::start(Behavior {
+            scores: scores(&[1]),
+            ..Default::default()
+        });
+        let disallowed = FakeDriver::start(Behavior {
+            scores: scores(&[2]),
+            ..Default::default()
+        });
+        let drivers = [
+            allowed.driver("allowed").await,
+            disallowed.driver("disallowed").await,
+        ];
+        let config = Config {
+            allowed_submission_addresses: Some(HashSet::from([allowed.submission_address()])),
+            ..config()
         };
-        Self::get()
-            .settle
-            .with_label_values(&[&driver.name, label])
-            .observe(elapsed.as_secs_f64());
-    }
 
-    fn matched_unsettled(unsettled: HashSet<&domain::OrderUid>) {
-        if !unsettled.is_empty() {
-            tracing::debug!(?unsettled, "some orders were matched but not settled");
-        }
-        Self::get().matched_unsettled.inc_by(unsettled.len() as u64);
+        let solutions = run_competition(&config, &drivers).await;
+
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].driver().name, "allowed");
     }
 
-    fn post_processed(elapsed: Duration) {
-        Self::get()
-            .auction_postprocessing_time
-            .observe(elapsed.as_secs_f64());
+    #[tokio::test]
+    async fn filters_solutions_exceeding_driver_gas_budget() {
+        let reported = FakeDriver::start(Behavior {
+            scores: scores(&[1]),
+            gas: Some(150_000),
+            ..Default::default()
+        });
+        // solutions without reported gas are checked with the fallback of
+        // 300k gas
+        let unreported = FakeDriver::start(Behavior {
+            scores: scores(&[2]),
+            ..Default::default()
+        });
+        let drivers = [
+            reported.budgeted_driver("within", 200_000).await,
+            reported.budgeted_driver("exceeding", 100_000).await,
+            unreported.budgeted_driver("unreported", 200_000).await,
+            unreported.driver("unlimited").await,
+        ];
+
+        let mut solutions: Vec<_> = run_competition(&config(), &drivers)
+            .await
+            .into_iter()
+            .map(|solution| solution.driver().name.clone())
+            .collect();
+        solutions.sort();
+
+        assert_eq!(solutions, ["unlimited", "within"]);
     }
 
-    fn ran_maintenance(elapsed: Duration) {
-        Self::get()
-            .service_maintenance_time
-            .observe(elapsed.as_secs_f64());
+    #[tokio::test]
+    async fn settlements_exceeding_driver_gas_budget_get_rejected() {
+        let fake = FakeDriver::start(Default::default());
+        let budgeted = fake.budgeted_driver("budgeted", 200_000).await;
+        let unlimited = fake.driver("unlimited").await;
+        let gas = |gas: u64| eth::Gas(gas.into());
+
+        assert!(!exceeds_gas_budget(&budgeted, gas(200_000)));
+        assert!(exceeds_gas_budget(&budgeted, gas(200_001)));
+        assert!(!exceeds_gas_budget(&unlimited, gas(u64::MAX)));
     }
 
-    fn single_run_completed(elapsed: Duration) {
-        Self::get().single_run_time.observe(elapsed.as_secs_f64());
+    #[tokio::test]
+    async fn replays_stay_out_of_live_metrics() {
+        let fake = FakeDriver::start(Behavior {
+            scores: scores(&[1]),
+            ..Default::default()
+        });
+        let drivers = [fake.driver("replayed").await];
+        let solve = |kind: AuctionKind| {
+            Metrics::get()
+                .solve
+                .with_label_values(&["replayed", "success", kind.label()])
+                .get_sample_count()
+        };
+
+        let (solutions, _) = collect_solutions(
+            &config(),
+            &drivers,
+            &SolverParticipationGuard::allow_all(),
+            &auction(),
+            &HashSet::new(),
+            None,
+            weth(),
+            &seeded_rng(),
+            AuctionKind::Replay,
+        )
+        .await;
+
+        // replays get solved as usual but only tracked with their own label
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solve(AuctionKind::Replay), 1);
+        assert_eq!(solve(AuctionKind::Regular), 0);
+        assert_eq!(
+            Metrics::get()
+                .solutions
+                .with_label_values(&["replayed", "success"])
+                .get(),
+            0
+        );
     }
 
-    fn auction_ready(init_block_timestamp: Instant) {
-        Self::get()
-            .current_block_delay
-            .observe(init_block_timestamp.elapsed().as_secs_f64())
+    #[tokio::test]
+    async fn ignores_failing_drivers() {
+        let failing = FakeDriver::start(Behavior {
+            error: Some(axum::http::StatusCode::BAD_REQUEST),
+            ..Default::default()
+        });
+        let drivers = [failing.driver("failing").await];
+
+        assert!(run_competition(&config(), &drivers).await.is_empty());
+
+        failing.set_behavior(Behavior {
+            scores: scores(&[1]),
+            ..Default::default()
+        });
+        assert_eq!(run_competition(&config(), &drivers).await.len(), 1);
     }
-}
 
-pub mod observe {
-    use {
-        crate::domain::{
-            self,
-            competition::{Unranked, winner_selection::Ranking},
-        },
-        std::collections::HashSet,
-    };
+    #[cfg(feature = "chaos")]
+    #[tokio::test]
+    async fn injected_latency_times_out_drivers() {
+        let fake = FakeDriver::start(Behavior {
+            scores: scores(&[1]),
+            ..Default::default()
+        });
+        let mut driver = Arc::into_inner(fake.driver("slow").await).unwrap();
+        driver.inject_latency = Some(Duration::from_secs(5));
+        let config = Config {
+            solve_deadline: Duration::from_millis(500),
+            ..config()
+        };
 
-    pub fn log_auction_delta(previous: &Option<domain::Auction>, current: &domain::Auction) {
-        let previous_uids = match previous {
-            Some(previous) => previous
-                .orders
-                .iter()
-                .map(|order| order.uid)
-                .collect::<HashSet<_>>(),
-            None => HashSet::new(),
+        let start = Instant::now();
+        let (solutions, participation) = collect_solutions(
+            &config,
+            &[Arc::new(driver)],
+            &SolverParticipationGuard::allow_all(),
+            &auction(),
+            &HashSet::new(),
+            None,
+            weth(),
+            &seeded_rng(),
+            AuctionKind::Regular,
+        )
+        .await;
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(solutions.is_empty());
+        assert_eq!(participation.0[0].outcome, participation::Outcome::Timeout);
+    }
+
+    #[tokio::test]
+    async fn records_driver_participation() {
+        let proposing = FakeDriver::start(Behavior {
+            scores: scores(&[1, 2, 3]),
+            ..Default::default()
+        });
+        let slow = FakeDriver::start(Behavior {
+            scores: scores(&[4]),
+            delay: Duration::from_secs(5),
+            ..Default::default()
+        });
+        let empty = FakeDriver::start(Behavior::default());
+        let failing = FakeDriver::start(Behavior {
+            error: Some(axum::http::StatusCode::BAD_REQUEST),
+            ..Default::default()
+        });
+        let config = Config {
+            solve_deadline: Duration::from_millis(500),
+            max_solutions_per_solver: NonZeroUsize::new(2).unwrap(),
+            ..config()
         };
-        let current_uids = current
-            .orders
+        let drivers = [
+            proposing.driver("proposing").await,
+            slow.driver("slow").await,
+            empty.driver("empty").await,
+            failing.driver("failing").await,
+        ];
+
+        let (_, participation) = collect_solutions(
+            &config,
+            &drivers,
+            &SolverParticipationGuard::allow_all(),
+            &auction(),
+            &HashSet::new(),
+            None,
+            weth(),
+            &seeded_rng(),
+            AuctionKind::Regular,
+        )
+        .await;
+
+        let outcomes: Vec<_> = participation
+            .0
             .iter()
-            .map(|order| order.uid)
-            .collect::<HashSet<_>>();
-        let added = current_uids.difference(&previous_uids);
-        let removed = previous_uids.difference(&current_uids);
-        tracing::debug!(
-            id = current.id,
-            added = ?added,
-            "New orders in auction"
-        );
-        tracing::debug!(
-            id = current.id,
-            removed = ?removed,
-            "Orders no longer in auction"
+            .map(|participation| {
+                (
+                    participation.driver.as_str(),
+                    participation.outcome,
+                    participation.accepted,
+                )
+            })
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                ("proposing", participation::Outcome::Proposed(3), 2),
+                ("slow", participation::Outcome::Timeout, 0),
+                ("empty", participation::Outcome::NoSolutions, 0),
+                ("failing", participation::Outcome::Error, 0),
+            ]
         );
     }
 
-    pub fn solutions(solutions: &[domain::competition::Participant<Unranked>]) {
-        if solutions.is_empty() {
-            tracing::info!("no solutions for auction");
-        }
-        for participant in solutions {
-            tracing::debug!(
-                driver = %participant.driver().name,
-                orders = ?participant.solution().order_ids(),
-                solution = %participant.solution().id(),
-                "proposed solution"
-            );
-        }
-    }
-
-    /// Records metrics for the matched but unsettled orders.
-    pub fn unsettled(ranking: &Ranking, auction: &domain::Auction) {
-        let mut non_winning_orders = {
-            let winning_orders = ranking
-                .winners()
-                .flat_map(|p| p.solution().order_ids())
-                .collect::<HashSet<_>>();
-            ranking
-                .ranked()
-                .flat_map(|p| p.solution().order_ids())
-                .filter(|uid| !winning_orders.contains(uid))
-                .collect::<HashSet<_>>()
+    #[tokio::test]
+    async fn skips_drivers_without_allowed_orders() {
+        let fake = FakeDriver::start(Behavior::default());
+        let config = Config {
+            solve_deadline: Duration::from_millis(500),
+            ..config()
         };
-        // Report orders that were part of a non-winning solution candidate
-        // but only if they were part of the auction (filter out jit orders)
-        let auction_uids = auction.orders.iter().map(|o| o.uid).collect::<HashSet<_>>();
-        non_winning_orders.retain(|uid| auction_uids.contains(uid));
-        super::Metrics::matched_unsettled(non_winning_orders);
+        let drivers = [
+            fake.driver("unrestricted").await,
+            fake.restricted_driver("restricted", Some(HashSet::new()))
+                .await,
+        ];
+
+        let (_, participation) = collect_solutions(
+            &config,
+            &drivers,
+            &SolverParticipationGuard::allow_all(),
+            &auction(),
+            &HashSet::new(),
+            None,
+            weth(),
+            &seeded_rng(),
+            AuctionKind::Regular,
+        )
+        .await;
+
+        let outcomes: Vec<_> = participation
+            .0
+            .iter()
+            .map(|participation| (participation.driver.as_str(), participation.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                ("unrestricted", participation::Outcome::NoSolutions),
+                ("restricted", participation::Outcome::Skipped),
+            ]
+        );
+        assert!(participation.any_responded());
     }
 }
@@ -0,0 +1,437 @@
+//! Executes the settlements of winning solutions in background tasks and keeps
+//! track of them so that all in-flight settlements can be observed and
+//! cancelled in one place.
+
+use {
+    super::{SettleError, Submitter},
+    crate::{
+        domain::{
+            auction::Id,
+            competition::Solution,
+            eth::{self, TxId},
+        },
+        infra,
+    },
+    futures::future::{BoxFuture, Either},
+    itertools::Itertools,
+    serde::Serialize,
+    serde_with::{DurationMilliSeconds, serde_as},
+    std::{
+        collections::HashMap,
+        future::Future,
+        sync::{
+            Arc,
+            Mutex,
+            atomic::{AtomicU64, Ordering},
+        },
+        time::{Duration, Instant},
+    },
+    tokio::task::{AbortHandle, JoinHandle},
+};
+
+/// Winning solution whose settlement should get executed.
+pub struct ExecutionRequest {
+    pub auction_id: Id,
+    pub driver: Arc<infra::Driver>,
+    pub solution: Solution,
+    /// Position of the solution in the ranking.
+    pub solution_uid: usize,
+    /// Last block the settlement may get included in.
+    pub block_deadline: u64,
+    /// When the run loop iteration that cut the auction started.
+    pub single_run_start: Instant,
+    /// Gas the settlement is expected to use.
+    pub gas: eth::Gas,
+    /// Lets the fallback submitter take over if the winning driver times out.
+    pub handoff: Option<Handoff>,
+}
+
+/// What the fallback submitter needs to submit a winning solution in place of
+/// the driver that proposed it.
+pub struct Handoff {
+    pub driver: Arc<infra::Driver>,
+    /// Uninternalized calldata of the solution as revealed by the winning
+    /// driver. The fallback submitter can't reveal it since it never saw the
+    /// solution.
+    pub calldata: Vec<u8>,
+}
+
+/// Settlement that is currently being executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveExecution {
+    pub auction_id: Id,
+    pub solver: eth::Address,
+    pub driver: String,
+    pub solution_uid: usize,
+    pub block_deadline: u64,
+    pub started_at: Instant,
+}
+
+struct Entry {
+    id: u64,
+    execution: ActiveExecution,
+    abort: AbortHandle,
+}
+
+/// Registry of the settlements being executed, keyed by auction and solver.
+/// A solver can win an auction with several solutions so each key can have
+/// several executions.
+#[derive(Default)]
+pub struct SettlementExecutor {
+    next_id: AtomicU64,
+    executions: Mutex<HashMap<(Id, eth::Address), Vec<Entry>>>,
+}
+
+impl SettlementExecutor {
+    /// Spawns the future `execute` builds for the request and tracks it until
+    /// it finishes or gets cancelled.
+    pub fn submit<F>(
+        self: &Arc<Self>,
+        request: ExecutionRequest,
+        execute: impl FnOnce(ExecutionRequest) -> F,
+    ) -> JoinHandle<Option<TxId>>
+    where
+        F: Future<Output = Option<TxId>> + Send + 'static,
+    {
+        let execution = ActiveExecution {
+            auction_id: request.auction_id,
+            solver: request.solution.solver(),
+            driver: request.driver.name.clone(),
+            solution_uid: request.solution_uid,
+            block_deadline: request.block_deadline,
+            started_at: Instant::now(),
+        };
+        let key = (execution.auction_id, execution.solver);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let future = execute(request);
+        let executor = self.clone();
+
+        // The lock is held until the execution is registered so it can't get
+        // unregistered before that even if it finishes right away.
+        let mut executions = self.executions.lock().unwrap();
+        let handle = tokio::spawn(async move {
+            let result = future.await;
+            executor.unregister(key, id);
+            result
+        });
+        executions.entry(key).or_default().push(Entry {
+            id,
+            execution,
+            abort: handle.abort_handle(),
+        });
+        handle
+    }
+
+    fn unregister(&self, key: (Id, eth::Address), id: u64) {
+        let mut executions = self.executions.lock().unwrap();
+        if let Some(entries) = executions.get_mut(&key) {
+            entries.retain(|entry| entry.id != id);
+            if entries.is_empty() {
+                executions.remove(&key);
+            }
+        }
+    }
+
+    /// Settlements that are currently being executed, oldest first.
+    pub fn active_executions(&self) -> Vec<ActiveExecution> {
+        self.executions
+            .lock()
+            .unwrap()
+            .values()
+            .flatten()
+            .map(|entry| entry.execution.clone())
+            .sorted_by_key(|execution| execution.started_at)
+            .collect()
+    }
+
+    /// Aborts all executions of the auction, e.g. on shutdown. Nothing gets
+    /// recorded about their outcome and releasing their in-flight orders is up
+    /// to the caller. Returns how many executions got cancelled.
+    pub fn cancel(&self, auction_id: Id) -> usize {
+        let mut cancelled = 0;
+        self.executions
+            .lock()
+            .unwrap()
+            .retain(|(auction, _), entries| {
+                if *auction != auction_id {
+                    return true;
+                }
+                for entry in entries.iter() {
+                    entry.abort.abort();
+                }
+                cancelled += entries.len();
+                false
+            });
+        cancelled
+    }
+}
+
+impl observe::metrics::StatusReporting for SettlementExecutor {
+    fn status(&self) -> serde_json::Value {
+        let executions: Vec<_> = self
+            .active_executions()
+            .into_iter()
+            .map(|execution| Status {
+                auction_id: execution.auction_id,
+                solver: execution.solver.0,
+                driver: execution.driver,
+                solution_uid: execution.solution_uid,
+                block_deadline: execution.block_deadline,
+                running_for: execution.started_at.elapsed(),
+            })
+            .collect();
+        serde_json::to_value(executions).expect("executions are serializable")
+    }
+}
+
+#[serde_as]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Status {
+    auction_id: Id,
+    solver: eth::H160,
+    driver: String,
+    solution_uid: usize,
+    block_deadline: u64,
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    running_for: Duration,
+}
+
+/// Races the `/settle` request to the winning driver against the settlement
+/// transaction appearing on chain. Whenever the transaction shows up first it
+/// decides the outcome. If the driver fails first `fallback` may hand the
+/// settlement to another submitter while the search for the transaction
+/// continues. Otherwise the driver's error is the outcome.
+pub(super) async fn drive<'a, F, Fut>(
+    dispatch: BoxFuture<'a, Result<(), SettleError>>,
+    wait_for_settlement_transaction: BoxFuture<'a, Result<TxId, SettleError>>,
+    fallback: F,
+) -> (Result<TxId, SettleError>, Submitter)
+where
+    F: FnOnce(SettleError, BoxFuture<'a, Result<TxId, SettleError>>) -> Result<Fut, SettleError>,
+    Fut: Future<Output = Result<TxId, SettleError>>,
+{
+    match futures::future::select(wait_for_settlement_transaction, dispatch).await {
+        Either::Left((result, _)) => (result, Submitter::Primary),
+        Either::Right((Ok(()), wait_for_settlement_transaction)) => {
+            (wait_for_settlement_transaction.await, Submitter::Primary)
+        }
+        Either::Right((Err(err), wait_for_settlement_transaction)) => {
+            match fallback(err, wait_for_settlement_transaction) {
+                Ok(handoff) => (handoff.await, Submitter::Fallback),
+                Err(err) => (Err(err), Submitter::Primary),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            domain::competition::Score,
+            infra::{http::HttpError, solvers::fake::FakeDriver},
+        },
+        futures::FutureExt,
+        tokio::sync::oneshot,
+    };
+
+    fn tx(byte: u8) -> TxId {
+        TxId(eth::H256([byte; 32]))
+    }
+
+    fn timeout() -> SettleError {
+        SettleError::Http(HttpError::Timeout)
+    }
+
+    type NoFallback = std::future::Ready<Result<TxId, SettleError>>;
+
+    #[tokio::test]
+    async fn transaction_decides_outcome() {
+        // the transaction shows up before the driver responds
+        let (result, submitter) = drive(
+            futures::future::pending().boxed(),
+            async { Ok(tx(1)) }.boxed(),
+            |_, _| -> Result<NoFallback, _> { unreachable!("driver never failed") },
+        )
+        .await;
+        assert_eq!(result.unwrap(), tx(1));
+        assert!(matches!(submitter, Submitter::Primary));
+
+        // the driver responds before the transaction shows up
+        let (sender, receiver) = oneshot::channel();
+        let (result, submitter) = drive(
+            async move {
+                sender.send(()).unwrap();
+                Ok(())
+            }
+            .boxed(),
+            async move {
+                receiver.await.unwrap();
+                Ok(tx(2))
+            }
+            .boxed(),
+            |_, _| -> Result<NoFallback, _> { unreachable!("driver never failed") },
+        )
+        .await;
+        assert_eq!(result.unwrap(), tx(2));
+        assert!(matches!(submitter, Submitter::Primary));
+    }
+
+    #[tokio::test]
+    async fn failed_drivers_hand_over_to_fallback() {
+        let (result, submitter) = drive(
+            async { Err(timeout()) }.boxed(),
+            futures::future::pending().boxed(),
+            |err, _| {
+                assert!(matches!(err, SettleError::Http(HttpError::Timeout)));
+                Ok(async { Ok(tx(3)) })
+            },
+        )
+        .await;
+        assert_eq!(result.unwrap(), tx(3));
+        assert!(matches!(submitter, Submitter::Fallback));
+
+        let (result, submitter) = drive(
+            async { Err(timeout()) }.boxed(),
+            futures::future::pending().boxed(),
+            |err, _| -> Result<NoFallback, _> { Err(err) },
+        )
+        .await;
+        assert!(matches!(result, Err(SettleError::Http(HttpError::Timeout))));
+        assert!(matches!(submitter, Submitter::Primary));
+    }
+
+    #[cfg(feature = "chaos")]
+    #[tokio::test]
+    async fn slow_drivers_time_out_at_the_deadline() {
+        use crate::infra::solvers::dto::settle;
+
+        let fake = FakeDriver::start(Default::default());
+        let mut driver = Arc::into_inner(fake.driver("slow").await).unwrap();
+        driver.inject_latency = Some(Duration::from_secs(5));
+        let request = settle::Request {
+            solution_id: 0,
+            submission_deadline_latest_block: 100,
+            auction_id: 1,
+            rebroadcast: false,
+            calldata: None,
+            score: eth::U256::one(),
+        };
+
+        let start = Instant::now();
+        let (result, _) = drive(
+            async {
+                driver
+                    .settle(&request, Duration::from_secs(10))
+                    .await
+                    .map_err(SettleError::Http)
+            }
+            .boxed(),
+            async {
+                // the deadline passes without the transaction showing up
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Err(SettleError::Timeout)
+            }
+            .boxed(),
+            |err, _| -> Result<NoFallback, _> { Err(err) },
+        )
+        .await;
+
+        assert!(matches!(result, Err(SettleError::Timeout)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    async fn request(auction_id: Id, solver: u8) -> ExecutionRequest {
+        let driver = FakeDriver::start(Default::default())
+            .driver(&format!("solver{solver}"))
+            .await;
+        ExecutionRequest {
+            auction_id,
+            solution: Solution::new(
+                0,
+                eth::Address(eth::H160([solver; 20])),
+                Score::try_new(eth::Ether(eth::U256::one())).unwrap(),
+                Default::default(),
+                Default::default(),
+                None,
+            ),
+            driver,
+            solution_uid: 0,
+            block_deadline: 100,
+            single_run_start: Instant::now(),
+            gas: eth::Gas(100_000.into()),
+            handoff: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn tracks_executions_until_they_finish() {
+        let executor = Arc::new(SettlementExecutor::default());
+        let (sender, receiver) = oneshot::channel();
+        let finished = executor.submit(request(1, 1).await, |_| async move {
+            receiver.await.unwrap();
+            Some(tx(1))
+        });
+        // solvers can win several times within the same auction
+        let pending = executor.submit(request(1, 1).await, |_| futures::future::pending());
+
+        let active = executor.active_executions();
+        assert_eq!(active.len(), 2);
+        assert!(active.iter().all(|execution| execution.auction_id == 1
+            && execution.solver == eth::Address(eth::H160([1; 20]))
+            && execution.driver == "solver1"));
+
+        sender.send(()).unwrap();
+        assert_eq!(finished.await.unwrap(), Some(tx(1)));
+        assert_eq!(executor.active_executions().len(), 1);
+        pending.abort();
+    }
+
+    #[tokio::test]
+    async fn cancels_executions_of_auction() {
+        let executor = Arc::new(SettlementExecutor::default());
+        let cancelled = [
+            executor.submit(request(1, 1).await, |_| futures::future::pending()),
+            executor.submit(request(1, 2).await, |_| futures::future::pending()),
+        ];
+        let (sender, receiver) = oneshot::channel();
+        let other = executor.submit(request(2, 1).await, |_| async move {
+            receiver.await.unwrap();
+            Some(tx(2))
+        });
+
+        assert_eq!(executor.cancel(1), 2);
+        for handle in cancelled {
+            assert!(handle.await.unwrap_err().is_cancelled());
+        }
+        let active = executor.active_executions();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].auction_id, 2);
+        assert_eq!(executor.cancel(1), 0);
+
+        sender.send(()).unwrap();
+        assert_eq!(other.await.unwrap(), Some(tx(2)));
+        assert!(executor.active_executions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reports_active_executions() {
+        use observe::metrics::StatusReporting;
+
+        let executor = Arc::new(SettlementExecutor::default());
+        assert_eq!(executor.status(), serde_json::json!([]));
+
+        let pending = executor.submit(request(1, 1).await, |_| futures::future::pending());
+        let status = executor.status();
+        assert_eq!(status[0]["auctionId"], 1);
+        assert_eq!(status[0]["driver"], "solver1");
+        assert_eq!(status[0]["blockDeadline"], 100);
+        assert!(status[0]["runningFor"].is_u64());
+
+        executor.cancel(1);
+        assert!(pending.await.unwrap_err().is_cancelled());
+        assert_eq!(executor.status(), serde_json::json!([]));
+    }
+}
@@ -6,13 +6,20 @@
 //! and runs a solver competition with that auction, instead of building one).
 //! The run-loop will report and log the winner **without** actually executing
 //! any settlements on-chain.
+//!
+//! If configured with a candidate driver the shadow autopilot also compares
+//! its results with the production competition of the same auction. This
+//! shows whether the candidate would have won in production and detects when
+//! the scoring code of both deployments drifted apart by ranking the
+//! production solutions with the shadow's arbitrator.
 
 use {
     crate::{
         domain::{
             self,
-            competition::{Participant, Unranked, winner_selection},
-            eth::WrappedNativeToken,
+            auction,
+            competition::{Participant, Score, Solution, TradedOrder, Unranked, winner_selection},
+            eth::{self, WrappedNativeToken},
         },
         infra::{
             self,
@@ -25,13 +32,26 @@ use {
     anyhow::Context,
     ethrpc::block_stream::CurrentBlockWatcher,
     itertools::Itertools,
+    model::solver_competition_v2,
+    number::db::u256_to_big_decimal,
+    primitive_types::{H160, U256},
     shared::token_list::AutoUpdatingTokenList,
-    std::{num::NonZeroUsize, sync::Arc, time::Duration},
+    std::{
+        collections::{HashMap, HashSet},
+        num::NonZeroUsize,
+        sync::Arc,
+        time::Duration,
+    },
     tracing::{Instrument, instrument},
 };
 
+/// How often to try loading the production competition. Production stores it
+/// only after its own competition finished.
+const PRODUCTION_RESULT_ATTEMPTS: usize = 10;
+const PRODUCTION_RESULT_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
 pub struct RunLoop {
-    orderbook: infra::shadow::Orderbook,
+    orderbook: Arc<infra::shadow::Orderbook>,
     drivers: Vec<Arc<infra::Driver>>,
     trusted_tokens: AutoUpdatingTokenList,
     auction: domain::auction::Id,
@@ -39,7 +59,19 @@ pub struct RunLoop {
     solve_deadline: Duration,
     liveness: Arc<Liveness>,
     current_block: CurrentBlockWatcher,
-    winner_selection: Box<dyn winner_selection::Arbitrator>,
+    winner_selection: Arc<dyn winner_selection::Arbitrator>,
+    weth: WrappedNativeToken,
+    comparison: Option<Arc<ProductionComparison>>,
+}
+
+/// Configuration for comparing the shadow competition with production.
+pub struct ProductionComparison {
+    /// Name of the driver under evaluation.
+    pub candidate: String,
+    /// Submission addresses the drivers use in production. The shadow
+    /// drivers use random addresses so solutions are matched by these.
+    pub production_addresses: HashMap<String, H160>,
+    pub db: crate::database::Postgres,
 }
 
 impl RunLoop {
@@ -53,16 +85,24 @@ impl RunLoop {
         current_block: CurrentBlockWatcher,
         max_winners_per_auction: NonZeroUsize,
         weth: WrappedNativeToken,
+        comparison: Option<ProductionComparison>,
     ) -> Self {
         Self {
             winner_selection: match max_winners_per_auction.get() {
-                0 | 1 => Box::new(winner_selection::max_score::Config),
-                n => Box::new(winner_selection::combinatorial::Config {
+                0 | 1 => Arc::new(winner_selection::max_score::Config::default()),
+                n => Arc::new(winner_selection::combinatorial::Config {
                     max_winners: n,
+                    max_winners_per_submission_address: None,
                     weth,
+                    treat_native_as_weth: true,
+                    score_tie_epsilon: Default::default(),
+                    objective: Default::default(),
+                    merging: Default::default(),
+                    fairness_threshold_floor: Default::default(),
                 }),
             },
-            orderbook,
+            weth,
+            orderbook: Arc::new(orderbook),
             drivers,
             trusted_tokens,
             auction: 0,
@@ -70,6 +110,7 @@ impl RunLoop {
             solve_deadline,
             liveness,
             current_block,
+            comparison: comparison.map(Arc::new),
         }
     }
 
@@ -143,7 +184,7 @@ impl RunLoop {
 
         for participant in ranking.ranked() {
             let is_winner = participant.is_winner();
-            let reference_score = scores.get(&participant.driver().submission_address);
+            let reference_score = scores.get(&participant.solver_address());
             let driver = participant.driver();
             let reward = reference_score
                 .map(|reference| total_score - *reference)
@@ -165,17 +206,46 @@ impl RunLoop {
                 .with_label_values(&[&driver.name])
                 .inc_by(u64::from(is_winner))
         }
+
+        if let Some(comparison) = &self.comparison {
+            // solutions of observe-only drivers are neither part of the ranked
+            // solutions nor of the production competition data so they don't
+            // affect the comparison
+            let shadow = ranking
+                .ranked()
+                .map(|p| (p.driver().name.clone(), p.solution().score().get().0))
+                .collect();
+            tokio::spawn(
+                Arc::clone(comparison)
+                    .compare(
+                        Arc::clone(&self.orderbook),
+                        Arc::clone(&self.winner_selection),
+                        auction.clone(),
+                        self.drivers.clone(),
+                        shadow,
+                    )
+                    .instrument(tracing::Span::current()),
+            );
+        }
     }
 
     /// Runs the solver competition, making all configured drivers participate.
     #[instrument(skip_all)]
     async fn competition(&self, auction: &domain::Auction) -> Vec<Participant<Unranked>> {
-        let request = solve::Request::new(auction, &self.trusted_tokens.all(), self.solve_deadline);
+        let request = solve::Request::new(
+            auction,
+            &self.trusted_tokens.all(),
+            self.solve_deadline,
+            false,
+            None,
+            Default::default(),
+            None,
+        );
 
         futures::future::join_all(
             self.drivers
                 .iter()
-                .map(|driver| self.participate(Arc::clone(driver), request.clone(), auction.id)),
+                .map(|driver| self.participate(Arc::clone(driver), request.clone(), auction)),
         )
         .await
         .into_iter()
@@ -189,15 +259,17 @@ impl RunLoop {
         &self,
         driver: Arc<infra::Driver>,
         request: solve::Request,
-        auction_id: i64,
+        auction: &domain::Auction,
     ) -> Vec<Participant<Unranked>> {
+        let auction_id = auction.id;
         let solutions = match self.fetch_solutions(&driver, request).await {
             Ok(response) => {
                 Metrics::get()
                     .results
                     .with_label_values(&[&driver.name, "ok"])
                     .inc();
-                response.into_domain()
+                // The shadow competition only drops executions with zero amounts.
+                response.into_domain(&auction.prices, Default::default(), self.weth)
             }
             Err(err) => {
                 Metrics::get()
@@ -258,6 +330,235 @@ impl RunLoop {
     }
 }
 
+impl ProductionComparison {
+    /// Compares the shadow ranking of the drivers' solutions (best first) with
+    /// the production competition of the same auction once it is available.
+    async fn compare(
+        self: Arc<Self>,
+        orderbook: Arc<infra::shadow::Orderbook>,
+        arbitrator: Arc<dyn winner_selection::Arbitrator>,
+        auction: domain::Auction,
+        drivers: Vec<Arc<infra::Driver>>,
+        shadow: Vec<(String, U256)>,
+    ) {
+        let auction_id = auction.id;
+        let mut production = None;
+        for _ in 0..PRODUCTION_RESULT_ATTEMPTS {
+            match orderbook.solver_competition(auction_id).await {
+                Ok(Some(competition)) => {
+                    production = Some(competition);
+                    break;
+                }
+                Ok(None) => {}
+                Err(err) => tracing::debug!(?err, "failed to fetch production competition"),
+            }
+            tokio::time::sleep(PRODUCTION_RESULT_RETRY_INTERVAL).await;
+        }
+        let Some(production) = production else {
+            tracing::debug!("production competition not available");
+            return;
+        };
+
+        let drivers = drivers
+            .into_iter()
+            .filter_map(|driver| Some((*self.production_addresses.get(&driver.name)?, driver)))
+            .collect();
+        let rescored = Rescored::new(&production, &auction, arbitrator.as_ref(), &drivers);
+        let comparison = Comparison::new(&self.candidate, &shadow, &production, &rescored);
+        tracing::info!(candidate = %self.candidate, ?comparison, "compared with production");
+        let metrics = Metrics::get();
+        if comparison.would_win() {
+            metrics
+                .would_win_total
+                .with_label_values(&[&self.candidate])
+                .inc();
+        }
+        if let Some(delta) = comparison.score_delta() {
+            let direction = if delta >= 0. { "ahead" } else { "behind" };
+            metrics
+                .score_delta
+                .with_label_values(&[&self.candidate, direction])
+                .observe(delta.abs());
+        }
+        if comparison.ranking_diverged {
+            tracing::warn!(
+                ?rescored,
+                "shadow ranked production solutions differently than production, scoring might \
+                 have drifted"
+            );
+            metrics.ranking_divergences.inc();
+        }
+
+        let row = database::shadow_competition_comparisons::Comparison {
+            auction_id,
+            candidate: self.candidate.clone(),
+            candidate_score: comparison.candidate_score.as_ref().map(u256_to_big_decimal),
+            production_score: comparison
+                .production_score
+                .as_ref()
+                .map(u256_to_big_decimal),
+            would_win: comparison.would_win(),
+            ranking_diverged: comparison.ranking_diverged,
+        };
+        let result = async {
+            let mut ex = self.db.pool.acquire().await?;
+            database::shadow_competition_comparisons::insert(&mut ex, &row).await
+        };
+        if let Err(err) = result.await {
+            tracing::warn!(?err, "failed to store comparison with production");
+        }
+    }
+}
+
+/// Outcome of comparing the shadow competition with production.
+#[derive(Debug, PartialEq)]
+struct Comparison {
+    /// Best score of the candidate in the shadow competition.
+    candidate_score: Option<U256>,
+    /// Best winning score in production.
+    production_score: Option<U256>,
+    /// Whether the shadow's arbitrator ranked the production solutions in a
+    /// different order than production did.
+    ranking_diverged: bool,
+}
+
+/// The production solutions ranked by the shadow's arbitrator.
+#[derive(Debug, Default)]
+struct Rescored {
+    /// Indices of the production solutions that could be rescored, i.e. the
+    /// ones of drivers known to the shadow that only traded auction orders.
+    considered: HashSet<usize>,
+    /// Indices of the rescored solutions that didn't get filtered out in the
+    /// order the shadow ranked them.
+    ranked: Vec<usize>,
+}
+
+impl Rescored {
+    fn new(
+        production: &solver_competition_v2::Response,
+        auction: &domain::Auction,
+        arbitrator: &dyn winner_selection::Arbitrator,
+        drivers: &HashMap<H160, Arc<infra::Driver>>,
+    ) -> Self {
+        let orders: HashMap<_, _> = auction
+            .orders
+            .iter()
+            .map(|order| (order.uid, order))
+            .collect();
+        let participants: Vec<_> = production
+            .solutions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, solution)| {
+                let driver = drivers.get(&solution.solver_address)?;
+                // the production data doesn't contain the limits of JIT orders
+                // so solutions trading them can't be rescored
+                let traded = solution
+                    .orders
+                    .iter()
+                    .map(|order| {
+                        let uid = domain::OrderUid(order.id.0);
+                        let limits = orders.get(&uid)?;
+                        Some((
+                            uid,
+                            TradedOrder {
+                                side: limits.side,
+                                sell: limits.sell,
+                                buy: limits.buy,
+                                executed_sell: order.sell_amount.into(),
+                                executed_buy: order.buy_amount.into(),
+                            },
+                        ))
+                    })
+                    .collect::<Option<HashMap<_, _>>>()?;
+                let prices = solution
+                    .clearing_prices
+                    .iter()
+                    .map(|(token, price)| {
+                        let price = auction::Price::try_new(eth::Ether(*price)).ok()?;
+                        Some((eth::TokenAddress(*token), price))
+                    })
+                    .collect::<Option<auction::Prices>>()?;
+                let solution = Solution::new(
+                    u64::try_from(index).ok()?,
+                    eth::Address(solution.solver_address),
+                    Score::try_new(eth::Ether(solution.score)).ok()?,
+                    traded,
+                    prices,
+                    None,
+                );
+                Some(Participant::new(solution, Arc::clone(driver)))
+            })
+            .collect();
+
+        let considered = participants
+            .iter()
+            .map(|participant| participant.solution().id() as usize)
+            .collect();
+        let ranked = arbitrator
+            .arbitrate(participants, auction)
+            .ranked()
+            .map(|participant| participant.solution().id() as usize)
+            .collect();
+        Self { considered, ranked }
+    }
+}
+
+impl Comparison {
+    fn new(
+        candidate: &str,
+        shadow: &[(String, U256)],
+        production: &solver_competition_v2::Response,
+        rescored: &Rescored,
+    ) -> Self {
+        let candidate_score = shadow
+            .iter()
+            .filter(|(driver, _)| driver == candidate)
+            .map(|(_, score)| *score)
+            .max();
+        let production_score = production
+            .solutions
+            .iter()
+            .filter(|solution| solution.is_winner)
+            .map(|solution| solution.score)
+            .max();
+
+        let production_order: Vec<_> = production
+            .solutions
+            .iter()
+            .enumerate()
+            .filter(|(index, solution)| {
+                !solution.filtered_out && rescored.considered.contains(index)
+            })
+            .sorted_by_key(|(_, solution)| solution.ranking)
+            .map(|(index, _)| index)
+            .collect();
+
+        Self {
+            candidate_score,
+            production_score,
+            ranking_diverged: production_order != rescored.ranked,
+        }
+    }
+
+    /// Whether the candidate's best solution outscored the production winner.
+    fn would_win(&self) -> bool {
+        match (self.candidate_score, self.production_score) {
+            (Some(candidate), Some(production)) => candidate > production,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// Difference between the candidate's best score and the production
+    /// winning score in units of the native token.
+    fn score_delta(&self) -> Option<f64> {
+        let candidate = self.candidate_score?.to_f64_lossy();
+        let production = self.production_score.unwrap_or_default().to_f64_lossy();
+        Some((candidate - production) / 1e18)
+    }
+}
+
 #[derive(prometheus_metric_storage::MetricStorage)]
 #[metric(subsystem = "shadow")]
 struct Metrics {
@@ -278,6 +579,22 @@ struct Metrics {
     /// Tracks the winner of every auction.
     #[metric(labels("driver"))]
     wins: prometheus::IntCounterVec,
+
+    /// How often the candidate would have won the production competition.
+    #[metric(labels("driver"))]
+    would_win_total: prometheus::IntCounterVec,
+
+    /// Absolute difference between the candidate's best score and the
+    /// production winning score in units of the native token. `direction`
+    /// tells whether the candidate was ahead or behind.
+    #[metric(
+        labels("driver", "direction"),
+        buckets(0.0001, 0.001, 0.01, 0.1, 1, 10)
+    )]
+    score_delta: prometheus::HistogramVec,
+
+    /// How often the shadow ranked drivers differently than production.
+    ranking_divergences: prometheus::IntCounter,
 }
 
 impl Metrics {
@@ -285,3 +602,133 @@ impl Metrics {
         Metrics::instance(metrics::get_storage_registry()).unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{boundary, domain::auction::ContentHash, infra::solvers::fake::FakeDriver},
+    };
+
+    fn production(solutions: &[(u64, u64, bool)]) -> solver_competition_v2::Response {
+        solver_competition_v2::Response {
+            solutions: solutions
+                .iter()
+                .enumerate()
+                .map(
+                    |(index, (solver, score, is_winner))| solver_competition_v2::Solution {
+                        solver_address: H160::from_low_u64_be(*solver),
+                        score: U256::from(*score),
+                        ranking: i64::try_from(index).unwrap() + 1,
+                        is_winner: *is_winner,
+                        ..Default::default()
+                    },
+                )
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn shadow(solutions: &[(&str, u64)]) -> Vec<(String, U256)> {
+        solutions
+            .iter()
+            .map(|(driver, score)| (driver.to_string(), U256::from(*score)))
+            .collect()
+    }
+
+    fn traded(uid: u8) -> solver_competition_v2::Order {
+        solver_competition_v2::Order {
+            id: model::order::OrderUid([uid; 56]),
+            sell_amount: 100.into(),
+            buy_amount: 100.into(),
+            buy_token: Default::default(),
+            sell_token: Default::default(),
+        }
+    }
+
+    #[test]
+    fn candidate_outscoring_production_would_win() {
+        let production = production(&[(1, 100, true), (2, 90, false)]);
+        let comparison = Comparison::new(
+            "candidate",
+            &shadow(&[("candidate", 120), ("a", 100), ("candidate", 50), ("b", 90)]),
+            &production,
+            &Rescored::default(),
+        );
+
+        assert_eq!(
+            comparison,
+            Comparison {
+                candidate_score: Some(120.into()),
+                production_score: Some(100.into()),
+                ranking_diverged: false,
+            }
+        );
+        assert!(comparison.would_win());
+        assert_eq!(comparison.score_delta(), Some(20. / 1e18));
+    }
+
+    #[test]
+    fn candidate_without_solutions_would_not_win() {
+        let comparison = Comparison::new(
+            "candidate",
+            &shadow(&[("a", 100)]),
+            &production(&[(1, 100, true)]),
+            &Rescored::default(),
+        );
+
+        assert_eq!(comparison.candidate_score, None);
+        assert!(!comparison.would_win());
+        assert_eq!(comparison.score_delta(), None);
+    }
+
+    #[tokio::test]
+    async fn detects_ranking_divergence_by_rescoring() {
+        let order = {
+            let mut order = boundary::order::to_domain(Default::default(), vec![], None);
+            order.uid = domain::OrderUid([1; 56]);
+            order
+        };
+        let auction = domain::Auction {
+            id: 0,
+            block: 0,
+            content_hash: ContentHash::new(std::slice::from_ref(&order), &Default::default(), &[]),
+            orders: Arc::new(vec![order]),
+            prices: Default::default(),
+            surplus_capturing_jit_order_owners: vec![],
+            quote_only: false,
+            prioritized_orders: Default::default(),
+        };
+        let fake = FakeDriver::start(Default::default());
+        let drivers = HashMap::from([
+            (H160::from_low_u64_be(1), fake.driver("a").await),
+            (H160::from_low_u64_be(2), fake.driver("b").await),
+        ]);
+        let arbitrator = winner_selection::max_score::Config::default();
+        let compare = |production: solver_competition_v2::Response| {
+            let rescored = Rescored::new(&production, &auction, &arbitrator, &drivers);
+            let comparison = Comparison::new("a", &[], &production, &rescored);
+            (rescored, comparison.ranking_diverged)
+        };
+
+        // solver 3 isn't known to the shadow and the last solution trades a
+        // JIT order so neither can be rescored
+        let mut same_order = production(&[
+            (1, 100, true),
+            (3, 95, false),
+            (2, 90, false),
+            (1, 80, false),
+        ]);
+        same_order.solutions[2].orders = vec![traded(1)];
+        same_order.solutions[3].orders = vec![traded(9)];
+        let (rescored, diverged) = compare(same_order);
+        assert_eq!(rescored.considered, HashSet::from([0, 2]));
+        assert_eq!(rescored.ranked, vec![0, 2]);
+        assert!(!diverged);
+
+        // production picked the lower score as winner
+        let (rescored, diverged) = compare(production(&[(2, 100, true), (1, 110, false)]));
+        assert_eq!(rescored.ranked, vec![1, 0]);
+        assert!(diverged);
+    }
+}
@@ -100,6 +100,8 @@ pub struct SolvableOrdersCache {
     cow_amm_registry: cow_amm::Registry,
     native_price_timeout: Duration,
     settlement_contract: H160,
+    /// Owners whose orders are exempt from the fairness check.
+    fairness_exempt_owners: HashSet<H160>,
 }
 
 type Balances = HashMap<Query, U256>;
@@ -107,6 +109,8 @@ type Balances = HashMap<Query, U256>;
 struct Inner {
     auction: domain::RawAuctionData,
     solvable_orders: boundary::SolvableOrders,
+    /// When the auction was built.
+    built_at: Instant,
 }
 
 impl SolvableOrdersCache {
@@ -125,6 +129,7 @@ impl SolvableOrdersCache {
         cow_amm_registry: cow_amm::Registry,
         native_price_timeout: Duration,
         settlement_contract: H160,
+        fairness_exempt_owners: HashSet<H160>,
     ) -> Arc<Self> {
         Arc::new(Self {
             min_order_validity_period,
@@ -142,15 +147,17 @@ impl SolvableOrdersCache {
             cow_amm_registry,
             native_price_timeout,
             settlement_contract,
+            fairness_exempt_owners,
         })
     }
 
-    pub async fn current_auction(&self) -> Option<domain::RawAuctionData> {
+    /// Returns the most recently built auction and when it was built.
+    pub async fn current_auction(&self) -> Option<(domain::RawAuctionData, Instant)> {
         self.cache
             .lock()
             .await
             .as_ref()
-            .map(|inner| inner.auction.clone())
+            .map(|inner| (inner.auction.clone(), inner.built_at))
     }
 
     /// Manually update solvable orders. Usually called by the background
@@ -160,9 +167,20 @@ impl SolvableOrdersCache {
     /// the case in unit tests, then concurrent calls might overwrite each
     /// other's results.
     pub async fn update(&self, block: u64) -> Result<()> {
+        self.update_from(block, false).await
+    }
+
+    /// Like [`Self::update`] but fetches all solvable orders from the database
+    /// instead of only the ones that changed since the last update. Used when
+    /// the cached state might be based on blocks that got reorged.
+    pub async fn full_update(&self, block: u64) -> Result<()> {
+        self.update_from(block, true).await
+    }
+
+    async fn update_from(&self, block: u64, full: bool) -> Result<()> {
         let start = Instant::now();
 
-        let db_solvable_orders = self.get_solvable_orders().await?;
+        let db_solvable_orders = self.get_solvable_orders(full).await?;
         tracing::trace!("fetched solvable orders from db");
 
         let orders = db_solvable_orders
@@ -272,29 +290,40 @@ impl SolvableOrdersCache {
             .collect::<Vec<_>>();
         let auction = domain::RawAuctionData {
             block,
-            orders: orders
-                .into_iter()
-                .map(|order| {
-                    let quote = db_solvable_orders
-                        .quotes
-                        .get(&order.metadata.uid.into())
-                        .cloned();
-                    self.protocol_fees
-                        .apply(order, quote, &surplus_capturing_jit_order_owners)
-                })
-                .collect(),
-            prices: prices
-                .into_iter()
-                .map(|(key, value)| {
-                    Price::try_new(value.into()).map(|price| (eth::TokenAddress(key), price))
-                })
-                .collect::<Result<_, _>>()?,
+            orders: Arc::new(
+                orders
+                    .into_iter()
+                    .map(|order| {
+                        let quote = db_solvable_orders
+                            .quotes
+                            .get(&order.metadata.uid.into())
+                            .cloned();
+                        let mut order = self.protocol_fees.apply(
+                            order,
+                            quote,
+                            &surplus_capturing_jit_order_owners,
+                        );
+                        order.fairness_exempt =
+                            self.fairness_exempt_owners.contains(&order.owner.0);
+                        order
+                    })
+                    .collect(),
+            ),
+            prices: Arc::new(
+                prices
+                    .into_iter()
+                    .map(|(key, value)| {
+                        Price::try_new(value.into()).map(|price| (eth::TokenAddress(key), price))
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
             surplus_capturing_jit_order_owners,
         };
 
         *self.cache.lock().await = Some(Inner {
             auction,
             solvable_orders: db_solvable_orders,
+            built_at: Instant::now(),
         });
 
         tracing::debug!(%block, "updated current auction cache");
@@ -332,7 +361,7 @@ impl SolvableOrdersCache {
     }
 
     /// Returns currently solvable orders.
-    async fn get_solvable_orders(&self) -> Result<SolvableOrders> {
+    async fn get_solvable_orders(&self, full: bool) -> Result<SolvableOrders> {
         let min_valid_to = now_in_epoch_seconds()
             + u32::try_from(self.min_order_validity_period.as_secs())
                 .context("min_order_validity_period is not u32")?;
@@ -342,7 +371,7 @@ impl SolvableOrdersCache {
         let fetch_orders = match &*lock {
             // Only use incremental query after cache already got initialized
             // because it's not optimized for very long durations.
-            Some(cache) => self
+            Some(cache) if !full => self
                 .persistence
                 .solvable_orders_after(
                     cache.solvable_orders.orders.clone(),
@@ -352,7 +381,7 @@ impl SolvableOrdersCache {
                     min_valid_to,
                 )
                 .boxed(),
-            None => self.persistence.all_solvable_orders(min_valid_to).boxed(),
+            _ => self.persistence.all_solvable_orders(min_valid_to).boxed(),
         };
 
         let mut orders = fetch_orders.await?;
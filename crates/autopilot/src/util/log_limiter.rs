@@ -0,0 +1,174 @@
+//! Rate limiting for log messages about failures that would otherwise repeat
+//! every auction (e.g. while a driver is down).
+
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    hash::Hash,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Tracks how often failures identified by a key occur within a window.
+/// Only the first occurrence per window is meant to get logged in full.
+/// Windows get closed lazily, either by the next occurrence of the same key
+/// or by [`LogLimiter::flush`], at which point a summary gets logged.
+pub struct LogLimiter<K> {
+    /// Name of the call site, included in the summaries.
+    name: &'static str,
+    window: Duration,
+    windows: Mutex<HashMap<K, Window>>,
+}
+
+struct Window {
+    start: Instant,
+    occurrences: usize,
+}
+
+/// How an occurrence of a failure should get logged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Occurrence {
+    /// First occurrence within the current window. Should get logged at warn
+    /// level with full detail.
+    First,
+    /// Repeated occurrence within the current window. Should get logged at
+    /// debug level. Counts occurrences within the window starting at 2.
+    Repeated(usize),
+}
+
+impl<K: Hash + Eq + Clone + Debug> LogLimiter<K> {
+    pub fn new(name: &'static str, window: Duration) -> Self {
+        Self {
+            name,
+            window,
+            windows: Default::default(),
+        }
+    }
+
+    /// Records an occurrence of the failure identified by `key` and logs a
+    /// summary if this closed the previous window of that key.
+    pub fn record(&self, key: K) -> Occurrence {
+        let (occurrence, closed) = self.record_at(key.clone(), Instant::now());
+        if let Some(occurrences) = closed {
+            self.summarize(&key, occurrences);
+        }
+        occurrence
+    }
+
+    /// Closes all windows that are over and logs a summary for each of them
+    /// that saw repeated occurrences.
+    pub fn flush(&self) {
+        for (key, occurrences) in self.flush_at(Instant::now()) {
+            self.summarize(&key, occurrences);
+        }
+    }
+
+    /// Returns how the occurrence should get logged and, if the previous
+    /// window of the key just closed with repeated occurrences, how many
+    /// occurrences it saw.
+    fn record_at(&self, key: K, now: Instant) -> (Occurrence, Option<usize>) {
+        let mut windows = self.windows.lock().unwrap();
+        match windows.get_mut(&key) {
+            Some(window) if now.duration_since(window.start) < self.window => {
+                window.occurrences += 1;
+                (Occurrence::Repeated(window.occurrences), None)
+            }
+            existing => {
+                let closed = existing
+                    .map(|window| window.occurrences)
+                    .filter(|occurrences| *occurrences > 1);
+                windows.insert(
+                    key,
+                    Window {
+                        start: now,
+                        occurrences: 1,
+                    },
+                );
+                (Occurrence::First, closed)
+            }
+        }
+    }
+
+    /// Removes all windows that are over and returns the keys and
+    /// occurrences of the ones that saw repeated occurrences.
+    fn flush_at(&self, now: Instant) -> Vec<(K, usize)> {
+        let mut closed = Vec::new();
+        self.windows.lock().unwrap().retain(|key, window| {
+            if now.duration_since(window.start) < self.window {
+                return true;
+            }
+            if window.occurrences > 1 {
+                closed.push((key.clone(), window.occurrences));
+            }
+            false
+        });
+        closed
+    }
+
+    fn summarize(&self, key: &K, occurrences: usize) {
+        tracing::warn!(
+            limiter = self.name,
+            ?key,
+            occurrences,
+            window = ?self.window,
+            "failure kept repeating"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_repeated_occurrences_per_window() {
+        let limiter = LogLimiter::new("test", Duration::from_secs(10));
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(limiter.record_at("a", at(0)), (Occurrence::First, None));
+        assert_eq!(
+            limiter.record_at("a", at(1)),
+            (Occurrence::Repeated(2), None)
+        );
+        // other keys are tracked independently
+        assert_eq!(limiter.record_at("b", at(2)), (Occurrence::First, None));
+        assert_eq!(
+            limiter.record_at("a", at(9)),
+            (Occurrence::Repeated(3), None)
+        );
+
+        // the window rolls over and the closed window gets summarized
+        assert_eq!(limiter.record_at("a", at(10)), (Occurrence::First, Some(3)));
+        assert_eq!(
+            limiter.record_at("a", at(11)),
+            (Occurrence::Repeated(2), None)
+        );
+        // windows without repetitions don't need a summary
+        assert_eq!(limiter.record_at("b", at(12)), (Occurrence::First, None));
+    }
+
+    #[test]
+    fn flush_closes_expired_windows() {
+        let limiter = LogLimiter::new("test", Duration::from_secs(10));
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        limiter.record_at("a", at(0));
+        limiter.record_at("a", at(1));
+        limiter.record_at("b", at(2));
+        limiter.record_at("c", at(5));
+        limiter.record_at("c", at(6));
+
+        // only "a" repeated within an expired window; "b" expired silently
+        assert_eq!(limiter.flush_at(at(12)), vec![("a", 2)]);
+        // flushed windows start over
+        assert_eq!(limiter.record_at("a", at(13)), (Occurrence::First, None));
+        assert_eq!(limiter.record_at("b", at(13)), (Occurrence::First, None));
+        // "c" is still within its window
+        assert_eq!(
+            limiter.record_at("c", at(13)),
+            (Occurrence::Repeated(3), None)
+        );
+    }
+}
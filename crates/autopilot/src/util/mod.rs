@@ -2,6 +2,7 @@ use url::Url;
 
 mod bytes;
 pub mod conv;
+pub mod log_limiter;
 
 pub use self::bytes::Bytes;
 
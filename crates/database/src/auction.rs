@@ -1,5 +1,5 @@
 use {
-    crate::{Address, OrderUid},
+    crate::{Address, OrderUid, byte_array::ByteArray},
     bigdecimal::BigDecimal,
     sqlx::{PgConnection, types::JsonValue},
 };
@@ -45,12 +45,13 @@ pub struct Auction {
     pub price_tokens: Vec<Address>,
     pub price_values: Vec<BigDecimal>,
     pub surplus_capturing_jit_order_owners: Vec<Address>,
+    pub content_hash: Option<ByteArray<32>>,
 }
 
 pub async fn save(ex: &mut PgConnection, auction: Auction) -> Result<(), sqlx::Error> {
     const QUERY: &str = r#"
-INSERT INTO competition_auctions (id, block, deadline, order_uids, price_tokens, price_values, surplus_capturing_jit_order_owners)
-VALUES ($1, $2, $3, $4, $5, $6, $7)
+INSERT INTO competition_auctions (id, block, deadline, order_uids, price_tokens, price_values, surplus_capturing_jit_order_owners, content_hash)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
     ;"#;
 
     sqlx::query(QUERY)
@@ -61,12 +62,23 @@ VALUES ($1, $2, $3, $4, $5, $6, $7)
         .bind(auction.price_tokens)
         .bind(auction.price_values)
         .bind(auction.surplus_capturing_jit_order_owners)
+        .bind(auction.content_hash)
         .execute(ex)
         .await?;
 
     Ok(())
 }
 
+/// Returns the ids of the `limit` most recent competition auctions, newest
+/// first.
+pub async fn fetch_latest_ids(
+    ex: &mut PgConnection,
+    limit: i64,
+) -> Result<Vec<AuctionId>, sqlx::Error> {
+    const QUERY: &str = r#"SELECT id FROM competition_auctions ORDER BY id DESC LIMIT $1;"#;
+    sqlx::query_scalar(QUERY).bind(limit).fetch_all(ex).await
+}
+
 pub async fn fetch(ex: &mut PgConnection, id: AuctionId) -> Result<Option<Auction>, sqlx::Error> {
     const QUERY: &str = r#"SELECT * FROM competition_auctions WHERE id = $1;"#;
     sqlx::query_as(QUERY).bind(id).fetch_optional(ex).await
@@ -74,7 +86,7 @@ pub async fn fetch(ex: &mut PgConnection, id: AuctionId) -> Result<Option<Auctio
 
 #[cfg(test)]
 mod tests {
-    use {super::*, crate::byte_array::ByteArray, sqlx::Connection};
+    use {super::*, sqlx::Connection};
 
     #[tokio::test]
     #[ignore]
@@ -106,9 +118,21 @@ mod tests {
             price_tokens: vec![ByteArray([1u8; 20])],
             price_values: vec![BigDecimal::from(1)],
             surplus_capturing_jit_order_owners: vec![ByteArray([1u8; 20])],
+            content_hash: Some(ByteArray([2u8; 32])),
         };
         save(&mut db, auction.clone()).await.unwrap();
         let auction_ = fetch(&mut db, id_).await.unwrap().unwrap();
         assert_eq!(auction, auction_);
+
+        let auction = Auction {
+            id: id_ + 1,
+            ..auction
+        };
+        save(&mut db, auction).await.unwrap();
+        assert_eq!(fetch_latest_ids(&mut db, 1).await.unwrap(), vec![id_ + 1]);
+        assert_eq!(
+            fetch_latest_ids(&mut db, 5).await.unwrap(),
+            vec![id_ + 1, id_]
+        );
     }
 }
@@ -18,6 +18,29 @@ pub struct AuctionPrice {
 pub async fn insert(
     ex: &mut PgTransaction<'_>,
     prices: &[AuctionPrice],
+) -> Result<(), sqlx::Error> {
+    insert_with_conflict_clause(ex, prices, "").await
+}
+
+/// Like [`insert`] but overwrites prices that are already stored, so the
+/// prices of an auction can be completed if only some of them got stored.
+#[instrument(skip_all)]
+pub async fn upsert(
+    ex: &mut PgTransaction<'_>,
+    prices: &[AuctionPrice],
+) -> Result<(), sqlx::Error> {
+    insert_with_conflict_clause(
+        ex,
+        prices,
+        " ON CONFLICT (auction_id, token) DO UPDATE SET price = EXCLUDED.price",
+    )
+    .await
+}
+
+async fn insert_with_conflict_clause(
+    ex: &mut PgTransaction<'_>,
+    prices: &[AuctionPrice],
+    on_conflict: &str,
 ) -> Result<(), sqlx::Error> {
     const BATCH_SIZE: usize = 5000;
     const QUERY: &str = "INSERT INTO auction_prices (auction_id, token, price) ";
@@ -31,6 +54,7 @@ pub async fn insert(
                 .push_bind(price.token)
                 .push_bind(price.price.clone());
         });
+        query_builder.push(on_conflict);
 
         query_builder.build().execute(ex.deref_mut()).await?;
     }
@@ -76,6 +100,52 @@ LIMIT 1
     Ok(auction_price.map(|ap| ap.price))
 }
 
+/// Deletes up to `limit` prices of auctions up to and including
+/// `last_auction`. Returns the number of deleted rows.
+#[instrument(skip_all)]
+pub async fn delete_up_to(
+    ex: &mut PgConnection,
+    last_auction: AuctionId,
+    limit: i64,
+) -> Result<u64, sqlx::Error> {
+    const QUERY: &str = r#"
+        DELETE FROM auction_prices
+        WHERE ctid IN (
+            SELECT ctid FROM auction_prices
+            WHERE auction_id <= $1
+            LIMIT $2
+        )
+    "#;
+    sqlx::query(QUERY)
+        .bind(last_auction)
+        .bind(limit)
+        .execute(ex)
+        .await
+        .map(|result| result.rows_affected())
+}
+
+/// Counts the prices of auctions up to and including `last_auction`. Stops
+/// counting at `limit` to keep the query cheap.
+#[instrument(skip_all)]
+pub async fn count_up_to(
+    ex: &mut PgConnection,
+    last_auction: AuctionId,
+    limit: i64,
+) -> Result<i64, sqlx::Error> {
+    const QUERY: &str = r#"
+        SELECT COUNT(*) FROM (
+            SELECT 1 FROM auction_prices
+            WHERE auction_id <= $1
+            LIMIT $2
+        ) AS prunable
+    "#;
+    sqlx::query_scalar(QUERY)
+        .bind(last_auction)
+        .bind(limit)
+        .fetch_one(ex)
+        .await
+}
+
 #[cfg(test)]
 mod tests {
     use {super::*, crate::byte_array::ByteArray, sqlx::Connection};
@@ -131,6 +201,23 @@ mod tests {
         // non-existent auction
         let output = fetch(&mut db, 4).await.unwrap();
         assert!(output.is_empty());
+        // upserting completes and overwrites the prices of an auction
+        let completed = vec![
+            AuctionPrice {
+                auction_id: 2,
+                token: ByteArray([2; 20]),
+                price: 6.into(),
+            },
+            AuctionPrice {
+                auction_id: 2,
+                token: ByteArray([3; 20]),
+                price: 7.into(),
+            },
+        ];
+        upsert(&mut db, &completed).await.unwrap();
+        let output = fetch(&mut db, 2).await.unwrap();
+        assert_eq!(output.len(), 2);
+        assert!(completed.iter().all(|price| output.contains(price)));
         // latest prices
         let output = fetch_latest_prices(&mut db).await.unwrap();
         assert_eq!(output, auction_3);
@@ -141,4 +228,35 @@ mod tests {
             .unwrap();
         assert_eq!(output, 3.into());
     }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_prune_up_to() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let prices: Vec<_> = (1..=3)
+            .flat_map(|auction_id| {
+                [2, 3].map(|token| AuctionPrice {
+                    auction_id,
+                    token: ByteArray([token; 20]),
+                    price: 1.into(),
+                })
+            })
+            .collect();
+        insert(&mut db, &prices).await.unwrap();
+
+        assert_eq!(count_up_to(&mut db, 2, 10).await.unwrap(), 4);
+        assert_eq!(count_up_to(&mut db, 2, 3).await.unwrap(), 3);
+
+        // deletes in batches of the given size
+        assert_eq!(delete_up_to(&mut db, 2, 3).await.unwrap(), 3);
+        assert_eq!(count_up_to(&mut db, 2, 10).await.unwrap(), 1);
+        assert_eq!(delete_up_to(&mut db, 2, 3).await.unwrap(), 1);
+        assert_eq!(delete_up_to(&mut db, 2, 3).await.unwrap(), 0);
+
+        // prices of auctions inside the retention window are untouched
+        assert_eq!(fetch(&mut db, 3).await.unwrap(), prices[4..]);
+    }
 }
@@ -0,0 +1,108 @@
+use {
+    crate::{Address, PgTransaction, auction::AuctionId, byte_array::ByteArray},
+    sqlx::PgConnection,
+    std::ops::DerefMut,
+    tracing::instrument,
+};
+
+/// Hash identifying a snapshot of trusted tokens.
+pub type SnapshotHash = ByteArray<32>;
+
+/// Stores the trusted tokens of an auction. Every distinct snapshot of tokens
+/// only gets stored once, the auction references it by its `hash`.
+#[instrument(skip_all)]
+pub async fn insert(
+    ex: &mut PgTransaction<'_>,
+    auction_id: AuctionId,
+    hash: SnapshotHash,
+    trusted_tokens: &[Address],
+) -> Result<(), sqlx::Error> {
+    const INSERT_SNAPSHOT: &str = r#"
+INSERT INTO trusted_token_snapshots (hash, tokens)
+VALUES ($1, $2)
+ON CONFLICT (hash) DO NOTHING
+    ;"#;
+    sqlx::query(INSERT_SNAPSHOT)
+        .bind(hash)
+        .bind(trusted_tokens)
+        .execute(ex.deref_mut())
+        .await?;
+
+    const INSERT_AUCTION: &str =
+        r#"INSERT INTO auction_trusted_tokens (auction_id, snapshot) VALUES ($1, $2);"#;
+    sqlx::query(INSERT_AUCTION)
+        .bind(auction_id)
+        .bind(hash)
+        .execute(ex.deref_mut())
+        .await?;
+    Ok(())
+}
+
+#[instrument(skip_all)]
+pub async fn fetch(
+    ex: &mut PgConnection,
+    auction_id: AuctionId,
+) -> Result<Option<Vec<Address>>, sqlx::Error> {
+    // auctions stored before snapshots existed have their tokens inline
+    const QUERY: &str = r#"
+SELECT COALESCE(s.tokens, a.tokens)
+FROM auction_trusted_tokens a
+LEFT JOIN trusted_token_snapshots s ON s.hash = a.snapshot
+WHERE a.auction_id = $1
+    ;"#;
+    let row = sqlx::query_scalar(QUERY)
+        .bind(auction_id)
+        .fetch_optional(ex)
+        .await?;
+    Ok(row)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, sqlx::Connection};
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_roundtrip() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let tokens = vec![ByteArray([1; 20]), ByteArray([2; 20])];
+
+        insert(&mut db, 1, ByteArray([1; 32]), &tokens)
+            .await
+            .unwrap();
+
+        let output = fetch(&mut db, 1).await.unwrap();
+        assert_eq!(output, Some(tokens.clone()));
+
+        // no trusted tokens
+        insert(&mut db, 2, ByteArray([2; 32]), &[]).await.unwrap();
+        let output = fetch(&mut db, 2).await.unwrap();
+        assert_eq!(output, Some(vec![]));
+
+        // non-existent auction
+        let output = fetch(&mut db, 3).await.unwrap();
+        assert!(output.is_none());
+
+        // identical snapshots only get stored once
+        insert(&mut db, 4, ByteArray([1; 32]), &tokens)
+            .await
+            .unwrap();
+        assert_eq!(fetch(&mut db, 4).await.unwrap(), Some(tokens.clone()));
+        let snapshots: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM trusted_token_snapshots")
+            .fetch_one(db.deref_mut())
+            .await
+            .unwrap();
+        assert_eq!(snapshots, 2);
+
+        // auctions stored before snapshots existed
+        sqlx::query("INSERT INTO auction_trusted_tokens (auction_id, tokens) VALUES (5, $1)")
+            .bind(&tokens)
+            .execute(db.deref_mut())
+            .await
+            .unwrap();
+        assert_eq!(fetch(&mut db, 5).await.unwrap(), Some(tokens));
+    }
+}
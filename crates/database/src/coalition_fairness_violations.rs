@@ -0,0 +1,102 @@
+use {
+    crate::{OrderUid, auction::AuctionId},
+    bigdecimal::BigDecimal,
+    sqlx::{PgConnection, QueryBuilder},
+    tracing::instrument,
+};
+
+/// An order a winner executed worse than a losing solution that could have
+/// won alongside the other winners.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Violation {
+    pub auction_id: AuctionId,
+    pub order_uid: OrderUid,
+    pub winner: String,
+    pub winner_executed_sell: BigDecimal,
+    pub winner_executed_buy: BigDecimal,
+    pub alternative: String,
+    pub alternative_executed_sell: BigDecimal,
+    pub alternative_executed_buy: BigDecimal,
+}
+
+/// Inserts all violations of an auction in one query.
+#[instrument(skip_all)]
+pub async fn insert_batch(
+    ex: &mut PgConnection,
+    violations: impl IntoIterator<Item = Violation>,
+) -> Result<(), sqlx::Error> {
+    let mut violations = violations.into_iter().peekable();
+    if violations.peek().is_none() {
+        return Ok(());
+    }
+
+    let mut query_builder = QueryBuilder::new(
+        "INSERT INTO coalition_fairness_violations (auction_id, order_uid, winner, \
+         winner_executed_sell, winner_executed_buy, alternative, alternative_executed_sell, \
+         alternative_executed_buy)",
+    );
+    query_builder.push_values(violations, |mut b, violation| {
+        b.push_bind(violation.auction_id)
+            .push_bind(violation.order_uid)
+            .push_bind(violation.winner)
+            .push_bind(violation.winner_executed_sell)
+            .push_bind(violation.winner_executed_buy)
+            .push_bind(violation.alternative)
+            .push_bind(violation.alternative_executed_sell)
+            .push_bind(violation.alternative_executed_buy);
+    });
+    query_builder.push(" ON CONFLICT (auction_id, order_uid) DO NOTHING");
+
+    query_builder.build().execute(ex).await.map(|_| ())
+}
+
+/// Returns all violations recorded for the given auction.
+#[instrument(skip_all)]
+pub async fn fetch(
+    ex: &mut PgConnection,
+    auction_id: AuctionId,
+) -> Result<Vec<Violation>, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT * FROM coalition_fairness_violations
+WHERE auction_id = $1
+ORDER BY order_uid
+    ;"#;
+    sqlx::query_as(QUERY).bind(auction_id).fetch_all(ex).await
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::byte_array::ByteArray, sqlx::Connection};
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_roundtrip() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let violation = |auction_id, order: u8| Violation {
+            auction_id,
+            order_uid: ByteArray([order; 56]),
+            winner: "winner".to_owned(),
+            winner_executed_sell: 100.into(),
+            winner_executed_buy: 95.into(),
+            alternative: "alternative".to_owned(),
+            alternative_executed_sell: 100.into(),
+            alternative_executed_buy: 97.into(),
+        };
+        insert_batch(&mut db, [violation(1, 2), violation(1, 1), violation(2, 1)])
+            .await
+            .unwrap();
+        // empty batches and duplicates are fine
+        insert_batch(&mut db, []).await.unwrap();
+        insert_batch(&mut db, [violation(1, 1)]).await.unwrap();
+
+        assert_eq!(
+            fetch(&mut db, 1).await.unwrap(),
+            vec![violation(1, 1), violation(1, 2)]
+        );
+        assert_eq!(fetch(&mut db, 2).await.unwrap(), vec![violation(2, 1)]);
+        assert!(fetch(&mut db, 3).await.unwrap().is_empty());
+    }
+}
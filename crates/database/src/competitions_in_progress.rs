@@ -0,0 +1,90 @@
+use {
+    crate::{auction::AuctionId, byte_array::ByteArray},
+    chrono::{DateTime, Utc},
+    sqlx::PgConnection,
+    tracing::instrument,
+};
+
+/// A competition that got ranked but whose data has not been stored yet.
+#[derive(Clone, Debug, Eq, PartialEq, sqlx::FromRow)]
+pub struct CompetitionInProgress {
+    pub auction_id: AuctionId,
+    pub drivers: Vec<String>,
+    pub solution_hashes: Vec<ByteArray<32>>,
+    pub started_at: DateTime<Utc>,
+}
+
+/// Replaces all competitions in progress with the given one. Only one
+/// competition gets handled at a time so any previous entry is stale. Should
+/// run in a transaction so the previous entry never disappears on its own.
+#[instrument(skip_all)]
+pub async fn replace(
+    ex: &mut PgConnection,
+    competition: &CompetitionInProgress,
+) -> Result<(), sqlx::Error> {
+    const DELETE: &str = "DELETE FROM competitions_in_progress;";
+    sqlx::query(DELETE).execute(&mut *ex).await?;
+
+    const INSERT: &str = r#"
+INSERT INTO competitions_in_progress (auction_id, drivers, solution_hashes, started_at)
+VALUES ($1, $2, $3, $4)
+    ;"#;
+    sqlx::query(INSERT)
+        .bind(competition.auction_id)
+        .bind(&competition.drivers)
+        .bind(&competition.solution_hashes)
+        .bind(competition.started_at)
+        .execute(ex)
+        .await?;
+    Ok(())
+}
+
+#[instrument(skip_all)]
+pub async fn delete(ex: &mut PgConnection, auction_id: AuctionId) -> Result<(), sqlx::Error> {
+    const QUERY: &str = "DELETE FROM competitions_in_progress WHERE auction_id = $1;";
+    sqlx::query(QUERY).bind(auction_id).execute(ex).await?;
+    Ok(())
+}
+
+/// Fetches all competitions in progress ordered by auction id.
+#[instrument(skip_all)]
+pub async fn fetch_all(ex: &mut PgConnection) -> Result<Vec<CompetitionInProgress>, sqlx::Error> {
+    const QUERY: &str = "SELECT * FROM competitions_in_progress ORDER BY auction_id;";
+    sqlx::query_as(QUERY).fetch_all(ex).await
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, sqlx::Connection};
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_roundtrip() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let competition = |auction_id| CompetitionInProgress {
+            auction_id,
+            drivers: vec!["a".to_string(), "b".to_string()],
+            solution_hashes: vec![ByteArray([1; 32]), ByteArray([2; 32])],
+            started_at: DateTime::from_timestamp(auction_id, 0).unwrap(),
+        };
+
+        assert!(fetch_all(&mut db).await.unwrap().is_empty());
+
+        replace(&mut db, &competition(1)).await.unwrap();
+        assert_eq!(fetch_all(&mut db).await.unwrap(), [competition(1)]);
+
+        // a new competition replaces the previous one
+        replace(&mut db, &competition(2)).await.unwrap();
+        assert_eq!(fetch_all(&mut db).await.unwrap(), [competition(2)]);
+
+        // deleting another auction keeps the entry
+        delete(&mut db, 1).await.unwrap();
+        assert_eq!(fetch_all(&mut db).await.unwrap(), [competition(2)]);
+
+        delete(&mut db, 2).await.unwrap();
+        assert!(fetch_all(&mut db).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,102 @@
+use {
+    crate::{Address, auction::AuctionId},
+    sqlx::{PgConnection, QueryBuilder},
+    tracing::instrument,
+};
+
+/// How a single driver took part in an auction.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct DriverParticipation {
+    pub auction_id: AuctionId,
+    pub driver: String,
+    pub submission_address: Address,
+    pub outcome: String,
+    pub proposed_solutions: i64,
+    pub accepted_solutions: i64,
+}
+
+/// Inserts the participation of all drivers of an auction in one query.
+#[instrument(skip_all)]
+pub async fn insert_batch(
+    ex: &mut PgConnection,
+    participations: impl IntoIterator<Item = DriverParticipation>,
+) -> Result<(), sqlx::Error> {
+    let mut participations = participations.into_iter().peekable();
+    if participations.peek().is_none() {
+        return Ok(());
+    }
+
+    let mut query_builder = QueryBuilder::new(
+        "INSERT INTO driver_participation (auction_id, driver, submission_address, outcome, \
+         proposed_solutions, accepted_solutions)",
+    );
+    query_builder.push_values(participations, |mut b, participation| {
+        b.push_bind(participation.auction_id)
+            .push_bind(participation.driver)
+            .push_bind(participation.submission_address)
+            .push_bind(participation.outcome)
+            .push_bind(participation.proposed_solutions)
+            .push_bind(participation.accepted_solutions);
+    });
+    query_builder.push(" ON CONFLICT (auction_id, driver) DO NOTHING");
+
+    query_builder.build().execute(ex).await.map(|_| ())
+}
+
+/// Returns the participation of all drivers in the given auction.
+#[instrument(skip_all)]
+pub async fn fetch(
+    ex: &mut PgConnection,
+    auction_id: AuctionId,
+) -> Result<Vec<DriverParticipation>, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT * FROM driver_participation
+WHERE auction_id = $1
+ORDER BY driver
+    ;"#;
+    sqlx::query_as(QUERY).bind(auction_id).fetch_all(ex).await
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::byte_array::ByteArray, sqlx::Connection};
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_roundtrip() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let participation =
+            |auction_id, driver: &str, outcome: &str, proposed| DriverParticipation {
+                auction_id,
+                driver: driver.to_owned(),
+                submission_address: ByteArray([1; 20]),
+                outcome: outcome.to_owned(),
+                proposed_solutions: proposed,
+                accepted_solutions: proposed,
+            };
+        insert_batch(
+            &mut db,
+            [
+                participation(1, "b", "proposed", 2),
+                participation(1, "a", "timeout", 0),
+                participation(2, "a", "no_solutions", 0),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let output = fetch(&mut db, 1).await.unwrap();
+        assert_eq!(
+            output,
+            vec![
+                participation(1, "a", "timeout", 0),
+                participation(1, "b", "proposed", 2),
+            ]
+        );
+        let output = fetch(&mut db, 3).await.unwrap();
+        assert!(output.is_empty());
+    }
+}
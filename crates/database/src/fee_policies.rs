@@ -57,6 +57,32 @@ pub async fn insert_batch(
     query_builder.build().execute(ex).await.map(|_| ())
 }
 
+/// Returns how many fee policies are stored for every order of the given
+/// auction. Orders without fee policies are omitted.
+#[instrument(skip_all)]
+pub async fn count_per_order(
+    ex: &mut PgConnection,
+    auction_id: AuctionId,
+) -> Result<Vec<(OrderUid, i64)>, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT order_uid, COUNT(*)
+FROM fee_policies
+WHERE auction_id = $1
+GROUP BY order_uid
+    "#;
+    sqlx::query_as(QUERY).bind(auction_id).fetch_all(ex).await
+}
+
+/// Deletes all fee policies of the given auction. Since the policies don't
+/// have a natural key this is required to store them once more without
+/// duplicating them.
+#[instrument(skip_all)]
+pub async fn delete(ex: &mut PgConnection, auction_id: AuctionId) -> Result<(), sqlx::Error> {
+    const QUERY: &str = "DELETE FROM fee_policies WHERE auction_id = $1";
+    sqlx::query(QUERY).bind(auction_id).execute(ex).await?;
+    Ok(())
+}
+
 #[instrument(skip_all)]
 pub async fn fetch_all(
     ex: &mut PgConnection,
@@ -191,5 +217,27 @@ mod tests {
         .await
         .unwrap();
         assert_eq!(output, expected);
+
+        assert_eq!(
+            count_per_order(&mut db, auction_id_a).await.unwrap(),
+            vec![(order_uid_a, 2)]
+        );
+        assert_eq!(
+            count_per_order(&mut db, auction_id_b).await.unwrap(),
+            vec![(order_uid_b, 2)]
+        );
+        assert!(count_per_order(&mut db, 3).await.unwrap().is_empty());
+
+        delete(&mut db, auction_id_a).await.unwrap();
+        assert!(
+            count_per_order(&mut db, auction_id_a)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            count_per_order(&mut db, auction_id_b).await.unwrap(),
+            vec![(order_uid_b, 2)]
+        );
     }
 }
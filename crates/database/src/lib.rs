@@ -3,7 +3,11 @@ pub mod auction;
 pub mod auction_orders;
 pub mod auction_participants;
 pub mod auction_prices;
+pub mod auction_trusted_tokens;
 pub mod byte_array;
+pub mod coalition_fairness_violations;
+pub mod competitions_in_progress;
+pub mod driver_participation;
 pub mod ethflow_orders;
 pub mod events;
 pub mod fee_policies;
@@ -13,16 +17,24 @@ pub mod onchain_broadcasted_orders;
 pub mod onchain_invalidations;
 pub mod order_events;
 pub mod order_execution;
+pub mod order_execution_status;
 pub mod order_history;
+pub mod order_price_improvements;
+pub mod order_settlement_latencies;
 pub mod orders;
 pub mod quotes;
 pub mod reference_scores;
+pub mod reward_inputs;
 pub mod settlement_executions;
 pub mod settlement_observations;
+pub mod settlement_price_deviations;
 pub mod settlement_scores;
 pub mod settlements;
+pub mod shadow_competition_comparisons;
+pub mod solution_gas;
 pub mod solver_competition;
 pub mod solver_competition_v2;
+pub mod solver_notifications;
 pub mod surplus_capturing_jit_order_owners;
 pub mod trades;
 
@@ -55,7 +67,11 @@ pub type PgTransaction<'a> = sqlx::Transaction<'a, sqlx::Postgres>;
 pub const TABLES: &[&str] = &[
     "app_data",
     "auction_orders",
+    "auction_trusted_tokens",
     "auctions",
+    "coalition_fairness_violations",
+    "competitions_in_progress",
+    "driver_participation",
     "ethflow_orders",
     "ethflow_refunds",
     "interactions",
@@ -64,17 +80,27 @@ pub const TABLES: &[&str] = &[
     "last_indexed_blocks",
     "onchain_order_invalidations",
     "onchain_placed_orders",
+    "order_execution_status",
+    "order_first_auction_appearances",
+    "order_price_improvements",
+    "order_settlement_latencies",
     "presignature_events",
     "proposed_jit_orders",
     "quotes",
     "reference_scores",
+    "reward_inputs",
     "settlement_executions",
     "settlement_observations",
+    "settlement_price_deviations",
     "settlement_scores",
     "settlements",
+    "shadow_competition_comparisons",
+    "solution_gas",
     "solver_competitions",
+    "solver_notifications",
     "surplus_capturing_jit_order_owners",
     "trades",
+    "trusted_token_snapshots",
 ];
 
 /// The names of potentially big volume tables we use in the db.
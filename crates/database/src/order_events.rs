@@ -31,6 +31,9 @@ pub enum OrderEventLabel {
     Traded,
     /// Order was cancelled by the user.
     Cancelled,
+    /// Order was included in the winning settlement but the settlement
+    /// transaction could not be submitted on-chain (e.g. it reverted).
+    Reverted,
 }
 
 /// Contains a single event of the life cycle of an order and when it was
@@ -119,6 +122,25 @@ mod tests {
         sqlx::Connection,
     };
 
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_reverted_order_event() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut ex = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut ex).await.unwrap();
+
+        let uid = ByteArray([1; 56]);
+        let event = OrderEvent {
+            order_uid: uid,
+            timestamp: Utc::now(),
+            label: OrderEventLabel::Reverted,
+        };
+        insert_order_event(&mut ex, &event).await.unwrap();
+
+        let latest = get_latest(&mut ex, &uid).await.unwrap().unwrap();
+        assert_eq!(latest.label, OrderEventLabel::Reverted);
+    }
+
     #[tokio::test]
     #[ignore]
     async fn postgres_non_subsequent_order_events() {
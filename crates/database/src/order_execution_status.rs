@@ -0,0 +1,133 @@
+use {
+    crate::{Address, OrderUid, TransactionHash, auction::AuctionId},
+    sqlx::{PgConnection, QueryBuilder},
+    tracing::instrument,
+};
+
+/// Execution progress of an order that is part of a winning solution.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct OrderExecutionStatus {
+    pub order_uid: OrderUid,
+    pub auction_id: AuctionId,
+    pub solver: Address,
+    pub solution_uid: i64,
+    pub deadline_block: i64,
+    /// Hash of the settlement transaction once it is known.
+    pub tx_hash: Option<TransactionHash>,
+}
+
+/// Inserts the statuses of all winning orders of an auction in one query.
+#[instrument(skip_all)]
+pub async fn insert_batch(
+    ex: &mut PgConnection,
+    statuses: impl IntoIterator<Item = OrderExecutionStatus>,
+) -> Result<(), sqlx::Error> {
+    let mut statuses = statuses.into_iter().peekable();
+    if statuses.peek().is_none() {
+        return Ok(());
+    }
+
+    let mut query_builder = QueryBuilder::new(
+        "INSERT INTO order_execution_status (order_uid, auction_id, solver, solution_uid, \
+         deadline_block, tx_hash)",
+    );
+    query_builder.push_values(statuses, |mut b, status| {
+        b.push_bind(status.order_uid)
+            .push_bind(status.auction_id)
+            .push_bind(status.solver)
+            .push_bind(status.solution_uid)
+            .push_bind(status.deadline_block)
+            .push_bind(status.tx_hash);
+    });
+    query_builder.push(" ON CONFLICT (order_uid, auction_id) DO NOTHING");
+
+    query_builder.build().execute(ex).await.map(|_| ())
+}
+
+/// Sets the settlement transaction of all orders of a winning solution.
+#[instrument(skip_all)]
+pub async fn set_tx_hash(
+    ex: &mut PgConnection,
+    auction_id: AuctionId,
+    solver: Address,
+    solution_uid: i64,
+    tx_hash: TransactionHash,
+) -> Result<(), sqlx::Error> {
+    const QUERY: &str = r#"
+UPDATE order_execution_status
+SET tx_hash = $4
+WHERE auction_id = $1 AND solver = $2 AND solution_uid = $3
+    ;"#;
+    sqlx::query(QUERY)
+        .bind(auction_id)
+        .bind(solver)
+        .bind(solution_uid)
+        .bind(tx_hash)
+        .execute(ex)
+        .await
+        .map(|_| ())
+}
+
+/// Returns the status of the most recent auction the order won.
+#[instrument(skip_all)]
+pub async fn fetch_latest(
+    ex: &mut PgConnection,
+    order_uid: &OrderUid,
+) -> Result<Option<OrderExecutionStatus>, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT * FROM order_execution_status
+WHERE order_uid = $1
+ORDER BY auction_id DESC
+LIMIT 1
+    ;"#;
+    sqlx::query_as(QUERY)
+        .bind(order_uid)
+        .fetch_optional(ex)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::byte_array::ByteArray, sqlx::Connection};
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_roundtrip() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let status = |order_uid: u8, auction_id| OrderExecutionStatus {
+            order_uid: ByteArray([order_uid; 56]),
+            auction_id,
+            solver: ByteArray([1; 20]),
+            solution_uid: 0,
+            deadline_block: 10,
+            tx_hash: None,
+        };
+        insert_batch(&mut db, [status(1, 1), status(2, 1), status(1, 2)])
+            .await
+            .unwrap();
+
+        // the most recent auction is returned
+        let output = fetch_latest(&mut db, &ByteArray([1; 56])).await.unwrap();
+        assert_eq!(output, Some(status(1, 2)));
+        let output = fetch_latest(&mut db, &ByteArray([3; 56])).await.unwrap();
+        assert_eq!(output, None);
+
+        set_tx_hash(&mut db, 1, ByteArray([1; 20]), 0, ByteArray([2; 32]))
+            .await
+            .unwrap();
+        let output = fetch_latest(&mut db, &ByteArray([2; 56])).await.unwrap();
+        assert_eq!(
+            output,
+            Some(OrderExecutionStatus {
+                tx_hash: Some(ByteArray([2; 32])),
+                ..status(2, 1)
+            })
+        );
+        // other auctions are not affected
+        let output = fetch_latest(&mut db, &ByteArray([1; 56])).await.unwrap();
+        assert_eq!(output, Some(status(1, 2)));
+    }
+}
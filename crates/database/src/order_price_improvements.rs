@@ -0,0 +1,96 @@
+use {
+    crate::{OrderUid, auction::AuctionId},
+    sqlx::{PgConnection, QueryBuilder},
+    tracing::instrument,
+};
+
+/// How much better than the external prices of the auction an order got
+/// executed.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct PriceImprovement {
+    pub auction_id: AuctionId,
+    pub order_uid: OrderUid,
+    pub improvement_bps: f64,
+}
+
+/// Inserts the price improvements of an auction in one query.
+#[instrument(skip_all)]
+pub async fn insert_batch(
+    ex: &mut PgConnection,
+    improvements: impl IntoIterator<Item = PriceImprovement>,
+) -> Result<(), sqlx::Error> {
+    let mut improvements = improvements.into_iter().peekable();
+    if improvements.peek().is_none() {
+        return Ok(());
+    }
+
+    let mut query_builder = QueryBuilder::new(
+        "INSERT INTO order_price_improvements (auction_id, order_uid, improvement_bps)",
+    );
+    query_builder.push_values(improvements, |mut b, improvement| {
+        b.push_bind(improvement.auction_id)
+            .push_bind(improvement.order_uid)
+            .push_bind(improvement.improvement_bps);
+    });
+    query_builder.push(" ON CONFLICT (auction_id, order_uid) DO NOTHING");
+
+    query_builder.build().execute(ex).await.map(|_| ())
+}
+
+/// Returns all price improvements recorded for the given auction.
+#[instrument(skip_all)]
+pub async fn fetch(
+    ex: &mut PgConnection,
+    auction_id: AuctionId,
+) -> Result<Vec<PriceImprovement>, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT * FROM order_price_improvements
+WHERE auction_id = $1
+ORDER BY order_uid
+    ;"#;
+    sqlx::query_as(QUERY).bind(auction_id).fetch_all(ex).await
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::byte_array::ByteArray, sqlx::Connection};
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_roundtrip() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let improvement = |auction_id, order: u8, improvement_bps| PriceImprovement {
+            auction_id,
+            order_uid: ByteArray([order; 56]),
+            improvement_bps,
+        };
+        insert_batch(
+            &mut db,
+            [
+                improvement(1, 2, -12.5),
+                improvement(1, 1, 3.),
+                improvement(2, 1, 0.),
+            ],
+        )
+        .await
+        .unwrap();
+        // empty batches and duplicates are fine
+        insert_batch(&mut db, []).await.unwrap();
+        insert_batch(&mut db, [improvement(1, 1, 4.)])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fetch(&mut db, 1).await.unwrap(),
+            vec![improvement(1, 1, 3.), improvement(1, 2, -12.5)]
+        );
+        assert_eq!(
+            fetch(&mut db, 2).await.unwrap(),
+            vec![improvement(2, 1, 0.)]
+        );
+        assert!(fetch(&mut db, 3).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+use {
+    crate::{OrderUid, TransactionHash, auction::AuctionId},
+    sqlx::{
+        PgConnection,
+        QueryBuilder,
+        types::chrono::{DateTime, Utc},
+    },
+    tracing::instrument,
+};
+
+/// When an order was first part of an auction.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct FirstAppearance {
+    pub order_uid: OrderUid,
+    pub auction_id: AuctionId,
+    pub first_seen: DateTime<Utc>,
+}
+
+/// Time between an order's first appearance in an auction and its settlement.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct SettlementLatency {
+    pub order_uid: OrderUid,
+    pub auction_id: AuctionId,
+    pub tx_hash: TransactionHash,
+    pub first_seen: DateTime<Utc>,
+    pub settled: DateTime<Utc>,
+}
+
+/// Records the first appearances of orders in one query. Orders that already
+/// appeared in an earlier auction keep their original record.
+#[instrument(skip_all)]
+pub async fn insert_first_appearances(
+    ex: &mut PgConnection,
+    appearances: impl IntoIterator<Item = FirstAppearance>,
+) -> Result<(), sqlx::Error> {
+    let mut appearances = appearances.into_iter().peekable();
+    if appearances.peek().is_none() {
+        return Ok(());
+    }
+
+    let mut query_builder = QueryBuilder::new(
+        "INSERT INTO order_first_auction_appearances (order_uid, auction_id, first_seen)",
+    );
+    query_builder.push_values(appearances, |mut b, appearance| {
+        b.push_bind(appearance.order_uid)
+            .push_bind(appearance.auction_id)
+            .push_bind(appearance.first_seen);
+    });
+    query_builder.push(" ON CONFLICT (order_uid) DO NOTHING");
+
+    query_builder.build().execute(ex).await.map(|_| ())
+}
+
+/// Records the settlement of the given orders and returns for every newly
+/// recorded order how many seconds passed since its first appearance in an
+/// auction. Orders without a recorded first appearance or whose settlement was
+/// already recorded get skipped.
+#[instrument(skip_all)]
+pub async fn record_settlement(
+    ex: &mut PgConnection,
+    order_uids: &[OrderUid],
+    auction_id: AuctionId,
+    tx_hash: TransactionHash,
+    settled: DateTime<Utc>,
+) -> Result<Vec<(OrderUid, f64)>, sqlx::Error> {
+    const QUERY: &str = r#"
+INSERT INTO order_settlement_latencies (order_uid, auction_id, tx_hash, first_seen, settled)
+SELECT order_uid, $2, $3, first_seen, $4
+FROM order_first_auction_appearances
+WHERE order_uid = ANY($1)
+ON CONFLICT (order_uid) DO NOTHING
+RETURNING order_uid, EXTRACT(EPOCH FROM settled - first_seen)::double precision
+    ;"#;
+    sqlx::query_as(QUERY)
+        .bind(order_uids)
+        .bind(auction_id)
+        .bind(tx_hash)
+        .bind(settled)
+        .fetch_all(ex)
+        .await
+}
+
+#[instrument(skip_all)]
+pub async fn fetch_first_appearance(
+    ex: &mut PgConnection,
+    order_uid: &OrderUid,
+) -> Result<Option<FirstAppearance>, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT * FROM order_first_auction_appearances
+WHERE order_uid = $1
+    ;"#;
+    sqlx::query_as(QUERY)
+        .bind(order_uid)
+        .fetch_optional(ex)
+        .await
+}
+
+#[instrument(skip_all)]
+pub async fn fetch_settlement_latency(
+    ex: &mut PgConnection,
+    order_uid: &OrderUid,
+) -> Result<Option<SettlementLatency>, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT * FROM order_settlement_latencies
+WHERE order_uid = $1
+    ;"#;
+    sqlx::query_as(QUERY)
+        .bind(order_uid)
+        .fetch_optional(ex)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::byte_array::ByteArray,
+        sqlx::{Connection, types::chrono::TimeZone},
+    };
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_roundtrip() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let time = |seconds| Utc.timestamp_opt(seconds, 0).unwrap();
+        let appearance = |order: u8, auction_id, seconds| FirstAppearance {
+            order_uid: ByteArray([order; 56]),
+            auction_id,
+            first_seen: time(seconds),
+        };
+        insert_first_appearances(&mut db, [appearance(1, 1, 100), appearance(2, 1, 100)])
+            .await
+            .unwrap();
+        // reappearing orders keep their first appearance
+        insert_first_appearances(&mut db, [appearance(1, 2, 112), appearance(3, 2, 112)])
+            .await
+            .unwrap();
+        insert_first_appearances(&mut db, []).await.unwrap();
+        assert_eq!(
+            fetch_first_appearance(&mut db, &ByteArray([1; 56]))
+                .await
+                .unwrap(),
+            Some(appearance(1, 1, 100))
+        );
+
+        // orders that never appeared in an auction get skipped
+        let latencies = record_settlement(
+            &mut db,
+            &[ByteArray([1; 56]), ByteArray([3; 56]), ByteArray([4; 56])],
+            2,
+            ByteArray([5; 32]),
+            time(142),
+        )
+        .await
+        .unwrap();
+        assert_eq!(latencies.len(), 2);
+        assert!(latencies.contains(&(ByteArray([1; 56]), 42.)));
+        assert!(latencies.contains(&(ByteArray([3; 56]), 30.)));
+        assert_eq!(
+            fetch_settlement_latency(&mut db, &ByteArray([1; 56]))
+                .await
+                .unwrap(),
+            Some(SettlementLatency {
+                order_uid: ByteArray([1; 56]),
+                auction_id: 2,
+                tx_hash: ByteArray([5; 32]),
+                first_seen: time(100),
+                settled: time(142),
+            })
+        );
+        assert!(
+            fetch_settlement_latency(&mut db, &ByteArray([4; 56]))
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // only the first settlement counts
+        let latencies = record_settlement(
+            &mut db,
+            &[ByteArray([1; 56])],
+            3,
+            ByteArray([6; 32]),
+            time(200),
+        )
+        .await
+        .unwrap();
+        assert!(latencies.is_empty());
+        // orders that never settle have no latency
+        assert!(
+            fetch_settlement_latency(&mut db, &ByteArray([2; 56]))
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -835,6 +835,22 @@ pub fn open_orders_by_time_or_uids<'a>(
         .fetch(ex)
 }
 
+/// Fetches the orders with the given uids regardless of their status.
+#[instrument(skip_all)]
+pub fn full_orders_by_uids<'a>(
+    ex: &'a mut PgConnection,
+    uids: &'a [OrderUid],
+) -> BoxStream<'a, Result<FullOrder, sqlx::Error>> {
+    #[rustfmt::skip]
+    const QUERY: &str = const_format::concatcp!(
+        "SELECT ", SELECT,
+        " FROM ", FROM,
+        " WHERE o.uid = ANY($1)",
+    );
+
+    sqlx::query_as(QUERY).bind(uids).fetch(ex)
+}
+
 #[instrument(skip_all)]
 pub async fn latest_settlement_block(ex: &mut PgConnection) -> Result<i64, sqlx::Error> {
     const QUERY: &str = r#"
@@ -1950,6 +1966,36 @@ mod tests {
         )
     }
 
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_full_orders_by_uids() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        for i in 1..=3 {
+            let order = Order {
+                uid: ByteArray([i; 56]),
+                cancellation_timestamp: (i == 2).then(Utc::now),
+                ..Default::default()
+            };
+            insert_order(&mut db, &order).await.unwrap();
+        }
+
+        let uids = [
+            ByteArray([2u8; 56]),
+            ByteArray([3u8; 56]),
+            ByteArray([4u8; 56]),
+        ];
+        let orders: HashSet<OrderUid> = full_orders_by_uids(&mut db, &uids)
+            .map_ok(|o| o.uid)
+            .try_collect()
+            .await
+            .unwrap();
+        // Cancelled orders get returned as well, unknown uids get ignored.
+        assert_eq!(orders, hashset![ByteArray([2u8; 56]), ByteArray([3u8; 56])]);
+    }
+
     #[tokio::test]
     #[ignore]
     async fn postgres_orders_in_tx() {
@@ -0,0 +1,86 @@
+use {
+    crate::auction::AuctionId,
+    sqlx::{PgConnection, types::JsonValue},
+    tracing::instrument,
+};
+
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct RewardInputs {
+    pub auction_id: AuctionId,
+    pub block_deadline: i64,
+    pub competition_data_complete: bool,
+    pub winners: JsonValue,
+}
+
+#[instrument(skip_all)]
+pub async fn upsert(ex: &mut PgConnection, inputs: &RewardInputs) -> Result<(), sqlx::Error> {
+    const QUERY: &str = r#"
+INSERT INTO reward_inputs (auction_id, block_deadline, competition_data_complete, winners)
+VALUES ($1, $2, $3, $4)
+ON CONFLICT (auction_id) DO UPDATE
+SET block_deadline = $2, competition_data_complete = $3, winners = $4
+    ;"#;
+    sqlx::query(QUERY)
+        .bind(inputs.auction_id)
+        .bind(inputs.block_deadline)
+        .bind(inputs.competition_data_complete)
+        .bind(&inputs.winners)
+        .execute(ex)
+        .await?;
+    Ok(())
+}
+
+/// Fetches the reward inputs of all auctions with ids in `[from, to]`, ordered
+/// by auction id.
+#[instrument(skip_all)]
+pub async fn fetch_range(
+    ex: &mut PgConnection,
+    from: AuctionId,
+    to: AuctionId,
+) -> Result<Vec<RewardInputs>, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT * FROM reward_inputs
+WHERE auction_id BETWEEN $1 AND $2
+ORDER BY auction_id
+    ;"#;
+    sqlx::query_as(QUERY)
+        .bind(from)
+        .bind(to)
+        .fetch_all(ex)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, sqlx::Connection};
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_roundtrip() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let inputs = |auction_id| RewardInputs {
+            auction_id,
+            block_deadline: 10,
+            competition_data_complete: true,
+            winners: JsonValue::Array(vec![]),
+        };
+        for auction_id in 1..=3 {
+            upsert(&mut db, &inputs(auction_id)).await.unwrap();
+        }
+
+        let output = fetch_range(&mut db, 2, 5).await.unwrap();
+        assert_eq!(output, vec![inputs(2), inputs(3)]);
+
+        // storing again overwrites the previous row
+        let updated = RewardInputs {
+            competition_data_complete: false,
+            ..inputs(2)
+        };
+        upsert(&mut db, &updated).await.unwrap();
+        let output = fetch_range(&mut db, 2, 2).await.unwrap();
+        assert_eq!(output, vec![updated]);
+    }
+}
@@ -1,10 +1,61 @@
 use {
     crate::{Address, auction::AuctionId},
     chrono::{DateTime, Utc},
-    sqlx::PgConnection,
+    sqlx::{PgConnection, QueryBuilder},
     tracing::instrument,
 };
 
+/// A settlement execution. The end columns are only set once the execution
+/// ended.
+#[derive(Debug, Clone, Eq, PartialEq, sqlx::FromRow)]
+pub struct Execution {
+    pub auction_id: AuctionId,
+    pub solver: Address,
+    pub solution_uid: i64,
+    pub start_timestamp: DateTime<Utc>,
+    pub end_timestamp: Option<DateTime<Utc>>,
+    pub start_block: i64,
+    pub end_block: Option<i64>,
+    pub deadline_block: i64,
+    pub outcome: Option<String>,
+}
+
+/// How a settlement execution ended.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "SettlementExecutionOutcome")]
+#[sqlx(rename_all = "lowercase")]
+pub enum OutcomeKind {
+    Success,
+    Timeout,
+    /// The settlement transaction got reorged out.
+    Reorged,
+    /// The driver failed to settle the solution.
+    Failed,
+}
+
+/// Outcomes settlement executions can be filtered by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Success,
+    Timeout,
+    /// The driver failed to settle the solution.
+    Failed,
+    /// The execution hasn't ended yet.
+    Unfinished,
+    /// The execution never ended although its deadline passed.
+    Orphaned,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub solver: Option<Address>,
+    /// Inclusive range of auction ids.
+    pub auctions: Option<(AuctionId, AuctionId)>,
+    pub outcome: Option<Outcome>,
+    /// Only executions that started within `[from, to)`.
+    pub started: Option<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
 #[instrument(skip_all)]
 pub async fn insert(
     ex: &mut PgConnection,
@@ -33,6 +84,7 @@ VALUES ($1, $2, $3, $4, $5, $6)
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 #[instrument(skip_all)]
 pub async fn update(
     ex: &mut PgConnection,
@@ -41,11 +93,12 @@ pub async fn update(
     solution_uid: i64,
     end_timestamp: DateTime<Utc>,
     end_block: i64,
+    outcome_kind: OutcomeKind,
     outcome: String,
 ) -> Result<(), sqlx::Error> {
     const QUERY: &str = r#"
 UPDATE settlement_executions
-SET end_timestamp = $4, end_block = $5, outcome = $6
+SET end_timestamp = $4, end_block = $5, outcome_kind = $6, outcome = $7
 WHERE auction_id = $1 AND solver = $2 AND solution_uid = $3
     ;"#;
 
@@ -55,6 +108,7 @@ WHERE auction_id = $1 AND solver = $2 AND solution_uid = $3
         .bind(solution_uid)
         .bind(end_timestamp)
         .bind(end_block)
+        .bind(outcome_kind)
         .bind(outcome)
         .execute(ex)
         .await?;
@@ -62,6 +116,184 @@ WHERE auction_id = $1 AND solver = $2 AND solution_uid = $3
     Ok(())
 }
 
+/// Records whether the mined settlement transaction of an execution deviated
+/// from the solution that won the competition.
+#[instrument(skip_all)]
+pub async fn set_deviates_from_solution(
+    ex: &mut PgConnection,
+    auction_id: AuctionId,
+    solver: Address,
+    solution_uid: i64,
+    deviates: bool,
+) -> Result<(), sqlx::Error> {
+    const QUERY: &str = r#"
+UPDATE settlement_executions
+SET deviates_from_solution = $4
+WHERE auction_id = $1 AND solver = $2 AND solution_uid = $3
+    ;"#;
+
+    sqlx::query(QUERY)
+        .bind(auction_id)
+        .bind(solver)
+        .bind(solution_uid)
+        .bind(deviates)
+        .execute(ex)
+        .await?;
+
+    Ok(())
+}
+
+/// Loads all settlement executions matching the filter ordered by auction.
+/// `current_block` is needed to tell which executions are orphaned.
+#[instrument(skip_all)]
+pub async fn load(
+    ex: &mut PgConnection,
+    filter: &Filter,
+    current_block: i64,
+) -> Result<Vec<Execution>, sqlx::Error> {
+    let mut builder = QueryBuilder::new(
+        "SELECT auction_id, solver, solution_uid, start_timestamp, end_timestamp, start_block, \
+         end_block, deadline_block, outcome FROM settlement_executions WHERE TRUE",
+    );
+    if let Some(solver) = filter.solver {
+        builder.push(" AND solver = ").push_bind(solver);
+    }
+    if let Some((from, to)) = filter.auctions {
+        builder
+            .push(" AND auction_id BETWEEN ")
+            .push_bind(from)
+            .push(" AND ")
+            .push_bind(to);
+    }
+    if let Some((from, to)) = filter.started {
+        builder
+            .push(" AND start_timestamp >= ")
+            .push_bind(from)
+            .push(" AND start_timestamp < ")
+            .push_bind(to);
+    }
+    match filter.outcome {
+        None => {}
+        Some(Outcome::Success) => {
+            builder
+                .push(" AND outcome_kind = ")
+                .push_bind(OutcomeKind::Success);
+        }
+        Some(Outcome::Timeout) => {
+            builder
+                .push(" AND outcome_kind = ")
+                .push_bind(OutcomeKind::Timeout);
+        }
+        Some(Outcome::Failed) => {
+            builder
+                .push(" AND outcome_kind = ")
+                .push_bind(OutcomeKind::Failed);
+        }
+        Some(Outcome::Unfinished) => {
+            builder.push(" AND end_timestamp IS NULL");
+        }
+        Some(Outcome::Orphaned) => {
+            builder
+                .push(" AND end_timestamp IS NULL AND deadline_block < ")
+                .push_bind(current_block);
+        }
+    }
+    builder.push(" ORDER BY auction_id, solver, solution_uid");
+
+    builder.build_query_as().fetch_all(ex).await
+}
+
+/// Counts the settlement executions that started at or after `since` and
+/// never ended although their deadline passed, e.g. because the autopilot
+/// crashed while settling. Only looking at recent executions keeps the query
+/// on the time range index.
+#[instrument(skip_all)]
+pub async fn count_orphans(
+    ex: &mut PgConnection,
+    current_block: i64,
+    since: DateTime<Utc>,
+) -> Result<i64, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT COUNT(*)
+FROM settlement_executions
+WHERE start_timestamp >= $2 AND end_timestamp IS NULL AND deadline_block < $1
+    ;"#;
+
+    sqlx::query_scalar(QUERY)
+        .bind(current_block)
+        .bind(since)
+        .fetch_one(ex)
+        .await
+}
+
+/// Deletes up to `limit` settlement executions that started before the
+/// provided timestamp. Returns the number of deleted rows.
+#[instrument(skip_all)]
+pub async fn delete_batch_before(
+    ex: &mut PgConnection,
+    timestamp: DateTime<Utc>,
+    limit: i64,
+) -> Result<u64, sqlx::Error> {
+    const QUERY: &str = r#"
+DELETE FROM settlement_executions
+WHERE ctid IN (
+    SELECT ctid FROM settlement_executions
+    WHERE start_timestamp < $1
+    LIMIT $2
+)
+    ;"#;
+
+    sqlx::query(QUERY)
+        .bind(timestamp)
+        .bind(limit)
+        .execute(ex)
+        .await
+        .map(|result| result.rows_affected())
+}
+
+/// Counts the settlement executions that started before the provided
+/// timestamp. Stops counting at `limit` to keep the query cheap.
+#[instrument(skip_all)]
+pub async fn count_before(
+    ex: &mut PgConnection,
+    timestamp: DateTime<Utc>,
+    limit: i64,
+) -> Result<i64, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT COUNT(*) FROM (
+    SELECT 1 FROM settlement_executions
+    WHERE start_timestamp < $1
+    LIMIT $2
+) AS prunable
+    ;"#;
+
+    sqlx::query_scalar(QUERY)
+        .bind(timestamp)
+        .bind(limit)
+        .fetch_one(ex)
+        .await
+}
+
+/// Returns the most recent auction for which a settlement execution started
+/// before the provided timestamp. Since auction ids are increasing all older
+/// auctions also started before that timestamp.
+#[instrument(skip_all)]
+pub async fn last_auction_started_before(
+    ex: &mut PgConnection,
+    timestamp: DateTime<Utc>,
+) -> Result<Option<AuctionId>, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT MAX(auction_id)
+FROM settlement_executions
+WHERE start_timestamp < $1
+    ;"#;
+
+    sqlx::query_scalar(QUERY)
+        .bind(timestamp)
+        .fetch_one(ex)
+        .await
+}
+
 #[cfg(test)]
 mod tests {
     use {
@@ -170,6 +402,7 @@ mod tests {
             1,
             end_timestamp_a,
             end_block_a,
+            OutcomeKind::Success,
             success_outcome.clone(),
         )
         .await
@@ -181,6 +414,7 @@ mod tests {
             2,
             end_timestamp_a,
             end_block_a,
+            OutcomeKind::Failed,
             failure_outcome.clone(),
         )
         .await
@@ -195,6 +429,7 @@ mod tests {
             1,
             end_timestamp_b,
             end_block_b,
+            OutcomeKind::Success,
             success_outcome.clone(),
         )
         .await
@@ -267,4 +502,239 @@ mod tests {
     fn now_truncated_to_microseconds() -> DateTime<Utc> {
         Utc::now().with_nanosecond(0).unwrap()
     }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_load_and_detect_orphans() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let solver_a = ByteArray([1u8; 20]);
+        let solver_b = ByteArray([2u8; 20]);
+        let start_timestamp = now_truncated_to_microseconds();
+        // (auction, solver, deadline block, outcome)
+        let executions = [
+            (1, solver_a, 5, Some((OutcomeKind::Success, "success"))),
+            (
+                2,
+                solver_a,
+                10,
+                Some((OutcomeKind::Failed, "driver failed: boom")),
+            ),
+            // orphaned once block 10 is reached
+            (3, solver_a, 9, None),
+            (3, solver_b, 12, Some((OutcomeKind::Timeout, "timeout"))),
+            // still within its deadline
+            (4, solver_b, 20, None),
+        ];
+        for (auction_id, solver, deadline_block, outcome) in executions {
+            insert(
+                &mut db,
+                auction_id,
+                solver,
+                0,
+                start_timestamp,
+                1,
+                deadline_block,
+            )
+            .await
+            .unwrap();
+            if let Some((outcome_kind, outcome)) = outcome {
+                update(
+                    &mut db,
+                    auction_id,
+                    solver,
+                    0,
+                    start_timestamp,
+                    deadline_block,
+                    outcome_kind,
+                    outcome.to_string(),
+                )
+                .await
+                .unwrap();
+            }
+        }
+
+        async fn auctions(ex: &mut PgConnection, filter: Filter) -> Vec<(AuctionId, Address)> {
+            load(ex, &filter, 10)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|execution| (execution.auction_id, execution.solver))
+                .collect()
+        }
+
+        assert_eq!(auctions(&mut db, Filter::default()).await.len(), 5);
+        assert_eq!(
+            auctions(
+                &mut db,
+                Filter {
+                    solver: Some(solver_b),
+                    ..Default::default()
+                }
+            )
+            .await,
+            [(3, solver_b), (4, solver_b)]
+        );
+        assert_eq!(
+            auctions(
+                &mut db,
+                Filter {
+                    auctions: Some((2, 3)),
+                    solver: Some(solver_a),
+                    ..Default::default()
+                }
+            )
+            .await,
+            [(2, solver_a), (3, solver_a)]
+        );
+        assert_eq!(
+            auctions(
+                &mut db,
+                Filter {
+                    outcome: Some(Outcome::Failed),
+                    ..Default::default()
+                }
+            )
+            .await,
+            [(2, solver_a)]
+        );
+        assert_eq!(
+            auctions(
+                &mut db,
+                Filter {
+                    outcome: Some(Outcome::Unfinished),
+                    ..Default::default()
+                }
+            )
+            .await,
+            [(3, solver_a), (4, solver_b)]
+        );
+        assert_eq!(
+            auctions(
+                &mut db,
+                Filter {
+                    outcome: Some(Outcome::Orphaned),
+                    ..Default::default()
+                }
+            )
+            .await,
+            [(3, solver_a)]
+        );
+        assert!(
+            auctions(
+                &mut db,
+                Filter {
+                    started: Some((
+                        start_timestamp + chrono::Duration::seconds(1),
+                        start_timestamp + chrono::Duration::seconds(2)
+                    )),
+                    ..Default::default()
+                }
+            )
+            .await
+            .is_empty()
+        );
+
+        assert_eq!(
+            count_orphans(&mut db, 10, start_timestamp).await.unwrap(),
+            1
+        );
+        assert_eq!(
+            count_orphans(&mut db, 21, start_timestamp).await.unwrap(),
+            2
+        );
+        assert_eq!(count_orphans(&mut db, 9, start_timestamp).await.unwrap(), 0);
+        // orphans that started before the window don't count anymore
+        assert_eq!(
+            count_orphans(&mut db, 21, start_timestamp + chrono::Duration::seconds(1))
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_set_deviates_from_solution() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let solver = ByteArray([1u8; 20]);
+        insert(
+            &mut db,
+            1,
+            solver,
+            0,
+            now_truncated_to_microseconds(),
+            1,
+            10,
+        )
+        .await
+        .unwrap();
+
+        const QUERY: &str = r#"SELECT deviates_from_solution FROM settlement_executions;"#;
+        let deviates: Option<bool> = sqlx::query_scalar(QUERY).fetch_one(&mut *db).await.unwrap();
+        assert_eq!(deviates, None);
+
+        set_deviates_from_solution(&mut db, 1, solver, 0, true)
+            .await
+            .unwrap();
+        let deviates: Option<bool> = sqlx::query_scalar(QUERY).fetch_one(&mut *db).await.unwrap();
+        assert_eq!(deviates, Some(true));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_prune_before() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let now = now_truncated_to_microseconds();
+        let hours_ago = |hours| now - chrono::Duration::hours(hours);
+        for (auction_id, start_timestamp) in [(1, hours_ago(3)), (2, hours_ago(2)), (3, now)] {
+            insert(
+                &mut db,
+                auction_id,
+                ByteArray([1u8; 20]),
+                0,
+                start_timestamp,
+                1,
+                10,
+            )
+            .await
+            .unwrap();
+        }
+
+        let cutoff = hours_ago(1);
+        assert_eq!(
+            last_auction_started_before(&mut db, cutoff).await.unwrap(),
+            Some(2)
+        );
+        assert_eq!(
+            last_auction_started_before(&mut db, hours_ago(4))
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(count_before(&mut db, cutoff, 10).await.unwrap(), 2);
+        assert_eq!(count_before(&mut db, cutoff, 1).await.unwrap(), 1);
+
+        // deletes in batches of the given size
+        assert_eq!(delete_batch_before(&mut db, cutoff, 1).await.unwrap(), 1);
+        assert_eq!(count_before(&mut db, cutoff, 10).await.unwrap(), 1);
+        assert_eq!(delete_batch_before(&mut db, cutoff, 10).await.unwrap(), 1);
+        assert_eq!(delete_batch_before(&mut db, cutoff, 10).await.unwrap(), 0);
+
+        // executions inside the retention window are untouched
+        let later = now + chrono::Duration::hours(1);
+        assert_eq!(count_before(&mut db, later, 10).await.unwrap(), 1);
+        assert_eq!(
+            last_auction_started_before(&mut db, later).await.unwrap(),
+            Some(3)
+        );
+    }
 }
@@ -0,0 +1,117 @@
+use {
+    crate::{Address, auction::AuctionId},
+    bigdecimal::BigDecimal,
+    sqlx::{PgConnection, QueryBuilder},
+    tracing::instrument,
+};
+
+/// Deviation of the native price of a token implied by a settlement from the
+/// native price of the settled auction.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct PriceDeviation {
+    pub block_number: i64,
+    pub log_index: i64,
+    pub auction_id: AuctionId,
+    pub token: Address,
+    pub auction_price: BigDecimal,
+    pub implied_price: BigDecimal,
+    pub deviation: f64,
+}
+
+#[instrument(skip_all)]
+pub async fn insert(
+    ex: &mut PgConnection,
+    deviations: &[PriceDeviation],
+) -> Result<(), sqlx::Error> {
+    const QUERY: &str = "INSERT INTO settlement_price_deviations (block_number, log_index, \
+                         auction_id, token, auction_price, implied_price, deviation) ";
+
+    if deviations.is_empty() {
+        return Ok(());
+    }
+
+    let mut query_builder = QueryBuilder::new(QUERY);
+    query_builder.push_values(deviations, |mut builder, deviation| {
+        builder
+            .push_bind(deviation.block_number)
+            .push_bind(deviation.log_index)
+            .push_bind(deviation.auction_id)
+            .push_bind(deviation.token)
+            .push_bind(deviation.auction_price.clone())
+            .push_bind(deviation.implied_price.clone())
+            .push_bind(deviation.deviation);
+    });
+    // settlements can get observed again after a reorg
+    query_builder.push(" ON CONFLICT DO NOTHING");
+
+    query_builder.build().execute(ex).await?;
+
+    Ok(())
+}
+
+#[instrument(skip_all)]
+pub async fn fetch(
+    ex: &mut PgConnection,
+    auction_id: AuctionId,
+) -> Result<Vec<PriceDeviation>, sqlx::Error> {
+    const QUERY: &str =
+        r#"SELECT * FROM settlement_price_deviations WHERE auction_id = $1 ORDER BY token"#;
+    sqlx::query_as(QUERY).bind(auction_id).fetch_all(ex).await
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::byte_array::ByteArray, sqlx::Connection};
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_roundtrip() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        insert(&mut db, &[]).await.unwrap();
+        assert!(fetch(&mut db, 1).await.unwrap().is_empty());
+
+        let deviation = |token: u8, deviation: f64| PriceDeviation {
+            block_number: 10,
+            log_index: 2,
+            auction_id: 1,
+            token: ByteArray([token; 20]),
+            auction_price: 100.into(),
+            implied_price: 150.into(),
+            deviation,
+        };
+        let input = vec![deviation(1, 0.5), deviation(2, -0.25)];
+        insert(&mut db, &input).await.unwrap();
+        // inserting the same settlement again is a no-op
+        insert(&mut db, &[deviation(1, 1.)]).await.unwrap();
+
+        assert_eq!(fetch(&mut db, 1).await.unwrap(), input);
+        assert!(fetch(&mut db, 2).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_deletes_reorged_deviations() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let deviation = |block_number: i64| PriceDeviation {
+            block_number,
+            log_index: 0,
+            auction_id: 1,
+            token: ByteArray([block_number as u8; 20]),
+            auction_price: 100.into(),
+            implied_price: 150.into(),
+            deviation: 0.5,
+        };
+        insert(&mut db, &[deviation(10), deviation(11), deviation(12)])
+            .await
+            .unwrap();
+
+        crate::settlements::delete(&mut db, 11).await.unwrap();
+        assert_eq!(fetch(&mut db, 1).await.unwrap(), [deviation(10)]);
+    }
+}
@@ -8,21 +8,48 @@ use {
 pub async fn find_settlement_transaction(
     ex: &mut PgConnection,
     auction_id: i64,
-    solver: Address,
+    solvers: &[Address],
 ) -> Result<Option<TransactionHash>, sqlx::Error> {
     const QUERY: &str = r#"
 SELECT tx_hash
 FROM settlements
 WHERE
-    auction_id = $1 AND solver = $2
+    auction_id = $1 AND solver = ANY($2)
     "#;
     sqlx::query_as(QUERY)
         .bind(auction_id)
-        .bind(solver)
+        .bind(solvers)
         .fetch_optional(ex)
         .await
 }
 
+/// Finds all settlement transactions of the solver for the auction that
+/// were mined within the (inclusive) block range, ordered by their position
+/// on chain.
+#[instrument(skip_all)]
+pub async fn find_settlement_transaction_in_range(
+    ex: &mut PgConnection,
+    auction_id: i64,
+    solver: Address,
+    from_block: i64,
+    to_block: i64,
+) -> Result<Vec<TransactionHash>, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT tx_hash
+FROM settlements
+WHERE
+    auction_id = $1 AND solver = $2 AND block_number BETWEEN $3 AND $4
+ORDER BY block_number ASC, log_index ASC
+    "#;
+    sqlx::query_scalar(QUERY)
+        .bind(auction_id)
+        .bind(solver)
+        .bind(from_block)
+        .bind(to_block)
+        .fetch_all(ex)
+        .await
+}
+
 #[derive(Debug, sqlx::FromRow)]
 pub struct SettlementEvent {
     pub block_number: i64,
@@ -108,6 +135,11 @@ pub async fn delete(
     ex.execute(sqlx::query(QUERY_JIT_ORDERS).bind(delete_from_block_number))
         .await?;
 
+    const QUERY_PRICE_DEVIATIONS: &str =
+        "DELETE FROM settlement_price_deviations WHERE block_number >= $1;";
+    ex.execute(sqlx::query(QUERY_PRICE_DEVIATIONS).bind(delete_from_block_number))
+        .await?;
+
     Ok(())
 }
 
@@ -215,4 +247,56 @@ mod tests {
 
         assert!(settlement.is_none());
     }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_find_settlement_transaction_in_range() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let solver = ByteArray([1; 20]);
+        for (block_number, log_index, auction_id, solver) in [
+            (1, 0, 1, solver),
+            (2, 0, 1, solver),
+            (2, 1, 1, solver),
+            // different auction
+            (3, 0, 2, solver),
+            // different solver
+            (4, 0, 1, ByteArray([2; 20])),
+            (5, 0, 1, solver),
+        ] {
+            let index = EventIndex {
+                block_number,
+                log_index,
+            };
+            let settlement = Settlement {
+                solver,
+                transaction_hash: ByteArray([(block_number * 10 + log_index) as u8; 32]),
+            };
+            crate::events::insert_settlement(&mut db, &index, &settlement)
+                .await
+                .unwrap();
+            update_settlement_auction(&mut db, block_number, log_index, auction_id)
+                .await
+                .unwrap();
+        }
+
+        let found = find_settlement_transaction_in_range(&mut db, 1, solver, 2, 5)
+            .await
+            .unwrap();
+        assert_eq!(
+            found,
+            [
+                ByteArray([20; 32]),
+                ByteArray([21; 32]),
+                ByteArray([50; 32])
+            ]
+        );
+
+        let found = find_settlement_transaction_in_range(&mut db, 1, solver, 3, 4)
+            .await
+            .unwrap();
+        assert!(found.is_empty());
+    }
 }
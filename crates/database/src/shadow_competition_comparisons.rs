@@ -0,0 +1,95 @@
+use {crate::auction::AuctionId, bigdecimal::BigDecimal, sqlx::PgConnection, tracing::instrument};
+
+/// How a candidate driver performed in a shadow competition compared to the
+/// production competition of the same auction.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Comparison {
+    pub auction_id: AuctionId,
+    pub candidate: String,
+    pub candidate_score: Option<BigDecimal>,
+    pub production_score: Option<BigDecimal>,
+    pub would_win: bool,
+    pub ranking_diverged: bool,
+}
+
+#[instrument(skip_all)]
+pub async fn insert(ex: &mut PgConnection, comparison: &Comparison) -> Result<(), sqlx::Error> {
+    const QUERY: &str = r#"
+INSERT INTO shadow_competition_comparisons (auction_id, candidate, candidate_score, production_score, would_win, ranking_diverged)
+VALUES ($1, $2, $3, $4, $5, $6)
+ON CONFLICT (auction_id, candidate) DO NOTHING
+    ;"#;
+    sqlx::query(QUERY)
+        .bind(comparison.auction_id)
+        .bind(&comparison.candidate)
+        .bind(&comparison.candidate_score)
+        .bind(&comparison.production_score)
+        .bind(comparison.would_win)
+        .bind(comparison.ranking_diverged)
+        .execute(ex)
+        .await
+        .map(|_| ())
+}
+
+/// Returns the comparisons of all candidates for the given auction.
+#[instrument(skip_all)]
+pub async fn fetch(
+    ex: &mut PgConnection,
+    auction_id: AuctionId,
+) -> Result<Vec<Comparison>, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT auction_id, candidate, candidate_score, production_score, would_win, ranking_diverged
+FROM shadow_competition_comparisons
+WHERE auction_id = $1
+ORDER BY candidate
+    ;"#;
+    sqlx::query_as(QUERY).bind(auction_id).fetch_all(ex).await
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, sqlx::Connection};
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_roundtrip() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let winning = Comparison {
+            auction_id: 1,
+            candidate: "a".to_owned(),
+            candidate_score: Some(3.into()),
+            production_score: Some(2.into()),
+            would_win: true,
+            ranking_diverged: false,
+        };
+        let without_solution = Comparison {
+            auction_id: 1,
+            candidate: "b".to_owned(),
+            candidate_score: None,
+            production_score: Some(2.into()),
+            would_win: false,
+            ranking_diverged: true,
+        };
+        insert(&mut db, &without_solution).await.unwrap();
+        insert(&mut db, &winning).await.unwrap();
+        // duplicates are ignored
+        insert(
+            &mut db,
+            &Comparison {
+                would_win: false,
+                ..winning.clone()
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            fetch(&mut db, 1).await.unwrap(),
+            vec![winning, without_solution]
+        );
+        assert!(fetch(&mut db, 2).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+use {crate::auction::AuctionId, bigdecimal::BigDecimal, sqlx::PgConnection, tracing::instrument};
+
+/// Where the gas of a solution comes from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "GasSource")]
+#[sqlx(rename_all = "lowercase")]
+pub enum GasSource {
+    /// The driver reported the gas along with the solution.
+    Reported,
+    /// The gas got estimated based on the calldata of the solution.
+    Estimated,
+    /// Estimating the gas failed so a configured default got used.
+    Fallback,
+}
+
+#[derive(Clone, Debug, PartialEq, sqlx::FromRow)]
+pub struct SolutionGas {
+    pub auction_id: AuctionId,
+    pub solution_uid: i64,
+    pub gas: BigDecimal,
+    pub source: GasSource,
+}
+
+#[instrument(skip_all)]
+pub async fn insert(ex: &mut PgConnection, gas: &SolutionGas) -> Result<(), sqlx::Error> {
+    const QUERY: &str = r#"
+INSERT INTO solution_gas (auction_id, solution_uid, gas, source)
+VALUES ($1, $2, $3, $4)
+ON CONFLICT (auction_id, solution_uid) DO NOTHING
+    ;"#;
+    sqlx::query(QUERY)
+        .bind(gas.auction_id)
+        .bind(gas.solution_uid)
+        .bind(&gas.gas)
+        .bind(gas.source)
+        .execute(ex)
+        .await
+        .map(|_| ())
+}
+
+/// Returns the gas of all winning solutions of the given auction.
+#[instrument(skip_all)]
+pub async fn fetch(
+    ex: &mut PgConnection,
+    auction_id: AuctionId,
+) -> Result<Vec<SolutionGas>, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT * FROM solution_gas
+WHERE auction_id = $1
+ORDER BY solution_uid
+    ;"#;
+    sqlx::query_as(QUERY).bind(auction_id).fetch_all(ex).await
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, sqlx::Connection};
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_roundtrip() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let gas = |solution_uid, gas: u32, source| SolutionGas {
+            auction_id: 1,
+            solution_uid,
+            gas: gas.into(),
+            source,
+        };
+        insert(&mut db, &gas(2, 300_000, GasSource::Fallback))
+            .await
+            .unwrap();
+        insert(&mut db, &gas(0, 150_000, GasSource::Reported))
+            .await
+            .unwrap();
+        insert(&mut db, &gas(1, 200_000, GasSource::Estimated))
+            .await
+            .unwrap();
+        // duplicates are ignored
+        insert(&mut db, &gas(1, 1, GasSource::Fallback))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fetch(&mut db, 1).await.unwrap(),
+            vec![
+                gas(0, 150_000, GasSource::Reported),
+                gas(1, 200_000, GasSource::Estimated),
+                gas(2, 300_000, GasSource::Fallback),
+            ]
+        );
+        assert!(fetch(&mut db, 2).await.unwrap().is_empty());
+    }
+}
@@ -111,6 +111,53 @@ GROUP BY sc.id
     sqlx::query_as(QUERY).bind(tx_hash).fetch_optional(ex).await
 }
 
+/// Deletes up to `limit` of the oldest competitions with an auction id up to
+/// and including `last_auction`. Returns the number of deleted rows.
+#[instrument(skip_all)]
+pub async fn delete_up_to(
+    ex: &mut PgConnection,
+    last_auction: AuctionId,
+    limit: i64,
+) -> Result<u64, sqlx::Error> {
+    const QUERY: &str = r#"
+DELETE FROM solver_competitions
+WHERE id IN (
+    SELECT id FROM solver_competitions
+    WHERE id <= $1
+    ORDER BY id
+    LIMIT $2
+)
+    ;"#;
+    sqlx::query(QUERY)
+        .bind(last_auction)
+        .bind(limit)
+        .execute(ex)
+        .await
+        .map(|result| result.rows_affected())
+}
+
+/// Counts the competitions with an auction id up to and including
+/// `last_auction`. Stops counting at `limit` to keep the query cheap.
+#[instrument(skip_all)]
+pub async fn count_up_to(
+    ex: &mut PgConnection,
+    last_auction: AuctionId,
+    limit: i64,
+) -> Result<i64, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT COUNT(*) FROM (
+    SELECT 1 FROM solver_competitions
+    WHERE id <= $1
+    LIMIT $2
+) AS prunable
+    ;"#;
+    sqlx::query_scalar(QUERY)
+        .bind(last_auction)
+        .bind(limit)
+        .fetch_one(ex)
+        .await
+}
+
 #[cfg(test)]
 mod tests {
     use {
@@ -248,4 +295,29 @@ mod tests {
             .unwrap();
         assert!(value_.is_none());
     }
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_prune_up_to() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        for id in 1..=3 {
+            save(&mut db, id, &JsonValue::Bool(true)).await.unwrap();
+        }
+
+        assert_eq!(count_up_to(&mut db, 2, 10).await.unwrap(), 2);
+        assert_eq!(count_up_to(&mut db, 2, 1).await.unwrap(), 1);
+
+        // the oldest competitions get deleted first
+        assert_eq!(delete_up_to(&mut db, 2, 1).await.unwrap(), 1);
+        assert!(load_by_id(&mut db, 1).await.unwrap().is_none());
+        assert!(load_by_id(&mut db, 2).await.unwrap().is_some());
+        assert_eq!(delete_up_to(&mut db, 2, 10).await.unwrap(), 1);
+        assert_eq!(delete_up_to(&mut db, 2, 10).await.unwrap(), 0);
+
+        // competitions inside the retention window are untouched
+        assert!(load_by_id(&mut db, 3).await.unwrap().is_some());
+    }
 }
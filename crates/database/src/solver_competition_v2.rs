@@ -145,10 +145,11 @@ pub async fn load_by_id(
 
     // we set `ranking to uid + 1` because uids get assigned from best to worst
     // solution starting at 0
+    // solutions of observe-only drivers are not part of the public competition
     const FETCH_SOLUTIONS: &str = r#"
         SELECT uid, uid + 1 as ranking, solver, is_winner, filtered_out, score, price_tokens, price_values
         FROM proposed_solutions
-        WHERE auction_id = $1;
+        WHERE auction_id = $1 AND NOT observe_only;
     "#;
     let solutions: Vec<ProposedSolution> = sqlx::query_as(FETCH_SOLUTIONS)
         .bind(id)
@@ -160,6 +161,10 @@ pub async fn load_by_id(
             COALESCE(o.sell_token, pjo.sell_token) AS sell_token,
             COALESCE(o.buy_token, pjo.buy_token) AS buy_token
         FROM proposed_trade_executions AS pte
+        JOIN proposed_solutions ps ON
+            pte.auction_id = ps.auction_id
+            AND pte.solution_uid = ps.uid
+            AND NOT ps.observe_only
         LEFT JOIN orders o ON
             pte.order_uid = o.uid
         LEFT JOIN proposed_jit_orders pjo ON
@@ -313,6 +318,8 @@ pub struct Solution {
     pub solver: Address,
     pub is_winner: bool,
     pub filtered_out: bool,
+    /// Whether the solution was proposed by an observe-only driver.
+    pub observe_only: bool,
     pub score: BigDecimal,
     pub orders: Vec<Order>,
     // UCP prices
@@ -357,7 +364,7 @@ async fn save_solutions(
 ) -> Result<(), sqlx::Error> {
     let mut builder = QueryBuilder::new(
         r#"INSERT INTO proposed_solutions 
-        (auction_id, uid, id, solver, is_winner, filtered_out, score, price_tokens, price_values)"#,
+        (auction_id, uid, id, solver, is_winner, filtered_out, observe_only, score, price_tokens, price_values)"#,
     );
 
     builder.push_values(solutions.iter(), |mut b, solution| {
@@ -367,6 +374,7 @@ async fn save_solutions(
             .push_bind(solution.solver)
             .push_bind(solution.is_winner)
             .push_bind(solution.filtered_out)
+            .push_bind(solution.observe_only)
             .push_bind(&solution.score)
             .push_bind(&solution.price_tokens)
             .push_bind(&solution.price_values);
@@ -450,6 +458,7 @@ struct SolutionRow {
     solver: Address,
     is_winner: bool,
     filtered_out: bool,
+    observe_only: bool,
     score: BigDecimal,
     price_tokens: Vec<Address>,
     price_values: Vec<BigDecimal>,
@@ -465,7 +474,7 @@ struct SolutionRow {
 
 const BASE_SOLUTIONS_QUERY: &str = r#"
     SELECT
-        ps.uid, ps.id, ps.solver, ps.is_winner, ps.filtered_out,
+        ps.uid, ps.id, ps.solver, ps.is_winner, ps.filtered_out, ps.observe_only,
         ps.score, ps.price_tokens, ps.price_values,
         pse.order_uid, pse.executed_sell, pse.executed_buy,
         COALESCE(pjo.sell_token, o.sell_token) AS sell_token,
@@ -533,6 +542,7 @@ fn map_rows_to_solutions(rows: Vec<SolutionRow>) -> Result<Vec<Solution>, sqlx::
                 solver: row.solver,
                 is_winner: row.is_winner,
                 filtered_out: row.filtered_out,
+                observe_only: row.observe_only,
                 score: row.score,
                 orders: Vec::new(),
                 price_tokens: row.price_tokens,
@@ -682,6 +692,7 @@ mod tests {
                 price_tokens: Default::default(),
                 price_values: Default::default(),
                 surplus_capturing_jit_order_owners: Default::default(),
+                content_hash: None,
             };
             auction::save(&mut db, auction).await.unwrap();
         }
@@ -696,6 +707,7 @@ mod tests {
                 price_tokens: Default::default(),
                 price_values: Default::default(),
                 surplus_capturing_jit_order_owners: Default::default(),
+                content_hash: None,
             };
             auction::save(&mut db, auction).await.unwrap();
         }
@@ -710,6 +722,7 @@ mod tests {
                 solver: non_settling_solver,
                 is_winner: true,
                 filtered_out: false,
+                observe_only: false,
                 score: Default::default(),
                 orders: Default::default(),
                 price_tokens: Default::default(),
@@ -729,6 +742,7 @@ mod tests {
                 solver: ByteArray([2u8; 20]),
                 is_winner: auction_id != 2,
                 filtered_out: false,
+                observe_only: false,
                 score: Default::default(),
                 orders: Default::default(),
                 price_tokens: Default::default(),
@@ -753,6 +767,7 @@ mod tests {
                 solver: ByteArray([3u8; 20]),
                 is_winner: true,
                 filtered_out: false,
+                observe_only: false,
                 score: Default::default(),
                 orders: Default::default(),
                 price_tokens: Default::default(),
@@ -773,6 +788,7 @@ mod tests {
                 solver: ByteArray([4u8; 20]),
                 is_winner: true,
                 filtered_out: false,
+                observe_only: false,
                 score: Default::default(),
                 orders: Default::default(),
                 price_tokens: Default::default(),
@@ -852,6 +868,7 @@ mod tests {
                 price_tokens: Default::default(),
                 price_values: Default::default(),
                 surplus_capturing_jit_order_owners: Default::default(),
+                content_hash: None,
             };
             auction::save(&mut db, auction).await.unwrap();
         }
@@ -866,6 +883,7 @@ mod tests {
                 solver: low_settling_solver,
                 is_winner: true,
                 filtered_out: false,
+                observe_only: false,
                 score: Default::default(),
                 orders: Default::default(),
                 price_tokens: Default::default(),
@@ -900,6 +918,7 @@ mod tests {
                 solver: non_settling_solver,
                 is_winner: true,
                 filtered_out: false,
+                observe_only: false,
                 score: Default::default(),
                 orders: Default::default(),
                 price_tokens: Default::default(),
@@ -920,6 +939,7 @@ mod tests {
                 solver: settling_solver,
                 is_winner: true,
                 filtered_out: false,
+                observe_only: false,
                 score: Default::default(),
                 orders: Default::default(),
                 price_tokens: Default::default(),
@@ -1057,6 +1077,7 @@ mod tests {
             price_tokens: vec![ByteArray([1u8; 20])],
             price_values: vec![BigDecimal::from(100)],
             surplus_capturing_jit_order_owners: vec![],
+            content_hash: None,
         };
         auction::save(&mut db, auction).await.unwrap();
 
@@ -1103,6 +1124,7 @@ mod tests {
             price_tokens: vec![order_sell_token],
             price_values: vec![order_limit_sell.clone()],
             surplus_capturing_jit_order_owners: vec![],
+            content_hash: None,
         };
         auction::save(&mut db, auction).await.unwrap();
 
@@ -1173,12 +1195,13 @@ mod tests {
         assert_eq!(solver_competition.auction.id, 1);
 
         // proposed_solutions
-        let solutions = vec![Solution {
+        let mut solutions = vec![Solution {
             uid: 0,
             id: 0.into(),
             solver: ByteArray([1u8; 20]),
             is_winner: true,
             filtered_out: false,
+            observe_only: false,
             score: BigDecimal::from(100),
             orders: vec![Order {
                 uid: order_uid,
@@ -1193,6 +1216,15 @@ mod tests {
             price_tokens: vec![ByteArray([1u8; 20])],
             price_values: vec![BigDecimal::from(100)],
         }];
+        // solutions of observe-only drivers get stored but are not part of the
+        // competition
+        solutions.push(Solution {
+            uid: 1,
+            solver: ByteArray([2u8; 20]),
+            is_winner: false,
+            observe_only: true,
+            ..solutions[0].clone()
+        });
         save_solutions(&mut db, auction_id, &solutions)
             .await
             .unwrap();
@@ -1240,5 +1272,15 @@ mod tests {
         assert_eq!(solver_competition.reference_scores.len(), 1);
         assert_eq!(solver_competition.solutions.len(), 1);
         assert_eq!(solver_competition.solutions.first().unwrap().uid, 0);
+
+        // the observe-only solution is still stored with its marker
+        let stored = fetch(&mut db, auction_id).await.unwrap();
+        assert_eq!(
+            stored
+                .iter()
+                .map(|solution| (solution.uid, solution.observe_only))
+                .collect::<Vec<_>>(),
+            [(0, false), (1, true)]
+        );
     }
 }
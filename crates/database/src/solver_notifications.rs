@@ -0,0 +1,102 @@
+use {
+    crate::Address,
+    chrono::{DateTime, Utc},
+    sqlx::PgConnection,
+    tracing::instrument,
+};
+
+/// What a solver got notified about.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "SolverNotificationKind")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Kind {
+    Banned,
+    CompetitionOutcome,
+}
+
+/// An attempt to notify a solver.
+#[derive(Clone, Debug, Eq, PartialEq, sqlx::FromRow)]
+pub struct Notification {
+    pub solver: Address,
+    pub kind: Kind,
+    pub timestamp: DateTime<Utc>,
+    /// Why the notification couldn't be delivered. `None` if it got delivered.
+    pub error: Option<String>,
+}
+
+#[instrument(skip_all)]
+pub async fn insert(ex: &mut PgConnection, notification: &Notification) -> Result<(), sqlx::Error> {
+    const QUERY: &str = r#"
+INSERT INTO solver_notifications (solver, kind, timestamp, error)
+VALUES ($1, $2, $3, $4)
+    ;"#;
+    sqlx::query(QUERY)
+        .bind(notification.solver)
+        .bind(notification.kind)
+        .bind(notification.timestamp)
+        .bind(&notification.error)
+        .execute(ex)
+        .await?;
+    Ok(())
+}
+
+/// Fetches all notifications of `solver` in `[from, to)` from oldest to
+/// newest.
+#[instrument(skip_all)]
+pub async fn fetch(
+    ex: &mut PgConnection,
+    solver: &Address,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<Notification>, sqlx::Error> {
+    const QUERY: &str = r#"
+SELECT * FROM solver_notifications
+WHERE solver = $1 AND timestamp >= $2 AND timestamp < $3
+ORDER BY timestamp
+    ;"#;
+    sqlx::query_as(QUERY)
+        .bind(solver)
+        .bind(from)
+        .bind(to)
+        .fetch_all(ex)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::byte_array::ByteArray, sqlx::Connection};
+
+    #[tokio::test]
+    #[ignore]
+    async fn postgres_roundtrip() {
+        let mut db = PgConnection::connect("postgresql://").await.unwrap();
+        let mut db = db.begin().await.unwrap();
+        crate::clear_DANGER_(&mut db).await.unwrap();
+
+        let time = |seconds| DateTime::from_timestamp(seconds, 0).unwrap();
+        let notification = |solver: u8, kind, seconds, error: Option<&str>| Notification {
+            solver: ByteArray([solver; 20]),
+            kind,
+            timestamp: time(seconds),
+            error: error.map(String::from),
+        };
+        let banned = notification(1, Kind::Banned, 20, Some("timeout"));
+        let outcome = notification(1, Kind::CompetitionOutcome, 10, None);
+        let other_solver = notification(2, Kind::Banned, 10, None);
+        let later = notification(1, Kind::CompetitionOutcome, 30, None);
+        for notification in [&banned, &outcome, &other_solver, &later] {
+            insert(&mut db, notification).await.unwrap();
+        }
+
+        let fetched = fetch(&mut db, &ByteArray([1; 20]), time(10), time(30))
+            .await
+            .unwrap();
+        assert_eq!(fetched, [outcome, banned]);
+        assert!(
+            fetch(&mut db, &ByteArray([3; 20]), time(0), time(100))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
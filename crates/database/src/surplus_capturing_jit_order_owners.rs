@@ -20,6 +20,25 @@ pub async fn insert(
     Ok(())
 }
 
+/// Like [`insert`] but overwrites the owners if they are already stored.
+#[instrument(skip_all)]
+pub async fn upsert(
+    ex: &mut PgConnection,
+    auction_id: AuctionId,
+    surplus_capturing_jit_order_owners: &[Address],
+) -> Result<(), sqlx::Error> {
+    const QUERY: &str = r#"
+INSERT INTO surplus_capturing_jit_order_owners (auction_id, owners) VALUES ($1, $2)
+ON CONFLICT (auction_id) DO UPDATE SET owners = EXCLUDED.owners;
+    "#;
+    sqlx::query(QUERY)
+        .bind(auction_id)
+        .bind(surplus_capturing_jit_order_owners)
+        .execute(ex)
+        .await?;
+    Ok(())
+}
+
 #[instrument(skip_all)]
 pub async fn fetch(
     ex: &mut PgConnection,
@@ -55,5 +74,11 @@ mod tests {
         // non-existent auction
         let output = fetch(&mut db, 2).await.unwrap();
         assert!(output.is_none());
+
+        let owners = vec![ByteArray([3; 20])];
+        upsert(&mut db, 1, &owners).await.unwrap();
+        upsert(&mut db, 2, &owners).await.unwrap();
+        assert_eq!(fetch(&mut db, 1).await.unwrap(), Some(owners.clone()));
+        assert_eq!(fetch(&mut db, 2).await.unwrap(), Some(owners));
     }
 }
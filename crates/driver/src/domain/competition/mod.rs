@@ -27,7 +27,7 @@ use {
         time::Duration,
     },
     tap::TapFallible,
-    tokio::sync::{mpsc, oneshot},
+    tokio::sync::{mpsc, oneshot, watch},
     tracing::{Instrument, instrument},
 };
 
@@ -58,9 +58,12 @@ pub struct Competition {
     pub simulator: Simulator,
     pub mempools: Mempools,
     /// Cached solutions with the most recent solutions at the front.
-    pub settlements: Mutex<VecDeque<Settlement>>,
+    pub settlements: Mutex<VecDeque<CachedSettlement>>,
     pub bad_tokens: Arc<bad_tokens::Detector>,
     settle_queue: mpsc::Sender<SettleRequest>,
+    /// Settlements that are currently being submitted, keyed by auction and
+    /// solution ID. Notifying them triggers a rebroadcast.
+    executing: Executing,
 }
 
 impl Competition {
@@ -83,6 +86,7 @@ impl Competition {
             settlements: Default::default(),
             settle_queue: settle_sender,
             bad_tokens,
+            executing: Default::default(),
         });
 
         let competition_clone = Arc::clone(&competition);
@@ -264,7 +268,9 @@ impl Competition {
             })
             .unzip();
 
-        let Some(settlement) = settlement else {
+        let Some((settlement, reported_score)) =
+            settlement.zip(score.as_ref().map(|solved| solved.score))
+        else {
             // Don't wait for the deadline because we can't produce a solution anyway.
             return Ok(score);
         };
@@ -272,7 +278,10 @@ impl Competition {
 
         {
             let mut lock = self.settlements.lock().unwrap();
-            lock.push_front(settlement.clone());
+            lock.push_front(CachedSettlement {
+                settlement: settlement.clone(),
+                score: reported_score,
+            });
 
             /// Number of solutions that may be cached at most.
             const MAX_SOLUTION_STORAGE: usize = 5;
@@ -296,7 +305,7 @@ impl Competition {
                         self.settlements
                             .lock()
                             .unwrap()
-                            .retain(|s| s.solution().get() != solution_id);
+                            .retain(|s| s.settlement.solution().get() != solution_id);
                         notify::simulation_failed(
                             &self.solver,
                             auction.id(),
@@ -324,8 +333,8 @@ impl Competition {
             .lock()
             .unwrap()
             .iter()
-            .find(|s| s.solution().get() == solution_id && s.auction_id == auction_id)
-            .cloned()
+            .find(|s| s.is(auction_id, solution_id))
+            .map(|s| s.settlement.clone())
             .ok_or(Error::SolutionNotAvailable)?;
         Ok(Revealed {
             internalized_calldata: settlement
@@ -346,6 +355,34 @@ impl Competition {
         auction_id: auction::Id,
         solution_id: u64,
         submission_deadline: BlockNo,
+        score: Option<eth::Ether>,
+    ) -> Result<Settled, Error> {
+        self.enqueue_settlement(auction_id, solution_id, submission_deadline, score, None)
+            .await
+    }
+
+    /// Submit the calldata of a solution another driver won the auction with.
+    /// The autopilot asks for this when this driver is its fallback submitter
+    /// and the winning driver didn't manage to settle in time. Goes through
+    /// the settle queue so it can't interfere with the driver's own
+    /// settlements.
+    pub async fn relay(
+        &self,
+        auction_id: auction::Id,
+        calldata: Bytes<Vec<u8>>,
+        submission_deadline: BlockNo,
+    ) -> Result<Settled, Error> {
+        self.enqueue_settlement(auction_id, 0, submission_deadline, None, Some(calldata))
+            .await
+    }
+
+    async fn enqueue_settlement(
+        &self,
+        auction_id: auction::Id,
+        solution_id: u64,
+        submission_deadline: BlockNo,
+        score: Option<eth::Ether>,
+        relay: Option<Bytes<Vec<u8>>>,
     ) -> Result<Settled, Error> {
         let (response_sender, response_receiver) = oneshot::channel();
 
@@ -353,6 +390,8 @@ impl Competition {
             auction_id,
             solution_id,
             submission_deadline,
+            score,
+            relay,
             response_sender,
             tracing_span: tracing::Span::current(),
         };
@@ -368,6 +407,19 @@ impl Competition {
         })?
     }
 
+    /// Resubmits the settlement of a solution that is currently being
+    /// executed with a higher gas price. This doesn't go through the settle
+    /// queue since the solution already left it.
+    pub fn rebroadcast(&self, auction_id: auction::Id, solution_id: u64) -> Result<(), Error> {
+        let executing = self.executing.lock().unwrap();
+        let rebroadcast = executing
+            .get(&(auction_id, solution_id))
+            .ok_or(Error::SolutionNotAvailable)?;
+        tracing::info!(?auction_id, solution_id, "rebroadcasting settlement");
+        rebroadcast.send_replace(());
+        Ok(())
+    }
+
     pub fn ensure_settle_queue_capacity(&self) -> Result<(), Error> {
         if self.settle_queue.capacity() == 0 {
             tracing::warn!("settlement queue is full; auction is rejected");
@@ -386,6 +438,8 @@ impl Competition {
                 auction_id,
                 solution_id,
                 submission_deadline,
+                score,
+                relay,
                 mut response_sender,
                 tracing_span,
             } = request;
@@ -401,11 +455,23 @@ impl Competition {
                 }
 
                 observe::settling();
-                let settle_fut = Box::pin(self.process_settle_request(
-                    auction_id,
-                    solution_id,
-                    submission_deadline,
-                ));
+                let settle_fut = Box::pin(async {
+                    match relay {
+                        Some(calldata) => {
+                            self.process_relay_request(auction_id, calldata, submission_deadline)
+                                .await
+                        }
+                        None => {
+                            self.process_settle_request(
+                                auction_id,
+                                solution_id,
+                                submission_deadline,
+                                score,
+                            )
+                            .await
+                        }
+                    }
+                });
                 let closed_fut = Box::pin(response_sender.closed());
                 let result = match futures::future::select(closed_fut, settle_fut).await {
                     // Cancel the settlement task if the sender is closed (client likely
@@ -436,17 +502,33 @@ impl Competition {
         auction_id: auction::Id,
         solution_id: u64,
         submission_deadline: BlockNo,
+        score: Option<eth::Ether>,
     ) -> Result<Settled, Error> {
         let mut settlement = {
             let mut lock = self.settlements.lock().unwrap();
             let index = lock
                 .iter()
-                .position(|s| s.solution().get() == solution_id && s.auction_id == auction_id)
+                .position(|s| s.is(auction_id, solution_id))
                 .ok_or(Error::SolutionNotAvailable)?;
+            if score.is_some_and(|score| score != lock[index].score) {
+                tracing::warn!(
+                    ?score,
+                    reported = ?lock[index].score,
+                    "settle request score doesn't match the solution"
+                );
+                return Err(Error::ScoreMismatch);
+            }
             // remove settlement to ensure we can't settle it twice by accident
             lock.swap_remove_front(index)
                 .ok_or(Error::SolutionNotAvailable)?
+                .settlement
         };
+        let (rebroadcast, rebroadcasts) = watch::channel(());
+        let _executing = ExecutionGuard::register(
+            &self.executing,
+            (settlement.auction_id, solution_id),
+            rebroadcast,
+        );
 
         // When settling, the gas price must be carefully chosen to ensure the
         // transaction is included in a block before the deadline.
@@ -463,7 +545,7 @@ impl Competition {
 
         let executed = self
             .mempools
-            .execute(&self.solver, &settlement, submission_deadline)
+            .execute(&self.solver, &settlement, submission_deadline, rebroadcasts)
             .await;
         notify::executed(
             &self.solver,
@@ -488,14 +570,54 @@ impl Competition {
         }
     }
 
+    async fn process_relay_request(
+        &self,
+        auction_id: auction::Id,
+        calldata: Bytes<Vec<u8>>,
+        submission_deadline: BlockNo,
+    ) -> Result<Settled, Error> {
+        let tx = eth::Tx {
+            from: self.solver.address(),
+            to: self.eth.contracts().settlement().address().into(),
+            value: 0.into(),
+            input: calldata,
+            access_list: Default::default(),
+        };
+        let estimate = self.eth.estimate_gas(&tx).await.map_err(|err| {
+            tracing::warn!(?err, ?auction_id, "relayed settlement doesn't simulate");
+            Error::SubmissionError
+        })?;
+        let time_limit = submission_time_limit(&self.eth, submission_deadline);
+        let price = self.eth.gas_price(time_limit).await.map_err(|err| {
+            tracing::warn!(?err, ?auction_id, "failed to price relayed settlement");
+            Error::SubmissionError
+        })?;
+        let gas =
+            settlement::Gas::new(estimate, self.eth.block_gas_limit(), price).map_err(|err| {
+                tracing::warn!(?err, ?auction_id, "relayed settlement uses too much gas");
+                Error::SubmissionError
+            })?;
+
+        let tx_hash = self
+            .mempools
+            .relay(&self.solver, &tx, gas, submission_deadline)
+            .await
+            .map_err(|_| Error::SubmissionError)?;
+        Ok(Settled {
+            tx_hash,
+            internalized_calldata: tx.input.clone(),
+            uninternalized_calldata: tx.input,
+        })
+    }
+
     /// The ID of the auction being competed on.
     pub fn auction_id(&self, solution_id: u64) -> Option<auction::Id> {
         self.settlements
             .lock()
             .unwrap()
             .iter()
-            .find(|s| s.solution().get() == solution_id)
-            .map(|s| s.auction_id)
+            .find(|s| s.settlement.solution().get() == solution_id)
+            .map(|s| s.settlement.auction_id)
     }
 
     /// Returns whether the settlement can be executed or would revert.
@@ -591,14 +713,57 @@ fn submission_time_limit(eth: &Ethereum, submission_deadline: BlockNo) -> Option
     // time):
     time_limit.checked_div(2)
 }
+type Executing = Mutex<HashMap<(auction::Id, u64), watch::Sender<()>>>;
+
+/// Keeps a settlement in [`Competition::executing`] until its execution
+/// finishes or gets cancelled.
+struct ExecutionGuard<'a> {
+    executing: &'a Executing,
+    key: (auction::Id, u64),
+}
+
+impl<'a> ExecutionGuard<'a> {
+    fn register(
+        executing: &'a Executing,
+        key: (auction::Id, u64),
+        rebroadcast: watch::Sender<()>,
+    ) -> Self {
+        executing.lock().unwrap().insert(key, rebroadcast);
+        Self { executing, key }
+    }
+}
+
+impl Drop for ExecutionGuard<'_> {
+    fn drop(&mut self) {
+        self.executing.lock().unwrap().remove(&self.key);
+    }
+}
+
 struct SettleRequest {
     auction_id: auction::Id,
     solution_id: u64,
     submission_deadline: BlockNo,
+    score: Option<eth::Ether>,
+    /// Calldata of another driver's solution to submit instead of one of
+    /// this driver's solutions.
+    relay: Option<Bytes<Vec<u8>>>,
     response_sender: oneshot::Sender<Result<Settled, Error>>,
     tracing_span: tracing::Span,
 }
 
+/// A proposed settlement together with the score the driver reported for it.
+#[derive(Clone)]
+pub struct CachedSettlement {
+    pub settlement: Settlement,
+    pub score: eth::Ether,
+}
+
+impl CachedSettlement {
+    fn is(&self, auction_id: auction::Id, solution_id: u64) -> bool {
+        self.settlement.solution().get() == solution_id && self.settlement.auction_id == auction_id
+    }
+}
+
 /// Solution information sent to the protocol by the driver before the solution
 /// ranking happens.
 #[derive(Debug)]
@@ -670,4 +835,6 @@ pub enum Error {
     TooManyPendingSettlements,
     #[error("no valid orders found in the auction")]
     NoValidOrdersFound,
+    #[error("the score doesn't match the one reported for the solution")]
+    ScoreMismatch,
 }
@@ -16,6 +16,7 @@ use {
     futures::{FutureExt, StreamExt, future::select_ok},
     std::ops::Sub,
     thiserror::Error,
+    tokio::sync::watch,
     tracing::Instrument,
 };
 
@@ -42,18 +43,69 @@ impl Mempools {
         }
     }
 
-    /// Publish a settlement to the mempools.
+    /// Publish a settlement to the mempools. Every change signaled through
+    /// `rebroadcasts` resubmits the pending transaction with a higher gas
+    /// price.
     pub async fn execute(
         &self,
         solver: &Solver,
         settlement: &Settlement,
         submission_deadline: BlockNo,
+        rebroadcasts: watch::Receiver<()>,
+    ) -> Result<eth::TxId, Error> {
+        self.publish(
+            solver,
+            settlement.transaction(settlement::Internalization::Enable),
+            settlement.gas,
+            settlement.may_revert(),
+            settlement,
+            submission_deadline,
+            rebroadcasts,
+        )
+        .await
+    }
+
+    /// Publish a transaction with calldata that was encoded by another driver
+    /// to the mempools. Since the calldata can't be inspected it's treated as
+    /// if it may revert.
+    pub async fn relay(
+        &self,
+        solver: &Solver,
+        tx: &eth::Tx,
+        gas: settlement::Gas,
+        submission_deadline: BlockNo,
+    ) -> Result<eth::TxId, Error> {
+        // Nobody can ask for a rebroadcast of relayed transactions.
+        let (_rebroadcast, rebroadcasts) = watch::channel(());
+        self.publish(solver, tx, gas, true, tx, submission_deadline, rebroadcasts)
+            .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn publish(
+        &self,
+        solver: &Solver,
+        tx: &eth::Tx,
+        gas: settlement::Gas,
+        may_revert: bool,
+        settlement: &(impl std::fmt::Debug + Sync),
+        submission_deadline: BlockNo,
+        rebroadcasts: watch::Receiver<()>,
     ) -> Result<eth::TxId, Error> {
         let (submission, _remaining_futures) =
             select_ok(self.mempools.iter().cloned().map(|mempool| {
+                let rebroadcasts = rebroadcasts.clone();
                 async move {
                     let result = self
-                        .submit(&mempool, solver, settlement, submission_deadline)
+                        .submit(
+                            &mempool,
+                            solver,
+                            tx,
+                            gas,
+                            may_revert,
+                            submission_deadline,
+                            rebroadcasts,
+                        )
                         .instrument(tracing::info_span!("mempool", kind = mempool.to_string()))
                         .await;
                     observe::mempool_executed(&mempool, settlement, &result);
@@ -84,24 +136,26 @@ impl Mempools {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn submit(
         &self,
         mempool: &infra::mempool::Mempool,
         solver: &Solver,
-        settlement: &Settlement,
+        tx: &eth::Tx,
+        mut gas: settlement::Gas,
+        may_revert: bool,
         submission_deadline: BlockNo,
+        mut rebroadcasts: watch::Receiver<()>,
     ) -> Result<SubmissionSuccess, Error> {
         // Don't submit risky transactions if revert protection is
         // enabled and the settlement may revert in this mempool.
-        if settlement.may_revert()
+        if may_revert
             && matches!(self.revert_protection(), RevertProtection::Enabled)
             && mempool.may_revert()
         {
             return Err(Error::Disabled);
         }
 
-        let tx = settlement.transaction(settlement::Internalization::Enable);
-
         // Instantiate block stream and skip the current block before we submit the
         // settlement. This way we only run iterations in blocks that can potentially
         // include the settlement.
@@ -130,13 +184,32 @@ impl Mempools {
             }
         }
 
-        let hash = mempool.submit(tx.clone(), settlement.gas, solver).await?;
+        let mut hash = mempool.submit(tx.clone(), gas, solver).await?;
         let submitted_at_block = self.ethereum.current_block().borrow().number;
         tracing::debug!(?hash, current_block = ?submitted_at_block, "submitted tx to the mempool");
 
         // Wait for the transaction to be mined, expired or failing.
         let result = async {
-            while let Some(block) = block_stream.next().await {
+            loop {
+                let block = tokio::select! {
+                    block = block_stream.next() => match block {
+                        Some(block) => block,
+                        None => break,
+                    },
+                    Ok(()) = rebroadcasts.changed() => {
+                        // Replaces the pending transaction since it uses the
+                        // same nonce.
+                        gas.price = gas.price * GAS_PRICE_BUMP;
+                        match mempool.submit(tx.clone(), gas, solver).await {
+                            Ok(replacement) => {
+                                tracing::info!(?hash, ?replacement, "rebroadcasted tx");
+                                hash = replacement;
+                            }
+                            Err(err) => tracing::warn!(?hash, ?err, "failed to rebroadcast tx"),
+                        }
+                        continue;
+                    }
+                };
                 tracing::debug!(?hash, current_block = ?block.number, "checking if tx is confirmed");
                 let receipt = self
                     .ethereum
@@ -165,7 +238,7 @@ impl Mempools {
                         // Check if the current block reached the submission deadline block number
                         if block.number >= submission_deadline {
                             let cancellation_tx_hash = self
-                                .cancel(mempool, settlement.gas.price, solver, blocks_elapsed)
+                                .cancel(mempool, gas.price, solver, blocks_elapsed)
                                 .await
                                 .context("cancellation tx due to deadline failed")?;
                             tracing::info!(
@@ -185,7 +258,7 @@ impl Mempools {
                         if let Err(err) = self.ethereum.estimate_gas(tx).await {
                             if err.is_revert() {
                                 let cancellation_tx_hash = self
-                                    .cancel(mempool, settlement.gas.price, solver, blocks_elapsed)
+                                    .cancel(mempool, gas.price, solver, blocks_elapsed)
                                     .await
                                     .context("cancellation tx due to revert failed")?;
                                 tracing::info!(
@@ -1,8 +1,13 @@
 use {
-    crate::infra::notify,
+    crate::{
+        domain::{competition::auction, eth},
+        infra::notify,
+    },
     chrono::{DateTime, Utc},
+    number::serialization::HexOrDecimalU256,
+    primitive_types::U256,
     serde::Deserialize,
-    serde_with::serde_as,
+    serde_with::{DisplayFromStr, serde_as},
 };
 
 #[serde_as]
@@ -13,6 +18,27 @@ pub enum NotifyRequest {
         reason: BanReason,
         until: DateTime<Utc>,
     },
+    #[serde(rename_all = "camelCase")]
+    CompetitionOutcome {
+        #[serde_as(as = "DisplayFromStr")]
+        auction_id: i64,
+        rank: usize,
+        is_winner: bool,
+        #[serde_as(as = "Option<HexOrDecimalU256>")]
+        reference_score: Option<U256>,
+    },
+}
+
+impl NotifyRequest {
+    /// The auction the notification refers to (if any).
+    pub fn auction_id(&self) -> Option<auction::Id> {
+        match self {
+            NotifyRequest::Banned { .. } => None,
+            NotifyRequest::CompetitionOutcome { auction_id, .. } => {
+                auction::Id::try_from(*auction_id).ok()
+            }
+        }
+    }
 }
 
 #[serde_as]
@@ -23,6 +49,8 @@ pub enum BanReason {
     UnsettledConsecutiveAuctions,
     /// Driver's settle failure rate is above the threshold.
     HighSettleFailureRate,
+    /// The solver is not allowed to settle by the authenticator contract.
+    DenyListed,
 }
 
 impl From<NotifyRequest> for notify::Kind {
@@ -34,9 +62,20 @@ impl From<NotifyRequest> for notify::Kind {
                         notify::BanReason::UnsettledConsecutiveAuctions
                     }
                     BanReason::HighSettleFailureRate => notify::BanReason::HighSettleFailureRate,
+                    BanReason::DenyListed => notify::BanReason::DenyListed,
                 },
                 until,
             },
+            NotifyRequest::CompetitionOutcome {
+                rank,
+                is_winner,
+                reference_score,
+                ..
+            } => notify::Kind::CompetitionOutcome {
+                rank,
+                is_winner,
+                reference_score: reference_score.map(eth::Ether),
+            },
         }
     }
 }
@@ -12,6 +12,7 @@ async fn route(
 ) -> Result<hyper::StatusCode, (hyper::StatusCode, axum::Json<Error>)> {
     let solver = &state.solver().name().0;
     tracing::debug!(?req, ?solver, "received a notification");
-    state.solver().notify(None, None, req.0.into());
+    let auction_id = req.0.auction_id();
+    state.solver().notify(auction_id, None, req.0.into());
     Ok(hyper::StatusCode::OK)
 }
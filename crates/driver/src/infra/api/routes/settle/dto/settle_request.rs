@@ -1,4 +1,10 @@
-use {serde::Deserialize, serde_with::serde_as};
+use {
+    crate::util::serialize,
+    number::serialization::HexOrDecimalU256,
+    primitive_types::U256,
+    serde::Deserialize,
+    serde_with::serde_as,
+};
 
 #[serde_as]
 #[derive(Debug, Deserialize)]
@@ -11,4 +17,19 @@ pub struct SettleRequest {
     /// Auction ID in which this solution is competing.
     #[serde_as(as = "serde_with::DisplayFromStr")]
     pub auction_id: i64,
+    /// Set when the settlement was already requested but didn't appear
+    /// on chain yet and should get resubmitted with a higher gas price.
+    #[serde(default)]
+    pub rebroadcast: bool,
+    /// Score the solution competed with. If set, the settlement is only
+    /// executed if it matches the score reported for the solution.
+    #[serde_as(as = "Option<HexOrDecimalU256>")]
+    #[serde(default)]
+    pub score: Option<U256>,
+    /// Calldata of a solution another driver won the auction with. If set,
+    /// this driver submits it from its own address instead of settling one
+    /// of its solutions and the solution ID and score get ignored.
+    #[serde_as(as = "Option<serialize::Hex>")]
+    #[serde(default)]
+    pub calldata: Option<Vec<u8>>,
 }
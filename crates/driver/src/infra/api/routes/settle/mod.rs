@@ -2,7 +2,7 @@ mod dto;
 
 use {
     crate::{
-        domain::competition::auction,
+        domain::{competition::auction, eth},
         infra::{
             api::{self, Error, State},
             observe,
@@ -24,15 +24,36 @@ async fn route(
     let solver = state.solver().name().to_string();
 
     async move {
+        if req.rebroadcast {
+            return state
+                .competition()
+                .rebroadcast(auction_id, req.solution_id)
+                .map_err(Into::into);
+        }
         observe::settling();
-        let result = state
-            .competition()
-            .settle(
-                auction_id,
-                req.solution_id,
-                req.submission_deadline_latest_block,
-            )
-            .await;
+        let result = match &req.calldata {
+            Some(calldata) => {
+                state
+                    .competition()
+                    .relay(
+                        auction_id,
+                        calldata.clone().into(),
+                        req.submission_deadline_latest_block,
+                    )
+                    .await
+            }
+            None => {
+                state
+                    .competition()
+                    .settle(
+                        auction_id,
+                        req.solution_id,
+                        req.submission_deadline_latest_block,
+                        req.score.map(eth::Ether),
+                    )
+                    .await
+            }
+        };
         observe::settled(state.solver().name(), &result);
         result.map(|_| ()).map_err(Into::into)
     }
@@ -51,6 +51,12 @@ pub enum Kind {
         reason: BanReason,
         until: DateTime<Utc>,
     },
+    /// How the solver performed in the competition of an auction.
+    CompetitionOutcome {
+        rank: usize,
+        is_winner: bool,
+        reference_score: Option<Ether>,
+    },
 }
 
 #[derive(Debug)]
@@ -70,6 +76,8 @@ pub enum BanReason {
     UnsettledConsecutiveAuctions,
     /// Driver's settle failure rate is above the threshold.
     HighSettleFailureRate,
+    /// The solver is not allowed to settle by the authenticator contract.
+    DenyListed,
 }
 
 #[derive(Debug)]
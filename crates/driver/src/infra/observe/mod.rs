@@ -322,7 +322,7 @@ pub fn solver_response(
 /// Observe the result of mempool transaction execution.
 pub fn mempool_executed(
     mempool: &Mempool,
-    settlement: &Settlement,
+    settlement: &impl std::fmt::Debug,
     res: &Result<SubmissionSuccess, mempools::Error>,
 ) {
     match res {
@@ -416,6 +416,7 @@ fn competition_error(err: &competition::Error) -> &'static str {
         competition::Error::SubmissionError => "SubmissionError",
         competition::Error::TooManyPendingSettlements => "TooManyPendingSettlements",
         competition::Error::NoValidOrdersFound => "NoValidOrdersFound",
+        competition::Error::ScoreMismatch => "ScoreMismatch",
     }
 }
 
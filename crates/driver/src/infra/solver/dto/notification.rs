@@ -66,9 +66,21 @@ pub fn new(
                     notify::BanReason::HighSettleFailureRate => {
                         solvers_dto::notification::BanReason::HighSettleFailureRate
                     }
+                    notify::BanReason::DenyListed => {
+                        solvers_dto::notification::BanReason::DenyListed
+                    }
                 },
                 until,
             },
+            notify::Kind::CompetitionOutcome {
+                rank,
+                is_winner,
+                reference_score,
+            } => solvers_dto::notification::Kind::CompetitionOutcome {
+                rank,
+                is_winner,
+                reference_score: reference_score.map(|score| score.0),
+            },
         },
     }
 }
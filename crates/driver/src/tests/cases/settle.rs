@@ -58,6 +58,57 @@ async fn solution_not_available() {
     test.settle(99).await.err().kind("SolutionNotAvailable");
 }
 
+/// Checks that a solution only gets settled if the score in the settle request
+/// matches the one reported for it.
+#[tokio::test]
+#[ignore]
+async fn score_mismatch() {
+    let test = tests::setup()
+        .name("score mismatch")
+        .pool(ab_pool())
+        .order(ab_order())
+        .solution(ab_solution())
+        .done()
+        .await;
+
+    let solved = test.solve().await.ok();
+    let (id, score) = (solved.id(), solved.score());
+    test.settle_with_score(id, score + 1)
+        .await
+        .err()
+        .kind("ScoreMismatch");
+    // the mismatching request doesn't drop the solution
+    test.settle_with_score(id, score)
+        .await
+        .ok()
+        .await
+        .ab_order_executed(&test)
+        .await;
+}
+
+/// Checks that the driver submits calldata another driver encoded from its
+/// own address.
+#[tokio::test]
+#[ignore]
+async fn relay() {
+    let test = tests::setup()
+        .name("relay")
+        .pool(ab_pool())
+        .order(ab_order())
+        .solution(ab_solution())
+        .done()
+        .await;
+
+    let id = test.solve().await.ok().id();
+    let calldata = test.reveal(id).await.ok().uninternalized();
+    test.relay(&calldata)
+        .await
+        .ok()
+        .await
+        .ab_order_executed(&test)
+        .await;
+}
+
 /// Checks that settlements with revert risk are not submitted via public
 /// mempool.
 #[tokio::test]
@@ -312,3 +363,48 @@ async fn accepts_new_settle_requests_after_timeout() {
         .err()
         .kind("FailedToSubmit");
 }
+
+/// Checks that rebroadcasting resubmits a pending settlement without settling
+/// the solution a second time.
+#[tokio::test]
+#[ignore]
+async fn rebroadcasts_pending_settlement() {
+    let test = Arc::new(
+        tests::setup()
+            .name("rebroadcast")
+            .pool(ab_pool())
+            .order(ab_order())
+            .solution(ab_solution())
+            .done()
+            .await,
+    );
+
+    let id = test.solve().await.ok().id();
+    // nothing is being executed yet
+    test.rebroadcast(id)
+        .await
+        .err()
+        .kind("SolutionNotAvailable");
+
+    // Keep the settlement pending until it got rebroadcasted.
+    test.set_auto_mining(false).await;
+    let test_clone = Arc::clone(&test);
+    let settlement = tokio::spawn(async move { test_clone.settle(id).await });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    test.rebroadcast(id).await.ok().await;
+    test.set_auto_mining(true).await;
+
+    settlement
+        .await
+        .unwrap()
+        .ok()
+        .await
+        .ab_order_executed(&test)
+        .await;
+    // the execution is over so there is nothing left to rebroadcast
+    test.rebroadcast(id)
+        .await
+        .err()
+        .kind("SolutionNotAvailable");
+}
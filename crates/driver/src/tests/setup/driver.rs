@@ -1,7 +1,7 @@
 use {
     super::{Mempool, Partial, Solver, Test, blockchain::Blockchain},
     crate::{
-        domain::competition::order,
+        domain::{competition::order, eth},
         infra::config::file::OrderPriorityStrategy,
         tests::{
             hex_address,
@@ -167,12 +167,23 @@ pub fn settle_req(
     submission_deadline_latest_block: u64,
     solution_id: u64,
     auction_id: &str,
+    rebroadcast: bool,
+    score: Option<eth::U256>,
+    calldata: Option<&str>,
 ) -> serde_json::Value {
-    json!({
+    let mut req = json!({
         "solutionId": solution_id,
         "submissionDeadlineLatestBlock": submission_deadline_latest_block,
         "auctionId": auction_id,
-    })
+        "rebroadcast": rebroadcast,
+    });
+    if let Some(score) = score {
+        req["score"] = json!(score.to_string());
+    }
+    if let Some(calldata) = calldata {
+        req["calldata"] = json!(calldata);
+    }
+    req
 }
 
 /// Create a request for the driver /quote endpoint.
@@ -1150,6 +1150,39 @@ impl Test {
     }
 
     pub async fn settle_with_solver(&self, solver_name: &str, solution_id: u64) -> Settle {
+        self.send_settle(solver_name, solution_id, false, None, None)
+            .await
+    }
+
+    /// Call the /settle endpoint with the score the solution is expected to
+    /// have competed with.
+    pub async fn settle_with_score(&self, solution_id: u64, score: eth::U256) -> Settle {
+        self.send_settle(solver::NAME, solution_id, false, Some(score), None)
+            .await
+    }
+
+    /// Call the /settle endpoint asking to rebroadcast a settlement that is
+    /// already being executed.
+    pub async fn rebroadcast(&self, solution_id: u64) -> Settle {
+        self.send_settle(solver::NAME, solution_id, true, None, None)
+            .await
+    }
+
+    /// Call the /settle endpoint asking to submit calldata another driver
+    /// encoded.
+    pub async fn relay(&self, calldata: &str) -> Settle {
+        self.send_settle(solver::NAME, 0, false, None, Some(calldata))
+            .await
+    }
+
+    async fn send_settle(
+        &self,
+        solver_name: &str,
+        solution_id: u64,
+        rebroadcast: bool,
+        score: Option<eth::U256>,
+        calldata: Option<&str>,
+    ) -> Settle {
         let submission_deadline_latest_block: u64 =
             u64::try_from(self.web3().eth().block_number().await.unwrap()).unwrap()
                 + self.settle_submission_deadline;
@@ -1164,6 +1197,9 @@ impl Test {
                 submission_deadline_latest_block,
                 solution_id,
                 &self.auction_id.to_string(),
+                rebroadcast,
+                score,
+                calldata,
             ))
             .send()
             .await
@@ -1472,6 +1508,15 @@ impl RevealOk {
         );
         self
     }
+
+    /// The revealed uninternalized calldata.
+    pub fn uninternalized(&self) -> String {
+        let result: serde_json::Value = serde_json::from_str(&self.body).unwrap();
+        result["calldata"]["uninternalized"]
+            .as_str()
+            .unwrap()
+            .to_owned()
+    }
 }
 
 pub struct RevealErr {
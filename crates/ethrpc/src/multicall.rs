@@ -103,8 +103,6 @@ where
         let len = calls.len();
         let value = calls.iter().map(|call| call.value).max();
 
-        println!("0x{}", hex::encode(encode(calls.clone()).0));
-
         let return_data = match self
             .call(
                 CallRequest {
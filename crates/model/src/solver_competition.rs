@@ -16,6 +16,14 @@ pub struct SolverCompetitionDB {
     pub competition_simulation_block: u64,
     pub auction: CompetitionAuction,
     pub solutions: Vec<SolverSettlement>,
+    /// Whether the competition only served to assess price quality and none
+    /// of the solutions got settled.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub quote_only: bool,
+    /// Hash of the tokens that were trusted when the auction was sent to the
+    /// solvers. Missing for competitions stored before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trusted_tokens_hash: Option<H256>,
 }
 
 /// Returned by the `/solver_competition` endpoint.
@@ -215,6 +223,8 @@ mod tests {
                     is_winner: true,
                     filtered_out: false,
                 }],
+                quote_only: false,
+                trusted_tokens_hash: None,
             },
         };
 
@@ -0,0 +1,141 @@
+//! Conversions between domain numbers and the types used to store them in the
+//! database. Token amounts are stored in `numeric(78,0)` columns, which can
+//! hold every `U256` but also larger values, and block numbers in `bigint`
+//! columns, which can't hold every `u64`.
+
+use {
+    crate::conversions,
+    bigdecimal::BigDecimal,
+    num::{BigInt, bigint::Sign},
+    primitive_types::U256,
+};
+
+/// Number of decimal digits of the `numeric` columns storing `U256` values.
+pub const NUMERIC_PRECISION: u64 = 78;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    #[error("{0} is not an integer")]
+    NotAnInteger(BigDecimal),
+    #[error("{0} is negative")]
+    Negative(BigDecimal),
+    #[error("{0} does not fit into a U256")]
+    TooLarge(BigDecimal),
+    #[error("block number {0} does not fit into an i64")]
+    BlockNumberOverflow(u64),
+}
+
+/// Converts a `U256` into a value that can be stored in a `numeric(78,0)`
+/// column. Every `U256` fits so this can't fail.
+pub fn u256_to_big_decimal(value: &U256) -> BigDecimal {
+    conversions::u256_to_big_decimal(value)
+}
+
+/// Converts a value read from a `numeric` column back into a `U256`. Unlike
+/// [`conversions::big_decimal_to_u256`] this reports why the value can't be
+/// represented.
+pub fn big_decimal_to_u256(value: &BigDecimal) -> Result<U256, Error> {
+    if !value.is_integer() {
+        return Err(Error::NotAnInteger(value.clone()));
+    }
+    if value.sign() == Sign::Minus {
+        return Err(Error::Negative(value.clone()));
+    }
+    let (digits, _) = value.with_scale(0).into_bigint_and_exponent();
+    let (_, bytes) = digits.to_bytes_be();
+    if bytes.len() > 32 {
+        return Err(Error::TooLarge(value.clone()));
+    }
+    Ok(U256::from_big_endian(&bytes))
+}
+
+/// Converts a block number into a value that can be stored in a `bigint`
+/// column.
+pub fn i64_block_number(block: u64) -> Result<i64, Error> {
+    i64::try_from(block).map_err(|_| Error::BlockNumberOverflow(block))
+}
+
+/// Largest value a `numeric(78,0)` column can hold.
+pub fn numeric_max() -> BigDecimal {
+    BigDecimal::from(BigInt::from(10).pow(NUMERIC_PRECISION as u32) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        num::One,
+        rand::{Rng, SeedableRng, rngs::StdRng},
+        std::str::FromStr,
+    };
+
+    fn random_u256(rng: &mut StdRng) -> U256 {
+        // vary the bit length so small values are covered as well
+        let bits = rng.gen_range(0..=256);
+        let value = U256::from_big_endian(&rng.r#gen::<[u8; 32]>());
+        match bits {
+            256 => value,
+            bits => value & ((U256::one() << bits) - 1),
+        }
+    }
+
+    #[test]
+    fn round_trips_u256_range() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let edge_cases = [U256::zero(), U256::one(), U256::MAX, U256::MAX - 1];
+        let random = std::iter::repeat_with(|| random_u256(&mut rng)).take(10_000);
+        for value in edge_cases.into_iter().chain(random) {
+            let stored = u256_to_big_decimal(&value);
+            assert!(stored.digits() <= NUMERIC_PRECISION, "{value}");
+            assert_eq!(big_decimal_to_u256(&stored), Ok(value));
+        }
+    }
+
+    #[test]
+    fn numeric_boundary() {
+        let u256_max = u256_to_big_decimal(&U256::MAX);
+        assert_eq!(u256_max.digits(), NUMERIC_PRECISION);
+        assert!(u256_max < numeric_max());
+
+        // values the column can hold but that don't fit into a U256
+        let above = &u256_max + BigDecimal::one();
+        assert_eq!(big_decimal_to_u256(&above), Err(Error::TooLarge(above)));
+        assert_eq!(
+            big_decimal_to_u256(&numeric_max()),
+            Err(Error::TooLarge(numeric_max()))
+        );
+    }
+
+    #[test]
+    fn rejects_non_u256_values() {
+        let negative = BigDecimal::from(-1);
+        assert_eq!(
+            big_decimal_to_u256(&negative),
+            Err(Error::Negative(negative))
+        );
+        let fraction = BigDecimal::from_str("0.5").unwrap();
+        assert_eq!(
+            big_decimal_to_u256(&fraction),
+            Err(Error::NotAnInteger(fraction))
+        );
+        // integers with trailing zeros in exponent notation are fine
+        let exponent = BigDecimal::from_str("1e3").unwrap();
+        assert_eq!(big_decimal_to_u256(&exponent), Ok(U256::from(1000)));
+        let scaled = BigDecimal::from_str("7.000").unwrap();
+        assert_eq!(big_decimal_to_u256(&scaled), Ok(U256::from(7)));
+    }
+
+    #[test]
+    fn block_numbers() {
+        assert_eq!(i64_block_number(0), Ok(0));
+        assert_eq!(i64_block_number(i64::MAX as u64), Ok(i64::MAX));
+        assert_eq!(
+            i64_block_number(i64::MAX as u64 + 1),
+            Err(Error::BlockNumberOverflow(i64::MAX as u64 + 1))
+        );
+        assert_eq!(
+            i64_block_number(u64::MAX),
+            Err(Error::BlockNumberOverflow(u64::MAX))
+        );
+    }
+}
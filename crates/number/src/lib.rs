@@ -1,3 +1,4 @@
 pub mod conversions;
+pub mod db;
 pub mod nonzero;
 pub mod serialization;
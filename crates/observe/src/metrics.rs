@@ -74,10 +74,33 @@ pub const DEFAULT_METRICS_PORT: u16 = 9586;
 #[async_trait::async_trait]
 pub trait LivenessChecking: Send + Sync {
     async fn is_alive(&self) -> bool;
+
+    /// Short status string returned as the body of the liveness response,
+    /// e.g. to point at the component that makes the service unhealthy.
+    async fn status(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Provides a JSON snapshot of some internal state of a service.
+pub trait StatusReporting: Send + Sync {
+    fn status(&self) -> serde_json::Value;
 }
 
 pub fn serve_metrics(liveness: Arc<dyn LivenessChecking>, address: SocketAddr) -> JoinHandle<()> {
-    let filter = handle_metrics().or(handle_liveness(liveness));
+    serve_metrics_with_status(liveness, Default::default(), address)
+}
+
+/// Like [`serve_metrics`] but additionally exposes every status reporter at
+/// `/status/{name}`.
+pub fn serve_metrics_with_status(
+    liveness: Arc<dyn LivenessChecking>,
+    status: HashMap<&'static str, Arc<dyn StatusReporting>>,
+    address: SocketAddr,
+) -> JoinHandle<()> {
+    let filter = handle_metrics()
+        .or(handle_liveness(liveness))
+        .or(handle_status(status));
     tracing::info!(%address, "serving metrics");
     task::spawn(warp::serve(filter).bind(address))
 }
@@ -99,7 +122,25 @@ fn handle_liveness(
             } else {
                 warp::http::StatusCode::SERVICE_UNAVAILABLE
             };
-            Result::<_, Infallible>::Ok(warp::reply::with_status(warp::reply(), status))
+            let body = liveness_checker.status().await.unwrap_or_default();
+            Result::<_, Infallible>::Ok(warp::reply::with_status(body, status))
         }
     })
 }
+
+// `/status/{name}` routes exposing JSON snapshots of the service's state
+fn handle_status(
+    reporters: HashMap<&'static str, Arc<dyn StatusReporting>>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let reporters = Arc::new(reporters);
+    warp::path!("status" / String).map(move |name: String| match reporters.get(name.as_str()) {
+        Some(reporter) => warp::reply::with_status(
+            warp::reply::json(&reporter.status()),
+            warp::http::StatusCode::OK,
+        ),
+        None => warp::reply::with_status(
+            warp::reply::json(&serde_json::Value::Null),
+            warp::http::StatusCode::NOT_FOUND,
+        ),
+    })
+}
@@ -582,6 +582,7 @@ impl Orderbook {
             OrderEventLabel::Cancelled => dto::order::Status::Cancelled,
             OrderEventLabel::Filtered => dto::order::Status::Open,
             OrderEventLabel::Invalid => dto::order::Status::Open,
+            OrderEventLabel::Reverted => dto::order::Status::Open,
         };
         Ok(status)
     }
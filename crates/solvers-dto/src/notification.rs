@@ -70,6 +70,13 @@ pub enum Kind {
         reason: BanReason,
         until: DateTime<Utc>,
     },
+    #[serde(rename_all = "camelCase")]
+    CompetitionOutcome {
+        rank: usize,
+        is_winner: bool,
+        #[serde_as(as = "Option<HexOrDecimalU256>")]
+        reference_score: Option<U256>,
+    },
 }
 
 type BlockNo = u64;
@@ -92,4 +99,5 @@ pub struct Tx {
 pub enum BanReason {
     UnsettledConsecutiveAuctions,
     HighSettleFailureRate,
+    DenyListed,
 }
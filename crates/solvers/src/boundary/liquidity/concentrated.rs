@@ -1,4 +1,5 @@
 use {
+    crate::{domain::eth::abi, util::bytes::Bytes},
     contracts::ethcontract::{H160, U256},
     model::TokenPair,
     shared::baseline_solver::BaselineSolvable,
@@ -16,6 +17,35 @@ pub struct Pool {
 impl Pool {
     // Estimated with https://dune.com/queries/5431793
     const POOL_SWAP_GAS_COST: usize = 106_000;
+
+    fn quote(&self, in_token: H160, out_token: H160, amount: U256) -> abi::UniswapV3Quote {
+        abi::UniswapV3Quote {
+            token_in: in_token,
+            token_out: out_token,
+            amount,
+            fee: self.fee,
+            sqrt_price_limit_x96: 0.into(),
+        }
+    }
+
+    /// Calls the quoter and returns the quoted amount.
+    async fn call_quoter(&self, calldata: Bytes<Vec<u8>>) -> Option<U256> {
+        let quoter = self.uni_v3_quoter_contract.raw_instance();
+        let output = quoter
+            .web3()
+            .eth()
+            .call(
+                web3::types::CallRequest {
+                    to: Some(quoter.address()),
+                    data: Some(calldata.0.into()),
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .ok()?;
+        abi::UniswapV3Quote::decode_amount(&output.0).ok()
+    }
 }
 
 /// Computes input or output amounts via eth_calls. The implementation was based
@@ -31,16 +61,8 @@ impl BaselineSolvable for Pool {
             return None;
         }
 
-        self.uni_v3_quoter_contract
-            .quote_exact_input_single((in_token, out_token, in_amount, self.fee, 0.into()))
-            .call()
-            .await
-            .map(
-                |(amount_out, _sqrt_price_x96_after, _initialized_ticks_crossed, _gas_estimate)| {
-                    amount_out
-                },
-            )
-            .ok()
+        let quote = self.quote(in_token, out_token, in_amount);
+        self.call_quoter(quote.encode_exact_input()).await
     }
 
     async fn get_amount_in(
@@ -53,16 +75,8 @@ impl BaselineSolvable for Pool {
             return None;
         }
 
-        self.uni_v3_quoter_contract
-            .quote_exact_output_single((in_token, out_token, out_amount, self.fee, 0.into()))
-            .call()
-            .await
-            .map(
-                |(amount_in, _sqrt_price_x96_after, _initialized_ticks_crossed, _gas_estimate)| {
-                    amount_in
-                },
-            )
-            .ok()
+        let quote = self.quote(in_token, out_token, out_amount);
+        self.call_quoter(quote.encode_exact_output()).await
     }
 
     async fn gas_cost(&self) -> usize {
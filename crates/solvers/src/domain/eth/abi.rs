@@ -0,0 +1,1174 @@
+//! Minimal and deterministic ABI encoding for the handful of contract calls
+//! the solvers build transactions for. Avoids going through `ethcontract` so
+//! calldata can be produced (and checked) without any contract bindings.
+//! Function selectors get derived from the signatures at compile time.
+
+use {
+    super::{H160, U256},
+    crate::util::bytes::Bytes,
+    anyhow::{Context, Result, ensure},
+};
+
+/// Size of a single ABI word in bytes.
+const WORD: usize = 32;
+
+/// An ABI encoded value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Token {
+    Address(H160),
+    Uint(U256),
+    /// `bytesN`, at most 32 bytes.
+    FixedBytes(Vec<u8>),
+    Bytes(Vec<u8>),
+    Array(Vec<Token>),
+    FixedArray(Vec<Token>),
+    Tuple(Vec<Token>),
+}
+
+impl Token {
+    fn is_dynamic(&self) -> bool {
+        match self {
+            Token::Address(_) | Token::Uint(_) | Token::FixedBytes(_) => false,
+            Token::Bytes(_) | Token::Array(_) => true,
+            Token::FixedArray(tokens) | Token::Tuple(tokens) => {
+                tokens.iter().any(Token::is_dynamic)
+            }
+        }
+    }
+}
+
+/// The type of a function parameter. Only references static data so
+/// function definitions can be constants.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamType {
+    Address,
+    /// `uintN` with the number of bits.
+    Uint(usize),
+    /// `bytesN` with the number of bytes.
+    FixedBytes(usize),
+    Bytes,
+    Array(&'static ParamType),
+    FixedArray(&'static ParamType, usize),
+    Tuple(&'static [ParamType]),
+}
+
+impl ParamType {
+    fn is_dynamic(&self) -> bool {
+        match self {
+            ParamType::Address | ParamType::Uint(_) | ParamType::FixedBytes(_) => false,
+            ParamType::Bytes | ParamType::Array(_) => true,
+            ParamType::FixedArray(inner, len) => *len > 0 && inner.is_dynamic(),
+            ParamType::Tuple(types) => types.iter().any(ParamType::is_dynamic),
+        }
+    }
+
+    /// Size of the head of a value of this type.
+    fn head_size(&self) -> usize {
+        if self.is_dynamic() {
+            return WORD;
+        }
+        match self {
+            ParamType::FixedArray(inner, len) => inner.head_size() * len,
+            ParamType::Tuple(types) => types.iter().map(ParamType::head_size).sum(),
+            _ => WORD,
+        }
+    }
+}
+
+/// A contract function together with its 4 byte selector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Function {
+    /// The canonical signature, e.g. `approve(address,uint256)`.
+    pub signature: &'static str,
+    pub inputs: &'static [ParamType],
+    pub selector: [u8; 4],
+}
+
+impl Function {
+    /// Defines a function. The selector is derived from the signature, which
+    /// has to match the inputs.
+    pub const fn new(signature: &'static str, inputs: &'static [ParamType]) -> Self {
+        let hash = keccak256(signature.as_bytes());
+        Self {
+            signature,
+            inputs,
+            selector: [hash[0], hash[1], hash[2], hash[3]],
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.signature
+            .split_once('(')
+            .map_or(self.signature, |(name, _)| name)
+    }
+
+    /// Encodes a call of this function. The caller is responsible for passing
+    /// tokens matching the function inputs.
+    pub fn encode(&self, tokens: &[Token]) -> Bytes<Vec<u8>> {
+        let mut input = self.selector.to_vec();
+        input.extend(encode(tokens));
+        Bytes(input)
+    }
+
+    /// Decodes the arguments of a call of this function.
+    pub fn decode(&self, input: &[u8]) -> Result<Vec<Token>> {
+        let (selector, data) = input.split_at_checked(4).context("missing selector")?;
+        ensure!(
+            selector == self.selector,
+            "selector mismatch for {}",
+            self.name()
+        );
+        decode_sequence(self.inputs, data, 0)
+    }
+}
+
+const ADDRESS_ARRAY: ParamType = ParamType::Array(&ParamType::Address);
+const UINT256: ParamType = ParamType::Uint(256);
+
+/// ERC20 `approve`.
+pub const APPROVE: Function =
+    Function::new("approve(address,uint256)", &[ParamType::Address, UINT256]);
+
+/// Uniswap V2 router `swapExactTokensForTokens`.
+pub const SWAP_EXACT_TOKENS_FOR_TOKENS: Function = Function::new(
+    "swapExactTokensForTokens(uint256,uint256,address[],address,uint256)",
+    &[UINT256, UINT256, ADDRESS_ARRAY, ParamType::Address, UINT256],
+);
+
+/// Uniswap V2 router `swapTokensForExactTokens`.
+pub const SWAP_TOKENS_FOR_EXACT_TOKENS: Function = Function::new(
+    "swapTokensForExactTokens(uint256,uint256,address[],address,uint256)",
+    &[UINT256, UINT256, ADDRESS_ARRAY, ParamType::Address, UINT256],
+);
+
+/// Uniswap V3 `SwapRouter` single pool swap parameters.
+const V3_SINGLE_PARAMS: ParamType = ParamType::Tuple(&[
+    ParamType::Address,
+    ParamType::Address,
+    ParamType::Uint(24),
+    ParamType::Address,
+    UINT256,
+    UINT256,
+    UINT256,
+    ParamType::Uint(160),
+]);
+
+/// Uniswap V3 `SwapRouter` `exactInputSingle`.
+pub const EXACT_INPUT_SINGLE: Function = Function::new(
+    "exactInputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))",
+    &[V3_SINGLE_PARAMS],
+);
+
+/// Uniswap V3 `SwapRouter` `exactOutputSingle`.
+pub const EXACT_OUTPUT_SINGLE: Function = Function::new(
+    "exactOutputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))",
+    &[V3_SINGLE_PARAMS],
+);
+
+/// Uniswap V3 `QuoterV2` single pool quote parameters.
+const V3_QUOTE_PARAMS: ParamType = ParamType::Tuple(&[
+    ParamType::Address,
+    ParamType::Address,
+    UINT256,
+    ParamType::Uint(24),
+    ParamType::Uint(160),
+]);
+
+/// Return values of the Uniswap V3 `QuoterV2` single pool quote functions.
+const V3_QUOTE_OUTPUTS: &[ParamType] =
+    &[UINT256, ParamType::Uint(160), ParamType::Uint(32), UINT256];
+
+/// Uniswap V3 `QuoterV2` `quoteExactInputSingle`.
+pub const QUOTE_EXACT_INPUT_SINGLE: Function = Function::new(
+    "quoteExactInputSingle((address,address,uint256,uint24,uint160))",
+    &[V3_QUOTE_PARAMS],
+);
+
+/// Uniswap V3 `QuoterV2` `quoteExactOutputSingle`.
+pub const QUOTE_EXACT_OUTPUT_SINGLE: Function = Function::new(
+    "quoteExactOutputSingle((address,address,uint256,uint24,uint160))",
+    &[V3_QUOTE_PARAMS],
+);
+
+const SETTLEMENT_TRADE: ParamType = ParamType::Tuple(&[
+    UINT256,
+    UINT256,
+    ParamType::Address,
+    UINT256,
+    UINT256,
+    ParamType::Uint(32),
+    ParamType::FixedBytes(32),
+    UINT256,
+    UINT256,
+    UINT256,
+    ParamType::Bytes,
+]);
+
+const SETTLEMENT_INTERACTION: ParamType =
+    ParamType::Tuple(&[ParamType::Address, UINT256, ParamType::Bytes]);
+
+/// Settlement contract `settle`.
+pub const SETTLE: Function = Function::new(
+    "settle(address[],uint256[],(uint256,uint256,address,uint256,uint256,uint32,bytes32,uint256,\
+     uint256,uint256,bytes)[],(address,uint256,bytes)[][3])",
+    &[
+        ADDRESS_ARRAY,
+        ParamType::Array(&UINT256),
+        ParamType::Array(&SETTLEMENT_TRADE),
+        ParamType::FixedArray(&ParamType::Array(&SETTLEMENT_INTERACTION), 3),
+    ],
+);
+
+/// All functions supported by this module.
+pub const FUNCTIONS: &[Function] = &[
+    APPROVE,
+    SWAP_EXACT_TOKENS_FOR_TOKENS,
+    SWAP_TOKENS_FOR_EXACT_TOKENS,
+    EXACT_INPUT_SINGLE,
+    EXACT_OUTPUT_SINGLE,
+    QUOTE_EXACT_INPUT_SINGLE,
+    QUOTE_EXACT_OUTPUT_SINGLE,
+    SETTLE,
+];
+
+/// Looks up a supported function by the selector at the start of `input`.
+pub fn function(input: &[u8]) -> Option<&'static Function> {
+    let selector = input.get(..4)?;
+    FUNCTIONS
+        .iter()
+        .find(|function| function.selector == selector)
+}
+
+/// ABI encodes a sequence of tokens (i.e. function arguments or the members
+/// of a tuple).
+pub fn encode(tokens: &[Token]) -> Vec<u8> {
+    let encoded: Vec<_> = tokens.iter().map(encode_token).collect();
+    let heads_size: usize = tokens
+        .iter()
+        .zip(&encoded)
+        .map(|(token, encoded)| {
+            if token.is_dynamic() {
+                WORD
+            } else {
+                encoded.len()
+            }
+        })
+        .sum();
+
+    let mut heads = Vec::with_capacity(heads_size);
+    let mut tails = Vec::new();
+    for (token, encoded) in tokens.iter().zip(encoded) {
+        if token.is_dynamic() {
+            heads.extend(word(U256::from(heads_size + tails.len())));
+            tails.extend(encoded);
+        } else {
+            heads.extend(encoded);
+        }
+    }
+    heads.extend(tails);
+    heads
+}
+
+fn encode_token(token: &Token) -> Vec<u8> {
+    match token {
+        Token::Address(address) => {
+            let mut encoded = vec![0; WORD - 20];
+            encoded.extend(address.as_bytes());
+            encoded
+        }
+        Token::Uint(value) => word(*value).to_vec(),
+        Token::FixedBytes(bytes) => padded(bytes),
+        Token::Bytes(bytes) => {
+            let mut encoded = word(bytes.len().into()).to_vec();
+            encoded.extend(padded(bytes));
+            encoded
+        }
+        Token::Array(tokens) => {
+            let mut encoded = word(tokens.len().into()).to_vec();
+            encoded.extend(encode(tokens));
+            encoded
+        }
+        Token::FixedArray(tokens) | Token::Tuple(tokens) => encode(tokens),
+    }
+}
+
+fn word(value: U256) -> [u8; WORD] {
+    let mut word = [0; WORD];
+    value.to_big_endian(&mut word);
+    word
+}
+
+/// Right pads the bytes with zeros to a multiple of the word size.
+fn padded(bytes: &[u8]) -> Vec<u8> {
+    let mut padded = bytes.to_vec();
+    padded.resize(bytes.len().div_ceil(WORD) * WORD, 0);
+    padded
+}
+
+/// ABI decodes a sequence of values (e.g. the return data of a call).
+pub fn decode(types: &[ParamType], data: &[u8]) -> Result<Vec<Token>> {
+    decode_sequence(types, data, 0)
+}
+
+/// Decodes a sequence of values whose heads start at `offset`. Offsets of
+/// dynamic values are relative to `offset`.
+fn decode_sequence<'a>(
+    types: impl IntoIterator<Item = &'a ParamType>,
+    data: &[u8],
+    offset: usize,
+) -> Result<Vec<Token>> {
+    let mut head = offset;
+    let mut tokens = Vec::new();
+    for ty in types {
+        let token = if ty.is_dynamic() {
+            let tail = offset
+                .checked_add(read_usize(data, head)?)
+                .context("offset overflow")?;
+            decode_token(ty, data, tail)?
+        } else {
+            decode_token(ty, data, head)?
+        };
+        tokens.push(token);
+        head += ty.head_size();
+    }
+    Ok(tokens)
+}
+
+fn decode_token(ty: &ParamType, data: &[u8], at: usize) -> Result<Token> {
+    match ty {
+        ParamType::Address => {
+            let word = read_word(data, at)?;
+            ensure!(word[..WORD - 20].iter().all(|b| *b == 0), "invalid address");
+            Ok(Token::Address(H160::from_slice(&word[WORD - 20..])))
+        }
+        ParamType::Uint(bits) => {
+            let value = U256::from_big_endian(read_word(data, at)?);
+            ensure!(value.bits() <= *bits, "value out of range for uint{bits}");
+            Ok(Token::Uint(value))
+        }
+        ParamType::FixedBytes(len) => {
+            let word = read_word(data, at)?;
+            ensure!(word[*len..].iter().all(|b| *b == 0), "invalid bytes{len}");
+            Ok(Token::FixedBytes(word[..*len].to_vec()))
+        }
+        ParamType::Bytes => {
+            let len = read_usize(data, at)?;
+            let bytes = data
+                .get(at + WORD..)
+                .and_then(|bytes| bytes.get(..len))
+                .context("bytes out of bounds")?;
+            Ok(Token::Bytes(bytes.to_vec()))
+        }
+        ParamType::Array(inner) => {
+            let len = read_usize(data, at)?;
+            // every element takes up at least one word
+            ensure!(len <= data.len() / WORD, "array length out of bounds");
+            let tokens = decode_sequence(std::iter::repeat_n(*inner, len), data, at + WORD)?;
+            Ok(Token::Array(tokens))
+        }
+        ParamType::FixedArray(inner, len) => Ok(Token::FixedArray(decode_sequence(
+            std::iter::repeat_n(*inner, *len),
+            data,
+            at,
+        )?)),
+        ParamType::Tuple(types) => Ok(Token::Tuple(decode_sequence(*types, data, at)?)),
+    }
+}
+
+fn read_word(data: &[u8], at: usize) -> Result<&[u8]> {
+    data.get(at..)
+        .and_then(|data| data.get(..WORD))
+        .context("word out of bounds")
+}
+
+fn read_usize(data: &[u8], at: usize) -> Result<usize> {
+    let value = U256::from_big_endian(read_word(data, at)?);
+    ensure!(
+        value <= U256::from(data.len()),
+        "offset or length out of bounds"
+    );
+    Ok(value.as_usize())
+}
+
+/// Typed arguments of the Uniswap V2 router swap functions. Depending on the
+/// function the amounts are interpreted as `(amountIn, amountOutMin)` or
+/// `(amountOut, amountInMax)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UniswapV2Swap {
+    pub exact_amount: U256,
+    pub limit_amount: U256,
+    pub path: Vec<H160>,
+    pub to: H160,
+    pub deadline: U256,
+}
+
+impl UniswapV2Swap {
+    /// Encodes a `swapExactTokensForTokens` call.
+    pub fn encode_exact_input(&self) -> Bytes<Vec<u8>> {
+        SWAP_EXACT_TOKENS_FOR_TOKENS.encode(&self.tokens())
+    }
+
+    /// Encodes a `swapTokensForExactTokens` call.
+    pub fn encode_exact_output(&self) -> Bytes<Vec<u8>> {
+        SWAP_TOKENS_FOR_EXACT_TOKENS.encode(&self.tokens())
+    }
+
+    /// Decodes a `swapExactTokensForTokens` call.
+    pub fn decode_exact_input(input: &[u8]) -> Result<Self> {
+        Self::from_tokens(SWAP_EXACT_TOKENS_FOR_TOKENS.decode(input)?)
+    }
+
+    /// Decodes a `swapTokensForExactTokens` call.
+    pub fn decode_exact_output(input: &[u8]) -> Result<Self> {
+        Self::from_tokens(SWAP_TOKENS_FOR_EXACT_TOKENS.decode(input)?)
+    }
+
+    fn tokens(&self) -> Vec<Token> {
+        vec![
+            Token::Uint(self.exact_amount),
+            Token::Uint(self.limit_amount),
+            Token::Array(self.path.iter().copied().map(Token::Address).collect()),
+            Token::Address(self.to),
+            Token::Uint(self.deadline),
+        ]
+    }
+
+    fn from_tokens(tokens: Vec<Token>) -> Result<Self> {
+        let [exact_amount, limit_amount, path, to, deadline] = array(tokens)?;
+        Ok(Self {
+            exact_amount: uint(exact_amount)?,
+            limit_amount: uint(limit_amount)?,
+            path: list(path)?
+                .into_iter()
+                .map(address)
+                .collect::<Result<_>>()?,
+            to: address(to)?,
+            deadline: uint(deadline)?,
+        })
+    }
+}
+
+/// Typed arguments of the Uniswap V3 `SwapRouter` single pool swap
+/// functions. Depending on the function the amounts are interpreted as
+/// `(amountIn, amountOutMinimum)` or `(amountOut, amountInMaximum)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UniswapV3SingleSwap {
+    pub token_in: H160,
+    pub token_out: H160,
+    /// Pool fee in hundredths of a basis point (`uint24`).
+    pub fee: u32,
+    pub recipient: H160,
+    pub deadline: U256,
+    pub exact_amount: U256,
+    pub limit_amount: U256,
+    pub sqrt_price_limit_x96: U256,
+}
+
+impl UniswapV3SingleSwap {
+    /// Encodes an `exactInputSingle` call.
+    pub fn encode_exact_input(&self) -> Bytes<Vec<u8>> {
+        EXACT_INPUT_SINGLE.encode(&[self.token()])
+    }
+
+    /// Encodes an `exactOutputSingle` call.
+    pub fn encode_exact_output(&self) -> Bytes<Vec<u8>> {
+        EXACT_OUTPUT_SINGLE.encode(&[self.token()])
+    }
+
+    /// Decodes an `exactInputSingle` call.
+    pub fn decode_exact_input(input: &[u8]) -> Result<Self> {
+        Self::from_tokens(EXACT_INPUT_SINGLE.decode(input)?)
+    }
+
+    /// Decodes an `exactOutputSingle` call.
+    pub fn decode_exact_output(input: &[u8]) -> Result<Self> {
+        Self::from_tokens(EXACT_OUTPUT_SINGLE.decode(input)?)
+    }
+
+    fn token(&self) -> Token {
+        Token::Tuple(vec![
+            Token::Address(self.token_in),
+            Token::Address(self.token_out),
+            Token::Uint(self.fee.into()),
+            Token::Address(self.recipient),
+            Token::Uint(self.deadline),
+            Token::Uint(self.exact_amount),
+            Token::Uint(self.limit_amount),
+            Token::Uint(self.sqrt_price_limit_x96),
+        ])
+    }
+
+    fn from_tokens(tokens: Vec<Token>) -> Result<Self> {
+        let [params] = array(tokens)?;
+        let [
+            token_in,
+            token_out,
+            fee,
+            recipient,
+            deadline,
+            exact_amount,
+            limit_amount,
+            sqrt_price_limit_x96,
+        ] = array(list(params)?)?;
+        Ok(Self {
+            token_in: address(token_in)?,
+            token_out: address(token_out)?,
+            // `uint24` range is checked when decoding
+            fee: uint(fee)?.as_u32(),
+            recipient: address(recipient)?,
+            deadline: uint(deadline)?,
+            exact_amount: uint(exact_amount)?,
+            limit_amount: uint(limit_amount)?,
+            sqrt_price_limit_x96: uint(sqrt_price_limit_x96)?,
+        })
+    }
+}
+
+/// Typed arguments of the Uniswap V3 `QuoterV2` single pool quote functions.
+/// Depending on the function `amount` is the input or the output amount.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UniswapV3Quote {
+    pub token_in: H160,
+    pub token_out: H160,
+    pub amount: U256,
+    /// Pool fee in hundredths of a basis point (`uint24`).
+    pub fee: u32,
+    pub sqrt_price_limit_x96: U256,
+}
+
+impl UniswapV3Quote {
+    /// Encodes a `quoteExactInputSingle` call.
+    pub fn encode_exact_input(&self) -> Bytes<Vec<u8>> {
+        QUOTE_EXACT_INPUT_SINGLE.encode(&[self.token()])
+    }
+
+    /// Encodes a `quoteExactOutputSingle` call.
+    pub fn encode_exact_output(&self) -> Bytes<Vec<u8>> {
+        QUOTE_EXACT_OUTPUT_SINGLE.encode(&[self.token()])
+    }
+
+    /// Decodes a `quoteExactInputSingle` call.
+    pub fn decode_exact_input(input: &[u8]) -> Result<Self> {
+        Self::from_tokens(QUOTE_EXACT_INPUT_SINGLE.decode(input)?)
+    }
+
+    /// Decodes a `quoteExactOutputSingle` call.
+    pub fn decode_exact_output(input: &[u8]) -> Result<Self> {
+        Self::from_tokens(QUOTE_EXACT_OUTPUT_SINGLE.decode(input)?)
+    }
+
+    /// Decodes the quoted amount from the return data of either quote
+    /// function. That's the output amount for exact input quotes and the
+    /// input amount for exact output quotes.
+    pub fn decode_amount(output: &[u8]) -> Result<U256> {
+        let [
+            amount,
+            _sqrt_price_x96_after,
+            _initialized_ticks_crossed,
+            _gas_estimate,
+        ] = array(decode(V3_QUOTE_OUTPUTS, output)?)?;
+        uint(amount)
+    }
+
+    fn token(&self) -> Token {
+        Token::Tuple(vec![
+            Token::Address(self.token_in),
+            Token::Address(self.token_out),
+            Token::Uint(self.amount),
+            Token::Uint(self.fee.into()),
+            Token::Uint(self.sqrt_price_limit_x96),
+        ])
+    }
+
+    fn from_tokens(tokens: Vec<Token>) -> Result<Self> {
+        let [params] = array(tokens)?;
+        let [token_in, token_out, amount, fee, sqrt_price_limit_x96] = array(list(params)?)?;
+        Ok(Self {
+            token_in: address(token_in)?,
+            token_out: address(token_out)?,
+            amount: uint(amount)?,
+            // `uint24` range is checked when decoding
+            fee: uint(fee)?.as_u32(),
+            sqrt_price_limit_x96: uint(sqrt_price_limit_x96)?,
+        })
+    }
+}
+
+/// A trade as passed to the settlement contract.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SettlementTrade {
+    pub sell_token_index: U256,
+    pub buy_token_index: U256,
+    pub receiver: H160,
+    pub sell_amount: U256,
+    pub buy_amount: U256,
+    pub valid_to: u32,
+    pub app_data: [u8; 32],
+    pub fee_amount: U256,
+    pub flags: U256,
+    pub executed_amount: U256,
+    pub signature: Vec<u8>,
+}
+
+/// An interaction as passed to the settlement contract.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SettlementInteraction {
+    pub target: H160,
+    pub value: U256,
+    pub call_data: Vec<u8>,
+}
+
+/// Typed arguments of the settlement contract `settle` function.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Settle {
+    pub tokens: Vec<H160>,
+    pub clearing_prices: Vec<U256>,
+    pub trades: Vec<SettlementTrade>,
+    /// Pre-, intra- and post-interactions.
+    pub interactions: [Vec<SettlementInteraction>; 3],
+}
+
+impl Settle {
+    pub fn encode(&self) -> Bytes<Vec<u8>> {
+        SETTLE.encode(&[
+            Token::Array(self.tokens.iter().copied().map(Token::Address).collect()),
+            Token::Array(
+                self.clearing_prices
+                    .iter()
+                    .copied()
+                    .map(Token::Uint)
+                    .collect(),
+            ),
+            Token::Array(
+                self.trades
+                    .iter()
+                    .map(|trade| {
+                        Token::Tuple(vec![
+                            Token::Uint(trade.sell_token_index),
+                            Token::Uint(trade.buy_token_index),
+                            Token::Address(trade.receiver),
+                            Token::Uint(trade.sell_amount),
+                            Token::Uint(trade.buy_amount),
+                            Token::Uint(trade.valid_to.into()),
+                            Token::FixedBytes(trade.app_data.to_vec()),
+                            Token::Uint(trade.fee_amount),
+                            Token::Uint(trade.flags),
+                            Token::Uint(trade.executed_amount),
+                            Token::Bytes(trade.signature.clone()),
+                        ])
+                    })
+                    .collect(),
+            ),
+            Token::FixedArray(
+                self.interactions
+                    .iter()
+                    .map(|interactions| {
+                        Token::Array(
+                            interactions
+                                .iter()
+                                .map(|interaction| {
+                                    Token::Tuple(vec![
+                                        Token::Address(interaction.target),
+                                        Token::Uint(interaction.value),
+                                        Token::Bytes(interaction.call_data.clone()),
+                                    ])
+                                })
+                                .collect(),
+                        )
+                    })
+                    .collect(),
+            ),
+        ])
+    }
+
+    pub fn decode(input: &[u8]) -> Result<Self> {
+        let [tokens, clearing_prices, trades, interactions] = array(SETTLE.decode(input)?)?;
+        Ok(Self {
+            tokens: list(tokens)?
+                .into_iter()
+                .map(address)
+                .collect::<Result<_>>()?,
+            clearing_prices: list(clearing_prices)?
+                .into_iter()
+                .map(uint)
+                .collect::<Result<_>>()?,
+            trades: list(trades)?
+                .into_iter()
+                .map(|trade| {
+                    let [
+                        sell_token_index,
+                        buy_token_index,
+                        receiver,
+                        sell_amount,
+                        buy_amount,
+                        valid_to,
+                        app_data,
+                        fee_amount,
+                        flags,
+                        executed_amount,
+                        signature,
+                    ] = array(list(trade)?)?;
+                    Ok(SettlementTrade {
+                        sell_token_index: uint(sell_token_index)?,
+                        buy_token_index: uint(buy_token_index)?,
+                        receiver: address(receiver)?,
+                        sell_amount: uint(sell_amount)?,
+                        buy_amount: uint(buy_amount)?,
+                        // `uint32` range is checked when decoding
+                        valid_to: uint(valid_to)?.as_u32(),
+                        app_data: bytes(app_data)?
+                            .try_into()
+                            .ok()
+                            .context("invalid app data")?,
+                        fee_amount: uint(fee_amount)?,
+                        flags: uint(flags)?,
+                        executed_amount: uint(executed_amount)?,
+                        signature: bytes(signature)?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            interactions: array::<3>(list(interactions)?)?
+                .map(|interactions| {
+                    list(interactions)?
+                        .into_iter()
+                        .map(|interaction| {
+                            let [target, value, call_data] = array(list(interaction)?)?;
+                            Ok(SettlementInteraction {
+                                target: address(target)?,
+                                value: uint(value)?,
+                                call_data: bytes(call_data)?,
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()
+                })
+                .into_iter()
+                .collect::<Result<Vec<_>>>()?
+                .try_into()
+                .expect("exactly 3 interaction groups"),
+        })
+    }
+}
+
+fn array<const N: usize>(tokens: Vec<Token>) -> Result<[Token; N]> {
+    tokens
+        .try_into()
+        .ok()
+        .with_context(|| format!("expected {N} values"))
+}
+
+fn list(token: Token) -> Result<Vec<Token>> {
+    match token {
+        Token::Array(tokens) | Token::FixedArray(tokens) | Token::Tuple(tokens) => Ok(tokens),
+        _ => anyhow::bail!("expected a list of values"),
+    }
+}
+
+fn address(token: Token) -> Result<H160> {
+    match token {
+        Token::Address(address) => Ok(address),
+        _ => anyhow::bail!("expected an address"),
+    }
+}
+
+fn uint(token: Token) -> Result<U256> {
+    match token {
+        Token::Uint(value) => Ok(value),
+        _ => anyhow::bail!("expected an unsigned integer"),
+    }
+}
+
+fn bytes(token: Token) -> Result<Vec<u8>> {
+    match token {
+        Token::Bytes(bytes) | Token::FixedBytes(bytes) => Ok(bytes),
+        _ => anyhow::bail!("expected bytes"),
+    }
+}
+
+/// Keccak-256 as a `const fn` so that selectors get derived at compile time.
+const fn keccak256(input: &[u8]) -> [u8; 32] {
+    /// Bytes absorbed per permutation.
+    const RATE: usize = 136;
+
+    let mut state = [0u64; 25];
+    let mut i = 0;
+    while i < input.len() {
+        let pos = i % RATE;
+        state[pos / 8] ^= (input[i] as u64) << (8 * (pos % 8));
+        if pos == RATE - 1 {
+            state = keccak_f(state);
+        }
+        i += 1;
+    }
+    // multi-rate padding with the original Keccak domain byte
+    let pos = input.len() % RATE;
+    state[pos / 8] ^= 0x01 << (8 * (pos % 8));
+    state[(RATE - 1) / 8] ^= 0x80 << (8 * ((RATE - 1) % 8));
+    state = keccak_f(state);
+
+    let mut hash = [0; 32];
+    let mut i = 0;
+    while i < hash.len() {
+        hash[i] = (state[i / 8] >> (8 * (i % 8))) as u8;
+        i += 1;
+    }
+    hash
+}
+
+/// The Keccak-f[1600] permutation. Lanes are indexed by `x + 5 * y`.
+const fn keccak_f(mut state: [u64; 25]) -> [u64; 25] {
+    const ROUND_CONSTANTS: [u64; 24] = [
+        0x0000000000000001,
+        0x0000000000008082,
+        0x800000000000808a,
+        0x8000000080008000,
+        0x000000000000808b,
+        0x0000000080000001,
+        0x8000000080008081,
+        0x8000000000008009,
+        0x000000000000008a,
+        0x0000000000000088,
+        0x0000000080008009,
+        0x000000008000000a,
+        0x000000008000808b,
+        0x800000000000008b,
+        0x8000000000008089,
+        0x8000000000008003,
+        0x8000000000008002,
+        0x8000000000000080,
+        0x000000000000800a,
+        0x800000008000000a,
+        0x8000000080008081,
+        0x8000000000008080,
+        0x0000000080000001,
+        0x8000000080008008,
+    ];
+    const ROTATIONS: [u32; 25] = [
+        0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56,
+        14,
+    ];
+
+    let mut round = 0;
+    while round < ROUND_CONSTANTS.len() {
+        // theta
+        let mut columns = [0u64; 5];
+        let mut x = 0;
+        while x < 5 {
+            columns[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+            x += 1;
+        }
+        let mut x = 0;
+        while x < 5 {
+            let d = columns[(x + 4) % 5] ^ columns[(x + 1) % 5].rotate_left(1);
+            let mut y = 0;
+            while y < 5 {
+                state[x + 5 * y] ^= d;
+                y += 1;
+            }
+            x += 1;
+        }
+        // rho and pi
+        let mut moved = [0u64; 25];
+        let mut x = 0;
+        while x < 5 {
+            let mut y = 0;
+            while y < 5 {
+                moved[y + 5 * ((2 * x + 3 * y) % 5)] =
+                    state[x + 5 * y].rotate_left(ROTATIONS[x + 5 * y]);
+                y += 1;
+            }
+            x += 1;
+        }
+        // chi
+        let mut x = 0;
+        while x < 5 {
+            let mut y = 0;
+            while y < 5 {
+                state[x + 5 * y] =
+                    moved[x + 5 * y] ^ (!moved[(x + 1) % 5 + 5 * y] & moved[(x + 2) % 5 + 5 * y]);
+                y += 1;
+            }
+            x += 1;
+        }
+        // iota
+        state[0] ^= ROUND_CONSTANTS[round];
+        round += 1;
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, hex_literal::hex};
+
+    /// Canonical name of the type as used in function signatures.
+    fn canonical(ty: &ParamType) -> String {
+        match ty {
+            ParamType::Address => "address".to_owned(),
+            ParamType::Uint(bits) => format!("uint{bits}"),
+            ParamType::FixedBytes(len) => format!("bytes{len}"),
+            ParamType::Bytes => "bytes".to_owned(),
+            ParamType::Array(inner) => format!("{}[]", canonical(inner)),
+            ParamType::FixedArray(inner, len) => format!("{}[{len}]", canonical(inner)),
+            ParamType::Tuple(types) => format!("({})", canonical_list(types)),
+        }
+    }
+
+    fn canonical_list(types: &[ParamType]) -> String {
+        types.iter().map(canonical).collect::<Vec<_>>().join(",")
+    }
+
+    #[test]
+    fn keccak_matches_reference() {
+        // lengths around the rate exercise the padding and multiple blocks
+        for len in [0, 1, 135, 136, 137, 300] {
+            let input: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            assert_eq!(keccak256(&input), web3::signing::keccak256(&input), "{len}");
+        }
+    }
+
+    #[test]
+    fn signatures_match_inputs() {
+        for function in FUNCTIONS {
+            assert_eq!(
+                function.signature,
+                format!("{}({})", function.name(), canonical_list(function.inputs))
+            );
+        }
+    }
+
+    #[test]
+    fn selectors() {
+        let selectors: Vec<_> = FUNCTIONS.iter().map(|function| function.selector).collect();
+        assert_eq!(
+            selectors,
+            [
+                hex!("095ea7b3"),
+                hex!("38ed1739"),
+                hex!("8803dbee"),
+                hex!("414bf389"),
+                hex!("db3e2198"),
+                hex!("c6a5026a"),
+                hex!("bd21704a"),
+                hex!("13d79a0b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn encodes_uniswap_v2_swap() {
+        let swap = UniswapV2Swap {
+            exact_amount: 1_000.into(),
+            limit_amount: 900.into(),
+            path: vec![H160([0x11; 20]), H160([0x22; 20])],
+            to: H160([0x33; 20]),
+            deadline: U256::MAX,
+        };
+
+        let encoded = swap.encode_exact_input();
+        assert_eq!(
+            encoded.0,
+            hex!(
+                "38ed1739"
+                "00000000000000000000000000000000000000000000000000000000000003e8"
+                "0000000000000000000000000000000000000000000000000000000000000384"
+                "00000000000000000000000000000000000000000000000000000000000000a0"
+                "0000000000000000000000003333333333333333333333333333333333333333"
+                "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff"
+                "0000000000000000000000000000000000000000000000000000000000000002"
+                "0000000000000000000000001111111111111111111111111111111111111111"
+                "0000000000000000000000002222222222222222222222222222222222222222"
+            )
+        );
+        assert_eq!(UniswapV2Swap::decode_exact_input(&encoded.0).unwrap(), swap);
+        // the selector determines the function
+        assert!(UniswapV2Swap::decode_exact_output(&encoded.0).is_err());
+
+        let encoded = swap.encode_exact_output();
+        assert_eq!(encoded.0[..4], hex!("8803dbee"));
+        assert_eq!(
+            UniswapV2Swap::decode_exact_output(&encoded.0).unwrap(),
+            swap
+        );
+    }
+
+    #[test]
+    fn encodes_uniswap_v3_single_swap() {
+        let swap = UniswapV3SingleSwap {
+            token_in: H160([0x11; 20]),
+            token_out: H160([0x22; 20]),
+            fee: 3_000,
+            recipient: H160([0x33; 20]),
+            deadline: 1_700_000_000.into(),
+            exact_amount: 1_000.into(),
+            limit_amount: 900.into(),
+            sqrt_price_limit_x96: 0.into(),
+        };
+
+        let encoded = swap.encode_exact_input();
+        assert_eq!(
+            encoded.0,
+            hex!(
+                "414bf389"
+                "0000000000000000000000001111111111111111111111111111111111111111"
+                "0000000000000000000000002222222222222222222222222222222222222222"
+                "0000000000000000000000000000000000000000000000000000000000000bb8"
+                "0000000000000000000000003333333333333333333333333333333333333333"
+                "000000000000000000000000000000000000000000000000000000006553f100"
+                "00000000000000000000000000000000000000000000000000000000000003e8"
+                "0000000000000000000000000000000000000000000000000000000000000384"
+                "0000000000000000000000000000000000000000000000000000000000000000"
+            )
+        );
+        assert_eq!(
+            UniswapV3SingleSwap::decode_exact_input(&encoded.0).unwrap(),
+            swap
+        );
+
+        let encoded = swap.encode_exact_output();
+        assert_eq!(encoded.0[..4], hex!("db3e2198"));
+        assert_eq!(
+            UniswapV3SingleSwap::decode_exact_output(&encoded.0).unwrap(),
+            swap
+        );
+
+        // fee doesn't fit into a `uint24`
+        let mut invalid = encoded.0.clone();
+        invalid[4 + 2 * 32 + 28] = 1;
+        assert!(UniswapV3SingleSwap::decode_exact_output(&invalid).is_err());
+    }
+
+    #[test]
+    fn encodes_uniswap_v3_quote() {
+        let quote = UniswapV3Quote {
+            token_in: H160([0x11; 20]),
+            token_out: H160([0x22; 20]),
+            amount: 1_000.into(),
+            fee: 3_000,
+            sqrt_price_limit_x96: 0.into(),
+        };
+
+        let encoded = quote.encode_exact_input();
+        assert_eq!(
+            encoded.0,
+            hex!(
+                "c6a5026a"
+                "0000000000000000000000001111111111111111111111111111111111111111"
+                "0000000000000000000000002222222222222222222222222222222222222222"
+                "00000000000000000000000000000000000000000000000000000000000003e8"
+                "0000000000000000000000000000000000000000000000000000000000000bb8"
+                "0000000000000000000000000000000000000000000000000000000000000000"
+            )
+        );
+        assert_eq!(
+            UniswapV3Quote::decode_exact_input(&encoded.0).unwrap(),
+            quote
+        );
+
+        let encoded = quote.encode_exact_output();
+        assert_eq!(encoded.0[..4], hex!("bd21704a"));
+        assert_eq!(
+            UniswapV3Quote::decode_exact_output(&encoded.0).unwrap(),
+            quote
+        );
+
+        let output = hex!(
+            "0000000000000000000000000000000000000000000000000000000000000384"
+            "0000000000000000000000000000000000000001000000000000000000000000"
+            "0000000000000000000000000000000000000000000000000000000000000002"
+            "0000000000000000000000000000000000000000000000000000000000013880"
+        );
+        assert_eq!(UniswapV3Quote::decode_amount(&output).unwrap(), 900.into());
+        assert!(UniswapV3Quote::decode_amount(&output[..96]).is_err());
+    }
+
+    #[test]
+    fn encodes_settlement() {
+        let settle = Settle {
+            tokens: vec![H160([0x11; 20])],
+            clearing_prices: vec![1.into()],
+            trades: vec![SettlementTrade {
+                sell_token_index: 0.into(),
+                buy_token_index: 0.into(),
+                receiver: H160([0x22; 20]),
+                sell_amount: 2.into(),
+                buy_amount: 3.into(),
+                valid_to: u32::MAX,
+                app_data: [0xaa; 32],
+                fee_amount: 0.into(),
+                flags: 0.into(),
+                executed_amount: 2.into(),
+                signature: vec![0xbb],
+            }],
+            interactions: [
+                vec![],
+                vec![SettlementInteraction {
+                    target: H160([0x33; 20]),
+                    value: 0.into(),
+                    call_data: vec![],
+                }],
+                vec![],
+            ],
+        };
+
+        let encoded = settle.encode();
+        assert_eq!(
+            encoded.0,
+            hex!(
+                "13d79a0b"
+                // heads
+                "0000000000000000000000000000000000000000000000000000000000000080"
+                "00000000000000000000000000000000000000000000000000000000000000c0"
+                "0000000000000000000000000000000000000000000000000000000000000100"
+                "00000000000000000000000000000000000000000000000000000000000002e0"
+                // tokens
+                "0000000000000000000000000000000000000000000000000000000000000001"
+                "0000000000000000000000001111111111111111111111111111111111111111"
+                // clearing prices
+                "0000000000000000000000000000000000000000000000000000000000000001"
+                "0000000000000000000000000000000000000000000000000000000000000001"
+                // trades
+                "0000000000000000000000000000000000000000000000000000000000000001"
+                "0000000000000000000000000000000000000000000000000000000000000020"
+                "0000000000000000000000000000000000000000000000000000000000000000"
+                "0000000000000000000000000000000000000000000000000000000000000000"
+                "0000000000000000000000002222222222222222222222222222222222222222"
+                "0000000000000000000000000000000000000000000000000000000000000002"
+                "0000000000000000000000000000000000000000000000000000000000000003"
+                "00000000000000000000000000000000000000000000000000000000ffffffff"
+                "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
+                "0000000000000000000000000000000000000000000000000000000000000000"
+                "0000000000000000000000000000000000000000000000000000000000000000"
+                "0000000000000000000000000000000000000000000000000000000000000002"
+                "0000000000000000000000000000000000000000000000000000000000000160"
+                "0000000000000000000000000000000000000000000000000000000000000001"
+                "bb00000000000000000000000000000000000000000000000000000000000000"
+                // interactions
+                "0000000000000000000000000000000000000000000000000000000000000060"
+                "0000000000000000000000000000000000000000000000000000000000000080"
+                "0000000000000000000000000000000000000000000000000000000000000140"
+                "0000000000000000000000000000000000000000000000000000000000000000"
+                "0000000000000000000000000000000000000000000000000000000000000001"
+                "0000000000000000000000000000000000000000000000000000000000000020"
+                "0000000000000000000000003333333333333333333333333333333333333333"
+                "0000000000000000000000000000000000000000000000000000000000000000"
+                "0000000000000000000000000000000000000000000000000000000000000060"
+                "0000000000000000000000000000000000000000000000000000000000000000"
+                "0000000000000000000000000000000000000000000000000000000000000000"
+            )
+        );
+        assert_eq!(Settle::decode(&encoded.0).unwrap(), settle);
+        assert_eq!(function(&encoded.0), Some(&SETTLE));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(SETTLE.decode(&[]).is_err());
+        assert!(SETTLE.decode(&SETTLE.selector).is_err());
+        // path offset pointing past the end of the data
+        let mut input = SWAP_EXACT_TOKENS_FOR_TOKENS.selector.to_vec();
+        input.extend([0; 5 * 32]);
+        input[4 + 3 * 32 - 1] = 0xff;
+        assert!(SWAP_EXACT_TOKENS_FOR_TOKENS.decode(&input).is_err());
+        assert!(function(&hex!("deadbeef")).is_none());
+    }
+}
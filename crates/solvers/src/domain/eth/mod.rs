@@ -1,3 +1,5 @@
+pub mod abi;
+
 pub use ethereum_types::{H160, H256, U256};
 use {crate::util::bytes::Bytes, derive_more::From, web3::types::AccessList};
 
@@ -48,9 +50,16 @@ impl From<i64> for SignedGas {
 }
 
 /// Gas amount.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Gas(pub U256);
 
+impl Gas {
+    /// Subtracts `rhs` from this gas amount, returning `None` on underflow.
+    pub fn checked_sub(self, rhs: Gas) -> Option<Gas> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+}
+
 impl std::ops::Add<SignedGas> for Gas {
     type Output = Self;
 
@@ -88,3 +97,32 @@ pub struct Interaction {
     pub value: Ether,
     pub calldata: Vec<u8>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gas_ordering_matches_numeric_ordering() {
+        let small = Gas(U256::from(21_000));
+        let large = Gas(U256::from(100_000));
+
+        assert!(small < large);
+        assert!(large > small);
+        assert_eq!(small.cmp(&small), std::cmp::Ordering::Equal);
+        assert_eq!(small.max(large), large);
+        assert_eq!(small.min(large), small);
+        assert_eq!(Gas(U256::MAX).max(large), Gas(U256::MAX));
+        assert_eq!(Gas::default().min(small), Gas::default());
+    }
+
+    #[test]
+    fn gas_checked_sub() {
+        let small = Gas(U256::from(21_000));
+        let large = Gas(U256::from(100_000));
+
+        assert_eq!(large.checked_sub(small), Some(Gas(U256::from(79_000))));
+        assert_eq!(small.checked_sub(small), Some(Gas::default()));
+        assert_eq!(small.checked_sub(large), None);
+    }
+}
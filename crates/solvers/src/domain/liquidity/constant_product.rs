@@ -29,6 +29,49 @@ impl Pool {
             .checked_mul(self.reserves.1.amount)
             .expect("product of two u96 cannot overflow a u256")
     }
+
+    /// Returns the reserves ordered as `(input, output)` for a swap selling
+    /// the specified token, or `None` if the pool does not trade it.
+    fn reserves_for(&self, sell: eth::TokenAddress) -> Option<(eth::Asset, eth::Asset)> {
+        let (a, b) = self.reserves.get();
+        if sell == a.token {
+            Some((a, b))
+        } else if sell == b.token {
+            Some((b, a))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the marginal price of the pool's other token denominated in
+    /// the specified sell token, without taking fees into account.
+    pub fn spot_price(&self, sell: eth::TokenAddress) -> Option<eth::Rational> {
+        let (input, output) = self.reserves_for(sell)?;
+        if input.amount.is_zero() {
+            return None;
+        }
+        Some(eth::Rational::new_raw(output.amount, input.amount))
+    }
+
+    /// Computes the asset received for swapping `input` through the pool,
+    /// following UniswapV2's `getAmountOut`. Returns `None` if the pool does
+    /// not trade the input token or the swap would yield nothing.
+    pub fn output(&self, input: eth::Asset) -> Option<eth::Asset> {
+        let (reserve_in, reserve_out) = self.reserves_for(input.token)?;
+        let input_with_fee = input
+            .amount
+            .checked_mul(self.fee.denom().checked_sub(*self.fee.numer())?)?;
+        let numerator = input_with_fee.checked_mul(reserve_out.amount)?;
+        let denominator = reserve_in
+            .amount
+            .checked_mul(*self.fee.denom())?
+            .checked_add(input_with_fee)?;
+        let amount = numerator.checked_div(denominator)?;
+        (!amount.is_zero()).then_some(eth::Asset {
+            token: reserve_out.token,
+            amount,
+        })
+    }
 }
 
 /// Constant product pool reserves.
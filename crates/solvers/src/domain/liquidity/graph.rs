@@ -0,0 +1,304 @@
+//! A token graph over indexed liquidity for finding multi-hop swap routes.
+//!
+//! Only constant product pools are currently priced in the domain, so other
+//! liquidity kinds are ignored when building the graph.
+
+use {
+    crate::domain::{
+        eth,
+        liquidity::{self, constant_product},
+        solution,
+    },
+    std::collections::{HashMap, HashSet},
+};
+
+/// The maximum number of hops a route search will consider. Deeper searches
+/// grow exponentially with the number of pools per token and rarely pay for
+/// the additional gas.
+pub const MAX_HOPS: usize = 3;
+
+/// Liquidity indexed by the tokens it can be swapped from.
+#[derive(Debug, Default)]
+pub struct Graph<'a> {
+    edges: HashMap<eth::TokenAddress, Vec<Edge<'a>>>,
+}
+
+/// A directed edge swapping one token for another through a single pool.
+#[derive(Clone, Copy, Debug)]
+pub struct Edge<'a> {
+    pub liquidity: &'a liquidity::Liquidity,
+    pub pool: &'a constant_product::Pool,
+    pub sell: eth::TokenAddress,
+    pub buy: eth::TokenAddress,
+}
+
+impl Edge<'_> {
+    /// The marginal price of the buy token denominated in the sell token.
+    pub fn spot_price(&self) -> Option<eth::Rational> {
+        self.pool.spot_price(self.sell)
+    }
+
+    /// The amount of buy token received for selling `amount` sell token.
+    pub fn output(&self, amount: eth::U256) -> Option<eth::Asset> {
+        self.pool.output(eth::Asset {
+            token: self.sell,
+            amount,
+        })
+    }
+}
+
+impl<'a> Graph<'a> {
+    /// Builds a graph from the specified liquidity. Every pool adds an edge in
+    /// both directions, so pairs with several pools have parallel edges.
+    pub fn new(liquidity: impl IntoIterator<Item = &'a liquidity::Liquidity>) -> Self {
+        let mut edges = HashMap::<_, Vec<_>>::new();
+        for liquidity in liquidity {
+            let liquidity::State::ConstantProduct(pool) = &liquidity.state else {
+                continue;
+            };
+            let (a, b) = pool.tokens().get();
+            for (sell, buy) in [(a, b), (b, a)] {
+                edges.entry(sell).or_default().push(Edge {
+                    liquidity,
+                    pool,
+                    sell,
+                    buy,
+                });
+            }
+        }
+        Self { edges }
+    }
+
+    /// Returns all edges selling the specified token.
+    pub fn edges(&self, sell: eth::TokenAddress) -> &[Edge<'a>] {
+        self.edges.get(&sell).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Finds the route with the largest output for selling `sell` into
+    /// `buy`, using at most `max_hops` pools (capped to [`MAX_HOPS`]). Routes
+    /// never visit a token twice, so cycles in the graph are not followed.
+    pub fn best_route(
+        &self,
+        sell: eth::Asset,
+        buy: eth::TokenAddress,
+        max_hops: usize,
+    ) -> Option<Route<'a>> {
+        if sell.token == buy {
+            return None;
+        }
+
+        let mut search = Search {
+            buy,
+            visited: HashSet::from([sell.token]),
+            path: Vec::new(),
+            best: None,
+        };
+        self.search(&mut search, sell, max_hops.min(MAX_HOPS));
+        search.best
+    }
+
+    fn search(&self, search: &mut Search<'a>, input: eth::Asset, hops_left: usize) {
+        if hops_left == 0 {
+            return;
+        }
+
+        for edge in self.edges(input.token) {
+            if search.visited.contains(&edge.buy) {
+                continue;
+            }
+            let Some(output) = edge.output(input.amount) else {
+                continue;
+            };
+
+            search.path.push(Segment {
+                liquidity: edge.liquidity,
+                input,
+                output,
+            });
+            if edge.buy == search.buy {
+                if search
+                    .best
+                    .as_ref()
+                    .is_none_or(|best| output.amount > best.output().amount)
+                {
+                    search.best = Some(Route {
+                        segments: search.path.clone(),
+                    });
+                }
+            } else {
+                search.visited.insert(edge.buy);
+                self.search(search, output, hops_left - 1);
+                search.visited.remove(&edge.buy);
+            }
+            search.path.pop();
+        }
+    }
+}
+
+/// State of an in-progress depth-first route search.
+struct Search<'a> {
+    buy: eth::TokenAddress,
+    visited: HashSet<eth::TokenAddress>,
+    path: Vec<Segment<'a>>,
+    best: Option<Route<'a>>,
+}
+
+/// A swap through a single pool of a route.
+#[derive(Clone, Copy, Debug)]
+pub struct Segment<'a> {
+    pub liquidity: &'a liquidity::Liquidity,
+    pub input: eth::Asset,
+    pub output: eth::Asset,
+}
+
+/// A sequence of swaps converting a sell asset into a buy token.
+#[derive(Clone, Debug)]
+pub struct Route<'a> {
+    segments: Vec<Segment<'a>>,
+}
+
+impl<'a> Route<'a> {
+    /// The individual swaps of the route. Never empty.
+    pub fn segments(&self) -> &[Segment<'a>] {
+        &self.segments
+    }
+
+    /// The asset received at the end of the route.
+    pub fn output(&self) -> eth::Asset {
+        self.segments
+            .last()
+            .expect("routes have at least one segment")
+            .output
+    }
+
+    /// The liquidity interactions executing the route in order.
+    pub fn interactions(&self) -> Vec<solution::LiquidityInteraction> {
+        self.segments
+            .iter()
+            .map(|segment| solution::LiquidityInteraction {
+                liquidity: segment.liquidity.clone(),
+                input: segment.input,
+                output: segment.output,
+                internalize: false,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, ethereum_types::H160};
+
+    fn token(n: u64) -> eth::TokenAddress {
+        eth::TokenAddress(H160::from_low_u64_be(n))
+    }
+
+    fn asset(n: u64, amount: u64) -> eth::Asset {
+        eth::Asset {
+            token: token(n),
+            amount: amount.into(),
+        }
+    }
+
+    fn pool(id: &str, a: (u64, u64), b: (u64, u64)) -> liquidity::Liquidity {
+        liquidity::Liquidity {
+            id: liquidity::Id(id.to_owned()),
+            address: H160::zero(),
+            gas: eth::Gas(110_000.into()),
+            state: liquidity::State::ConstantProduct(constant_product::Pool {
+                reserves: constant_product::Reserves::new(asset(a.0, a.1), asset(b.0, b.1))
+                    .unwrap(),
+                fee: eth::Rational::new_raw(3.into(), 1000.into()),
+            }),
+        }
+    }
+
+    fn ids(route: &Route) -> Vec<&str> {
+        route
+            .segments()
+            .iter()
+            .map(|segment| segment.liquidity.id.0.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn computes_uniswap_v2_output() {
+        let liquidity = pool("ab", (1, 1_000_000), (2, 2_000_000));
+        let graph = Graph::new([&liquidity]);
+
+        let edge = graph.edges(token(1))[0];
+        // 1000 * 997 * 2_000_000 / (1_000_000 * 1000 + 1000 * 997)
+        assert_eq!(
+            edge.output(1000.into()).unwrap().amount,
+            eth::U256::from(1992)
+        );
+        let price = edge.spot_price().unwrap();
+        assert_eq!(
+            (*price.numer(), *price.denom()),
+            (eth::U256::from(2_000_000), eth::U256::from(1_000_000)),
+        );
+    }
+
+    #[test]
+    fn picks_best_of_duplicate_pools() {
+        let shallow = pool("shallow", (1, 10_000), (2, 10_000));
+        let deep = pool("deep", (1, 1_000_000), (2, 1_000_000));
+        let graph = Graph::new([&shallow, &deep]);
+
+        let route = graph.best_route(asset(1, 1000), token(2), 1).unwrap();
+        assert_eq!(ids(&route), ["deep"]);
+        assert_eq!(route.output().token, token(2));
+    }
+
+    #[test]
+    fn respects_max_hops() {
+        let ab = pool("ab", (1, 1_000_000), (2, 1_000_000));
+        let bc = pool("bc", (2, 1_000_000), (3, 1_000_000));
+        let cd = pool("cd", (3, 1_000_000), (4, 1_000_000));
+        let graph = Graph::new([&ab, &bc, &cd]);
+
+        assert!(graph.best_route(asset(1, 1000), token(4), 0).is_none());
+        assert!(graph.best_route(asset(1, 1000), token(4), 2).is_none());
+
+        let route = graph.best_route(asset(1, 1000), token(4), 3).unwrap();
+        assert_eq!(ids(&route), ["ab", "bc", "cd"]);
+        assert_eq!(
+            route.segments()[1].input.amount,
+            route.segments()[0].output.amount,
+        );
+
+        let interactions = route.interactions();
+        assert_eq!(interactions.len(), 3);
+        assert_eq!(interactions[2].output.amount, route.output().amount);
+    }
+
+    #[test]
+    fn prefers_multi_hop_when_direct_pool_is_shallow() {
+        let direct = pool("direct", (1, 1_000), (3, 1_000));
+        let ab = pool("ab", (1, 1_000_000), (2, 1_000_000));
+        let bc = pool("bc", (2, 1_000_000), (3, 1_000_000));
+        let graph = Graph::new([&direct, &ab, &bc]);
+
+        let route = graph.best_route(asset(1, 500), token(3), 1).unwrap();
+        assert_eq!(ids(&route), ["direct"]);
+
+        let route = graph.best_route(asset(1, 500), token(3), 2).unwrap();
+        assert_eq!(ids(&route), ["ab", "bc"]);
+    }
+
+    #[test]
+    fn does_not_follow_cycles() {
+        // A triangle between tokens 1, 2 and 3 with an exit to token 4 only
+        // from token 1 - any route to 4 would have to revisit token 1.
+        let ab = pool("ab", (1, 1_000_000), (2, 1_000_000));
+        let bc = pool("bc", (2, 1_000_000), (3, 1_000_000));
+        let ca = pool("ca", (3, 1_000_000), (1, 1_000_000));
+        let ad = pool("ad", (1, 1_000_000), (4, 1_000_000));
+        let graph = Graph::new([&ab, &bc, &ca, &ad]);
+
+        assert!(graph.best_route(asset(2, 1000), token(1), 3).is_some());
+        assert!(graph.best_route(asset(1, 1000), token(1), 3).is_none());
+        let route = graph.best_route(asset(2, 1000), token(4), 3).unwrap();
+        assert_eq!(ids(&route), ["ab", "ad"]);
+    }
+}
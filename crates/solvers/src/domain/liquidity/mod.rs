@@ -2,6 +2,7 @@
 
 pub mod concentrated;
 pub mod constant_product;
+pub mod graph;
 pub mod limit_order;
 pub mod stable;
 pub mod weighted_product;
@@ -57,6 +57,32 @@ pub fn ether_to_decimal(e: &eth::Ether) -> BigDecimal {
     BigDecimal::new(u256_to_biguint(&e.0).into(), 18)
 }
 
+/// Scales a token amount with `decimals` decimals to the same amount expressed
+/// with 18 decimals (i.e. the precision of Ether). Saturates at `U256::MAX`
+/// when scaling up and rounds down when scaling down.
+pub fn scale_to_18_decimals(amount: U256, decimals: u8) -> U256 {
+    rescale(amount, decimals, 18)
+}
+
+/// Inverse of [`scale_to_18_decimals`]: scales an amount with 18 decimals to
+/// the same amount expressed with `decimals` decimals. Saturates at
+/// `U256::MAX` when scaling up and rounds down when scaling down.
+pub fn scale_from_18_decimals(amount: U256, decimals: u8) -> U256 {
+    rescale(amount, 18, decimals)
+}
+
+fn rescale(amount: U256, from: u8, to: u8) -> U256 {
+    let factor = U256::from(10).checked_pow(from.abs_diff(to).into());
+    match (to >= from, factor) {
+        (true, Some(factor)) => amount.saturating_mul(factor),
+        (true, None) if amount.is_zero() => U256::zero(),
+        (true, None) => U256::MAX,
+        (false, Some(factor)) => amount / factor,
+        // the factor exceeds any representable amount
+        (false, None) => U256::zero(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,4 +134,58 @@ mod tests {
             assert_eq!(ether_to_decimal(&ether), decimal);
         }
     }
+
+    #[test]
+    fn scales_between_18_and_arbitrary_decimals() {
+        let e18 = U256::exp10(18);
+        for (decimals, amount, scaled) in [
+            // 1 USDC
+            (6, U256::exp10(6), e18),
+            // 1 WBTC
+            (8, U256::exp10(8), e18),
+            (18, e18, e18),
+            (24, U256::exp10(24), e18),
+            // precision lost when scaling down
+            (24, U256::exp10(6) - 1, U256::zero()),
+        ] {
+            assert_eq!(scale_to_18_decimals(amount, decimals), scaled);
+        }
+        for (decimals, amount, scaled) in [
+            (6, e18, U256::exp10(6)),
+            (8, e18, U256::exp10(8)),
+            (18, e18, e18),
+            (24, e18, U256::exp10(24)),
+            // precision lost when scaling down
+            (6, U256::exp10(12) - 1, U256::zero()),
+        ] {
+            assert_eq!(scale_from_18_decimals(amount, decimals), scaled);
+        }
+    }
+
+    #[test]
+    fn scaling_saturates_near_overflow() {
+        for decimals in [6, 8] {
+            let factor = U256::exp10(18 - decimals as usize);
+            let max = U256::MAX / factor;
+            assert_eq!(scale_to_18_decimals(max, decimals), max * factor);
+            assert_eq!(scale_to_18_decimals(max + 1, decimals), U256::MAX);
+            assert_eq!(scale_to_18_decimals(U256::MAX, decimals), U256::MAX);
+            assert_eq!(scale_from_18_decimals(U256::MAX, decimals), max);
+        }
+
+        assert_eq!(scale_to_18_decimals(U256::MAX, 18), U256::MAX);
+        assert_eq!(scale_from_18_decimals(U256::MAX, 18), U256::MAX);
+
+        let factor = U256::exp10(6);
+        let max = U256::MAX / factor;
+        assert_eq!(scale_to_18_decimals(U256::MAX, 24), max);
+        assert_eq!(scale_from_18_decimals(max, 24), max * factor);
+        assert_eq!(scale_from_18_decimals(max + 1, 24), U256::MAX);
+
+        // large factors and factors that don't fit into a `U256`
+        assert_eq!(scale_to_18_decimals(U256::one(), 0), U256::exp10(18));
+        assert_eq!(scale_to_18_decimals(U256::MAX, u8::MAX), U256::zero());
+        assert_eq!(scale_from_18_decimals(U256::one(), u8::MAX), U256::MAX);
+        assert_eq!(scale_from_18_decimals(U256::zero(), u8::MAX), U256::zero());
+    }
 }
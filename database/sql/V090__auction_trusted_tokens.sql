@@ -0,0 +1,5 @@
+-- All tokens that were considered trusted (i.e. safe to internalize) when an auction was sent to the solvers.
+CREATE TABLE auction_trusted_tokens (
+  auction_id bigint PRIMARY KEY,
+  tokens bytea[] NOT NULL
+);
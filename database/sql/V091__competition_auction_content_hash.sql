@@ -0,0 +1,4 @@
+-- Hash over the content of an auction (orders, prices and surplus capturing jit order owners) that
+-- the autopilot uses to detect duplicate auctions. Older auctions don't have a hash.
+ALTER TABLE competition_auctions
+    ADD COLUMN content_hash bytea;
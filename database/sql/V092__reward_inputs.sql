@@ -0,0 +1,8 @@
+-- Denormalized data required to compute solver rewards for an auction. Written by the autopilot once all winners
+-- finished settling.
+CREATE TABLE reward_inputs (
+  auction_id bigint PRIMARY KEY,
+  block_deadline bigint NOT NULL,
+  competition_data_complete boolean NOT NULL,
+  winners jsonb NOT NULL
+);
@@ -0,0 +1,13 @@
+-- Tracks the progress of winning orders between the competition and their on-chain execution so that users polling
+-- the order status can see which solver is settling their order.
+CREATE TABLE order_execution_status (
+  order_uid bytea NOT NULL,
+  auction_id bigint NOT NULL,
+  solver bytea NOT NULL,
+  solution_uid bigint NOT NULL,
+  deadline_block bigint NOT NULL,
+  tx_hash bytea,
+  PRIMARY KEY (order_uid, auction_id)
+);
+
+CREATE INDEX order_execution_status_auction_solver ON order_execution_status USING BTREE (auction_id, solver);
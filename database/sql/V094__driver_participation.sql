@@ -0,0 +1,11 @@
+-- Records which drivers were asked to solve an auction, how many solutions each of them proposed and why the others
+-- produced nothing. Unlike metrics this can be queried per auction after the fact.
+CREATE TABLE driver_participation (
+  auction_id bigint NOT NULL,
+  driver text NOT NULL,
+  submission_address bytea NOT NULL,
+  outcome text NOT NULL,
+  proposed_solutions bigint NOT NULL,
+  accepted_solutions bigint NOT NULL,
+  PRIMARY KEY (auction_id, driver)
+);
@@ -0,0 +1,3 @@
+-- Whether the mined settlement deviated from the solution that won the competition (dropped or added orders, changed
+-- clearing prices). Stays NULL until the settlement transaction got verified.
+ALTER TABLE settlement_executions ADD COLUMN deviates_from_solution boolean;
@@ -0,0 +1,14 @@
+-- Relative deviation of the native prices implied by the clearing prices of a settlement from the native prices of its
+-- auction. Used to detect when the native price estimator starts disagreeing with realized prices.
+CREATE TABLE settlement_price_deviations (
+  block_number bigint NOT NULL,
+  log_index bigint NOT NULL,
+  auction_id bigint NOT NULL,
+  token bytea NOT NULL,
+  auction_price numeric(78,0) NOT NULL,
+  implied_price numeric(78,0) NOT NULL,
+  deviation double precision NOT NULL,
+  PRIMARY KEY (block_number, log_index, token)
+);
+
+CREATE INDEX settlement_price_deviations_auction_id ON settlement_price_deviations USING BTREE (auction_id);
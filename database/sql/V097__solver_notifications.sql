@@ -0,0 +1,13 @@
+-- Every notification the autopilot tried to send to a solver and whether it got delivered. Lets us prove that solvers
+-- got informed e.g. about being banned.
+CREATE TYPE SolverNotificationKind AS ENUM ('banned', 'competition_outcome');
+
+CREATE TABLE solver_notifications (
+  solver bytea NOT NULL,
+  kind SolverNotificationKind NOT NULL,
+  timestamp timestamptz NOT NULL,
+  -- NULL if the notification got delivered
+  error text
+);
+
+CREATE INDEX solver_notifications_solver_timestamp ON solver_notifications USING BTREE (solver, timestamp);
@@ -0,0 +1,4 @@
+-- Orders of a winning solution whose settlement could not be submitted (e.g.
+-- because the transaction reverted) get this event instead of staying in
+-- `executing` until they get picked up by the next auction.
+ALTER TYPE OrderEventLabel ADD VALUE 'reverted';
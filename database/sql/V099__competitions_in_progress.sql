@@ -0,0 +1,10 @@
+-- Competitions that were ranked but whose data was not stored yet. An entry that is still present when the autopilot
+-- starts indicates that the process died while handling the competition and its solutions were lost.
+CREATE TABLE competitions_in_progress (
+  auction_id bigint PRIMARY KEY,
+  -- names of the drivers that proposed solutions
+  drivers text[] NOT NULL,
+  -- hash of every proposed solution
+  solution_hashes bytea[] NOT NULL,
+  started_at timestamptz NOT NULL
+);
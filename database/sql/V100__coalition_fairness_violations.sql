@@ -0,0 +1,15 @@
+-- Orders a winner of an auction executed worse than a losing solution that could have won alongside the other
+-- winners. Recorded after winner selection to monitor the selection policy; the auction outcome is not affected.
+CREATE TABLE coalition_fairness_violations (
+  auction_id bigint NOT NULL,
+  order_uid bytea NOT NULL,
+  -- name of the driver whose winning solution executed the order
+  winner text NOT NULL,
+  winner_executed_sell numeric(78,0) NOT NULL,
+  winner_executed_buy numeric(78,0) NOT NULL,
+  -- name of the driver with the best execution that was compatible with the other winners
+  alternative text NOT NULL,
+  alternative_executed_sell numeric(78,0) NOT NULL,
+  alternative_executed_buy numeric(78,0) NOT NULL,
+  PRIMARY KEY (auction_id, order_uid)
+);
@@ -0,0 +1,16 @@
+-- Comparison of a candidate driver's result in a shadow competition with the production competition of the same
+-- auction. Lets us judge how a driver would have performed before it participates in production.
+CREATE TABLE shadow_competition_comparisons (
+  auction_id bigint NOT NULL,
+  -- name of the driver under evaluation
+  candidate text NOT NULL,
+  -- best score of the candidate in the shadow competition
+  candidate_score numeric(78,0),
+  -- best winning score in production
+  production_score numeric(78,0),
+  would_win boolean NOT NULL,
+  -- whether the shadow ranked the drivers of both competitions differently than production
+  ranking_diverged boolean NOT NULL,
+  created_at timestamptz NOT NULL DEFAULT now(),
+  PRIMARY KEY (auction_id, candidate)
+);
@@ -0,0 +1,9 @@
+-- How much better than the external prices of the auction the winning solutions executed each order. Only used for
+-- analytics.
+CREATE TABLE order_price_improvements (
+  auction_id bigint NOT NULL,
+  order_uid bytea NOT NULL,
+  -- value the user received relative to the value they sold according to the external prices in basis points
+  improvement_bps double precision NOT NULL,
+  PRIMARY KEY (auction_id, order_uid)
+);
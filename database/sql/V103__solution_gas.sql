@@ -0,0 +1,12 @@
+CREATE TYPE GasSource AS ENUM ('reported', 'estimated', 'fallback');
+
+-- Gas of the winning solutions of an auction. Winners whose driver didn't report the gas of their solution get an
+-- estimate based on the calldata of the solution.
+CREATE TABLE solution_gas (
+  auction_id bigint NOT NULL,
+  -- uid of the solution in `proposed_solutions`
+  solution_uid bigint NOT NULL,
+  gas numeric(78,0) NOT NULL,
+  source GasSource NOT NULL,
+  PRIMARY KEY (auction_id, solution_uid)
+);
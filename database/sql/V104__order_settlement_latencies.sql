@@ -0,0 +1,19 @@
+-- When an order was first part of an auction. Persisted so that the latency until the order gets settled survives
+-- restarts of the autopilot.
+CREATE TABLE order_first_auction_appearances (
+  order_uid bytea PRIMARY KEY,
+  -- the first auction the order was part of
+  auction_id bigint NOT NULL,
+  first_seen timestamptz NOT NULL
+);
+
+-- How long it took from an order's first appearance in an auction until the autopilot observed its first settlement.
+-- Orders that never settle have no entry.
+CREATE TABLE order_settlement_latencies (
+  order_uid bytea PRIMARY KEY,
+  -- the auction whose winning solution settled the order
+  auction_id bigint NOT NULL,
+  tx_hash bytea NOT NULL,
+  first_seen timestamptz NOT NULL,
+  settled timestamptz NOT NULL
+);
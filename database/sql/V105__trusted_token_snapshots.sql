@@ -0,0 +1,12 @@
+-- Consecutive auctions mostly use the same trusted tokens, so every distinct set of trusted tokens is only stored once
+-- and auctions reference it by its hash.
+CREATE TABLE trusted_token_snapshots (
+  -- keccak256 of the sorted and concatenated token addresses
+  hash bytea PRIMARY KEY,
+  tokens bytea[] NOT NULL
+);
+
+-- Auctions stored before snapshots existed keep their tokens inline.
+ALTER TABLE auction_trusted_tokens
+  ALTER COLUMN tokens DROP NOT NULL,
+  ADD COLUMN snapshot bytea;
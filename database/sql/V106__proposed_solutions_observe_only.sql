@@ -0,0 +1,3 @@
+-- Solutions of observe-only drivers get stored for comparison but are not part of the public competition data.
+ALTER TABLE proposed_solutions
+  ADD COLUMN observe_only boolean NOT NULL DEFAULT false;
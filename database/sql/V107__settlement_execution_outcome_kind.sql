@@ -0,0 +1,14 @@
+CREATE TYPE SettlementExecutionOutcome AS ENUM ('success', 'timeout', 'reorged', 'failed');
+
+-- How a settlement execution ended. Unlike `outcome` this doesn't contain the error message so executions can be
+-- filtered by it.
+ALTER TABLE settlement_executions ADD COLUMN outcome_kind SettlementExecutionOutcome;
+
+UPDATE settlement_executions
+SET outcome_kind = CASE
+    WHEN outcome = 'success' THEN 'success'::SettlementExecutionOutcome
+    WHEN outcome = 'timeout' THEN 'timeout'::SettlementExecutionOutcome
+    WHEN outcome = 'reorged' THEN 'reorged'::SettlementExecutionOutcome
+    ELSE 'failed'::SettlementExecutionOutcome
+END
+WHERE outcome IS NOT NULL;